@@ -0,0 +1,328 @@
+//! Network access policies for self-hosted instances.
+//!
+//! A self-hosted instance, such as one run by a hospital or a company, can be
+//! restricted to clients on its own networks and to members of its own
+//! organization. The [policy](AccessPolicy) is read from the environment and
+//! enforced by middleware before dispatch: the [network
+//! allowlist](enforce_network) on all routes, and the [sign-in
+//! requirement](enforce_sign_in) on the RPC, REST, and compute APIs and on
+//! document sync, after authentication. Requests violating the policy fail with
+//! [`AppError::PolicyDenied`], whose status is 403, while requests without
+//! valid credentials on an instance requiring sign-in still fail with
+//! [`AppError::Unauthorized`], whose status is 401, so that clients can tell
+//! whether signing in would help.
+//!
+//! Sign-in through the organization's identity provider is configured in
+//! Firebase. The server requires only that the user's email address be
+//! verified and belong to one of the allowed domains.
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use firebase_auth::FirebaseUser;
+use thiserror::Error;
+
+use crate::app::AppError;
+use crate::feature_flags::verified_email_domain;
+use crate::rest::ApiError;
+
+/// Network of IP addresses, in CIDR notation such as `10.0.0.0/8`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    /// Whether the network contains an address.
+    ///
+    /// IPv4 addresses mapped into IPv6 are treated as IPv4 addresses.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let addr = addr.to_canonical();
+        match (self.addr, addr) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                prefix_eq(&net.octets(), &addr.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                prefix_eq(&net.octets(), &addr.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Whether two addresses agree in their first bits.
+fn prefix_eq(a: &[u8], b: &[u8], bits: u8) -> bool {
+    let (bytes, rest) = ((bits / 8) as usize, bits % 8);
+    if a[..bytes] != b[..bytes] {
+        return false;
+    }
+    rest == 0 || (a[bytes] ^ b[bytes]) >> (8 - rest) == 0
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    /// Parses a network in CIDR notation, or a single address.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.parse().map_err(|_| format!("Invalid IP address: {addr}"))?;
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(len) => len
+                .parse()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| format!("Invalid prefix length: {len}"))?,
+            None => max_len,
+        };
+        Ok(Self { addr, prefix_len })
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// Reason that a request was denied by the access policy.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum PolicyDenial {
+    /// Client address is outside the allowed networks.
+    #[error("Address {0} is not in an allowed network")]
+    Network(IpAddr),
+
+    /// User is signed in, but not with a verified email address at an allowed
+    /// domain.
+    #[error("User is not signed in with an allowed email domain")]
+    EmailDomain,
+}
+
+/// Access policy of the instance.
+#[derive(Clone, Debug, Default)]
+pub struct AccessPolicy {
+    /// Networks that clients may connect from, or any network if unset.
+    pub allowed_networks: Option<Vec<IpNetwork>>,
+
+    /// Domains of the email addresses that users must sign in with, or no
+    /// sign-in required if unset.
+    pub sso_domains: Option<Vec<String>>,
+
+    /// Whether to take the client address from the `X-Forwarded-For` header,
+    /// as set by a reverse proxy, rather than from the connection.
+    pub trust_forwarded_for: bool,
+}
+
+impl AccessPolicy {
+    /// Reads the policy from the environment.
+    ///
+    /// Both `ALLOWED_NETWORKS` and `SSO_DOMAINS` are whitespace- or
+    /// comma-separated lists. Setting `TRUST_FORWARDED_FOR` to `true` should be
+    /// done only behind a reverse proxy, as the header is otherwise forged
+    /// trivially. Panics if a network is invalid, so that a misconfigured
+    /// instance does not start without its allowlist.
+    pub fn from_env() -> Self {
+        let list = |var: &str| {
+            dotenvy::var(var).ok().map(|value| {
+                value
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+        };
+        let allowed_networks = list("ALLOWED_NETWORKS").map(|networks| {
+            networks
+                .iter()
+                .map(|network| network.parse().expect("`ALLOWED_NETWORKS` should be valid"))
+                .collect()
+        });
+        let sso_domains = list("SSO_DOMAINS")
+            .map(|domains| domains.into_iter().map(|domain| domain.to_lowercase()).collect());
+        let trust_forwarded_for = dotenvy::var("TRUST_FORWARDED_FOR").is_ok_and(|v| v == "true");
+        Self {
+            allowed_networks,
+            sso_domains,
+            trust_forwarded_for,
+        }
+    }
+
+    /// Whether the policy restricts access at all.
+    pub fn is_restricted(&self) -> bool {
+        self.allowed_networks.is_some() || self.sso_domains.is_some()
+    }
+
+    /// Gets the address of the client making a request.
+    ///
+    /// The nearest proxy appends the address it received the request from to
+    /// the `X-Forwarded-For` header, so only the last entry is trusted.
+    fn client_addr(&self, req: &Request) -> Option<IpAddr> {
+        if self.trust_forwarded_for {
+            let forwarded = req
+                .headers()
+                .get_all("x-forwarded-for")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .next_back()
+                .and_then(|addr| addr.trim().parse().ok());
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+        let peer = req.extensions().get::<ConnectInfo<SocketAddr>>();
+        peer.map(|ConnectInfo(peer)| peer.ip())
+    }
+
+    /// Checks that a client address is allowed.
+    ///
+    /// When the policy has an allowlist, requests from unknown addresses are
+    /// denied.
+    pub fn check_network(&self, addr: Option<IpAddr>) -> Result<(), AppError> {
+        let Some(networks) = &self.allowed_networks else {
+            return Ok(());
+        };
+        let addr = addr.unwrap_or(IpAddr::from([0, 0, 0, 0]));
+        if networks.iter().any(|network| network.contains(addr)) {
+            Ok(())
+        } else {
+            Err(AppError::PolicyDenied(PolicyDenial::Network(addr)))
+        }
+    }
+
+    /// Checks that the user is signed in with an allowed email domain, if the
+    /// policy requires it.
+    pub fn check_user(&self, user: Option<&FirebaseUser>) -> Result<(), AppError> {
+        let Some(domains) = &self.sso_domains else {
+            return Ok(());
+        };
+        let user = user.ok_or(AppError::Unauthorized)?;
+        match verified_email_domain(user) {
+            Some(domain) if domains.contains(&domain) => Ok(()),
+            _ => Err(AppError::PolicyDenied(PolicyDenial::EmailDomain)),
+        }
+    }
+}
+
+/// Middleware enforcing the network part of the access policy.
+///
+/// Applies to all routes but the status check, including the frontend itself.
+pub async fn enforce_network(
+    State(policy): State<Arc<AccessPolicy>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    match policy.check_network(policy.client_addr(&req)) {
+        Ok(()) => next.run(req).await,
+        Err(err) => ApiError::from(err).into_response(),
+    }
+}
+
+/// Middleware enforcing the sign-in part of the access policy.
+///
+/// Applies to the APIs and the document sync websocket, and must run after the
+/// authentication middleware, so that the user is known.
+pub async fn enforce_sign_in(
+    State(policy): State<Arc<AccessPolicy>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    match policy.check_user(req.extensions().get::<FirebaseUser>()) {
+        Ok(()) => next.run(req).await,
+        Err(err) => ApiError::from(err).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ip_networks() {
+        let network: IpNetwork = "10.1.0.0/16".parse().unwrap();
+        assert!(network.contains("10.1.2.3".parse().unwrap()));
+        assert!(network.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!network.contains("10.2.0.1".parse().unwrap()));
+        assert_eq!(network.to_string(), "10.1.0.0/16");
+
+        let network: IpNetwork = "192.168.1.128/25".parse().unwrap();
+        assert!(network.contains("192.168.1.200".parse().unwrap()));
+        assert!(!network.contains("192.168.1.100".parse().unwrap()));
+
+        let network: IpNetwork = "2001:db8::/32".parse().unwrap();
+        assert!(network.contains("2001:db8::1".parse().unwrap()));
+        assert!(!network.contains("10.1.2.3".parse().unwrap()));
+
+        let host: IpNetwork = "127.0.0.1".parse().unwrap();
+        assert!(host.contains("127.0.0.1".parse().unwrap()));
+        assert!(!host.contains("127.0.0.2".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+        assert!("example.com/8".parse::<IpNetwork>().is_err());
+    }
+
+    #[test]
+    fn policy_denials() {
+        let policy = AccessPolicy {
+            allowed_networks: Some(vec!["10.0.0.0/8".parse().unwrap()]),
+            sso_domains: Some(vec!["example.org".into()]),
+            trust_forwarded_for: false,
+        };
+        assert!(policy.is_restricted());
+        assert!(policy.check_network(Some("10.0.0.1".parse().unwrap())).is_ok());
+        let denied = policy.check_network(Some("8.8.8.8".parse().unwrap()));
+        assert!(matches!(denied, Err(AppError::PolicyDenied(PolicyDenial::Network(_)))));
+        assert!(policy.check_network(None).is_err());
+
+        // Missing credentials are an authentication failure, not a denial.
+        assert!(matches!(policy.check_user(None), Err(AppError::Unauthorized)));
+
+        assert!(!AccessPolicy::default().is_restricted());
+        assert!(AccessPolicy::default().check_user(None).is_ok());
+    }
+
+    #[tokio::test]
+    async fn websocket_requires_sign_in() {
+        use axum::body::Body;
+        use axum::middleware::from_fn_with_state;
+        use axum::{Router, routing::get};
+        use http::StatusCode;
+        use tower::ServiceExt;
+
+        // Layered like the document sync route of the server.
+        let router = |policy: AccessPolicy| {
+            Router::new()
+                .route("/repo-ws", get(|| async { "upgraded" }))
+                .layer(from_fn_with_state(Arc::new(policy), enforce_sign_in))
+        };
+        let upgrade = || {
+            Request::builder()
+                .uri("/repo-ws")
+                .header("connection", "upgrade")
+                .header("upgrade", "websocket")
+                .header("sec-websocket-version", "13")
+                .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let policy = AccessPolicy {
+            sso_domains: Some(vec!["example.org".into()]),
+            ..Default::default()
+        };
+        let response = router(policy).oneshot(upgrade()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = router(AccessPolicy::default()).oneshot(upgrade()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
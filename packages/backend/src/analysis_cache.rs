@@ -0,0 +1,163 @@
+//! Cache of analysis results keyed by document content.
+//!
+//! Results are keyed by the ref, a hash of the document content, the ID of the
+//! analysis, and a hash of the analysis configuration. Since results are
+//! supplied by clients with write access to the ref, they are never shared
+//! between refs, even refs with the same content such as a copy and its
+//! original. The content hash is derived from
+//! the Automerge heads of the ref's current snapshot, which identify the
+//! document content exactly, so a cached result remains valid for as long as the
+//! document is unchanged. Entries expire after a time-to-live and are purged
+//! lazily when new results are stored.
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::app::{AppError, AppState};
+
+/// Default and maximum time-to-live for cached analysis results, in seconds.
+pub const DEFAULT_TTL_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// Maximum allowed size of a cached result in bytes (1MB).
+const MAX_RESULT_SIZE: usize = 1024 * 1024;
+
+/// Key identifying an analysis of a ref, up to its content.
+#[qubit::ts]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AnalysisKey {
+    /// ID of the analysis, such as `"mass-action"`.
+    #[serde(rename = "analysisId")]
+    pub analysis_id: String,
+
+    /// Hash of the analysis configuration, computed by the client.
+    #[serde(rename = "configHash")]
+    pub config_hash: String,
+}
+
+/// Cached result of an analysis.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct CachedAnalysis {
+    /// The analysis result.
+    pub result: Value,
+
+    /// When the result was stored.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Computes the content hash of the current snapshot of a ref.
+///
+/// The hash is the hex encoding of the snapshot's sorted Automerge heads.
+pub async fn content_hash(state: &AppState, ref_id: Uuid) -> Result<String, AppError> {
+    let row = sqlx::query(
+        "
+        SELECT snapshots.heads FROM refs
+        JOIN snapshots ON snapshots.id = refs.current_snapshot
+        WHERE refs.id = $1
+        ",
+    )
+    .bind(ref_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("ref {ref_id}")))?;
+
+    let mut heads: Vec<Vec<u8>> = row.get("heads");
+    heads.sort();
+    let mut hash = String::new();
+    for (i, head) in heads.iter().enumerate() {
+        if i > 0 {
+            hash.push('.');
+        }
+        for byte in head {
+            let _ = write!(hash, "{byte:02x}");
+        }
+    }
+    Ok(hash)
+}
+
+/// Gets the cached result of an analysis of the current content of a ref.
+pub async fn get_cached_analysis(
+    state: &AppState,
+    ref_id: Uuid,
+    key: &AnalysisKey,
+) -> Result<Option<CachedAnalysis>, AppError> {
+    let content_hash = content_hash(state, ref_id).await?;
+    let row = sqlx::query(
+        "
+        SELECT result, created_at FROM analysis_cache
+        WHERE ref_id = $1 AND content_hash = $2 AND analysis_id = $3 AND config_hash = $4
+          AND expires_at > NOW()
+        ",
+    )
+    .bind(ref_id)
+    .bind(&content_hash)
+    .bind(&key.analysis_id)
+    .bind(&key.config_hash)
+    .fetch_optional(&state.db)
+    .await?;
+
+    Ok(row.map(|row| CachedAnalysis {
+        result: row.get("result"),
+        created_at: row.get("created_at"),
+    }))
+}
+
+/// Stores the result of an analysis of the current content of a ref.
+///
+/// Replaces any existing result with the same key and purges expired entries.
+pub async fn put_cached_analysis(
+    state: &AppState,
+    ref_id: Uuid,
+    key: &AnalysisKey,
+    result: Value,
+    ttl_seconds: Option<i64>,
+) -> Result<(), AppError> {
+    let result_size = serde_json::to_string(&result).map(|s| s.len()).unwrap_or(0);
+    if result_size > MAX_RESULT_SIZE {
+        return Err(AppError::Invalid(format!(
+            "Analysis result size ({result_size} bytes) exceeds maximum allowed size ({MAX_RESULT_SIZE} bytes)"
+        )));
+    }
+    let ttl = ttl_seconds.unwrap_or(DEFAULT_TTL_SECONDS);
+    if ttl <= 0 {
+        return Err(AppError::Invalid("Time-to-live must be positive".to_string()));
+    }
+    let expires_at = Utc::now() + Duration::seconds(ttl.min(DEFAULT_TTL_SECONDS));
+
+    let content_hash = content_hash(state, ref_id).await?;
+    let mut txn = state.db.begin().await?;
+
+    sqlx::query("DELETE FROM analysis_cache WHERE expires_at <= NOW()")
+        .execute(&mut *txn)
+        .await?;
+
+    sqlx::query(
+        "
+        INSERT INTO analysis_cache(
+            ref_id, content_hash, analysis_id, config_hash, result, expires_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (ref_id, content_hash, analysis_id, config_hash)
+        DO UPDATE SET result = EXCLUDED.result,
+                      created_at = NOW(),
+                      expires_at = EXCLUDED.expires_at
+        ",
+    )
+    .bind(ref_id)
+    .bind(&content_hash)
+    .bind(&key.analysis_id)
+    .bind(&key.config_hash)
+    .bind(result)
+    .bind(expires_at)
+    .execute(&mut *txn)
+    .await?;
+
+    txn.commit().await?;
+    Ok(())
+}
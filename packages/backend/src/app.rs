@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use firebase_auth::FirebaseUser;
+use http::StatusCode;
 use samod::DocumentId;
 use sqlx::PgPool;
 use std::collections::HashMap;
@@ -7,6 +9,15 @@ use thiserror::Error;
 use tokio::sync::{RwLock, mpsc, oneshot};
 use uuid::Uuid;
 
+use crate::attachments::S3Config;
+use crate::auth::PermissionCache;
+use crate::job_scheduler::JobScheduler;
+use crate::maintenance::MaintenanceMode;
+use crate::presence::PresenceTracker;
+use crate::publications::ArchiveConfig;
+use crate::scratch::ScratchDocsMap;
+use crate::verification::OrcidConfig;
+
 /// Reply channel type used by all ref actor messages.
 pub type RefReply = oneshot::Sender<Result<(), AppError>>;
 
@@ -22,6 +33,11 @@ pub enum RefMsg {
         /// The target snapshot to set as current.
         snapshot_id: i32,
     },
+    /// Replace the content of the document and save a snapshot.
+    ReplaceContent {
+        /// The new content of the document, as JSON.
+        content: serde_json::Value,
+    },
     /// Soft-delete the document ref.
     Delete,
     /// Restore a soft-deleted document ref.
@@ -42,6 +58,9 @@ pub struct AppState {
     /// Channel senders for per-ref actors that coordinate document mutations.
     pub ref_actors: RefActorsMap,
 
+    /// Scratch documents not yet saved as refs, by Automerge document ID.
+    pub scratch_docs: ScratchDocsMap,
+
     /// Tracks user IDs whose state docs were refreshed from DB in this process,
     /// mapped to their Automerge document IDs.
     pub initialized_user_states: Arc<RwLock<HashMap<String, DocumentId>>>,
@@ -51,6 +70,27 @@ pub struct AppState {
 
     /// Base URL for the Julia compute service, if configured.
     pub julia_url: Option<String>,
+
+    /// Object storage for attachments, if configured.
+    pub s3: Option<S3Config>,
+
+    /// ORCID OAuth client for linking ORCID iDs, if configured.
+    pub orcid: Option<OrcidConfig>,
+
+    /// Archive to which published versions are pushed, if configured.
+    pub archive: Option<ArchiveConfig>,
+
+    /// Maintenance mode of the API, if it is in maintenance.
+    pub maintenance: Arc<RwLock<Option<MaintenanceMode>>>,
+
+    /// Cache of the permission levels of users on refs.
+    pub permission_cache: PermissionCache,
+
+    /// Tracker of editing sessions on refs, limiting concurrent writers.
+    pub presence: PresenceTracker,
+
+    /// Scheduler of background jobs, sharing the slots for jobs among users.
+    pub job_scheduler: JobScheduler,
 }
 
 /// Context available to RPC procedures.
@@ -102,4 +142,47 @@ pub enum AppError {
     /// document ref.
     #[error("Not authorized to access ref: {0}")]
     Forbidden(Uuid),
+
+    /// User must be verified to perform the requested action.
+    #[error("Action requires a verified email address or linked ORCID iD")]
+    Unverified,
+
+    /// User must be an administrator to perform the requested action.
+    #[error("Action requires administrator privileges")]
+    AdminOnly,
+
+    /// Feature must be enabled for the user to perform the requested action.
+    #[error("Feature is not enabled: {0}")]
+    FeatureDisabled(String),
+
+    /// Request was denied by the access policy of the instance.
+    #[error("Denied by access policy: {0}")]
+    PolicyDenied(#[from] crate::access_policy::PolicyDenial),
+
+    /// Document is encrypted on the client, so its plaintext is unavailable.
+    #[error("Document is encrypted: {0}")]
+    Encrypted(Uuid),
+
+    /// API is in read-only maintenance mode, possibly with an expected end.
+    #[error("API is in read-only maintenance mode")]
+    Maintenance(Option<DateTime<Utc>>),
+}
+
+impl AppError {
+    /// HTTP status code corresponding to the error.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Invalid(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_)
+            | AppError::Unverified
+            | AppError::AdminOnly
+            | AppError::FeatureDisabled(_)
+            | AppError::PolicyDenied(_) => StatusCode::FORBIDDEN,
+            AppError::Encrypted(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::NotFound(_) | AppError::Db(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+            AppError::Maintenance(_) => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
 }
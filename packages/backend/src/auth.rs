@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use autosurgeon::{Hydrate, Reconcile};
 use firebase_auth::{FirebaseAuth, FirebaseUser};
 use serde::{Deserialize, Serialize};
+use sqlx::Row;
 use uuid::Uuid;
 
 #[cfg(feature = "property-tests")]
@@ -12,6 +13,11 @@ use super::app::{AppCtx, AppError, AppState};
 use super::user::UserSummary;
 use crate::user_state_updates::update_ref_for_users;
 
+mod cache;
+pub use cache::PermissionCache;
+
+pub mod invites;
+
 /// Levels of permission that a user can have on a document.
 #[qubit::ts]
 #[cfg_attr(feature = "property-tests", derive(Arbitrary))]
@@ -131,10 +137,19 @@ pub async fn is_authorized(
 }
 
 /// Gets the highest level of permissions allowed for a ref.
+///
+/// Levels are looked up in the [permission cache](PermissionCache) first.
 pub async fn max_permission_level(
     ctx: &AppCtx,
     ref_id: Uuid,
 ) -> Result<Option<PermissionLevel>, AppError> {
+    let user_id = ctx.user.as_ref().map(|user| user.user_id.as_str());
+    let cache = &ctx.state.permission_cache;
+    if let Some(level) = cache.get(user_id, ref_id) {
+        return Ok(level);
+    }
+    let generation = cache.generation();
+
     let query = sqlx::query_scalar!(
         r#"
         SELECT MAX(level) AS "max: PermissionLevel" FROM permissions
@@ -150,9 +165,84 @@ pub async fn max_permission_level(
         ref_exists(ctx, ref_id).await?;
     }
 
+    cache.insert(generation, user_id, ref_id, level);
     Ok(level)
 }
 
+/// Verify that user is authorized to access many refs at a given permission level.
+///
+/// Like [`authorize`] but checks all the refs using [`max_permission_levels`].
+pub async fn authorize_all(
+    ctx: &AppCtx,
+    ref_ids: &[Uuid],
+    level: PermissionLevel,
+) -> Result<(), AppError> {
+    let levels = max_permission_levels(ctx, ref_ids).await?;
+    match ref_ids
+        .iter()
+        .find(|ref_id| levels[*ref_id].is_none_or(|max_level| level > max_level))
+    {
+        Some(ref_id) => Err(AppError::Forbidden(*ref_id)),
+        None => Ok(()),
+    }
+}
+
+/// Gets the highest levels of permissions allowed for many refs.
+///
+/// Equivalent to calling [`max_permission_level`] for each ref, but looks up
+/// all the refs missing from the permission cache in a single query. The
+/// result is an error if any of the refs does not exist.
+pub async fn max_permission_levels(
+    ctx: &AppCtx,
+    ref_ids: &[Uuid],
+) -> Result<HashMap<Uuid, Option<PermissionLevel>>, AppError> {
+    let user_id = ctx.user.as_ref().map(|user| user.user_id.as_str());
+    let cache = &ctx.state.permission_cache;
+    let mut levels = HashMap::new();
+    let mut missing = Vec::new();
+    for &ref_id in ref_ids {
+        match cache.get(user_id, ref_id) {
+            Some(level) => {
+                levels.insert(ref_id, level);
+            }
+            None => missing.push(ref_id),
+        }
+    }
+    if missing.is_empty() {
+        return Ok(levels);
+    }
+    missing.sort();
+    missing.dedup();
+    let generation = cache.generation();
+
+    let rows = sqlx::query(
+        "
+        SELECT refs.id, MAX(permissions.level) AS level FROM refs
+        LEFT JOIN permissions ON permissions.object = refs.id
+            AND (permissions.subject IS NULL OR permissions.subject = $2)
+        WHERE refs.id = ANY($1)
+        GROUP BY refs.id
+        ",
+    )
+    .bind(&missing)
+    .bind(user_id)
+    .fetch_all(&ctx.state.db)
+    .await?;
+
+    for row in rows {
+        let ref_id: Uuid = row.get("id");
+        let level: Option<PermissionLevel> = row.get("level");
+        cache.insert(generation, user_id, ref_id, level);
+        levels.insert(ref_id, level);
+    }
+
+    // Return 404 if any ref does not exist at all.
+    match missing.iter().find(|ref_id| !levels.contains_key(*ref_id)) {
+        Some(ref_id) => Err(AppError::NotFound(format!("ref {ref_id}"))),
+        None => Ok(levels),
+    }
+}
+
 /// Gets the permissions allowed for a ref.
 pub async fn permissions(ctx: &AppCtx, ref_id: Uuid) -> Result<Permissions, AppError> {
     let query = sqlx::query!(
@@ -275,6 +365,7 @@ pub async fn set_permissions(
     insert_query.execute(&mut *transaction).await?;
 
     transaction.commit().await?;
+    state.permission_cache.invalidate_ref(ref_id);
 
     if let Err(e) = update_ref_for_users(state, ref_id, old_holders).await {
         tracing::error!(%ref_id, error = %e, "Failed to update user states after permission change");
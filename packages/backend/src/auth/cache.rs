@@ -0,0 +1,215 @@
+//! In-process cache of permission levels.
+//!
+//! Lookups of the highest permission level of a user on a ref are cached in a
+//! least-recently-used cache. Entries for a ref are invalidated explicitly
+//! whenever the permissions on the ref change. Since a lookup that began before
+//! an invalidation may complete after it, the cache is optimistic: a lookup
+//! records the generation of the cache when it begins and its result is only
+//! stored if no invalidation has happened in the meantime. Entries also expire
+//! after a time-to-live, bounding the staleness due to writes from outside the
+//! process.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use super::PermissionLevel;
+
+/// Default maximum number of entries in the cache.
+const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Default time after which entries expire.
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// Cache key: the user, or `None` for anonymous users, and the ref.
+type CacheKey = (Option<String>, Uuid);
+
+/// Cache of the highest permission levels of users on refs.
+///
+/// Cheaply cloneable, with clones sharing the same cache.
+#[derive(Clone)]
+pub struct PermissionCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    capacity: usize,
+    ttl: Duration,
+    generation: u64,
+    tick: u64,
+    entries: HashMap<CacheKey, Entry>,
+    recency: BTreeMap<u64, CacheKey>,
+}
+
+struct Entry {
+    level: Option<PermissionLevel>,
+    tick: u64,
+    expires_at: Instant,
+}
+
+/// Generation of the cache, recorded at the start of a lookup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Generation(u64);
+
+impl Default for PermissionCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY, DEFAULT_TTL)
+    }
+}
+
+impl PermissionCache {
+    /// Creates an empty cache with the given capacity and time-to-live.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        let inner = Inner {
+            capacity,
+            ttl,
+            generation: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+        };
+        Self { inner: Arc::new(Mutex::new(inner)) }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // The cache is left consistent by every operation, so a poisoned lock
+        // can be safely reused.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Gets a cached permission level, marking it as recently used.
+    ///
+    /// The outer option is `None` on a cache miss.
+    pub fn get(&self, user_id: Option<&str>, ref_id: Uuid) -> Option<Option<PermissionLevel>> {
+        let mut inner = self.lock();
+        let key = (user_id.map(String::from), ref_id);
+        let entry = inner.entries.get(&key)?;
+        if entry.expires_at <= Instant::now() {
+            inner.remove(&key);
+            return None;
+        }
+        let (level, old_tick) = (entry.level, entry.tick);
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.recency.remove(&old_tick);
+        inner.recency.insert(tick, key.clone());
+        if let Some(entry) = inner.entries.get_mut(&key) {
+            entry.tick = tick;
+        }
+        Some(level)
+    }
+
+    /// Gets the current generation, to be passed to [`insert`](Self::insert)
+    /// after looking up a permission level.
+    pub fn generation(&self) -> Generation {
+        Generation(self.lock().generation)
+    }
+
+    /// Stores a permission level looked up since the given generation.
+    ///
+    /// The level is discarded if the cache has been invalidated since then.
+    pub fn insert(
+        &self,
+        generation: Generation,
+        user_id: Option<&str>,
+        ref_id: Uuid,
+        level: Option<PermissionLevel>,
+    ) {
+        let mut inner = self.lock();
+        if generation.0 != inner.generation || inner.capacity == 0 {
+            return;
+        }
+        let key = (user_id.map(String::from), ref_id);
+        inner.remove(&key);
+        while inner.entries.len() >= inner.capacity {
+            let Some((_, oldest)) = inner.recency.pop_first() else {
+                break;
+            };
+            inner.entries.remove(&oldest);
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        let expires_at = Instant::now() + inner.ttl;
+        inner.recency.insert(tick, key.clone());
+        inner.entries.insert(key, Entry { level, tick, expires_at });
+    }
+
+    /// Invalidates the cached permission levels of all users on a ref.
+    pub fn invalidate_ref(&self, ref_id: Uuid) {
+        let mut inner = self.lock();
+        inner.generation += 1;
+        let keys: Vec<_> = inner.entries.keys().filter(|key| key.1 == ref_id).cloned().collect();
+        for key in keys {
+            inner.remove(&key);
+        }
+    }
+
+    /// Number of entries in the cache, including expired ones.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Inner {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.tick);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_eviction() {
+        let cache = PermissionCache::new(2, DEFAULT_TTL);
+        let (a, b, c) = (Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7());
+        let generation = cache.generation();
+        cache.insert(generation, Some("alice"), a, Some(PermissionLevel::Own));
+        cache.insert(generation, None, b, None);
+        assert_eq!(cache.get(Some("alice"), a), Some(Some(PermissionLevel::Own)));
+
+        cache.insert(generation, Some("alice"), c, Some(PermissionLevel::Read));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(None, b), None);
+        assert_eq!(cache.get(Some("alice"), a), Some(Some(PermissionLevel::Own)));
+        assert_eq!(cache.get(Some("alice"), c), Some(Some(PermissionLevel::Read)));
+    }
+
+    #[test]
+    fn invalidation() {
+        let cache = PermissionCache::default();
+        let (a, b) = (Uuid::now_v7(), Uuid::now_v7());
+        let generation = cache.generation();
+        cache.insert(generation, Some("alice"), a, Some(PermissionLevel::Write));
+        cache.insert(generation, None, a, Some(PermissionLevel::Read));
+        cache.insert(generation, Some("alice"), b, Some(PermissionLevel::Write));
+
+        cache.invalidate_ref(a);
+        assert_eq!(cache.get(Some("alice"), a), None);
+        assert_eq!(cache.get(None, a), None);
+        assert_eq!(cache.get(Some("alice"), b), Some(Some(PermissionLevel::Write)));
+
+        // A lookup that began before the invalidation is not cached.
+        cache.insert(generation, Some("alice"), a, Some(PermissionLevel::Write));
+        assert_eq!(cache.get(Some("alice"), a), None);
+    }
+
+    #[test]
+    fn expiration() {
+        let cache = PermissionCache::new(10, Duration::ZERO);
+        let a = Uuid::now_v7();
+        cache.insert(cache.generation(), None, a, Some(PermissionLevel::Read));
+        assert_eq!(cache.get(None, a), None);
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+//! Invites to collaborate on documents, sent to email addresses.
+//!
+//! An owner of a document can share it with someone who does not yet have an
+//! account by inviting their email address. The invite is stored as a pending
+//! grant of a permission level on the ref. When a user signs in with a verified
+//! email address, any pending invites for the address are claimed: they are
+//! turned into permissions for the user and deleted, in the same transaction
+//! that records the sign-in. The backend does not deliver invites itself; the
+//! created invite is returned so that the client can notify the invitee.
+
+use chrono::{DateTime, Utc};
+use firebase_auth::FirebaseUser;
+use serde::Serialize;
+use sqlx::{PgConnection, Row};
+use uuid::Uuid;
+
+use super::PermissionLevel;
+use crate::app::{AppCtx, AppError, AppState};
+use crate::user_state_updates::update_ref_for_users;
+
+/// Maximum length of an email address, in characters.
+const MAX_EMAIL_LENGTH: usize = 254;
+
+/// A pending invite to collaborate on a document.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct Invite {
+    /// ID of the invite.
+    pub id: Uuid,
+
+    /// ID of the ref that the invite grants access to.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// Email address of the invitee, in lowercase.
+    pub email: String,
+
+    /// Permission level granted upon accepting the invite.
+    pub level: PermissionLevel,
+
+    /// ID of the user who sent the invite, if the account still exists.
+    #[serde(rename = "invitedBy")]
+    pub invited_by: Option<String>,
+
+    /// When the invite was created.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Normalizes an email address, failing if it is not plausibly valid.
+pub(crate) fn normalize_email(email: &str) -> Result<String, AppError> {
+    let email = email.trim().to_lowercase();
+    let valid = email.chars().count() <= MAX_EMAIL_LENGTH
+        && !email.chars().any(char::is_whitespace)
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+    if valid {
+        Ok(email)
+    } else {
+        Err(AppError::Invalid(format!("Invalid email address: {email}")))
+    }
+}
+
+/// Invites an email address to collaborate on a ref.
+///
+/// Inviting the same address to the same ref again replaces the permission
+/// level of the earlier invite. Ownership cannot be granted by invite.
+pub async fn invite_by_email(
+    ctx: &AppCtx,
+    ref_id: Uuid,
+    email: &str,
+    level: PermissionLevel,
+) -> Result<Invite, AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    if level == PermissionLevel::Own {
+        return Err(AppError::Invalid("Ownership cannot be granted by invite".to_string()));
+    }
+    let email = normalize_email(email)?;
+    let row = sqlx::query(
+        "
+        INSERT INTO invites(id, ref_id, email, level, invited_by)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (ref_id, email) DO UPDATE
+        SET level = EXCLUDED.level, invited_by = EXCLUDED.invited_by, created_at = NOW()
+        RETURNING id, created_at
+        ",
+    )
+    .bind(Uuid::now_v7())
+    .bind(ref_id)
+    .bind(&email)
+    .bind(level)
+    .bind(&user.user_id)
+    .fetch_one(&ctx.state.db)
+    .await?;
+    Ok(Invite {
+        id: row.get("id"),
+        ref_id,
+        email,
+        level,
+        invited_by: Some(user.user_id.clone()),
+        created_at: row.get("created_at"),
+    })
+}
+
+/// Lists the pending invites for a ref, oldest first.
+pub async fn list_invites(state: &AppState, ref_id: Uuid) -> Result<Vec<Invite>, AppError> {
+    let rows = sqlx::query(
+        "
+        SELECT id, ref_id, email, level, invited_by, created_at
+        FROM invites WHERE ref_id = $1
+        ORDER BY created_at
+        ",
+    )
+    .bind(ref_id)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| Invite {
+            id: row.get("id"),
+            ref_id: row.get("ref_id"),
+            email: row.get("email"),
+            level: row.get("level"),
+            invited_by: row.get("invited_by"),
+            created_at: row.get("created_at"),
+        })
+        .collect())
+}
+
+/// Gets the ref to which an invite grants access.
+pub async fn invite_ref(state: &AppState, invite_id: Uuid) -> Result<Uuid, AppError> {
+    sqlx::query_scalar("SELECT ref_id FROM invites WHERE id = $1")
+        .bind(invite_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Invite {invite_id}")))
+}
+
+/// Revokes a pending invite.
+pub async fn revoke_invite(state: &AppState, invite_id: Uuid) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM invites WHERE id = $1")
+        .bind(invite_id)
+        .execute(&state.db)
+        .await?;
+    Ok(())
+}
+
+/// Claims the pending invites for a user's verified email address.
+///
+/// The invites are converted into permissions for the user, never lowering a
+/// permission that the user already has, and then deleted. Should be called in
+/// a transaction after the user is recorded in the database. Returns the refs
+/// on which the user was granted permissions.
+pub async fn claim_invites(
+    conn: &mut PgConnection,
+    user: &FirebaseUser,
+) -> Result<Vec<Uuid>, AppError> {
+    if user.email_verified != Some(true) {
+        return Ok(Vec::new());
+    }
+    let Some(email) = user.email.as_deref() else {
+        return Ok(Vec::new());
+    };
+    let ref_ids = sqlx::query_scalar(
+        "
+        WITH claimed AS (
+            DELETE FROM invites WHERE email = $1
+            RETURNING ref_id, level
+        )
+        INSERT INTO permissions(subject, object, level)
+        SELECT $2, ref_id, level FROM claimed
+        ON CONFLICT ON CONSTRAINT permissions_is_relation DO UPDATE
+        SET level = GREATEST(permissions.level, EXCLUDED.level)
+        RETURNING object
+        ",
+    )
+    .bind(email.trim().to_lowercase())
+    .bind(&user.user_id)
+    .fetch_all(conn)
+    .await?;
+    Ok(ref_ids)
+}
+
+/// Refreshes cached permissions and user states after invites are claimed.
+pub async fn after_claiming_invites(state: &AppState, ref_ids: &[Uuid]) {
+    for &ref_id in ref_ids {
+        state.permission_cache.invalidate_ref(ref_id);
+        if let Err(e) = update_ref_for_users(state, ref_id, vec![]).await {
+            tracing::error!(%ref_id, error = %e, "Failed to update user states after invite");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_addresses() {
+        assert_eq!(normalize_email(" Ada@Example.org ").unwrap(), "ada@example.org");
+        assert!(normalize_email("ada").is_err());
+        assert!(normalize_email("@example.org").is_err());
+        assert!(normalize_email("ada@localhost").is_err());
+        assert!(normalize_email("a da@example.org").is_err());
+    }
+}
@@ -0,0 +1,343 @@
+//! Reproducible run bundles.
+//!
+//! A run bundle packages a document together with every document that it links
+//! to, transitively, such as the model analyzed by an analysis or the models
+//! instantiated in a composite model. Each document is stored as the content of
+//! the snapshot pinned by the link to it, or of its current snapshot if the link
+//! is not pinned to a version, along with the Automerge heads identifying that
+//! snapshot. Analysis configurations are part of the content of analysis
+//! documents and theories are identified by their IDs, so that, together with
+//! the recorded versions of the backend and document format, a bundle suffices
+//! to re-run the analyses exactly, as in the supplementary materials of a paper.
+//!
+//! Encrypted documents cannot be bundled, since the server does not have their
+//! plaintext, so exporting fails if the bundle would contain one.
+//!
+//! Importing a bundle creates a copy of each document owned by the importing
+//! user, with links rewritten to point to the copies. If any copy cannot be
+//! created, the copies already created are deleted.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError};
+use crate::auth::{self, PermissionLevel};
+use crate::document as doc;
+use crate::encryption;
+
+/// Version of the bundle format, incremented on incompatible changes.
+pub const BUNDLE_FORMAT: u32 = 1;
+
+/// Maximum number of documents in a bundle.
+const MAX_BUNDLE_DOCUMENTS: usize = 100;
+
+/// Versions of the software that produced a bundle.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BundleVersions {
+    /// Version of the backend package.
+    pub backend: String,
+
+    /// Git revision of the CatColab repository, including `catlog`, if known
+    /// at build time.
+    pub revision: Option<String>,
+
+    /// Version of the document format.
+    #[serde(rename = "documentFormat")]
+    pub document_format: String,
+}
+
+impl BundleVersions {
+    /// Versions of the running backend.
+    pub fn current() -> Self {
+        Self {
+            backend: env!("CARGO_PKG_VERSION").to_string(),
+            revision: option_env!("CATCOLAB_GIT_REV").map(String::from),
+            document_format: catcolab_document_types::CURRENT_VERSION.to_string(),
+        }
+    }
+}
+
+/// Document packaged in a bundle.
+#[qubit::ts]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BundledDocument {
+    /// ID of the ref from which the document was exported.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// Version of the ref that was exported, if links to it are pinned to one.
+    #[serde(default)]
+    pub version: Option<String>,
+
+    /// Content of the document at the exported snapshot.
+    pub content: Value,
+
+    /// Automerge heads of the exported snapshot, hex encoded.
+    pub heads: Vec<String>,
+}
+
+/// Self-contained archive of a document and the documents it links to.
+#[qubit::ts]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunBundle {
+    /// Version of the bundle format.
+    pub format: u32,
+
+    /// Versions of the software that produced the bundle.
+    pub versions: BundleVersions,
+
+    /// ID of the ref from which the bundle was exported.
+    pub root: Uuid,
+
+    /// IDs of the theories of the models in the bundle, sorted.
+    pub theories: Vec<String>,
+
+    /// Documents in the bundle, starting with the root and followed by the
+    /// documents it links to in breadth-first order.
+    pub documents: Vec<BundledDocument>,
+}
+
+/// Target of a link: a ref, at a version if the link is pinned to one.
+type LinkTarget = (Uuid, Option<String>);
+
+/// Gets the target of a link, if the object is one.
+///
+/// Links are recognized as objects with an `_id` and a `_server` field, per
+/// the format of stable refs.
+fn link_target(object: &Map<String, Value>) -> Option<LinkTarget> {
+    if !object.contains_key("_server") {
+        return None;
+    }
+    let id = Uuid::parse_str(object.get("_id")?.as_str()?).ok()?;
+    let version = object.get("_version").and_then(Value::as_str).map(String::from);
+    Some((id, version))
+}
+
+/// Gets the targets of the links in document content.
+fn linked_refs(content: &Value) -> Vec<LinkTarget> {
+    let mut targets = Vec::new();
+    let mut stack = vec![content];
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Object(object) => {
+                targets.extend(link_target(object));
+                stack.extend(object.values());
+            }
+            Value::Array(values) => stack.extend(values),
+            _ => {}
+        }
+    }
+    targets
+}
+
+/// Rewrites the links in document content according to a map of link targets.
+///
+/// Rewritten links refer to the head of the new ref, since the new ref has no
+/// history to which a version could refer.
+fn remap_links(content: &mut Value, ids: &HashMap<LinkTarget, Uuid>) {
+    match content {
+        Value::Object(object) => {
+            if let Some(new_id) = link_target(object).and_then(|target| ids.get(&target)) {
+                object.insert("_id".to_string(), Value::String(new_id.to_string()));
+                object.insert("_version".to_string(), Value::Null);
+            }
+            object.values_mut().for_each(|value| remap_links(value, ids));
+        }
+        Value::Array(values) => values.iter_mut().for_each(|value| remap_links(value, ids)),
+        _ => {}
+    }
+}
+
+/// Exports a ref and the refs it links to as a run bundle.
+///
+/// The user must be able to read every document in the bundle, and none of
+/// them may be encrypted.
+pub async fn export_run_bundle(ctx: &AppCtx, root: Uuid) -> Result<RunBundle, AppError> {
+    export(ctx, root, None).await
+}
+
+/// Exports a ref at a given snapshot and the refs it links to as a run bundle.
+///
+/// Linked refs are exported at the versions pinned by the links, if any, and
+/// otherwise at their current snapshots.
+pub async fn export_run_bundle_at(
+    ctx: &AppCtx,
+    root: Uuid,
+    snapshot_id: i32,
+) -> Result<RunBundle, AppError> {
+    export(ctx, root, Some(snapshot_id)).await
+}
+
+async fn export(
+    ctx: &AppCtx,
+    root: Uuid,
+    root_snapshot: Option<i32>,
+) -> Result<RunBundle, AppError> {
+    let mut documents = Vec::new();
+    let mut theories = HashSet::new();
+    let root_target = (root, root_snapshot.map(|id| id.to_string()));
+    let mut seen = HashSet::from([root_target.clone()]);
+    let mut frontier = vec![root_target];
+
+    while !frontier.is_empty() {
+        if documents.len() + frontier.len() > MAX_BUNDLE_DOCUMENTS {
+            return Err(AppError::Invalid(format!(
+                "Bundle exceeds maximum number of documents ({MAX_BUNDLE_DOCUMENTS})"
+            )));
+        }
+        let ref_ids: Vec<_> = frontier.iter().map(|(ref_id, _)| *ref_id).collect();
+        auth::authorize_all(ctx, &ref_ids, PermissionLevel::Read).await?;
+        let mut next = Vec::new();
+        for (ref_id, version) in frontier {
+            encryption::require_plaintext(&ctx.state, ref_id).await?;
+            let snapshot_id = version
+                .as_deref()
+                .map(str::parse::<i32>)
+                .transpose()
+                .map_err(|_| AppError::Invalid(format!("Invalid version of ref {ref_id}")))?;
+            let row = sqlx::query(
+                "
+                SELECT snapshots.content, snapshots.heads FROM refs
+                JOIN snapshots ON snapshots.for_ref = refs.id
+                    AND snapshots.id = COALESCE($2, refs.current_snapshot)
+                WHERE refs.id = $1 AND refs.deleted_at IS NULL
+                ",
+            )
+            .bind(ref_id)
+            .bind(snapshot_id)
+            .fetch_optional(&ctx.state.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("ref {ref_id}")))?;
+            let content: Value = row.get("content");
+            let heads: Vec<Vec<u8>> = row.get("heads");
+
+            if let Some(theory) = content.get("theory").and_then(Value::as_str) {
+                theories.insert(theory.to_string());
+            }
+            for target in linked_refs(&content) {
+                if seen.insert(target.clone()) {
+                    next.push(target);
+                }
+            }
+            documents.push(BundledDocument {
+                ref_id,
+                version,
+                content,
+                heads: heads.iter().map(hex::encode).collect(),
+            });
+        }
+        frontier = next;
+    }
+
+    let mut theories: Vec<_> = theories.into_iter().collect();
+    theories.sort();
+    Ok(RunBundle {
+        format: BUNDLE_FORMAT,
+        versions: BundleVersions::current(),
+        root,
+        theories,
+        documents,
+    })
+}
+
+/// Imports a run bundle, returning the ID of the copy of its root document.
+///
+/// Documents are created in reverse order, so that documents are usually
+/// created after those that they link to. Links to documents not in the bundle,
+/// or not yet created because of a cycle, are left unchanged.
+pub async fn import_run_bundle(ctx: &AppCtx, bundle: RunBundle) -> Result<Uuid, AppError> {
+    if ctx.user.is_none() {
+        return Err(AppError::Unauthorized);
+    }
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(AppError::Invalid(format!(
+            "Unsupported bundle format {} (expected {BUNDLE_FORMAT})",
+            bundle.format
+        )));
+    }
+    if bundle.documents.len() > MAX_BUNDLE_DOCUMENTS {
+        return Err(AppError::Invalid(format!(
+            "Bundle exceeds maximum number of documents ({MAX_BUNDLE_DOCUMENTS})"
+        )));
+    }
+    let root = (bundle.documents.iter())
+        .find(|document| document.ref_id == bundle.root)
+        .map(|document| (document.ref_id, document.version.clone()))
+        .ok_or_else(|| AppError::Invalid("Bundle does not contain its root document".into()))?;
+
+    let mut ids = HashMap::new();
+    for BundledDocument { ref_id, version, mut content, .. } in bundle.documents.into_iter().rev() {
+        remap_links(&mut content, &ids);
+        match doc::new_ref(ctx.clone(), content).await {
+            Ok(new_id) => {
+                ids.insert((ref_id, version), new_id);
+            }
+            Err(err) => {
+                for new_id in ids.into_values() {
+                    if let Err(e) = doc::delete_ref(ctx.state.clone(), new_id).await {
+                        tracing::error!(%new_id, error = %e, "Failed to delete imported copy");
+                    }
+                }
+                return Err(err);
+            }
+        }
+    }
+    Ok(ids[&root])
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn links_in_content() {
+        let model_id = Uuid::now_v7();
+        let mut content = json!({
+            "type": "analysis",
+            "analysisOf": {
+                "_id": model_id.to_string(),
+                "_version": "3",
+                "_server": "catcolab.org",
+                "type": "analysis-of",
+            },
+            "notebook": { "cellOrder": [], "cellContents": {} },
+        });
+        let pinned = (model_id, Some("3".to_string()));
+        assert_eq!(linked_refs(&content), vec![pinned.clone()]);
+
+        let copy_id = Uuid::now_v7();
+        remap_links(&mut content, &HashMap::from([(pinned, copy_id)]));
+        assert_eq!(content["analysisOf"]["_id"], copy_id.to_string());
+        assert_eq!(content["analysisOf"]["_version"], Value::Null);
+        assert_eq!(linked_refs(&content), vec![(copy_id, None)]);
+    }
+
+    #[test]
+    fn links_at_versions() {
+        let model_id = Uuid::now_v7();
+        let link = |version: Option<&str>| {
+            json!({
+                "_id": model_id.to_string(),
+                "_version": version,
+                "_server": "catcolab.org",
+            })
+        };
+        let mut content = json!([link(Some("3")), link(None)]);
+        let mut targets = linked_refs(&content);
+        targets.sort();
+        assert_eq!(targets, vec![(model_id, None), (model_id, Some("3".to_string()))]);
+
+        // Links to other versions of the same ref are left unchanged.
+        let copy_id = Uuid::now_v7();
+        remap_links(&mut content, &HashMap::from([((model_id, None), copy_id)]));
+        assert_eq!(content[0]["_id"], model_id.to_string());
+        assert_eq!(content[1]["_id"], copy_id.to_string());
+    }
+}
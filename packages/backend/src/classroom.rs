@@ -0,0 +1,227 @@
+//! Classroom mode: provisioning copies of a document for students.
+//!
+//! For a class or workshop, an instructor prepares a template document and
+//! provisions one copy of it for each student. Students are given by username
+//! or, if they do not have an account yet, by email address, in which case they
+//! are [invited](crate::auth::invites) to their copy and receive access when
+//! they sign up. The copies are owned by the instructor and recorded in the
+//! `classroom_copies` table, so that the instructor can follow the progress of
+//! the class from a dashboard.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState};
+use crate::auth::{PermissionLevel, invites};
+use crate::document as doc;
+use crate::user::{UserSummary, user_by_username};
+use crate::user_state_updates::update_ref_for_users;
+
+/// Maximum number of students for which copies can be provisioned at once.
+const MAX_STUDENTS: usize = 500;
+
+/// A copy of a template provisioned for a student.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct ProvisionedCopy {
+    /// The student, as given by the instructor.
+    pub student: String,
+
+    /// ID of the student's copy.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+}
+
+/// Status of a student's copy, as shown on the instructor dashboard.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct ClassroomCopy {
+    /// ID of the copy.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// The student, as given by the instructor.
+    pub student: String,
+
+    /// The student's account, once the student has access to the copy.
+    pub user: Option<UserSummary>,
+
+    /// Whether an invite to the copy is still waiting to be claimed.
+    #[serde(rename = "invitePending")]
+    pub invite_pending: bool,
+
+    /// When the copy was provisioned.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+
+    /// When the copy was last saved, if known.
+    #[serde(rename = "lastEdited")]
+    pub last_edited: Option<DateTime<Utc>>,
+
+    /// Number of snapshots of the copy.
+    pub snapshots: i64,
+}
+
+/// A student resolved to an account or an email address.
+enum Student {
+    User(UserSummary),
+    Email(String),
+}
+
+/// Resolves the students given by username or email address.
+///
+/// Fails without provisioning anything if any username is unknown.
+async fn resolve_students(
+    state: &AppState,
+    students: &[String],
+) -> Result<Vec<(String, Student)>, AppError> {
+    if students.is_empty() {
+        return Err(AppError::Invalid("No students given".to_string()));
+    }
+    if students.len() > MAX_STUDENTS {
+        return Err(AppError::Invalid(format!(
+            "Cannot provision copies for more than {MAX_STUDENTS} students at once"
+        )));
+    }
+    let mut seen = HashSet::new();
+    let mut resolved = Vec::new();
+    for student in students {
+        let student = student.trim();
+        if !seen.insert(student.to_lowercase()) {
+            continue;
+        }
+        let resolution = if student.contains('@') {
+            Student::Email(invites::normalize_email(student)?)
+        } else {
+            let user = user_by_username(state.clone(), student).await?;
+            Student::User(
+                user.ok_or_else(|| AppError::Invalid(format!("Unknown username: {student}")))?,
+            )
+        };
+        resolved.push((student.to_string(), resolution));
+    }
+    Ok(resolved)
+}
+
+/// Provisions a copy of a template ref for each student.
+///
+/// Each copy is owned by the active user and named after the template and the
+/// student, who is granted the given permission level on it. Copies provisioned
+/// before a failure are kept.
+pub async fn provision_copies(
+    ctx: &AppCtx,
+    template_ref: Uuid,
+    students: &[String],
+    level: PermissionLevel,
+) -> Result<Vec<ProvisionedCopy>, AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    if level == PermissionLevel::Own {
+        return Err(AppError::Invalid("Students cannot own their copies".to_string()));
+    }
+    let students = resolve_students(&ctx.state, students).await?;
+    let template = doc::get_content(ctx.state.clone(), template_ref).await?;
+    let name = template.get("name").and_then(Value::as_str).unwrap_or_default().to_string();
+
+    let mut copies = Vec::new();
+    for (label, student) in students {
+        let mut content = template.clone();
+        if let Some(obj) = content.as_object_mut() {
+            obj.insert("name".to_string(), Value::String(format!("{name} ({label})")));
+        }
+        let ref_id = doc::new_ref(ctx.clone(), content).await?;
+
+        match &student {
+            Student::User(student) => {
+                sqlx::query(
+                    "
+                    INSERT INTO permissions(subject, object, level)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT ON CONSTRAINT permissions_is_relation DO UPDATE
+                    SET level = GREATEST(permissions.level, EXCLUDED.level)
+                    ",
+                )
+                .bind(&student.id)
+                .bind(ref_id)
+                .bind(level)
+                .execute(&ctx.state.db)
+                .await?;
+                ctx.state.permission_cache.invalidate_ref(ref_id);
+                if let Err(e) = update_ref_for_users(&ctx.state, ref_id, vec![]).await {
+                    tracing::error!(%ref_id, error = %e, "Failed to update user states");
+                }
+            }
+            Student::Email(email) => {
+                invites::invite_by_email(ctx, ref_id, email, level).await?;
+            }
+        }
+
+        sqlx::query(
+            "
+            INSERT INTO classroom_copies(copy_ref, template_ref, student, created_by)
+            VALUES ($1, $2, $3, $4)
+            ",
+        )
+        .bind(ref_id)
+        .bind(template_ref)
+        .bind(&label)
+        .bind(&user.user_id)
+        .execute(&ctx.state.db)
+        .await?;
+
+        copies.push(ProvisionedCopy { student: label, ref_id });
+    }
+    Ok(copies)
+}
+
+/// Lists the copies provisioned from a template ref, excluding deleted ones.
+pub async fn list_classroom_copies(
+    state: &AppState,
+    template_ref: Uuid,
+) -> Result<Vec<ClassroomCopy>, AppError> {
+    let rows = sqlx::query(
+        "
+        SELECT c.copy_ref, c.student, c.created_at,
+               r.current_snapshot_updated_at AS last_edited,
+               (SELECT COUNT(*) FROM snapshots s WHERE s.for_ref = c.copy_ref) AS snapshots,
+               EXISTS (SELECT 1 FROM invites i WHERE i.ref_id = c.copy_ref) AS invite_pending,
+               u.id AS user_id, u.username, u.display_name
+        FROM classroom_copies c
+        JOIN refs r ON r.id = c.copy_ref
+        LEFT JOIN LATERAL (
+            SELECT users.id, users.username, users.display_name
+            FROM permissions p JOIN users ON users.id = p.subject
+            WHERE p.object = c.copy_ref AND p.level < 'own'
+            ORDER BY p.level DESC
+            LIMIT 1
+        ) u ON TRUE
+        WHERE c.template_ref = $1 AND r.deleted_at IS NULL
+        ORDER BY c.created_at, c.student
+        ",
+    )
+    .bind(template_ref)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| ClassroomCopy {
+            ref_id: row.get("copy_ref"),
+            student: row.get("student"),
+            user: row.get::<Option<String>, _>("user_id").map(|id| UserSummary {
+                id,
+                username: row.get("username"),
+                display_name: row.get("display_name"),
+            }),
+            invite_pending: row.get("invite_pending"),
+            created_at: row.get("created_at"),
+            last_edited: row.get("last_edited"),
+            snapshots: row.get("snapshots"),
+        })
+        .collect())
+}
@@ -0,0 +1,399 @@
+//! Registry of tabular datasets parsed from CSV.
+//!
+//! Datasets, such as time series used for parameter fitting, are associated
+//! with a document ref and referenced by ID, so that their data need not be
+//! inlined into documents. On upload, a CSV file is parsed into typed columns,
+//! each stored as a row of the `dataset_columns` table. Clients can then fetch
+//! slices of selected columns without downloading the whole dataset.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState};
+use crate::auth::{self, PermissionLevel};
+
+/// Maximum allowed size of an uploaded CSV file in bytes (10MB).
+const MAX_CSV_SIZE: usize = 10 * 1024 * 1024;
+
+/// Maximum number of rows that can be fetched in a single slice.
+const MAX_SLICE_ROWS: usize = 100_000;
+
+/// Values in a column of a dataset.
+///
+/// A column is numeric when every non-empty cell parses as a number. Empty
+/// cells are represented as missing values.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "tag", content = "content")]
+pub enum ColumnValues {
+    /// Numeric column.
+    Number(Vec<Option<f64>>),
+
+    /// Text column.
+    Text(Vec<Option<String>>),
+}
+
+impl ColumnValues {
+    /// Number of values in the column.
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnValues::Number(values) => values.len(),
+            ColumnValues::Text(values) => values.len(),
+        }
+    }
+
+    /// Whether the column is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Restricts the column to a range of rows, clamped to the column length.
+    pub fn slice(&self, start: usize, end: usize) -> Self {
+        let end = end.min(self.len());
+        let start = start.min(end);
+        match self {
+            ColumnValues::Number(values) => ColumnValues::Number(values[start..end].to_vec()),
+            ColumnValues::Text(values) => ColumnValues::Text(values[start..end].to_vec()),
+        }
+    }
+
+    /// Type of the values in the column.
+    pub fn column_type(&self) -> ColumnType {
+        match self {
+            ColumnValues::Number(_) => ColumnType::Number,
+            ColumnValues::Text(_) => ColumnType::Text,
+        }
+    }
+}
+
+/// Type of a column in a dataset.
+#[qubit::ts]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[sqlx(type_name = "dataset_column_type", rename_all = "lowercase")]
+pub enum ColumnType {
+    /// Numeric column.
+    Number,
+
+    /// Text column.
+    Text,
+}
+
+/// Named column of a dataset.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct DatasetColumn {
+    /// Name of the column, from the header of the CSV file.
+    pub name: String,
+
+    /// Values in the column.
+    pub values: ColumnValues,
+}
+
+/// Summary of a column in a dataset.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct ColumnSummary {
+    /// Name of the column.
+    pub name: String,
+
+    /// Type of the column.
+    #[serde(rename = "columnType")]
+    pub column_type: ColumnType,
+}
+
+/// Metadata of a dataset.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct Dataset {
+    /// ID of the dataset.
+    pub id: Uuid,
+
+    /// ID of the ref with which the dataset is associated.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// Human-readable name of the dataset.
+    pub name: String,
+
+    /// Columns of the dataset, in order.
+    pub columns: Vec<ColumnSummary>,
+
+    /// Number of rows in the dataset.
+    #[serde(rename = "numRows")]
+    pub num_rows: i64,
+
+    /// When the dataset was created.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request for a slice of a dataset.
+#[qubit::ts]
+#[derive(Clone, Debug, Deserialize)]
+pub struct SliceRequest {
+    /// Names of the columns to fetch, or all columns if omitted.
+    pub columns: Option<Vec<String>>,
+
+    /// Index of the first row, inclusive.
+    pub start: usize,
+
+    /// Index of the last row, exclusive.
+    pub end: usize,
+}
+
+/// Parses a CSV file with a header row into typed columns.
+pub fn parse_csv(data: &str) -> Result<Vec<DatasetColumn>, AppError> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(data.as_bytes());
+    let invalid = |e: csv::Error| AppError::Invalid(format!("Invalid CSV: {e}"));
+
+    let names: Vec<String> = reader.headers().map_err(invalid)?.iter().map(String::from).collect();
+    if names.is_empty() || names.iter().any(|name| name.is_empty()) {
+        return Err(AppError::Invalid("CSV header must name every column".to_string()));
+    }
+    for (i, name) in names.iter().enumerate() {
+        if names[..i].contains(name) {
+            return Err(AppError::Invalid(format!("Duplicate column name: {name}")));
+        }
+    }
+
+    let mut cells: Vec<Vec<Option<String>>> = vec![Vec::new(); names.len()];
+    for record in reader.records() {
+        let record = record.map_err(invalid)?;
+        for (column, cell) in cells.iter_mut().zip(record.iter()) {
+            column.push((!cell.is_empty()).then(|| cell.to_string()));
+        }
+    }
+
+    let columns = names
+        .into_iter()
+        .zip(cells)
+        .map(|(name, cells)| {
+            let numbers: Option<Vec<Option<f64>>> = cells
+                .iter()
+                .map(|cell| match cell {
+                    Some(cell) => cell.parse::<f64>().ok().filter(|x| x.is_finite()).map(Some),
+                    None => Some(None),
+                })
+                .collect();
+            let values = match numbers {
+                Some(numbers) => ColumnValues::Number(numbers),
+                None => ColumnValues::Text(cells),
+            };
+            DatasetColumn { name, values }
+        })
+        .collect();
+    Ok(columns)
+}
+
+/// Creates a dataset for a ref by parsing a CSV file.
+pub async fn create_dataset(
+    ctx: &AppCtx,
+    ref_id: Uuid,
+    name: String,
+    csv: &str,
+) -> Result<Dataset, AppError> {
+    if csv.len() > MAX_CSV_SIZE {
+        return Err(AppError::Invalid(format!(
+            "CSV size ({} bytes) exceeds maximum allowed size ({MAX_CSV_SIZE} bytes)",
+            csv.len()
+        )));
+    }
+    auth::authorize(ctx, ref_id, PermissionLevel::Write).await?;
+
+    let columns = parse_csv(csv)?;
+    let num_rows = columns.first().map_or(0, |column| column.values.len()) as i64;
+    let id = Uuid::now_v7();
+    let created_by = ctx.user.as_ref().map(|user| user.user_id.clone());
+
+    let mut txn = ctx.state.db.begin().await?;
+    let created_at: DateTime<Utc> = sqlx::query_scalar(
+        "
+        INSERT INTO datasets(id, ref_id, name, num_rows, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING created_at
+        ",
+    )
+    .bind(id)
+    .bind(ref_id)
+    .bind(&name)
+    .bind(num_rows)
+    .bind(created_by)
+    .fetch_one(&mut *txn)
+    .await?;
+
+    for (position, column) in columns.iter().enumerate() {
+        sqlx::query(
+            "
+            INSERT INTO dataset_columns(dataset_id, position, name, column_type, data)
+            VALUES ($1, $2, $3, $4, $5)
+            ",
+        )
+        .bind(id)
+        .bind(position as i32)
+        .bind(&column.name)
+        .bind(column.values.column_type())
+        .bind(Json(&column.values))
+        .execute(&mut *txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    Ok(Dataset {
+        id,
+        ref_id,
+        name,
+        columns: columns
+            .iter()
+            .map(|column| ColumnSummary {
+                name: column.name.clone(),
+                column_type: column.values.column_type(),
+            })
+            .collect(),
+        num_rows,
+        created_at,
+    })
+}
+
+/// Gets the metadata of a dataset.
+pub async fn get_dataset(state: &AppState, dataset_id: Uuid) -> Result<Dataset, AppError> {
+    let row =
+        sqlx::query("SELECT id, ref_id, name, num_rows, created_at FROM datasets WHERE id = $1")
+            .bind(dataset_id)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("dataset {dataset_id}")))?;
+
+    let columns = sqlx::query(
+        "
+        SELECT name, column_type FROM dataset_columns
+        WHERE dataset_id = $1 ORDER BY position
+        ",
+    )
+    .bind(dataset_id)
+    .fetch_all(&state.db)
+    .await?
+    .iter()
+    .map(|column| ColumnSummary {
+        name: column.get("name"),
+        column_type: column.get("column_type"),
+    })
+    .collect();
+
+    Ok(Dataset {
+        id: row.get("id"),
+        ref_id: row.get("ref_id"),
+        name: row.get("name"),
+        columns,
+        num_rows: row.get("num_rows"),
+        created_at: row.get("created_at"),
+    })
+}
+
+/// Lists the datasets of a ref, oldest first.
+pub async fn list_datasets(state: &AppState, ref_id: Uuid) -> Result<Vec<Dataset>, AppError> {
+    let ids: Vec<Uuid> =
+        sqlx::query_scalar("SELECT id FROM datasets WHERE ref_id = $1 ORDER BY created_at")
+            .bind(ref_id)
+            .fetch_all(&state.db)
+            .await?;
+    let mut datasets = Vec::with_capacity(ids.len());
+    for id in ids {
+        datasets.push(get_dataset(state, id).await?);
+    }
+    Ok(datasets)
+}
+
+/// Gets a slice of rows from selected columns of a dataset.
+pub async fn get_dataset_slice(
+    ctx: &AppCtx,
+    dataset_id: Uuid,
+    request: SliceRequest,
+) -> Result<Vec<DatasetColumn>, AppError> {
+    if request.end < request.start {
+        return Err(AppError::Invalid("Slice must not end before it starts".to_string()));
+    }
+    if request.end - request.start > MAX_SLICE_ROWS {
+        return Err(AppError::Invalid(format!(
+            "Slice exceeds maximum number of rows ({MAX_SLICE_ROWS})"
+        )));
+    }
+
+    let ref_id: Uuid = sqlx::query_scalar("SELECT ref_id FROM datasets WHERE id = $1")
+        .bind(dataset_id)
+        .fetch_optional(&ctx.state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("dataset {dataset_id}")))?;
+    auth::authorize(ctx, ref_id, PermissionLevel::Read).await?;
+
+    let rows = sqlx::query(
+        "
+        SELECT name, data FROM dataset_columns
+        WHERE dataset_id = $1 AND ($2::text[] IS NULL OR name = ANY($2))
+        ORDER BY position
+        ",
+    )
+    .bind(dataset_id)
+    .bind(&request.columns)
+    .fetch_all(&ctx.state.db)
+    .await?;
+
+    if let Some(names) = &request.columns
+        && let Some(missing) = names
+            .iter()
+            .find(|name| !rows.iter().any(|row| row.get::<&str, _>("name") == *name))
+    {
+        return Err(AppError::NotFound(format!("column {missing} of dataset {dataset_id}")));
+    }
+
+    Ok(rows
+        .iter()
+        .map(|row| DatasetColumn {
+            name: row.get("name"),
+            values: row.get::<Json<ColumnValues>, _>("data").0.slice(request.start, request.end),
+        })
+        .collect())
+}
+
+/// Deletes a dataset.
+pub async fn delete_dataset(ctx: &AppCtx, dataset_id: Uuid) -> Result<(), AppError> {
+    let dataset = get_dataset(&ctx.state, dataset_id).await?;
+    auth::authorize(ctx, dataset.ref_id, PermissionLevel::Write).await?;
+    sqlx::query("DELETE FROM datasets WHERE id = $1")
+        .bind(dataset_id)
+        .execute(&ctx.state.db)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_time_series() {
+        let csv = "time, S, I, label\n0, 99, 1, start\n1.5, 90, , \n3, 80.25, 20, end\n";
+        let columns = parse_csv(csv).unwrap();
+        assert_eq!(columns.len(), 4);
+        assert_eq!(columns[0].name, "time");
+        assert_eq!(columns[0].values, ColumnValues::Number(vec![Some(0.0), Some(1.5), Some(3.0)]));
+        assert_eq!(columns[2].values, ColumnValues::Number(vec![Some(1.0), None, Some(20.0)]));
+        assert_eq!(
+            columns[3].values,
+            ColumnValues::Text(vec![Some("start".into()), None, Some("end".into())])
+        );
+        assert_eq!(
+            columns[1].values.slice(1, 10),
+            ColumnValues::Number(vec![Some(90.0), Some(80.25)])
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse_csv("a,a\n1,2\n").is_err());
+        assert!(parse_csv("a,\n1,2\n").is_err());
+        assert!(parse_csv("a,b\n1,2,3\n").is_err());
+    }
+}
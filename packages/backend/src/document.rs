@@ -1,47 +1,90 @@
 //! Procedures to create and manipulate documents.
 
 use crate::app::{AppCtx, AppError, AppState};
+use crate::encryption::{self, EncryptionMetadata};
+use crate::lints;
 use crate::ref_actor::ensure_ref_actor;
+use crate::similarity;
 use crate::user_state_updates::{update_ref_for_users, update_user_state};
 use catcolab_document_types::automerge_json::{hydrate_to_json, populate_automerge_from_json};
 use catcolab_document_types::automerge_util::copy_doc_at_heads;
 use chrono::{DateTime, Utc};
 use samod::DocumentId;
 use serde_json::Value;
+use sqlx::types::Json;
 use uuid::Uuid;
 
 /// Maximum allowed document size in bytes (5MB).
 const MAX_DOCUMENT_SIZE: usize = 5 * 1024 * 1024;
 
-/// Creates a new document ref with initial content.
-pub async fn new_ref(ctx: AppCtx, content: Value) -> Result<Uuid, AppError> {
+/// Validates the size and structure of document content.
+pub fn validate_content(content: &Value) -> Result<(), AppError> {
     // Check document size before processing
-    let content_size = serde_json::to_string(&content).map(|s| s.len()).unwrap_or(0);
-    if content_size > MAX_DOCUMENT_SIZE {
-        return Err(AppError::Invalid(format!(
-            "Document size ({} bytes) exceeds maximum allowed size ({} bytes)",
-            content_size, MAX_DOCUMENT_SIZE
-        )));
-    }
+    validate_content_size(content)?;
 
     // Validate document structure by attempting to deserialize it
     let _validated_doc: catcolab_document_types::VersionedDocument =
         serde_json::from_value(content.clone())
             .map_err(|e| AppError::Invalid(format!("Failed to parse document: {}", e)))?;
 
-    let ref_id = Uuid::now_v7();
+    Ok(())
+}
+
+/// Validates the size of document content.
+pub fn validate_content_size(content: &Value) -> Result<(), AppError> {
+    let content_size = serde_json::to_string(content).map(|s| s.len()).unwrap_or(0);
+    if content_size > MAX_DOCUMENT_SIZE {
+        return Err(AppError::Invalid(format!(
+            "Document size ({} bytes) exceeds maximum allowed size ({} bytes)",
+            content_size, MAX_DOCUMENT_SIZE
+        )));
+    }
+    Ok(())
+}
 
-    // Create automerge document and populate it with the JSON content
+/// Creates an Automerge document populated with JSON content.
+pub fn automerge_from_json(content: &Value) -> Result<automerge::Automerge, AppError> {
     let mut automerge_doc = automerge::Automerge::new();
     automerge_doc
         .transact(|tx| {
-            populate_automerge_from_json(tx, automerge::ROOT, &content)?;
+            populate_automerge_from_json(tx, automerge::ROOT, content)?;
             Ok::<_, automerge::AutomergeError>(())
         })
         .map_err(|e| AppError::Invalid(format!("Failed to populate document: {:?}", e)))?;
+    Ok(automerge_doc)
+}
+
+/// Creates a new document ref with initial content.
+pub async fn new_ref(ctx: AppCtx, content: Value) -> Result<Uuid, AppError> {
+    validate_content(&content)?;
 
+    let automerge_doc = automerge_from_json(&content)?;
     let doc_handle = ctx.state.repo.create(automerge_doc).await?;
 
+    create_ref_for_doc(ctx, doc_handle, content).await
+}
+
+/// Creates a document ref for an existing Automerge document.
+///
+/// The given content, which should be that of the document, is saved as the
+/// initial snapshot of the ref, which is owned by the active user.
+pub async fn create_ref_for_doc(
+    ctx: AppCtx,
+    doc_handle: samod::DocHandle,
+    content: Value,
+) -> Result<Uuid, AppError> {
+    create_ref_with_encryption(ctx, doc_handle, content, None).await
+}
+
+/// Creates a document ref for an existing Automerge document, which is
+/// [encrypted](crate::encryption) if metadata about its key is given.
+pub async fn create_ref_with_encryption(
+    ctx: AppCtx,
+    doc_handle: samod::DocHandle,
+    content: Value,
+    encryption: Option<EncryptionMetadata>,
+) -> Result<Uuid, AppError> {
+    let ref_id = Uuid::now_v7();
     let doc_id = doc_handle.document_id().to_string();
     let heads: Vec<Vec<u8>> =
         doc_handle.with_document(|doc| doc.get_heads().iter().map(|h| h.0.to_vec()).collect());
@@ -60,8 +103,10 @@ pub async fn new_ref(ctx: AppCtx, content: Value) -> Result<Uuid, AppError> {
             VALUES ($1, $2, NOW(), $4)
         RETURNING id
         )
-        INSERT INTO refs(id, current_snapshot, created, doc_id, current_snapshot_updated_at)
-        VALUES ($1, (SELECT id FROM snapshot), NOW(), $3, NOW())
+        INSERT INTO refs(
+            id, current_snapshot, created, doc_id, current_snapshot_updated_at, encryption
+        )
+        VALUES ($1, (SELECT id FROM snapshot), NOW(), $3, NOW(), $5)
         ",
     )
     .bind(ref_id)
@@ -70,6 +115,7 @@ pub async fn new_ref(ctx: AppCtx, content: Value) -> Result<Uuid, AppError> {
     .bind(content)
     .bind(doc_id)
     .bind(&heads)
+    .bind(encryption.map(Json))
     .execute(&mut *txn)
     .await?;
 
@@ -117,6 +163,60 @@ pub async fn get_doc_binary_data(state: AppState, ref_id: Uuid) -> Result<String
     Ok(base64_data)
 }
 
+/// Gets the current content of a document as JSON.
+///
+/// Fails if the document is [encrypted](crate::encryption), since its
+/// plaintext is not available on the server.
+pub async fn get_content(state: AppState, ref_id: Uuid) -> Result<Value, AppError> {
+    encryption::require_plaintext(&state, ref_id).await?;
+    let doc_id = get_doc_id(state.clone(), ref_id).await?;
+
+    let doc_handle = state
+        .repo
+        .find(doc_id)
+        .await?
+        .ok_or_else(|| AppError::Invalid("Document not found".to_string()))?;
+
+    Ok(doc_handle.with_document(|doc| hydrate_to_json(&doc.hydrate(None))))
+}
+
+/// Replaces the content of a live Automerge document and saves a snapshot.
+///
+/// Like loading a snapshot, the document is updated in-place by deleting all
+/// root keys and repopulating them, so that connected clients receive the new
+/// content via normal Automerge sync.
+///
+/// The caller is responsible for suppressing autosave (the snapshot actor does
+/// this via its `skip_changes` counter).
+pub async fn replace_content(
+    state: &AppState,
+    ref_id: Uuid,
+    content: Value,
+    doc_handle: &samod::DocHandle,
+) -> Result<(), AppError> {
+    match encryption::get_encryption(state, ref_id).await? {
+        Some(metadata) => encryption::validate_encrypted_content(&content, &metadata)?,
+        None => validate_content(&content)?,
+    }
+
+    doc_handle.with_document(|doc| {
+        doc.transact::<_, _, automerge::AutomergeError>(|tx| {
+            use automerge::ReadDoc;
+            use automerge::transaction::Transactable;
+
+            let current_keys: Vec<String> = tx.keys(automerge::ROOT).collect();
+            for key in &current_keys {
+                tx.delete(automerge::ROOT, key.as_str())?;
+            }
+            populate_automerge_from_json(tx, automerge::ROOT, &content)
+        })
+        .map_err(|e| AppError::Automerge(e.error))?;
+        Ok::<(), AppError>(())
+    })?;
+
+    create_snapshot(state.clone(), ref_id).await
+}
+
 /// Gets the deleted_at timestamp for a document ref.
 pub async fn ref_deleted_at(
     state: AppState,
@@ -149,7 +249,7 @@ pub async fn create_snapshot(state: AppState, ref_id: Uuid) -> Result<(), AppErr
         (heads, doc_content)
     });
 
-    sqlx::query(
+    let snapshot_id: i32 = sqlx::query_scalar(
         "
         WITH snapshot AS (
             INSERT INTO snapshots(for_ref, content, created_at, heads, parent)
@@ -160,14 +260,25 @@ pub async fn create_snapshot(state: AppState, ref_id: Uuid) -> Result<(), AppErr
         SET current_snapshot = (SELECT id FROM snapshot),
             current_snapshot_updated_at = NOW()
         WHERE id = $1
+        RETURNING current_snapshot
         ",
     )
     .bind(ref_id)
-    .bind(doc_content)
+    .bind(&doc_content)
     .bind(&heads)
-    .execute(&state.db)
+    .fetch_one(&state.db)
     .await?;
 
+    if !encryption::is_encrypted_content(&doc_content) {
+        if let Err(e) = similarity::store_snapshot_features(&state, snapshot_id, &doc_content).await
+        {
+            tracing::error!(%ref_id, error = %e, "Failed to store features of snapshot");
+        }
+        if let Err(e) = lints::store_snapshot_lints(&state, snapshot_id, &doc_content).await {
+            tracing::error!(%ref_id, error = %e, "Failed to store lints of snapshot");
+        }
+    }
+
     if let Err(e) = update_ref_for_users(&state, ref_id, vec![]).await {
         tracing::error!(%ref_id, error = %e, "Failed to update user states after create_snapshot");
     }
@@ -0,0 +1,274 @@
+//! Cross-origin access policies and embedding of documents.
+//!
+//! Documents can be embedded as iframes in other sites, such as course pages.
+//! The HTTP policy controls which origins may call the API from a browser and
+//! which sites may frame the app. Since an embedding page should not be able to
+//! act on behalf of the viewer, embeds are authorized by an embed token rather
+//! than by the viewer's credentials. An embed token grants read-only access to a
+//! single ref through a single procedure and can be revoked at any time.
+
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Duration, Utc};
+use http::HeaderValue;
+use rand::Rng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState};
+use crate::auth::{self, PermissionLevel};
+use crate::{document as doc, moderation, verification};
+
+/// Policy for cross-origin requests and framing.
+#[derive(Clone, Debug, Default)]
+pub struct HttpPolicy {
+    /// Origins allowed to make cross-origin requests, or any origin if unset.
+    pub cors_origins: Option<Vec<String>>,
+
+    /// Sources allowed to embed the app in frames, or any source if unset.
+    ///
+    /// Sources use the syntax of the `frame-ancestors` directive of the Content
+    /// Security Policy, such as `'self'` or `https://*.example.edu`.
+    pub frame_ancestors: Option<Vec<String>>,
+}
+
+impl HttpPolicy {
+    /// Reads the policy from the environment.
+    ///
+    /// Both `CORS_ALLOWED_ORIGINS` and `FRAME_ANCESTORS` are whitespace- or
+    /// comma-separated lists.
+    pub fn from_env() -> Self {
+        let list = |var: &str| {
+            dotenvy::var(var).ok().map(|value| {
+                value
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+        };
+        Self {
+            cors_origins: list("CORS_ALLOWED_ORIGINS"),
+            frame_ancestors: list("FRAME_ANCESTORS"),
+        }
+    }
+
+    /// Layer applying the CORS policy.
+    pub fn cors_layer(&self) -> CorsLayer {
+        let Some(origins) = &self.cors_origins else {
+            return CorsLayer::very_permissive();
+        };
+        let origins: Vec<HeaderValue> =
+            origins.iter().filter_map(|origin| HeaderValue::from_str(origin).ok()).collect();
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods(AllowMethods::mirror_request())
+            .allow_headers(AllowHeaders::mirror_request())
+    }
+
+    /// Value of the `Content-Security-Policy` header restricting framing, if any.
+    pub fn content_security_policy(&self) -> Option<HeaderValue> {
+        let ancestors = self.frame_ancestors.as_ref()?;
+        let value = if ancestors.is_empty() {
+            "frame-ancestors 'none'".to_string()
+        } else {
+            format!("frame-ancestors {}", ancestors.join(" "))
+        };
+        HeaderValue::from_str(&value).ok()
+    }
+}
+
+/// Maximum lifetime of an embed token, in seconds.
+const MAX_EMBED_TOKEN_TTL_SECONDS: i64 = 5 * 365 * 24 * 60 * 60;
+
+/// Newly created embed token.
+///
+/// The token itself is only ever returned here, as the backend stores a hash.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct NewEmbedToken {
+    /// Metadata of the token.
+    pub info: EmbedTokenInfo,
+
+    /// The secret token, to be included in the embed URL.
+    pub token: String,
+}
+
+/// Metadata of an embed token.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct EmbedTokenInfo {
+    /// ID of the token.
+    pub id: Uuid,
+
+    /// ID of the ref that the token allows to be embedded.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// When the token was created.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+
+    /// When the token expires, if ever.
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Document accessed through an embed token.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct EmbeddedDoc {
+    /// ID of the embedded ref.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// Binary Automerge data of the document (base64 encoded).
+    #[serde(rename = "binaryData")]
+    pub binary_data: String,
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Creates an embed token for a ref.
+///
+/// Creating an embed token is a form of publishing, so it requires the same
+/// verification as making a document public.
+pub async fn create_embed_token(
+    ctx: &AppCtx,
+    ref_id: Uuid,
+    expires_in_seconds: Option<i64>,
+) -> Result<NewEmbedToken, AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    if expires_in_seconds.is_some_and(|ttl| !(1..=MAX_EMBED_TOKEN_TTL_SECONDS).contains(&ttl)) {
+        return Err(AppError::Invalid(format!(
+            "Embed token lifetime must be between 1 and {MAX_EMBED_TOKEN_TTL_SECONDS} seconds"
+        )));
+    }
+    auth::authorize(ctx, ref_id, PermissionLevel::Maintain).await?;
+    if moderation::is_hidden(&ctx.state, ref_id).await? {
+        return Err(AppError::Invalid(
+            "Document has been hidden by moderators and cannot be embedded".to_string(),
+        ));
+    }
+    verification::require_verified(ctx).await?;
+
+    let token = general_purpose::URL_SAFE_NO_PAD.encode(rand::thread_rng().r#gen::<[u8; 32]>());
+    let id = Uuid::now_v7();
+    let expires_at = expires_in_seconds.map(|ttl| Utc::now() + Duration::seconds(ttl));
+    let created_at: DateTime<Utc> = sqlx::query_scalar(
+        "
+        INSERT INTO embed_tokens(id, ref_id, token_hash, created_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING created_at
+        ",
+    )
+    .bind(id)
+    .bind(ref_id)
+    .bind(hash_token(&token))
+    .bind(&user.user_id)
+    .bind(expires_at)
+    .fetch_one(&ctx.state.db)
+    .await?;
+
+    Ok(NewEmbedToken {
+        info: EmbedTokenInfo { id, ref_id, created_at, expires_at },
+        token,
+    })
+}
+
+/// Lists the unrevoked embed tokens of a ref.
+pub async fn list_embed_tokens(
+    state: &AppState,
+    ref_id: Uuid,
+) -> Result<Vec<EmbedTokenInfo>, AppError> {
+    let rows = sqlx::query(
+        "
+        SELECT id, ref_id, created_at, expires_at FROM embed_tokens
+        WHERE ref_id = $1 AND revoked_at IS NULL
+        ORDER BY created_at
+        ",
+    )
+    .bind(ref_id)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| EmbedTokenInfo {
+            id: row.get("id"),
+            ref_id: row.get("ref_id"),
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+        })
+        .collect())
+}
+
+/// Revokes an embed token.
+pub async fn revoke_embed_token(ctx: &AppCtx, token_id: Uuid) -> Result<(), AppError> {
+    let ref_id: Uuid = sqlx::query_scalar("SELECT ref_id FROM embed_tokens WHERE id = $1")
+        .bind(token_id)
+        .fetch_optional(&ctx.state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("embed token {token_id}")))?;
+    auth::authorize(ctx, ref_id, PermissionLevel::Maintain).await?;
+    sqlx::query("UPDATE embed_tokens SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(token_id)
+        .execute(&ctx.state.db)
+        .await?;
+    Ok(())
+}
+
+/// Gets a document through an embed token.
+///
+/// Fails if the token is unknown, revoked, or expired, or if the document has
+/// since been deleted or hidden by moderators.
+pub async fn get_embedded_doc(state: &AppState, token: &str) -> Result<EmbeddedDoc, AppError> {
+    let ref_id: Uuid = sqlx::query_scalar(
+        "
+        SELECT embed_tokens.ref_id FROM embed_tokens
+        JOIN refs ON refs.id = embed_tokens.ref_id
+        WHERE token_hash = $1
+          AND revoked_at IS NULL
+          AND (expires_at IS NULL OR expires_at > NOW())
+          AND refs.deleted_at IS NULL
+          AND refs.hidden_at IS NULL
+        ",
+    )
+    .bind(hash_token(token))
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("embedded document".to_string()))?;
+
+    let binary_data = doc::get_doc_binary_data(state.clone(), ref_id).await?;
+    Ok(EmbeddedDoc { ref_id, binary_data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_ancestors() {
+        assert_eq!(HttpPolicy::default().content_security_policy(), None);
+
+        let policy = HttpPolicy {
+            cors_origins: None,
+            frame_ancestors: Some(vec!["'self'".into(), "https://*.example.edu".into()]),
+        };
+        assert_eq!(
+            policy.content_security_policy().unwrap(),
+            "frame-ancestors 'self' https://*.example.edu"
+        );
+
+        let policy = HttpPolicy {
+            cors_origins: None,
+            frame_ancestors: Some(vec![]),
+        };
+        assert_eq!(policy.content_security_policy().unwrap(), "frame-ancestors 'none'");
+    }
+}
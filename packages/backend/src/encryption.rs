@@ -0,0 +1,214 @@
+//! Documents encrypted on the client.
+//!
+//! For sensitive workflows, such as clinical modeling, a document can be
+//! encrypted by the client so that the server never sees its plaintext. The
+//! content of an encrypted document is an [envelope](EncryptedContent) holding
+//! the ciphertext of the actual document, which is synced and snapshotted like
+//! any other content. Since the ciphertext is replaced as a whole on each save,
+//! concurrent edits are resolved by the last writer winning rather than by
+//! merging. The key never reaches the server: the ref records only
+//! [metadata](EncryptionMetadata) about the key, in the `encryption` column of
+//! the `refs` table, from which clients derive or unwrap it.
+//!
+//! Whatever the server does with plaintext is unavailable for encrypted
+//! documents. Their content is not validated beyond the envelope, their
+//! features are not stored for similarity search, and
+//! [getting their content](crate::document::get_content), as analyses and
+//! exports do, fails with [`AppError::Encrypted`].
+
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState};
+use crate::document as doc;
+
+/// Supported encryption algorithms, as named by the Web Crypto API and
+/// libsodium.
+const ALGORITHMS: &[&str] = &["AES-256-GCM", "XChaCha20-Poly1305"];
+
+/// Maximum length of a key ID, in characters.
+const MAX_KEY_ID_LENGTH: usize = 128;
+
+/// Maximum length of a nonce, in bytes.
+const MAX_NONCE_LENGTH: usize = 32;
+
+/// Value of the `type` field of encrypted content.
+const ENCRYPTED_TYPE: &str = "encrypted";
+
+/// Metadata about the key of an encrypted document.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptionMetadata {
+    /// Encryption algorithm, such as `AES-256-GCM`.
+    pub algorithm: String,
+
+    /// Identifier of the key, chosen by the client.
+    #[serde(rename = "keyId")]
+    pub key_id: String,
+
+    /// Parameters for deriving or unwrapping the key, such as a salt, which
+    /// are opaque to the server.
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// Content of an encrypted document.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedContent {
+    /// Type of the content, always `encrypted`.
+    #[serde(rename = "type")]
+    pub content_type: String,
+
+    /// Name of the document, stored in plaintext so that it can be listed.
+    ///
+    /// Clients should leave out the name when it is itself sensitive.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Identifier of the key that the document is encrypted with.
+    #[serde(rename = "keyId")]
+    pub key_id: String,
+
+    /// Ciphertext of the document (base64 encoded).
+    pub ciphertext: String,
+
+    /// Nonce used to encrypt the document (base64 encoded).
+    pub nonce: String,
+}
+
+impl EncryptionMetadata {
+    /// Validates the metadata.
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !ALGORITHMS.contains(&self.algorithm.as_str()) {
+            return Err(AppError::Invalid(format!(
+                "Unsupported encryption algorithm: {}",
+                self.algorithm
+            )));
+        }
+        if self.key_id.is_empty() || self.key_id.chars().count() > MAX_KEY_ID_LENGTH {
+            return Err(AppError::Invalid("Invalid key ID".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Whether document content is encrypted.
+pub fn is_encrypted_content(content: &Value) -> bool {
+    content.get("type").and_then(Value::as_str) == Some(ENCRYPTED_TYPE)
+}
+
+/// Validates the content of a document encrypted with the given key.
+pub fn validate_encrypted_content(
+    content: &Value,
+    metadata: &EncryptionMetadata,
+) -> Result<(), AppError> {
+    doc::validate_content_size(content)?;
+    let content: EncryptedContent = serde_json::from_value(content.clone())
+        .map_err(|e| AppError::Invalid(format!("Failed to parse encrypted content: {e}")))?;
+    if content.content_type != ENCRYPTED_TYPE {
+        return Err(AppError::Invalid("Content is not encrypted".into()));
+    }
+    if content.key_id != metadata.key_id {
+        return Err(AppError::Invalid(format!(
+            "Content not encrypted with key {}",
+            metadata.key_id
+        )));
+    }
+    let decode = |field: &str, data: &str| {
+        general_purpose::STANDARD
+            .decode(data)
+            .map_err(|_| AppError::Invalid(format!("Invalid base64 in {field}")))
+    };
+    decode("ciphertext", &content.ciphertext)?;
+    let nonce = decode("nonce", &content.nonce)?;
+    if nonce.is_empty() || nonce.len() > MAX_NONCE_LENGTH {
+        return Err(AppError::Invalid("Invalid nonce".into()));
+    }
+    Ok(())
+}
+
+/// Creates a new encrypted document ref with initial content.
+pub async fn new_encrypted_ref(
+    ctx: AppCtx,
+    content: Value,
+    encryption: EncryptionMetadata,
+) -> Result<Uuid, AppError> {
+    encryption.validate()?;
+    validate_encrypted_content(&content, &encryption)?;
+
+    let automerge_doc = doc::automerge_from_json(&content)?;
+    let doc_handle = ctx.state.repo.create(automerge_doc).await?;
+
+    doc::create_ref_with_encryption(ctx, doc_handle, content, Some(encryption)).await
+}
+
+/// Gets the encryption metadata of a ref, if it is encrypted.
+pub async fn get_encryption(
+    state: &AppState,
+    ref_id: Uuid,
+) -> Result<Option<EncryptionMetadata>, AppError> {
+    let row = sqlx::query("SELECT encryption FROM refs WHERE id = $1")
+        .bind(ref_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("ref {ref_id}")))?;
+    let encryption: Option<Json<EncryptionMetadata>> = row.try_get("encryption")?;
+    Ok(encryption.map(|Json(metadata)| metadata))
+}
+
+/// Checks that a ref is not encrypted, so that its plaintext is available.
+pub async fn require_plaintext(state: &AppState, ref_id: Uuid) -> Result<(), AppError> {
+    if get_encryption(state, ref_id).await?.is_some() {
+        Err(AppError::Encrypted(ref_id))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn validate_envelope() {
+        let metadata = EncryptionMetadata {
+            algorithm: "AES-256-GCM".into(),
+            key_id: "key-1".into(),
+            params: json!({ "salt": "c2FsdA==" }),
+        };
+        assert!(metadata.validate().is_ok());
+
+        let content = json!({
+            "type": "encrypted",
+            "keyId": "key-1",
+            "ciphertext": "AAECAwQ=",
+            "nonce": "AAAAAAAAAAAAAAAA",
+        });
+        assert!(is_encrypted_content(&content));
+        assert!(validate_encrypted_content(&content, &metadata).is_ok());
+
+        let other_key = EncryptionMetadata {
+            key_id: "key-2".into(),
+            ..metadata.clone()
+        };
+        assert!(validate_encrypted_content(&content, &other_key).is_err());
+        let mut bad = content.clone();
+        bad["ciphertext"] = json!("not base64!");
+        assert!(validate_encrypted_content(&bad, &metadata).is_err());
+
+        let plaintext = json!({ "type": "model", "name": "SIR" });
+        assert!(!is_encrypted_content(&plaintext));
+        assert!(validate_encrypted_content(&plaintext, &metadata).is_err());
+
+        let unsupported = EncryptionMetadata { algorithm: "ROT13".into(), ..metadata };
+        assert!(unsupported.validate().is_err());
+    }
+}
@@ -0,0 +1,263 @@
+//! Feature flags for experimental features.
+//!
+//! A feature flag is identified by a key, such as `history_insights`, and is
+//! set in the `feature_flags` table for one of three scopes: everyone, the users
+//! of an organization, or a single user. Organizations are identified by the
+//! domain of their members' verified email addresses. When a flag is set in
+//! several scopes that apply to a user, the most specific setting wins, so a
+//! feature enabled for an organization can still be disabled for one of its
+//! members. Flags that are not set anywhere are disabled.
+//!
+//! Handlers for experimental features check their flag with [`require_feature`],
+//! while the frontend fetches the enabled flags to decide which UI to show.
+//! Flags are managed by administrators through the RPC API.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use firebase_auth::FirebaseUser;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+
+use crate::app::{AppCtx, AppError, AppState};
+
+/// Maximum length of a feature flag key, in characters.
+const MAX_FLAG_LENGTH: usize = 64;
+
+/// Flag for edit history insights on documents.
+pub const HISTORY_INSIGHTS: &str = "history_insights";
+
+/// Scope in which a feature flag is set.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(tag = "tag", content = "content")]
+pub enum FeatureFlagScope {
+    /// All users, including anonymous ones.
+    Everyone,
+
+    /// Users with a verified email address at the given domain.
+    Domain(String),
+
+    /// The user with the given ID.
+    User(String),
+}
+
+impl FeatureFlagScope {
+    /// Specificity of the scope; more specific settings take precedence.
+    fn specificity(&self) -> u8 {
+        match self {
+            FeatureFlagScope::Everyone => 0,
+            FeatureFlagScope::Domain(_) => 1,
+            FeatureFlagScope::User(_) => 2,
+        }
+    }
+
+    /// Gets the user ID and email domain columns for the scope.
+    fn columns(&self) -> (Option<&str>, Option<&str>) {
+        match self {
+            FeatureFlagScope::Everyone => (None, None),
+            FeatureFlagScope::Domain(domain) => (None, Some(domain)),
+            FeatureFlagScope::User(user_id) => (Some(user_id), None),
+        }
+    }
+
+    /// Gets the scope of a row of the `feature_flags` table.
+    fn from_row(row: &sqlx::postgres::PgRow) -> Self {
+        match (row.get("user_id"), row.get("email_domain")) {
+            (Some(user_id), _) => FeatureFlagScope::User(user_id),
+            (None, Some(domain)) => FeatureFlagScope::Domain(domain),
+            (None, None) => FeatureFlagScope::Everyone,
+        }
+    }
+}
+
+/// Setting of a feature flag in a scope.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct FeatureFlag {
+    /// Key of the flag.
+    pub flag: String,
+
+    /// Scope in which the flag is set.
+    pub scope: FeatureFlagScope,
+
+    /// Whether the feature is enabled in the scope.
+    pub enabled: bool,
+
+    /// When the setting was last changed.
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+fn validate_flag(flag: &str) -> Result<(), AppError> {
+    if flag.is_empty() || flag.chars().count() > MAX_FLAG_LENGTH {
+        return Err(AppError::Invalid(format!(
+            "Feature flag must have between 1 and {MAX_FLAG_LENGTH} characters"
+        )));
+    }
+    if !flag.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+        return Err(AppError::Invalid(
+            "Feature flag may only contain lowercase letters, digits, and '_'".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Gets the domain of a user's email address, if it is verified.
+pub(crate) fn verified_email_domain(user: &FirebaseUser) -> Option<String> {
+    if user.email_verified != Some(true) {
+        return None;
+    }
+    let (_, domain) = user.email.as_deref()?.rsplit_once('@')?;
+    Some(domain.to_lowercase())
+}
+
+/// Resolves the settings of flags applying to a user into the enabled flags.
+fn resolve_flags(
+    settings: impl IntoIterator<Item = (String, FeatureFlagScope, bool)>,
+) -> Vec<String> {
+    let mut resolved: HashMap<String, (u8, bool)> = HashMap::new();
+    for (flag, scope, enabled) in settings {
+        let specificity = scope.specificity();
+        let entry = resolved.entry(flag).or_insert((specificity, enabled));
+        if specificity > entry.0 {
+            *entry = (specificity, enabled);
+        }
+    }
+    let mut flags: Vec<_> = resolved
+        .into_iter()
+        .filter(|(_, (_, enabled))| *enabled)
+        .map(|(flag, _)| flag)
+        .collect();
+    flags.sort();
+    flags
+}
+
+/// Gets the feature flags enabled for the active user, sorted by key.
+pub async fn enabled_features(ctx: &AppCtx) -> Result<Vec<String>, AppError> {
+    let user_id = ctx.user.as_ref().map(|user| user.user_id.clone());
+    let domain = ctx.user.as_ref().and_then(verified_email_domain);
+    let rows = sqlx::query(
+        "
+        SELECT flag, user_id, email_domain, enabled FROM feature_flags
+        WHERE (user_id IS NULL AND email_domain IS NULL)
+            OR user_id = $1 OR email_domain = $2
+        ",
+    )
+    .bind(user_id)
+    .bind(domain)
+    .fetch_all(&ctx.state.db)
+    .await?;
+    Ok(resolve_flags(rows.iter().map(|row| {
+        (row.get("flag"), FeatureFlagScope::from_row(row), row.get("enabled"))
+    })))
+}
+
+/// Is the feature enabled for the active user?
+pub async fn is_enabled(ctx: &AppCtx, flag: &str) -> Result<bool, AppError> {
+    Ok(enabled_features(ctx).await?.iter().any(|f| f == flag))
+}
+
+/// Requires the feature to be enabled for the active user.
+pub async fn require_feature(ctx: &AppCtx, flag: &str) -> Result<(), AppError> {
+    if is_enabled(ctx, flag).await? {
+        Ok(())
+    } else {
+        Err(AppError::FeatureDisabled(flag.to_string()))
+    }
+}
+
+/// Lists the settings of all feature flags, ordered by key.
+pub async fn list_feature_flags(state: &AppState) -> Result<Vec<FeatureFlag>, AppError> {
+    let rows = sqlx::query(
+        "
+        SELECT flag, user_id, email_domain, enabled, updated_at FROM feature_flags
+        ORDER BY flag, user_id NULLS FIRST, email_domain NULLS FIRST
+        ",
+    )
+    .fetch_all(&state.db)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| FeatureFlag {
+            flag: row.get("flag"),
+            scope: FeatureFlagScope::from_row(row),
+            enabled: row.get("enabled"),
+            updated_at: row.get("updated_at"),
+        })
+        .collect())
+}
+
+/// Sets a feature flag in a scope or, given `None`, clears the setting.
+pub async fn set_feature_flag(
+    state: &AppState,
+    flag: &str,
+    scope: &FeatureFlagScope,
+    enabled: Option<bool>,
+) -> Result<(), AppError> {
+    validate_flag(flag)?;
+    let scope = match scope {
+        FeatureFlagScope::Domain(domain) => FeatureFlagScope::Domain(domain.to_lowercase()),
+        scope => scope.clone(),
+    };
+    let (user_id, domain) = scope.columns();
+    let query = match enabled {
+        Some(enabled) => sqlx::query(
+            "
+            INSERT INTO feature_flags(flag, user_id, email_domain, enabled)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (flag, (COALESCE(user_id, '')), (COALESCE(email_domain, '')))
+            DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = NOW()
+            ",
+        )
+        .bind(flag)
+        .bind(user_id)
+        .bind(domain)
+        .bind(enabled),
+        None => sqlx::query(
+            "
+            DELETE FROM feature_flags
+            WHERE flag = $1 AND user_id IS NOT DISTINCT FROM $2
+                AND email_domain IS NOT DISTINCT FROM $3
+            ",
+        )
+        .bind(flag)
+        .bind(user_id)
+        .bind(domain),
+    };
+    match query.execute(&state.db).await {
+        Ok(_) => Ok(()),
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            Err(AppError::Invalid("No user exists with the given ID".to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_keys() {
+        assert!(validate_flag("history_insights").is_ok());
+        assert!(validate_flag("").is_err());
+        assert!(validate_flag("History").is_err());
+        assert!(validate_flag(&"x".repeat(MAX_FLAG_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn most_specific_setting_wins() {
+        let domain = FeatureFlagScope::Domain("example.org".into());
+        let user = FeatureFlagScope::User("alice".into());
+        let settings = [
+            ("jobs".to_string(), FeatureFlagScope::Everyone, true),
+            ("jobs".to_string(), user.clone(), false),
+            ("analyses".to_string(), domain.clone(), true),
+            ("analyses".to_string(), FeatureFlagScope::Everyone, false),
+            ("history_insights".to_string(), user, true),
+            ("history_insights".to_string(), domain, false),
+        ];
+        assert_eq!(resolve_flags(settings), vec!["analyses", "history_insights"]);
+    }
+}
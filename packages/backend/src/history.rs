@@ -0,0 +1,242 @@
+//! Analytics of the edit history of documents.
+//!
+//! The edit history of a ref is summarized from two sources: the snapshots
+//! saved in the database, which record how the size of the document grows over
+//! time, and the changes in the live Automerge document, which record who edited
+//! the document and when. The statistics are returned as series ready to be
+//! charted in the history panel of the web app.
+//!
+//! Contributors are identified by their Automerge actor IDs. An actor ID is
+//! created per editing session rather than per user, so a single user can appear
+//! as several contributors.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::app::{AppError, AppState};
+use crate::document::get_doc_id;
+
+/// Statistics about the edit history of a ref.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct EditHistory {
+    /// Size of the document at each snapshot, oldest first.
+    pub snapshots: Vec<SnapshotSize>,
+
+    /// Edit activity per day, in chronological order.
+    pub daily: Vec<DailyEdits>,
+
+    /// Statistics per contributor, most active first.
+    pub contributors: Vec<ContributorStats>,
+}
+
+/// Size of a document at one of its snapshots.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct SnapshotSize {
+    /// When the snapshot was created.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+
+    /// Size of the JSON content of the snapshot, in bytes.
+    pub bytes: i64,
+}
+
+/// Edit activity on a document during a single day (UTC).
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DailyEdits {
+    /// The day.
+    pub day: NaiveDate,
+
+    /// Number of snapshots created on the day.
+    pub snapshots: usize,
+
+    /// Number of Automerge changes made on the day.
+    pub changes: usize,
+
+    /// Number of distinct contributors making changes on the day.
+    pub contributors: usize,
+}
+
+/// Edit statistics of a single contributor to a document.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ContributorStats {
+    /// Automerge actor ID of the contributor, in hexadecimal.
+    pub actor: String,
+
+    /// Number of changes made by the contributor.
+    pub changes: usize,
+
+    /// When the contributor first edited the document.
+    #[serde(rename = "firstEdit")]
+    pub first_edit: DateTime<Utc>,
+
+    /// When the contributor last edited the document.
+    #[serde(rename = "lastEdit")]
+    pub last_edit: DateTime<Utc>,
+}
+
+/// A change to a document, by actor and time.
+struct Edit {
+    actor: String,
+    time: DateTime<Utc>,
+}
+
+/// Computes statistics about the edit history of a ref.
+pub async fn get_edit_history(state: &AppState, ref_id: Uuid) -> Result<EditHistory, AppError> {
+    let rows = sqlx::query(
+        "
+        SELECT created_at, octet_length(content::text)::bigint AS bytes
+        FROM snapshots WHERE for_ref = $1
+        ORDER BY created_at
+        ",
+    )
+    .bind(ref_id)
+    .fetch_all(&state.db)
+    .await?;
+    let snapshots: Vec<_> = rows
+        .iter()
+        .map(|row| SnapshotSize {
+            created_at: row.get("created_at"),
+            bytes: row.get("bytes"),
+        })
+        .collect();
+
+    let doc_id = get_doc_id(state.clone(), ref_id).await?;
+    let doc_handle = state
+        .repo
+        .find(doc_id)
+        .await?
+        .ok_or_else(|| AppError::Invalid("Document not found".to_string()))?;
+    let edits: Vec<_> = doc_handle.with_document(|doc| {
+        doc.get_changes(&[])
+            .iter()
+            .filter_map(|change| {
+                // Changes made without a timestamp are recorded at the epoch.
+                let time = DateTime::from_timestamp_millis(change.timestamp())
+                    .filter(|time| time.timestamp() > 0)?;
+                Some(Edit {
+                    actor: change.actor_id().to_hex_string(),
+                    time,
+                })
+            })
+            .collect()
+    });
+
+    let snapshot_times: Vec<_> = snapshots.iter().map(|s| s.created_at).collect();
+    Ok(EditHistory {
+        daily: daily_edits(&snapshot_times, &edits),
+        contributors: contributor_stats(&edits),
+        snapshots,
+    })
+}
+
+/// Buckets snapshots and changes by day.
+fn daily_edits(snapshot_times: &[DateTime<Utc>], edits: &[Edit]) -> Vec<DailyEdits> {
+    let mut days: BTreeMap<NaiveDate, (usize, Vec<&str>)> = BTreeMap::new();
+    for time in snapshot_times {
+        days.entry(time.date_naive()).or_default().0 += 1;
+    }
+    for edit in edits {
+        days.entry(edit.time.date_naive()).or_default().1.push(&edit.actor);
+    }
+    days.into_iter()
+        .map(|(day, (snapshots, mut actors))| {
+            let changes = actors.len();
+            actors.sort_unstable();
+            actors.dedup();
+            DailyEdits {
+                day,
+                snapshots,
+                changes,
+                contributors: actors.len(),
+            }
+        })
+        .collect()
+}
+
+/// Aggregates changes by contributor, most active first.
+fn contributor_stats(edits: &[Edit]) -> Vec<ContributorStats> {
+    let mut stats: BTreeMap<&str, ContributorStats> = BTreeMap::new();
+    for edit in edits {
+        stats
+            .entry(&edit.actor)
+            .and_modify(|s| {
+                s.changes += 1;
+                s.first_edit = s.first_edit.min(edit.time);
+                s.last_edit = s.last_edit.max(edit.time);
+            })
+            .or_insert_with(|| ContributorStats {
+                actor: edit.actor.clone(),
+                changes: 1,
+                first_edit: edit.time,
+                last_edit: edit.time,
+            });
+    }
+    let mut stats: Vec<_> = stats.into_values().collect();
+    stats.sort_by(|a, b| b.changes.cmp(&a.changes).then(a.first_edit.cmp(&b.first_edit)));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn edit(actor: &str, time: &str) -> Edit {
+        Edit { actor: actor.to_string(), time: at(time) }
+    }
+
+    #[test]
+    fn edit_statistics() {
+        let edits = [
+            edit("aa", "2026-03-01T09:00:00Z"),
+            edit("bb", "2026-03-01T10:00:00Z"),
+            edit("aa", "2026-03-01T11:00:00Z"),
+            edit("aa", "2026-03-03T08:00:00Z"),
+        ];
+        let snapshots = [at("2026-03-01T12:00:00Z"), at("2026-03-02T12:00:00Z")];
+
+        let daily = daily_edits(&snapshots, &edits);
+        let day = |s: &str| s.parse::<NaiveDate>().unwrap();
+        assert_eq!(
+            daily,
+            vec![
+                DailyEdits {
+                    day: day("2026-03-01"),
+                    snapshots: 1,
+                    changes: 3,
+                    contributors: 2,
+                },
+                DailyEdits {
+                    day: day("2026-03-02"),
+                    snapshots: 1,
+                    changes: 0,
+                    contributors: 0,
+                },
+                DailyEdits {
+                    day: day("2026-03-03"),
+                    snapshots: 0,
+                    changes: 1,
+                    contributors: 1,
+                },
+            ]
+        );
+
+        let contributors = contributor_stats(&edits);
+        assert_eq!(contributors.len(), 2);
+        assert_eq!(contributors[0].actor, "aa");
+        assert_eq!(contributors[0].changes, 3);
+        assert_eq!(contributors[0].first_edit, at("2026-03-01T09:00:00Z"));
+        assert_eq!(contributors[0].last_edit, at("2026-03-03T08:00:00Z"));
+    }
+}
@@ -0,0 +1,168 @@
+//! Localization of user-facing messages.
+//!
+//! User-facing messages are identified by a key together with named parameters,
+//! so that clients can render them in the user's language. Message templates
+//! are stored in per-locale JSON catalogs under `i18n/`, which are compiled into
+//! the binary. Parameters are substituted into templates where their names
+//! appear in braces, as in `{refId}`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::LazyLock;
+
+use serde::Serialize;
+
+use crate::access_policy::PolicyDenial;
+use crate::app::AppError;
+
+/// Locale used when a message is not available in the requested locale.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Sources of the compiled-in message catalogs, by locale.
+const CATALOG_SOURCES: &[(&str, &str)] = &[("en", include_str!("../i18n/en.json"))];
+
+static CATALOGS: LazyLock<HashMap<&'static str, HashMap<String, String>>> = LazyLock::new(|| {
+    CATALOG_SOURCES
+        .iter()
+        .map(|(locale, source)| {
+            let catalog = serde_json::from_str(source).expect("message catalog should be valid");
+            (*locale, catalog)
+        })
+        .collect()
+});
+
+/// A user-facing message, identified by a key with named parameters.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Message {
+    /// Key of the message in the catalogs.
+    pub key: String,
+
+    /// Values of the parameters appearing in the message template.
+    pub params: BTreeMap<String, String>,
+}
+
+impl Message {
+    /// Constructs a message without parameters.
+    pub fn new(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a parameter to the message.
+    pub fn param(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Renders the message in a locale.
+    ///
+    /// Falls back to the default locale if the message is missing from the
+    /// requested locale, and to the message key if it is missing altogether.
+    pub fn localize(&self, locale: &str) -> String {
+        let Some(template) = [locale, DEFAULT_LOCALE]
+            .iter()
+            .find_map(|locale| CATALOGS.get(*locale)?.get(&self.key))
+        else {
+            return self.key.clone();
+        };
+        self.params.iter().fold(template.clone(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+    }
+}
+
+/// Gets the message catalog for a locale, if one exists.
+pub fn catalog(locale: &str) -> Option<&'static HashMap<String, String>> {
+    CATALOGS.get(locale)
+}
+
+impl AppError {
+    /// User-facing message describing the error.
+    ///
+    /// Unlike the `Display` implementation, which is intended for logs, the
+    /// message does not include internal details of server errors.
+    pub fn message(&self) -> Message {
+        match self {
+            AppError::Db(_) => Message::new("error.database"),
+            AppError::NotFound(resource) => {
+                Message::new("error.not_found").param("resource", resource)
+            }
+            AppError::AutomergeRepo(_) => Message::new("error.document_repo"),
+            AppError::Automerge(_) => Message::new("error.document"),
+            AppError::UserStateSync(_) => Message::new("error.user_state_sync"),
+            AppError::Json(_) => Message::new("error.serialization"),
+            AppError::Invalid(detail) => {
+                Message::new("error.invalid_request").param("detail", detail)
+            }
+            AppError::Unauthorized => Message::new("error.unauthorized"),
+            AppError::Forbidden(ref_id) => Message::new("error.forbidden").param("refId", ref_id),
+            AppError::Unverified => Message::new("error.unverified"),
+            AppError::AdminOnly => Message::new("error.admin_only"),
+            AppError::FeatureDisabled(feature) => {
+                Message::new("error.feature_disabled").param("feature", feature)
+            }
+            AppError::PolicyDenied(PolicyDenial::Network(addr)) => {
+                Message::new("error.policy_network").param("address", addr)
+            }
+            AppError::PolicyDenied(PolicyDenial::EmailDomain) => {
+                Message::new("error.policy_email_domain")
+            }
+            AppError::Encrypted(ref_id) => Message::new("error.encrypted").param("refId", ref_id),
+            AppError::Maintenance(None) => Message::new("error.maintenance"),
+            AppError::Maintenance(Some(eta)) => {
+                Message::new("error.maintenance_until").param("eta", eta.to_rfc3339())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn localize_errors() {
+        let ref_id = Uuid::nil();
+        let message = AppError::Forbidden(ref_id).message();
+        assert_eq!(message.key, "error.forbidden");
+        assert_eq!(
+            message.localize("en"),
+            format!("You do not have permission to access document {ref_id}.")
+        );
+        assert_eq!(message.localize("xx"), message.localize(DEFAULT_LOCALE));
+        assert_eq!(Message::new("error.nonexistent").localize("en"), "error.nonexistent");
+    }
+
+    #[test]
+    fn catalogs_are_complete() {
+        let keys: Vec<_> = catalog(DEFAULT_LOCALE).unwrap().keys().collect();
+        for (locale, _) in CATALOG_SOURCES {
+            let catalog = catalog(locale).unwrap();
+            assert!(keys.iter().all(|key| catalog.contains_key(*key)), "{locale} is incomplete");
+        }
+        for error in [
+            AppError::NotFound("x".into()),
+            AppError::UserStateSync("x".into()),
+            AppError::Invalid("x".into()),
+            AppError::Unauthorized,
+            AppError::Forbidden(Uuid::nil()),
+            AppError::Unverified,
+            AppError::AdminOnly,
+            AppError::FeatureDisabled("x".into()),
+            AppError::PolicyDenied(PolicyDenial::Network([10, 0, 0, 1].into())),
+            AppError::PolicyDenied(PolicyDenial::EmailDomain),
+            AppError::Encrypted(Uuid::nil()),
+            AppError::Maintenance(None),
+            AppError::Maintenance(Some(chrono::Utc::now())),
+        ] {
+            let message = error.message();
+            let text = message.localize(DEFAULT_LOCALE);
+            assert_ne!(text, message.key);
+            assert!(!text.contains('{'), "unsubstituted parameter in {text}");
+        }
+    }
+}
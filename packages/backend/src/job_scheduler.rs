@@ -0,0 +1,272 @@
+//! Scheduling of background jobs.
+//!
+//! [Jobs](crate::jobs) run in a fixed number of slots, so that a flood of jobs
+//! cannot exhaust the blocking thread pool. Jobs waiting for a slot are queued
+//! and started in order of
+//!
+//! 1. priority class, interactive jobs that a user is waiting on going before
+//!    batch jobs such as ensemble runs;
+//! 2. number of jobs of the same user already running, fewest first;
+//! 3. time since the user last had a job started, longest first;
+//! 4. time queued, longest first.
+//!
+//! The middle two criteria share the slots fairly among users, round-robin when
+//! they are all busy, so that one user's thousands of jobs do not block
+//! everyone else's. To protect batch jobs from starvation by a steady stream of
+//! interactive ones, batch jobs queued for long enough are promoted to the
+//! interactive class.
+//!
+//! The queue is held in memory, so jobs queued when the server restarts are
+//! never started.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::oneshot;
+
+/// Default maximum number of jobs running at once.
+const DEFAULT_MAX_RUNNING: usize = 4;
+
+/// Time after which a queued batch job is promoted to the interactive class.
+const STARVATION_THRESHOLD: Duration = Duration::from_secs(5 * 60);
+
+/// Priority class of a job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobPriority {
+    /// A job that a user is waiting on.
+    Interactive,
+
+    /// A job running in the background, such as a large ensemble.
+    Batch,
+}
+
+/// Scheduler of background jobs.
+///
+/// Cheaply cloneable, with clones sharing the same slots and queue.
+#[derive(Clone)]
+pub struct JobScheduler {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    max_running: usize,
+    starvation_threshold: Duration,
+    running: usize,
+    users: HashMap<String, UserJobs>,
+    queue: Vec<QueuedJob>,
+}
+
+/// Jobs of a single user, tracked while the user has any running or queued.
+#[derive(Default)]
+struct UserJobs {
+    running: usize,
+    queued: usize,
+    last_started: Option<Instant>,
+}
+
+struct QueuedJob {
+    owner: String,
+    priority: JobPriority,
+    queued_at: Instant,
+    start: oneshot::Sender<()>,
+}
+
+/// Permission for a job to run, holding one of the slots until dropped.
+pub struct JobPermit {
+    scheduler: JobScheduler,
+    owner: String,
+}
+
+impl Default for JobScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_RUNNING, STARVATION_THRESHOLD)
+    }
+}
+
+impl JobScheduler {
+    /// Creates a scheduler with the given number of slots and time after which
+    /// batch jobs are promoted.
+    pub fn new(max_running: usize, starvation_threshold: Duration) -> Self {
+        let inner = Inner {
+            max_running: max_running.max(1),
+            starvation_threshold,
+            running: 0,
+            users: HashMap::new(),
+            queue: Vec::new(),
+        };
+        Self { inner: Arc::new(Mutex::new(inner)) }
+    }
+
+    /// Creates a scheduler with the number of slots read from the
+    /// `MAX_RUNNING_JOBS` environment variable, if set.
+    pub fn from_env() -> Self {
+        let max_running = dotenvy::var("MAX_RUNNING_JOBS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MAX_RUNNING);
+        Self::new(max_running, STARVATION_THRESHOLD)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // The queue and counts are left consistent by every operation, so a
+        // poisoned lock can be safely reused.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits for a slot to run a job of a user.
+    pub async fn acquire(&self, owner: &str, priority: JobPriority) -> JobPermit {
+        let receiver = {
+            let mut inner = self.lock();
+            if inner.running < inner.max_running && inner.queue.is_empty() {
+                inner.start(owner);
+                None
+            } else {
+                let (start, receiver) = oneshot::channel();
+                inner.users.entry(owner.to_string()).or_default().queued += 1;
+                inner.queue.push(QueuedJob {
+                    owner: owner.to_string(),
+                    priority,
+                    queued_at: Instant::now(),
+                    start,
+                });
+                Some(receiver)
+            }
+        };
+        if let Some(receiver) = receiver {
+            // The slot is handed over before the job is started, and the
+            // sender is only dropped along with the scheduler.
+            let _ = receiver.await;
+        }
+        JobPermit {
+            scheduler: self.clone(),
+            owner: owner.to_string(),
+        }
+    }
+
+    /// Number of jobs running and queued.
+    pub fn load(&self) -> (usize, usize) {
+        let inner = self.lock();
+        (inner.running, inner.queue.len())
+    }
+}
+
+impl Inner {
+    /// Records that a job of a user has started.
+    fn start(&mut self, owner: &str) {
+        self.running += 1;
+        let user = self.users.entry(owner.to_string()).or_default();
+        user.running += 1;
+        user.last_started = Some(Instant::now());
+    }
+
+    /// Records that a job of a user has finished.
+    fn finish(&mut self, owner: &str) {
+        self.running -= 1;
+        if let Some(user) = self.users.get_mut(owner) {
+            user.running -= 1;
+        }
+        self.prune(owner);
+    }
+
+    /// Forgets a user with no running or queued jobs.
+    fn prune(&mut self, owner: &str) {
+        if self.users.get(owner).is_some_and(|user| user.running == 0 && user.queued == 0) {
+            self.users.remove(owner);
+        }
+    }
+
+    /// Index of the queued job to start next.
+    fn next(&self, now: Instant) -> Option<usize> {
+        self.queue
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, job)| {
+                let promoted = job.priority == JobPriority::Interactive
+                    || now.duration_since(job.queued_at) >= self.starvation_threshold;
+                let user = self.users.get(&job.owner);
+                (
+                    !promoted,
+                    user.map_or(0, |user| user.running),
+                    user.and_then(|user| user.last_started),
+                    job.queued_at,
+                )
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// Starts queued jobs while there are free slots.
+    fn dispatch(&mut self) {
+        while self.running < self.max_running {
+            let Some(i) = self.next(Instant::now()) else {
+                break;
+            };
+            let job = self.queue.remove(i);
+            if let Some(user) = self.users.get_mut(&job.owner) {
+                user.queued -= 1;
+            }
+            self.start(&job.owner);
+            if job.start.send(()).is_err() {
+                // The job was abandoned while queued.
+                self.finish(&job.owner);
+            }
+        }
+    }
+}
+
+impl Drop for JobPermit {
+    fn drop(&mut self) {
+        let mut inner = self.scheduler.lock();
+        inner.finish(&self.owner);
+        inner.dispatch();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Queues jobs behind a running one and returns the order they start in.
+    async fn start_order(
+        scheduler: &JobScheduler,
+        jobs: &[(&'static str, JobPriority)],
+    ) -> Vec<&'static str> {
+        let first = scheduler.acquire("alice", JobPriority::Batch).await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for &(owner, priority) in jobs {
+            let (scheduler, order) = (scheduler.clone(), order.clone());
+            tasks.push(tokio::spawn(async move {
+                let _permit = scheduler.acquire(owner, priority).await;
+                order.lock().unwrap().push(owner);
+            }));
+            // Let the job queue before the next one.
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(scheduler.load(), (1, jobs.len()));
+        drop(first);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(scheduler.load(), (0, 0));
+        Arc::try_unwrap(order).unwrap().into_inner().unwrap()
+    }
+
+    #[tokio::test]
+    async fn fair_scheduling() {
+        use JobPriority::*;
+        let scheduler = JobScheduler::new(1, STARVATION_THRESHOLD);
+        let jobs = [("alice", Batch), ("alice", Batch), ("bob", Batch), ("carol", Interactive)];
+        let order = start_order(&scheduler, &jobs).await;
+        assert_eq!(order, vec!["carol", "bob", "alice", "alice"]);
+    }
+
+    #[tokio::test]
+    async fn batch_jobs_promoted() {
+        use JobPriority::*;
+        let scheduler = JobScheduler::new(1, Duration::ZERO);
+        let jobs = [("bob", Batch), ("carol", Interactive)];
+        let order = start_order(&scheduler, &jobs).await;
+        assert_eq!(order, vec!["bob", "carol"]);
+    }
+}
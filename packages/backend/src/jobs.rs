@@ -0,0 +1,789 @@
+//! Background jobs for long-running computations.
+//!
+//! Computations too slow to answer within a request, such as clustering the
+//! submissions of a class, are run as jobs. Starting a job records it in the
+//! `jobs` table and returns its ID at once. The job stays queued until the
+//! [scheduler](crate::job_scheduler) gives it a slot. The computation then runs
+//! in the background, on the blocking thread pool since it is CPU-bound, and its
+//! result or error is recorded on the job, from which the client polls for it. A
+//! job is visible only to the user who started it.
+//!
+//! While running, a job can report its [progress](ProgressReporter): how much
+//! of the work is done, a message, and optionally a partial result. Reports are
+//! recorded in the `job_progress` table in order of sequence number. A client
+//! follows the progress of a job by [long polling](wait_for_job_progress),
+//! repeatedly asking for the records after the last one it has seen until the
+//! job finishes.
+//!
+//! Each job runs within [limits](JobLimits) on its time and memory. Resource
+//! limits of the operating system, such as rlimits, apply to the whole server
+//! process rather than to a single job, so the limits are enforced by the job
+//! itself through its [budget](JobBudget): the computation checks the budget
+//! between stages and reserves memory before large allocations. A job that
+//! overruns its time limit without noticing is abandoned and recorded as timed
+//! out, though its thread runs on until the computation returns. Exceeding a
+//! limit is recorded as a typed [failure](JobFailure) of the job, so that the
+//! client can tell it apart from an error in the computation.
+//!
+//! Besides its result, a job can produce [artifacts](JobArtifact), such as
+//! plots, CSV exports, and serialized results, too large or too opaque to
+//! include in the result itself. They are uploaded to the object storage used
+//! for [attachments](crate::attachments) when the job completes, recorded in
+//! the `job_artifacts` table, and listed on the job with presigned URLs from
+//! which the client downloads them.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use catlog::instrument::{Budget, BudgetExhausted};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::Row;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState};
+use crate::attachments;
+use crate::job_scheduler::JobPriority;
+
+/// Status of a job.
+#[qubit::ts]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    /// The job has been created but has not started.
+    Queued,
+
+    /// The job is running.
+    Running,
+
+    /// The job finished with a result.
+    Succeeded,
+
+    /// The job finished with an error.
+    Failed,
+}
+
+impl JobStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self, AppError> {
+        match s {
+            "queued" => Ok(JobStatus::Queued),
+            "running" => Ok(JobStatus::Running),
+            "succeeded" => Ok(JobStatus::Succeeded),
+            "failed" => Ok(JobStatus::Failed),
+            _ => Err(AppError::Invalid(format!("Unknown job status: {s}"))),
+        }
+    }
+
+    /// Whether a job with this status has finished.
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed)
+    }
+}
+
+/// Kind of failure of a job.
+#[qubit::ts]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobFailure {
+    /// The computation returned an error.
+    Error,
+
+    /// The computation panicked.
+    Panicked,
+
+    /// The job exceeded its time limit.
+    TimedOut,
+
+    /// The job exceeded its memory limit.
+    OutOfMemory,
+}
+
+impl JobFailure {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobFailure::Error => "error",
+            JobFailure::Panicked => "panicked",
+            JobFailure::TimedOut => "timed-out",
+            JobFailure::OutOfMemory => "out-of-memory",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self, AppError> {
+        match s {
+            "error" => Ok(JobFailure::Error),
+            "panicked" => Ok(JobFailure::Panicked),
+            "timed-out" => Ok(JobFailure::TimedOut),
+            "out-of-memory" => Ok(JobFailure::OutOfMemory),
+            _ => Err(AppError::Invalid(format!("Unknown job failure: {s}"))),
+        }
+    }
+}
+
+/// Error from the computation of a job.
+#[derive(Error, Debug)]
+pub enum JobError {
+    /// Error in the computation.
+    #[error(transparent)]
+    App(#[from] AppError),
+
+    /// The computation panicked.
+    #[error("Job panicked: {0}")]
+    Panicked(String),
+
+    /// The job exceeded its time limit.
+    #[error("Job exceeded its time limit of {} seconds", .0.as_secs())]
+    TimedOut(Duration),
+
+    /// The job exceeded its memory limit, in bytes.
+    #[error("Job exceeded its memory limit of {} MiB", .0 >> 20)]
+    OutOfMemory(usize),
+}
+
+impl JobError {
+    /// Kind of failure of a job failing with this error.
+    pub fn failure(&self) -> JobFailure {
+        match self {
+            JobError::App(_) => JobFailure::Error,
+            JobError::Panicked(_) => JobFailure::Panicked,
+            JobError::TimedOut(_) => JobFailure::TimedOut,
+            JobError::OutOfMemory(_) => JobFailure::OutOfMemory,
+        }
+    }
+}
+
+impl From<serde_json::Error> for JobError {
+    fn from(e: serde_json::Error) -> Self {
+        JobError::App(e.into())
+    }
+}
+
+/// A background job.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct Job {
+    /// ID of the job.
+    pub id: Uuid,
+
+    /// Kind of computation, such as `model-clustering`.
+    pub kind: String,
+
+    /// Status of the job.
+    pub status: JobStatus,
+
+    /// Result of the job, once it has succeeded.
+    pub result: Option<Value>,
+
+    /// Error message, if the job has failed.
+    pub error: Option<String>,
+
+    /// Kind of failure, if the job has failed.
+    pub failure: Option<JobFailure>,
+
+    /// Artifacts produced by the job, once it has succeeded.
+    pub artifacts: Vec<JobArtifact>,
+
+    /// When the job was created.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+
+    /// When the job finished, if it has.
+    #[serde(rename = "finishedAt")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// A file produced by a job, such as a plot or a CSV export.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct JobArtifact {
+    /// ID of the artifact.
+    pub id: Uuid,
+
+    /// File name of the artifact.
+    pub filename: String,
+
+    /// MIME type of the artifact.
+    #[serde(rename = "contentType")]
+    pub content_type: String,
+
+    /// Size of the artifact in bytes.
+    pub size: i64,
+
+    /// Presigned URL from which the artifact can be downloaded, valid for a
+    /// limited time.
+    #[serde(rename = "downloadUrl")]
+    pub download_url: String,
+}
+
+/// Output of a successful job: its result and any artifacts to attach to it.
+///
+/// A computation with no artifacts can simply return its result, which
+/// converts into an output.
+#[derive(Clone, Debug, Default)]
+pub struct JobOutput {
+    /// Result of the job.
+    pub result: Value,
+
+    /// Artifacts to attach to the job.
+    pub artifacts: Vec<NewJobArtifact>,
+}
+
+/// An artifact produced by a job, not yet stored.
+#[derive(Clone, Debug)]
+pub struct NewJobArtifact {
+    /// File name of the artifact.
+    pub filename: String,
+
+    /// MIME type of the artifact.
+    pub content_type: String,
+
+    /// Contents of the artifact.
+    pub data: Vec<u8>,
+}
+
+impl JobOutput {
+    /// Creates an output with the given result and no artifacts.
+    pub fn new(result: Value) -> Self {
+        Self { result, artifacts: Vec::new() }
+    }
+
+    /// Adds an artifact to the output.
+    pub fn artifact(
+        mut self,
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        data: Vec<u8>,
+    ) -> Self {
+        self.artifacts.push(NewJobArtifact {
+            filename: filename.into(),
+            content_type: content_type.into(),
+            data,
+        });
+        self
+    }
+}
+
+impl From<Value> for JobOutput {
+    fn from(result: Value) -> Self {
+        Self::new(result)
+    }
+}
+
+/// A record of progress reported by a running job.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct JobProgress {
+    /// Sequence number of the record, counting up from zero.
+    pub seq: i32,
+
+    /// Percentage of the work done, from 0 to 100.
+    pub percent: f32,
+
+    /// Message describing the current stage of the work.
+    pub message: String,
+
+    /// Partial result of the job, if reported.
+    #[serde(rename = "partialResult")]
+    pub partial_result: Option<Value>,
+
+    /// When the progress was reported.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Update on a job: its current state and any new progress records.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct JobUpdate {
+    /// The job.
+    pub job: Job,
+
+    /// Progress records after the one requested, in order.
+    pub progress: Vec<JobProgress>,
+}
+
+/// Reports the progress of a running job.
+///
+/// Reporting never blocks the computation: reports are sent to a task that
+/// records them in the background. They are all recorded before the outcome
+/// of the job, so a finished job has its complete progress.
+#[derive(Clone, Debug)]
+pub struct ProgressReporter {
+    sender: mpsc::UnboundedSender<ProgressReport>,
+}
+
+/// Progress reported by a job, to be recorded.
+#[derive(Debug)]
+struct ProgressReport {
+    percent: f32,
+    message: String,
+    partial_result: Option<Value>,
+}
+
+impl ProgressReporter {
+    /// Reports the percentage of the work done, with a message.
+    pub fn report(&self, percent: f32, message: impl Into<String>) {
+        self.send(percent, message.into(), None);
+    }
+
+    /// Reports the percentage of the work done, with a message and a partial
+    /// result.
+    pub fn report_partial(&self, percent: f32, message: impl Into<String>, partial_result: Value) {
+        self.send(percent, message.into(), Some(partial_result));
+    }
+
+    fn send(&self, percent: f32, message: String, partial_result: Option<Value>) {
+        let report = ProgressReport {
+            percent: percent.clamp(0.0, 100.0),
+            message,
+            partial_result,
+        };
+        // The recorder outlives every reporter, so sending fails only if it
+        // panicked, in which case the progress is lost.
+        let _ = self.sender.send(report);
+    }
+}
+
+/// Default time limit of a job.
+const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(10 * 60);
+
+/// Default memory limit of a job, in bytes.
+const DEFAULT_MEMORY_LIMIT: usize = 1 << 30;
+
+/// Time after its time limit that a job is given to notice it before being
+/// abandoned.
+const TIME_LIMIT_GRACE: Duration = Duration::from_secs(10);
+
+/// Limits on the resources used by a job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JobLimits {
+    /// Maximum wall time of the computation.
+    pub time: Duration,
+
+    /// Maximum memory reserved by the computation, in bytes.
+    pub memory: usize,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        Self {
+            time: DEFAULT_TIME_LIMIT,
+            memory: DEFAULT_MEMORY_LIMIT,
+        }
+    }
+}
+
+impl JobLimits {
+    /// Sets the time limit.
+    pub fn time(mut self, time: Duration) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Sets the memory limit, in bytes.
+    pub fn memory(mut self, memory: usize) -> Self {
+        self.memory = memory;
+        self
+    }
+}
+
+/// Budget of a running job, enforcing its [limits](JobLimits).
+///
+/// The budget is cooperative: the computation should [check](Self::check) it
+/// regularly and [reserve](Self::reserve) memory before allocating much of it.
+#[derive(Debug)]
+pub struct JobBudget {
+    limits: JobLimits,
+    started: std::time::Instant,
+    reserved: AtomicUsize,
+}
+
+impl JobBudget {
+    /// Starts enforcing the limits of a job.
+    pub fn start(limits: JobLimits) -> Self {
+        Self {
+            limits,
+            started: std::time::Instant::now(),
+            reserved: AtomicUsize::new(0),
+        }
+    }
+
+    /// Time remaining before the time limit.
+    pub fn remaining_time(&self) -> Duration {
+        self.limits.time.saturating_sub(self.started.elapsed())
+    }
+
+    /// Checks that the job is within its time limit.
+    pub fn check(&self) -> Result<(), JobError> {
+        if self.remaining_time().is_zero() {
+            Err(JobError::TimedOut(self.limits.time))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reserves memory for the job, checking that it stays within its memory
+    /// limit.
+    pub fn reserve(&self, bytes: usize) -> Result<(), JobError> {
+        let reserved = self.reserved.fetch_add(bytes, Ordering::Relaxed).saturating_add(bytes);
+        if reserved > self.limits.memory {
+            self.reserved.fetch_sub(bytes, Ordering::Relaxed);
+            Err(JobError::OutOfMemory(self.limits.memory))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Releases memory previously reserved.
+    pub fn release(&self, bytes: usize) {
+        self.reserved.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    /// Budget for a search in `catlog` using the time remaining.
+    pub fn search_budget(&self) -> Budget {
+        let millis = self.remaining_time().as_millis().try_into().unwrap_or(u64::MAX);
+        Budget::unlimited().millis(millis)
+    }
+
+    /// Error for a search in `catlog` that ran out of the
+    /// [budget](Self::search_budget) given to it.
+    pub fn exhausted(&self, _: BudgetExhausted) -> JobError {
+        JobError::TimedOut(self.limits.time)
+    }
+}
+
+/// Starts a job running the given computation in the background.
+///
+/// The job is owned by the current user, who must be logged in, and scheduled
+/// with the given priority and limits. The computation returns the result of
+/// the job, or a [`JobOutput`] to attach artifacts to it. Returns the ID of the
+/// job.
+pub async fn spawn_job<F, R>(
+    ctx: &AppCtx,
+    kind: &str,
+    priority: JobPriority,
+    limits: JobLimits,
+    params: Value,
+    compute: F,
+) -> Result<Uuid, AppError>
+where
+    F: FnOnce(ProgressReporter, &JobBudget) -> Result<R, JobError> + Send + 'static,
+    R: Into<JobOutput> + Send + 'static,
+{
+    let owner = ctx.user.as_ref().ok_or(AppError::Unauthorized)?.user_id.clone();
+    let id = Uuid::now_v7();
+    sqlx::query("INSERT INTO jobs (id, kind, owner, params) VALUES ($1, $2, $3, $4)")
+        .bind(id)
+        .bind(kind)
+        .bind(&owner)
+        .bind(params)
+        .execute(&ctx.state.db)
+        .await?;
+    tokio::spawn(run_job(ctx.state.clone(), id, owner, priority, limits, compute));
+    Ok(id)
+}
+
+/// Runs a job once it is scheduled, recording its status and outcome.
+async fn run_job<F, R>(
+    state: AppState,
+    id: Uuid,
+    owner: String,
+    priority: JobPriority,
+    limits: JobLimits,
+    compute: F,
+) where
+    F: FnOnce(ProgressReporter, &JobBudget) -> Result<R, JobError> + Send + 'static,
+    R: Into<JobOutput> + Send + 'static,
+{
+    let _permit = state.job_scheduler.acquire(&owner, priority).await;
+    let started = sqlx::query("UPDATE jobs SET status = $2, started_at = NOW() WHERE id = $1")
+        .bind(id)
+        .bind(JobStatus::Running.as_str())
+        .execute(&state.db)
+        .await;
+    if let Err(e) = started {
+        tracing::error!(job_id = %id, error = %e, "Failed to start job");
+        return;
+    }
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let recorder = tokio::spawn(record_progress(state.clone(), id, receiver));
+    let reporter = ProgressReporter { sender };
+    let mut computation = tokio::task::spawn_blocking(move || {
+        let budget = JobBudget::start(limits);
+        compute(reporter, &budget).map(Into::into)
+    });
+    match tokio::time::timeout(limits.time + TIME_LIMIT_GRACE, &mut computation).await {
+        Ok(joined) => {
+            // The computation has dropped its reporters, so the recorder
+            // finishes once it has recorded all the progress reported.
+            if let Err(e) = recorder.await {
+                tracing::error!(job_id = %id, error = %e, "Failed to record job progress");
+            }
+            let outcome = joined.unwrap_or_else(|e| Err(JobError::Panicked(e.to_string())));
+            record_outcome(&state, id, outcome).await;
+        }
+        Err(_) => {
+            // Progress reported after the outcome is not recorded.
+            recorder.abort();
+            record_outcome(&state, id, Err(JobError::TimedOut(limits.time))).await;
+            // The slot is held until the abandoned computation returns, so
+            // that runaway jobs cannot pile up.
+            let _ = computation.await;
+        }
+    }
+}
+
+/// Records the outcome of a job, storing its artifacts if it succeeded.
+async fn record_outcome(state: &AppState, id: Uuid, outcome: Result<JobOutput, JobError>) {
+    let outcome = match outcome {
+        Ok(output) => match store_artifacts(state, id, output.artifacts).await {
+            Ok(()) => Ok(output.result),
+            Err(e) => Err(JobError::App(e)),
+        },
+        Err(e) => Err(e),
+    };
+    let (status, result, error, failure) = match outcome {
+        Ok(result) => (JobStatus::Succeeded, Some(result), None, None),
+        Err(e) => {
+            tracing::error!(job_id = %id, error = %e, "Job failed");
+            (JobStatus::Failed, None, Some(e.to_string()), Some(e.failure().as_str()))
+        }
+    };
+    let finished = sqlx::query(
+        "
+        UPDATE jobs SET status = $2, result = $3, error = $4, failure = $5, finished_at = NOW()
+        WHERE id = $1
+        ",
+    )
+    .bind(id)
+    .bind(status.as_str())
+    .bind(result)
+    .bind(error)
+    .bind(failure)
+    .execute(&state.db)
+    .await;
+    if let Err(e) = finished {
+        tracing::error!(job_id = %id, error = %e, "Failed to record job outcome");
+    }
+}
+
+/// Key of the object storing an artifact of a job.
+pub fn artifact_key(job_id: Uuid, artifact_id: Uuid) -> String {
+    format!("jobs/{job_id}/{artifact_id}")
+}
+
+/// Uploads the artifacts of a job to object storage and records them.
+async fn store_artifacts(
+    state: &AppState,
+    job_id: Uuid,
+    artifacts: Vec<NewJobArtifact>,
+) -> Result<(), AppError> {
+    for artifact in artifacts {
+        let id = Uuid::now_v7();
+        let size = artifact.data.len() as i64;
+        let key = artifact_key(job_id, id);
+        attachments::put_object(state, &key, &artifact.content_type, artifact.data).await?;
+        sqlx::query(
+            "
+            INSERT INTO job_artifacts (id, job_id, filename, content_type, size)
+            VALUES ($1, $2, $3, $4, $5)
+            ",
+        )
+        .bind(id)
+        .bind(job_id)
+        .bind(&artifact.filename)
+        .bind(&artifact.content_type)
+        .bind(size)
+        .execute(&state.db)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Lists the artifacts of a job, oldest first, with presigned download URLs.
+async fn list_job_artifacts(state: &AppState, job_id: Uuid) -> Result<Vec<JobArtifact>, AppError> {
+    let rows = sqlx::query(
+        "
+        SELECT id, filename, content_type, size
+        FROM job_artifacts WHERE job_id = $1 ORDER BY created_at, id
+        ",
+    )
+    .bind(job_id)
+    .fetch_all(&state.db)
+    .await?;
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+    let s3 = attachments::s3_config(state)?;
+    rows.into_iter()
+        .map(|row| {
+            let id = row.get("id");
+            Ok(JobArtifact {
+                id,
+                filename: row.get("filename"),
+                content_type: row.get("content_type"),
+                size: row.get("size"),
+                download_url: attachments::presign_download(s3, &artifact_key(job_id, id))?,
+            })
+        })
+        .collect()
+}
+
+/// Records the progress reported by a job until all its reporters are dropped.
+async fn record_progress(
+    state: AppState,
+    id: Uuid,
+    mut receiver: mpsc::UnboundedReceiver<ProgressReport>,
+) {
+    let mut seq: i32 = 0;
+    while let Some(report) = receiver.recv().await {
+        let recorded = sqlx::query(
+            "
+            INSERT INTO job_progress (job_id, seq, percent, message, partial_result)
+            VALUES ($1, $2, $3, $4, $5)
+            ",
+        )
+        .bind(id)
+        .bind(seq)
+        .bind(report.percent)
+        .bind(report.message)
+        .bind(report.partial_result)
+        .execute(&state.db)
+        .await;
+        if let Err(e) = recorded {
+            tracing::error!(job_id = %id, error = %e, "Failed to record job progress");
+        }
+        seq += 1;
+    }
+}
+
+/// Gets a job started by the current user.
+pub async fn get_job(ctx: &AppCtx, job_id: Uuid) -> Result<Job, AppError> {
+    let owner = ctx.user.as_ref().ok_or(AppError::Unauthorized)?.user_id.as_str();
+    let row = sqlx::query(
+        "
+        SELECT id, kind, status, result, error, failure, created_at, finished_at
+        FROM jobs WHERE id = $1 AND owner = $2
+        ",
+    )
+    .bind(job_id)
+    .bind(owner)
+    .fetch_optional(&ctx.state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("job {job_id}")))?;
+    Ok(Job {
+        id: row.get("id"),
+        kind: row.get("kind"),
+        status: JobStatus::parse(row.get("status"))?,
+        result: row.get("result"),
+        error: row.get("error"),
+        failure: row.get::<Option<&str>, _>("failure").map(JobFailure::parse).transpose()?,
+        artifacts: list_job_artifacts(&ctx.state, job_id).await?,
+        created_at: row.get("created_at"),
+        finished_at: row.get("finished_at"),
+    })
+}
+
+/// Longest time to wait for progress in a single long poll.
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval between checks for progress during a long poll.
+const LONG_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Waits for progress of a job started by the current user.
+///
+/// Returns the progress records after the given sequence number, or all of
+/// them if none is given, as soon as there are any or the job has finished. If
+/// neither happens within 30 seconds, returns with no records, and the client
+/// should simply ask again.
+pub async fn wait_for_job_progress(
+    ctx: &AppCtx,
+    job_id: Uuid,
+    after: Option<i32>,
+) -> Result<JobUpdate, AppError> {
+    let deadline = Instant::now() + LONG_POLL_TIMEOUT;
+    loop {
+        let job = get_job(ctx, job_id).await?;
+        let progress = list_job_progress(ctx, job_id, after).await?;
+        if !progress.is_empty() || job.status.is_finished() || Instant::now() >= deadline {
+            return Ok(JobUpdate { job, progress });
+        }
+        tokio::time::sleep(LONG_POLL_INTERVAL).await;
+    }
+}
+
+/// Lists the progress records of a job after the given sequence number.
+async fn list_job_progress(
+    ctx: &AppCtx,
+    job_id: Uuid,
+    after: Option<i32>,
+) -> Result<Vec<JobProgress>, AppError> {
+    let rows = sqlx::query(
+        "
+        SELECT seq, percent, message, partial_result, created_at
+        FROM job_progress WHERE job_id = $1 AND seq > $2
+        ORDER BY seq
+        ",
+    )
+    .bind(job_id)
+    .bind(after.unwrap_or(-1))
+    .fetch_all(&ctx.state.db)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| JobProgress {
+            seq: row.get("seq"),
+            percent: row.get("percent"),
+            message: row.get("message"),
+            partial_result: row.get("partial_result"),
+            created_at: row.get("created_at"),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enforce_budget() {
+        let budget = JobBudget::start(JobLimits::default().memory(100));
+        assert!(budget.check().is_ok());
+        assert!(budget.reserve(60).is_ok());
+        let err = budget.reserve(60).unwrap_err();
+        assert_eq!(err.failure(), JobFailure::OutOfMemory);
+        budget.release(60);
+        assert!(budget.reserve(40).is_ok());
+
+        let budget = JobBudget::start(JobLimits::default().time(Duration::ZERO));
+        assert_eq!(budget.check().map_err(|e| e.failure()), Err(JobFailure::TimedOut));
+        assert_eq!(budget.search_budget().max_millis, Some(0));
+    }
+
+    #[test]
+    fn job_output() {
+        let output = JobOutput::from(Value::Null);
+        assert!(output.artifacts.is_empty());
+
+        let output = JobOutput::new(Value::Bool(true))
+            .artifact("plot.svg", "image/svg+xml", b"<svg/>".to_vec())
+            .artifact("data.csv", "text/csv", b"t,x\n0,1\n".to_vec());
+        assert_eq!(output.result, Value::Bool(true));
+        let filenames: Vec<_> = output.artifacts.iter().map(|a| a.filename.as_str()).collect();
+        assert_eq!(filenames, ["plot.svg", "data.csv"]);
+    }
+}
@@ -1,8 +1,17 @@
 //! The CatColab backend library.
 
+/// Network access policies for self-hosted instances.
+pub mod access_policy;
+
+/// Cache of analysis results keyed by document content.
+pub mod analysis_cache;
+
 /// Top-level application state and error types.
 pub mod app;
 
+/// Attachments stored in S3-compatible object storage.
+pub mod attachments;
+
 /// Authentication and authorization for document refs.
 pub mod auth;
 
@@ -12,15 +21,84 @@ pub mod ref_actor;
 /// Autosurgeon utilities for datetime serialization.
 pub mod autosurgeon_datetime;
 
+/// Provisioning of copies of documents for students in a class.
+pub mod classroom;
+
+/// Reproducible run bundles of documents and the documents they link to.
+pub mod bundle;
+
+/// Registry of tabular datasets parsed from CSV.
+pub mod datasets;
+
+/// Cross-origin access policies and embedding of documents.
+pub mod embed;
+
+/// Documents encrypted on the client.
+pub mod encryption;
+
+/// Feature flags for experimental features.
+pub mod feature_flags;
+
+/// Analytics of the edit history of documents.
+pub mod history;
+
+/// Localization of user-facing messages.
+pub mod i18n;
+
+/// Scheduling of background jobs with priorities and fair sharing among users.
+pub mod job_scheduler;
+
+/// Background jobs for long-running computations.
+pub mod jobs;
+
+/// Lints of models, run when their snapshots are saved.
+pub mod lints;
+
+/// Read-only maintenance mode.
+pub mod maintenance;
+
+/// Clustering of the students' copies of a classroom template.
+pub mod model_clustering;
+
+/// Reporting and moderation of public documents.
+pub mod moderation;
+
+/// Presence of editing sessions and limits on concurrent writers.
+pub mod presence;
+
 /// Procedures to create and manipulate documents.
 pub mod document;
 
+/// Publication of tagged versions of documents and their archival.
+pub mod publications;
+
+/// Settings of document refs shared by all their users.
+pub mod ref_settings;
+
+/// Versioned REST API for clients other than the web app.
+pub mod rest;
+
 /// RPC service for the backend.
 pub mod rpc;
 
+/// Ephemeral scratch documents not yet saved as refs.
+pub mod scratch;
+
+/// Search for documents similar to a given one.
+pub mod similarity;
+
+/// Export of schema documents as SQL.
+pub mod sql_export;
+
+/// Export of model and diagram documents as SVG images.
+pub mod svg_export;
+
 /// Storage backend for Automerge documents.
 pub mod storage;
 
+/// Enforcement of read-only edit sessions on document sync.
+pub mod sync_guard;
+
 /// User accounts and profiles.
 pub mod user;
 
@@ -29,3 +107,6 @@ pub mod user_state;
 
 /// User-state update helpers called from RPC handlers.
 pub mod user_state_updates;
+
+/// Verification of user identities.
+pub mod verification;
@@ -0,0 +1,213 @@
+//! Lints of models, run when their snapshots are saved.
+//!
+//! When a snapshot of a model is created, the model is elaborated by `catlog`
+//! and checked against the [lint set](catlog::lint::LintSet) of its theory. The
+//! diagnostics are stored in the `snapshot_lints` table, so that they can be
+//! shown without elaborating the model again. Models of theories with no lint
+//! set are not linted.
+
+use catcolab_document_types::VersionedDocument;
+use catcolab_document_types::current::{Document, ModelDocumentContent};
+use catlog::lint::{LintDiagnostic, Severity};
+use catlog::stdlib::{lints, theories, versions::TheoryVersion};
+use catlog::tt::{
+    modelgen::Model,
+    notebook_elab::Elaborator,
+    prelude::ustr,
+    theory::{Theory, TheoryDef},
+    toplevel::Toplevel,
+};
+use catlog::zero::name;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState};
+use crate::auth::{self, PermissionLevel};
+
+/// Severity of a lint violation.
+#[qubit::ts]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LintSeverity {
+    /// Worth pointing out, but often intended.
+    Info,
+
+    /// Probably a mistake.
+    Warning,
+}
+
+/// A violation of a lint by a model.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelLint {
+    /// Name of the violated lint, such as `isolated_object`.
+    pub lint: String,
+
+    /// Severity of the violation.
+    pub severity: LintSeverity,
+
+    /// ID of the object or morphism violating the lint.
+    pub subject: String,
+
+    /// Description of the violation.
+    pub message: String,
+}
+
+impl From<LintDiagnostic> for ModelLint {
+    fn from(diagnostic: LintDiagnostic) -> Self {
+        Self {
+            lint: diagnostic.lint,
+            severity: match diagnostic.severity {
+                Severity::Info => LintSeverity::Info,
+                Severity::Warning => LintSeverity::Warning,
+            },
+            subject: diagnostic.subject.serialize_string(),
+            message: diagnostic.message,
+        }
+    }
+}
+
+/// Checks a document against the lints of its theory, if it is a model.
+fn document_lints(content: &Value) -> Option<Vec<ModelLint>> {
+    let document: VersionedDocument = serde_json::from_value(content.clone()).ok()?;
+    let Document::Model(model) = document.to_current() else {
+        return None;
+    };
+    let diagnostics = model_lints(&model)?;
+    Some(diagnostics.into_iter().map(ModelLint::from).collect())
+}
+
+/// Elaborates a model and checks it against the lints of its theory.
+///
+/// Instantiated models are not resolved, so only the model's own generators
+/// are linted.
+fn model_lints(model: &ModelDocumentContent) -> Option<Vec<LintDiagnostic>> {
+    let theory: TheoryVersion = model.theory.parse().ok()?;
+    let toplevel = Toplevel::new(Default::default());
+    let elaborate = |definition: TheoryDef| {
+        let theory = Theory::new(name(theory.id.as_str()), definition);
+        let mut elab = Elaborator::new(theory.clone(), &toplevel, ustr(""));
+        let (_, ty_v) = elab.notebook(model.notebook.formal_content());
+        Model::from_ty(&toplevel, &theory.definition, &ty_v).0
+    };
+    match theory.id.as_str() {
+        "causal-loop" | "reg-net" => {
+            let model = elaborate(TheoryDef::discrete(theories::th_signed_category()));
+            Some(lints::discrete_lints().run(&model.as_discrete()?))
+        }
+        "primitive-stock-flow" => {
+            let model = elaborate(TheoryDef::discrete_tab(theories::th_category_links()));
+            Some(lints::stock_flow_lints().run(&model.as_discrete_tab()?))
+        }
+        "petri-net" => {
+            let model = elaborate(TheoryDef::modal_unital(theories::th_sym_monoidal_category()));
+            Some(lints::petri_net_lints().run(&model.as_modal()?))
+        }
+        _ => None,
+    }
+}
+
+/// Lints a snapshot and stores the diagnostics, if it is of a linted model.
+pub async fn store_snapshot_lints(
+    state: &AppState,
+    snapshot_id: i32,
+    content: &Value,
+) -> Result<(), AppError> {
+    let Some(lints) = document_lints(content) else {
+        return Ok(());
+    };
+    sqlx::query(
+        "
+        INSERT INTO snapshot_lints (snapshot_id, lints) VALUES ($1, $2)
+        ON CONFLICT (snapshot_id) DO NOTHING
+        ",
+    )
+    .bind(snapshot_id)
+    .bind(serde_json::to_value(lints)?)
+    .execute(&state.db)
+    .await?;
+    Ok(())
+}
+
+/// Gets the lint violations of the current snapshot of a document.
+///
+/// Documents that are not linted, or not saved since lints were introduced,
+/// have no violations.
+pub async fn get_lints(ctx: &AppCtx, ref_id: Uuid) -> Result<Vec<ModelLint>, AppError> {
+    auth::authorize(ctx, ref_id, PermissionLevel::Read).await?;
+    let lints: Option<Value> = sqlx::query_scalar(
+        "
+        SELECT l.lints FROM refs
+        JOIN snapshot_lints l ON l.snapshot_id = refs.current_snapshot
+        WHERE refs.id = $1
+        ",
+    )
+    .bind(ref_id)
+    .fetch_optional(&ctx.state.db)
+    .await?;
+    match lints {
+        Some(lints) => Ok(serde_json::from_value(lints)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn causal_loop_lints() {
+        let (x, y, z, f) = (Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7());
+        let object = |id: Uuid, name: &str| {
+            json!({
+                "tag": "formal",
+                "id": id,
+                "content": {
+                    "tag": "object",
+                    "name": name,
+                    "id": id,
+                    "obType": { "tag": "Basic", "content": "Object" },
+                },
+            })
+        };
+        let morphism = json!({
+            "tag": "formal",
+            "id": f,
+            "content": {
+                "tag": "morphism",
+                "name": "",
+                "id": f,
+                "morType": { "tag": "Basic", "content": "Negative" },
+                "dom": { "tag": "Basic", "content": x.to_string() },
+                "cod": { "tag": "Basic", "content": y.to_string() },
+            },
+        });
+        let content = json!({
+            "type": "model",
+            "name": "Linted",
+            "theory": "causal-loop",
+            "notebook": {
+                "cellContents": {
+                    x.to_string(): object(x, "x"),
+                    y.to_string(): object(y, "y"),
+                    z.to_string(): object(z, "z"),
+                    f.to_string(): morphism,
+                },
+                "cellOrder": [x, y, z, f],
+            },
+            "version": "2",
+        });
+
+        let lints = document_lints(&content).unwrap();
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].lint, "isolated_object");
+        assert_eq!(lints[0].severity, LintSeverity::Warning);
+        assert_eq!(lints[0].subject, z.to_string());
+
+        let content = json!({ "type": "model", "theory": "simple-olog" });
+        assert_eq!(document_lints(&content), None);
+    }
+}
@@ -1,26 +1,31 @@
 //! Main entry point for the CatColab backend.
 
 use axum::extract::Request;
-use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::ws::{Message, WebSocketUpgrade};
 use axum::middleware::from_fn_with_state;
 use axum::{Router, routing::get};
 use axum::{extract::State, response::IntoResponse};
 use clap::{Parser, Subcommand};
-use firebase_auth::{FirebaseAuth, FirebaseUser}; // FirebaseUser used by julia_proxy_handler
+use firebase_auth::{FirebaseAuth, FirebaseUser};
+use futures_util::future;
+use futures_util::{SinkExt, StreamExt};
 use sqlx::postgres::PgPoolOptions;
 use sqlx_migrator::cli::MigrationCommand;
 use sqlx_migrator::migrator::{Migrate, Migrator};
 use sqlx_migrator::{Info, Plan};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower::ServiceBuilder;
-use tower_http::cors::CorsLayer;
 use tracing::{error, info};
 use tracing_subscriber::filter::{EnvFilter, LevelFilter};
 
-use backend::{app, auth, rpc, storage, user_state};
+use backend::{
+    access_policy, app, attachments, auth, embed, job_scheduler, presence, publications, rest, rpc,
+    scratch, storage, sync_guard, user_state, verification,
+};
 
 /// Port for the web server providing the RPC API.
 fn web_port() -> String {
@@ -132,14 +137,36 @@ async fn main() {
             } else {
                 info!("Julia compute server not configured (JULIA_URL not set)");
             }
+            let s3 = attachments::S3Config::from_env();
+            if let Some(ref s3) = s3 {
+                info!("Attachments stored in bucket {} at: {}", s3.bucket, s3.endpoint);
+            } else {
+                info!("Attachments not configured (S3_* variables not set)");
+            }
+            let orcid = verification::OrcidConfig::from_env();
+            if orcid.is_none() {
+                info!("ORCID linking not configured (ORCID_* variables not set)");
+            }
+            let archive = publications::ArchiveConfig::from_env();
+            if archive.is_none() {
+                info!("Archiving of publications not configured (ARCHIVE_TARGET not set)");
+            }
 
             let state = app::AppState {
                 db: db.clone(),
                 repo,
                 ref_actors: Arc::new(RwLock::new(HashMap::new())),
+                scratch_docs: Arc::new(RwLock::new(HashMap::new())),
                 initialized_user_states: Arc::new(RwLock::new(HashMap::new())),
                 http_client,
                 julia_url,
+                s3,
+                orcid,
+                archive,
+                maintenance: Arc::new(RwLock::new(None)),
+                permission_cache: Default::default(),
+                presence: presence::PresenceTracker::from_env(),
+                job_scheduler: job_scheduler::JobScheduler::from_env(),
             };
 
             // We need to wrap FirebaseAuth in an Arc because if it's ever dropped the process which updates it's
@@ -157,7 +184,15 @@ async fn main() {
             // Notify systemd we're ready
             sd_notify::notify(false, &[sd_notify::NotifyState::Ready]).ok();
 
-            run_web_server(state.clone(), repo_acceptor, firebase_auth.clone())
+            let policy = embed::HttpPolicy::from_env();
+            let access = Arc::new(access_policy::AccessPolicy::from_env());
+            if access.is_restricted() {
+                info!("Restricting access with policy: {access:?}");
+            }
+
+            tokio::spawn(scratch::run_scratch_sweeper(state.clone()));
+
+            run_web_server(state.clone(), repo_acceptor, firebase_auth.clone(), policy, access)
                 .await
                 .unwrap();
         }
@@ -188,10 +223,24 @@ async fn status_handler() -> &'static str {
 
 async fn websocket_handler(
     ws: WebSocketUpgrade,
-    State(acceptor): State<samod::AcceptorHandle>,
+    State((acceptor, state)): State<(samod::AcceptorHandle, app::AppState)>,
+    user: Option<axum::Extension<FirebaseUser>>,
 ) -> axum::response::Response {
+    let guard = sync_guard::SyncGuard::new(state, user.map(|user| user.0.user_id));
     ws.on_upgrade(|socket| async move {
-        acceptor.accept_axum(socket).expect("Failed to accept WebSocket connection");
+        let (sink, stream) = socket.split();
+        let stream = stream.filter_map(|msg| {
+            future::ready(match msg {
+                Ok(Message::Binary(bytes)) => Some(Ok(bytes.to_vec())),
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            })
+        });
+        let sink = sink.with(|bytes: Vec<u8>| {
+            future::ready(Ok::<_, axum::Error>(Message::Binary(bytes.into())))
+        });
+        let transport = samod::Transport::new(guard.filter(stream), sink);
+        acceptor.accept(transport).expect("Failed to accept WebSocket connection");
     })
 }
 
@@ -272,6 +321,8 @@ async fn run_web_server(
     state: app::AppState,
     repo_acceptor: samod::AcceptorHandle,
     firebase_auth: Arc<FirebaseAuth>,
+    policy: embed::HttpPolicy,
+    access: Arc<access_policy::AccessPolicy>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let port = web_port();
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{port}")).await?;
@@ -281,25 +332,43 @@ async fn run_web_server(
 
     let rpc_with_mw = ServiceBuilder::new()
         .layer(from_fn_with_state(firebase_auth.clone(), auth_middleware))
+        .layer(from_fn_with_state(access.clone(), access_policy::enforce_sign_in))
         .service(qubit_service);
 
     let samod_router = Router::new()
-        .layer(from_fn_with_state(firebase_auth.clone(), auth_middleware))
         .route("/repo-ws", get(websocket_handler))
-        .with_state(repo_acceptor);
+        .layer(from_fn_with_state(access.clone(), access_policy::enforce_sign_in))
+        .layer(from_fn_with_state(firebase_auth.clone(), auth_middleware))
+        .with_state((repo_acceptor, state.clone()));
+
+    let rest_router = Router::new()
+        .nest("/api/v1", rest::router_v1(state.clone()))
+        .layer(from_fn_with_state(access.clone(), access_policy::enforce_sign_in))
+        .layer(from_fn_with_state(firebase_auth.clone(), auth_middleware));
 
     let julia_router = Router::new()
         .route("/julia/{*path}", axum::routing::post(julia_proxy_handler))
+        .layer(from_fn_with_state(access.clone(), access_policy::enforce_sign_in))
         .layer(from_fn_with_state(firebase_auth, auth_middleware))
         .with_state(state.clone());
 
     // used by tests to tell when the backend is ready
     let status_router = Router::new().route("/status", get(status_handler));
 
+    let description = Arc::new(rpc::describe());
+    let description_router = Router::new().route(
+        "/.well-known/catcolab-rpc.json",
+        get(move || {
+            let description = description.clone();
+            async move { axum::Json(description.as_ref().clone()) }
+        }),
+    );
+
     let mut app = Router::new()
-        .merge(status_router)
+        .merge(description_router)
         .nest_service("/rpc", rpc_with_mw)
         .merge(samod_router)
+        .merge(rest_router)
         .merge(julia_router);
 
     if let Some(spa_dir) = spa_directory() {
@@ -314,11 +383,26 @@ async fn run_web_server(
         app = app.route("/", get(|| async { "Hello! The CatColab server is running" }));
     }
 
-    app = app.layer(CorsLayer::very_permissive());
+    // The status check is exempt from the network allowlist, for health checks.
+    app = app.layer(from_fn_with_state(access, access_policy::enforce_network));
+    app = app.merge(status_router);
+
+    if let Some(csp) = policy.content_security_policy() {
+        info!("Restricting framing with policy: {}", csp.to_str().unwrap_or_default());
+        app =
+            app.layer(axum::middleware::map_response(move |mut res: axum::response::Response| {
+                let csp = csp.clone();
+                async move {
+                    res.headers_mut().insert(axum::http::header::CONTENT_SECURITY_POLICY, csp);
+                    res
+                }
+            }));
+    }
+    app = app.layer(policy.cors_layer());
 
     info!("Web server listening at port {port}");
 
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
     qubit_handle.stop().ok();
 
     Ok(())
@@ -0,0 +1,47 @@
+//! Read-only maintenance mode.
+//!
+//! Operators can put the API into maintenance mode while performing operations
+//! such as schema migrations. In maintenance mode, the API is read-only: RPC
+//! mutations and REST writes fail with [`AppError::Maintenance`], which carries
+//! the expected end of the maintenance, and autosaves of documents are deferred
+//! until maintenance ends. Users can still sign in, though recording them in
+//! the database is only attempted. Maintenance mode is held in memory and is toggled by
+//! administrators through the RPC API.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{AppError, AppState};
+
+/// Status of an ongoing maintenance.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MaintenanceMode {
+    /// Reason for the maintenance, to be shown to users.
+    pub reason: Option<String>,
+
+    /// When the maintenance is expected to end, if known.
+    pub eta: Option<DateTime<Utc>>,
+}
+
+/// Gets the current maintenance mode, if the API is in maintenance.
+pub async fn maintenance_mode(state: &AppState) -> Option<MaintenanceMode> {
+    state.maintenance.read().await.clone()
+}
+
+/// Puts the API into maintenance mode or, given `None`, takes it out.
+pub async fn set_maintenance_mode(state: &AppState, mode: Option<MaintenanceMode>) {
+    match &mode {
+        Some(mode) => tracing::warn!(eta = ?mode.eta, "Entering maintenance mode"),
+        None => tracing::info!("Leaving maintenance mode"),
+    }
+    *state.maintenance.write().await = mode;
+}
+
+/// Requires the API to be writable, failing if it is in maintenance mode.
+pub async fn require_writable(state: &AppState) -> Result<(), AppError> {
+    match state.maintenance.read().await.as_ref() {
+        Some(mode) => Err(AppError::Maintenance(mode.eta)),
+        None => Ok(()),
+    }
+}
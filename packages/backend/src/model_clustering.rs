@@ -0,0 +1,144 @@
+//! Clustering of the students' copies of a classroom template.
+//!
+//! To give an overview of a class on the instructor dashboard, the copies of a
+//! template are clustered by the edit distance between the generating graphs of
+//! the models, each cluster being represented by its medoid. Since computing the
+//! pairwise distances is quadratic in the number of students, the clustering is
+//! run as a [job](crate::jobs).
+
+use catcolab_document_types::VersionedDocument;
+use catlog::one::graph_clustering::{cluster_with_medoids, edit_distance_matrix};
+use catlog::one::graph_edit::EditCosts;
+use catlog::one::graph_io::LabeledGraph;
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError};
+use crate::auth::{self, PermissionLevel};
+use crate::document as doc;
+use crate::job_scheduler::JobPriority;
+use crate::jobs::{JobBudget, JobError, JobLimits, JobOutput, ProgressReporter};
+use crate::{classroom, jobs, svg_export};
+
+/// Kind of the clustering jobs.
+pub const JOB_KIND: &str = "model-clustering";
+
+/// Maximum number of search states when computing each edit distance.
+const MAX_SEARCH_STATES: usize = 10_000;
+
+/// A cluster of copies.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct ModelCluster {
+    /// The copy representing the cluster.
+    pub medoid: Uuid,
+
+    /// All copies in the cluster, including the medoid.
+    pub members: Vec<Uuid>,
+}
+
+/// Result of a clustering job.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct ModelClustering {
+    /// The clustered copies.
+    pub refs: Vec<Uuid>,
+
+    /// Edit distances between the copies, indexed as `refs`.
+    pub distances: Vec<Vec<f32>>,
+
+    /// Clusters of the copies, ordered by their earliest provisioned member.
+    pub clusters: Vec<ModelCluster>,
+}
+
+/// Starts clustering the copies of a template into at most the given number of
+/// clusters.
+///
+/// Returns the ID of the job, whose result is a [`ModelClustering`]. Copies that
+/// are not models or diagrams, or are encrypted, are left out. When object
+/// storage is configured, the distances are also attached to the job as a CSV
+/// artifact.
+pub async fn start_model_clustering(
+    ctx: &AppCtx,
+    template_ref: Uuid,
+    n_clusters: usize,
+) -> Result<Uuid, AppError> {
+    auth::authorize(ctx, template_ref, PermissionLevel::Maintain).await?;
+    if n_clusters == 0 {
+        return Err(AppError::Invalid("Number of clusters must be positive".into()));
+    }
+
+    let mut refs = Vec::new();
+    let mut graphs = Vec::new();
+    for copy in classroom::list_classroom_copies(&ctx.state, template_ref).await? {
+        let content = match doc::get_content(ctx.state.clone(), copy.ref_id).await {
+            Ok(content) => content,
+            // Encrypted copies cannot be read by the server, so they are left out.
+            Err(AppError::Encrypted(_)) => continue,
+            Err(err) => return Err(err),
+        };
+        let Ok(document) = serde_json::from_value::<VersionedDocument>(content) else {
+            continue;
+        };
+        if let Some(graph) = svg_export::document_graph(&document.to_current()) {
+            refs.push(copy.ref_id);
+            graphs.push(graph);
+        }
+    }
+
+    let params = json!({ "templateRef": template_ref, "nClusters": n_clusters });
+    let (priority, limits) = (JobPriority::Interactive, JobLimits::default());
+    let export_csv = ctx.state.s3.is_some();
+    jobs::spawn_job(ctx, JOB_KIND, priority, limits, params, move |progress, budget| {
+        let clustering = cluster_graphs(refs, &graphs, n_clusters, &progress, budget)?;
+        let mut output = JobOutput::new(serde_json::to_value(&clustering)?);
+        if export_csv {
+            let csv = distances_csv(&clustering.refs, &clustering.distances);
+            output = output.artifact("distances.csv", "text/csv", csv.into_bytes());
+        }
+        Ok(output)
+    })
+    .await
+}
+
+/// Clusters the graphs of copies, running as a job.
+fn cluster_graphs(
+    refs: Vec<Uuid>,
+    graphs: &[LabeledGraph],
+    n_clusters: usize,
+    progress: &ProgressReporter,
+    budget: &JobBudget,
+) -> Result<ModelClustering, JobError> {
+    progress.report(0.0, format!("Computing distances between {} copies", refs.len()));
+    budget.reserve(refs.len() * refs.len() * std::mem::size_of::<f32>())?;
+    let costs = EditCosts::default();
+    let distances = edit_distance_matrix(graphs, &costs, MAX_SEARCH_STATES, budget.search_budget())
+        .map_err(|err| budget.exhausted(err))?;
+    progress.report(90.0, "Clustering copies");
+    let clusters = cluster_with_medoids(&distances, n_clusters)
+        .into_iter()
+        .map(|cluster| ModelCluster {
+            medoid: refs[cluster.medoid],
+            members: cluster.members.iter().map(|&i| refs[i]).collect(),
+        })
+        .collect();
+    Ok(ModelClustering { refs, distances, clusters })
+}
+
+/// Writes the distances between copies as CSV, with a row and a column for each.
+fn distances_csv(refs: &[Uuid], distances: &[Vec<f32>]) -> String {
+    let mut csv = String::from("ref");
+    for ref_id in refs {
+        csv.push_str(&format!(",{ref_id}"));
+    }
+    csv.push('\n');
+    for (ref_id, row) in refs.iter().zip(distances) {
+        csv.push_str(&ref_id.to_string());
+        for distance in row {
+            csv.push_str(&format!(",{distance}"));
+        }
+        csv.push('\n');
+    }
+    csv
+}
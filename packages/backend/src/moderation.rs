@@ -0,0 +1,249 @@
+//! Reporting and moderation of public documents.
+//!
+//! Any signed-in user who can read a document may report it, placing a report in
+//! the moderation queue. Administrators review open reports and either dismiss
+//! them or hide the reported document. Hiding a document revokes its public
+//! permission and prevents its owners from making it public again until an
+//! administrator unhides it; access granted to individual users is unaffected.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState};
+use crate::auth::{self, PermissionLevel};
+
+/// Maximum length of the reason given in a report, in characters.
+const MAX_REASON_LENGTH: usize = 2000;
+
+/// Status of a content report.
+#[qubit::ts]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[sqlx(type_name = "report_status", rename_all = "lowercase")]
+pub enum ReportStatus {
+    /// The report is waiting to be reviewed.
+    Open,
+    /// The report was reviewed and no action was taken.
+    Dismissed,
+    /// The report was reviewed and the document was hidden.
+    Actioned,
+}
+
+/// Action taken by a moderator on a report.
+#[qubit::ts]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum ModerationAction {
+    /// Dismiss the report, taking no action on the document.
+    Dismiss,
+    /// Hide the reported document from the public.
+    Hide,
+}
+
+/// A report of a document in the moderation queue.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct ContentReport {
+    /// ID of the report.
+    pub id: Uuid,
+
+    /// ID of the reported ref.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// ID of the user who made the report, if the account still exists.
+    pub reporter: Option<String>,
+
+    /// Reason given for the report.
+    pub reason: String,
+
+    /// Status of the report.
+    pub status: ReportStatus,
+
+    /// When the report was made.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+
+    /// When the report was resolved, if it has been.
+    #[serde(rename = "resolvedAt")]
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Reports a document for review by moderators.
+///
+/// A user can have at most one open report on any given document.
+pub async fn report_content(ctx: &AppCtx, ref_id: Uuid, reason: &str) -> Result<Uuid, AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::Invalid("Report must give a reason".to_string()));
+    }
+    if reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(AppError::Invalid(format!(
+            "Report reason exceeds maximum length ({MAX_REASON_LENGTH} characters)"
+        )));
+    }
+    auth::authorize(ctx, ref_id, PermissionLevel::Read).await?;
+
+    let id = Uuid::now_v7();
+    let result = sqlx::query(
+        "
+        INSERT INTO content_reports(id, ref_id, reporter, reason)
+        VALUES ($1, $2, $3, $4)
+        ",
+    )
+    .bind(id)
+    .bind(ref_id)
+    .bind(&user.user_id)
+    .bind(reason)
+    .execute(&ctx.state.db)
+    .await;
+    match result {
+        Ok(_) => Ok(id),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Err(AppError::Invalid("Document has already been reported".to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Requires the active user to be an administrator.
+pub async fn require_admin(ctx: &AppCtx) -> Result<(), AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    let is_admin: Option<bool> = sqlx::query_scalar("SELECT is_admin FROM users WHERE id = $1")
+        .bind(&user.user_id)
+        .fetch_optional(&ctx.state.db)
+        .await?;
+    if is_admin == Some(true) {
+        Ok(())
+    } else {
+        Err(AppError::AdminOnly)
+    }
+}
+
+/// Lists reports with the given status, or all reports, oldest first.
+pub async fn list_reports(
+    state: &AppState,
+    status: Option<ReportStatus>,
+) -> Result<Vec<ContentReport>, AppError> {
+    let rows = sqlx::query(
+        "
+        SELECT id, ref_id, reporter, reason, status, created_at, resolved_at
+        FROM content_reports
+        WHERE $1::report_status IS NULL OR status = $1
+        ORDER BY created_at
+        ",
+    )
+    .bind(status)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| ContentReport {
+            id: row.get("id"),
+            ref_id: row.get("ref_id"),
+            reporter: row.get("reporter"),
+            reason: row.get("reason"),
+            status: row.get("status"),
+            created_at: row.get("created_at"),
+            resolved_at: row.get("resolved_at"),
+        })
+        .collect())
+}
+
+/// Resolves an open report by dismissing it or hiding the reported document.
+///
+/// Hiding a document also resolves all other open reports on it.
+pub async fn resolve_report(
+    ctx: &AppCtx,
+    report_id: Uuid,
+    action: ModerationAction,
+) -> Result<(), AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    let mut txn = ctx.state.db.begin().await?;
+
+    let row = sqlx::query("SELECT ref_id, status FROM content_reports WHERE id = $1 FOR UPDATE")
+        .bind(report_id)
+        .fetch_optional(&mut *txn)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("report {report_id}")))?;
+    if row.get::<ReportStatus, _>("status") != ReportStatus::Open {
+        return Err(AppError::Invalid("Report has already been resolved".to_string()));
+    }
+    let ref_id: Uuid = row.get("ref_id");
+
+    match action {
+        ModerationAction::Dismiss => {
+            sqlx::query(
+                "
+                UPDATE content_reports
+                SET status = 'dismissed', resolved_by = $2, resolved_at = NOW()
+                WHERE id = $1
+                ",
+            )
+            .bind(report_id)
+            .bind(&user.user_id)
+            .execute(&mut *txn)
+            .await?;
+        }
+        ModerationAction::Hide => {
+            sqlx::query(
+                "
+                UPDATE content_reports
+                SET status = 'actioned', resolved_by = $2, resolved_at = NOW()
+                WHERE ref_id = $1 AND status = 'open'
+                ",
+            )
+            .bind(ref_id)
+            .bind(&user.user_id)
+            .execute(&mut *txn)
+            .await?;
+
+            sqlx::query("UPDATE refs SET hidden_at = NOW() WHERE id = $1")
+                .bind(ref_id)
+                .execute(&mut *txn)
+                .await?;
+
+            sqlx::query(
+                "DELETE FROM permissions WHERE object = $1 AND subject IS NULL AND level < 'own'",
+            )
+            .bind(ref_id)
+            .execute(&mut *txn)
+            .await?;
+        }
+    }
+
+    txn.commit().await?;
+    if action == ModerationAction::Hide {
+        ctx.state.permission_cache.invalidate_ref(ref_id);
+    }
+    Ok(())
+}
+
+/// Unhides a document, allowing its owners to make it public again.
+pub async fn unhide_ref(state: &AppState, ref_id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("UPDATE refs SET hidden_at = NULL WHERE id = $1")
+        .bind(ref_id)
+        .execute(&state.db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("ref {ref_id}")));
+    }
+    Ok(())
+}
+
+/// Is the document hidden by moderators?
+pub async fn is_hidden(state: &AppState, ref_id: Uuid) -> Result<bool, AppError> {
+    let hidden: Option<bool> =
+        sqlx::query_scalar("SELECT hidden_at IS NOT NULL FROM refs WHERE id = $1")
+            .bind(ref_id)
+            .fetch_optional(&state.db)
+            .await?;
+    hidden.ok_or_else(|| AppError::NotFound(format!("ref {ref_id}")))
+}
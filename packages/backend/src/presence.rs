@@ -0,0 +1,321 @@
+//! Presence of editing sessions on documents.
+//!
+//! Clients open a session on a document ref when they start editing it and keep
+//! the session alive by renewing it periodically; sessions that are not renewed
+//! expire. The presence tracker caps the number of simultaneous write sessions
+//! on each ref, since many concurrent writers on one document, as when a whole
+//! classroom opens the same document, produce storms of Automerge changes to
+//! merge. Sessions beyond the cap are downgraded to read-only with a notice
+//! saying why, and are promoted to writers on renewal once a write session
+//! closes or expires.
+//!
+//! Clients open the editor read-only when their session is, and the server
+//! enforces the cap on document sync as well, by dropping changes from users who
+//! [may not write](PresenceTracker::may_write) to a ref (see
+//! [`sync_guard`](crate::sync_guard)). The tracker is held in memory, so sessions
+//! are forgotten when the server restarts and clients must open them again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Default maximum number of write sessions on a single ref.
+const DEFAULT_MAX_WRITERS: usize = 25;
+
+/// Time after which sessions that are not renewed expire.
+const SESSION_TTL: Duration = Duration::from_secs(60);
+
+/// Mode of an editing session.
+#[qubit::ts]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum SessionMode {
+    /// The session can edit the document.
+    Write,
+    /// The session can only view the document.
+    Read,
+}
+
+/// Notice to the client about the mode of its session.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "tag", content = "content")]
+pub enum SessionNotice {
+    /// The ref already has the maximum number of write sessions, so the session
+    /// is read-only until a writer leaves.
+    WriterLimitReached {
+        /// Maximum number of write sessions on a ref.
+        limit: usize,
+    },
+}
+
+/// An editing session on a document ref, as seen by its client.
+#[qubit::ts]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct EditSession {
+    /// ID of the session.
+    pub id: Uuid,
+
+    /// ID of the ref being edited.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// Current mode of the session.
+    pub mode: SessionMode,
+
+    /// Why the session is read-only, if it was downgraded.
+    pub notice: Option<SessionNotice>,
+
+    /// Number of sessions on the ref, including this one.
+    pub sessions: usize,
+
+    /// Number of write sessions on the ref.
+    pub writers: usize,
+}
+
+/// Tracker of the editing sessions on document refs.
+///
+/// Cheaply cloneable, with clones sharing the same sessions.
+#[derive(Clone)]
+pub struct PresenceTracker {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    max_writers: usize,
+    ttl: Duration,
+    sessions: HashMap<Uuid, Vec<Session>>,
+}
+
+struct Session {
+    id: Uuid,
+    user_id: Option<String>,
+    wants_write: bool,
+    mode: SessionMode,
+    expires_at: Instant,
+}
+
+impl Default for PresenceTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_WRITERS, SESSION_TTL)
+    }
+}
+
+impl PresenceTracker {
+    /// Creates a tracker with the given cap on writers per ref and time-to-live.
+    pub fn new(max_writers: usize, ttl: Duration) -> Self {
+        let inner = Inner {
+            max_writers,
+            ttl,
+            sessions: HashMap::new(),
+        };
+        Self { inner: Arc::new(Mutex::new(inner)) }
+    }
+
+    /// Creates a tracker with the cap on writers per ref read from the
+    /// `MAX_WRITE_SESSIONS_PER_DOC` environment variable, if set.
+    pub fn from_env() -> Self {
+        let max_writers = dotenvy::var("MAX_WRITE_SESSIONS_PER_DOC")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MAX_WRITERS);
+        Self::new(max_writers, SESSION_TTL)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // The sessions are left consistent by every operation, so a poisoned
+        // lock can be safely reused.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Opens a session on a ref for a user.
+    ///
+    /// The session is a write session if the user is allowed to write and the
+    /// ref has fewer than the maximum number of writers.
+    pub fn open(&self, ref_id: Uuid, user_id: Option<&str>, can_write: bool) -> EditSession {
+        let mut inner = self.lock();
+        inner.prune(ref_id);
+        let id = Uuid::now_v7();
+        let mode = if can_write && inner.has_writer_slot(ref_id) {
+            SessionMode::Write
+        } else {
+            SessionMode::Read
+        };
+        let session = Session {
+            id,
+            user_id: user_id.map(str::to_string),
+            wants_write: can_write,
+            mode,
+            expires_at: Instant::now() + inner.ttl,
+        };
+        inner.sessions.entry(ref_id).or_default().push(session);
+        inner.describe(ref_id, id).expect("Session should have been opened")
+    }
+
+    /// Renews a session of a user, promoting it to a writer if possible.
+    ///
+    /// Returns `None` if the session does not exist or has expired.
+    pub fn renew(
+        &self,
+        ref_id: Uuid,
+        session_id: Uuid,
+        user_id: Option<&str>,
+    ) -> Option<EditSession> {
+        let mut inner = self.lock();
+        inner.prune(ref_id);
+        let has_slot = inner.has_writer_slot(ref_id);
+        let expires_at = Instant::now() + inner.ttl;
+        let session = inner.session_mut(ref_id, session_id, user_id)?;
+        session.expires_at = expires_at;
+        if session.wants_write && session.mode == SessionMode::Read && has_slot {
+            session.mode = SessionMode::Write;
+        }
+        inner.describe(ref_id, session_id)
+    }
+
+    /// Whether a user may write to a ref over document sync.
+    ///
+    /// Users with a write session on the ref may write, while users whose
+    /// sessions on it are all read-only may not. Users without a session may
+    /// write only while the ref has a free writer slot, so that the cap cannot be
+    /// evaded by not opening a session.
+    pub fn may_write(&self, ref_id: Uuid, user_id: Option<&str>) -> bool {
+        let mut inner = self.lock();
+        inner.prune(ref_id);
+        let mut modes = (inner.sessions.get(&ref_id).into_iter().flatten())
+            .filter(|s| s.user_id.as_deref() == user_id)
+            .map(|s| s.mode)
+            .peekable();
+        if modes.peek().is_none() {
+            return inner.has_writer_slot(ref_id);
+        }
+        modes.any(|mode| mode == SessionMode::Write)
+    }
+
+    /// Closes a session of a user, freeing its slot if it was a writer.
+    pub fn close(&self, ref_id: Uuid, session_id: Uuid, user_id: Option<&str>) -> bool {
+        let mut inner = self.lock();
+        let Some(sessions) = inner.sessions.get_mut(&ref_id) else {
+            return false;
+        };
+        let len = sessions.len();
+        sessions.retain(|s| !(s.id == session_id && s.user_id.as_deref() == user_id));
+        let closed = sessions.len() < len;
+        inner.prune(ref_id);
+        closed
+    }
+}
+
+impl Inner {
+    /// Removes the expired sessions on a ref.
+    fn prune(&mut self, ref_id: Uuid) {
+        let now = Instant::now();
+        if let Some(sessions) = self.sessions.get_mut(&ref_id) {
+            sessions.retain(|s| s.expires_at > now);
+            if sessions.is_empty() {
+                self.sessions.remove(&ref_id);
+            }
+        }
+    }
+
+    fn writers(&self, ref_id: Uuid) -> usize {
+        self.sessions
+            .get(&ref_id)
+            .map_or(0, |sessions| sessions.iter().filter(|s| s.mode == SessionMode::Write).count())
+    }
+
+    fn has_writer_slot(&self, ref_id: Uuid) -> bool {
+        self.writers(ref_id) < self.max_writers
+    }
+
+    fn session_mut(
+        &mut self,
+        ref_id: Uuid,
+        session_id: Uuid,
+        user_id: Option<&str>,
+    ) -> Option<&mut Session> {
+        self.sessions
+            .get_mut(&ref_id)?
+            .iter_mut()
+            .find(|s| s.id == session_id && s.user_id.as_deref() == user_id)
+    }
+
+    fn describe(&self, ref_id: Uuid, session_id: Uuid) -> Option<EditSession> {
+        let sessions = self.sessions.get(&ref_id)?;
+        let session = sessions.iter().find(|s| s.id == session_id)?;
+        let notice = (session.wants_write && session.mode == SessionMode::Read)
+            .then_some(SessionNotice::WriterLimitReached { limit: self.max_writers });
+        Some(EditSession {
+            id: session.id,
+            ref_id,
+            mode: session.mode,
+            notice,
+            sessions: sessions.len(),
+            writers: self.writers(ref_id),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writer_limit() {
+        let tracker = PresenceTracker::new(2, SESSION_TTL);
+        let ref_id = Uuid::now_v7();
+        let first = tracker.open(ref_id, Some("alice"), true);
+        let second = tracker.open(ref_id, Some("bob"), true);
+        let reader = tracker.open(ref_id, None, false);
+        let third = tracker.open(ref_id, Some("carol"), true);
+        assert_eq!(first.mode, SessionMode::Write);
+        assert_eq!(second.mode, SessionMode::Write);
+        assert_eq!((reader.mode, reader.notice), (SessionMode::Read, None));
+        assert_eq!(third.mode, SessionMode::Read);
+        assert_eq!(third.notice, Some(SessionNotice::WriterLimitReached { limit: 2 }));
+        assert_eq!((third.sessions, third.writers), (4, 2));
+
+        // Sessions can only be renewed or closed by their own users.
+        assert!(tracker.renew(ref_id, third.id, Some("bob")).is_none());
+        assert!(!tracker.close(ref_id, first.id, Some("bob")));
+
+        // The downgraded session is promoted once a writer leaves.
+        assert!(tracker.close(ref_id, first.id, Some("alice")));
+        let third = tracker.renew(ref_id, third.id, Some("carol")).unwrap();
+        assert_eq!((third.mode, third.notice), (SessionMode::Write, None));
+        let reader = tracker.renew(ref_id, reader.id, None).unwrap();
+        assert_eq!(reader.mode, SessionMode::Read);
+    }
+
+    #[test]
+    fn read_sessions_cannot_write() {
+        let tracker = PresenceTracker::new(1, SESSION_TTL);
+        let ref_id = Uuid::now_v7();
+        assert!(tracker.may_write(ref_id, Some("carol")));
+
+        let writer = tracker.open(ref_id, Some("alice"), true);
+        let reader = tracker.open(ref_id, Some("bob"), true);
+        assert_eq!(reader.mode, SessionMode::Read);
+        assert!(tracker.may_write(ref_id, Some("alice")));
+        assert!(!tracker.may_write(ref_id, Some("bob")));
+
+        // Users without a session cannot write past the cap either.
+        assert!(!tracker.may_write(ref_id, Some("carol")));
+        assert!(tracker.close(ref_id, writer.id, Some("alice")));
+        assert!(tracker.may_write(ref_id, Some("carol")));
+        assert!(!tracker.may_write(ref_id, Some("bob")));
+    }
+
+    #[test]
+    fn sessions_expire() {
+        let tracker = PresenceTracker::new(1, Duration::ZERO);
+        let ref_id = Uuid::now_v7();
+        let first = tracker.open(ref_id, Some("alice"), true);
+        assert_eq!(first.mode, SessionMode::Write);
+        assert!(tracker.renew(ref_id, first.id, Some("alice")).is_none());
+        let second = tracker.open(ref_id, Some("bob"), true);
+        assert_eq!(second.mode, SessionMode::Write);
+    }
+}
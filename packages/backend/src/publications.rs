@@ -0,0 +1,423 @@
+//! Publication of tagged versions of documents and their archival.
+//!
+//! Publishing a version of a document tags its current snapshot with a
+//! user-chosen name, such as `v1.0`, recorded in the `publications` table. If
+//! an archive is configured, the run bundle of the published version is then
+//! pushed to the archive in the background, and the resulting URL, along with a
+//! DOI when archiving to Zenodo, is recorded on the publication. Depositions on
+//! Zenodo are created as drafts with a reserved DOI, to be reviewed and
+//! published by the author on Zenodo itself.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState, RefMsg};
+use crate::auth::{self, PermissionLevel};
+use crate::bundle::{self, RunBundle};
+use crate::ref_actor::send_to_actor;
+use crate::{moderation, verification};
+
+/// Maximum length of a version tag, in characters.
+const MAX_TAG_LENGTH: usize = 100;
+
+/// Lifetime of the presigned URL used to upload an archive to S3, in seconds.
+const UPLOAD_URL_EXPIRES_SECONDS: u64 = 15 * 60;
+
+/// Configuration of the Zenodo API.
+#[derive(Clone, Debug)]
+pub struct ZenodoConfig {
+    /// Base URL of the Zenodo service, such as `https://zenodo.org`.
+    pub base_url: String,
+
+    /// Personal access token with the `deposit:write` scope.
+    pub access_token: String,
+}
+
+/// Archive to which published versions are pushed.
+#[derive(Clone, Debug)]
+pub enum ArchiveConfig {
+    /// Deposit versions on Zenodo.
+    Zenodo(ZenodoConfig),
+
+    /// Store versions in the S3 bucket used for attachments.
+    S3,
+}
+
+impl ArchiveConfig {
+    /// Reads the configuration from the environment, if it is fully specified.
+    ///
+    /// The archive is chosen by `ARCHIVE_TARGET`, which is either `zenodo` or
+    /// `s3`.
+    pub fn from_env() -> Option<Self> {
+        match dotenvy::var("ARCHIVE_TARGET").ok()?.as_str() {
+            "zenodo" => Some(ArchiveConfig::Zenodo(ZenodoConfig {
+                base_url: dotenvy::var("ZENODO_URL").unwrap_or("https://zenodo.org".to_string()),
+                access_token: dotenvy::var("ZENODO_ACCESS_TOKEN").ok()?,
+            })),
+            "s3" => Some(ArchiveConfig::S3),
+            _ => None,
+        }
+    }
+}
+
+/// Published version of a document.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct Publication {
+    /// ID of the publication.
+    pub id: Uuid,
+
+    /// ID of the published ref.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// ID of the published snapshot.
+    #[serde(rename = "snapshotId")]
+    pub snapshot_id: i32,
+
+    /// Tag naming the version.
+    pub tag: String,
+
+    /// When the version was published.
+    #[serde(rename = "publishedAt")]
+    pub published_at: DateTime<Utc>,
+
+    /// URL of the archived version, once archived.
+    #[serde(rename = "archiveUrl")]
+    pub archive_url: Option<String>,
+
+    /// DOI of the archived version, if the archive assigns one.
+    pub doi: Option<String>,
+
+    /// Error that occurred while archiving the version, if any.
+    #[serde(rename = "archiveError")]
+    pub archive_error: Option<String>,
+}
+
+fn validate_tag(tag: &str) -> Result<(), AppError> {
+    if tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(AppError::Invalid(format!(
+            "Version tag must have between 1 and {MAX_TAG_LENGTH} characters"
+        )));
+    }
+    if !tag.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')) {
+        return Err(AppError::Invalid(
+            "Version tag may only contain letters, digits, '.', '-', and '_'".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Publishes the current version of a document under a tag.
+///
+/// Like making a document public, publishing requires the user to be verified.
+/// If an archive is configured, the version is archived in the background.
+pub async fn publish_version(
+    ctx: &AppCtx,
+    ref_id: Uuid,
+    tag: &str,
+) -> Result<Publication, AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    validate_tag(tag)?;
+    auth::authorize(ctx, ref_id, PermissionLevel::Maintain).await?;
+    if moderation::is_hidden(&ctx.state, ref_id).await? {
+        return Err(AppError::Invalid(
+            "Document has been hidden by moderators and cannot be published".to_string(),
+        ));
+    }
+    verification::require_verified(ctx).await?;
+
+    // Save any pending changes, so that the latest content is published.
+    send_to_actor(&ctx.state, ref_id, RefMsg::CreateSnapshot).await?;
+
+    let id = Uuid::now_v7();
+    let result = sqlx::query(
+        "
+        INSERT INTO publications(id, ref_id, snapshot_id, tag, published_by)
+        SELECT $1, id, current_snapshot, $3, $4 FROM refs WHERE id = $2
+        RETURNING snapshot_id, published_at
+        ",
+    )
+    .bind(id)
+    .bind(ref_id)
+    .bind(tag)
+    .bind(&user.user_id)
+    .fetch_one(&ctx.state.db)
+    .await;
+    let row = match result {
+        Ok(row) => row,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Err(AppError::Invalid(format!("Version {tag} has already been published")));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let publication = Publication {
+        id,
+        ref_id,
+        snapshot_id: row.get("snapshot_id"),
+        tag: tag.to_string(),
+        published_at: row.get("published_at"),
+        archive_url: None,
+        doi: None,
+        archive_error: None,
+    };
+    if ctx.state.archive.is_some() {
+        tokio::spawn(archive_publication(ctx.clone(), publication.clone()));
+    }
+    Ok(publication)
+}
+
+/// Lists the published versions of a ref, oldest first.
+pub async fn list_publications(
+    state: &AppState,
+    ref_id: Uuid,
+) -> Result<Vec<Publication>, AppError> {
+    let rows = sqlx::query(
+        "
+        SELECT id, ref_id, snapshot_id, tag, published_at, archive_url, doi, archive_error
+        FROM publications WHERE ref_id = $1
+        ORDER BY published_at
+        ",
+    )
+    .bind(ref_id)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| Publication {
+            id: row.get("id"),
+            ref_id: row.get("ref_id"),
+            snapshot_id: row.get("snapshot_id"),
+            tag: row.get("tag"),
+            published_at: row.get("published_at"),
+            archive_url: row.get("archive_url"),
+            doi: row.get("doi"),
+            archive_error: row.get("archive_error"),
+        })
+        .collect())
+}
+
+/// Location of an archived version.
+struct ArchiveLocation {
+    url: String,
+    doi: Option<String>,
+}
+
+/// Archives a published version, recording the outcome on the publication.
+async fn archive_publication(ctx: AppCtx, publication: Publication) {
+    let result = async {
+        let config = ctx
+            .state
+            .archive
+            .as_ref()
+            .ok_or_else(|| AppError::Invalid("Archiving is not configured".to_string()))?;
+        let bundle =
+            bundle::export_run_bundle_at(&ctx, publication.ref_id, publication.snapshot_id).await?;
+        match config {
+            ArchiveConfig::Zenodo(zenodo) => {
+                deposit_to_zenodo(&ctx.state, zenodo, &publication, &bundle).await
+            }
+            ArchiveConfig::S3 => upload_to_s3(&ctx.state, &publication, &bundle).await,
+        }
+    }
+    .await;
+
+    let (location, error) = match result {
+        Ok(location) => (Some(location), None),
+        Err(e) => {
+            tracing::error!(publication_id = %publication.id, error = %e,
+                "Failed to archive publication");
+            (None, Some(e.to_string()))
+        }
+    };
+    let update = sqlx::query(
+        "UPDATE publications SET archive_url = $2, doi = $3, archive_error = $4 WHERE id = $1",
+    )
+    .bind(publication.id)
+    .bind(location.as_ref().map(|location| &location.url))
+    .bind(location.as_ref().and_then(|location| location.doi.as_ref()))
+    .bind(error)
+    .execute(&ctx.state.db)
+    .await;
+    if let Err(e) = update {
+        tracing::error!(publication_id = %publication.id, error = %e, "Failed to record archival");
+    }
+}
+
+/// File name of the archived bundle of a publication.
+fn archive_file_name(publication: &Publication) -> String {
+    format!("catcolab-{}-{}.json", publication.ref_id, publication.tag)
+}
+
+fn request_error(e: reqwest::Error) -> AppError {
+    AppError::Invalid(format!("Archive request failed: {e}"))
+}
+
+/// Fails unless a response from the archive is successful, returning its body.
+async fn response_text(response: reqwest::Response) -> Result<String, AppError> {
+    let status = response.status();
+    let text = response.text().await.map_err(request_error)?;
+    if status.is_success() {
+        Ok(text)
+    } else {
+        Err(AppError::Invalid(format!("Archive responded with status {status}: {text}")))
+    }
+}
+
+/// Metadata of a new Zenodo deposition for a publication.
+fn zenodo_metadata(publication: &Publication, bundle: &RunBundle) -> serde_json::Value {
+    let name = bundle
+        .documents
+        .first()
+        .and_then(|document| document.content.get("name"))
+        .and_then(|name| name.as_str())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("Untitled");
+    json!({
+        "metadata": {
+            "title": format!("{name} ({})", publication.tag),
+            "upload_type": "dataset",
+            "description": format!(
+                "Version {} of a CatColab document, with the documents it links to.",
+                publication.tag
+            ),
+            "prereserve_doi": true,
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct ZenodoDeposition {
+    links: ZenodoLinks,
+    metadata: ZenodoMetadata,
+}
+
+#[derive(Deserialize)]
+struct ZenodoLinks {
+    bucket: String,
+    html: String,
+}
+
+#[derive(Deserialize)]
+struct ZenodoMetadata {
+    prereserve_doi: Option<ZenodoDoi>,
+}
+
+#[derive(Deserialize)]
+struct ZenodoDoi {
+    doi: String,
+}
+
+/// Creates a Zenodo deposition and uploads the bundle to it.
+async fn deposit_to_zenodo(
+    state: &AppState,
+    config: &ZenodoConfig,
+    publication: &Publication,
+    bundle: &RunBundle,
+) -> Result<ArchiveLocation, AppError> {
+    let url = format!("{}/api/deposit/depositions", config.base_url.trim_end_matches('/'));
+    let response = state
+        .http_client
+        .post(url)
+        .bearer_auth(&config.access_token)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(zenodo_metadata(publication, bundle).to_string())
+        .send()
+        .await
+        .map_err(request_error)?;
+    let deposition: ZenodoDeposition = serde_json::from_str(&response_text(response).await?)?;
+
+    let upload_url = format!("{}/{}", deposition.links.bucket, archive_file_name(publication));
+    let response = state
+        .http_client
+        .put(upload_url)
+        .bearer_auth(&config.access_token)
+        .header(http::header::CONTENT_TYPE, "application/octet-stream")
+        .body(serde_json::to_vec(bundle)?)
+        .send()
+        .await
+        .map_err(request_error)?;
+    response_text(response).await?;
+
+    Ok(ArchiveLocation {
+        url: deposition.links.html,
+        doi: deposition.metadata.prereserve_doi.map(|doi| doi.doi),
+    })
+}
+
+/// Uploads the bundle to the S3 bucket used for attachments.
+async fn upload_to_s3(
+    state: &AppState,
+    publication: &Publication,
+    bundle: &RunBundle,
+) -> Result<ArchiveLocation, AppError> {
+    let s3 = state
+        .s3
+        .as_ref()
+        .ok_or_else(|| AppError::Invalid("Object storage is not configured".to_string()))?;
+    let key = format!("archives/{}/{}", publication.ref_id, archive_file_name(publication));
+    let upload_url = s3.presign("PUT", &key, UPLOAD_URL_EXPIRES_SECONDS, Utc::now())?;
+    let response = state
+        .http_client
+        .put(upload_url)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(bundle)?)
+        .send()
+        .await
+        .map_err(request_error)?;
+    response_text(response).await?;
+
+    let url = format!("{}/{}/{key}", s3.endpoint.trim_end_matches('/'), s3.bucket);
+    Ok(ArchiveLocation { url, doi: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_tags() {
+        assert!(validate_tag("v1.0.2-rc_1").is_ok());
+        assert!(validate_tag("").is_err());
+        assert!(validate_tag("v1 final").is_err());
+        assert!(validate_tag("../v1").is_err());
+        assert!(validate_tag(&"x".repeat(MAX_TAG_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn zenodo_deposition_metadata() {
+        let ref_id = Uuid::now_v7();
+        let publication = Publication {
+            id: Uuid::now_v7(),
+            ref_id,
+            snapshot_id: 1,
+            tag: "v1".to_string(),
+            published_at: Utc::now(),
+            archive_url: None,
+            doi: None,
+            archive_error: None,
+        };
+        let bundle = RunBundle {
+            format: bundle::BUNDLE_FORMAT,
+            versions: bundle::BundleVersions::current(),
+            root: ref_id,
+            theories: vec![],
+            documents: vec![bundle::BundledDocument {
+                ref_id,
+                version: None,
+                content: json!({ "name": "SIR model" }),
+                heads: vec![],
+            }],
+        };
+        let metadata = zenodo_metadata(&publication, &bundle);
+        assert_eq!(metadata["metadata"]["title"], "SIR model (v1)");
+        assert_eq!(metadata["metadata"]["prereserve_doi"], true);
+        assert_eq!(archive_file_name(&publication), format!("catcolab-{ref_id}-v1.json"));
+    }
+}
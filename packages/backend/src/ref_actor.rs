@@ -3,7 +3,7 @@
 use std::time::Duration;
 
 use crate::app::{AppError, AppState, RefMsg, RefReply};
-use crate::document;
+use crate::{document, maintenance};
 use futures_util::stream::StreamExt;
 use samod::DocHandle;
 use tokio::sync::mpsc;
@@ -12,6 +12,9 @@ use uuid::Uuid;
 
 const SNAPSHOT_DEBOUNCE: Duration = Duration::from_millis(500);
 
+/// Interval at which deferred autosaves are retried during maintenance.
+const MAINTENANCE_RETRY: Duration = Duration::from_secs(30);
+
 /// Ensures a ref actor is running for the given ref, spawning one if needed.
 pub async fn ensure_ref_actor(state: AppState, ref_id: Uuid, doc_handle: DocHandle) {
     let mut actors = state.ref_actors.write().await;
@@ -68,6 +71,11 @@ pub async fn send_to_actor(state: &AppState, ref_id: Uuid, msg: RefMsg) -> Resul
         .map_err(|_| AppError::Invalid(format!("Ref actor for {ref_id} dropped reply")))?
 }
 
+/// Gets the current heads of the document.
+fn doc_heads(doc_handle: &DocHandle) -> Vec<automerge::ChangeHash> {
+    doc_handle.with_document(|doc| doc.get_heads())
+}
+
 /// The main actor loop for a single document ref.
 async fn run_ref_actor(
     state: AppState,
@@ -97,10 +105,26 @@ async fn run_ref_actor(
                     }
                     RefMsg::LoadSnapshot { snapshot_id } => {
                         deadline = None;
-                        skip_changes += 1;
-                        document::load_snapshot(
+                        let heads = doc_heads(&doc_handle);
+                        let result = document::load_snapshot(
                             &state, ref_id, snapshot_id, &doc_handle,
-                        ).await
+                        ).await;
+                        // Skip our own change, if one was actually made.
+                        if doc_heads(&doc_handle) != heads {
+                            skip_changes += 1;
+                        }
+                        result
+                    }
+                    RefMsg::ReplaceContent { content } => {
+                        deadline = None;
+                        let heads = doc_heads(&doc_handle);
+                        let result = document::replace_content(
+                            &state, ref_id, content, &doc_handle,
+                        ).await;
+                        if doc_heads(&doc_handle) != heads {
+                            skip_changes += 1;
+                        }
+                        result
                     }
                     RefMsg::Delete => {
                         deadline = None;
@@ -129,6 +153,10 @@ async fn run_ref_actor(
             }
 
             _ = &mut sleep => {
+                if maintenance::require_writable(&state).await.is_err() {
+                    deadline = Some(Instant::now() + MAINTENANCE_RETRY);
+                    continue;
+                }
                 deadline = None;
                 if let Err(e) = document::create_snapshot(state.clone(), ref_id).await {
                     tracing::error!("Autosave snapshot failed for ref {}: {:?}", ref_id, e);
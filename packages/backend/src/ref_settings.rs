@@ -0,0 +1,189 @@
+//! Per-ref settings.
+//!
+//! Each ref has settings that apply to every user of the document, such as a
+//! pinned version of its theory and default options for analyses, stored in the
+//! `settings` column of the `refs` table as JSON. Settings are read and written
+//! as a whole through the typed [`RefSettings`], whose TypeScript binding is
+//! used by the frontend. Settings written by clients are deserialized into this
+//! type, rejecting unknown fields, and then validated before they are stored.
+
+use catlog::stdlib::versions::{TheoryVersion, check_theory};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::app::{AppError, AppState};
+
+/// Maximum length of a pinned theory version, in bytes.
+const MAX_VERSION_LENGTH: usize = 64;
+
+/// Maximum number of steps that a solver can be allowed to take.
+const MAX_SOLVER_STEPS: u32 = 1_000_000;
+
+/// Settings of a document ref.
+#[qubit::ts]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RefSettings {
+    /// Version of the document's theory to use, instead of the latest one, as
+    /// an identifier and version such as `"causal-loop@2"`.
+    #[serde(rename = "pinnedTheoryVersion", default)]
+    pub pinned_theory_version: Option<String>,
+
+    /// Default options for solvers in analyses of the document.
+    #[serde(rename = "solverOptions", default)]
+    pub solver_options: SolverOptions,
+}
+
+/// Default options for numerical solvers, each unset to use the solver default.
+#[qubit::ts]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SolverOptions {
+    /// Duration of simulations.
+    #[serde(default)]
+    pub duration: Option<f64>,
+
+    /// Relative tolerance of adaptive solvers.
+    #[serde(rename = "relTol", default)]
+    pub rel_tol: Option<f64>,
+
+    /// Absolute tolerance of adaptive solvers.
+    #[serde(rename = "absTol", default)]
+    pub abs_tol: Option<f64>,
+
+    /// Maximum number of steps taken by a solver.
+    #[serde(rename = "maxSteps", default)]
+    pub max_steps: Option<u32>,
+}
+
+impl RefSettings {
+    /// Validates the settings, beyond what is enforced by their type.
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(version) = &self.pinned_theory_version {
+            validate_theory_version(version)?;
+        }
+        self.solver_options.validate()
+    }
+}
+
+/// Validates a theory version, which must exist in the standard library.
+fn validate_theory_version(version: &str) -> Result<(), AppError> {
+    let invalid = || AppError::Invalid(format!("Invalid theory version: {version}"));
+    if version.len() > MAX_VERSION_LENGTH {
+        return Err(invalid());
+    }
+    let parsed: TheoryVersion = version.parse().map_err(|_| invalid())?;
+    let valid_id = !parsed.id.is_empty()
+        && parsed
+            .id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid_id || parsed.version == 0 {
+        return Err(invalid());
+    }
+    check_theory(&parsed).map_err(|err| AppError::Invalid(err.to_string()))?;
+    Ok(())
+}
+
+impl SolverOptions {
+    fn validate(&self) -> Result<(), AppError> {
+        let positive =
+            [("duration", self.duration), ("relTol", self.rel_tol), ("absTol", self.abs_tol)];
+        for (name, value) in positive {
+            if value.is_some_and(|x| !(x.is_finite() && x > 0.0)) {
+                return Err(AppError::Invalid(format!("Solver option {name} must be positive")));
+            }
+        }
+        if self.max_steps.is_some_and(|n| n == 0 || n > MAX_SOLVER_STEPS) {
+            return Err(AppError::Invalid(format!(
+                "Solver option maxSteps must be between 1 and {MAX_SOLVER_STEPS}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Gets the settings of a ref.
+///
+/// Settings that are missing, or were stored by an older version and are no
+/// longer valid, are replaced by their defaults.
+pub async fn get_ref_settings(state: &AppState, ref_id: Uuid) -> Result<RefSettings, AppError> {
+    let settings: Value =
+        sqlx::query_scalar("SELECT settings FROM refs WHERE id = $1 AND deleted_at IS NULL")
+            .bind(ref_id)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Ref {ref_id}")))?;
+    Ok(serde_json::from_value(settings).unwrap_or_else(|e| {
+        tracing::warn!(%ref_id, error = %e, "Discarding invalid ref settings");
+        RefSettings::default()
+    }))
+}
+
+/// Updates the settings of a ref, after validating them.
+pub async fn update_ref_settings(
+    state: &AppState,
+    ref_id: Uuid,
+    settings: RefSettings,
+) -> Result<RefSettings, AppError> {
+    settings.validate()?;
+    let result = sqlx::query("UPDATE refs SET settings = $2 WHERE id = $1 AND deleted_at IS NULL")
+        .bind(ref_id)
+        .bind(Json(&settings))
+        .execute(&state.db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Ref {ref_id}")));
+    }
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn deserialize_settings() {
+        let settings: RefSettings = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(settings, RefSettings::default());
+
+        let settings: RefSettings = serde_json::from_value(json!({
+            "pinnedTheoryVersion": "causal-loop",
+            "solverOptions": { "duration": 10.0, "maxSteps": 500 },
+        }))
+        .unwrap();
+        assert_eq!(settings.solver_options.duration, Some(10.0));
+        assert!(settings.validate().is_ok());
+
+        let unknown = json!({ "solverOptions": { "tolerance": 1e-6 } });
+        assert!(serde_json::from_value::<RefSettings>(unknown).is_err());
+    }
+
+    #[test]
+    fn validate_settings() {
+        let mut settings = RefSettings {
+            pinned_theory_version: Some("".into()),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+        for invalid in ["2.1", "causal-loop@", "causal-loop@0", "causal-loop@x", "@1"] {
+            settings.pinned_theory_version = Some(invalid.into());
+            assert!(settings.validate().is_err(), "{invalid} should be invalid");
+        }
+        // Versions newer than the runtime theory cannot be pinned.
+        settings.pinned_theory_version = Some("causal-loop@99".into());
+        assert!(settings.validate().is_err());
+        settings.pinned_theory_version = Some("causal-loop@1".into());
+        assert!(settings.validate().is_ok());
+        settings.solver_options.rel_tol = Some(-1e-6);
+        assert!(settings.validate().is_err());
+        settings.solver_options.rel_tol = Some(f64::NAN);
+        assert!(settings.validate().is_err());
+        settings.solver_options = SolverOptions { max_steps: Some(0), ..Default::default() };
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,286 @@
+//! Versioned REST API for clients other than the web app.
+//!
+//! The REST API is a facade over the core procedures of the RPC API, intended
+//! for scripting CatColab from other languages, such as Python notebooks. Unlike
+//! the RPC API, whose shape follows the needs of the frontend, the REST API is
+//! versioned by path prefix and its request and response formats are kept
+//! stable within a version. Requests are authenticated in the same way as RPC
+//! calls, with a Firebase ID token passed as a bearer token.
+//!
+//! Analyses implemented in `catlog` are run by clients, so the API exposes the
+//! cached results of analyses rather than running them on the server.
+
+use axum::extract::{Path, Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use catcolab_document_types::current::export_latex;
+use chrono::{DateTime, Utc};
+use firebase_auth::FirebaseUser;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::analysis_cache::{self, AnalysisKey, CachedAnalysis};
+use crate::app::{AppCtx, AppError, AppState, RefMsg};
+use crate::auth::{self, PermissionLevel};
+use crate::document as doc;
+use crate::maintenance;
+use crate::ref_actor::send_to_actor;
+use crate::sql_export::{self, SqlDialect};
+use crate::svg_export::{self, THUMBNAIL_SIZE};
+
+/// Creates the router for version 1 of the REST API.
+pub fn router_v1(state: AppState) -> Router {
+    Router::new()
+        .route("/refs", get(list_refs).post(create_ref))
+        .route("/refs/{ref_id}", get(get_ref).put(save_ref))
+        .route("/refs/{ref_id}/analyses/{analysis_id}", get(get_analysis))
+        .route("/refs/{ref_id}/sql", get(get_sql))
+        .route("/refs/{ref_id}/latex", get(get_latex))
+        .route("/refs/{ref_id}/svg", get(get_svg))
+        .route("/refs/{ref_id}/thumbnail.svg", get(get_thumbnail))
+        .with_state(state)
+}
+
+/// Error returned by the REST API, serialized as JSON.
+pub struct ApiError(AppError);
+
+impl From<AppError> for ApiError {
+    fn from(error: AppError) -> Self {
+        ApiError(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.0.status_code();
+        let message = self.0.message();
+        let body = json!({
+            "code": status.as_u16(),
+            "message": message.localize(crate::i18n::DEFAULT_LOCALE),
+            "key": message.key,
+            "params": message.params,
+        });
+        (status, Json(body)).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+fn app_ctx(state: AppState, user: Option<Extension<FirebaseUser>>) -> AppCtx {
+    AppCtx {
+        state,
+        user: user.map(|Extension(user)| user),
+    }
+}
+
+/// Summary of a document ref.
+#[derive(Clone, Debug, Serialize)]
+pub struct RefSummary {
+    /// ID of the ref.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// Name of the document.
+    pub name: Option<String>,
+
+    /// Type of the document, such as `"model"` or `"analysis"`.
+    #[serde(rename = "type")]
+    pub type_name: Option<String>,
+
+    /// Highest permission level of the user on the ref.
+    pub permission: PermissionLevel,
+
+    /// When the ref was created.
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+
+    /// When the document was last saved.
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Lists the undeleted refs on which the user has explicit permissions.
+async fn list_refs(
+    State(state): State<AppState>,
+    user: Option<Extension<FirebaseUser>>,
+) -> ApiResult<Vec<RefSummary>> {
+    let Some(Extension(user)) = user else {
+        return Err(AppError::Unauthorized.into());
+    };
+    let rows = sqlx::query(
+        "
+        SELECT refs.id, snapshots.content->>'name' AS name,
+               snapshots.content->>'type' AS type_name,
+               MAX(permissions.level) AS permission,
+               refs.created, refs.current_snapshot_updated_at
+        FROM refs
+        JOIN snapshots ON snapshots.id = refs.current_snapshot
+        JOIN permissions ON permissions.object = refs.id
+        WHERE permissions.subject = $1 AND refs.deleted_at IS NULL
+        GROUP BY refs.id, snapshots.id
+        ORDER BY refs.created DESC
+        ",
+    )
+    .bind(&user.user_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(
+        rows.iter()
+            .map(|row| RefSummary {
+                ref_id: row.get("id"),
+                name: row.get("name"),
+                type_name: row.get("type_name"),
+                permission: row.get("permission"),
+                created_at: row.get("created"),
+                updated_at: row.get("current_snapshot_updated_at"),
+            })
+            .collect(),
+    ))
+}
+
+/// Response to the creation of a ref.
+#[derive(Clone, Debug, Serialize)]
+pub struct CreatedRef {
+    /// ID of the new ref.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+}
+
+/// Creates a ref with the given document content.
+async fn create_ref(
+    State(state): State<AppState>,
+    user: Option<Extension<FirebaseUser>>,
+    Json(content): Json<Value>,
+) -> ApiResult<CreatedRef> {
+    let ctx = app_ctx(state, user);
+    maintenance::require_writable(&ctx.state).await?;
+    if ctx.user.is_none() {
+        return Err(AppError::Unauthorized.into());
+    }
+    let ref_id = doc::new_ref(ctx, content).await?;
+    Ok(Json(CreatedRef { ref_id }))
+}
+
+/// Gets the current content of a document.
+async fn get_ref(
+    State(state): State<AppState>,
+    user: Option<Extension<FirebaseUser>>,
+    Path(ref_id): Path<Uuid>,
+) -> ApiResult<Value> {
+    let ctx = app_ctx(state, user);
+    auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+    Ok(Json(doc::get_content(ctx.state, ref_id).await?))
+}
+
+/// Replaces the content of a document.
+async fn save_ref(
+    State(state): State<AppState>,
+    user: Option<Extension<FirebaseUser>>,
+    Path(ref_id): Path<Uuid>,
+    Json(content): Json<Value>,
+) -> Result<StatusCode, ApiError> {
+    let ctx = app_ctx(state, user);
+    maintenance::require_writable(&ctx.state).await?;
+    auth::authorize(&ctx, ref_id, PermissionLevel::Write).await?;
+    send_to_actor(&ctx.state, ref_id, RefMsg::ReplaceContent { content }).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Query parameters identifying the configuration of an analysis.
+#[derive(Deserialize)]
+struct AnalysisQuery {
+    #[serde(rename = "configHash")]
+    config_hash: String,
+}
+
+/// Gets the cached result of an analysis of the current content of a document.
+async fn get_analysis(
+    State(state): State<AppState>,
+    user: Option<Extension<FirebaseUser>>,
+    Path((ref_id, analysis_id)): Path<(Uuid, String)>,
+    Query(AnalysisQuery { config_hash }): Query<AnalysisQuery>,
+) -> ApiResult<CachedAnalysis> {
+    let ctx = app_ctx(state, user);
+    auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+    let key = AnalysisKey { analysis_id, config_hash };
+    analysis_cache::get_cached_analysis(&ctx.state, ref_id, &key)
+        .await?
+        .map(Json)
+        .ok_or_else(|| {
+            AppError::NotFound(format!("analysis {} of ref {ref_id}", key.analysis_id)).into()
+        })
+}
+
+/// Query parameters for exporting a schema as SQL.
+#[derive(Deserialize)]
+struct SqlQuery {
+    #[serde(default)]
+    dialect: SqlDialect,
+}
+
+/// Downloads the current content of a schema document as SQL DDL.
+async fn get_sql(
+    State(state): State<AppState>,
+    user: Option<Extension<FirebaseUser>>,
+    Path(ref_id): Path<Uuid>,
+    Query(SqlQuery { dialect }): Query<SqlQuery>,
+) -> Result<Response, ApiError> {
+    let ctx = app_ctx(state, user);
+    let sql = sql_export::export_sql(&ctx, ref_id, dialect).await?;
+    let headers = [
+        (header::CONTENT_TYPE, "application/sql; charset=utf-8".to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{ref_id}.sql\"")),
+    ];
+    Ok((headers, sql).into_response())
+}
+
+/// Downloads the current content of a document as a LaTeX article.
+async fn get_latex(
+    State(state): State<AppState>,
+    user: Option<Extension<FirebaseUser>>,
+    Path(ref_id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let ctx = app_ctx(state, user);
+    auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+    let content = doc::get_content(ctx.state.clone(), ref_id).await?;
+    let document: catcolab_document_types::VersionedDocument = serde_json::from_value(content)
+        .map_err(|e| AppError::Invalid(format!("Failed to parse document: {e}")))?;
+    let latex = export_latex(&document.to_current());
+    let headers = [
+        (header::CONTENT_TYPE, "application/x-latex; charset=utf-8".to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{ref_id}.tex\"")),
+    ];
+    Ok((headers, latex).into_response())
+}
+
+/// Downloads the current content of a document as an SVG diagram.
+async fn get_svg(
+    State(state): State<AppState>,
+    user: Option<Extension<FirebaseUser>>,
+    Path(ref_id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let ctx = app_ctx(state, user);
+    let svg = svg_export::export_svg(&ctx, ref_id, None).await?;
+    let headers = [
+        (header::CONTENT_TYPE, "image/svg+xml".to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{ref_id}.svg\"")),
+    ];
+    Ok((headers, svg).into_response())
+}
+
+/// Gets a small SVG preview of the current content of a document.
+async fn get_thumbnail(
+    State(state): State<AppState>,
+    user: Option<Extension<FirebaseUser>>,
+    Path(ref_id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let ctx = app_ctx(state, user);
+    let svg = svg_export::export_svg(&ctx, ref_id, Some(THUMBNAIL_SIZE)).await?;
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response())
+}
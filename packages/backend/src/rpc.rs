@@ -10,12 +10,22 @@ use super::app::{AppCtx, AppError, AppState, RefMsg};
 use super::auth::{NewPermissions, PermissionLevel, Permissions};
 use super::ref_actor::{ensure_ref_actor, send_to_actor};
 use super::user_state::get_or_create_user_state_doc;
-use super::{auth, document as doc, user};
+use super::{
+    analysis_cache, attachments, auth, bundle, classroom, datasets, document as doc, embed,
+    encryption, feature_flags, history, i18n, jobs, lints, maintenance, model_clustering,
+    moderation, presence, publications, ref_settings, scratch, similarity, sql_export, user,
+    verification,
+};
+
+mod description;
+pub use description::{ApiDescription, describe};
 
 /// Create router for RPC API.
 pub fn router() -> Router<AppState> {
     Router::new()
         .handler(new_ref)
+        .handler(new_encrypted_ref)
+        .handler(get_encryption)
         .handler(get_doc)
         .handler(load_snapshot)
         .handler(delete_ref)
@@ -29,11 +39,95 @@ pub fn router() -> Router<AppState> {
         .handler(get_active_user_profile)
         .handler(set_active_user_profile)
         .handler(get_user_state_doc_id)
+        .handler(get_message_catalog)
+        .handler(get_verification_status)
+        .handler(link_orcid)
+        .handler(get_cached_analysis)
+        .handler(put_cached_analysis)
+        .handler(create_attachment)
+        .handler(complete_attachment)
+        .handler(list_attachments)
+        .handler(get_attachment_url)
+        .handler(delete_attachment)
+        .handler(create_dataset)
+        .handler(list_datasets)
+        .handler(get_dataset)
+        .handler(get_dataset_slice)
+        .handler(delete_dataset)
+        .handler(report_content)
+        .handler(list_reports)
+        .handler(resolve_report)
+        .handler(unhide_ref)
+        .handler(create_embed_token)
+        .handler(list_embed_tokens)
+        .handler(revoke_embed_token)
+        .handler(get_embedded_doc)
+        .handler(export_run_bundle)
+        .handler(import_run_bundle)
+        .handler(export_sql)
+        .handler(get_maintenance_mode)
+        .handler(set_maintenance_mode)
+        .handler(publish_version)
+        .handler(list_publications)
+        .handler(get_edit_history)
+        .handler(get_feature_flags)
+        .handler(list_feature_flags)
+        .handler(set_feature_flag)
+        .handler(invite_by_email)
+        .handler(list_invites)
+        .handler(revoke_invite)
+        .handler(open_edit_session)
+        .handler(renew_edit_session)
+        .handler(close_edit_session)
+        .handler(provision_copies)
+        .handler(list_classroom_copies)
+        .handler(get_ref_settings)
+        .handler(update_ref_settings)
+        .handler(start_model_clustering)
+        .handler(get_job)
+        .handler(wait_for_job_progress)
+        .handler(find_similar)
+        .handler(get_lints)
+        .handler(create_scratch_doc)
+        .handler(promote_scratch_doc)
+        .handler(discard_scratch_doc)
 }
 
 #[handler(mutation)]
 async fn new_ref(ctx: AppCtx, content: Value) -> RpcResult<Uuid> {
-    doc::new_ref(ctx, content).await.into()
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        doc::new_ref(ctx, content).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn new_encrypted_ref(
+    ctx: AppCtx,
+    content: Value,
+    encryption: encryption::EncryptionMetadata,
+) -> RpcResult<Uuid> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        encryption::new_encrypted_ref(ctx, content, encryption).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_encryption(
+    ctx: AppCtx,
+    ref_id: Uuid,
+) -> RpcResult<Option<encryption::EncryptionMetadata>> {
+    async {
+        auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+        encryption::get_encryption(&ctx.state, ref_id).await
+    }
+    .await
+    .into()
 }
 
 #[handler(query)]
@@ -94,6 +188,7 @@ enum RefDoc {
 #[handler(mutation)]
 async fn load_snapshot(ctx: AppCtx, ref_id: Uuid, snapshot_id: i32) -> RpcResult<()> {
     async {
+        maintenance::require_writable(&ctx.state).await?;
         auth::authorize(&ctx, ref_id, PermissionLevel::Write).await?;
         send_to_actor(&ctx.state, ref_id, RefMsg::LoadSnapshot { snapshot_id }).await
     }
@@ -104,6 +199,7 @@ async fn load_snapshot(ctx: AppCtx, ref_id: Uuid, snapshot_id: i32) -> RpcResult
 #[handler(mutation)]
 async fn delete_ref(ctx: AppCtx, ref_id: Uuid) -> RpcResult<()> {
     async {
+        maintenance::require_writable(&ctx.state).await?;
         auth::authorize(&ctx, ref_id, PermissionLevel::Own).await?;
         send_to_actor(&ctx.state, ref_id, RefMsg::Delete).await
     }
@@ -114,6 +210,7 @@ async fn delete_ref(ctx: AppCtx, ref_id: Uuid) -> RpcResult<()> {
 #[handler(mutation)]
 async fn restore_ref(ctx: AppCtx, ref_id: Uuid) -> RpcResult<()> {
     async {
+        maintenance::require_writable(&ctx.state).await?;
         auth::authorize(&ctx, ref_id, PermissionLevel::Own).await?;
         send_to_actor(&ctx.state, ref_id, RefMsg::Restore).await
     }
@@ -129,10 +226,19 @@ async fn get_permissions(ctx: AppCtx, ref_id: Uuid) -> RpcResult<Permissions> {
 #[handler(mutation)]
 async fn set_permissions(ctx: AppCtx, ref_id: Uuid, new: NewPermissions) -> RpcResult<()> {
     async {
+        maintenance::require_writable(&ctx.state).await?;
         if ctx.user.is_none() {
             return Err(AppError::Unauthorized);
         }
         auth::authorize(&ctx, ref_id, PermissionLevel::Own).await?;
+        if new.anyone.is_some() && auth::permissions(&ctx, ref_id).await?.anyone.is_none() {
+            if moderation::is_hidden(&ctx.state, ref_id).await? {
+                return Err(AppError::Invalid(
+                    "Document has been hidden by moderators and cannot be made public".to_string(),
+                ));
+            }
+            verification::require_verified(&ctx).await?;
+        }
         auth::set_permissions(&ctx.state, ref_id, new).await
     }
     .await
@@ -146,7 +252,21 @@ async fn validate_session(ctx: AppCtx) -> RpcResult<()> {
 
 #[handler(mutation)]
 async fn sign_up_or_sign_in(ctx: AppCtx) -> RpcResult<()> {
-    user::sign_up_or_sign_in(ctx).await.into()
+    let result = async {
+        user::sign_up_or_sign_in(ctx.clone()).await?;
+        verification::record_email_verification(&ctx).await
+    }
+    .await;
+    // Users can still sign in during maintenance, when recording the user is
+    // best-effort since the database may be unavailable. It is retried at the
+    // next sign-in.
+    if let Err(e) = &result
+        && maintenance::maintenance_mode(&ctx.state).await.is_some()
+    {
+        warn!(error = %e, "Failed to record user during maintenance");
+        return RpcResult::Ok { content: () };
+    }
+    result.into()
 }
 
 #[handler(query)]
@@ -166,7 +286,12 @@ async fn get_active_user_profile(ctx: AppCtx) -> RpcResult<user::UserProfile> {
 
 #[handler(mutation)]
 async fn set_active_user_profile(ctx: AppCtx, user: user::UserProfile) -> RpcResult<()> {
-    user::set_active_user_profile(ctx, user).await.into()
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        user::set_active_user_profile(ctx, user).await
+    }
+    .await
+    .into()
 }
 
 #[handler(query)]
@@ -180,24 +305,579 @@ async fn get_user_state_doc_id(ctx: AppCtx) -> RpcResult<String> {
     .into()
 }
 
+#[handler(query)]
+async fn get_message_catalog(
+    _ctx: AppCtx,
+    locale: String,
+) -> RpcResult<std::collections::HashMap<String, String>> {
+    i18n::catalog(&locale)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("message catalog for locale {locale}")))
+        .into()
+}
+
+#[handler(query)]
+async fn get_verification_status(ctx: AppCtx) -> RpcResult<verification::VerificationStatus> {
+    verification::verification_status(&ctx).await.into()
+}
+
+#[handler(mutation)]
+async fn link_orcid(ctx: AppCtx, code: String) -> RpcResult<String> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        verification::link_orcid(&ctx, &code).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_cached_analysis(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    key: analysis_cache::AnalysisKey,
+) -> RpcResult<Option<analysis_cache::CachedAnalysis>> {
+    async {
+        auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+        analysis_cache::get_cached_analysis(&ctx.state, ref_id, &key).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn put_cached_analysis(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    key: analysis_cache::AnalysisKey,
+    result: Value,
+    ttl_seconds: Option<i64>,
+) -> RpcResult<()> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        auth::authorize(&ctx, ref_id, PermissionLevel::Write).await?;
+        analysis_cache::put_cached_analysis(&ctx.state, ref_id, &key, result, ttl_seconds).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn create_attachment(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    new: attachments::NewAttachment,
+) -> RpcResult<attachments::AttachmentUpload> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        attachments::create_attachment(&ctx, ref_id, new).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn complete_attachment(
+    ctx: AppCtx,
+    attachment_id: Uuid,
+) -> RpcResult<attachments::Attachment> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        attachments::complete_attachment(&ctx, attachment_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn list_attachments(ctx: AppCtx, ref_id: Uuid) -> RpcResult<Vec<attachments::Attachment>> {
+    async {
+        auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+        attachments::list_attachments(&ctx.state, ref_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_attachment_url(ctx: AppCtx, attachment_id: Uuid) -> RpcResult<String> {
+    attachments::get_download_url(&ctx, attachment_id).await.into()
+}
+
+#[handler(mutation)]
+async fn delete_attachment(ctx: AppCtx, attachment_id: Uuid) -> RpcResult<()> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        attachments::delete_attachment(&ctx, attachment_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn create_dataset(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    name: String,
+    csv: String,
+) -> RpcResult<datasets::Dataset> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        datasets::create_dataset(&ctx, ref_id, name, &csv).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn list_datasets(ctx: AppCtx, ref_id: Uuid) -> RpcResult<Vec<datasets::Dataset>> {
+    async {
+        auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+        datasets::list_datasets(&ctx.state, ref_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_dataset(ctx: AppCtx, dataset_id: Uuid) -> RpcResult<datasets::Dataset> {
+    async {
+        let dataset = datasets::get_dataset(&ctx.state, dataset_id).await?;
+        auth::authorize(&ctx, dataset.ref_id, PermissionLevel::Read).await?;
+        Ok(dataset)
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_dataset_slice(
+    ctx: AppCtx,
+    dataset_id: Uuid,
+    request: datasets::SliceRequest,
+) -> RpcResult<Vec<datasets::DatasetColumn>> {
+    datasets::get_dataset_slice(&ctx, dataset_id, request).await.into()
+}
+
+#[handler(mutation)]
+async fn delete_dataset(ctx: AppCtx, dataset_id: Uuid) -> RpcResult<()> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        datasets::delete_dataset(&ctx, dataset_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn report_content(ctx: AppCtx, ref_id: Uuid, reason: String) -> RpcResult<Uuid> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        moderation::report_content(&ctx, ref_id, &reason).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn list_reports(
+    ctx: AppCtx,
+    status: Option<moderation::ReportStatus>,
+) -> RpcResult<Vec<moderation::ContentReport>> {
+    async {
+        moderation::require_admin(&ctx).await?;
+        moderation::list_reports(&ctx.state, status).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn resolve_report(
+    ctx: AppCtx,
+    report_id: Uuid,
+    action: moderation::ModerationAction,
+) -> RpcResult<()> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        moderation::require_admin(&ctx).await?;
+        moderation::resolve_report(&ctx, report_id, action).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn unhide_ref(ctx: AppCtx, ref_id: Uuid) -> RpcResult<()> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        moderation::require_admin(&ctx).await?;
+        moderation::unhide_ref(&ctx.state, ref_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn create_embed_token(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    expires_in_seconds: Option<i64>,
+) -> RpcResult<embed::NewEmbedToken> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        embed::create_embed_token(&ctx, ref_id, expires_in_seconds).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn list_embed_tokens(ctx: AppCtx, ref_id: Uuid) -> RpcResult<Vec<embed::EmbedTokenInfo>> {
+    async {
+        auth::authorize(&ctx, ref_id, PermissionLevel::Maintain).await?;
+        embed::list_embed_tokens(&ctx.state, ref_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn revoke_embed_token(ctx: AppCtx, token_id: Uuid) -> RpcResult<()> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        embed::revoke_embed_token(&ctx, token_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_embedded_doc(ctx: AppCtx, token: String) -> RpcResult<embed::EmbeddedDoc> {
+    embed::get_embedded_doc(&ctx.state, &token).await.into()
+}
+
+#[handler(query)]
+async fn export_run_bundle(ctx: AppCtx, ref_id: Uuid) -> RpcResult<bundle::RunBundle> {
+    bundle::export_run_bundle(&ctx, ref_id).await.into()
+}
+
+#[handler(mutation)]
+async fn import_run_bundle(ctx: AppCtx, bundle: bundle::RunBundle) -> RpcResult<Uuid> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        bundle::import_run_bundle(&ctx, bundle).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn export_sql(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    dialect: sql_export::SqlDialect,
+) -> RpcResult<String> {
+    sql_export::export_sql(&ctx, ref_id, dialect).await.into()
+}
+
+#[handler(query)]
+async fn get_maintenance_mode(ctx: AppCtx) -> RpcResult<Option<maintenance::MaintenanceMode>> {
+    RpcResult::Ok {
+        content: maintenance::maintenance_mode(&ctx.state).await,
+    }
+}
+
+#[handler(mutation)]
+async fn set_maintenance_mode(
+    ctx: AppCtx,
+    mode: Option<maintenance::MaintenanceMode>,
+) -> RpcResult<()> {
+    async {
+        moderation::require_admin(&ctx).await?;
+        maintenance::set_maintenance_mode(&ctx.state, mode).await;
+        Ok(())
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn publish_version(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    tag: String,
+) -> RpcResult<publications::Publication> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        publications::publish_version(&ctx, ref_id, &tag).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn list_publications(ctx: AppCtx, ref_id: Uuid) -> RpcResult<Vec<publications::Publication>> {
+    async {
+        auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+        publications::list_publications(&ctx.state, ref_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_edit_history(ctx: AppCtx, ref_id: Uuid) -> RpcResult<history::EditHistory> {
+    async {
+        feature_flags::require_feature(&ctx, feature_flags::HISTORY_INSIGHTS).await?;
+        auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+        history::get_edit_history(&ctx.state, ref_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_feature_flags(ctx: AppCtx) -> RpcResult<Vec<String>> {
+    feature_flags::enabled_features(&ctx).await.into()
+}
+
+#[handler(query)]
+async fn list_feature_flags(ctx: AppCtx) -> RpcResult<Vec<feature_flags::FeatureFlag>> {
+    async {
+        moderation::require_admin(&ctx).await?;
+        feature_flags::list_feature_flags(&ctx.state).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn set_feature_flag(
+    ctx: AppCtx,
+    flag: String,
+    scope: feature_flags::FeatureFlagScope,
+    enabled: Option<bool>,
+) -> RpcResult<()> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        moderation::require_admin(&ctx).await?;
+        feature_flags::set_feature_flag(&ctx.state, &flag, &scope, enabled).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn invite_by_email(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    email: String,
+    level: PermissionLevel,
+) -> RpcResult<auth::invites::Invite> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        auth::authorize(&ctx, ref_id, PermissionLevel::Own).await?;
+        auth::invites::invite_by_email(&ctx, ref_id, &email, level).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn list_invites(ctx: AppCtx, ref_id: Uuid) -> RpcResult<Vec<auth::invites::Invite>> {
+    async {
+        auth::authorize(&ctx, ref_id, PermissionLevel::Own).await?;
+        auth::invites::list_invites(&ctx.state, ref_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn revoke_invite(ctx: AppCtx, invite_id: Uuid) -> RpcResult<()> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        let ref_id = auth::invites::invite_ref(&ctx.state, invite_id).await?;
+        auth::authorize(&ctx, ref_id, PermissionLevel::Own).await?;
+        auth::invites::revoke_invite(&ctx.state, invite_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn open_edit_session(ctx: AppCtx, ref_id: Uuid) -> RpcResult<presence::EditSession> {
+    async {
+        auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+        let can_write = auth::is_authorized(&ctx, ref_id, PermissionLevel::Write).await?;
+        let user_id = ctx.user.as_ref().map(|user| user.user_id.as_str());
+        Ok(ctx.state.presence.open(ref_id, user_id, can_write))
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn renew_edit_session(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    session_id: Uuid,
+) -> RpcResult<presence::EditSession> {
+    let user_id = ctx.user.as_ref().map(|user| user.user_id.as_str());
+    ctx.state
+        .presence
+        .renew(ref_id, session_id, user_id)
+        .ok_or_else(|| AppError::NotFound(format!("Edit session {session_id}")))
+        .into()
+}
+
+#[handler(mutation)]
+async fn close_edit_session(ctx: AppCtx, ref_id: Uuid, session_id: Uuid) -> RpcResult<()> {
+    let user_id = ctx.user.as_ref().map(|user| user.user_id.as_str());
+    ctx.state.presence.close(ref_id, session_id, user_id);
+    RpcResult::Ok { content: () }
+}
+
+#[handler(mutation)]
+async fn provision_copies(
+    ctx: AppCtx,
+    template_ref: Uuid,
+    students: Vec<String>,
+    level: PermissionLevel,
+) -> RpcResult<Vec<classroom::ProvisionedCopy>> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        auth::authorize(&ctx, template_ref, PermissionLevel::Maintain).await?;
+        classroom::provision_copies(&ctx, template_ref, &students, level).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn list_classroom_copies(
+    ctx: AppCtx,
+    template_ref: Uuid,
+) -> RpcResult<Vec<classroom::ClassroomCopy>> {
+    async {
+        auth::authorize(&ctx, template_ref, PermissionLevel::Maintain).await?;
+        classroom::list_classroom_copies(&ctx.state, template_ref).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_ref_settings(ctx: AppCtx, ref_id: Uuid) -> RpcResult<ref_settings::RefSettings> {
+    async {
+        auth::authorize(&ctx, ref_id, PermissionLevel::Read).await?;
+        ref_settings::get_ref_settings(&ctx.state, ref_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn update_ref_settings(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    settings: ref_settings::RefSettings,
+) -> RpcResult<ref_settings::RefSettings> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        auth::authorize(&ctx, ref_id, PermissionLevel::Maintain).await?;
+        ref_settings::update_ref_settings(&ctx.state, ref_id, settings).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn start_model_clustering(
+    ctx: AppCtx,
+    template_ref: Uuid,
+    n_clusters: usize,
+) -> RpcResult<Uuid> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        model_clustering::start_model_clustering(&ctx, template_ref, n_clusters).await
+    }
+    .await
+    .into()
+}
+
+#[handler(query)]
+async fn get_job(ctx: AppCtx, job_id: Uuid) -> RpcResult<jobs::Job> {
+    jobs::get_job(&ctx, job_id).await.into()
+}
+
+#[handler(query)]
+async fn wait_for_job_progress(
+    ctx: AppCtx,
+    job_id: Uuid,
+    after: Option<i32>,
+) -> RpcResult<jobs::JobUpdate> {
+    jobs::wait_for_job_progress(&ctx, job_id, after).await.into()
+}
+
+#[handler(query)]
+async fn find_similar(
+    ctx: AppCtx,
+    ref_id: Uuid,
+    limit: usize,
+) -> RpcResult<Vec<similarity::SimilarDocument>> {
+    similarity::find_similar(&ctx, ref_id, limit).await.into()
+}
+
+#[handler(query)]
+async fn get_lints(ctx: AppCtx, ref_id: Uuid) -> RpcResult<Vec<lints::ModelLint>> {
+    lints::get_lints(&ctx, ref_id).await.into()
+}
+
+#[handler(mutation)]
+async fn create_scratch_doc(ctx: AppCtx, content: Value) -> RpcResult<String> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        scratch::create_scratch_doc(&ctx, content).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn promote_scratch_doc(ctx: AppCtx, doc_id: String) -> RpcResult<Uuid> {
+    async {
+        maintenance::require_writable(&ctx.state).await?;
+        scratch::promote_scratch_doc(&ctx, &doc_id).await
+    }
+    .await
+    .into()
+}
+
+#[handler(mutation)]
+async fn discard_scratch_doc(ctx: AppCtx, doc_id: String) -> RpcResult<()> {
+    scratch::discard_scratch_doc(&ctx, &doc_id).await.into()
+}
+
 /// Result returned by an RPC handler.
 #[qubit::ts]
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "tag")]
 enum RpcResult<T> {
-    Ok { content: T },
-    Err { code: u16, message: String },
+    Ok {
+        content: T,
+    },
+    Err {
+        code: u16,
+        message: String,
+        localized: i18n::Message,
+    },
 }
 
 impl<T> From<AppError> for RpcResult<T> {
     fn from(error: AppError) -> Self {
-        let code = match error {
-            AppError::Invalid(_) => StatusCode::BAD_REQUEST,
-            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
-            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
-            AppError::NotFound(_) | AppError::Db(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let code = error.status_code();
         let message = error.to_string();
         match code {
             StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND => {
@@ -208,7 +888,11 @@ impl<T> From<AppError> for RpcResult<T> {
             }
             _ => {}
         }
-        RpcResult::Err { code: code.as_u16(), message }
+        RpcResult::Err {
+            code: code.as_u16(),
+            message,
+            localized: error.message(),
+        }
     }
 }
 
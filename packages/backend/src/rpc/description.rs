@@ -0,0 +1,229 @@
+//! Machine-readable description of the RPC API.
+//!
+//! The description lists every procedure with its kind, parameters, and result,
+//! where types are given as TypeScript type expressions generated by `ts-rs`,
+//! together with declarations of the named types they refer to. It is served as
+//! JSON so that clients in other languages can be generated without the
+//! TypeScript bindings.
+
+use std::any::TypeId;
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+use serde_json::Value;
+use ts_rs::{TS, TypeVisitor};
+use uuid::Uuid;
+
+use super::{RefDoc, RpcResult};
+use crate::auth::{NewPermissions, PermissionLevel, Permissions, invites};
+use crate::{
+    analysis_cache, attachments, bundle, classroom, datasets, embed, encryption, feature_flags,
+    history, jobs, lints, maintenance, moderation, presence, publications, ref_settings,
+    similarity, sql_export, user, verification,
+};
+
+/// Description of the RPC API.
+#[derive(Clone, Debug, Serialize)]
+pub struct ApiDescription {
+    /// Procedures of the API, in the order registered.
+    pub procedures: Vec<ProcedureDescription>,
+
+    /// TypeScript declarations of the named types, by name.
+    pub types: BTreeMap<String, String>,
+}
+
+/// Description of a single procedure.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProcedureDescription {
+    /// Name of the procedure.
+    pub name: &'static str,
+
+    /// Kind of procedure, either `"query"` or `"mutation"`.
+    pub kind: &'static str,
+
+    /// Parameters of the procedure, in order.
+    pub params: Vec<ParamDescription>,
+
+    /// Type of the result, which is wrapped in the `RpcResult` envelope.
+    pub result: String,
+}
+
+/// Description of a parameter of a procedure.
+#[derive(Clone, Debug, Serialize)]
+pub struct ParamDescription {
+    /// Name of the parameter.
+    pub name: &'static str,
+
+    /// Type of the parameter.
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// Collects declarations of named types reachable from the visited types.
+#[derive(Default)]
+struct DeclCollector {
+    seen: HashSet<TypeId>,
+    types: BTreeMap<String, String>,
+}
+
+impl TypeVisitor for DeclCollector {
+    fn visit<T: TS + 'static + ?Sized>(&mut self) {
+        if !self.seen.insert(TypeId::of::<T>()) {
+            return;
+        }
+        if T::output_path().is_some() {
+            self.types.insert(T::ident(), T::decl());
+        }
+        T::visit_dependencies(self);
+        T::visit_generics(self);
+    }
+}
+
+macro_rules! describe_procedures {
+    ($($kind:ident $name:ident($($param:ident: $ty:ty),*) -> $result:ty;)*) => {{
+        let mut collector = DeclCollector::default();
+        let procedures = vec![$({
+            $(collector.visit::<$ty>();)*
+            collector.visit::<RpcResult<$result>>();
+            ProcedureDescription {
+                name: stringify!($name),
+                kind: stringify!($kind),
+                params: vec![$(ParamDescription {
+                    name: stringify!($param),
+                    ty: <$ty as TS>::inline(),
+                }),*],
+                result: <$result as TS>::inline(),
+            }
+        }),*];
+        ApiDescription { procedures, types: collector.types }
+    }};
+}
+
+/// Describes the RPC API.
+///
+/// The procedures must be kept in sync with the [router](super::router).
+pub fn describe() -> ApiDescription {
+    describe_procedures! {
+        mutation new_ref(content: Value) -> Uuid;
+        mutation new_encrypted_ref(
+            content: Value,
+            encryption: encryption::EncryptionMetadata
+        ) -> Uuid;
+        query get_encryption(ref_id: Uuid) -> Option<encryption::EncryptionMetadata>;
+        query get_doc(ref_id: Uuid) -> RefDoc;
+        mutation load_snapshot(ref_id: Uuid, snapshot_id: i32) -> ();
+        mutation delete_ref(ref_id: Uuid) -> ();
+        mutation restore_ref(ref_id: Uuid) -> ();
+        query get_permissions(ref_id: Uuid) -> Permissions;
+        mutation set_permissions(ref_id: Uuid, new: NewPermissions) -> ();
+        query validate_session() -> ();
+        mutation sign_up_or_sign_in() -> ();
+        query user_by_username(username: String) -> Option<user::UserSummary>;
+        query username_status(username: String) -> user::UsernameStatus;
+        query get_active_user_profile() -> user::UserProfile;
+        mutation set_active_user_profile(user: user::UserProfile) -> ();
+        query get_user_state_doc_id() -> String;
+        query get_message_catalog(locale: String) -> std::collections::HashMap<String, String>;
+        query get_verification_status() -> verification::VerificationStatus;
+        mutation link_orcid(code: String) -> String;
+        query get_cached_analysis(
+            ref_id: Uuid,
+            key: analysis_cache::AnalysisKey
+        ) -> Option<analysis_cache::CachedAnalysis>;
+        mutation put_cached_analysis(
+            ref_id: Uuid,
+            key: analysis_cache::AnalysisKey,
+            result: Value,
+            ttl_seconds: Option<i64>
+        ) -> ();
+        mutation create_attachment(
+            ref_id: Uuid,
+            new: attachments::NewAttachment
+        ) -> attachments::AttachmentUpload;
+        mutation complete_attachment(attachment_id: Uuid) -> attachments::Attachment;
+        query list_attachments(ref_id: Uuid) -> Vec<attachments::Attachment>;
+        query get_attachment_url(attachment_id: Uuid) -> String;
+        mutation delete_attachment(attachment_id: Uuid) -> ();
+        mutation create_dataset(ref_id: Uuid, name: String, csv: String) -> datasets::Dataset;
+        query list_datasets(ref_id: Uuid) -> Vec<datasets::Dataset>;
+        query get_dataset(dataset_id: Uuid) -> datasets::Dataset;
+        query get_dataset_slice(
+            dataset_id: Uuid,
+            request: datasets::SliceRequest
+        ) -> Vec<datasets::DatasetColumn>;
+        mutation delete_dataset(dataset_id: Uuid) -> ();
+        mutation report_content(ref_id: Uuid, reason: String) -> Uuid;
+        query list_reports(
+            status: Option<moderation::ReportStatus>
+        ) -> Vec<moderation::ContentReport>;
+        mutation resolve_report(report_id: Uuid, action: moderation::ModerationAction) -> ();
+        mutation unhide_ref(ref_id: Uuid) -> ();
+        mutation create_embed_token(
+            ref_id: Uuid,
+            expires_in_seconds: Option<i64>
+        ) -> embed::NewEmbedToken;
+        query list_embed_tokens(ref_id: Uuid) -> Vec<embed::EmbedTokenInfo>;
+        mutation revoke_embed_token(token_id: Uuid) -> ();
+        query get_embedded_doc(token: String) -> embed::EmbeddedDoc;
+        query export_run_bundle(ref_id: Uuid) -> bundle::RunBundle;
+        mutation import_run_bundle(bundle: bundle::RunBundle) -> Uuid;
+        query export_sql(ref_id: Uuid, dialect: sql_export::SqlDialect) -> String;
+        query get_maintenance_mode() -> Option<maintenance::MaintenanceMode>;
+        mutation set_maintenance_mode(mode: Option<maintenance::MaintenanceMode>) -> ();
+        mutation publish_version(ref_id: Uuid, tag: String) -> publications::Publication;
+        query list_publications(ref_id: Uuid) -> Vec<publications::Publication>;
+        query get_edit_history(ref_id: Uuid) -> history::EditHistory;
+        query get_feature_flags() -> Vec<String>;
+        query list_feature_flags() -> Vec<feature_flags::FeatureFlag>;
+        mutation set_feature_flag(
+            flag: String,
+            scope: feature_flags::FeatureFlagScope,
+            enabled: Option<bool>
+        ) -> ();
+        mutation invite_by_email(
+            ref_id: Uuid,
+            email: String,
+            level: PermissionLevel
+        ) -> invites::Invite;
+        query list_invites(ref_id: Uuid) -> Vec<invites::Invite>;
+        mutation revoke_invite(invite_id: Uuid) -> ();
+        mutation open_edit_session(ref_id: Uuid) -> presence::EditSession;
+        mutation renew_edit_session(ref_id: Uuid, session_id: Uuid) -> presence::EditSession;
+        mutation close_edit_session(ref_id: Uuid, session_id: Uuid) -> ();
+        mutation provision_copies(
+            template_ref: Uuid,
+            students: Vec<String>,
+            level: PermissionLevel
+        ) -> Vec<classroom::ProvisionedCopy>;
+        query list_classroom_copies(template_ref: Uuid) -> Vec<classroom::ClassroomCopy>;
+        query get_ref_settings(ref_id: Uuid) -> ref_settings::RefSettings;
+        mutation update_ref_settings(
+            ref_id: Uuid,
+            settings: ref_settings::RefSettings
+        ) -> ref_settings::RefSettings;
+        mutation start_model_clustering(template_ref: Uuid, n_clusters: usize) -> Uuid;
+        query get_job(job_id: Uuid) -> jobs::Job;
+        query wait_for_job_progress(job_id: Uuid, after: Option<i32>) -> jobs::JobUpdate;
+        query find_similar(ref_id: Uuid, limit: usize) -> Vec<similarity::SimilarDocument>;
+        query get_lints(ref_id: Uuid) -> Vec<lints::ModelLint>;
+        mutation create_scratch_doc(content: Value) -> String;
+        mutation promote_scratch_doc(doc_id: String) -> Uuid;
+        mutation discard_scratch_doc(doc_id: String) -> ();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the description lists the procedures registered in the router.
+    #[test]
+    fn describes_all_procedures() {
+        let registered: Vec<_> = include_str!("../rpc.rs")
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix(".handler(")?.strip_suffix(')'))
+            .collect();
+        let described: Vec<_> = describe().procedures.iter().map(|proc| proc.name).collect();
+        assert_eq!(described, registered);
+    }
+}
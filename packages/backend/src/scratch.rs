@@ -0,0 +1,202 @@
+//! Ephemeral scratch documents.
+//!
+//! A scratch document is an Automerge document that has not been saved as a
+//! document ref: it has no snapshots in the database and does not appear among
+//! the user's documents. Clients edit scratch documents through the Automerge
+//! sync server like any other document. Scratch documents are registered in
+//! memory with the user who created them and are forgotten when the server
+//! restarts, unless the user promotes them to persistent refs in the meantime.
+//!
+//! The Automerge repo persists scratch documents like any other, so they are
+//! also recorded in the `scratch_docs` table until they are promoted. Their
+//! storage is deleted when they are discarded, and a periodic
+//! [sweep](sweep_scratch_docs) deletes the storage of those older than
+//! [`SCRATCH_DOC_TTL_DAYS`], including any left over from a restart.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use catcolab_document_types::automerge_json::hydrate_to_json;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::Value;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState};
+use crate::document as doc;
+
+/// Maximum number of scratch documents held for a single user.
+const MAX_SCRATCH_DOCS_PER_USER: usize = 20;
+
+/// Number of days after which scratch documents expire.
+pub const SCRATCH_DOC_TTL_DAYS: i64 = 7;
+
+/// Interval between sweeps of expired scratch documents.
+const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Registration of a scratch document.
+#[derive(Clone, Debug)]
+pub struct ScratchDoc {
+    /// ID of the user who created the scratch document.
+    pub owner: String,
+
+    /// When the scratch document was created.
+    pub created_at: DateTime<Utc>,
+}
+
+/// Type alias for the scratch documents map, keyed by Automerge document ID.
+pub type ScratchDocsMap = Arc<RwLock<HashMap<String, ScratchDoc>>>;
+
+/// Creates a scratch document with initial content, returning its document ID.
+pub async fn create_scratch_doc(ctx: &AppCtx, content: Value) -> Result<String, AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    doc::validate_content(&content)?;
+
+    let count = ctx
+        .state
+        .scratch_docs
+        .read()
+        .await
+        .values()
+        .filter(|scratch| scratch.owner == user.user_id)
+        .count();
+    if count >= MAX_SCRATCH_DOCS_PER_USER {
+        return Err(AppError::Invalid(format!(
+            "Cannot have more than {MAX_SCRATCH_DOCS_PER_USER} scratch documents"
+        )));
+    }
+
+    let automerge_doc = doc::automerge_from_json(&content)?;
+    let doc_handle = ctx.state.repo.create(automerge_doc).await?;
+
+    let doc_id = doc_handle.document_id().to_string();
+    let scratch = ScratchDoc {
+        owner: user.user_id.clone(),
+        created_at: Utc::now(),
+    };
+    register_scratch_doc(&ctx.state, &doc_id, scratch).await?;
+    Ok(doc_id)
+}
+
+/// Registers a scratch document, both in memory and in the database.
+async fn register_scratch_doc(
+    state: &AppState,
+    doc_id: &str,
+    scratch: ScratchDoc,
+) -> Result<(), AppError> {
+    let mut scratch_docs = state.scratch_docs.write().await;
+    sqlx::query(
+        "
+        INSERT INTO scratch_docs (doc_id, owner_id, created_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (doc_id) DO NOTHING
+        ",
+    )
+    .bind(doc_id)
+    .bind(&scratch.owner)
+    .bind(scratch.created_at)
+    .execute(&state.db)
+    .await?;
+    scratch_docs.insert(doc_id.to_string(), scratch);
+    Ok(())
+}
+
+/// Removes the registration of a scratch document owned by the active user.
+async fn take_scratch_doc(ctx: &AppCtx, doc_id: &str) -> Result<ScratchDoc, AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    let mut scratch_docs = ctx.state.scratch_docs.write().await;
+    match scratch_docs.get(doc_id) {
+        Some(scratch) if scratch.owner == user.user_id => {}
+        _ => return Err(AppError::NotFound(format!("scratch document {doc_id}"))),
+    }
+    sqlx::query("DELETE FROM scratch_docs WHERE doc_id = $1")
+        .bind(doc_id)
+        .execute(&ctx.state.db)
+        .await?;
+    Ok(scratch_docs.remove(doc_id).expect("scratch document should be registered"))
+}
+
+/// Promotes a scratch document to a persistent ref owned by the active user.
+///
+/// The ref is created for the scratch document itself, so connected clients
+/// continue editing the same document. Promotion is atomic: the scratch
+/// document is unregistered before the ref is created, so it cannot be promoted
+/// twice, and is registered again if creating the ref fails.
+pub async fn promote_scratch_doc(ctx: &AppCtx, doc_id: &str) -> Result<Uuid, AppError> {
+    let scratch = take_scratch_doc(ctx, doc_id).await?;
+
+    let result = async {
+        let parsed_id: samod::DocumentId = doc_id
+            .parse()
+            .map_err(|_| AppError::Invalid("Invalid document ID".to_string()))?;
+        let doc_handle = ctx
+            .state
+            .repo
+            .find(parsed_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("document {doc_id}")))?;
+        let content = doc_handle.with_document(|doc| hydrate_to_json(&doc.hydrate(None)));
+        doc::validate_content(&content)?;
+        doc::create_ref_for_doc(ctx.clone(), doc_handle, content).await
+    }
+    .await;
+
+    if result.is_err() {
+        register_scratch_doc(&ctx.state, doc_id, scratch).await?;
+    }
+    result
+}
+
+/// Discards a scratch document owned by the active user.
+///
+/// The document can no longer be promoted, and its storage is deleted.
+pub async fn discard_scratch_doc(ctx: &AppCtx, doc_id: &str) -> Result<(), AppError> {
+    take_scratch_doc(ctx, doc_id).await?;
+    delete_storage(&ctx.state, &[doc_id.to_string()]).await
+}
+
+/// Deletes the expired scratch documents, returning how many were deleted.
+///
+/// Holds the registrations while sweeping, so that a document cannot be
+/// promoted as its storage is deleted.
+pub async fn sweep_scratch_docs(state: &AppState) -> Result<usize, AppError> {
+    let mut scratch_docs = state.scratch_docs.write().await;
+    let cutoff = Utc::now() - TimeDelta::days(SCRATCH_DOC_TTL_DAYS);
+    let doc_ids: Vec<String> =
+        sqlx::query_scalar("DELETE FROM scratch_docs WHERE created_at < $1 RETURNING doc_id")
+            .bind(cutoff)
+            .fetch_all(&state.db)
+            .await?;
+    for doc_id in &doc_ids {
+        scratch_docs.remove(doc_id);
+    }
+    delete_storage(state, &doc_ids).await?;
+    Ok(doc_ids.len())
+}
+
+/// Sweeps the expired scratch documents periodically, for as long as the
+/// server runs.
+pub async fn run_scratch_sweeper(state: AppState) {
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        match sweep_scratch_docs(&state).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Deleted {n} expired scratch documents"),
+            Err(err) => tracing::error!("Failed to sweep scratch documents: {err}"),
+        }
+    }
+}
+
+/// Deletes the Automerge storage of documents, which is keyed by document ID.
+async fn delete_storage(state: &AppState, doc_ids: &[String]) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM storage WHERE key[1] = ANY($1)")
+        .bind(doc_ids)
+        .execute(&state.db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,156 @@
+//! Search for documents similar to a given one.
+//!
+//! When a snapshot of a model or diagram is saved, the feature vector of its
+//! generating graph, as computed by `catlog`, is stored in the
+//! `snapshot_features` table. Documents are then compared by the Euclidean
+//! distance between the feature vectors of their current snapshots. Features
+//! are stored by name, since documents using different types have feature
+//! vectors of different lengths, and a feature missing from one document is
+//! taken to be zero there. Only documents of the same theory are compared.
+
+use std::collections::BTreeMap;
+
+use catcolab_document_types::VersionedDocument;
+use catlog::one::graph_features::FeatureSpec;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError, AppState};
+use crate::auth::{self, PermissionLevel};
+use crate::document as doc;
+use crate::svg_export;
+
+/// Number of eigenvalues of the adjacency matrix included in the features.
+const N_EIGENVALUES: usize = 4;
+
+/// Maximum number of similar documents returned.
+const MAX_RESULTS: usize = 50;
+
+/// Named features of a document.
+type Features = BTreeMap<String, f32>;
+
+/// A document similar to another one.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct SimilarDocument {
+    /// ID of the document.
+    #[serde(rename = "refId")]
+    pub ref_id: Uuid,
+
+    /// Name of the document.
+    pub name: Option<String>,
+
+    /// Distance between the feature vectors of the documents.
+    pub distance: f32,
+}
+
+/// Computes the features of a document, if it is a model or diagram.
+fn document_features(content: &Value) -> Option<Features> {
+    let document: VersionedDocument = serde_json::from_value(content.clone()).ok()?;
+    let graph = svg_export::document_graph(&document.to_current())?;
+    let spec = FeatureSpec::from_graphs([&graph], N_EIGENVALUES);
+    Some(spec.names().into_iter().zip(graph.features(&spec)).collect())
+}
+
+/// Gets the theory of a model document.
+fn document_theory(content: &Value) -> Option<String> {
+    content.get("theory")?.as_str().map(String::from)
+}
+
+/// Stores the features of a snapshot, if it is of a model or diagram.
+pub async fn store_snapshot_features(
+    state: &AppState,
+    snapshot_id: i32,
+    content: &Value,
+) -> Result<(), AppError> {
+    let Some(features) = document_features(content) else {
+        return Ok(());
+    };
+    sqlx::query(
+        "
+        INSERT INTO snapshot_features (snapshot_id, theory, features) VALUES ($1, $2, $3)
+        ON CONFLICT (snapshot_id) DO NOTHING
+        ",
+    )
+    .bind(snapshot_id)
+    .bind(document_theory(content))
+    .bind(serde_json::to_value(features)?)
+    .execute(&state.db)
+    .await?;
+    Ok(())
+}
+
+/// Finds the documents most similar to a given one, nearest first.
+///
+/// Only documents that the user can read are returned. Documents not saved
+/// since features were introduced are not found.
+pub async fn find_similar(
+    ctx: &AppCtx,
+    ref_id: Uuid,
+    limit: usize,
+) -> Result<Vec<SimilarDocument>, AppError> {
+    auth::authorize(ctx, ref_id, PermissionLevel::Read).await?;
+    let content = doc::get_content(ctx.state.clone(), ref_id).await?;
+    let features = document_features(&content).ok_or_else(|| {
+        AppError::Invalid("Only model and diagram documents can be compared".into())
+    })?;
+
+    let user_id = ctx.user.as_ref().map(|user| user.user_id.as_str());
+    let rows = sqlx::query(
+        "
+        SELECT refs.id, snapshots.content->>'name' AS name, f.features
+        FROM refs
+        JOIN snapshots ON snapshots.id = refs.current_snapshot
+        JOIN snapshot_features f ON f.snapshot_id = refs.current_snapshot
+        WHERE refs.id <> $1 AND refs.deleted_at IS NULL
+            AND f.theory IS NOT DISTINCT FROM $2
+            AND get_max_permission($3, refs.id) >= 'read'::permission_level
+        ",
+    )
+    .bind(ref_id)
+    .bind(document_theory(&content))
+    .bind(user_id)
+    .fetch_all(&ctx.state.db)
+    .await?;
+
+    let mut similar = rows
+        .iter()
+        .map(|row| {
+            let other: Features = serde_json::from_value(row.get("features"))?;
+            Ok(SimilarDocument {
+                ref_id: row.get("id"),
+                name: row.get("name"),
+                distance: distance(&features, &other),
+            })
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+    similar.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    similar.truncate(limit.min(MAX_RESULTS));
+    Ok(similar)
+}
+
+/// Euclidean distance between named features, missing features being zero.
+fn distance(x: &Features, y: &Features) -> f32 {
+    let value = |features: &Features, name: &String| features.get(name).copied().unwrap_or(0.0);
+    x.keys()
+        .chain(y.keys().filter(|name| !x.contains_key(*name)))
+        .map(|name| (value(x, name) - value(y, name)).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_with_missing_features() {
+        let x: Features = [("nodes".into(), 3.0), ("edges".into(), 2.0)].into_iter().collect();
+        let y: Features = [("nodes".into(), 3.0), ("loops".into(), 2.0)].into_iter().collect();
+        assert_eq!(distance(&x, &x), 0.0);
+        assert_eq!(distance(&x, &y), 8f32.sqrt());
+        assert_eq!(distance(&y, &x), distance(&x, &y));
+    }
+}
@@ -0,0 +1,346 @@
+//! Export of schema documents as SQL.
+//!
+//! A model of the theory of schemas describes a relational database: each
+//! entity becomes a table with an integer primary key, each mapping between
+//! entities becomes a column holding a foreign key, and each attribute becomes
+//! a column whose SQL type is guessed from the name of its attribute type.
+//! Path equations in the schema are not expressible as table constraints and
+//! are omitted from the DDL.
+//!
+//! Foreign keys can form cycles, so for dialects that check the referenced
+//! table at creation time they are added by `ALTER TABLE` statements after all
+//! tables have been created. SQLite does not support adding constraints to an
+//! existing table but also does not check references on creation, so there the
+//! foreign keys are declared inline.
+
+use std::collections::{HashMap, HashSet};
+
+use catcolab_document_types::current::{
+    Document, ModelDocumentContent, ModelJudgment, MorType, NotebookCell, Ob, ObType,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError};
+use crate::auth::{self, PermissionLevel};
+use crate::document as doc;
+
+/// ID of the theory of schemas.
+pub const SCHEMA_THEORY: &str = "simple-schema";
+
+/// Name of the primary key column of each table.
+const ID_COLUMN: &str = "id";
+
+/// Dialect of SQL to generate.
+#[qubit::ts]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SqlDialect {
+    /// PostgreSQL.
+    #[default]
+    #[serde(rename = "postgres")]
+    Postgres,
+
+    /// MySQL.
+    #[serde(rename = "mysql")]
+    MySql,
+
+    /// SQLite.
+    #[serde(rename = "sqlite")]
+    Sqlite,
+}
+
+impl SqlDialect {
+    fn quote(self, ident: &str) -> String {
+        match self {
+            SqlDialect::MySql => format!("`{}`", ident.replace('`', "``")),
+            SqlDialect::Postgres | SqlDialect::Sqlite => {
+                format!("\"{}\"", ident.replace('"', "\"\""))
+            }
+        }
+    }
+
+    fn key_type(self) -> &'static str {
+        match self {
+            SqlDialect::MySql => "BIGINT",
+            SqlDialect::Postgres | SqlDialect::Sqlite => "INTEGER",
+        }
+    }
+
+    /// Guesses the SQL type of an attribute from the name of its type.
+    fn attr_type(self, type_name: &str) -> &'static str {
+        match type_name.to_lowercase().as_str() {
+            "int" | "integer" | "nat" | "natural" => self.key_type(),
+            "float" | "double" | "real" | "number" => match self {
+                SqlDialect::Postgres => "DOUBLE PRECISION",
+                SqlDialect::MySql => "DOUBLE",
+                SqlDialect::Sqlite => "REAL",
+            },
+            "bool" | "boolean" => "BOOLEAN",
+            "date" => "DATE",
+            "datetime" | "timestamp" => "TIMESTAMP",
+            _ => "TEXT",
+        }
+    }
+}
+
+/// Column of a table derived from a schema.
+struct Column {
+    name: String,
+    sql_type: &'static str,
+    references: Option<String>,
+}
+
+/// Table derived from an entity of a schema.
+struct Table {
+    name: String,
+    columns: Vec<Column>,
+}
+
+/// Exports the current content of a schema document as SQL DDL.
+pub async fn export_sql(
+    ctx: &AppCtx,
+    ref_id: Uuid,
+    dialect: SqlDialect,
+) -> Result<String, AppError> {
+    auth::authorize(ctx, ref_id, PermissionLevel::Read).await?;
+    let content = doc::get_content(ctx.state.clone(), ref_id).await?;
+    let document: catcolab_document_types::VersionedDocument = serde_json::from_value(content)
+        .map_err(|e| AppError::Invalid(format!("Failed to parse document: {e}")))?;
+    match document.to_current() {
+        Document::Model(model) => schema_ddl(&model, dialect),
+        _ => Err(AppError::Invalid("Only model documents can be exported as SQL".into())),
+    }
+}
+
+/// Generates SQL DDL from a model of the theory of schemas.
+pub fn schema_ddl(model: &ModelDocumentContent, dialect: SqlDialect) -> Result<String, AppError> {
+    if model.theory != SCHEMA_THEORY {
+        return Err(AppError::Invalid(format!(
+            "Only schemas can be exported as SQL, not models of theory `{}`",
+            model.theory
+        )));
+    }
+    let judgments: Vec<_> = model
+        .notebook
+        .cell_order
+        .iter()
+        .filter_map(|id| match model.notebook.cell_contents.get(id)? {
+            NotebookCell::Formal { content, .. } => Some(content),
+            _ => None,
+        })
+        .collect();
+
+    let entity = ObType::Basic("Entity".into());
+    let attr_type = ObType::Basic("AttrType".into());
+    let mut tables = Vec::new();
+    let mut table_index = HashMap::new();
+    let mut type_names = HashMap::new();
+    for judgment in &judgments {
+        match judgment {
+            ModelJudgment::Object(decl) if decl.ob_type == entity => {
+                let name = required_name(&decl.name, "entity")?;
+                table_index.insert(decl.id.to_string(), tables.len());
+                tables.push(Table {
+                    name,
+                    columns: vec![Column {
+                        name: ID_COLUMN.into(),
+                        sql_type: dialect.key_type(),
+                        references: None,
+                    }],
+                });
+            }
+            ModelJudgment::Object(decl) if decl.ob_type == attr_type => {
+                type_names.insert(decl.id.to_string(), decl.name.clone());
+            }
+            ModelJudgment::Instantiation(_) => {
+                return Err(AppError::Invalid(
+                    "Schemas with instantiated models cannot be exported as SQL".into(),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    let fk_type = MorType::Hom(Box::new(entity));
+    let attr = MorType::Basic("Attr".into());
+    for judgment in &judgments {
+        let ModelJudgment::Morphism(decl) = judgment else {
+            continue;
+        };
+        if decl.mor_type != fk_type && decl.mor_type != attr {
+            continue;
+        }
+        let name = required_name(&decl.name, "column")?;
+        let (Some(Ob::Basic(dom)), Some(Ob::Basic(cod))) = (&decl.dom, &decl.cod) else {
+            return Err(AppError::Invalid(format!("Column `{name}` is missing its endpoints")));
+        };
+        let &dom = table_index
+            .get(dom)
+            .ok_or_else(|| AppError::Invalid(format!("Column `{name}` has no table")))?;
+        let column = if decl.mor_type == fk_type {
+            let &cod = table_index.get(cod).ok_or_else(|| {
+                AppError::Invalid(format!("Foreign key `{name}` refers to no table"))
+            })?;
+            Column {
+                name,
+                sql_type: dialect.key_type(),
+                references: Some(tables[cod].name.clone()),
+            }
+        } else {
+            let type_name = type_names.get(cod).ok_or_else(|| {
+                AppError::Invalid(format!("Attribute `{name}` has no attribute type"))
+            })?;
+            Column {
+                name,
+                sql_type: dialect.attr_type(type_name),
+                references: None,
+            }
+        };
+        tables[dom].columns.push(column);
+    }
+
+    let mut seen = HashSet::new();
+    for table in &tables {
+        if !seen.insert(&table.name) {
+            return Err(AppError::Invalid(format!("Duplicate table `{}`", table.name)));
+        }
+        let mut columns = HashSet::new();
+        for column in &table.columns {
+            if !columns.insert(&column.name) {
+                return Err(AppError::Invalid(format!(
+                    "Duplicate column `{}` in table `{}`",
+                    column.name, table.name
+                )));
+            }
+        }
+    }
+    Ok(write_ddl(&tables, dialect))
+}
+
+fn required_name(name: &str, kind: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        Err(AppError::Invalid(format!("Every {kind} must be named to export as SQL")))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+fn write_ddl(tables: &[Table], dialect: SqlDialect) -> String {
+    let q = |ident: &str| dialect.quote(ident);
+    let inline_keys = dialect == SqlDialect::Sqlite;
+    let mut sql = String::new();
+    for table in tables {
+        let mut lines: Vec<_> = table
+            .columns
+            .iter()
+            .map(|column| {
+                let constraint = if column.name == ID_COLUMN {
+                    " PRIMARY KEY"
+                } else {
+                    " NOT NULL"
+                };
+                format!("  {} {}{}", q(&column.name), column.sql_type, constraint)
+            })
+            .collect();
+        if inline_keys {
+            for column in &table.columns {
+                if let Some(target) = &column.references {
+                    lines.push(format!(
+                        "  FOREIGN KEY ({}) REFERENCES {} ({})",
+                        q(&column.name),
+                        q(target),
+                        q(ID_COLUMN)
+                    ));
+                }
+            }
+        }
+        sql.push_str(&format!("CREATE TABLE {} (\n{}\n);\n\n", q(&table.name), lines.join(",\n")));
+    }
+    if !inline_keys {
+        for table in tables {
+            for column in &table.columns {
+                if let Some(target) = &column.references {
+                    sql.push_str(&format!(
+                        "ALTER TABLE {} ADD FOREIGN KEY ({}) REFERENCES {} ({});\n",
+                        q(&table.name),
+                        q(&column.name),
+                        q(target),
+                        q(ID_COLUMN)
+                    ));
+                }
+            }
+        }
+    }
+    sql.trim_end().to_string() + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> ModelDocumentContent {
+        let entity = json!({ "tag": "Basic", "content": "Entity" });
+        let ob = |id: u128, name: &str, ob_type: &str| {
+            let id = Uuid::from_u128(id);
+            json!({ "tag": "formal", "id": id, "content": {
+                "tag": "object", "name": name, "id": id,
+                "obType": { "tag": "Basic", "content": ob_type },
+            }})
+        };
+        let mor = |id: u128, name: &str, mor_type: &serde_json::Value, dom: u128, cod: u128| {
+            let id = Uuid::from_u128(id);
+            json!({ "tag": "formal", "id": id, "content": {
+                "tag": "morphism", "name": name, "id": id, "morType": mor_type,
+                "dom": { "tag": "Basic", "content": Uuid::from_u128(dom) },
+                "cod": { "tag": "Basic", "content": Uuid::from_u128(cod) },
+            }})
+        };
+        let cells = vec![
+            ob(1, "Person", "Entity"),
+            ob(2, "Dept", "Entity"),
+            ob(3, "Int", "AttrType"),
+            mor(4, "works_in", &json!({ "tag": "Hom", "content": entity }), 1, 2),
+            mor(5, "manager", &json!({ "tag": "Hom", "content": entity }), 2, 1),
+            mor(6, "age", &json!({ "tag": "Basic", "content": "Attr" }), 1, 3),
+        ];
+        let order: Vec<_> = cells.iter().map(|cell| cell["id"].clone()).collect();
+        let contents: serde_json::Map<_, _> = cells
+            .into_iter()
+            .map(|cell| (cell["id"].as_str().unwrap().into(), cell))
+            .collect();
+        serde_json::from_value(json!({
+            "name": "Company",
+            "theory": SCHEMA_THEORY,
+            "notebook": { "cellContents": contents, "cellOrder": order },
+            "version": "2",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn schema_to_sql() {
+        let model = schema();
+        let sql = schema_ddl(&model, SqlDialect::Postgres).unwrap();
+        assert!(sql.starts_with("CREATE TABLE \"Person\" (\n  \"id\" INTEGER PRIMARY KEY,\n"));
+        assert!(sql.contains("  \"age\" INTEGER NOT NULL\n"));
+        assert!(sql.ends_with(
+            "ALTER TABLE \"Dept\" ADD FOREIGN KEY (\"manager\") REFERENCES \"Person\" (\"id\");\n"
+        ));
+
+        let sql = schema_ddl(&model, SqlDialect::Sqlite).unwrap();
+        assert!(sql.contains("  FOREIGN KEY (\"works_in\") REFERENCES \"Dept\" (\"id\")\n"));
+        assert!(!sql.contains("ALTER TABLE"));
+
+        let sql = schema_ddl(&model, SqlDialect::MySql).unwrap();
+        assert!(sql.contains("`works_in` BIGINT NOT NULL"));
+    }
+
+    #[test]
+    fn non_schema_rejected() {
+        let mut model = schema();
+        model.theory = "causal-loop".into();
+        assert!(schema_ddl(&model, SqlDialect::Postgres).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//! Export of model and diagram documents as SVG images.
+//!
+//! The generating graph of a model or diagram, consisting of its declared
+//! objects and the morphisms between basic objects, is rendered on the server
+//! by `catlog` without a headless browser. Layouts computed by the frontend are
+//! not stored with documents, so nodes are placed automatically.
+
+use catcolab_document_types::current::{
+    DiagramJudgment, Document, ModelJudgment, MorType, Ob, ObType,
+};
+use catlog::one::graph_io::{LabeledEdge, LabeledGraph, LabeledNode};
+use catlog::one::tikz_cd::Layout;
+use uuid::Uuid;
+
+use crate::app::{AppCtx, AppError};
+use crate::auth::{self, PermissionLevel};
+use crate::document as doc;
+
+/// Width and height of thumbnails, in pixels.
+pub const THUMBNAIL_SIZE: (f32, f32) = (320.0, 240.0);
+
+/// Exports the current content of a model or diagram document as SVG.
+///
+/// The image has its natural size unless a size is given, as for thumbnails.
+pub async fn export_svg(
+    ctx: &AppCtx,
+    ref_id: Uuid,
+    size: Option<(f32, f32)>,
+) -> Result<String, AppError> {
+    auth::authorize(ctx, ref_id, PermissionLevel::Read).await?;
+    let content = doc::get_content(ctx.state.clone(), ref_id).await?;
+    let document: catcolab_document_types::VersionedDocument = serde_json::from_value(content)
+        .map_err(|e| AppError::Invalid(format!("Failed to parse document: {e}")))?;
+    let graph = document_graph(&document.to_current()).ok_or_else(|| {
+        AppError::Invalid("Only model and diagram documents can be exported as SVG".into())
+    })?;
+    Ok(graph.to_svg(&Layout::new(), size))
+}
+
+/// Gets the generating graph of a model or diagram document.
+pub fn document_graph(document: &Document) -> Option<LabeledGraph> {
+    let mut graph = LabeledGraph::default();
+    match document {
+        Document::Model(model) => {
+            for judgment in model.notebook.formal_content() {
+                match judgment {
+                    ModelJudgment::Object(decl) => {
+                        graph.nodes.push(node(decl.id, &decl.name, &decl.ob_type));
+                    }
+                    ModelJudgment::Morphism(decl) => graph.edges.extend(edge(
+                        decl.id,
+                        &decl.name,
+                        &decl.mor_type,
+                        (&decl.dom, &decl.cod),
+                    )),
+                    _ => {}
+                }
+            }
+        }
+        Document::Diagram(diagram) => {
+            for judgment in diagram.notebook.formal_content() {
+                match judgment {
+                    DiagramJudgment::Object(decl) => {
+                        graph.nodes.push(node(decl.id, &decl.name, &decl.ob_type));
+                    }
+                    DiagramJudgment::Morphism(decl) => graph.edges.extend(edge(
+                        decl.id,
+                        &decl.name,
+                        &decl.mor_type,
+                        (&decl.dom, &decl.cod),
+                    )),
+                    _ => {}
+                }
+            }
+        }
+        Document::Analysis(_) => return None,
+    }
+    Some(graph)
+}
+
+fn node(id: Uuid, name: &str, ob_type: &ObType) -> LabeledNode {
+    LabeledNode {
+        id: id.to_string(),
+        label: Some(name.to_string()),
+        node_type: match ob_type {
+            ObType::Basic(name) => Some(name.clone()),
+            _ => None,
+        },
+    }
+}
+
+/// Makes an edge from a morphism declaration, if it is between basic objects.
+fn edge(
+    id: Uuid,
+    name: &str,
+    mor_type: &MorType,
+    endpoints: (&Option<Ob>, &Option<Ob>),
+) -> Option<LabeledEdge> {
+    let (Some(Ob::Basic(source)), Some(Ob::Basic(target))) = endpoints else {
+        return None;
+    };
+    Some(LabeledEdge {
+        id: id.to_string(),
+        source: source.clone(),
+        target: target.clone(),
+        label: Some(name.to_string()),
+        edge_type: match mor_type {
+            MorType::Basic(name) => Some(name.clone()),
+            MorType::Hom(_) => Some("Hom".into()),
+            _ => None,
+        },
+    })
+}
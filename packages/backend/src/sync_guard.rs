@@ -0,0 +1,194 @@
+//! Enforcement of read-only edit sessions on document sync.
+//!
+//! Clients edit documents by sending Automerge sync messages over the sync
+//! websocket, which carries the messages for many documents at once. A
+//! [guard](SyncGuard) on each connection drops the sync messages with which a
+//! user would write to a ref that the [presence
+//! tracker](crate::presence::PresenceTracker) does not let them write to.
+//!
+//! A sync message writes to a document when its sender has changes that the
+//! server does not, which shows in the heads of the sender's copy of the
+//! document. Such messages are dropped whole, so that the changes are neither
+//! applied nor relayed to other clients. Messages from a read-only client that
+//! is up to date still pass, so that the client keeps receiving changes.
+
+use std::collections::HashMap;
+
+use automerge::Automerge;
+use automerge::sync::Message;
+use futures_util::stream::{self, Stream, StreamExt};
+use samod::DocumentId;
+use uuid::Uuid;
+
+use crate::app::AppState;
+
+/// Message of the Automerge repo protocol that syncs a document.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SyncFrame {
+    /// ID of the document being synced.
+    pub document_id: String,
+
+    /// Encoded Automerge sync message.
+    pub data: Vec<u8>,
+}
+
+impl SyncFrame {
+    /// Parses a frame of the protocol.
+    ///
+    /// Returns `None` unless the frame is a sync message or a request for a
+    /// document, both of which carry a sync message.
+    pub fn parse(frame: &[u8]) -> Option<Self> {
+        let mut decoder = minicbor::Decoder::new(frame);
+        let len = decoder.map().ok()??;
+        let (mut kind, mut document_id, mut data) = (None, None, None);
+        for _ in 0..len {
+            match decoder.str().ok()? {
+                "type" => kind = Some(decoder.str().ok()?),
+                "documentId" => document_id = Some(decoder.str().ok()?.to_string()),
+                "data" => data = Some(decoder.bytes().ok()?.to_vec()),
+                _ => decoder.skip().ok()?,
+            }
+        }
+        if !matches!(kind, Some("sync" | "request")) {
+            return None;
+        }
+        Some(Self { document_id: document_id?, data: data? })
+    }
+
+    /// Whether the message writes to the document, given the server's copy.
+    ///
+    /// Messages that cannot be decoded are treated as writes.
+    pub fn writes_to(&self, doc: &Automerge) -> bool {
+        match Message::decode(&self.data) {
+            Ok(message) => !doc.get_missing_deps(&message.heads).is_empty(),
+            Err(_) => true,
+        }
+    }
+}
+
+/// Guard on the sync connection of a user.
+pub struct SyncGuard {
+    state: AppState,
+    user_id: Option<String>,
+
+    /// Refs whose head is the document with a given ID, or `None` for
+    /// documents that are not the head of a ref, such as user states.
+    refs: HashMap<String, Option<Uuid>>,
+}
+
+impl SyncGuard {
+    /// Creates a guard for a connection of a user, or of an anonymous client.
+    pub fn new(state: AppState, user_id: Option<String>) -> Self {
+        Self { state, user_id, refs: HashMap::new() }
+    }
+
+    /// Whether a frame received from the client should be passed on to the repo.
+    pub async fn admit(&mut self, frame: &[u8]) -> bool {
+        let Some(sync) = SyncFrame::parse(frame) else {
+            return true;
+        };
+        let Some(ref_id) = self.ref_for(&sync.document_id).await else {
+            return true;
+        };
+        if self.state.presence.may_write(ref_id, self.user_id.as_deref()) {
+            return true;
+        }
+        let Ok(doc_id) = sync.document_id.parse::<DocumentId>() else {
+            return true;
+        };
+        let Ok(Some(doc_handle)) = self.state.repo.find(doc_id).await else {
+            return true;
+        };
+        let writes = doc_handle.with_document(|doc| sync.writes_to(doc));
+        if writes {
+            tracing::debug!("Dropped changes to ref {ref_id} from a read-only client");
+        }
+        !writes
+    }
+
+    /// Filters the frames received from the client.
+    pub fn filter<S, E>(self, frames: S) -> impl Stream<Item = Result<Vec<u8>, E>> + Send
+    where
+        S: Stream<Item = Result<Vec<u8>, E>> + Send + 'static,
+        E: Send,
+    {
+        stream::unfold((Box::pin(frames), self), |(mut frames, mut guard)| async move {
+            loop {
+                let item = frames.next().await?;
+                if let Ok(frame) = &item
+                    && !guard.admit(frame).await
+                {
+                    continue;
+                }
+                return Some((item, (frames, guard)));
+            }
+        })
+    }
+
+    /// Gets the ref whose head is a document, caching the result.
+    async fn ref_for(&mut self, document_id: &str) -> Option<Uuid> {
+        if let Some(ref_id) = self.refs.get(document_id) {
+            return *ref_id;
+        }
+        let result = sqlx::query_scalar("SELECT id FROM refs WHERE doc_id = $1")
+            .bind(document_id)
+            .fetch_optional(&self.state.db)
+            .await;
+        match result {
+            Ok(ref_id) => {
+                self.refs.insert(document_id.to_string(), ref_id);
+                ref_id
+            }
+            Err(err) => {
+                tracing::error!("Failed to look up ref of document {document_id}: {err}");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use automerge::sync::{self, SyncDoc};
+    use automerge::transaction::Transactable;
+    use automerge::{AutomergeError, ROOT};
+
+    use super::*;
+
+    fn frame(kind: &str, document_id: &str, data: &[u8]) -> Vec<u8> {
+        let mut encoder = minicbor::Encoder::new(Vec::new());
+        encoder.map(4).unwrap();
+        encoder.str("type").unwrap().str(kind).unwrap();
+        encoder.str("senderId").unwrap().str("client").unwrap();
+        encoder.str("documentId").unwrap().str(document_id).unwrap();
+        encoder.str("data").unwrap().bytes(data).unwrap();
+        encoder.into_writer()
+    }
+
+    fn sync_message(doc: &Automerge) -> Vec<u8> {
+        doc.generate_sync_message(&mut sync::State::new()).unwrap().encode()
+    }
+
+    #[test]
+    fn read_only_client_cannot_write() {
+        let mut server = Automerge::new();
+        server
+            .transact::<_, _, AutomergeError>(|tx| tx.put(ROOT, "name", "model"))
+            .unwrap();
+        let reader = server.fork();
+        let mut writer = server.fork();
+        writer
+            .transact::<_, _, AutomergeError>(|tx| tx.put(ROOT, "name", "edit"))
+            .unwrap();
+
+        let sync = SyncFrame::parse(&frame("sync", "doc", &sync_message(&reader))).unwrap();
+        assert_eq!(sync.document_id, "doc");
+        assert!(!sync.writes_to(&server));
+
+        let sync = SyncFrame::parse(&frame("request", "doc", &sync_message(&writer))).unwrap();
+        assert!(sync.writes_to(&server));
+
+        assert_eq!(SyncFrame::parse(&frame("ephemeral", "doc", b"")), None);
+        assert_eq!(SyncFrame::parse(b"not cbor"), None);
+    }
+}
@@ -2,13 +2,18 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use super::app::{AppCtx, AppError, AppState};
+use crate::auth::invites;
 use crate::user_state_updates::update_profile_for_users;
 
 /// Notify the backend that a user has signed up or signed in.
+///
+/// Any pending invites for the user's verified email address are claimed in
+/// the same transaction.
 pub async fn sign_up_or_sign_in(ctx: AppCtx) -> Result<(), AppError> {
     let Some(user) = ctx.user else {
         return Err(AppError::Unauthorized);
     };
+    let mut transaction = ctx.state.db.begin().await?;
     let query = sqlx::query!(
         "
         INSERT INTO users(id, created, signed_in)
@@ -18,7 +23,11 @@ pub async fn sign_up_or_sign_in(ctx: AppCtx) -> Result<(), AppError> {
         ",
         user.user_id,
     );
-    query.execute(&ctx.state.db).await?;
+    query.execute(&mut *transaction).await?;
+    let claimed = invites::claim_invites(&mut *transaction, &user).await?;
+    transaction.commit().await?;
+
+    invites::after_claiming_invites(&ctx.state, &claimed).await;
     Ok(())
 }
 
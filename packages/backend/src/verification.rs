@@ -0,0 +1,194 @@
+//! Verification of user identities.
+//!
+//! Making a document public requires the user to be verified, which helps to
+//! keep spam out of the public gallery. A user becomes verified either by
+//! verifying their email address, a flow handled by Firebase and reported in the
+//! claims of the ID token, or by linking an ORCID iD through ORCID's OAuth
+//! authorization code flow. Verification is recorded in the `verified` flag on
+//! the `users` table and is never revoked.
+
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+
+use crate::app::{AppCtx, AppError, AppState};
+
+/// Configuration of an ORCID OAuth client.
+#[derive(Clone, Debug)]
+pub struct OrcidConfig {
+    /// Base URL of the ORCID service, such as `https://orcid.org`.
+    pub base_url: String,
+
+    /// Client ID of the registered application.
+    pub client_id: String,
+
+    /// Client secret of the registered application.
+    pub client_secret: String,
+
+    /// Redirect URI registered with ORCID and used in the authorization request.
+    pub redirect_uri: String,
+}
+
+impl OrcidConfig {
+    /// Reads the configuration from the environment, if it is fully specified.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            base_url: dotenvy::var("ORCID_URL").unwrap_or("https://orcid.org".to_string()),
+            client_id: dotenvy::var("ORCID_CLIENT_ID").ok()?,
+            client_secret: dotenvy::var("ORCID_CLIENT_SECRET").ok()?,
+            redirect_uri: dotenvy::var("ORCID_REDIRECT_URI").ok()?,
+        })
+    }
+
+    /// URL of the endpoint exchanging authorization codes for access tokens.
+    fn token_url(&self) -> String {
+        format!("{}/oauth/token", self.base_url.trim_end_matches('/'))
+    }
+
+    /// Form-encoded body of a request to exchange an authorization code.
+    fn token_request_body(&self, code: &str) -> String {
+        let mut url = reqwest::Url::parse("http://localhost").expect("valid URL");
+        url.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("client_secret", &self.client_secret)
+            .append_pair("grant_type", "authorization_code")
+            .append_pair("code", code)
+            .append_pair("redirect_uri", &self.redirect_uri);
+        url.query().unwrap_or_default().to_string()
+    }
+}
+
+/// Response from ORCID's token endpoint, of which only the iD is used.
+#[derive(Deserialize)]
+struct OrcidTokenResponse {
+    orcid: String,
+}
+
+/// Verification status of a user.
+#[qubit::ts]
+#[derive(Clone, Debug, Serialize)]
+pub struct VerificationStatus {
+    /// Whether the user is verified.
+    pub verified: bool,
+
+    /// The user's linked ORCID iD, if any.
+    pub orcid: Option<String>,
+}
+
+/// Records that the active user is verified if Firebase reports their email
+/// address as verified.
+pub async fn record_email_verification(ctx: &AppCtx) -> Result<(), AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    if user.email_verified == Some(true) {
+        sqlx::query("UPDATE users SET verified = TRUE WHERE id = $1 AND NOT verified")
+            .bind(&user.user_id)
+            .execute(&ctx.state.db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Gets the verification status of the active user.
+pub async fn verification_status(ctx: &AppCtx) -> Result<VerificationStatus, AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    let row = sqlx::query("SELECT verified, orcid FROM users WHERE id = $1")
+        .bind(&user.user_id)
+        .fetch_optional(&ctx.state.db)
+        .await?;
+    Ok(match row {
+        Some(row) => VerificationStatus {
+            verified: row.get("verified"),
+            orcid: row.get("orcid"),
+        },
+        None => VerificationStatus { verified: false, orcid: None },
+    })
+}
+
+/// Requires the active user to be verified.
+pub async fn require_verified(ctx: &AppCtx) -> Result<(), AppError> {
+    if verification_status(ctx).await?.verified {
+        Ok(())
+    } else {
+        Err(AppError::Unverified)
+    }
+}
+
+/// Links an ORCID iD to the active user, verifying them.
+///
+/// The authorization code is obtained by the client from ORCID's authorization
+/// endpoint and is exchanged here for the user's authenticated iD.
+pub async fn link_orcid(ctx: &AppCtx, code: &str) -> Result<String, AppError> {
+    let Some(user) = &ctx.user else {
+        return Err(AppError::Unauthorized);
+    };
+    let config = orcid_config(&ctx.state)?;
+
+    let response = ctx
+        .state
+        .http_client
+        .post(config.token_url())
+        .header(http::header::ACCEPT, "application/json")
+        .header(http::header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(config.token_request_body(code))
+        .send()
+        .await
+        .map_err(|e| AppError::Invalid(format!("Failed to contact ORCID: {e}")))?;
+    if !response.status().is_success() {
+        return Err(AppError::Invalid(format!(
+            "ORCID rejected the authorization code with status {}",
+            response.status()
+        )));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| AppError::Invalid(format!("Failed to read ORCID response: {e}")))?;
+    let OrcidTokenResponse { orcid } = serde_json::from_str(&body)?;
+
+    let result = sqlx::query("UPDATE users SET orcid = $2, verified = TRUE WHERE id = $1")
+        .bind(&user.user_id)
+        .bind(&orcid)
+        .execute(&ctx.state.db)
+        .await;
+    match result {
+        Ok(result) if result.rows_affected() == 0 => {
+            Err(AppError::NotFound(format!("user {}", user.user_id)))
+        }
+        Ok(_) => Ok(orcid),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Err(AppError::Invalid("ORCID iD is already linked to another account".to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn orcid_config(state: &AppState) -> Result<&OrcidConfig, AppError> {
+    state
+        .orcid
+        .as_ref()
+        .ok_or_else(|| AppError::Invalid("ORCID linking is not configured".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orcid_token_request() {
+        let config = OrcidConfig {
+            base_url: "https://sandbox.orcid.org/".to_string(),
+            client_id: "APP-123".to_string(),
+            client_secret: "s3cr3t".to_string(),
+            redirect_uri: "https://catcolab.org/orcid".to_string(),
+        };
+        assert_eq!(config.token_url(), "https://sandbox.orcid.org/oauth/token");
+        assert_eq!(
+            config.token_request_body("a b"),
+            "client_id=APP-123&client_secret=s3cr3t&grant_type=authorization_code\
+             &code=a+b&redirect_uri=https%3A%2F%2Fcatcolab.org%2Forcid"
+        );
+    }
+}
@@ -46,9 +46,17 @@ pub async fn create_test_app_state(pool: PgPool) -> AppState {
         db: pool,
         repo,
         ref_actors: Arc::new(RwLock::new(HashMap::new())),
+        scratch_docs: Arc::new(RwLock::new(HashMap::new())),
         initialized_user_states: Arc::new(RwLock::new(HashMap::new())),
         http_client: reqwest::Client::new(),
         julia_url: None,
+        s3: None,
+        orcid: None,
+        archive: None,
+        maintenance: Arc::new(RwLock::new(None)),
+        permission_cache: Default::default(),
+        presence: Default::default(),
+        job_scheduler: Default::default(),
     }
 }
 
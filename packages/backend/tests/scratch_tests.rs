@@ -0,0 +1,65 @@
+//! Integration tests for scratch documents.
+//!
+//! These tests require a running PostgreSQL database and check that the
+//! Automerge storage of scratch documents does not outlive them.
+#[cfg(feature = "integration-tests")]
+mod common;
+
+#[cfg(feature = "integration-tests")]
+mod integration_tests {
+    use crate::common::test_utils::{
+        create_test_app_state, create_test_document_content, create_test_firebase_user,
+        ensure_user_exists, run_migrations,
+    };
+    use backend::app::AppCtx;
+    use backend::scratch;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    async fn storage_rows(pool: &PgPool, doc_id: &str) -> sqlx::Result<i64> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM storage WHERE key[1] = $1")
+            .bind(doc_id)
+            .fetch_one(pool)
+            .await
+    }
+
+    /// Discarding a scratch document or letting it expire deletes its storage.
+    #[sqlx::test]
+    async fn scratch_storage_is_deleted(pool: PgPool) -> sqlx::Result<()> {
+        run_migrations(&pool).await?;
+        let state = create_test_app_state(pool.clone()).await;
+
+        let user_id = format!("test_user_{}", Uuid::now_v7());
+        ensure_user_exists(&pool, &user_id).await.expect("Failed to create user");
+        let ctx = AppCtx {
+            state: state.clone(),
+            user: Some(create_test_firebase_user(&user_id)),
+        };
+
+        let content = create_test_document_content("Discarded");
+        let doc_id = scratch::create_scratch_doc(&ctx, content)
+            .await
+            .expect("Failed to create scratch document");
+        scratch::discard_scratch_doc(&ctx, &doc_id)
+            .await
+            .expect("Failed to discard scratch document");
+        assert_eq!(storage_rows(&pool, &doc_id).await?, 0);
+
+        let content = create_test_document_content("Expired");
+        let doc_id = scratch::create_scratch_doc(&ctx, content)
+            .await
+            .expect("Failed to create scratch document");
+        sqlx::query(
+            "UPDATE scratch_docs SET created_at = NOW() - INTERVAL '30 days' WHERE doc_id = $1",
+        )
+        .bind(&doc_id)
+        .execute(&pool)
+        .await?;
+        let swept = scratch::sweep_scratch_docs(&state).await.expect("Failed to sweep");
+        assert_eq!(swept, 1);
+        assert_eq!(storage_rows(&pool, &doc_id).await?, 0);
+        assert!(scratch::discard_scratch_doc(&ctx, &doc_id).await.is_err());
+
+        Ok(())
+    }
+}
@@ -1110,9 +1110,17 @@ mod integration_tests {
                 db: test_db.pool().clone(),
                 repo,
                 ref_actors: Arc::new(RwLock::new(HashMap::new())),
+                scratch_docs: Arc::new(RwLock::new(HashMap::new())),
                 initialized_user_states: Arc::new(RwLock::new(HashMap::new())),
                 http_client: reqwest::Client::new(),
                 julia_url: None,
+                s3: None,
+                orcid: None,
+                archive: None,
+                maintenance: Arc::new(RwLock::new(None)),
+                permission_cache: Default::default(),
+                presence: Default::default(),
+                job_scheduler: Default::default(),
             };
 
             let expected_state =
@@ -66,8 +66,13 @@ pub(crate) fn polynomial_ode_simulation(
     let sys_extended_scalars = ode::extend_polynomial_ode_scalars(sys?, &data);
     let latex_equations =
         sys_extended_scalars.map_variables(latex_ob_names(model)).to_latex_equations();
-    let analysis = ode::polynomial_ode_analysis(sys_extended_scalars, data);
-    let solution = analysis.solve_with_defaults().map_err(|err| format!("{err:?}"));
+    let solution = if data.double_precision {
+        let sys = sys_extended_scalars.extend_scalars(f64::from);
+        ode::polynomial_ode_analysis(sys, data).solve_with_defaults()
+    } else {
+        ode::polynomial_ode_analysis(sys_extended_scalars, data).solve_with_defaults()
+    };
+    let solution = solution.map_err(|err| format!("{err:?}"));
     Ok(ODEResultWithEquations {
         solution: ODEResult(solution.into()),
         latex_equations: LatexEquations(latex_equations),
@@ -136,8 +141,13 @@ pub(crate) fn mass_action_simulation(
     let sys_extended_scalars = ode::extend_mass_action_scalars(sys?, &data);
     let latex_equations =
         sys_extended_scalars.map_variables(latex_ob_names(model)).to_latex_equations();
-    let analysis = ode::into_mass_action_analysis(sys_extended_scalars, data);
-    let solution = analysis.solve_with_defaults().map_err(|err| format!("{err:?}"));
+    let solution = if data.double_precision {
+        let sys = sys_extended_scalars.extend_scalars(f64::from);
+        ode::into_mass_action_analysis(sys, data).solve_with_defaults()
+    } else {
+        ode::into_mass_action_analysis(sys_extended_scalars, data).solve_with_defaults()
+    };
+    let solution = solution.map_err(|err| format!("{err:?}"));
     Ok(ODEResultWithEquations {
         solution: ODEResult(solution.into()),
         latex_equations: LatexEquations(latex_equations),
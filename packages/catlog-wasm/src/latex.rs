@@ -35,7 +35,8 @@ pub(crate) fn latex_mor_names(model: &DblModel) -> impl Fn(&QualifiedName) -> St
     // Named morphisms produce `\text{name}`, unnamed ones produce
     // `\text{dom} \to \text{cod}` so that `\to` is in math mode.
     let morphism_subscript = |morphism: &QualifiedName| -> String {
-        if let Some(label) = model.mor_namespace.label(morphism) {
+        if model.mor_namespace.label(morphism).is_some() {
+            let label = model.mor_namespace.label_string(morphism);
             format!("\\text{{{label}}}")
         } else {
             let (dom, cod) = model
@@ -62,7 +63,8 @@ pub(crate) fn latex_mor_names_mass_action(
     // Named morphisms produce `\text{name}`, unnamed ones produce
     // `\text{dom} \to \text{cod}` so that `\to` is in math mode.
     let transition_subscript = |transition: &QualifiedName| -> String {
-        if let Some(label) = model.mor_namespace.label(transition) {
+        if model.mor_namespace.label(transition).is_some() {
+            let label = model.mor_namespace.label_string(transition);
             format!("\\text{{{label}}}")
         } else {
             let (dom, cod) = model
@@ -28,6 +28,12 @@ pub mod theories;
 
 use wasm_bindgen::prelude::*;
 
+/// Maximum number of nodes visited by a search run from the browser.
+///
+/// Searches can take exponential time, so they are bounded to avoid freezing
+/// the browser tab on adversarial inputs.
+pub const MAX_SEARCH_NODES: u64 = 1_000_000;
+
 /// Set panic hook to get better error messages on panics.
 ///
 /// When the `console_error_panic_hook` feature is enabled, we can call the
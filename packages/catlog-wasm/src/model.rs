@@ -13,13 +13,17 @@ use wasm_bindgen::prelude::*;
 
 use catcolab_document_types::current::{path as notebook_path, *};
 use catlog::dbl::{
+    discrete::ModelRepairs,
     model::{
         self as dbl_model, DblModel as _, FpDblModel, InvalidDblModel, ModalMor, ModalOb,
         MutDblModel, TabEdge, TabMor, TabOb,
     },
     theory::{self as dbl_theory, ModalObOp, NonUnital, Unital},
 };
+use catlog::instrument::Budget;
 use catlog::one::{Category as _, FgCategory, Path, QualifiedPath};
+use catlog::stdlib::analyses::query::{ConjunctiveQuery, QueryResult};
+use catlog::stdlib::versions::{Compatibility, TheoryVersion, check_theory};
 use catlog::tt::{
     self,
     notebook_elab::{Elaborator as ElaboratorNext, demote_modality, promote_modality},
@@ -28,6 +32,7 @@ use catlog::tt::{
 use catlog::validate::Validate;
 use catlog::zero::{NameLookup, NameSegment, Namespace, QualifiedLabel, QualifiedName};
 
+use super::MAX_SEARCH_NODES;
 use super::result::JsResult;
 use super::theory::{DblTheory, DblTheoryBox, expect_single_name};
 use super::{model_presentation::*, notation::*, wd::*};
@@ -659,6 +664,29 @@ impl DblModel {
         ModelValidationResult(result.map_err(|errs| errs.into()).into())
     }
 
+    /// Answers a conjunctive query over the model.
+    #[wasm_bindgen]
+    pub fn query(&self, query: ConjunctiveQuery) -> Result<QueryResult, String> {
+        let budget = Budget::nodes(MAX_SEARCH_NODES);
+        query.eval_within(self.discrete()?, budget).map_err(|err| err.to_string())
+    }
+
+    /// Suggests repairs for each validation failure, for use in quick fixes.
+    #[wasm_bindgen(js_name = "repairSuggestions")]
+    pub fn repair_suggestions(&self) -> Result<Vec<ModelRepairs>, String> {
+        Ok(self.discrete()?.repair_suggestions(&self.ob_namespace))
+    }
+
+    /// Extracts the submodel induced by a set of objects.
+    #[wasm_bindgen(js_name = "inducedSubmodel")]
+    pub fn induced_submodel(&self, obs: Vec<QualifiedName>) -> Result<DblModel, String> {
+        let submodel = self.discrete()?.induced_submodel(obs);
+        Ok(Self {
+            ty: None,
+            ..self.replace_box(submodel.into())
+        })
+    }
+
     /// Extracts a composition pattern (UWD) from the model.
     #[wasm_bindgen(js_name = "compositionPattern")]
     pub fn composition_pattern(&self) -> Option<UWD> {
@@ -736,13 +764,33 @@ impl DblModelMap {
 }
 
 /// Elaborates a model defined by a notebook into a catlog model.
+///
+/// The theory version recorded in the model document, such as
+/// `"simple-schema@2"`, is checked against the runtime theory. A model recorded
+/// against an older version may use types that the runtime theory lacks, so it
+/// is elaborated without checking types and then upgraded to the runtime theory.
 #[wasm_bindgen(js_name = "elaborateModel")]
 pub fn elaborate_model(
     notebook: &ModelNotebook,
     instantiated: &DblModelMap,
     theory: &DblTheory,
     ref_id: String,
+    theory_version: Option<String>,
 ) -> Result<DblModel, String> {
+    if let Some(theory_version) = theory_version {
+        let recorded: TheoryVersion = theory_version
+            .parse()
+            .map_err(|_| format!("Invalid theory version: {theory_version}"))?;
+        let (history, compatibility) = check_theory(&recorded).map_err(|err| err.to_string())?;
+        if let Compatibility::Outdated { .. } = compatibility {
+            let model = elaborate_legacy(notebook, theory)?;
+            let upgraded = history
+                .upgrade(model.discrete()?.as_ref().clone(), &recorded, theory.discrete()?.clone())
+                .map_err(|err| err.to_string())?;
+            return Ok(model.replace_box(upgraded.into()));
+        }
+    }
+
     if let Some(theory_def) = theory.try_into_tt() {
         let theory = tt::theory::Theory::new(ustr("_").into(), theory_def);
         let ref_id = ustr(&ref_id);
@@ -757,22 +805,26 @@ pub fn elaborate_model(
             mor_namespace: namespace.clone(),
         })
     } else {
-        // Legacy elaboration.
-        let mut model = DblModel::new(theory);
-        for judgment in notebook.0.formal_content() {
-            match judgment {
-                ModelJudgment::Object(decl) => model.add_ob(decl)?,
-                ModelJudgment::Morphism(decl) => model.add_mor(decl)?,
-                ModelJudgment::Instantiation(_) => {
-                    return Err("Legacy model elaborator does not support instantiation".into());
-                }
-                ModelJudgment::Equation(_) => {
-                    return Err("Legacy model elaborator does not support equations".into());
-                }
+        elaborate_legacy(notebook, theory)
+    }
+}
+
+/// Elaborates a model with the legacy elaborator, which does not check types.
+fn elaborate_legacy(notebook: &ModelNotebook, theory: &DblTheory) -> Result<DblModel, String> {
+    let mut model = DblModel::new(theory);
+    for judgment in notebook.0.formal_content() {
+        match judgment {
+            ModelJudgment::Object(decl) => model.add_ob(decl)?,
+            ModelJudgment::Morphism(decl) => model.add_mor(decl)?,
+            ModelJudgment::Instantiation(_) => {
+                return Err("Legacy model elaborator does not support instantiation".into());
+            }
+            ModelJudgment::Equation(_) => {
+                return Err("Legacy model elaborator does not support equations".into());
             }
         }
-        Ok(model)
     }
+    Ok(model)
 }
 
 #[cfg(test)]
@@ -5,9 +5,10 @@ use std::collections::HashSet;
 use serde::{Deserialize, Serialize};
 use tsify::Tsify;
 
+use super::MAX_SEARCH_NODES;
 use super::model::DblModel;
 use catlog::dbl::{model, model_morphism::DiscreteDblModelMapping};
-use catlog::{one::FgCategory, zero::QualifiedName};
+use catlog::{instrument::Budget, one::FgCategory, zero::QualifiedName};
 
 /// Options for motif finder.
 #[derive(Debug, Deserialize, Serialize, Tsify)]
@@ -61,7 +62,9 @@ pub fn motifs(
     }
     let mut images: Vec<_> = finder
         .monic()
-        .find_all()
+        .budget(Budget::nodes(MAX_SEARCH_NODES))
+        .try_find_all()
+        .map_err(|err| err.to_string())?
         .into_iter()
         .map(|mapping| MotifOccurrence::from_image(mapping, model))
         .collect();
@@ -7,8 +7,9 @@ use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
 use catlog::dbl::theory::{self as theory, NonUnital, Unital};
+use catlog::lint::LintDiagnostic;
 use catlog::one::Path;
-use catlog::stdlib::{analyses, models, theories, theory_morphisms};
+use catlog::stdlib::{analyses, lints, models, theories, theory_morphisms};
 use catlog::zero::name;
 
 use super::latex::LatexEquations;
@@ -60,6 +61,34 @@ impl ThCategory {
         );
         Ok(boxed.replace_box(model.into()))
     }
+
+    /// Analyzes a state-transition diagram as a continuous-time Markov chain.
+    #[wasm_bindgen(js_name = "markovChain")]
+    pub fn markov_chain(
+        &self,
+        model: &DblModel,
+        data: analyses::stochastic::MarkovChainProblemData,
+    ) -> Result<analyses::stochastic::MarkovChainResult, String> {
+        let chain = analyses::stochastic::ContinuousTimeMarkovChain::from_model(
+            model.discrete()?,
+            &data.rates,
+        );
+        Ok(chain.analyze(&data))
+    }
+
+    /// Computes the transient distribution of a continuous-time Markov chain.
+    #[wasm_bindgen(js_name = "markovChainTransient")]
+    pub fn markov_chain_transient(
+        &self,
+        model: &DblModel,
+        data: analyses::stochastic::MarkovChainTransientData,
+    ) -> Result<ODEResult, String> {
+        let chain = analyses::stochastic::ContinuousTimeMarkovChain::from_model(
+            model.discrete()?,
+            &data.rates,
+        );
+        Ok(ODEResult(JsResult::Ok(chain.transient_analysis(&data))))
+    }
 }
 
 /// The theory of database schemas with attributes.
@@ -133,6 +162,12 @@ impl ThSignedCategory {
         motifs(&positive_loop, model, options)
     }
 
+    /// Checks the model against the lints for causal loop diagrams.
+    #[wasm_bindgen]
+    pub fn lint(&self, model: &DblModel) -> Result<Vec<LintDiagnostic>, String> {
+        Ok(lints::discrete_lints().run(model.discrete()?))
+    }
+
     /// Find negative feedback loops in a model.
     #[wasm_bindgen(js_name = "negativeLoops")]
     pub fn negative_loops(
@@ -323,6 +358,12 @@ impl ThCategoryLinks {
     ) -> Result<LatexEquations, String> {
         mass_action_equations(model, data, MassActionAnalysisLogic::StockFlow)
     }
+
+    /// Checks the model against the lints for stock-flow diagrams.
+    #[wasm_bindgen]
+    pub fn lint(&self, model: &DblModel) -> Result<Vec<LintDiagnostic>, String> {
+        Ok(lints::stock_flow_lints().run(model.discrete_tab()?))
+    }
 }
 
 /// The theory of categories with signed links.
@@ -398,6 +439,12 @@ impl ThSymMonoidalCategory {
         mass_action_equations(model, data, MassActionAnalysisLogic::PetriNet)
     }
 
+    /// Checks the model against the lints for Petri nets.
+    #[wasm_bindgen]
+    pub fn lint(&self, model: &DblModel) -> Result<Vec<LintDiagnostic>, String> {
+        Ok(lints::petri_net_lints().run(model.modal_unital()?))
+    }
+
     /// Simulates the stochastic mass-action system derived from a model.
     #[wasm_bindgen(js_name = "stochasticMassAction")]
     pub fn stochastic_mass_action(
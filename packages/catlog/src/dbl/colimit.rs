@@ -0,0 +1,9 @@
+//! Colimits of models of double theories.
+//!
+//! Colimits glue models together. The most common case is the
+//! [pushout](https://ncatlab.org/nlab/show/pushout) of a span of models `B ← A →
+//! C`, which composes the models `B` and `C` by identifying their common
+//! interface `A`. More generally, the colimit of a finite diagram of models glues
+//! the models along all the model morphisms in the diagram.
+
+pub use super::discrete::colimit::*;
@@ -0,0 +1,487 @@
+//! Automorphisms of models of discrete double theories.
+//!
+//! An automorphism of a model is an invertible model morphism from the model to
+//! itself. We compute automorphisms that permute the generators of the model,
+//! preserving their types, the domains and codomains of morphism generators, and
+//! the equations of the presentation. Such automorphisms form a group, the
+//! symmetries of the model, which is described by a set of generators together
+//! with the orbits of the object and morphism generators.
+//!
+//! The group is computed by individualization and refinement, the technique
+//! behind canonical labeling programs such as nauty. Generators are colored by
+//! their types, then colors are iteratively refined by the colors of incident
+//! generators until stable. When colors alone cannot distinguish generators, a
+//! generator is individualized and the search branches. Comparing the leaves of
+//! the search tree, where every generator has its own color, to the first leaf
+//! yields automorphisms, and orbits found along the way prune the search.
+
+use std::collections::{HashMap, HashSet};
+
+use super::model::DiscreteDblModel;
+use super::model_morphism::DiscreteDblModelMapping;
+use crate::dbl::model::{FpDblModel, MutDblModel};
+use crate::instrument::{Budget, BudgetExhausted, BudgetGuard, Instrument, SearchStats};
+use crate::one::{FgCategory, Path, QualifiedPath};
+use crate::zero::QualifiedName;
+
+/// The automorphism group of a model, described by generators and orbits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelAutomorphisms {
+    /// Generators of the automorphism group.
+    pub generators: Vec<DiscreteDblModelMapping>,
+
+    /// Orbits of the object generators under the group.
+    pub ob_orbits: Vec<Vec<QualifiedName>>,
+
+    /// Orbits of the morphism generators under the group.
+    pub mor_orbits: Vec<Vec<QualifiedName>>,
+
+    /// Order of the group, saturating at the maximum value.
+    pub order: u128,
+
+    /// Counters reported by the search.
+    pub stats: SearchStats,
+}
+
+impl ModelAutomorphisms {
+    /// Is the group trivial, so that the model has no symmetries?
+    pub fn is_trivial(&self) -> bool {
+        self.generators.is_empty()
+    }
+
+    /// Gets the orbit of an object generator.
+    pub fn ob_orbit(&self, x: &QualifiedName) -> Option<&[QualifiedName]> {
+        self.ob_orbits
+            .iter()
+            .find(|orbit| orbit.contains(x))
+            .map(|orbit| orbit.as_slice())
+    }
+
+    /// Gets the orbit of a morphism generator.
+    pub fn mor_orbit(&self, f: &QualifiedName) -> Option<&[QualifiedName]> {
+        self.mor_orbits
+            .iter()
+            .find(|orbit| orbit.contains(f))
+            .map(|orbit| orbit.as_slice())
+    }
+}
+
+impl DiscreteDblModel {
+    /// Computes the automorphism group of the model.
+    pub fn automorphisms(&self) -> ModelAutomorphisms {
+        self.automorphisms_within(Budget::unlimited())
+            .expect("Search with unlimited budget should not be exhausted")
+    }
+
+    /// Computes the automorphism group of the model within a budget.
+    pub fn automorphisms_within(
+        &self,
+        budget: Budget,
+    ) -> Result<ModelAutomorphisms, BudgetExhausted> {
+        let incidence = Incidence::new(self);
+        let mut search = AutomorphismSearch::new(&incidence, self, budget);
+        let order = search.run()?;
+        let AutomorphismSearch {
+            generators, mut orbits, mut stats, guard, ..
+        } = search;
+        guard.stop(&mut stats);
+
+        let mut ob_orbits = Vec::new();
+        let mut mor_orbits = Vec::new();
+        let mut orbit_index = HashMap::new();
+        for v in 0..incidence.len() {
+            let kind_orbits = if incidence.is_ob(v) {
+                &mut ob_orbits
+            } else {
+                &mut mor_orbits
+            };
+            let i = *orbit_index.entry(orbits.find(v)).or_insert_with(|| {
+                kind_orbits.push(Vec::new());
+                kind_orbits.len() - 1
+            });
+            kind_orbits[i].push(incidence.names[v].clone());
+        }
+        Ok(ModelAutomorphisms {
+            generators: generators.iter().map(|perm| incidence.mapping(perm)).collect(),
+            ob_orbits,
+            mor_orbits,
+            order,
+            stats,
+        })
+    }
+}
+
+/// The generators of a model as a colored graph.
+///
+/// Object generators are numbered first, followed by morphism generators. The
+/// initial color of a generator is the index of its type among the sorted types
+/// of the model, so that colors do not depend on the order of generators.
+pub(super) struct Incidence {
+    pub(super) names: Vec<QualifiedName>,
+    pub(super) num_obs: usize,
+    /// Domain and codomain of each morphism generator.
+    pub(super) ends: Vec<[Option<usize>; 2]>,
+    /// Morphism generators incident to each object generator, labeled by
+    /// whether the object is the domain or codomain.
+    incident: Vec<Vec<(usize, usize)>>,
+    pub(super) initial: Vec<usize>,
+    /// Object types of the model, sorted.
+    pub(super) ob_types: Vec<QualifiedName>,
+    /// Morphism types of the model, sorted by their generators.
+    pub(super) mor_types: Vec<QualifiedPath>,
+}
+
+/// Key by which morphism types are sorted.
+pub(super) fn mor_type_key(m: &QualifiedPath) -> (bool, Vec<&QualifiedName>) {
+    match m {
+        Path::Id(x) => (false, vec![x]),
+        Path::Seq(fs) => (true, fs.iter().collect()),
+    }
+}
+
+impl Incidence {
+    pub(super) fn new(model: &DiscreteDblModel) -> Self {
+        let obs: Vec<_> = model.ob_generators().collect();
+        let mors: Vec<_> = model.mor_generators().collect();
+        let num_obs = obs.len();
+        let index: HashMap<_, _> = obs.iter().cloned().zip(0..).collect();
+
+        let ob_gen_types: Vec<_> = obs.iter().map(|x| model.ob_generator_type(x)).collect();
+        let mor_gen_types: Vec<_> = mors.iter().map(|f| model.mor_generator_type(f)).collect();
+        let mut ob_types = ob_gen_types.clone();
+        ob_types.sort();
+        ob_types.dedup();
+        let mut mor_types = mor_gen_types.clone();
+        mor_types.sort_by(|m, n| mor_type_key(m).cmp(&mor_type_key(n)));
+        mor_types.dedup();
+        let ob_colors = ob_gen_types.iter().map(|x| ob_types.binary_search(x).unwrap());
+        let mor_colors = mor_gen_types.iter().map(|m| {
+            let i = mor_types.binary_search_by(|n| mor_type_key(n).cmp(&mor_type_key(m)));
+            ob_types.len() + i.unwrap()
+        });
+        let initial = ob_colors.chain(mor_colors).collect();
+
+        let mut incident = vec![Vec::new(); num_obs];
+        let mut ends = Vec::new();
+        for (i, f) in mors.iter().enumerate() {
+            let dom = model.get_dom(f).and_then(|x| index.get(x).copied());
+            let cod = model.get_cod(f).and_then(|x| index.get(x).copied());
+            for (label, x) in [(0, dom), (1, cod)] {
+                if let Some(x) = x {
+                    incident[x].push((label, num_obs + i));
+                }
+            }
+            ends.push([dom, cod]);
+        }
+        let names = obs.into_iter().chain(mors).collect();
+        Self {
+            names,
+            num_obs,
+            ends,
+            incident,
+            initial,
+            ob_types,
+            mor_types,
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub(super) fn is_ob(&self, v: usize) -> bool {
+        v < self.num_obs
+    }
+
+    /// Colors of the neighbors of a generator, with their labels.
+    fn signature(&self, v: usize, colors: &[usize]) -> Vec<(usize, usize)> {
+        let mut signature: Vec<_> = if self.is_ob(v) {
+            self.incident[v].iter().map(|&(label, f)| (label, colors[f])).collect()
+        } else {
+            let ends = self.ends[v - self.num_obs];
+            (0..2).filter_map(|label| Some((label, colors[ends[label]?]))).collect()
+        };
+        signature.sort_unstable();
+        signature
+    }
+
+    /// Refines a coloring until it is stable.
+    ///
+    /// Colors are renumbered by sorting, so that the result depends only on
+    /// the structure of the model and not on the numbering of generators.
+    pub(super) fn refine(&self, mut colors: Vec<usize>) -> Vec<usize> {
+        let mut count = num_colors(&colors);
+        loop {
+            let keys: Vec<_> =
+                (0..self.len()).map(|v| (colors[v], self.signature(v, &colors))).collect();
+            let mut sorted = keys.clone();
+            sorted.sort_unstable();
+            sorted.dedup();
+            colors = keys.iter().map(|key| sorted.binary_search(key).unwrap()).collect();
+            if sorted.len() == count {
+                return colors;
+            }
+            count = sorted.len();
+        }
+    }
+
+    /// Gives a generator its own color and refines.
+    pub(super) fn individualize(&self, colors: &[usize], v: usize) -> Vec<usize> {
+        let c = colors[v];
+        let split = colors
+            .iter()
+            .enumerate()
+            .map(|(u, &d)| 2 * d + usize::from(d == c && u != v))
+            .collect();
+        self.refine(split)
+    }
+
+    /// Is the permutation of generators an automorphism of the incidence?
+    fn preserves_incidence(&self, perm: &[usize]) -> bool {
+        (self.num_obs..self.len()).all(|f| {
+            let [dom, cod] = self.ends[f - self.num_obs];
+            self.ends[perm[f] - self.num_obs] == [dom.map(|x| perm[x]), cod.map(|x| perm[x])]
+        })
+    }
+
+    fn permute_path(&self, perm: &[usize], path: &QualifiedPath) -> QualifiedPath {
+        let permute = |range: std::ops::Range<usize>, x: QualifiedName| match self.names
+            [range.clone()]
+        .iter()
+        .position(|y| *y == x)
+        {
+            Some(i) => self.names[perm[range.start + i]].clone(),
+            None => x,
+        };
+        match path {
+            Path::Id(x) => Path::Id(permute(0..self.num_obs, x.clone())),
+            Path::Seq(fs) => Path::Seq(fs.clone().map(|f| permute(self.num_obs..self.len(), f))),
+        }
+    }
+
+    fn mapping(&self, perm: &[usize]) -> DiscreteDblModelMapping {
+        let obs = (0..self.num_obs).map(|x| (self.names[x].clone(), self.names[perm[x]].clone()));
+        let mors = (self.num_obs..self.len())
+            .map(|f| (self.names[f].clone(), Path::single(self.names[perm[f]].clone())));
+        DiscreteDblModelMapping::new(obs, mors)
+    }
+}
+
+fn num_colors(colors: &[usize]) -> usize {
+    colors.iter().collect::<HashSet<_>>().len()
+}
+
+/// The first cell of a coloring having more than one generator, if any.
+pub(super) fn target_cell(colors: &[usize]) -> Option<Vec<usize>> {
+    let mut sizes = HashMap::new();
+    for &c in colors {
+        *sizes.entry(c).or_insert(0) += 1;
+    }
+    let c = sizes.into_iter().filter(|&(_, n)| n > 1).map(|(c, _)| c).min()?;
+    Some((0..colors.len()).filter(|&v| colors[v] == c).collect())
+}
+
+pub(super) struct AutomorphismSearch<'a> {
+    incidence: &'a Incidence,
+    equations: HashSet<(QualifiedPath, QualifiedPath)>,
+    /// Generator having each color in the first leaf.
+    first_leaf: Vec<usize>,
+    /// Generators of the group, as permutations of the generators of the model.
+    pub(super) generators: Vec<Vec<usize>>,
+    orbits: UnionFind,
+    pub(super) stats: SearchStats,
+    pub(super) guard: BudgetGuard,
+}
+
+impl<'a> AutomorphismSearch<'a> {
+    pub(super) fn new(incidence: &'a Incidence, model: &DiscreteDblModel, budget: Budget) -> Self {
+        Self {
+            incidence,
+            equations: model
+                .category
+                .equations()
+                .map(|eq| (eq.lhs.clone(), eq.rhs.clone()))
+                .collect(),
+            first_leaf: Vec::new(),
+            generators: Vec::new(),
+            orbits: UnionFind::new(incidence.len()),
+            stats: SearchStats::default(),
+            guard: BudgetGuard::start(budget),
+        }
+    }
+
+    /// Runs the search, returning the order of the group.
+    pub(super) fn run(&mut self) -> Result<u128, BudgetExhausted> {
+        let mut colors = self.incidence.refine(self.incidence.initial.clone());
+        let mut levels = Vec::new();
+        while let Some(cell) = target_cell(&colors) {
+            self.visit()?;
+            let next = self.incidence.individualize(&colors, cell[0]);
+            levels.push((colors, cell));
+            colors = next;
+        }
+        self.first_leaf = vec![0; colors.len()];
+        for (v, &c) in colors.iter().enumerate() {
+            self.first_leaf[c] = v;
+        }
+
+        // Find generators of the stabilizer of each prefix of the first path,
+        // from the deepest level up, so that the generators found so far
+        // always fix the prefix.
+        let mut order: u128 = 1;
+        for (colors, cell) in levels.iter().rev() {
+            let v0 = cell[0];
+            for &w in &cell[1..] {
+                if self.orbits.find(w) == self.orbits.find(v0) {
+                    continue;
+                }
+                let branch = self.incidence.individualize(colors, w);
+                if let Some(perm) = self.find_leaf(branch)? {
+                    for (v, &u) in perm.iter().enumerate() {
+                        self.orbits.union(v, u);
+                    }
+                    self.generators.push(perm);
+                }
+            }
+            let root = self.orbits.find(v0);
+            let orbit_size = cell.iter().filter(|&&v| self.orbits.find(v) == root).count();
+            order = order.saturating_mul(orbit_size as u128);
+        }
+        Ok(order)
+    }
+
+    pub(super) fn visit(&mut self) -> Result<(), BudgetExhausted> {
+        self.stats.node();
+        self.guard.check(&self.stats)
+    }
+
+    /// Searches below a node for a leaf equivalent to the first leaf.
+    fn find_leaf(&mut self, colors: Vec<usize>) -> Result<Option<Vec<usize>>, BudgetExhausted> {
+        self.visit()?;
+        let Some(cell) = target_cell(&colors) else {
+            let mut by_color = vec![0; colors.len()];
+            for (v, &c) in colors.iter().enumerate() {
+                by_color[c] = v;
+            }
+            let mut perm = vec![0; colors.len()];
+            for (c, &v) in self.first_leaf.iter().enumerate() {
+                perm[v] = by_color[c];
+            }
+            if self.is_automorphism(&perm) {
+                self.stats.solution();
+                return Ok(Some(perm));
+            }
+            self.stats.backtrack();
+            return Ok(None);
+        };
+        for v in cell {
+            let branch = self.incidence.individualize(&colors, v);
+            if let Some(perm) = self.find_leaf(branch)? {
+                return Ok(Some(perm));
+            }
+        }
+        Ok(None)
+    }
+
+    fn is_automorphism(&self, perm: &[usize]) -> bool {
+        self.incidence.preserves_incidence(perm)
+            && self.equations.iter().all(|(lhs, rhs)| {
+                let lhs = self.incidence.permute_path(perm, lhs);
+                let rhs = self.incidence.permute_path(perm, rhs);
+                let swapped = (rhs.clone(), lhs.clone());
+                self.equations.contains(&(lhs, rhs)) || self.equations.contains(&swapped)
+            })
+    }
+}
+
+/// Union-find structure for orbits of generators.
+pub(super) struct UnionFind(Vec<usize>);
+
+impl UnionFind {
+    pub(super) fn new(n: usize) -> Self {
+        Self((0..n).collect())
+    }
+
+    pub(super) fn find(&mut self, mut v: usize) -> usize {
+        while self.0[v] != v {
+            self.0[v] = self.0[self.0[v]];
+            v = self.0[v];
+        }
+        v
+    }
+
+    pub(super) fn union(&mut self, u: usize, v: usize) {
+        let (u, v) = (self.find(u), self.find(v));
+        self.0[u.max(v)] = u.min(v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::one::PathEq;
+    use crate::stdlib::theories::{th_category, th_signed_category};
+    use crate::zero::{Column, name};
+
+    fn model(obs: &[&str], mors: &[(&str, &str, &str)]) -> DiscreteDblModel {
+        let mut model = DiscreteDblModel::new(Rc::new(th_category()));
+        for x in obs {
+            model.add_ob(name(*x), name("Object"));
+        }
+        for (f, x, y) in mors {
+            model.add_mor(name(*f), name(*x), name(*y), Path::Id(name("Object")));
+        }
+        model
+    }
+
+    #[test]
+    fn span_automorphisms() {
+        let span = model(&["x", "y", "z"], &[("f", "x", "y"), ("g", "x", "z")]);
+        let auts = span.automorphisms();
+        assert_eq!(auts.order, 2);
+        assert_eq!(auts.generators.len(), 1);
+        assert_eq!(auts.ob_orbit(&name("x")), Some(&[name("x")][..]));
+        assert_eq!(auts.ob_orbit(&name("y")).map(|orbit| orbit.len()), Some(2));
+        assert_eq!(auts.mor_orbits, vec![vec![name("f"), name("g")]]);
+        let swap = &auts.generators[0].0.mor_generator_map;
+        assert_eq!(swap.get(&name("f")), Some(&Path::single(name("g"))));
+    }
+
+    #[test]
+    fn cycle_automorphisms() {
+        let cycle = model(&["a", "b", "c"], &[("f", "a", "b"), ("g", "b", "c"), ("h", "c", "a")]);
+        let auts = cycle.automorphisms();
+        assert_eq!(auts.order, 3);
+        assert_eq!(auts.ob_orbits.len(), 1);
+        assert!(cycle.automorphisms_within(Budget::nodes(0)).is_err());
+
+        // Parallel morphisms can be permuted without moving any objects.
+        let parallel = model(&["a", "b"], &[("f", "a", "b"), ("g", "a", "b"), ("h", "a", "b")]);
+        let auts = parallel.automorphisms();
+        assert_eq!(auts.order, 6);
+        assert_eq!(auts.ob_orbits.len(), 2);
+
+        let path = model(&["a", "b", "c"], &[("f", "a", "b"), ("g", "b", "c")]);
+        assert!(path.automorphisms().is_trivial());
+    }
+
+    #[test]
+    fn typed_automorphisms() {
+        // Loops of different signs cannot be swapped.
+        let mut model = DiscreteDblModel::new(Rc::new(th_signed_category()));
+        model.add_ob(name("x"), name("Object"));
+        model.add_ob(name("y"), name("Object"));
+        model.add_mor(name("p"), name("x"), name("x"), Path::Id(name("Object")));
+        model.add_mor(name("n"), name("y"), name("y"), name("Negative").into());
+        assert!(model.automorphisms().is_trivial());
+
+        // Equations must be preserved.
+        let mut model =
+            self::model(&["a", "b"], &[("f", "a", "b"), ("g", "a", "b"), ("e", "b", "b")]);
+        assert_eq!(model.automorphisms().order, 2);
+        model.add_equation(PathEq::new(Path::pair(name("f"), name("e")), Path::single(name("g"))));
+        assert!(model.automorphisms().is_trivial());
+    }
+}
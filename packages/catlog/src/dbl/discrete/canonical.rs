@@ -0,0 +1,235 @@
+//! Canonical forms of models of discrete double theories.
+//!
+//! The canonical form of a model is a byte string that depends only on the
+//! isomorphism class of the model: two models of the same theory have equal
+//! canonical forms if and only if they are isomorphic by a bijection of
+//! generators preserving types, domains, codomains, and equations. Isomorphic
+//! models can thus be deduplicated by hashing their canonical forms, rather than
+//! by testing all pairs of models for isomorphism.
+//!
+//! The canonical form is computed by the same individualization and refinement
+//! search as the [automorphism group](super::automorphisms). Each leaf of the
+//! search tree numbers the generators, and the canonical form is the least
+//! encoding of the model among these numberings. Automorphisms of the model are
+//! used to prune branches of the search tree that would lead to the same
+//! encodings.
+
+use std::collections::{HashMap, HashSet};
+
+use super::automorphisms::{AutomorphismSearch, Incidence, UnionFind, mor_type_key, target_cell};
+use super::model::DiscreteDblModel;
+use crate::instrument::{Budget, BudgetExhausted, Instrument};
+use crate::one::{Path, QualifiedPath};
+use crate::zero::QualifiedName;
+
+impl DiscreteDblModel {
+    /// Computes the canonical form of the model.
+    ///
+    /// See the [module-level docs](super::canonical) for the guarantees.
+    pub fn canonical_form(&self) -> Vec<u8> {
+        self.canonical_form_within(Budget::unlimited())
+            .expect("Search with unlimited budget should not be exhausted")
+    }
+
+    /// Computes the canonical form of the model within a budget.
+    pub fn canonical_form_within(&self, budget: Budget) -> Result<Vec<u8>, BudgetExhausted> {
+        let incidence = Incidence::new(self);
+        let mut search = AutomorphismSearch::new(&incidence, self, budget);
+        search.run()?;
+
+        let mut canon = Canonizer {
+            incidence: &incidence,
+            ob_index: incidence.names[..incidence.num_obs].iter().zip(0..).collect(),
+            mor_index: incidence.names[incidence.num_obs..]
+                .iter()
+                .zip(incidence.num_obs..)
+                .collect(),
+            equations: self.category.equations().map(|eq| (&eq.lhs, &eq.rhs)).collect(),
+            automorphisms: search,
+            best: None,
+        };
+        let colors = incidence.refine(incidence.initial.clone());
+        canon.search(colors, &mut Vec::new())?;
+
+        let mut bytes = Vec::new();
+        write_usize(&mut bytes, incidence.ob_types.len());
+        for x in &incidence.ob_types {
+            write_name(&mut bytes, x);
+        }
+        write_usize(&mut bytes, incidence.mor_types.len());
+        for m in &incidence.mor_types {
+            let (is_seq, names) = mor_type_key(m);
+            bytes.push(u8::from(is_seq));
+            write_usize(&mut bytes, names.len());
+            for name in names {
+                write_name(&mut bytes, name);
+            }
+        }
+        bytes.extend(canon.best.unwrap_or_default());
+        Ok(bytes)
+    }
+}
+
+struct Canonizer<'a> {
+    incidence: &'a Incidence,
+    ob_index: HashMap<&'a QualifiedName, usize>,
+    mor_index: HashMap<&'a QualifiedName, usize>,
+    equations: Vec<(&'a QualifiedPath, &'a QualifiedPath)>,
+    automorphisms: AutomorphismSearch<'a>,
+    best: Option<Vec<u8>>,
+}
+
+impl Canonizer<'_> {
+    /// Searches below a node for the least encoding of the model.
+    fn search(
+        &mut self,
+        colors: Vec<usize>,
+        prefix: &mut Vec<usize>,
+    ) -> Result<(), BudgetExhausted> {
+        self.automorphisms.visit()?;
+        let Some(cell) = target_cell(&colors) else {
+            let encoding = self.encode(&colors);
+            if self.best.as_ref().is_none_or(|best| encoding < *best) {
+                self.automorphisms.stats.solution();
+                self.best = Some(encoding);
+            } else {
+                self.automorphisms.stats.backtrack();
+            }
+            return Ok(());
+        };
+
+        // Branches related by an automorphism fixing the prefix are equivalent.
+        let mut orbits = UnionFind::new(colors.len());
+        for perm in &self.automorphisms.generators {
+            if prefix.iter().all(|&v| perm[v] == v) {
+                for (v, &u) in perm.iter().enumerate() {
+                    orbits.union(v, u);
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        for v in cell {
+            if seen.insert(orbits.find(v)) {
+                prefix.push(v);
+                self.search(self.incidence.individualize(&colors, v), prefix)?;
+                prefix.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the model using the numbering of generators given by a leaf.
+    fn encode(&self, colors: &[usize]) -> Vec<u8> {
+        let incidence = self.incidence;
+        let mut by_color = vec![0; colors.len()];
+        for (v, &c) in colors.iter().enumerate() {
+            by_color[c] = v;
+        }
+
+        let mut bytes = Vec::new();
+        write_usize(&mut bytes, incidence.num_obs);
+        write_usize(&mut bytes, incidence.len() - incidence.num_obs);
+        for &v in &by_color {
+            write_usize(&mut bytes, incidence.initial[v]);
+            if !incidence.is_ob(v) {
+                for end in incidence.ends[v - incidence.num_obs] {
+                    write_usize(&mut bytes, end.map_or(usize::MAX, |x| colors[x]));
+                }
+            }
+        }
+
+        let encode_path = |path: &QualifiedPath| -> Vec<usize> {
+            let color = |index: &HashMap<&QualifiedName, usize>, x: &QualifiedName| {
+                index.get(x).map_or(usize::MAX, |&v| colors[v])
+            };
+            match path {
+                Path::Id(x) => vec![0, color(&self.ob_index, x)],
+                Path::Seq(fs) => {
+                    let fs = fs.iter().map(|f| color(&self.mor_index, f));
+                    std::iter::once(1).chain(fs).collect()
+                }
+            }
+        };
+        let mut equations: Vec<_> = self
+            .equations
+            .iter()
+            .map(|(lhs, rhs)| {
+                let (lhs, rhs) = (encode_path(lhs), encode_path(rhs));
+                if lhs <= rhs { (lhs, rhs) } else { (rhs, lhs) }
+            })
+            .collect();
+        equations.sort();
+        equations.dedup();
+        write_usize(&mut bytes, equations.len());
+        for (lhs, rhs) in equations {
+            for path in [lhs, rhs] {
+                write_usize(&mut bytes, path.len());
+                for n in path {
+                    write_usize(&mut bytes, n);
+                }
+            }
+        }
+        bytes
+    }
+}
+
+fn write_usize(bytes: &mut Vec<u8>, n: usize) {
+    bytes.extend((n as u64).to_be_bytes());
+}
+
+fn write_name(bytes: &mut Vec<u8>, name: &QualifiedName) {
+    let name = name.to_string();
+    write_usize(bytes, name.len());
+    bytes.extend(name.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::model::MutDblModel;
+    use crate::stdlib::theories::th_schema;
+    use crate::zero::name;
+
+    fn schema(names: [&str; 4], swap: bool) -> DiscreteDblModel {
+        let [x, y, f, g] = names;
+        let mut model = DiscreteDblModel::new(Rc::new(th_schema()));
+        model.add_ob(name(x), name("Entity"));
+        model.add_ob(name(y), name("AttrType"));
+        let (dom, cod) = if swap { (y, x) } else { (x, y) };
+        model.add_mor(name(f), name(x), name(x), Path::Id(name("Entity")));
+        model.add_mor(name(g), name(dom), name(cod), name("Attr").into());
+        model
+    }
+
+    #[test]
+    fn canonical_forms() {
+        let model = schema(["x", "y", "f", "g"], false);
+        let renamed = schema(["b", "a", "h", "k"], false);
+        assert_eq!(model.canonical_form(), renamed.canonical_form());
+
+        let reversed = schema(["x", "y", "f", "g"], true);
+        assert_ne!(model.canonical_form(), reversed.canonical_form());
+
+        // Deduplicate the four quivers with two arrows on two vertices.
+        let th = Rc::new(crate::stdlib::theories::th_category());
+        let quivers = [
+            ("a", "a", "a", "a"),
+            ("a", "b", "b", "a"),
+            ("b", "a", "a", "b"),
+            ("a", "b", "a", "b"),
+        ];
+        let mut forms = HashSet::new();
+        for (x1, y1, x2, y2) in quivers {
+            let mut model = DiscreteDblModel::new(th.clone());
+            model.add_ob(name("a"), name("Object"));
+            model.add_ob(name("b"), name("Object"));
+            model.add_mor(name("f"), name(x1), name(y1), Path::Id(name("Object")));
+            model.add_mor(name("g"), name(x2), name(y2), Path::Id(name("Object")));
+            forms.insert(model.canonical_form());
+        }
+        assert_eq!(forms.len(), 3);
+        assert!(model.canonical_form_within(Budget::nodes(1)).is_ok());
+    }
+}
@@ -0,0 +1,289 @@
+//! Colimits of models of discrete double theories.
+//!
+//! A model of a discrete double theory is a finitely presented category over the
+//! theory, so colimits of models are computed at the level of presentations. The
+//! colimit of a finite diagram of models has the disjoint union of the generators
+//! of the models, with object generators identified along the model morphisms in
+//! the diagram. Morphism generators sent to single generators are identified in
+//! the same way, while those sent to other paths are instead equated with their
+//! images. The equations of each model are carried along to the colimit.
+//!
+//! Each generator of the colimit is named after a generator in its equivalence
+//! class, taken from the first model in the diagram having one. Names that
+//! clash between classes are disambiguated by appending a numeric segment.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use thiserror::Error;
+
+use super::automorphisms::UnionFind;
+use super::model::DiscreteDblModel;
+use super::model_morphism::{DblModelMorphism, DiscreteDblModelMapping};
+use super::rewriting::fresh_name;
+use crate::dbl::model::*;
+use crate::one::{FgCategory, Path, PathEq, QualifiedPath};
+use crate::validate::Validate;
+use crate::zero::{Mapping, QualifiedName};
+
+/// An arrow in a finite diagram of models.
+///
+/// The domain and codomain are indices into the list of models in the diagram.
+#[derive(Clone, Copy, Debug)]
+pub struct ModelArrow<'a> {
+    /// Index of the domain model.
+    pub dom: usize,
+
+    /// Index of the codomain model.
+    pub cod: usize,
+
+    /// The model morphism.
+    pub map: &'a DiscreteDblModelMapping,
+}
+
+/// The colimit of a finite diagram of models.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelColimit {
+    /// The colimit model.
+    pub model: DiscreteDblModel,
+
+    /// Legs of the colimit cocone, one for each model in the diagram.
+    pub legs: Vec<DiscreteDblModelMapping>,
+}
+
+/// The pushout of a span of models.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelPushout {
+    /// The pushout model.
+    pub model: DiscreteDblModel,
+
+    /// Map from the left model of the span into the pushout.
+    pub left: DiscreteDblModelMapping,
+
+    /// Map from the right model of the span into the pushout.
+    pub right: DiscreteDblModelMapping,
+}
+
+/// An error in computing a colimit of models.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ColimitError {
+    /// The diagram has no models, so the theory of the colimit is unknown.
+    #[error("Diagram should have at least one model")]
+    Empty,
+
+    /// A model in the diagram has a different theory from the first model.
+    #[error("Model {0} in the diagram has a different theory")]
+    TheoryMismatch(usize),
+
+    /// An arrow in the diagram refers to a model not in the diagram.
+    #[error("Arrow {0} in the diagram refers to a missing model")]
+    MissingModel(usize),
+
+    /// An arrow in the diagram is not a model morphism.
+    #[error("Arrow {0} in the diagram is not a valid model morphism")]
+    InvalidMorphism(usize),
+}
+
+/// Computes the colimit of a finite diagram of models.
+///
+/// The diagram is given by a list of models, all of the same theory, and a list
+/// of arrows between them. Without any arrows, the colimit is the coproduct of
+/// the models.
+pub fn colimit(
+    models: &[DiscreteDblModel],
+    arrows: &[ModelArrow<'_>],
+) -> Result<ModelColimit, ColimitError> {
+    let theory = models.first().ok_or(ColimitError::Empty)?.theory();
+    if let Some(i) = models.iter().position(|model| !Rc::ptr_eq(&model.theory(), &theory)) {
+        return Err(ColimitError::TheoryMismatch(i));
+    }
+    for (k, arrow) in arrows.iter().enumerate() {
+        let (Some(dom), Some(cod)) = (models.get(arrow.dom), models.get(arrow.cod)) else {
+            return Err(ColimitError::MissingModel(k));
+        };
+        if DblModelMorphism(arrow.map, dom, cod).validate().is_err() {
+            return Err(ColimitError::InvalidMorphism(k));
+        }
+    }
+
+    // Index the generators of all the models.
+    let index = |gens: Vec<Vec<QualifiedName>>| -> HashMap<(usize, QualifiedName), usize> {
+        let pairs = gens
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, xs)| xs.into_iter().map(move |x| (i, x)));
+        pairs.zip(0..).collect()
+    };
+    let ob_gens: Vec<Vec<_>> = models.iter().map(|m| m.ob_generators().collect()).collect();
+    let mor_gens: Vec<Vec<_>> = models.iter().map(|m| m.mor_generators().collect()).collect();
+    let (ob_index, mor_index) = (index(ob_gens.clone()), index(mor_gens.clone()));
+
+    // Identify generators along the arrows.
+    let mut obs = UnionFind::new(ob_index.len());
+    let mut mors = UnionFind::new(mor_index.len());
+    let mut mor_images = Vec::new();
+    for arrow in arrows {
+        let (i, j) = (arrow.dom, arrow.cod);
+        for x in &ob_gens[i] {
+            let y = arrow.map.0.ob_generator_map.apply_to_ref(x).unwrap();
+            obs.union(ob_index[&(i, x.clone())], ob_index[&(j, y)]);
+        }
+        for f in &mor_gens[i] {
+            let path = arrow.map.0.mor_generator_map.apply_to_ref(f).unwrap();
+            match path {
+                Path::Seq(gs) if gs.len() == 1 => {
+                    mors.union(mor_index[&(i, f.clone())], mor_index[&(j, gs.head)]);
+                }
+                path => mor_images.push((i, f, j, path)),
+            }
+        }
+    }
+
+    // Add one generator to the colimit for each equivalence class.
+    let mut colim = DiscreteDblModel::new(theory);
+    let mut ob_names: HashMap<usize, QualifiedName> = HashMap::new();
+    for (i, xs) in ob_gens.iter().enumerate() {
+        for x in xs {
+            let class = obs.find(ob_index[&(i, x.clone())]);
+            if !ob_names.contains_key(&class) {
+                let name = fresh_name(x, &colim);
+                colim.add_ob(name.clone(), models[i].ob_generator_type(x));
+                ob_names.insert(class, name);
+            }
+        }
+    }
+    let mut ob_name =
+        |i: usize, x: &QualifiedName| ob_names[&obs.find(ob_index[&(i, x.clone())])].clone();
+    let mut mor_names: HashMap<usize, QualifiedName> = HashMap::new();
+    for (i, fs) in mor_gens.iter().enumerate() {
+        for f in fs {
+            let class = mors.find(mor_index[&(i, f.clone())]);
+            if !mor_names.contains_key(&class) {
+                let model = &models[i];
+                let dom = ob_name(i, &model.mor_generator_dom(f));
+                let cod = ob_name(i, &model.mor_generator_cod(f));
+                let name = fresh_name(f, &colim);
+                colim.add_mor(name.clone(), dom, cod, model.mor_generator_type(f));
+                mor_names.insert(class, name);
+            }
+        }
+    }
+    let mut mor_name =
+        |i: usize, f: &QualifiedName| mor_names[&mors.find(mor_index[&(i, f.clone())])].clone();
+
+    // Carry along the equations of the models, along with equations between
+    // morphism generators and their images under the arrows.
+    let mut equations = Vec::new();
+    for (i, model) in models.iter().enumerate() {
+        for eq in model.category.equations() {
+            equations.push((i, eq.lhs.clone(), i, eq.rhs.clone()));
+        }
+    }
+    for (i, f, j, path) in mor_images {
+        equations.push((i, Path::single(f.clone()), j, path));
+    }
+    let mut seen = HashSet::new();
+    for (i, lhs, j, rhs) in equations {
+        let lhs: QualifiedPath = lhs.map(|x| ob_name(i, &x), |f| mor_name(i, &f));
+        let rhs: QualifiedPath = rhs.map(|x| ob_name(j, &x), |f| mor_name(j, &f));
+        if lhs != rhs && seen.insert((lhs.clone(), rhs.clone())) {
+            colim.add_equation(PathEq::new(lhs, rhs));
+        }
+    }
+
+    let legs = ob_gens
+        .iter()
+        .zip(&mor_gens)
+        .enumerate()
+        .map(|(i, (xs, fs))| {
+            DiscreteDblModelMapping::new(
+                xs.iter().map(|x| (x.clone(), ob_name(i, x))),
+                fs.iter().map(|f| (f.clone(), Path::single(mor_name(i, f)))),
+            )
+        })
+        .collect();
+    Ok(ModelColimit { model: colim, legs })
+}
+
+/// Computes the pushout of a span of models.
+///
+/// The span consists of an apex model, typically an interface shared by the left
+/// and right models, and model morphisms from the apex into the left and right
+/// models. Generators in the image of the apex are named after the apex.
+pub fn pushout(
+    apex: &DiscreteDblModel,
+    left: &DiscreteDblModel,
+    right: &DiscreteDblModel,
+    left_map: &DiscreteDblModelMapping,
+    right_map: &DiscreteDblModelMapping,
+) -> Result<ModelPushout, ColimitError> {
+    let models = [apex.clone(), left.clone(), right.clone()];
+    let arrows = [
+        ModelArrow { dom: 0, cod: 1, map: left_map },
+        ModelArrow { dom: 0, cod: 2, map: right_map },
+    ];
+    let ModelColimit { model, mut legs } = colimit(&models, &arrows)?;
+    let right = legs.pop().unwrap();
+    let left = legs.pop().unwrap();
+    Ok(ModelPushout { model, left, right })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::theories::th_category;
+    use crate::zero::name;
+
+    #[test]
+    fn pushout_along_interface() {
+        let th = Rc::new(th_category());
+        let ob = |model: &mut DiscreteDblModel, x: &str| model.add_ob(name(x), name("Object"));
+        let mor = |model: &mut DiscreteDblModel, f: &str, x: &str, y: &str| {
+            model.add_mor(name(f), name(x), name(y), Path::Id(name("Object")))
+        };
+
+        // Glue an arrow `a → b` and an arrow `c → d` along a shared object.
+        let mut apex = DiscreteDblModel::new(th.clone());
+        ob(&mut apex, "shared");
+        let mut left = DiscreteDblModel::new(th.clone());
+        ob(&mut left, "a");
+        ob(&mut left, "b");
+        mor(&mut left, "f", "a", "b");
+        let mut right = DiscreteDblModel::new(th.clone());
+        ob(&mut right, "c");
+        ob(&mut right, "d");
+        mor(&mut right, "f", "c", "d");
+
+        let left_map = DiscreteDblModelMapping::new([(name("shared"), name("b"))], []);
+        let right_map = DiscreteDblModelMapping::new([(name("shared"), name("c"))], []);
+        let po = pushout(&apex, &left, &right, &left_map, &right_map).unwrap();
+        assert!(po.model.validate().is_ok());
+        assert_eq!(po.model.ob_generators().count(), 3);
+        assert_eq!(po.model.mor_generators().count(), 2);
+        assert_eq!(po.left.0.ob_generator_map.apply(name("b")), Some(name("shared")));
+        assert_eq!(po.right.0.ob_generator_map.apply(name("c")), Some(name("shared")));
+        let g = po.right.0.mor_generator_map.apply(name("f")).and_then(Path::only).unwrap();
+        assert_eq!(g, name(["f", "1"]));
+        assert_eq!(po.model.mor_generator_dom(&g), name("shared"));
+
+        // A morphism sent to an identity becomes an equation in the colimit.
+        let mut loop_model = DiscreteDblModel::new(th.clone());
+        ob(&mut loop_model, "x");
+        mor(&mut loop_model, "e", "x", "x");
+        let mut point = DiscreteDblModel::new(th.clone());
+        ob(&mut point, "p");
+        let collapse = DiscreteDblModelMapping::new(
+            [(name("x"), name("p"))],
+            [(name("e"), Path::Id(name("p")))],
+        );
+        let arrows = [ModelArrow { dom: 0, cod: 1, map: &collapse }];
+        let colim = colimit(&[loop_model.clone(), point], &arrows).unwrap();
+        assert_eq!(colim.model.ob_generators().count(), 1);
+        assert_eq!(colim.model.category.equations().count(), 1);
+
+        assert_eq!(colimit(&[], &[]), Err(ColimitError::Empty));
+        let bad = DiscreteDblModelMapping::new([(name("x"), name("p"))], []);
+        let arrows = [ModelArrow { dom: 0, cod: 0, map: &bad }];
+        assert_eq!(colimit(&[loop_model], &arrows), Err(ColimitError::InvalidMorphism(0)));
+    }
+}
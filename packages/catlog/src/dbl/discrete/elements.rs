@@ -0,0 +1,152 @@
+//! Category of elements of a model of a discrete double theory.
+//!
+//! A model of a discrete double theory is a [displayed
+//! category](https://ncatlab.org/nlab/show/displayed+category) over the theory.
+//! Its *category of elements*, or Grothendieck construction, is the total
+//! category of the displayed category: its objects and morphisms are those of
+//! the model, and it comes with a projection functor onto the theory sending
+//! each object and morphism to its type. The fibers of the projection are the
+//! elements of the model having a given type, which is what is browsed when
+//! viewing a model as instance data.
+
+use std::rc::Rc;
+
+use super::{model::DiscreteDblModel, theory::DiscreteDblTheory};
+use crate::dbl::model::{DblModel, FpDblModel};
+use crate::one::fp_category::QualifiedFpCategory;
+use crate::one::{Category, FgCategory, FpFunctor, FpFunctorData, Path, QualifiedPath};
+use crate::zero::{Column, IndexedHashColumn, QualifiedName};
+
+/// Data of the projection functor from a category of elements.
+pub type ElementsProjection = FpFunctorData<
+    IndexedHashColumn<QualifiedName, QualifiedName>,
+    IndexedHashColumn<QualifiedName, QualifiedPath>,
+>;
+
+/// The category of elements of a model of a discrete double theory.
+#[derive(Clone, Debug)]
+pub struct CategoryOfElements {
+    theory: Rc<DiscreteDblTheory>,
+    category: QualifiedFpCategory,
+    projection: ElementsProjection,
+}
+
+impl CategoryOfElements {
+    /// Constructs the category of elements of a model.
+    pub fn new(model: &DiscreteDblModel) -> Self {
+        let ob_map = model.ob_generators().map(|x| (x.clone(), model.ob_generator_type(&x)));
+        let mor_map = model.mor_generators().map(|f| (f.clone(), model.mor_generator_type(&f)));
+        Self {
+            theory: model.theory(),
+            category: model.category.clone(),
+            projection: FpFunctorData::new(ob_map.collect(), mor_map.collect()),
+        }
+    }
+
+    /// Gets the finite presentation of the category of elements.
+    pub fn presentation(&self) -> &QualifiedFpCategory {
+        &self.category
+    }
+
+    /// Gets the projection functor onto the theory.
+    pub fn projection(&self) -> FpFunctor<'_, ElementsProjection, QualifiedFpCategory> {
+        self.projection.functor_into(&self.theory.0)
+    }
+
+    /// Iterates over the objects in the fiber over an object type.
+    pub fn fiber_obs(&self, ob_type: &QualifiedName) -> impl Iterator<Item = QualifiedName> {
+        self.projection.ob_generator_map.preimage(ob_type)
+    }
+
+    /// Iterates over the morphism generators in the fiber over a morphism type.
+    pub fn fiber_mor_generators(
+        &self,
+        mor_type: &QualifiedPath,
+    ) -> impl Iterator<Item = QualifiedName> {
+        self.projection.mor_generator_map.preimage(mor_type)
+    }
+}
+
+impl DiscreteDblModel {
+    /// Constructs the category of elements of the model.
+    pub fn elements(&self) -> CategoryOfElements {
+        CategoryOfElements::new(self)
+    }
+}
+
+impl Category for CategoryOfElements {
+    type Ob = QualifiedName;
+    type Mor = QualifiedPath;
+
+    fn has_ob(&self, x: &Self::Ob) -> bool {
+        self.category.has_ob(x)
+    }
+    fn has_mor(&self, m: &Self::Mor) -> bool {
+        self.category.has_mor(m)
+    }
+    fn dom(&self, m: &Self::Mor) -> Self::Ob {
+        self.category.dom(m)
+    }
+    fn cod(&self, m: &Self::Mor) -> Self::Ob {
+        self.category.cod(m)
+    }
+    fn compose(&self, path: Path<Self::Ob, Self::Mor>) -> Self::Mor {
+        self.category.compose(path)
+    }
+    fn morphisms_are_equal(&self, f: Self::Mor, g: Self::Mor) -> bool {
+        self.category.morphisms_are_equal(f, g)
+    }
+}
+
+impl FgCategory for CategoryOfElements {
+    type ObGen = QualifiedName;
+    type MorGen = QualifiedName;
+
+    fn ob_generators(&self) -> impl Iterator<Item = Self::ObGen> {
+        self.category.ob_generators()
+    }
+    fn mor_generators(&self) -> impl Iterator<Item = Self::MorGen> {
+        self.category.mor_generators()
+    }
+    fn mor_generator_dom(&self, f: &Self::MorGen) -> Self::Ob {
+        self.category.mor_generator_dom(f)
+    }
+    fn mor_generator_cod(&self, f: &Self::MorGen) -> Self::Ob {
+        self.category.mor_generator_cod(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbl::model::MutDblModel;
+    use crate::one::CategoryMap;
+    use crate::stdlib::theories::th_schema;
+    use crate::zero::name;
+
+    #[test]
+    fn schema_elements() {
+        let mut model = DiscreteDblModel::new(Rc::new(th_schema()));
+        model.add_ob(name("Person"), name("Entity"));
+        model.add_ob(name("Dept"), name("Entity"));
+        model.add_ob(name("String"), name("AttrType"));
+        model.add_mor(name("works_in"), name("Person"), name("Dept"), Path::Id(name("Entity")));
+        model.add_mor(name("title"), name("Dept"), name("String"), name("Attr").into());
+
+        let elements = model.elements();
+        assert_eq!(elements.ob_generators().count(), 3);
+        assert_eq!(elements.fiber_obs(&name("Entity")).count(), 2);
+        assert_eq!(
+            elements.fiber_mor_generators(&name("Attr").into()).collect::<Vec<_>>(),
+            vec![name("title")]
+        );
+
+        let projection = elements.projection();
+        assert!(projection.validate_on(elements.presentation()).is_ok());
+        assert_eq!(projection.apply_ob(name("String")), Some(name("AttrType")));
+        assert_eq!(
+            projection.apply_mor(Path::pair(name("works_in"), name("title"))),
+            Some(name("Attr").into())
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Functors between discrete double theories.
+
+use std::rc::Rc;
+
+use nonempty::NonEmpty;
+
+use super::theory::DiscreteDblTheory;
+use crate::dbl::functor::{DblFunctor, InvalidDblFunctor};
+use crate::one::{CategoryMap, FpFunctorData, InvalidFpFunctor, Path, QualifiedPath};
+use crate::validate::{self, Validate};
+use crate::zero::{HashColumn, QualifiedName};
+
+/// Data of a map between discrete double theories.
+///
+/// A discrete double theory is essentially a finitely presented category, so a
+/// map between such theories is determined by its values on the generating object
+/// types and morphism types.
+pub type DiscreteDblTheoryMap = FpFunctorData<
+    HashColumn<QualifiedName, QualifiedName>,
+    HashColumn<QualifiedName, QualifiedPath>,
+>;
+
+/// A functor between discrete double theories.
+///
+/// Since discrete double theories have only identity operations, such a functor
+/// amounts to a functor between the underlying categories of object and morphism
+/// types.
+#[derive(Clone, Debug)]
+pub struct DiscreteDblFunctor {
+    dom: Rc<DiscreteDblTheory>,
+    cod: Rc<DiscreteDblTheory>,
+    map: DiscreteDblTheoryMap,
+}
+
+impl DiscreteDblFunctor {
+    /// Constructs a functor from its domain, codomain, and underlying data.
+    pub fn new(
+        dom: Rc<DiscreteDblTheory>,
+        cod: Rc<DiscreteDblTheory>,
+        map: DiscreteDblTheoryMap,
+    ) -> Self {
+        Self { dom, cod, map }
+    }
+
+    /// Gets the underlying data of the functor.
+    pub fn map(&self) -> &DiscreteDblTheoryMap {
+        &self.map
+    }
+
+    /// Gets a shared reference to the domain theory.
+    pub fn dom_rc(&self) -> Rc<DiscreteDblTheory> {
+        self.dom.clone()
+    }
+
+    /// Gets a shared reference to the codomain theory.
+    pub fn cod_rc(&self) -> Rc<DiscreteDblTheory> {
+        self.cod.clone()
+    }
+
+    /// Iterates over failures of the map to be a functor.
+    pub fn iter_invalid(&self) -> impl Iterator<Item = InvalidDblFunctor> + '_ {
+        self.map
+            .functor_into(&self.cod.0)
+            .iter_invalid_on(&self.dom.0)
+            .map(|err| match err {
+                InvalidFpFunctor::ObGen(x) => InvalidDblFunctor::ObType(x),
+                InvalidFpFunctor::MorGen(m) => InvalidDblFunctor::MorType(m),
+                InvalidFpFunctor::Dom(m) => InvalidDblFunctor::SrcType(m),
+                InvalidFpFunctor::Cod(m) => InvalidDblFunctor::TgtType(m),
+                InvalidFpFunctor::Eq(id) => InvalidDblFunctor::MorTypeEq(id),
+            })
+    }
+}
+
+impl DblFunctor for DiscreteDblFunctor {
+    type DomTheory = DiscreteDblTheory;
+    type CodTheory = DiscreteDblTheory;
+
+    fn dom(&self) -> &Self::DomTheory {
+        &self.dom
+    }
+    fn cod(&self) -> &Self::CodTheory {
+        &self.cod
+    }
+
+    fn apply_ob_type(&self, x: QualifiedName) -> Option<QualifiedName> {
+        self.map.functor_into(&self.cod.0).apply_ob(x)
+    }
+    fn apply_mor_type(&self, m: QualifiedPath) -> Option<QualifiedPath> {
+        self.map.functor_into(&self.cod.0).apply_mor(m)
+    }
+
+    /// Operations on objects in a discrete theory are identities, so they are
+    /// mapped like object types.
+    fn apply_ob_op(&self, f: QualifiedName) -> Option<QualifiedName> {
+        self.apply_ob_type(f)
+    }
+
+    /// Operations on morphisms in a discrete theory are paths of morphism types,
+    /// so they are mapped by mapping each morphism type in the path.
+    fn apply_mor_op(
+        &self,
+        α: Path<QualifiedName, QualifiedPath>,
+    ) -> Option<Path<QualifiedName, QualifiedPath>> {
+        α.partial_map(|x| self.apply_ob_type(x), |m| self.apply_mor_type(m))
+    }
+}
+
+impl Validate for DiscreteDblFunctor {
+    type ValidationError = InvalidDblFunctor;
+
+    fn validate(&self) -> Result<(), NonEmpty<Self::ValidationError>> {
+        validate::wrap_errors(self.iter_invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::{theories::*, theory_morphisms::*};
+    use crate::zero::name;
+
+    #[test]
+    fn discrete_dbl_functor() {
+        let (th_cat, th_sch) = (Rc::new(th_category()), Rc::new(th_schema()));
+        let incl = DiscreteDblFunctor::new(th_cat.clone(), th_sch.clone(), th_category_to_schema());
+        assert!(incl.validate().is_ok());
+        assert_eq!(incl.apply_ob_type(name("Object")), Some(name("Entity")));
+        assert_eq!(incl.apply_mor_type(Path::Id(name("Object"))), Some(Path::Id(name("Entity"))));
+
+        let proj = DiscreteDblFunctor::new(th_sch.clone(), th_cat, th_schema_to_category());
+        assert!(proj.validate().is_ok());
+        assert_eq!(proj.apply_mor_type(name("Attr").into()), Some(Path::Id(name("Object"))));
+        let cell = Path::pair(Path::Id(name("Entity")), name("Attr").into());
+        assert_eq!(
+            proj.apply_mor_op(cell),
+            Some(Path::pair(Path::Id(name("Object")), Path::Id(name("Object"))))
+        );
+
+        // Attributes cannot be sent to morphisms between entities.
+        let map = FpFunctorData::new(
+            HashColumn::from_iter([
+                (name("Entity"), name("Entity")),
+                (name("AttrType"), name("AttrType")),
+            ]),
+            HashColumn::from_iter([(name("Attr"), Path::Id(name("Entity")))]),
+        );
+        let invalid = DiscreteDblFunctor::new(th_sch.clone(), th_sch, map);
+        assert_eq!(
+            invalid.validate(),
+            Err(NonEmpty::new(InvalidDblFunctor::TgtType(name("Attr"))))
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Incremental maintenance of models of discrete double theories.
+//!
+//! Models are usually elaborated from notebooks that are edited collaboratively
+//! through Automerge. Rather than re-elaborating the whole notebook after every
+//! change, an [`IncrementalModel`] consumes a stream of [document
+//! patches](DocumentPatch) and keeps an in-memory model up to date, reporting
+//! after each patch how the set of validation failures has changed.
+//!
+//! Inserting a cell, or updating the domain or codomain of a morphism, is
+//! applied to the model in place. Since models do not support deleting
+//! generators, other patches rebuild the model from the current cells, which is
+//! still much cheaper than reparsing the document. Cells that do not declare a
+//! generator of a discrete model, such as text cells, equations, and
+//! declarations with types outside a discrete theory, are ignored.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use catcolab_document_types::current::{
+    DocumentPatch, ModelJudgment, MorType, Notebook, NotebookCell, Ob, ObType, PatchError,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::{model::DiscreteDblModel, theory::DiscreteDblTheory};
+use crate::dbl::model::{InvalidDblModel, MutDblModel};
+use crate::one::{Path, QualifiedPath};
+use crate::zero::QualifiedName;
+
+/// A generator of a model declared by a notebook cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ModelDecl {
+    Ob {
+        name: QualifiedName,
+        ob_type: QualifiedName,
+    },
+    Mor {
+        name: QualifiedName,
+        dom: Option<QualifiedName>,
+        cod: Option<QualifiedName>,
+        mor_type: QualifiedPath,
+    },
+}
+
+impl ModelDecl {
+    /// Gets the generator declared by a cell, if any.
+    fn from_cell(cell: &NotebookCell<ModelJudgment>) -> Option<Self> {
+        let NotebookCell::Formal { content, .. } = cell else {
+            return None;
+        };
+        match content {
+            ModelJudgment::Object(decl) => {
+                let ObType::Basic(ob_type) = decl.ob_type else {
+                    return None;
+                };
+                Some(ModelDecl::Ob {
+                    name: decl.id.into(),
+                    ob_type: ob_type.into(),
+                })
+            }
+            ModelJudgment::Morphism(decl) => Some(ModelDecl::Mor {
+                name: decl.id.into(),
+                dom: decl.dom.as_ref().and_then(ob_name),
+                cod: decl.cod.as_ref().and_then(ob_name),
+                mor_type: mor_type_path(&decl.mor_type)?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Tries to replace another declaration in the model by only adding data
+    /// to it.
+    ///
+    /// Returns whether the update succeeded, which happens when the
+    /// declaration changes only the domain or codomain of a morphism, without
+    /// unsetting them.
+    fn update_in_place(&self, old: &ModelDecl, model: &mut DiscreteDblModel) -> bool {
+        if self == old {
+            return true;
+        }
+        let (
+            ModelDecl::Mor { name, dom, cod, mor_type },
+            ModelDecl::Mor {
+                name: old_name,
+                dom: old_dom,
+                cod: old_cod,
+                mor_type: old_mor_type,
+            },
+        ) = (self, old)
+        else {
+            return false;
+        };
+        if name != old_name
+            || mor_type != old_mor_type
+            || (dom.is_none() && old_dom.is_some())
+            || (cod.is_none() && old_cod.is_some())
+        {
+            return false;
+        }
+        if let Some(dom) = dom {
+            model.set_dom(name.clone(), dom.clone());
+        }
+        if let Some(cod) = cod {
+            model.set_cod(name.clone(), cod.clone());
+        }
+        true
+    }
+
+    fn add_to(&self, model: &mut DiscreteDblModel) {
+        match self {
+            ModelDecl::Ob { name, ob_type } => model.add_ob(name.clone(), ob_type.clone()),
+            ModelDecl::Mor { name, dom, cod, mor_type } => {
+                model.make_mor(name.clone(), mor_type.clone());
+                if let Some(dom) = dom {
+                    model.set_dom(name.clone(), dom.clone());
+                }
+                if let Some(cod) = cod {
+                    model.set_cod(name.clone(), cod.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Gets the name of a basic object, treating other objects as unset.
+fn ob_name(ob: &Ob) -> Option<QualifiedName> {
+    match ob {
+        Ob::Basic(name) => QualifiedName::deserialize_str(name).ok(),
+        _ => None,
+    }
+}
+
+/// Converts a morphism type into a path in a discrete double theory.
+fn mor_type_path(mor_type: &MorType) -> Option<QualifiedPath> {
+    match mor_type {
+        MorType::Basic(id) => Some(Path::single((*id).into())),
+        MorType::Hom(ob_type) => match ob_type.as_ref() {
+            ObType::Basic(id) => Some(Path::Id((*id).into())),
+            _ => None,
+        },
+        MorType::Composite(fs) => {
+            let fs: Option<Vec<_>> = fs.iter().map(mor_type_path).collect();
+            Some(Path::from_vec(fs?)?.flatten())
+        }
+        MorType::ModeApp { .. } => None,
+    }
+}
+
+/// Change in the validation failures of a model after a patch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ValidationDelta {
+    /// Failures that did not occur before the patch.
+    pub added: Vec<InvalidDblModel>,
+
+    /// Failures that no longer occur after the patch.
+    pub resolved: Vec<InvalidDblModel>,
+}
+
+impl ValidationDelta {
+    /// Whether the validation failures are unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.resolved.is_empty()
+    }
+}
+
+/// A model of a discrete double theory maintained incrementally from a
+/// notebook.
+#[derive(Debug)]
+pub struct IncrementalModel {
+    theory: Rc<DiscreteDblTheory>,
+    notebook: Notebook<ModelJudgment>,
+    model: DiscreteDblModel,
+    errors: Vec<InvalidDblModel>,
+}
+
+impl IncrementalModel {
+    /// Creates an empty model of the given theory.
+    pub fn new(theory: Rc<DiscreteDblTheory>) -> Self {
+        Self {
+            model: DiscreteDblModel::new(theory.clone()),
+            theory,
+            notebook: Notebook {
+                cell_contents: HashMap::new(),
+                cell_order: Vec::new(),
+            },
+            errors: Default::default(),
+        }
+    }
+
+    /// Gets the current model.
+    pub fn model(&self) -> &DiscreteDblModel {
+        &self.model
+    }
+
+    /// Gets the current validation failures of the model.
+    pub fn errors(&self) -> &[InvalidDblModel] {
+        &self.errors
+    }
+
+    /// Gets the notebook that the model is elaborated from.
+    pub fn notebook(&self) -> &Notebook<ModelJudgment> {
+        &self.notebook
+    }
+
+    /// Applies a patch, returning the change in validation failures.
+    ///
+    /// The notebook and model are left unchanged if the patch cannot be
+    /// applied.
+    pub fn apply(
+        &mut self,
+        patch: DocumentPatch<ModelJudgment>,
+    ) -> Result<ValidationDelta, PatchError> {
+        let id = patch.cell_id();
+        let decl = |notebook: &Notebook<ModelJudgment>| {
+            notebook.cell_contents.get(&id).and_then(ModelDecl::from_cell)
+        };
+        match patch.apply(&mut self.notebook)? {
+            // The patch inserted the cell.
+            DocumentPatch::DeleteCell { .. } => {
+                if let Some(decl) = decl(&self.notebook) {
+                    decl.add_to(&mut self.model);
+                }
+            }
+            // The patch deleted the cell.
+            DocumentPatch::InsertCell { cell, .. } => {
+                if ModelDecl::from_cell(&cell).is_some() {
+                    self.rebuild();
+                }
+            }
+            // The patch updated the cell.
+            DocumentPatch::UpdateCell { cell } => {
+                let in_place = match (decl(&self.notebook), ModelDecl::from_cell(&cell)) {
+                    (Some(decl), Some(old)) => decl.update_in_place(&old, &mut self.model),
+                    (Some(decl), None) => {
+                        decl.add_to(&mut self.model);
+                        true
+                    }
+                    (None, old) => old.is_none(),
+                };
+                if !in_place {
+                    self.rebuild();
+                }
+            }
+            DocumentPatch::MoveCell { .. } => {}
+        }
+        Ok(self.revalidate())
+    }
+
+    /// Applies a sequence of patches, returning the overall change in
+    /// validation failures.
+    ///
+    /// Stops at the first patch that cannot be applied, keeping the effect of
+    /// the patches before it.
+    pub fn apply_all(
+        &mut self,
+        patches: impl IntoIterator<Item = DocumentPatch<ModelJudgment>>,
+    ) -> Result<ValidationDelta, PatchError> {
+        let before = self.errors.clone();
+        for patch in patches {
+            self.apply(patch)?;
+        }
+        Ok(diff(&before, &self.errors))
+    }
+
+    fn rebuild(&mut self) {
+        self.model = DiscreteDblModel::new(self.theory.clone());
+        let cells = self.notebook.cell_order.iter().map(|id| &self.notebook.cell_contents[id]);
+        for decl in cells.filter_map(ModelDecl::from_cell) {
+            decl.add_to(&mut self.model);
+        }
+    }
+
+    fn revalidate(&mut self) -> ValidationDelta {
+        let errors: Vec<_> = self.model.iter_invalid().collect();
+        let delta = diff(&self.errors, &errors);
+        self.errors = errors;
+        delta
+    }
+}
+
+fn diff(before: &[InvalidDblModel], after: &[InvalidDblModel]) -> ValidationDelta {
+    ValidationDelta {
+        added: after.iter().filter(|err| !before.contains(err)).cloned().collect(),
+        resolved: before.iter().filter(|err| !after.contains(err)).cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use catcolab_document_types::current::{MorDecl, ObDecl};
+    use ustr::ustr;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::stdlib::theories::th_schema;
+
+    #[test]
+    fn incremental_validation() {
+        let (x, y, f) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        let entity = |id: Uuid| NotebookCell::Formal {
+            id,
+            content: ModelJudgment::Object(ObDecl {
+                name: String::new(),
+                id,
+                ob_type: ObType::Basic(ustr("Entity")),
+            }),
+        };
+        let f_cell = |cod: Option<Uuid>| NotebookCell::Formal {
+            id: f,
+            content: ModelJudgment::Morphism(MorDecl {
+                name: "f".into(),
+                id: f,
+                mor_type: MorType::Hom(Box::new(ObType::Basic(ustr("Entity")))),
+                dom: Some(Ob::Basic(x.to_string())),
+                cod: cod.map(|id| Ob::Basic(id.to_string())),
+            }),
+        };
+        let insert = |index, cell| DocumentPatch::InsertCell { index, cell };
+
+        let mut model = IncrementalModel::new(Rc::new(th_schema()));
+        assert!(model.apply(insert(0, entity(x))).unwrap().is_empty());
+
+        let delta = model.apply(insert(1, f_cell(Some(y)))).unwrap();
+        assert_eq!(delta.added, vec![InvalidDblModel::Cod(f.into())]);
+
+        let delta = model.apply(insert(1, entity(y))).unwrap();
+        assert_eq!(delta.resolved, vec![InvalidDblModel::Cod(f.into())]);
+        assert!(model.errors().is_empty());
+
+        // Unsetting the codomain requires rebuilding the model.
+        let delta = model.apply(DocumentPatch::UpdateCell { cell: f_cell(None) }).unwrap();
+        assert_eq!(delta.added, vec![InvalidDblModel::Cod(f.into())]);
+        assert_eq!(model.model().get_cod(&f.into()), None);
+
+        let delta = model
+            .apply_all([
+                DocumentPatch::UpdateCell { cell: f_cell(Some(y)) },
+                DocumentPatch::MoveCell { id: y, index: 0 },
+                DocumentPatch::DeleteCell { id: y },
+            ])
+            .unwrap();
+        assert!(delta.is_empty());
+        assert_eq!(model.notebook().cell_order, vec![x, f]);
+
+        // Patches that cannot be applied leave the model unchanged.
+        assert_eq!(
+            model.apply(DocumentPatch::DeleteCell { id: y }),
+            Err(PatchError::UnknownCell(y))
+        );
+        assert_eq!(model.errors(), &[InvalidDblModel::Cod(f.into())]);
+    }
+}
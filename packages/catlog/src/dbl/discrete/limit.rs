@@ -0,0 +1,286 @@
+//! Limits of models of discrete double theories.
+//!
+//! A model of a discrete double theory is a category displayed over the theory,
+//! so the product of two models is their fiber product over the theory: its
+//! objects of a given type are pairs of objects of that type, and likewise for
+//! its morphisms. At the level of presentations, the product of models `M` and
+//! `N` is generated by
+//!
+//! - a pair `(x, y)` for each object generator `x` of `M` and `y` of `N` of the
+//!   same type;
+//! - a pair `(f, y)` for each morphism generator `f` of `M` of an identity type
+//!   and each object generator `y` of `N` of the corresponding object type, and
+//!   symmetrically a pair `(x, g)`;
+//! - a pair `(f, g)` for each pair of morphism generators of the same non-identity
+//!   type.
+//!
+//! The pairs of morphisms of identity types commute with each other, and the
+//! equations of `M` and `N` between morphisms of identity types hold in each
+//! fiber of the product. Generators of the product are named by concatenating
+//! the names of the generators in the pair. Morphism generators are assumed to
+//! have basic types, so that a generator of a composite type is paired only
+//! with generators of the same type.
+//!
+//! Products of more than two models are computed by iterated binary products.
+
+use std::rc::Rc;
+
+use thiserror::Error;
+
+use super::model::DiscreteDblModel;
+use super::model_morphism::DiscreteDblModelMapping;
+use crate::dbl::model::*;
+use crate::one::{CategoryMap, FgCategory, Path, PathEq, QualifiedPath};
+use crate::zero::{Column, QualifiedName};
+
+/// The product of a finite list of models.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelProduct {
+    /// The product model.
+    pub model: DiscreteDblModel,
+
+    /// Projections from the product onto each of the factors.
+    pub projections: Vec<DiscreteDblModelMapping>,
+}
+
+/// An error in computing a limit of models.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum LimitError {
+    /// There are no models, so the theory of the limit is unknown.
+    #[error("Limit should have at least one model")]
+    Empty,
+
+    /// A model has a different theory from the first model.
+    #[error("Model {0} has a different theory")]
+    TheoryMismatch(usize),
+}
+
+/// Computes the product of a nonempty list of models of the same theory.
+pub fn product(models: &[DiscreteDblModel]) -> Result<ModelProduct, LimitError> {
+    let (first, rest) = models.split_first().ok_or(LimitError::Empty)?;
+    let theory = first.theory();
+    if let Some(i) = models.iter().position(|model| !Rc::ptr_eq(&model.theory(), &theory)) {
+        return Err(LimitError::TheoryMismatch(i));
+    }
+
+    let mut model = first.clone();
+    let mut projections = vec![DiscreteDblModelMapping::new(
+        first.ob_generators().map(|x| (x.clone(), x)),
+        first.mor_generators().map(|f| (f.clone(), Path::single(f))),
+    )];
+    for factor in rest {
+        let (prod, left, right) = binary_product(&model, factor);
+        projections = projections
+            .iter()
+            .zip(models)
+            .map(|(proj, cod)| compose(&left, proj, cod))
+            .collect();
+        projections.push(right);
+        model = prod;
+    }
+    Ok(ModelProduct { model, projections })
+}
+
+/// Computes the product of two models, with its two projections.
+fn binary_product(
+    m: &DiscreteDblModel,
+    n: &DiscreteDblModel,
+) -> (DiscreteDblModel, DiscreteDblModelMapping, DiscreteDblModelMapping) {
+    let mut prod = DiscreteDblModel::new(m.theory());
+    let mut left = DiscreteDblModelMapping::default();
+    let mut right = DiscreteDblModelMapping::default();
+
+    for x in m.ob_generators() {
+        let typ = m.ob_generator_type(&x);
+        for y in n.ob_generators_with_type(&typ) {
+            let xy = pair_name(&x, &y);
+            prod.add_ob(xy.clone(), typ.clone());
+            left.assign_ob(xy.clone(), x.clone());
+            right.assign_ob(xy, y);
+        }
+    }
+
+    let mut add_mor = |name: QualifiedName,
+                       (dom, cod): (QualifiedName, QualifiedName),
+                       typ: QualifiedPath,
+                       (l, r): (QualifiedPath, QualifiedPath)| {
+        prod.add_mor(name.clone(), dom, cod, typ);
+        left.assign_mor(name.clone(), l);
+        right.assign_mor(name, r);
+    };
+    for f in m.mor_generators() {
+        let (x, x2) = (m.mor_generator_dom(&f), m.mor_generator_cod(&f));
+        match m.mor_generator_type(&f) {
+            Path::Id(typ) => {
+                for y in n.ob_generators_with_type(&typ) {
+                    add_mor(
+                        pair_name(&f, &y),
+                        (pair_name(&x, &y), pair_name(&x2, &y)),
+                        Path::Id(typ.clone()),
+                        (Path::single(f.clone()), Path::Id(y)),
+                    );
+                }
+            }
+            typ => {
+                for g in n.mor_generators_with_type(&typ) {
+                    let (y, y2) = (n.mor_generator_dom(&g), n.mor_generator_cod(&g));
+                    add_mor(
+                        pair_name(&f, &g),
+                        (pair_name(&x, &y), pair_name(&x2, &y2)),
+                        typ.clone(),
+                        (Path::single(f.clone()), Path::single(g)),
+                    );
+                }
+            }
+        }
+    }
+    for g in n.mor_generators() {
+        let Path::Id(typ) = n.mor_generator_type(&g) else {
+            continue;
+        };
+        let (y, y2) = (n.mor_generator_dom(&g), n.mor_generator_cod(&g));
+        for x in m.ob_generators_with_type(&typ) {
+            add_mor(
+                pair_name(&x, &g),
+                (pair_name(&x, &y), pair_name(&x, &y2)),
+                Path::Id(typ.clone()),
+                (Path::Id(x), Path::single(g.clone())),
+            );
+        }
+    }
+
+    // Morphisms of identity types in the two factors commute.
+    let identity_typed = |model: &DiscreteDblModel| -> Vec<_> {
+        model
+            .mor_generators()
+            .filter(|f| matches!(model.mor_generator_type(f), Path::Id(_)))
+            .map(|f| (model.mor_generator_dom(&f), model.mor_generator_cod(&f), f))
+            .collect()
+    };
+    let (m_mors, n_mors) = (identity_typed(m), identity_typed(n));
+    for (x, x2, f) in &m_mors {
+        for (y, y2, g) in &n_mors {
+            if m.ob_generator_type(x) == n.ob_generator_type(y) {
+                let lhs = Path::pair(pair_name(f, y), pair_name(x2, g));
+                let rhs = Path::pair(pair_name(x, g), pair_name(f, y2));
+                prod.add_equation(PathEq::new(lhs, rhs));
+            }
+        }
+    }
+
+    // Equations in each factor hold in every fiber of the product.
+    let is_identity_typed = |model: &DiscreteDblModel, path: &QualifiedPath| {
+        path.iter().all(|f| matches!(model.mor_generator_type(f), Path::Id(_)))
+    };
+    for eq in m.category.equations() {
+        if is_identity_typed(m, &eq.lhs) && is_identity_typed(m, &eq.rhs) {
+            let typ = m.ob_generator_type(&eq.src(m.generating_graph()));
+            for y in n.ob_generators_with_type(&typ) {
+                let lift = |path: &QualifiedPath| {
+                    path.clone().map(|x| pair_name(&x, &y), |f| pair_name(&f, &y))
+                };
+                prod.add_equation(PathEq::new(lift(&eq.lhs), lift(&eq.rhs)));
+            }
+        }
+    }
+    for eq in n.category.equations() {
+        if is_identity_typed(n, &eq.lhs) && is_identity_typed(n, &eq.rhs) {
+            let typ = n.ob_generator_type(&eq.src(n.generating_graph()));
+            for x in m.ob_generators_with_type(&typ) {
+                let lift = |path: &QualifiedPath| {
+                    path.clone().map(|y| pair_name(&x, &y), |g| pair_name(&x, &g))
+                };
+                prod.add_equation(PathEq::new(lift(&eq.lhs), lift(&eq.rhs)));
+            }
+        }
+    }
+
+    (prod, left, right)
+}
+
+/// Composes two model mappings, the first followed by the second.
+fn compose(
+    first: &DiscreteDblModelMapping,
+    second: &DiscreteDblModelMapping,
+    cod: &DiscreteDblModel,
+) -> DiscreteDblModelMapping {
+    let second = second.functor_into(cod);
+    DiscreteDblModelMapping::new(
+        first
+            .0
+            .ob_generator_map
+            .iter()
+            .filter_map(|(x, y)| Some((x, second.apply_ob(y.clone())?))),
+        first
+            .0
+            .mor_generator_map
+            .iter()
+            .filter_map(|(f, path)| Some((f, second.apply_mor(path.clone())?))),
+    )
+}
+
+/// Names a pair of generators by concatenating their names.
+pub(super) fn pair_name(x: &QualifiedName, y: &QualifiedName) -> QualifiedName {
+    x.segments().chain(y.segments()).copied().collect::<Vec<_>>().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbl::discrete::model_morphism::DblModelMorphism;
+    use crate::stdlib::theories::{th_category, th_schema};
+    use crate::validate::Validate;
+    use crate::zero::name;
+
+    #[test]
+    fn product_of_models() {
+        let th = Rc::new(th_category());
+        let arrow = |f: &str, x: &str, y: &str| {
+            let mut model = DiscreteDblModel::new(th.clone());
+            model.add_ob(name(x), name("Object"));
+            model.add_ob(name(y), name("Object"));
+            model.add_mor(name(f), name(x), name(y), Path::Id(name("Object")));
+            model
+        };
+
+        // The product of two arrows is a commutative square.
+        let (m, n) = (arrow("f", "a", "b"), arrow("g", "c", "d"));
+        let ModelProduct { model, projections } = product(&[m.clone(), n.clone()]).unwrap();
+        assert!(model.validate().is_ok());
+        assert_eq!(model.ob_generators().count(), 4);
+        assert_eq!(model.mor_generators().count(), 4);
+        assert_eq!(model.category.equations().count(), 1);
+        assert_eq!(projections.len(), 2);
+        assert!(DblModelMorphism(&projections[0], &model, &m).validate().is_ok());
+        assert!(DblModelMorphism(&projections[1], &model, &n).validate().is_ok());
+        assert_eq!(
+            projections[1].0.mor_generator_map.apply(name(["f", "c"])),
+            Some(Path::Id(name("c")))
+        );
+
+        // The product of three arrows is a commutative cube.
+        let o = arrow("h", "e", "k");
+        let ModelProduct { model, projections } = product(&[m, n, o.clone()]).unwrap();
+        assert!(model.validate().is_ok());
+        assert_eq!(model.ob_generators().count(), 8);
+        assert_eq!(model.mor_generators().count(), 12);
+        assert!(
+            DblModelMorphism(&projections[0], &model, &arrow("f", "a", "b"))
+                .validate()
+                .is_ok()
+        );
+        assert!(DblModelMorphism(&projections[2], &model, &o).validate().is_ok());
+
+        // Objects are paired only with objects of the same type.
+        let mut schema = DiscreteDblModel::new(Rc::new(th_schema()));
+        schema.add_ob(name("x"), name("Entity"));
+        schema.add_ob(name("v"), name("AttrType"));
+        schema.add_mor(name("attr"), name("x"), name("v"), name("Attr").into());
+        let ModelProduct { model, .. } = product(&[schema.clone(), schema]).unwrap();
+        assert!(model.validate().is_ok());
+        assert_eq!(model.ob_generators().count(), 2);
+        assert_eq!(model.mor_generators().count(), 1);
+
+        assert_eq!(product(&[]), Err(LimitError::Empty));
+    }
+}
@@ -1,11 +1,34 @@
 //! Doctrine of discrete double theories.
 
+pub mod automorphisms;
+pub mod canonical;
+pub mod colimit;
+pub mod elements;
+pub mod functor;
+pub mod incremental;
+pub mod limit;
 pub mod model;
 pub mod model_diagram;
+pub mod model_migration;
 pub mod model_morphism;
+pub mod repair;
+pub mod rewriting;
+pub mod rewriting_analysis;
+pub mod stratify;
 pub mod theory;
 
+pub use automorphisms::*;
+pub use colimit::*;
+pub use elements::*;
+pub use functor::*;
+pub use incremental::*;
+pub use limit::*;
 pub use model::*;
 pub use model_diagram::*;
+pub use model_migration::*;
 pub use model_morphism::*;
+pub use repair::*;
+pub use rewriting::*;
+pub use rewriting_analysis::*;
+pub use stratify::*;
 pub use theory::*;
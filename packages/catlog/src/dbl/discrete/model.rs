@@ -1,11 +1,13 @@
 //! Models of discrete double theories.
 
+use std::collections::HashSet;
 use std::rc::Rc;
 
 use derivative::Derivative;
 
 use super::theory::DiscreteDblTheory;
 use crate::dbl::{category::*, model::*, theory::DblTheory};
+use crate::one::commutativity::{self, ParallelPaths};
 use crate::one::{fp_category::QualifiedFpCategory, *};
 use crate::tt::util::pretty::*;
 use crate::validate::{self, Validate};
@@ -53,6 +55,19 @@ impl DiscreteDblModel {
         self.category.add_equation(eq);
     }
 
+    /// Enumerates pairs of parallel paths between two objects in the model.
+    ///
+    /// Reports which pairs commute under the equations of the model. See
+    /// [`parallel_paths`](crate::one::commutativity::parallel_paths).
+    pub fn parallel_paths(
+        &self,
+        x: &QualifiedName,
+        y: &QualifiedName,
+        max_length: usize,
+    ) -> Vec<ParallelPaths<QualifiedName, QualifiedName>> {
+        commutativity::parallel_paths(&self.category, x, y, max_length)
+    }
+
     /// Iterates over failures of model to be well defined.
     pub fn iter_invalid(&self) -> impl Iterator<Item = InvalidDblModel> + '_ {
         type Invalid = InvalidDblModel;
@@ -61,29 +76,44 @@ impl DiscreteDblModel {
             InvalidFpCategory::Cod(e) => Invalid::Cod(e),
             InvalidFpCategory::Eqn(eq, errs) => Invalid::Eqn(Some(eq), errs.map(|e| e.into())),
         });
-        let ob_type_errors = self.category.ob_generators().filter_map(|x| {
-            if self.theory.has_ob_type(&self.ob_type(&x)) {
+        // Basic types are checked against the theory's table of types, falling
+        // back to the theory itself for composite morphism types.
+        let table = self.theory.type_table();
+        let ob_type_errors = self.category.ob_generators().filter_map(move |x| {
+            if table.ob_type_index(&self.ob_type(&x)).is_some() {
                 None
             } else {
                 Some(Invalid::ObType(x))
             }
         });
-        let mor_type_errors = self.category.mor_generators().flat_map(|e| {
+        let mor_type_errors = self.category.mor_generators().flat_map(move |e| {
             let mut errs = Vec::new();
             let mor_type = self.mor_generator_type(&e);
-            if self.theory.has_mor_type(&mor_type) {
-                if self.category.get_dom(&e).is_some_and(|x| {
-                    self.has_ob(x) && self.ob_type(x) != self.theory.src(&mor_type)
-                }) {
-                    errs.push(Invalid::DomType(e.clone()));
+            let (src, tgt) = match table.mor_type_index(&mor_type) {
+                Some(m) => {
+                    (table.ob_type(table.src(m)).clone(), table.ob_type(table.tgt(m)).clone())
                 }
-                if self.category.get_cod(&e).is_some_and(|x| {
-                    self.has_ob(x) && self.ob_type(x) != self.theory.tgt(&mor_type)
-                }) {
-                    errs.push(Invalid::CodType(e));
+                None if self.theory.has_mor_type(&mor_type) => {
+                    (self.theory.src(&mor_type), self.theory.tgt(&mor_type))
                 }
-            } else {
-                errs.push(Invalid::MorType(e));
+                None => {
+                    errs.push(Invalid::MorType(e));
+                    return errs.into_iter();
+                }
+            };
+            if self
+                .category
+                .get_dom(&e)
+                .is_some_and(|x| self.has_ob(x) && self.ob_type(x) != src)
+            {
+                errs.push(Invalid::DomType(e.clone()));
+            }
+            if self
+                .category
+                .get_cod(&e)
+                .is_some_and(|x| self.has_ob(x) && self.ob_type(x) != tgt)
+            {
+                errs.push(Invalid::CodType(e));
             }
             errs.into_iter()
         });
@@ -110,6 +140,42 @@ impl DiscreteDblModel {
         }
     }
 
+    /// Extracts the submodel induced by a set of objects.
+    ///
+    /// The induced submodel contains the given object generators, every
+    /// morphism generator whose domain and codomain are both among them, and
+    /// every equation between paths of those morphisms. Objects not in the model
+    /// are ignored.
+    pub fn induced_submodel(&self, obs: impl IntoIterator<Item = QualifiedName>) -> Self {
+        let obs: HashSet<_> = obs.into_iter().filter(|x| self.has_ob(x)).collect();
+        let mut submodel = Self::new(self.theory.clone());
+        for x in self.ob_generators().filter(|x| obs.contains(x)) {
+            submodel.add_ob(x.clone(), self.ob_generator_type(&x));
+        }
+
+        let mut mors = HashSet::new();
+        for f in self.mor_generators() {
+            if let (Some(x), Some(y)) = (self.get_dom(&f), self.get_cod(&f))
+                && obs.contains(x)
+                && obs.contains(y)
+            {
+                submodel.add_mor(f.clone(), x.clone(), y.clone(), self.mor_generator_type(&f));
+                mors.insert(f);
+            }
+        }
+
+        let in_submodel = |path: &QualifiedPath| match path {
+            Path::Id(x) => obs.contains(x),
+            Path::Seq(fs) => fs.iter().all(|f| mors.contains(f)),
+        };
+        for eq in self.category.equations() {
+            if in_submodel(&eq.lhs) && in_submodel(&eq.rhs) {
+                submodel.add_equation(eq.clone());
+            }
+        }
+        submodel
+    }
+
     /// Migrate model forward along a map between discrete double theories.
     pub fn push_forward<F>(&mut self, f: &F, new_theory: Rc<DiscreteDblTheory>)
     where
@@ -145,6 +211,9 @@ impl Category for DiscreteDblModel {
     fn compose(&self, path: Path<Self::Ob, Self::Mor>) -> Self::Mor {
         self.category.compose(path)
     }
+    fn morphisms_are_equal(&self, f: Self::Mor, g: Self::Mor) -> bool {
+        self.category.morphisms_are_equal(f, g)
+    }
 }
 
 impl FgCategory for DiscreteDblModel {
@@ -311,6 +380,34 @@ mod tests {
         assert_eq!(model.validate(), Err(nonempty![InvalidDblModel::CodType(name("b"))]));
     }
 
+    #[test]
+    fn induced_submodel() {
+        let th = Rc::new(th_schema());
+        let mut model = DiscreteDblModel::new(th.clone());
+        model.add_ob(name("a"), name("Entity"));
+        model.add_ob(name("b"), name("Entity"));
+        model.add_ob(name("c"), name("Entity"));
+        model.add_mor(name("f"), name("a"), name("b"), Path::Id(name("Entity")));
+        model.add_mor(name("g"), name("b"), name("c"), Path::Id(name("Entity")));
+        model.add_mor(name("h"), name("a"), name("a"), Path::Id(name("Entity")));
+        model.add_equation(PathEq {
+            lhs: Path::single(name("h")),
+            rhs: Path::Id(name("a")),
+        });
+
+        let sub = model.induced_submodel([name("a"), name("b"), name("missing")]);
+        assert_eq!(sub.ob_generators().count(), 2);
+        assert!(sub.has_ob(&name("a")) && !sub.has_ob(&name("c")));
+        assert_eq!(sub.mor_generators().count(), 2);
+        assert!(!sub.mor_generators().any(|f| f == name("g")));
+        assert_eq!(sub.equations().count(), 1);
+        assert!(sub.validate().is_ok());
+
+        let sub = model.induced_submodel([name("b"), name("c")]);
+        assert_eq!(sub.mor_generators().collect::<Vec<_>>(), vec![name("g")]);
+        assert_eq!(sub.equations().count(), 0);
+    }
+
     #[test]
     fn pretty_print() {
         let model = walking_attr(Rc::new(th_schema()));
@@ -322,6 +419,25 @@ mod tests {
         expected.assert_eq(&format!("{model}"));
     }
 
+    #[test]
+    fn commuting_paths() {
+        let th = Rc::new(th_category());
+        let mut model = DiscreteDblModel::new(th);
+        model.add_ob(name("x"), name("Object"));
+        model.add_ob(name("y"), name("Object"));
+        model.add_ob(name("z"), name("Object"));
+        model.add_mor(name("f"), name("x"), name("y"), Path::Id(name("Object")));
+        model.add_mor(name("g"), name("y"), name("z"), Path::Id(name("Object")));
+        model.add_mor(name("h"), name("x"), name("z"), Path::Id(name("Object")));
+        let pairs = model.parallel_paths(&name("x"), &name("z"), 2);
+        assert_eq!(pairs.len(), 1);
+        assert!(!pairs[0].commutes);
+
+        model.add_equation(PathEq::new(Path::pair(name("f"), name("g")), name("h").into()));
+        let pairs = model.parallel_paths(&name("x"), &name("z"), 2);
+        assert!(pairs[0].commutes);
+    }
+
     #[test]
     fn infer_missing() {
         let th = Rc::new(th_schema());
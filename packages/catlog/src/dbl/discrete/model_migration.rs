@@ -0,0 +1,163 @@
+//! Migration of models of discrete double theories along functors.
+
+use std::collections::HashMap;
+
+use super::functor::DiscreteDblFunctor;
+use super::model::DiscreteDblModel;
+use crate::dbl::{functor::DblFunctor, model::*, theory::DblTheory};
+use crate::one::{Category, FgCategory, Path, PathEq, QualifiedPath};
+use crate::zero::QualifiedName;
+
+/// Pushes forward a model along a functor between discrete double theories.
+///
+/// The pushforward of a model has the same generators and equations as the
+/// model, with their types sent along the functor. The model is assumed to be a
+/// model of the domain theory of the functor.
+pub fn push_forward_model(
+    model: &DiscreteDblModel,
+    functor: &DiscreteDblFunctor,
+) -> DiscreteDblModel {
+    let mut model = model.clone();
+    model.push_forward(&functor.map().functor_into(&functor.cod().0), functor.cod_rc());
+    model
+}
+
+/// Pulls back a model along a functor between discrete double theories.
+///
+/// The pullback of a model of the codomain theory is a model of the domain
+/// theory. It has a copy of each object generator for every object type sent to
+/// the type of the object, and a copy of each morphism generator for every hom
+/// type or generating morphism type sent to the type of the morphism. A copy
+/// keeps the name of its generator when it is the only copy, and otherwise has
+/// the name of the type appended. Equations are pulled back when all the
+/// generators in them have exactly one copy; other equations are dropped.
+pub fn pull_back_model(model: &DiscreteDblModel, functor: &DiscreteDblFunctor) -> DiscreteDblModel {
+    let (dom, cod) = (functor.dom(), functor.cod());
+    let mut pulled = DiscreteDblModel::new(functor.dom_rc());
+
+    let ob_types: Vec<_> = dom.0.ob_generators().collect();
+    let mut ob_copies: HashMap<QualifiedName, Vec<(QualifiedName, QualifiedName)>> = HashMap::new();
+    for x in model.ob_generators() {
+        let t = model.ob_generator_type(&x);
+        let preimages: Vec<_> = ob_types
+            .iter()
+            .filter(|s| functor.apply_ob_type((*s).clone()).as_ref() == Some(&t))
+            .collect();
+        let copies = preimages
+            .iter()
+            .map(|&s| {
+                let name = if preimages.len() == 1 {
+                    x.clone()
+                } else {
+                    qualify(&x, s)
+                };
+                pulled.add_ob(name.clone(), s.clone());
+                (s.clone(), name)
+            })
+            .collect();
+        ob_copies.insert(x, copies);
+    }
+    let ob_copy = |x: &QualifiedName, s: &QualifiedName| {
+        ob_copies.get(x)?.iter().find(|(t, _)| t == s).map(|(_, name)| name.clone())
+    };
+
+    let mor_types: Vec<(QualifiedName, QualifiedPath)> = ob_types
+        .iter()
+        .map(|s| (s.clone(), Path::Id(s.clone())))
+        .chain(dom.0.mor_generators().map(|m| (m.clone(), Path::single(m))))
+        .collect();
+    let mut mor_copies: HashMap<QualifiedName, Vec<QualifiedName>> = HashMap::new();
+    for f in model.mor_generators() {
+        let (Some(x), Some(y)) = (model.get_dom(&f), model.get_cod(&f)) else {
+            continue;
+        };
+        let t = model.mor_generator_type(&f);
+        let preimages: Vec<_> = mor_types
+            .iter()
+            .filter(|(_, m)| {
+                functor
+                    .apply_mor_type(m.clone())
+                    .is_some_and(|n| cod.0.morphisms_are_equal(n, t.clone()))
+            })
+            .collect();
+        let mut copies = Vec::new();
+        for (m_name, m) in &preimages {
+            let (Some(x), Some(y)) = (ob_copy(x, &dom.src_type(m)), ob_copy(y, &dom.tgt_type(m)))
+            else {
+                continue;
+            };
+            let name = if preimages.len() == 1 {
+                f.clone()
+            } else {
+                qualify(&f, m_name)
+            };
+            pulled.add_mor(name.clone(), x, y, m.clone());
+            copies.push(name);
+        }
+        mor_copies.insert(f, copies);
+    }
+
+    let only_copy = |path: &QualifiedPath| match path {
+        Path::Id(x) => match ob_copies.get(x)?.as_slice() {
+            [(_, name)] => Some(Path::Id(name.clone())),
+            _ => None,
+        },
+        Path::Seq(fs) => {
+            let fs = fs.iter().map(|f| match mor_copies.get(f)?.as_slice() {
+                [name] => Some(name.clone()),
+                _ => None,
+            });
+            Path::from_vec(fs.collect::<Option<_>>()?)
+        }
+    };
+    for eq in model.category.equations() {
+        if let (Some(lhs), Some(rhs)) = (only_copy(&eq.lhs), only_copy(&eq.rhs)) {
+            pulled.add_equation(PathEq::new(lhs, rhs));
+        }
+    }
+    pulled
+}
+
+/// Qualifies the name of a copy of a generator by the name of a type.
+fn qualify(x: &QualifiedName, typ: &QualifiedName) -> QualifiedName {
+    x.segments().chain(typ.segments()).copied().collect::<Vec<_>>().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::stdlib::{theories::*, theory_morphisms::*};
+    use crate::validate::Validate;
+    use crate::zero::name;
+
+    #[test]
+    fn migrate_models() {
+        let (th_cat, th_sch) = (Rc::new(th_category()), Rc::new(th_schema()));
+        let incl = DiscreteDblFunctor::new(th_cat.clone(), th_sch.clone(), th_category_to_schema());
+        let proj = DiscreteDblFunctor::new(th_sch.clone(), th_cat.clone(), th_schema_to_category());
+
+        let mut model = DiscreteDblModel::new(th_cat);
+        model.add_ob(name("x"), name("Object"));
+        model.add_mor(name("f"), name("x"), name("x"), Path::Id(name("Object")));
+        model.add_equation(PathEq::new(Path::pair(name("f"), name("f")), Path::single(name("f"))));
+
+        let pushed = push_forward_model(&model, &incl);
+        assert_eq!(pushed.ob_generator_type(&name("x")), name("Entity"));
+        assert!(pushed.validate().is_ok());
+
+        // Pulling back along the inclusion recovers the model.
+        let pulled = pull_back_model(&pushed, &incl);
+        assert_eq!(pulled, model);
+
+        // Pulling back along the projection makes copies as entities and types.
+        let pulled = pull_back_model(&model, &proj);
+        assert!(pulled.validate().is_ok());
+        assert_eq!(pulled.ob_generators().count(), 2);
+        assert_eq!(pulled.mor_generators().count(), 3);
+        let x_entity = qualify(&name("x"), &name("Entity"));
+        assert_eq!(pulled.ob_generator_type(&x_entity), name("Entity"));
+        assert_eq!(pulled.category.equations().count(), 0);
+    }
+}
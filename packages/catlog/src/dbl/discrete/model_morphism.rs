@@ -6,6 +6,7 @@ use std::rc::Rc;
 use nonempty::NonEmpty;
 
 use crate::dbl::{model::*, model_morphism::*};
+use crate::instrument::{Budget, BudgetExhausted, BudgetGuard, Instrument, SearchStats};
 use crate::one::graph_algorithms::{bounded_simple_paths, simple_paths, spec_order};
 use crate::one::*;
 use crate::validate::{self, Validate};
@@ -219,6 +220,9 @@ pub struct DiscreteDblModelMorphismFinder<'a> {
     ob_init: HashColumn<QualifiedName, QualifiedName>,
     mor_init: HashColumn<QualifiedName, QualifiedPath>,
     ob_inv: HashColumn<QualifiedName, QualifiedName>,
+    budget: Budget,
+    guard: Option<BudgetGuard>,
+    stats: SearchStats,
 }
 
 impl<'a> DiscreteDblModelMorphismFinder<'a> {
@@ -250,6 +254,9 @@ impl<'a> DiscreteDblModelMorphismFinder<'a> {
             ob_init: Default::default(),
             mor_init: Default::default(),
             ob_inv: Default::default(),
+            budget: Default::default(),
+            guard: None,
+            stats: Default::default(),
         }
     }
 
@@ -297,20 +304,60 @@ impl<'a> DiscreteDblModelMorphismFinder<'a> {
         self
     }
 
+    /// Limit the resources used by the search.
+    pub fn budget(&mut self, budget: Budget) -> &mut Self {
+        self.budget = budget;
+        self
+    }
+
     /// Finds all morphisms.
+    ///
+    /// If the search runs out of budget, only the morphisms found so far are
+    /// returned. Use [`try_find_all`](Self::try_find_all) to detect this.
     pub fn find_all(&mut self) -> Vec<DiscreteDblModelMapping> {
-        self.search(0);
+        let _ = self.run();
         std::mem::take(&mut self.results)
     }
 
-    fn search(&mut self, depth: usize) {
+    /// Finds all morphisms, failing if the search runs out of budget.
+    pub fn try_find_all(&mut self) -> Result<Vec<DiscreteDblModelMapping>, BudgetExhausted> {
+        let result = self.run();
+        let results = std::mem::take(&mut self.results);
+        result.map(|_| results)
+    }
+
+    fn run(&mut self) -> Result<(), BudgetExhausted> {
+        self.map = Default::default();
+        self.ob_inv = Default::default();
+        self.stats = Default::default();
+        self.guard = Some(BudgetGuard::start(self.budget));
+        let result = self.search(0);
+        if let Some(guard) = self.guard.take() {
+            guard.stop(&mut self.stats);
+        }
+        result
+    }
+
+    /// Gets the counters reported by the last search.
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    fn search(&mut self, depth: usize) -> Result<(), BudgetExhausted> {
+        self.stats.node();
+        if let Some(guard) = &self.guard {
+            guard.check(&self.stats)?;
+        }
         if depth >= self.var_order.len() {
             if !self.faithful
                 || DblModelMorphism(&self.map, self.dom, self.cod).is_free_simple_faithful()
             {
+                self.stats.solution();
                 self.results.push(self.map.clone());
+            } else {
+                self.stats.backtrack();
             }
-            return;
+            return Ok(());
         }
         let var = &self.var_order[depth];
         match var.clone() {
@@ -318,15 +365,19 @@ impl<'a> DiscreteDblModelMorphismFinder<'a> {
                 if let Some(y) = self.ob_init.apply_to_ref(&x) {
                     let can_assign = self.assign_ob(x.clone(), y.clone());
                     if can_assign {
-                        self.search(depth + 1);
+                        self.search(depth + 1)?;
                         self.unassign_ob(x, y)
+                    } else {
+                        self.stats.backtrack();
                     }
                 } else {
                     for y in self.cod.ob_generators_with_type(&self.dom.ob_type(&x)) {
                         let can_assign = self.assign_ob(x.clone(), y.clone());
                         if can_assign {
-                            self.search(depth + 1);
+                            self.search(depth + 1)?;
                             self.unassign_ob(x.clone(), y)
+                        } else {
+                            self.stats.backtrack();
                         }
                     }
                 }
@@ -334,7 +385,7 @@ impl<'a> DiscreteDblModelMorphismFinder<'a> {
             GraphElem::Edge(m) => {
                 if let Some(path) = self.mor_init.apply_to_ref(&m) {
                     self.map.assign_mor(m, path);
-                    self.search(depth + 1);
+                    self.search(depth + 1)?;
                 } else {
                     let functor = self.map.0.functor_into(&self.cod.category);
                     let mor_type = self.dom.mor_generator_type(&m);
@@ -352,12 +403,15 @@ impl<'a> DiscreteDblModelMorphismFinder<'a> {
                             && !(self.faithful && path.is_empty())
                         {
                             self.map.assign_mor(m.clone(), path);
-                            self.search(depth + 1);
+                            self.search(depth + 1)?;
+                        } else {
+                            self.stats.backtrack();
                         }
                     }
                 }
             }
         }
+        Ok(())
     }
 
     /// Attempt an object assignment, returning true iff successful.
@@ -408,6 +462,21 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn positive_loop_search_stats() {
+        let positive_loop = positive_loop(Rc::new(th_signed_category()));
+
+        let mut finder = DiscreteDblModelMapping::morphisms(&positive_loop, &positive_loop);
+        let maps = finder.monic().find_all();
+        assert_eq!(maps.len(), 1);
+        assert_eq!(finder.stats().solutions, 1);
+        assert!(finder.stats().nodes > 1 && finder.stats().backtracks > 0);
+
+        let result = finder.budget(Budget::nodes(2)).try_find_all();
+        assert_eq!(result.map_err(|err| err.stats.nodes), Err(3));
+        assert_eq!(finder.budget(Budget::nodes(100)).try_find_all().map(|maps| maps.len()), Ok(1));
+    }
+
     /// The [simple path](crate::one::graph_algorithms::simple_paths) should
     /// give identical results to hom search from a walking morphism (assuming
     /// all the object/morphism types are the same).
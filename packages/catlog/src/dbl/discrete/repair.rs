@@ -0,0 +1,336 @@
+//! Repair suggestions for invalid models of discrete double theories.
+//!
+//! When a model fails to [validate](crate::validate::Validate), it is often
+//! because of a small local mistake: a morphism whose domain or codomain refers to
+//! an object that was deleted or mistyped, or that was assigned the wrong kind of
+//! object, or an equation between paths that do not compose or are not parallel.
+//! For each such failure, we generate a list of candidate repairs, ranked
+//! by how much they change the model, suitable for display in a quick-fix menu.
+//!
+//! Repairs are only *suggested*, never applied, since the model is typically
+//! elaborated from a notebook that is the real source of truth.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::model::DiscreteDblModel;
+use crate::dbl::{model::*, theory::DblTheory};
+use crate::one::{Category, FgCategory, Path};
+use crate::zero::{Namespace, QualifiedName};
+
+/// A candidate repair of a model of a discrete double theory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag", content = "content"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum ModelRepair {
+    /// Delete the morphism generator, along with any equations using it.
+    RemoveMor(QualifiedName),
+
+    /// Delete the equation with the given index.
+    RemoveEqn(usize),
+
+    /// Retarget the domain of a morphism generator to an existing object.
+    SetDom {
+        /// Morphism generator to retarget.
+        mor: QualifiedName,
+        /// New domain of the morphism.
+        ob: QualifiedName,
+    },
+
+    /// Retarget the codomain of a morphism generator to an existing object.
+    SetCod {
+        /// Morphism generator to retarget.
+        mor: QualifiedName,
+        /// New codomain of the morphism.
+        ob: QualifiedName,
+    },
+
+    /// Insert an object generator that is referred to but missing.
+    AddOb {
+        /// Object generator to insert.
+        ob: QualifiedName,
+        /// Type of the new object.
+        #[cfg_attr(feature = "serde", serde(rename = "obType"))]
+        ob_type: QualifiedName,
+    },
+}
+
+/// A candidate repair together with its distance from the original model.
+///
+/// The distance counts the number of generators and equations added, removed, or
+/// changed by the repair. Among repairs at the same distance, retargetings to
+/// objects whose labels are closer, in string edit distance, to the label of the
+/// original object are ranked first, so that fixing a likely typo comes first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct RepairSuggestion {
+    /// The suggested repair.
+    pub repair: ModelRepair,
+
+    /// Edit distance of the repair.
+    pub distance: usize,
+}
+
+/// Suggested repairs for a single validation failure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ModelRepairs {
+    /// The validation failure.
+    pub error: InvalidDblModel,
+
+    /// Candidate repairs, ranked by increasing distance.
+    pub suggestions: Vec<RepairSuggestion>,
+}
+
+impl DiscreteDblModel {
+    /// Suggests repairs for all failures of the model to be well defined.
+    ///
+    /// The namespace is used to look up human-readable labels of objects when
+    /// ranking candidate retargetings. Failures for which there are no known
+    /// repairs are still included, with an empty list of suggestions.
+    pub fn repair_suggestions(&self, ob_ns: &Namespace) -> Vec<ModelRepairs> {
+        self.iter_invalid()
+            .map(|error| {
+                let suggestions = self.repairs_for(&error, ob_ns);
+                ModelRepairs { error, suggestions }
+            })
+            .collect()
+    }
+
+    /// Suggests repairs for a single failure of the model to be well defined.
+    pub fn repairs_for(&self, error: &InvalidDblModel, ob_ns: &Namespace) -> Vec<RepairSuggestion> {
+        // Suggestions paired with a secondary key used to break ties.
+        let mut suggestions = Vec::new();
+        match error {
+            InvalidDblModel::Dom(f) | InvalidDblModel::DomType(f) => {
+                self.retarget_suggestions(f, Side::Dom, ob_ns, &mut suggestions);
+                suggestions.push((self.remove_mor_suggestion(f), 0));
+            }
+            InvalidDblModel::Cod(f) | InvalidDblModel::CodType(f) => {
+                self.retarget_suggestions(f, Side::Cod, ob_ns, &mut suggestions);
+                suggestions.push((self.remove_mor_suggestion(f), 0));
+            }
+            InvalidDblModel::MorType(f) => {
+                suggestions.push((self.remove_mor_suggestion(f), 0));
+            }
+            InvalidDblModel::Eqn(Some(id), _) => {
+                self.equation_suggestions(*id, &mut suggestions);
+            }
+            _ => {}
+        }
+        suggestions.sort_by_key(|(s, label_distance)| (s.distance, *label_distance));
+        suggestions.into_iter().map(|(s, _)| s).collect()
+    }
+
+    /// Suggestions to fix the domain or codomain of a morphism generator.
+    fn retarget_suggestions(
+        &self,
+        f: &QualifiedName,
+        side: Side,
+        ob_ns: &Namespace,
+        suggestions: &mut Vec<(RepairSuggestion, usize)>,
+    ) {
+        let mor_type = self.mor_generator_type(f);
+        let theory = self.theory();
+        let ob_type = theory.has_mor_type(&mor_type).then(|| match side {
+            Side::Dom => theory.src_type(&mor_type),
+            Side::Cod => theory.tgt_type(&mor_type),
+        });
+        let current = match side {
+            Side::Dom => self.get_dom(f),
+            Side::Cod => self.get_cod(f),
+        };
+
+        // Insert the missing object, when the morphism refers to one.
+        if let (Some(x), Some(ob_type)) = (current, ob_type.as_ref())
+            && !self.has_ob(x)
+        {
+            let repair = ModelRepair::AddOb { ob: x.clone(), ob_type: ob_type.clone() };
+            suggestions.push((RepairSuggestion { repair, distance: 1 }, 0));
+        }
+
+        // Retarget to any existing object of the right type.
+        let current_label = current.map(|x| ob_ns.label_string(x)).unwrap_or_default();
+        let candidates: Vec<_> = match ob_type.as_ref() {
+            Some(ob_type) => self.ob_generators_with_type(ob_type).collect(),
+            None => self.ob_generators().collect(),
+        };
+        for x in candidates {
+            if current == Some(&x) {
+                continue;
+            }
+            let label_distance = edit_distance(&current_label, &ob_ns.label_string(&x));
+            let repair = match side {
+                Side::Dom => ModelRepair::SetDom { mor: f.clone(), ob: x },
+                Side::Cod => ModelRepair::SetCod { mor: f.clone(), ob: x },
+            };
+            suggestions.push((RepairSuggestion { repair, distance: 1 }, label_distance));
+        }
+    }
+
+    /// Suggestions to fix an equation whose sides are ill-typed or not parallel.
+    ///
+    /// Besides deleting the equation itself, any generator in either side may be
+    /// the offending one, so deleting each of them is also suggested.
+    fn equation_suggestions(&self, id: usize, suggestions: &mut Vec<(RepairSuggestion, usize)>) {
+        let Some(eq) = self.category.equations().nth(id) else {
+            return;
+        };
+        let repair = ModelRepair::RemoveEqn(id);
+        suggestions.push((RepairSuggestion { repair, distance: 1 }, 0));
+
+        let mut generators: Vec<&QualifiedName> = Vec::new();
+        for path in [&eq.lhs, &eq.rhs] {
+            if let Path::Seq(edges) = path {
+                for e in edges.iter() {
+                    if !generators.contains(&e) {
+                        generators.push(e);
+                    }
+                }
+            }
+        }
+        for f in generators {
+            suggestions.push((self.remove_mor_suggestion(f), 0));
+        }
+    }
+
+    /// Suggestion to delete a morphism generator.
+    ///
+    /// Deleting a morphism discards its domain and codomain, as well as every
+    /// equation that uses it, so this is usually the most drastic repair.
+    fn remove_mor_suggestion(&self, f: &QualifiedName) -> RepairSuggestion {
+        let uses = |path: &Path<_, _>| match path {
+            Path::Id(_) => false,
+            Path::Seq(edges) => edges.iter().any(|e| e == f),
+        };
+        let n_equations =
+            self.category.equations().filter(|eq| uses(&eq.lhs) || uses(&eq.rhs)).count();
+        RepairSuggestion {
+            repair: ModelRepair::RemoveMor(f.clone()),
+            distance: 3 + n_equations,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Dom,
+    Cod,
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (diag + usize::from(ca != *cb)).min(row[j] + 1).min(row[j + 1] + 1);
+            diag = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::one::PathEq;
+    use crate::stdlib::theories::{th_category, th_schema};
+    use crate::zero::name;
+
+    #[test]
+    fn levenshtein() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("entity", "entity"), 0);
+    }
+
+    #[test]
+    fn dangling_codomain() {
+        let th = Rc::new(th_schema());
+        let mut model = DiscreteDblModel::new(th);
+        model.add_ob(name("entity"), name("Entity"));
+        model.add_ob(name("type"), name("AttrType"));
+        model.add_ob(name("other"), name("AttrType"));
+        model.add_mor(name("attr"), name("entity"), name("typ"), name("Attr").into());
+
+        let ns = Namespace::new_for_text();
+        let repairs = model.repair_suggestions(&ns);
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].error, InvalidDblModel::Cod(name("attr")));
+
+        let suggestions: Vec<_> = repairs[0].suggestions.iter().map(|s| &s.repair).collect();
+        assert_eq!(
+            suggestions,
+            vec![
+                &ModelRepair::AddOb {
+                    ob: name("typ"),
+                    ob_type: name("AttrType")
+                },
+                &ModelRepair::SetCod { mor: name("attr"), ob: name("type") },
+                &ModelRepair::SetCod { mor: name("attr"), ob: name("other") },
+                &ModelRepair::RemoveMor(name("attr")),
+            ]
+        );
+    }
+
+    #[test]
+    fn ill_typed_domain() {
+        let th = Rc::new(th_schema());
+        let mut model = DiscreteDblModel::new(th);
+        model.add_ob(name("entity"), name("Entity"));
+        model.add_ob(name("type"), name("AttrType"));
+        model.add_mor(name("attr"), name("type"), name("type"), name("Attr").into());
+
+        let repairs =
+            model.repairs_for(&InvalidDblModel::DomType(name("attr")), &Namespace::new_for_text());
+        assert_eq!(
+            repairs[0].repair,
+            ModelRepair::SetDom { mor: name("attr"), ob: name("entity") }
+        );
+        assert_eq!(repairs.last().unwrap().repair, ModelRepair::RemoveMor(name("attr")));
+    }
+
+    #[test]
+    fn ill_typed_composite() {
+        let th = Rc::new(th_category());
+        let mut model = DiscreteDblModel::new(th);
+        for x in ["x", "y", "z"] {
+            model.add_ob(name(x), name("Object"));
+        }
+        model.add_mor(name("f"), name("x"), name("y"), Path::Id(name("Object")));
+        model.add_mor(name("g"), name("x"), name("z"), Path::Id(name("Object")));
+        model.add_mor(name("h"), name("x"), name("z"), Path::Id(name("Object")));
+        // The composite of `f` and `g` is not defined.
+        model.add_equation(PathEq::new(Path::pair(name("f"), name("g")), name("h").into()));
+
+        let repairs = model.repair_suggestions(&Namespace::new_for_text());
+        assert_eq!(repairs.len(), 1);
+        assert!(matches!(repairs[0].error, InvalidDblModel::Eqn(Some(0), _)));
+
+        let suggestions: Vec<_> = repairs[0].suggestions.iter().map(|s| &s.repair).collect();
+        assert_eq!(
+            suggestions,
+            vec![
+                &ModelRepair::RemoveEqn(0),
+                &ModelRepair::RemoveMor(name("f")),
+                &ModelRepair::RemoveMor(name("g")),
+                &ModelRepair::RemoveMor(name("h")),
+            ]
+        );
+        assert_eq!(repairs[0].suggestions[1].distance, 4);
+    }
+}
@@ -0,0 +1,430 @@
+//! Double-pushout rewriting of models of discrete double theories.
+//!
+//! A [double-pushout (DPO) rule](https://ncatlab.org/nlab/show/double+pushout+rewriting)
+//! is a span of models `L ← K → R`: the left-hand side `L` is the pattern to be
+//! matched, the interface `K` is the part of the pattern that is preserved, and
+//! the right-hand side `R` is what the pattern is replaced with. Given a match of
+//! `L` in a model `G`, rewriting deletes the image of `L` minus `K` from `G` and
+//! then glues in `R` along `K`.
+//!
+//! We restrict to the simplest and most common setting. Both legs of the rule and
+//! the match must be monomorphisms sending generators to generators, and all
+//! three models in the rule must be free. Matches are found using the
+//! [morphism finder](super::model_morphism::DiscreteDblModelMorphismFinder).
+
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use super::model::DiscreteDblModel;
+use super::model_morphism::{DblModelMorphism, DiscreteDblModelMapping};
+use crate::dbl::model::*;
+use crate::instrument::{Budget, BudgetExhausted, SearchStats};
+use crate::one::{Category, FgCategory, Graph, Path, QualifiedPath};
+use crate::validate::Validate;
+use crate::zero::{Column, Mapping, QualifiedName, name_seg};
+
+/// A double-pushout rewrite rule on models of a discrete double theory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RewriteRule {
+    left: DiscreteDblModel,
+    interface: DiscreteDblModel,
+    right: DiscreteDblModel,
+    left_map: DiscreteDblModelMapping,
+    right_map: DiscreteDblModelMapping,
+}
+
+/// An invalid rewrite rule.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum InvalidRewriteRule {
+    /// A model in the rule is not free.
+    #[error("Models in a rewrite rule should be free")]
+    NotFree,
+
+    /// The left leg of the rule is not a monomorphism of generators.
+    #[error("Left leg of rule should be an injective map of generators")]
+    LeftNotMonic,
+
+    /// The right leg of the rule is not a monomorphism of generators.
+    #[error("Right leg of rule should be an injective map of generators")]
+    RightNotMonic,
+}
+
+/// An error in applying a rewrite rule at a given match.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum RewriteError {
+    /// The match is not a monomorphism of generators.
+    #[error("Match should be an injective map of generators")]
+    MatchNotMonic,
+
+    /// Rewriting would leave the given morphism without a domain or codomain.
+    #[error("Rewriting would leave morphism `{0}` dangling")]
+    Dangling(QualifiedName),
+}
+
+/// The result of applying a rewrite rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rewrite {
+    /// The rewritten model.
+    pub model: DiscreteDblModel,
+
+    /// Embedding of the right-hand side of the rule into the rewritten model.
+    pub comatch: DiscreteDblModelMapping,
+}
+
+/// The result of rewriting a model exhaustively.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RewriteOutcome {
+    /// The rewritten model.
+    pub model: DiscreteDblModel,
+
+    /// Number of rewrite steps applied.
+    pub steps: usize,
+
+    /// Whether rewriting stopped because no rule applies, rather than because
+    /// the step limit was reached.
+    pub terminated: bool,
+
+    /// Counters reported by the searches for matches, taken together.
+    pub stats: SearchStats,
+}
+
+impl RewriteRule {
+    /// Constructs a rewrite rule from a span of models.
+    pub fn new(
+        left: DiscreteDblModel,
+        interface: DiscreteDblModel,
+        right: DiscreteDblModel,
+        left_map: DiscreteDblModelMapping,
+        right_map: DiscreteDblModelMapping,
+    ) -> Result<Self, InvalidRewriteRule> {
+        if !(left.is_free() && interface.is_free() && right.is_free()) {
+            return Err(InvalidRewriteRule::NotFree);
+        }
+        if !is_generator_injection(&left_map, &interface, &left) {
+            return Err(InvalidRewriteRule::LeftNotMonic);
+        }
+        if !is_generator_injection(&right_map, &interface, &right) {
+            return Err(InvalidRewriteRule::RightNotMonic);
+        }
+        Ok(Self {
+            left,
+            interface,
+            right,
+            left_map,
+            right_map,
+        })
+    }
+
+    /// Gets the left-hand side of the rule.
+    pub fn left(&self) -> &DiscreteDblModel {
+        &self.left
+    }
+
+    /// Gets the interface of the rule.
+    pub fn interface(&self) -> &DiscreteDblModel {
+        &self.interface
+    }
+
+    /// Gets the right-hand side of the rule.
+    pub fn right(&self) -> &DiscreteDblModel {
+        &self.right
+    }
+
+    /// Finds all matches of the rule in a model at which the rule can be applied.
+    pub fn matches(&self, model: &DiscreteDblModel) -> Vec<DiscreteDblModelMapping> {
+        let (matches, _) = self
+            .matches_within(model, Budget::unlimited())
+            .expect("Search with unlimited budget should not be exhausted");
+        matches
+    }
+
+    /// Finds all matches of the rule within a budget, along with the counters
+    /// of the search.
+    pub fn matches_within(
+        &self,
+        model: &DiscreteDblModel,
+        budget: Budget,
+    ) -> Result<(Vec<DiscreteDblModelMapping>, SearchStats), BudgetExhausted> {
+        let mut finder = DiscreteDblModelMapping::morphisms(&self.left, model);
+        let mut matches = finder.monic().max_path_len(1).budget(budget).try_find_all()?;
+        matches.retain(|m| self.check_gluing(model, m).is_ok());
+        Ok((matches, finder.stats().clone()))
+    }
+
+    /// Applies the rule to a model at the given match.
+    pub fn apply(
+        &self,
+        model: &DiscreteDblModel,
+        m: &DiscreteDblModelMapping,
+    ) -> Result<Rewrite, RewriteError> {
+        let (deleted_obs, deleted_mors) = self.check_gluing(model, m)?;
+        let match_ob = |x: &QualifiedName| {
+            m.0.ob_generator_map
+                .apply_to_ref(x)
+                .expect("Match should be defined on objects")
+        };
+        let match_mor = |f: &QualifiedName| {
+            m.0.mor_generator_map
+                .apply_to_ref(f)
+                .expect("Match should be defined on morphisms")
+        };
+
+        // Construct the pushout complement by deleting the image of `L - K`.
+        let mut result = DiscreteDblModel::new(model.theory());
+        for x in model.ob_generators().filter(|x| !deleted_obs.contains(x)) {
+            result.add_ob(x.clone(), model.ob_generator_type(&x));
+        }
+        for f in model.mor_generators().filter(|f| !deleted_mors.contains(f)) {
+            let (dom, cod) = (model.mor_generator_dom(&f), model.mor_generator_cod(&f));
+            result.add_mor(f.clone(), dom, cod, model.mor_generator_type(&f));
+        }
+        for eq in model.category.equations() {
+            if !eq.lhs.iter().chain(eq.rhs.iter()).any(|f| deleted_mors.contains(f)) {
+                result.add_equation(eq.clone());
+            }
+        }
+
+        // Construct the pushout by gluing in `R` along `K`.
+        let mut comatch = DiscreteDblModelMapping::default();
+        for (k, x) in self.right_map.0.ob_generator_map.iter() {
+            let l_k = self.left_map.0.ob_generator_map.apply_to_ref(&k).unwrap();
+            comatch.assign_ob(x.clone(), match_ob(&l_k));
+        }
+        for x in self.right.ob_generators() {
+            if comatch.0.ob_generator_map.is_set(&x) {
+                continue;
+            }
+            let new_x = fresh_name(&x, &result);
+            result.add_ob(new_x.clone(), self.right.ob_generator_type(&x));
+            comatch.assign_ob(x, new_x);
+        }
+        for (k, path) in self.right_map.0.mor_generator_map.iter() {
+            let f = path.clone().only().unwrap();
+            let l_k = self.left_map.0.mor_generator_map.apply_to_ref(&k).unwrap();
+            comatch.assign_mor(f, match_mor(&l_k.only().unwrap()));
+        }
+        for f in self.right.mor_generators() {
+            if comatch.0.mor_generator_map.is_set(&f) {
+                continue;
+            }
+            let [dom, cod] = [self.right.mor_generator_dom(&f), self.right.mor_generator_cod(&f)]
+                .map(|x| comatch.0.ob_generator_map.apply(x).unwrap());
+            let new_f = fresh_name(&f, &result);
+            result.add_mor(new_f.clone(), dom, cod, self.right.mor_generator_type(&f));
+            comatch.assign_mor(f, Path::single(new_f));
+        }
+
+        Ok(Rewrite { model: result, comatch })
+    }
+
+    /// Checks the gluing condition for the rule at a match.
+    ///
+    /// On success, returns the object and morphism generators of the model to be
+    /// deleted by rewriting.
+    pub(super) fn check_gluing(
+        &self,
+        model: &DiscreteDblModel,
+        m: &DiscreteDblModelMapping,
+    ) -> Result<(HashSet<QualifiedName>, HashSet<QualifiedName>), RewriteError> {
+        if !is_generator_injection(m, &self.left, model) {
+            return Err(RewriteError::MatchNotMonic);
+        }
+        let kept_obs: HashSet<_> = self.left_map.0.ob_generator_map.values().cloned().collect();
+        let kept_mors: HashSet<_> = self.left_map.0.mor_generator_map.values().cloned().collect();
+        let deleted_obs: HashSet<_> = self
+            .left
+            .ob_generators()
+            .filter(|x| !kept_obs.contains(x))
+            .filter_map(|x| m.0.ob_generator_map.apply(x))
+            .collect();
+        let deleted_mors: HashSet<_> = self
+            .left
+            .mor_generators()
+            .filter(|f| !kept_mors.contains(&Path::single(f.clone())))
+            .filter_map(|f| m.0.mor_generator_map.apply(f).and_then(Path::only))
+            .collect();
+
+        // Dangling condition: no surviving morphism touches a deleted object.
+        if let Some(f) = model.mor_generators().find(|f| {
+            !deleted_mors.contains(f)
+                && (deleted_obs.contains(&model.mor_generator_dom(f))
+                    || deleted_obs.contains(&model.mor_generator_cod(f)))
+        }) {
+            return Err(RewriteError::Dangling(f));
+        }
+        Ok((deleted_obs, deleted_mors))
+    }
+}
+
+/// Rewrites a model until no rule applies or the step limit is reached.
+///
+/// At each step, the first rule having a match is applied at its first match.
+pub fn rewrite_exhaustively(
+    rules: &[RewriteRule],
+    model: DiscreteDblModel,
+    max_steps: usize,
+) -> RewriteOutcome {
+    rewrite_within(rules, model, max_steps, Budget::unlimited())
+        .expect("Search with unlimited budget should not be exhausted")
+}
+
+/// Rewrites a model exhaustively, within a budget for all searches for matches.
+pub fn rewrite_within(
+    rules: &[RewriteRule],
+    model: DiscreteDblModel,
+    max_steps: usize,
+    budget: Budget,
+) -> Result<RewriteOutcome, BudgetExhausted> {
+    let mut model = model;
+    let mut stats = SearchStats::default();
+    for steps in 0..max_steps {
+        let mut next = None;
+        for rule in rules {
+            let (matches, rule_stats) = match rule.matches_within(&model, budget.remaining(&stats))
+            {
+                Ok(found) => found,
+                Err(err) => {
+                    stats.merge(&err.stats);
+                    return Err(BudgetExhausted { stats });
+                }
+            };
+            stats.merge(&rule_stats);
+            next = matches.first().and_then(|m| rule.apply(&model, m).ok());
+            if next.is_some() {
+                break;
+            }
+        }
+        match next {
+            Some(rewrite) => model = rewrite.model,
+            None => {
+                return Ok(RewriteOutcome { model, steps, terminated: true, stats });
+            }
+        }
+    }
+    Ok(RewriteOutcome {
+        model,
+        steps: max_steps,
+        terminated: false,
+        stats,
+    })
+}
+
+/// Is the mapping a valid model morphism that is injective on generators?
+fn is_generator_injection(
+    map: &DiscreteDblModelMapping,
+    dom: &DiscreteDblModel,
+    cod: &DiscreteDblModel,
+) -> bool {
+    let morphism = DblModelMorphism(map, dom, cod);
+    if morphism.validate().is_err() || !morphism.is_injective_objects() {
+        return false;
+    }
+    let mut seen = HashSet::new();
+    dom.mor_generators().all(|f| {
+        let image: Option<QualifiedPath> = map.0.mor_generator_map.apply(f);
+        image.and_then(Path::only).is_some_and(|g| seen.insert(g))
+    })
+}
+
+/// Chooses a name for a generator not already used in the model.
+///
+/// The given name is used if possible; otherwise, a numeric segment is appended.
+pub(super) fn fresh_name(x: &QualifiedName, model: &DiscreteDblModel) -> QualifiedName {
+    let is_taken = |x: &QualifiedName| model.has_ob(x) || model.generating_graph().has_edge(x);
+    if !is_taken(x) {
+        return x.clone();
+    }
+    (1..)
+        .map(|i: usize| x.snoc(name_seg(i.to_string().as_str())))
+        .find(|x| !is_taken(x))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::discrete::DiscreteDblTheory;
+    use crate::stdlib::theories::th_category;
+    use crate::zero::name;
+
+    fn arrow(th: Rc<DiscreteDblTheory>, mors: &[&str]) -> DiscreteDblModel {
+        let mut model = DiscreteDblModel::new(th);
+        model.add_ob(name("a"), name("Object"));
+        model.add_ob(name("b"), name("Object"));
+        for f in mors {
+            model.add_mor(name(*f), name("a"), name("b"), Path::Id(name("Object")));
+        }
+        model
+    }
+
+    /// Rule that adds a parallel morphism to any morphism.
+    fn add_parallel(th: Rc<DiscreteDblTheory>) -> RewriteRule {
+        let inc = DiscreteDblModelMapping::new(
+            [(name("a"), name("a")), (name("b"), name("b"))],
+            [(name("f"), Path::single(name("f")))],
+        );
+        RewriteRule::new(
+            arrow(th.clone(), &["f"]),
+            arrow(th.clone(), &["f"]),
+            arrow(th, &["f", "g"]),
+            inc.clone(),
+            inc,
+        )
+        .unwrap()
+    }
+
+    /// Rule that deletes an object.
+    fn delete_object(th: Rc<DiscreteDblTheory>) -> RewriteRule {
+        let mut left = DiscreteDblModel::new(th.clone());
+        left.add_ob(name("a"), name("Object"));
+        let empty = DiscreteDblModel::new(th);
+        RewriteRule::new(left, empty.clone(), empty, Default::default(), Default::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn apply_rule() {
+        let th = Rc::new(th_category());
+        let rule = add_parallel(th.clone());
+        let model = arrow(th.clone(), &["g"]);
+        let matches = rule.matches(&model);
+        assert_eq!(matches.len(), 1);
+
+        let Rewrite { model, comatch } = rule.apply(&model, &matches[0]).unwrap();
+        assert_eq!(model.mor_generators().count(), 2);
+        let new_mor = comatch.0.mor_generator_map.apply(name("g")).and_then(Path::only).unwrap();
+        assert_eq!(new_mor, name(["g", "1"]));
+        assert_eq!(model.mor_generator_dom(&new_mor), name("a"));
+
+        let outcome = rewrite_exhaustively(&[rule], model, 3);
+        assert_eq!(outcome.steps, 3);
+        assert!(!outcome.terminated);
+        assert_eq!(outcome.model.mor_generators().count(), 5);
+    }
+
+    #[test]
+    fn dangling_condition() {
+        let th = Rc::new(th_category());
+        let rule = delete_object(th.clone());
+        let mut model = arrow(th, &["f"]);
+        model.add_ob(name("c"), name("Object"));
+        let matches = rule.matches(&model);
+        assert_eq!(matches.len(), 1);
+
+        let m = DiscreteDblModelMapping::new([(name("a"), name("a"))], []);
+        assert_eq!(rule.apply(&model, &m), Err(RewriteError::Dangling(name("f"))));
+
+        let outcome = rewrite_exhaustively(&[rule.clone()], model.clone(), 10);
+        assert_eq!(outcome.steps, 1);
+        assert!(outcome.terminated);
+        assert!(!outcome.model.has_ob(&name("c")));
+        assert!(outcome.stats.nodes > 0);
+
+        let nodes = outcome.stats.nodes;
+        let result = rewrite_within(&[rule], model, 10, Budget::nodes(nodes - 1));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! Termination and confluence analysis for sets of rewrite rules.
+//!
+//! Before applying a set of [rewrite rules](super::rewriting::RewriteRule) in
+//! batch, we would like to know that rewriting terminates and that the result
+//! does not depend on the order in which the rules are applied.
+//!
+//! Termination is checked using additive [measures](WeightMeasure) supplied per
+//! rule. Since rewriting along monomorphisms replaces a copy of the left-hand
+//! side with a copy of the right-hand side, an additive measure decreases under a
+//! rewrite step exactly when it is smaller on the right-hand side than on the
+//! left-hand side.
+//!
+//! Confluence is investigated through [critical pairs](CriticalPair): minimal
+//! overlaps of two left-hand sides on which applying one rule disables the other.
+//! A terminating rule set is confluent if and only if all of its critical pairs
+//! are joinable, by Newman's lemma.
+
+use std::collections::{HashMap, HashSet};
+
+use super::model::DiscreteDblModel;
+use super::model_morphism::DiscreteDblModelMapping;
+use super::rewriting::{RewriteRule, fresh_name, rewrite_exhaustively};
+use crate::dbl::model::*;
+use crate::one::{FgCategory, Path, QualifiedPath};
+use crate::zero::{Column, Mapping, QualifiedName};
+
+/// An additive measure on models, weighting each generator by its type.
+///
+/// Generators whose type is not assigned a weight are given the default weight,
+/// which is initially one, so that the default measure counts generators.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightMeasure {
+    ob_weights: HashMap<QualifiedName, usize>,
+    mor_weights: HashMap<QualifiedPath, usize>,
+    default_weight: usize,
+}
+
+impl Default for WeightMeasure {
+    fn default() -> Self {
+        Self {
+            ob_weights: Default::default(),
+            mor_weights: Default::default(),
+            default_weight: 1,
+        }
+    }
+}
+
+impl WeightMeasure {
+    /// Creates a measure counting generators.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the weight of object generators of the given type.
+    pub fn ob_weight(mut self, ob_type: QualifiedName, weight: usize) -> Self {
+        self.ob_weights.insert(ob_type, weight);
+        self
+    }
+
+    /// Sets the weight of morphism generators of the given type.
+    pub fn mor_weight(mut self, mor_type: QualifiedPath, weight: usize) -> Self {
+        self.mor_weights.insert(mor_type, weight);
+        self
+    }
+
+    /// Sets the weight of generators whose type is not otherwise weighted.
+    pub fn default_weight(mut self, weight: usize) -> Self {
+        self.default_weight = weight;
+        self
+    }
+
+    /// Evaluates the measure on a model.
+    pub fn measure(&self, model: &DiscreteDblModel) -> usize {
+        let obs = model.ob_generators().map(|x| {
+            let ob_type = model.ob_generator_type(&x);
+            self.ob_weights.get(&ob_type).copied().unwrap_or(self.default_weight)
+        });
+        let mors = model.mor_generators().map(|f| {
+            let mor_type = model.mor_generator_type(&f);
+            self.mor_weights.get(&mor_type).copied().unwrap_or(self.default_weight)
+        });
+        obs.chain(mors).sum()
+    }
+
+    /// Change in the measure under a rewrite step by the given rule.
+    fn delta(&self, rule: &RewriteRule) -> isize {
+        self.measure(rule.right()) as isize - self.measure(rule.left()) as isize
+    }
+}
+
+/// Checks that rewriting with a set of rules terminates.
+///
+/// The `i`th rule is paired with the `i`th measure. The check succeeds when each
+/// rule strictly decreases its own measure and does not increase the measures of
+/// any preceding rules, so that the tuple of all measures decreases
+/// lexicographically at every step. Returns the indices of the rules violating
+/// this condition, which is empty if termination is proved.
+pub fn check_termination(rules: &[RewriteRule], measures: &[WeightMeasure]) -> Vec<usize> {
+    assert_eq!(rules.len(), measures.len(), "Each rule should have a measure");
+    rules
+        .iter()
+        .enumerate()
+        .filter(|(i, rule)| {
+            measures[*i].delta(rule) >= 0
+                || measures[..*i].iter().any(|measure| measure.delta(rule) > 0)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// A critical pair of two rewrite rules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CriticalPair {
+    /// Indices of the two overlapping rules.
+    pub rules: (usize, usize),
+
+    /// Model formed by overlapping the left-hand sides of the rules.
+    pub overlap: DiscreteDblModel,
+
+    /// Whether rewriting the two results of the overlap leads to the same model.
+    pub joinable: bool,
+}
+
+/// Computes the critical pairs of a set of rewrite rules.
+///
+/// An overlap is formed by identifying some generators of the left-hand side of
+/// one rule with generators of the same type in the left-hand side of another.
+/// It is critical when each rule applies to the overlap but deletes part of the
+/// match of the other. To decide joinability, the two results are rewritten
+/// exhaustively with the given step limit and compared up to isomorphism. This
+/// only explores one rewriting strategy, so a pair reported as not joinable may
+/// still be joinable by some other sequence of rewrites.
+pub fn critical_pairs(rules: &[RewriteRule], max_steps: usize) -> Vec<CriticalPair> {
+    let mut pairs = Vec::new();
+    for (i, rule1) in rules.iter().enumerate() {
+        for (j, rule2) in rules.iter().enumerate().skip(i) {
+            for (overlap, m1, m2) in overlaps(rule1.left(), rule2.left()) {
+                if i == j && m1 == m2 {
+                    continue;
+                }
+                let (Ok(deleted1), Ok(deleted2)) =
+                    (rule1.check_gluing(&overlap, &m1), rule2.check_gluing(&overlap, &m2))
+                else {
+                    continue;
+                };
+                if !(disables(&deleted1, &m2) || disables(&deleted2, &m1)) {
+                    continue;
+                }
+                let [result1, result2] = [(rule1, &m1), (rule2, &m2)].map(|(rule, m)| {
+                    let rewrite = rule.apply(&overlap, m).expect("Gluing condition should hold");
+                    rewrite_exhaustively(rules, rewrite.model, max_steps).model
+                });
+                pairs.push(CriticalPair {
+                    rules: (i, j),
+                    overlap,
+                    joinable: are_isomorphic(&result1, &result2),
+                });
+            }
+        }
+    }
+    pairs
+}
+
+type Deleted = (HashSet<QualifiedName>, HashSet<QualifiedName>);
+
+/// Does deleting the given generators destroy part of the image of the match?
+fn disables((obs, mors): &Deleted, m: &DiscreteDblModelMapping) -> bool {
+    m.0.ob_generator_map.values().any(|x| obs.contains(x))
+        || m.0.mor_generator_map.values().any(|path| path.iter().any(|f| mors.contains(f)))
+}
+
+/// Enumerates the nontrivial overlaps of two free models.
+///
+/// Each overlap is returned together with the inclusions of the two models.
+fn overlaps(
+    left: &DiscreteDblModel,
+    right: &DiscreteDblModel,
+) -> Vec<(DiscreteDblModel, DiscreteDblModelMapping, DiscreteDblModelMapping)> {
+    let left_obs: Vec<_> = left.ob_generators().collect();
+    let left_mors: Vec<_> = left.mor_generators().collect();
+    let right_obs: Vec<_> = right.ob_generators().collect();
+    let right_mors: Vec<_> = right.mor_generators().collect();
+
+    let mut result = Vec::new();
+    let ob_injections = partial_injections(&right_obs, &|x: &QualifiedName| {
+        let ob_type = right.ob_generator_type(x);
+        left_obs
+            .iter()
+            .filter(|y| left.ob_generator_type(y) == ob_type)
+            .cloned()
+            .collect()
+    });
+    for ob_inj in ob_injections.into_iter().filter(|inj| !inj.is_empty()) {
+        let mor_injections = partial_injections(&right_mors, &|f: &QualifiedName| {
+            let mor_type = right.mor_generator_type(f);
+            let dom = ob_inj.get(&right.mor_generator_dom(f));
+            let cod = ob_inj.get(&right.mor_generator_cod(f));
+            left_mors
+                .iter()
+                .filter(|g| {
+                    left.mor_generator_type(g) == mor_type
+                        && dom == Some(&left.mor_generator_dom(g))
+                        && cod == Some(&left.mor_generator_cod(g))
+                })
+                .cloned()
+                .collect()
+        });
+        for mor_inj in mor_injections {
+            let mut overlap = left.clone();
+            let incl = DiscreteDblModelMapping::new(
+                left_obs.iter().map(|x| (x.clone(), x.clone())),
+                left_mors.iter().map(|f| (f.clone(), Path::single(f.clone()))),
+            );
+            let mut m = DiscreteDblModelMapping::default();
+            for x in &right_obs {
+                let y = ob_inj.get(x).cloned().unwrap_or_else(|| {
+                    let y = fresh_name(x, &overlap);
+                    overlap.add_ob(y.clone(), right.ob_generator_type(x));
+                    y
+                });
+                m.assign_ob(x.clone(), y);
+            }
+            for f in &right_mors {
+                let g = mor_inj.get(f).cloned().unwrap_or_else(|| {
+                    let [dom, cod] = [right.mor_generator_dom(f), right.mor_generator_cod(f)]
+                        .map(|x| m.0.ob_generator_map.apply(x).unwrap());
+                    let g = fresh_name(f, &overlap);
+                    overlap.add_mor(g.clone(), dom, cod, right.mor_generator_type(f));
+                    g
+                });
+                m.assign_mor(f.clone(), Path::single(g));
+            }
+            result.push((overlap, incl, m));
+        }
+    }
+    result
+}
+
+/// Enumerates partial injections out of a list of generators.
+fn partial_injections(
+    dom: &[QualifiedName],
+    candidates: &dyn Fn(&QualifiedName) -> Vec<QualifiedName>,
+) -> Vec<HashMap<QualifiedName, QualifiedName>> {
+    let Some((x, rest)) = dom.split_first() else {
+        return vec![HashMap::new()];
+    };
+    let mut result = Vec::new();
+    for inj in partial_injections(rest, candidates) {
+        for y in candidates(x) {
+            if !inj.values().any(|z| *z == y) {
+                let mut inj = inj.clone();
+                inj.insert(x.clone(), y);
+                result.push(inj);
+            }
+        }
+        result.push(inj);
+    }
+    result
+}
+
+/// Are two free models isomorphic?
+fn are_isomorphic(model1: &DiscreteDblModel, model2: &DiscreteDblModel) -> bool {
+    model1.ob_generators().count() == model2.ob_generators().count()
+        && model1.mor_generators().count() == model2.mor_generators().count()
+        && !DiscreteDblModelMapping::morphisms(model1, model2)
+            .monic()
+            .max_path_len(1)
+            .find_all()
+            .is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::discrete::DiscreteDblTheory;
+    use crate::stdlib::theories::th_category;
+    use crate::zero::name;
+
+    fn point(th: Rc<DiscreteDblTheory>) -> DiscreteDblModel {
+        let mut model = DiscreteDblModel::new(th);
+        model.add_ob(name("a"), name("Object"));
+        model
+    }
+
+    /// Rule that deletes an object.
+    fn delete_object(th: Rc<DiscreteDblTheory>) -> RewriteRule {
+        let empty = DiscreteDblModel::new(th.clone());
+        RewriteRule::new(point(th), empty.clone(), empty, Default::default(), Default::default())
+            .unwrap()
+    }
+
+    /// Rule that adds a loop to an object.
+    fn add_loop(th: Rc<DiscreteDblTheory>) -> RewriteRule {
+        let mut right = point(th.clone());
+        right.add_mor(name("f"), name("a"), name("a"), Path::Id(name("Object")));
+        let inc = DiscreteDblModelMapping::new([(name("a"), name("a"))], []);
+        RewriteRule::new(point(th.clone()), point(th), right, inc.clone(), inc).unwrap()
+    }
+
+    #[test]
+    fn termination() {
+        let th = Rc::new(th_category());
+        let rules = [delete_object(th.clone()), add_loop(th.clone())];
+        let counting = WeightMeasure::new();
+        assert_eq!(check_termination(&rules, &[counting.clone(), counting.clone()]), vec![1]);
+
+        let loops = WeightMeasure::new().default_weight(0).ob_weight(name("Object"), 1);
+        assert_eq!(check_termination(&rules[1..], &[loops]), vec![0]);
+        assert!(check_termination(&rules[..1], &[counting]).is_empty());
+    }
+
+    #[test]
+    fn confluence() {
+        let th = Rc::new(th_category());
+        let rules = [delete_object(th.clone())];
+        assert!(critical_pairs(&rules, 10).is_empty());
+
+        let rules = [delete_object(th.clone()), delete_object(th.clone())];
+        let pairs = critical_pairs(&rules, 10);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].rules, (0, 1));
+        assert!(pairs[0].joinable);
+
+        let rules = [delete_object(th.clone()), add_loop(th)];
+        let pairs = critical_pairs(&rules, 10);
+        assert_eq!(pairs.len(), 1);
+        assert!(!pairs[0].joinable);
+    }
+}
@@ -0,0 +1,210 @@
+//! Stratification of models of discrete double theories.
+//!
+//! Stratifying a model divides each of its objects into strata, such as age
+//! groups or locations, which is a staple of epidemiological modeling. The
+//! strata form a graph whose edges are transitions between strata, such as
+//! aging or travel. The stratified model is generated by
+//!
+//! - an object `(x, s)` for each object generator `x` of the model and each
+//!   stratum `s`, of the same type as `x`;
+//! - a morphism `(x, e): (x, s) -> (x, t)` of the identity type on the type of
+//!   `x` for each object generator `x` and each transition `e: s -> t`;
+//! - a morphism `(f, s): (x, s) -> (y, s)` for each morphism generator
+//!   `f: x -> y` acting within the stratum `s`, and a morphism
+//!   `(f, s, t): (x, s) -> (y, t)` for each pair of distinct strata between
+//!   which `f` acts, of the same type as `f`.
+//!
+//! Which strata the morphisms act within and between is given by an
+//! [interaction specification](InteractionSpec). When all morphisms act only
+//! within strata, the generating graph of the stratified model is the
+//! [box product](crate::one::graph_products::box_product) of the generating
+//! graphs of the model and the strata. The equations of the model hold in each
+//! stratum in which all the morphisms in them act. Generators are named by
+//! concatenating names, as in [products](super::limit) of models.
+
+use std::collections::HashMap;
+
+use super::limit::pair_name;
+use super::model::DiscreteDblModel;
+use super::model_morphism::DiscreteDblModelMapping;
+use crate::dbl::model::*;
+use crate::one::{FgCategory, FinGraph, Path, PathEq, QualifiedPath};
+use crate::zero::QualifiedName;
+
+/// Strata within and between which a morphism of a model acts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Interactions {
+    /// Only within each stratum.
+    #[default]
+    WithinStrata,
+
+    /// Between every pair of strata, including within each stratum.
+    AllStrata,
+
+    /// Between the given pairs of source and target strata.
+    Between(Vec<(QualifiedName, QualifiedName)>),
+}
+
+impl Interactions {
+    /// Whether a morphism acts from the first stratum to the second.
+    pub fn contains(&self, s: &QualifiedName, t: &QualifiedName) -> bool {
+        match self {
+            Interactions::WithinStrata => s == t,
+            Interactions::AllStrata => true,
+            Interactions::Between(pairs) => pairs.iter().any(|(s2, t2)| s == s2 && t == t2),
+        }
+    }
+}
+
+/// Specification of the strata within and between which morphisms act.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InteractionSpec {
+    /// Interactions of morphism generators not given their own.
+    pub default: Interactions,
+
+    /// Interactions of specific morphism generators.
+    pub morphisms: HashMap<QualifiedName, Interactions>,
+}
+
+impl InteractionSpec {
+    /// Creates a specification with the same interactions for all morphisms.
+    pub fn new(default: Interactions) -> Self {
+        Self { default, morphisms: HashMap::new() }
+    }
+
+    /// Sets the interactions of a morphism generator.
+    pub fn with(mut self, mor: QualifiedName, interactions: Interactions) -> Self {
+        self.morphisms.insert(mor, interactions);
+        self
+    }
+
+    /// Gets the interactions of a morphism generator.
+    pub fn get(&self, mor: &QualifiedName) -> &Interactions {
+        self.morphisms.get(mor).unwrap_or(&self.default)
+    }
+}
+
+/// A stratified model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stratification {
+    /// The stratified model.
+    pub model: DiscreteDblModel,
+
+    /// Projection from the stratified model onto the base model, forgetting
+    /// the strata.
+    pub projection: DiscreteDblModelMapping,
+}
+
+/// Stratifies a model by a graph of strata.
+pub fn stratify<G>(
+    model: &DiscreteDblModel,
+    strata: &G,
+    interactions: &InteractionSpec,
+) -> Stratification
+where
+    G: FinGraph<V = QualifiedName, E = QualifiedName>,
+{
+    let mut stratified = DiscreteDblModel::new(model.theory());
+    let mut projection = DiscreteDblModelMapping::default();
+
+    for x in model.ob_generators() {
+        let typ = model.ob_generator_type(&x);
+        for s in strata.vertices() {
+            let xs = pair_name(&x, &s);
+            stratified.add_ob(xs.clone(), typ.clone());
+            projection.assign_ob(xs, x.clone());
+        }
+        for e in strata.edges() {
+            let xe = pair_name(&x, &e);
+            let (s, t) = (strata.src(&e), strata.tgt(&e));
+            stratified.add_mor(
+                xe.clone(),
+                pair_name(&x, &s),
+                pair_name(&x, &t),
+                Path::Id(typ.clone()),
+            );
+            projection.assign_mor(xe, Path::Id(x.clone()));
+        }
+    }
+
+    for f in model.mor_generators() {
+        let (x, y) = (model.mor_generator_dom(&f), model.mor_generator_cod(&f));
+        let typ = model.mor_generator_type(&f);
+        let acts = interactions.get(&f);
+        for s in strata.vertices() {
+            for t in strata.vertices().filter(|t| acts.contains(&s, t)) {
+                let name = if s == t {
+                    pair_name(&f, &s)
+                } else {
+                    pair_name(&pair_name(&f, &s), &t)
+                };
+                stratified.add_mor(name.clone(), pair_name(&x, &s), pair_name(&y, &t), typ.clone());
+                projection.assign_mor(name, Path::single(f.clone()));
+            }
+        }
+    }
+
+    for eq in model.category.equations() {
+        for s in strata.vertices() {
+            if eq.lhs.iter().chain(eq.rhs.iter()).all(|f| interactions.get(f).contains(&s, &s)) {
+                let lift = |path: &QualifiedPath| {
+                    path.clone().map(|x| pair_name(&x, &s), |f| pair_name(&f, &s))
+                };
+                stratified.add_equation(PathEq::new(lift(&eq.lhs), lift(&eq.rhs)));
+            }
+        }
+    }
+
+    Stratification { model: stratified, projection }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::discrete::model_morphism::DblModelMorphism;
+    use crate::one::HashGraph;
+    use crate::stdlib::theories::th_category;
+    use crate::validate::Validate;
+    use crate::zero::{Column, name};
+
+    #[test]
+    fn age_stratified_sir() {
+        let mut sir = DiscreteDblModel::new(Rc::new(th_category()));
+        for x in ["S", "I", "R"] {
+            sir.add_ob(name(x), name("Object"));
+        }
+        sir.add_mor(name("infect"), name("S"), name("I"), Path::Id(name("Object")));
+        sir.add_mor(name("recover"), name("I"), name("R"), Path::Id(name("Object")));
+
+        let mut ages = HashGraph::default();
+        ages.add_vertices([name("young"), name("old")]);
+        ages.add_edge(name("aging"), name("young"), name("old"));
+
+        // Infection and recovery happen within age groups.
+        let Stratification { model, projection } =
+            stratify(&sir, &ages, &InteractionSpec::default());
+        assert!(model.validate().is_ok());
+        assert_eq!(model.ob_generators().count(), 6);
+        assert_eq!(model.mor_generators().count(), 7);
+        assert!(DblModelMorphism(&projection, &model, &sir).validate().is_ok());
+        assert_eq!(model.mor_generator_dom(&name(["infect", "old"])), name(["S", "old"]));
+        assert_eq!(model.mor_generator_cod(&name(["S", "aging"])), name(["S", "old"]));
+        assert_eq!(
+            projection.0.mor_generator_map.apply(name(["I", "aging"])),
+            Some(Path::Id(name("I")))
+        );
+
+        // Infection also happens between age groups.
+        let spec = InteractionSpec::default().with(name("infect"), Interactions::AllStrata);
+        let Stratification { model, projection } = stratify(&sir, &ages, &spec);
+        assert!(model.validate().is_ok());
+        assert_eq!(model.mor_generators().count(), 9);
+        assert!(DblModelMorphism(&projection, &model, &sir).validate().is_ok());
+        let f = name(["infect", "young", "old"]);
+        assert_eq!(model.mor_generator_dom(&f), name(["S", "young"]));
+        assert_eq!(model.mor_generator_cod(&f), name(["I", "old"]));
+        assert_eq!(projection.0.mor_generator_map.apply(f), Some(Path::single(name("infect"))));
+    }
+}
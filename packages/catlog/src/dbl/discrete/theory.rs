@@ -1,8 +1,8 @@
 //! Discrete double theories.
 
+use std::collections::HashMap;
 use std::ops::Range;
 
-use derive_more::From;
 use ref_cast::RefCast;
 
 use crate::dbl::{category::*, theory::InvalidDblTheory, tree::DblTree};
@@ -18,9 +18,120 @@ use crate::zero::QualifiedName;
 ///
 /// - a discrete object in the 2-category of double categories
 /// - a double category whose underlying categories are both discrete categories
-#[derive(From, RefCast, Debug)]
-#[repr(transparent)]
-pub struct DiscreteDblTheory(pub QualifiedFpCategory);
+///
+/// The theory carries a [table](DiscreteDblTypeTable) of its basic types, computed
+/// when the theory is constructed, so the category presenting the theory should
+/// not be modified afterwards.
+#[derive(Debug)]
+pub struct DiscreteDblTheory(pub QualifiedFpCategory, DiscreteDblTypeTable);
+
+impl From<QualifiedFpCategory> for DiscreteDblTheory {
+    fn from(cat: QualifiedFpCategory) -> Self {
+        let table = DiscreteDblTypeTable::new(&cat);
+        Self(cat, table)
+    }
+}
+
+impl DiscreteDblTheory {
+    /// Gets the table of basic types in the theory.
+    pub fn type_table(&self) -> &DiscreteDblTypeTable {
+        &self.1
+    }
+}
+
+/// Dense tables of the basic types in a discrete double theory.
+///
+/// The basic morphism types are the hom types and the generating morphism types.
+/// Types are numbered, and their sources and targets are stored by number, so
+/// that checking the typing of a generator in a model takes a single lookup of
+/// its type, rather than traversing the presentation of the theory.
+#[derive(Clone, Debug, Default)]
+pub struct DiscreteDblTypeTable {
+    ob_types: Vec<QualifiedName>,
+    ob_index: HashMap<QualifiedName, usize>,
+    mor_types: Vec<QualifiedPath>,
+    mor_index: HashMap<QualifiedPath, usize>,
+    src: Vec<usize>,
+    tgt: Vec<usize>,
+    /// Basic morphism types indexed by source and target, in row-major order.
+    between: Vec<Vec<usize>>,
+}
+
+impl DiscreteDblTypeTable {
+    /// Computes the table of basic types in a presentation of a theory.
+    pub fn new(cat: &QualifiedFpCategory) -> Self {
+        let ob_types: Vec<_> = cat.ob_generators().collect();
+        let ob_index: HashMap<_, _> = ob_types.iter().cloned().zip(0..).collect();
+        let mut mor_types = Vec::new();
+        let (mut src, mut tgt) = (Vec::new(), Vec::new());
+        for (i, x) in ob_types.iter().enumerate() {
+            mor_types.push(Path::Id(x.clone()));
+            src.push(i);
+            tgt.push(i);
+        }
+        for e in cat.mor_generators() {
+            let (Some(&s), Some(&t)) = (
+                ob_index.get(&cat.mor_generator_dom(&e)),
+                ob_index.get(&cat.mor_generator_cod(&e)),
+            ) else {
+                continue;
+            };
+            mor_types.push(Path::single(e));
+            src.push(s);
+            tgt.push(t);
+        }
+        let n = ob_types.len();
+        let mut between = vec![Vec::new(); n * n];
+        for (m, (&s, &t)) in src.iter().zip(&tgt).enumerate() {
+            between[s * n + t].push(m);
+        }
+        let mor_index = mor_types.iter().cloned().zip(0..).collect();
+        Self {
+            ob_types,
+            ob_index,
+            mor_types,
+            mor_index,
+            src,
+            tgt,
+            between,
+        }
+    }
+
+    /// Gets the number of an object type, if it belongs to the theory.
+    pub fn ob_type_index(&self, x: &QualifiedName) -> Option<usize> {
+        self.ob_index.get(x).copied()
+    }
+
+    /// Gets the number of a basic morphism type, if it is one.
+    pub fn mor_type_index(&self, m: &QualifiedPath) -> Option<usize> {
+        self.mor_index.get(m).copied()
+    }
+
+    /// Gets the object type with the given number.
+    pub fn ob_type(&self, i: usize) -> &QualifiedName {
+        &self.ob_types[i]
+    }
+
+    /// Gets the basic morphism type with the given number.
+    pub fn mor_type(&self, m: usize) -> &QualifiedPath {
+        &self.mor_types[m]
+    }
+
+    /// Gets the number of the source of a basic morphism type.
+    pub fn src(&self, m: usize) -> usize {
+        self.src[m]
+    }
+
+    /// Gets the number of the target of a basic morphism type.
+    pub fn tgt(&self, m: usize) -> usize {
+        self.tgt[m]
+    }
+
+    /// Gets the basic morphism types with the given source and target.
+    pub fn mor_types_between(&self, s: usize, t: usize) -> &[usize] {
+        &self.between[s * self.ob_types.len() + t]
+    }
+}
 
 impl VDblCategory for DiscreteDblTheory {
     type Ob = QualifiedName;
@@ -129,5 +240,12 @@ mod tests {
 
         assert_eq!(th.hom_type(name("*")), Path::Id(name("*")));
         assert_eq!(th.hom_op(name("*")), Path::single(Path::Id(name("*"))));
+
+        let table = th.type_table();
+        let star = table.ob_type_index(&name("*")).unwrap();
+        let n = table.mor_type_index(&name("n").into()).unwrap();
+        assert_eq!((table.src(n), table.tgt(n)), (star, star));
+        assert_eq!(table.mor_types_between(star, star).len(), 2);
+        assert_eq!(table.mor_type_index(&Path::pair(name("n"), name("n"))), None);
     }
 }
@@ -0,0 +1,93 @@
+//! Functors between double theories.
+//!
+//! A functor between double theories is a (strict) double functor between the
+//! theories viewed as virtual double categories. It sends object types to object
+//! types, morphism types to morphism types, and likewise for operations, while
+//! preserving sources, targets, and composites. Functors between theories express
+//! inclusions and translations of theories, such as the inclusion of the theory
+//! of categories into the theory of schemas, and they are the maps along which
+//! models can be migrated from one theory to another.
+
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::theory::DblTheory;
+use crate::zero::QualifiedName;
+
+pub use super::discrete::functor::*;
+
+/// A functor between double theories.
+///
+/// Like a [`CategoryMap`](crate::one::CategoryMap), the functor may be only
+/// partially defined, in which case its methods return `None` outside of the
+/// domain of definition. Unlike a category map, the functor knows its domain and
+/// codomain theories.
+pub trait DblFunctor {
+    /// The domain theory.
+    type DomTheory: DblTheory;
+
+    /// The codomain theory.
+    type CodTheory: DblTheory;
+
+    /// Gets the domain theory.
+    fn dom(&self) -> &Self::DomTheory;
+
+    /// Gets the codomain theory.
+    fn cod(&self) -> &Self::CodTheory;
+
+    /// Applies the functor to an object type.
+    fn apply_ob_type(
+        &self,
+        x: <Self::DomTheory as DblTheory>::ObType,
+    ) -> Option<<Self::CodTheory as DblTheory>::ObType>;
+
+    /// Applies the functor to a morphism type.
+    fn apply_mor_type(
+        &self,
+        m: <Self::DomTheory as DblTheory>::MorType,
+    ) -> Option<<Self::CodTheory as DblTheory>::MorType>;
+
+    /// Applies the functor to an operation on objects.
+    fn apply_ob_op(
+        &self,
+        f: <Self::DomTheory as DblTheory>::ObOp,
+    ) -> Option<<Self::CodTheory as DblTheory>::ObOp>;
+
+    /// Applies the functor to an operation on morphisms.
+    fn apply_mor_op(
+        &self,
+        α: <Self::DomTheory as DblTheory>::MorOp,
+    ) -> Option<<Self::CodTheory as DblTheory>::MorOp>;
+}
+
+/// A failure of a map between double theories to be functorial.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag", content = "content"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum InvalidDblFunctor {
+    /// A generating object type not mapped to an object type in the codomain.
+    #[error("Object type `{0}` is not mapped to an object type in the codomain")]
+    ObType(QualifiedName),
+
+    /// A generating morphism type not mapped to a morphism type in the codomain.
+    #[error("Morphism type `{0}` is not mapped to a morphism type in the codomain")]
+    MorType(QualifiedName),
+
+    /// A generating morphism type whose source is not preserved.
+    #[error("Source of morphism type `{0}` is not preserved")]
+    SrcType(QualifiedName),
+
+    /// A generating morphism type whose target is not preserved.
+    #[error("Target of morphism type `{0}` is not preserved")]
+    TgtType(QualifiedName),
+
+    /// An equation between composites of morphism types that is not preserved.
+    #[error("Equation `{0}` between morphism types is not preserved")]
+    MorTypeEq(usize),
+}
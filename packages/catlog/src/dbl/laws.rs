@@ -0,0 +1,348 @@
+//! Checking the laws of a virtual double category.
+//!
+//! Implementations of [`VDblCategory`] are not checked by the type system to
+//! satisfy the axioms of a virtual double category. This module provides a
+//! harness that exhaustively checks the axioms on given finite sets of arrows,
+//! proarrows, and cells, by enumerating all composable tuples up to a bound on
+//! the arity of cells. It is intended to be used in the tests of any
+//! implementation of a VDC, including custom double theories defined outside
+//! this crate.
+//!
+//! The laws checked are:
+//!
+//! - boundaries of composites and identities are as expected
+//! - composition of arrows is unital and associative
+//! - composition of cells is unital and associative
+//! - composing a pasting diagram all at once agrees with composing it level by
+//!   level, which in a VDC plays the role of the interchange law
+
+use std::fmt::Debug;
+
+use nonempty::NonEmpty;
+use ref_cast::RefCast;
+
+use super::category::{UnderlyingDblGraph, VDblCategory};
+use super::graph::ProedgeGraph;
+use super::tree::{DblNode, DblTree};
+use crate::one::{Path, tree::OpenTree};
+use crate::validate;
+
+/// A law of a virtual double category.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VDblLaw {
+    /// Composites and identities have the expected boundaries.
+    Boundary,
+
+    /// Identity arrows are units for composition of arrows.
+    ArrowUnit,
+
+    /// Composition of arrows is associative.
+    ArrowAssociativity,
+
+    /// Identity cells are units for composition of cells.
+    CellUnit,
+
+    /// Composition of cells is associative.
+    CellAssociativity,
+
+    /// Composing a pasting of cells at once agrees with composing by levels.
+    Interchange,
+}
+
+/// A failure of a law of a virtual double category.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LawViolation {
+    /// The law that fails.
+    pub law: VDblLaw,
+
+    /// Description of the data on which the law fails.
+    pub description: String,
+}
+
+/// Exhaustive checker for the laws of a virtual double category.
+///
+/// The laws are checked on all composable tuples of the given arrows,
+/// proarrows, and cells, together with the identity arrows and cells on them.
+/// Cells whose arity exceeds the maximum arity are ignored, which bounds the
+/// number of composable tuples.
+pub struct VDblLawChecker<'a, VDC: VDblCategory> {
+    vdc: &'a VDC,
+    arrows: Vec<VDC::Arr>,
+    proarrows: Vec<VDC::Pro>,
+    cells: Vec<VDC::Cell>,
+    max_arity: usize,
+}
+
+impl<'a, VDC> VDblLawChecker<'a, VDC>
+where
+    VDC: VDblCategory,
+    VDC::Ob: Debug,
+    VDC::Arr: Debug,
+    VDC::Pro: Debug,
+    VDC::Cell: Debug,
+{
+    /// Creates a law checker for a VDC, initially with no data.
+    pub fn new(vdc: &'a VDC) -> Self {
+        Self {
+            vdc,
+            arrows: Vec::new(),
+            proarrows: Vec::new(),
+            cells: Vec::new(),
+            max_arity: 2,
+        }
+    }
+
+    /// Adds arrows on which to check the laws.
+    pub fn arrows(mut self, arrows: impl IntoIterator<Item = VDC::Arr>) -> Self {
+        self.arrows.extend(arrows);
+        self
+    }
+
+    /// Adds proarrows on which to check the laws.
+    pub fn proarrows(mut self, proarrows: impl IntoIterator<Item = VDC::Pro>) -> Self {
+        self.proarrows.extend(proarrows);
+        self
+    }
+
+    /// Adds cells on which to check the laws.
+    pub fn cells(mut self, cells: impl IntoIterator<Item = VDC::Cell>) -> Self {
+        self.cells.extend(cells);
+        self
+    }
+
+    /// Sets the maximum arity of cells to check, by default 2.
+    pub fn max_arity(mut self, n: usize) -> Self {
+        self.max_arity = n;
+        self
+    }
+
+    /// Checks the laws, returning all violations found.
+    pub fn check(&self) -> Result<(), NonEmpty<LawViolation>> {
+        let mut violations = Vec::new();
+        self.check_arrows(&mut violations);
+
+        let vdc = self.vdc;
+        let mut cells: Vec<_> = self.proarrows.iter().map(|m| vdc.id_cell(m.clone())).collect();
+        for (m, cell) in self.proarrows.iter().zip(cells.iter()) {
+            self.check_id_cell(m, cell, &mut violations);
+        }
+        cells.extend(self.cells.iter().filter(|cell| vdc.arity(cell) <= self.max_arity).cloned());
+        for cell in &cells {
+            self.check_cell(cell, &mut violations);
+        }
+        self.check_cell_composition(&cells, &mut violations);
+        validate::wrap_errors(violations.into_iter())
+    }
+
+    fn check_arrows(&self, violations: &mut Vec<LawViolation>) {
+        let vdc = self.vdc;
+        let mut violate = |law, description| violations.push(LawViolation { law, description });
+
+        for f in &self.arrows {
+            let (x, y) = (vdc.dom(f), vdc.cod(f));
+            if vdc.compose2(vdc.id(x.clone()), f.clone()) != *f
+                || vdc.compose2(f.clone(), vdc.id(y.clone())) != *f
+            {
+                violate(VDblLaw::ArrowUnit, format!("Arrow {f:?} with identities"));
+            }
+            let id = vdc.id(x.clone());
+            if vdc.dom(&id) != x || vdc.cod(&id) != x {
+                violate(VDblLaw::Boundary, format!("Identity arrow on {x:?}"));
+            }
+        }
+
+        let composable = |f: &VDC::Arr, g: &VDC::Arr| vdc.cod(f) == vdc.dom(g);
+        for f in &self.arrows {
+            for g in self.arrows.iter().filter(|g| composable(f, g)) {
+                let fg = vdc.compose2(f.clone(), g.clone());
+                if vdc.dom(&fg) != vdc.dom(f) || vdc.cod(&fg) != vdc.cod(g) {
+                    violate(VDblLaw::Boundary, format!("Composite of {f:?} and {g:?}"));
+                }
+                for h in self.arrows.iter().filter(|h| composable(g, h)) {
+                    let gh = vdc.compose2(g.clone(), h.clone());
+                    let lhs = vdc.compose2(fg.clone(), h.clone());
+                    let rhs = vdc.compose2(f.clone(), gh);
+                    let all =
+                        vdc.compose(Path::from_vec(vec![f.clone(), g.clone(), h.clone()]).unwrap());
+                    if lhs != rhs || lhs != all {
+                        violate(
+                            VDblLaw::ArrowAssociativity,
+                            format!("Composite of {f:?}, {g:?}, and {h:?}"),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_id_cell(&self, m: &VDC::Pro, cell: &VDC::Cell, violations: &mut Vec<LawViolation>) {
+        let vdc = self.vdc;
+        if vdc.cell_dom(cell) != Path::single(m.clone())
+            || vdc.cell_cod(cell) != *m
+            || vdc.cell_src(cell) != vdc.id(vdc.src(m))
+            || vdc.cell_tgt(cell) != vdc.id(vdc.tgt(m))
+        {
+            violations.push(LawViolation {
+                law: VDblLaw::Boundary,
+                description: format!("Identity cell on {m:?}"),
+            });
+        }
+    }
+
+    fn check_cell(&self, cell: &VDC::Cell, violations: &mut Vec<LawViolation>) {
+        let vdc = self.vdc;
+        let graph = ProedgeGraph::ref_cast(UnderlyingDblGraph::ref_cast(vdc));
+        let (dom, cod) = (vdc.cell_dom(cell), vdc.cell_cod(cell));
+        let (src, tgt) = (vdc.cell_src(cell), vdc.cell_tgt(cell));
+        if dom.src(graph) != vdc.dom(&src)
+            || dom.tgt(graph) != vdc.dom(&tgt)
+            || vdc.src(&cod) != vdc.cod(&src)
+            || vdc.tgt(&cod) != vdc.cod(&tgt)
+        {
+            violations.push(LawViolation {
+                law: VDblLaw::Boundary,
+                description: format!("Cell {cell:?}"),
+            });
+        }
+
+        let ids = dom.iter().map(|m| vdc.id_cell(m.clone()));
+        let above = vdc.compose_cells2(ids, cell.clone());
+        let below = vdc.compose_cells2([cell.clone()], vdc.id_cell(cod));
+        if above != *cell || below != *cell {
+            violations.push(LawViolation {
+                law: VDblLaw::CellUnit,
+                description: format!("Cell {cell:?} with identities"),
+            });
+        }
+    }
+
+    fn check_cell_composition(&self, cells: &[VDC::Cell], violations: &mut Vec<LawViolation>) {
+        let vdc = self.vdc;
+        let graph = UnderlyingDblGraph::ref_cast(vdc);
+        for β in cells {
+            for αs in self.fillers(&vdc.cell_dom(β), cells) {
+                let βα = vdc.compose_cells2(αs.clone(), β.clone());
+                let dom = Path::collect(αs.iter().map(|α| vdc.cell_dom(α)))
+                    .map_or_else(|| vdc.cell_dom(β), |paths| paths.flatten());
+                if vdc.cell_dom(&βα) != dom || vdc.cell_cod(&βα) != vdc.cell_cod(β) {
+                    violations.push(LawViolation {
+                        law: VDblLaw::Boundary,
+                        description: format!("Composite of {αs:?} with {β:?}"),
+                    });
+                    continue;
+                }
+
+                for γs in self.fillers(&dom, cells) {
+                    // Split the top level of cells according to the middle level.
+                    let mut rest = γs.iter().cloned();
+                    let γss: Vec<Vec<_>> =
+                        αs.iter().map(|α| rest.by_ref().take(vdc.arity(α)).collect()).collect();
+
+                    let lhs = vdc.compose_cells2(γs.clone(), βα.clone());
+                    let rhs = vdc.compose_cells2(
+                        std::iter::zip(γss.iter(), αs.iter())
+                            .map(|(γs, α)| vdc.compose_cells2(γs.clone(), α.clone())),
+                        β.clone(),
+                    );
+                    if lhs != rhs {
+                        violations.push(LawViolation {
+                            law: VDblLaw::CellAssociativity,
+                            description: format!("Composite of {γs:?} with {αs:?} and {β:?}"),
+                        });
+                    }
+
+                    let subtrees = std::iter::zip(γss, αs.iter())
+                        .map(|(γs, α)| DblTree::two_level(γs, α.clone(), graph).0);
+                    let tree = DblTree(OpenTree::graft(subtrees, DblNode::Cell(β.clone())));
+                    if vdc.compose_cells(tree) != lhs {
+                        violations.push(LawViolation {
+                            law: VDblLaw::Interchange,
+                            description: format!("Pasting of {γs:?} with {αs:?} and {β:?}"),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Enumerates the tuples of cells whose codomains form the given path and
+    /// whose sources and targets are compatible.
+    fn fillers(&self, path: &Path<VDC::Ob, VDC::Pro>, cells: &[VDC::Cell]) -> Vec<Vec<VDC::Cell>> {
+        let vdc = self.vdc;
+        let mut tuples = vec![Vec::new()];
+        for m in path.iter() {
+            let mut extended = Vec::new();
+            for tuple in tuples {
+                for cell in cells.iter().filter(|cell| vdc.cell_cod(cell) == *m) {
+                    if tuple.last().is_none_or(|prev| vdc.cell_tgt(prev) == vdc.cell_src(cell)) {
+                        let mut tuple = tuple.clone();
+                        tuple.push(cell.clone());
+                        extended.push(tuple);
+                    }
+                }
+            }
+            tuples = extended;
+        }
+        tuples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbl::category::VDCWithComposites;
+    use crate::dbl::theory::{DblTheory, TabMorType, TabObProj, TabObType};
+    use crate::stdlib::theories::{th_category_links, th_schema};
+    use crate::zero::name;
+
+    #[test]
+    fn discrete_theory_laws() {
+        let th = th_schema();
+        let (entity, attr_type) = (name("Entity"), name("AttrType"));
+        let (id_entity, id_attr_type) = (Path::Id(entity.clone()), Path::Id(attr_type.clone()));
+        let attr: Path<_, _> = name("Attr").into();
+        let cells = [
+            Path::Id(entity.clone()),
+            Path::single(id_entity.clone()),
+            Path::single(attr.clone()),
+            Path::pair(id_entity.clone(), attr.clone()),
+            Path::pair(attr.clone(), id_attr_type.clone()),
+            Path::pair(id_entity.clone(), id_entity.clone()),
+        ];
+        let checker = VDblLawChecker::new(&th)
+            .arrows([entity, attr_type])
+            .proarrows([id_entity, attr, id_attr_type])
+            .cells(cells);
+        assert_eq!(checker.check(), Ok(()));
+    }
+
+    #[test]
+    fn tabulator_theory_laws() {
+        let th = th_category_links();
+        let x = TabObType::Basic(name("Object"));
+        let hom = th.hom_type(x.clone());
+        let tab = th.tabulator(hom.clone());
+        let link = TabMorType::Basic(name("Link"));
+        let hom_tab = th.hom_type(tab.clone());
+
+        let arrows = [
+            Path::Id(x.clone()),
+            Path::Id(tab.clone()),
+            Path::single(TabObProj::Src(hom.clone())),
+            Path::single(TabObProj::Tgt(hom.clone())),
+        ];
+        let paths = [
+            Path::Id(x.clone()),
+            Path::Id(tab.clone()),
+            Path::pair(hom.clone(), link.clone()),
+            Path::pair(link.clone(), hom_tab.clone()),
+            Path::pair(hom.clone(), hom.clone()),
+        ];
+        let cells = paths.into_iter().map(|path| th.composite_ext(path).unwrap());
+        let checker = VDblLawChecker::new(&th)
+            .arrows(arrows)
+            .proarrows([hom, link, hom_tab])
+            .cells(cells);
+        assert_eq!(checker.check(), Ok(()));
+    }
+}
@@ -0,0 +1,8 @@
+//! Limits of models of double theories.
+//!
+//! The [product](https://ncatlab.org/nlab/show/product) of models combines
+//! independent models into a single model. Its objects and morphisms are tuples
+//! of objects and morphisms of the factors of the same type, and it comes with
+//! projection morphisms onto each factor.
+
+pub use super::discrete::limit::*;
@@ -14,6 +14,7 @@
 //!   categories
 //! - [Virtual double graphs](graph), the data underlying a virtual double category
 //! - [Double trees](tree), the data structure for pasting diagrams in a VDC
+//! - [Law checking](laws), a test harness for implementations of VDCs
 //!
 //! ## Double-categorical logic
 //!
@@ -21,11 +22,17 @@
 //!
 //! - [Double theories](theory), a kind of two-dimensional
 //!   [theory](https://ncatlab.org/nlab/show/theory) in the sense of logic
+//! - [Functors](functor) between double theories, expressing inclusions and
+//!   translations of theories
 //! - [Models](model) of double theories, which are categorical structures
 //! - [Morphisms](model_morphism) between models of double theories, generalizing
 //!   functors between categories
 //! - [Diagrams](model_diagram) in a model, generalizing
 //!   [diagrams](https://ncatlab.org/nlab/show/diagram) in a category
+//! - [Migration](model_migration) of models along functors between theories
+//! - [Colimits](colimit) of models, such as pushouts gluing models along a
+//!   shared interface
+//! - [Limits](limit) of models, such as products combining independent models
 //!
 //! These submodules mostly provide traits and generic data structures applicable to
 //! any kind of double theory, model, etc. Specific kinds are implemented in the
@@ -50,8 +57,14 @@ pub mod computad;
 pub mod graph;
 pub mod tree;
 
+pub mod laws;
+
+pub mod colimit;
+pub mod functor;
+pub mod limit;
 pub mod model;
 pub mod model_diagram;
+pub mod model_migration;
 pub mod model_morphism;
 pub mod theory;
 
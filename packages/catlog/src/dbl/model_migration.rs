@@ -0,0 +1,14 @@
+//! Migration of models along functors between double theories.
+//!
+//! A [functor](super::functor) F: S → T between double theories induces two ways
+//! to move models between the theories:
+//!
+//! - *Pulling back* a model of T gives a model of S, reinterpreting the objects
+//!   and morphisms of each type in T as having every type in S sent to it
+//! - *Pushing forward* a model of S gives a model of T, sending the type of each
+//!   object and morphism along F
+//!
+//! These let users relax a model into a less structured logic or refine it into a
+//! more structured one without rebuilding the model from scratch.
+
+pub use super::discrete::model_migration::*;
@@ -0,0 +1,245 @@
+//! Instrumentation of long-running algorithms.
+//!
+//! Backtracking searches, such as finding morphisms between models or
+//! evaluating queries, can take exponential time on unlucky inputs. To help
+//! users and developers see why a search is slow, such algorithms report events
+//! to an [`Instrument`] as they run. The standard instrument is [`SearchStats`],
+//! which counts the events and measures wall time, and is returned alongside the
+//! results of the search. The unit type is an instrument that ignores all
+//! events.
+//!
+//! Searches can also be bounded by a [`Budget`] on the number of nodes visited
+//! and the wall time taken. A search that runs out of budget stops early and
+//! reports that it was [exhausted](BudgetExhausted), rather than returning
+//! incomplete results as if they were complete. Budgets on nodes are the only
+//! way to bound a search when compiled to WebAssembly, where there is no clock.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+/// Receiver of events from a search algorithm.
+///
+/// All methods do nothing by default.
+pub trait Instrument {
+    /// A node of the search tree is visited.
+    fn node(&mut self) {}
+
+    /// A candidate is rejected, forcing the search to backtrack.
+    fn backtrack(&mut self) {}
+
+    /// A solution is found.
+    fn solution(&mut self) {}
+}
+
+impl Instrument for () {}
+
+/// Counters reported by a search algorithm.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct SearchStats {
+    /// Number of nodes of the search tree visited.
+    pub nodes: u64,
+
+    /// Number of candidates rejected.
+    pub backtracks: u64,
+
+    /// Number of solutions found.
+    pub solutions: u64,
+
+    /// Wall time taken by the search in microseconds, if measured.
+    ///
+    /// Wall time is not measured when compiled to WebAssembly, where the
+    /// standard library has no clock.
+    #[cfg_attr(feature = "serde", serde(rename = "elapsedMicros"))]
+    pub elapsed_micros: Option<u64>,
+}
+
+impl Instrument for SearchStats {
+    fn node(&mut self) {
+        self.nodes += 1;
+    }
+    fn backtrack(&mut self) {
+        self.backtracks += 1;
+    }
+    fn solution(&mut self) {
+        self.solutions += 1;
+    }
+}
+
+impl SearchStats {
+    /// Adds the counters of another search to these ones.
+    pub fn merge(&mut self, other: &SearchStats) {
+        self.nodes += other.nodes;
+        self.backtracks += other.backtracks;
+        self.solutions += other.solutions;
+        self.elapsed_micros = match (self.elapsed_micros, other.elapsed_micros) {
+            (Some(t), Some(s)) => Some(t + s),
+            (t, s) => t.or(s),
+        };
+    }
+}
+
+/// Limits on the resources used by a search.
+///
+/// The default budget is unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct Budget {
+    /// Maximum number of nodes of the search tree to visit.
+    #[cfg_attr(feature = "serde", serde(rename = "maxNodes"))]
+    pub max_nodes: Option<u64>,
+
+    /// Maximum wall time in milliseconds, ignored where there is no clock.
+    #[cfg_attr(feature = "serde", serde(rename = "maxMillis"))]
+    pub max_millis: Option<u64>,
+}
+
+impl Budget {
+    /// The unlimited budget.
+    pub fn unlimited() -> Self {
+        Default::default()
+    }
+
+    /// A budget limiting the number of nodes visited.
+    pub fn nodes(n: u64) -> Self {
+        Self { max_nodes: Some(n), max_millis: None }
+    }
+
+    /// Limits the wall time of the search.
+    pub fn millis(mut self, ms: u64) -> Self {
+        self.max_millis = Some(ms);
+        self
+    }
+
+    /// Budget remaining after the resources used by a search.
+    pub fn remaining(&self, used: &SearchStats) -> Self {
+        let used_millis = used.elapsed_micros.unwrap_or(0) / 1000;
+        Self {
+            max_nodes: self.max_nodes.map(|n| n.saturating_sub(used.nodes)),
+            max_millis: self.max_millis.map(|ms| ms.saturating_sub(used_millis)),
+        }
+    }
+}
+
+/// A search stopped early because it ran out of budget.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("Search budget exhausted after visiting {} nodes", .stats.nodes)]
+pub struct BudgetExhausted {
+    /// Counters reported by the search up to the point it stopped.
+    pub stats: SearchStats,
+}
+
+/// Enforces a budget during a search.
+pub(crate) struct BudgetGuard {
+    budget: Budget,
+    watch: Stopwatch,
+}
+
+impl BudgetGuard {
+    /// Number of nodes visited between checks of the clock.
+    const CLOCK_INTERVAL: u64 = 256;
+
+    /// Starts enforcing a budget.
+    pub(crate) fn start(budget: Budget) -> Self {
+        Self { budget, watch: Stopwatch::start() }
+    }
+
+    /// Checks that the search has not exceeded the budget.
+    pub(crate) fn check(&self, stats: &SearchStats) -> Result<(), BudgetExhausted> {
+        let over_nodes = self.budget.max_nodes.is_some_and(|n| stats.nodes > n);
+        let over_time = self.budget.max_millis.is_some_and(|ms| {
+            stats.nodes % Self::CLOCK_INTERVAL == 0
+                && self.watch.elapsed_micros().is_some_and(|t| t / 1000 >= ms)
+        });
+        if over_nodes || over_time {
+            let mut stats = stats.clone();
+            self.watch.record(&mut stats);
+            Err(BudgetExhausted { stats })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Records the wall time of the search.
+    pub(crate) fn stop(self, stats: &mut SearchStats) {
+        self.watch.stop(stats);
+    }
+}
+
+/// Measures the wall time of a search, where a clock is available.
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    /// Starts the stopwatch.
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /// Gets the time elapsed since the stopwatch started, if measurable.
+    pub(crate) fn elapsed_micros(&self) -> Option<u64> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let elapsed = Some(self.start.elapsed().as_micros().try_into().unwrap_or(u64::MAX));
+        #[cfg(target_arch = "wasm32")]
+        let elapsed = None;
+        elapsed
+    }
+
+    /// Records the time elapsed so far.
+    pub(crate) fn record(&self, stats: &mut SearchStats) {
+        stats.elapsed_micros = self.elapsed_micros();
+    }
+
+    /// Records the time elapsed since the stopwatch started.
+    pub(crate) fn stop(self, stats: &mut SearchStats) {
+        self.record(stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_events() {
+        let mut stats = SearchStats::default();
+        let watch = Stopwatch::start();
+        stats.node();
+        stats.node();
+        stats.backtrack();
+        stats.solution();
+        watch.stop(&mut stats);
+        assert_eq!((stats.nodes, stats.backtracks, stats.solutions), (2, 1, 1));
+        assert!(stats.elapsed_micros.is_some());
+
+        let mut total = SearchStats::default();
+        total.merge(&stats);
+        total.merge(&stats);
+        assert_eq!(total.nodes, 4);
+    }
+
+    #[test]
+    fn enforce_budget() {
+        let guard = BudgetGuard::start(Budget::nodes(2));
+        let mut stats = SearchStats::default();
+        stats.node();
+        stats.node();
+        assert!(guard.check(&stats).is_ok());
+        stats.node();
+        assert_eq!(guard.check(&stats).map_err(|err| err.stats.nodes), Err(3));
+        assert_eq!(Budget::nodes(2).remaining(&stats).max_nodes, Some(0));
+        assert_eq!(Budget::unlimited().remaining(&stats), Budget::unlimited());
+    }
+}
@@ -20,6 +20,8 @@
 pub mod refs;
 
 pub mod egglog_util;
+pub mod instrument;
+pub mod lint;
 pub mod validate;
 
 pub mod dbl;
@@ -0,0 +1,184 @@
+//! Soft constraints, or lints, on objects such as models.
+//!
+//! Unlike [validation](crate::validate), which decides whether an object is well
+//! defined at all, linting flags objects that are well defined but suspicious,
+//! such as a model containing an object not connected to anything else. Lints
+//! are named, so that they can be individually allowed, and carry a severity.
+//!
+//! Lints are organized into [lint sets](LintSet), typically one per theory, since
+//! what counts as suspicious depends heavily on what the model is meant to
+//! represent. The standard lint sets are found in the
+//! [standard library](crate::stdlib::lints).
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::zero::QualifiedName;
+
+/// Severity level of a lint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum Severity {
+    /// Worth pointing out, but often intended.
+    Info,
+
+    /// Probably a mistake.
+    Warning,
+}
+
+/// A named soft constraint on objects of type `T`.
+///
+/// A lint is checked by a function that returns the identifiers of the elements
+/// of the object, such as object or morphism generators of a model, that violate
+/// the constraint.
+pub struct Lint<T> {
+    /// Unique name of the lint, conventionally in snake case.
+    pub name: &'static str,
+
+    /// Severity of violations of the lint.
+    pub severity: Severity,
+
+    /// Human-readable description of a violation.
+    pub message: &'static str,
+
+    /// Function returning the elements that violate the lint.
+    pub check: fn(&T) -> Vec<QualifiedName>,
+}
+
+impl<T> Lint<T> {
+    /// Checks the lint against an object.
+    pub fn run(&self, x: &T) -> impl Iterator<Item = LintDiagnostic> + '_ {
+        (self.check)(x).into_iter().map(|subject| LintDiagnostic {
+            lint: self.name.into(),
+            severity: self.severity,
+            subject,
+            message: self.message.into(),
+        })
+    }
+}
+
+/// A violation of a lint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct LintDiagnostic {
+    /// Name of the violated lint.
+    pub lint: String,
+
+    /// Severity of the violation.
+    pub severity: Severity,
+
+    /// Element violating the lint.
+    pub subject: QualifiedName,
+
+    /// Description of the violation.
+    pub message: String,
+}
+
+/// A collection of lints, usually those appropriate for a particular theory.
+pub struct LintSet<T> {
+    lints: Vec<Lint<T>>,
+}
+
+impl<T> Default for LintSet<T> {
+    fn default() -> Self {
+        Self { lints: Vec::new() }
+    }
+}
+
+impl<T> LintSet<T> {
+    /// Creates an empty lint set.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a lint to the set, replacing any existing lint with the same name.
+    pub fn with(mut self, lint: Lint<T>) -> Self {
+        self.lints.retain(|other| other.name != lint.name);
+        self.lints.push(lint);
+        self
+    }
+
+    /// Removes the lint with the given name from the set, if present.
+    pub fn allow(mut self, name: &str) -> Self {
+        self.lints.retain(|lint| lint.name != name);
+        self
+    }
+
+    /// Iterates over the names of the lints in the set.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.lints.iter().map(|lint| lint.name)
+    }
+
+    /// Checks all lints in the set against an object.
+    ///
+    /// Diagnostics are ordered by decreasing severity, then by the order in which
+    /// the lints were added to the set.
+    pub fn run(&self, x: &T) -> Vec<LintDiagnostic> {
+        let mut diagnostics: Vec<_> = self.lints.iter().flat_map(|lint| lint.run(x)).collect();
+        diagnostics.sort_by(|d1, d2| d2.severity.cmp(&d1.severity));
+        diagnostics
+    }
+}
+
+impl<T> FromIterator<Lint<T>> for LintSet<T> {
+    fn from_iter<I: IntoIterator<Item = Lint<T>>>(iter: I) -> Self {
+        iter.into_iter().fold(LintSet::new(), LintSet::with)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zero::name;
+
+    struct Names(Vec<&'static str>);
+
+    fn lint_short(names: &Names) -> Vec<QualifiedName> {
+        names.0.iter().filter(|x| x.len() < 3).map(|x| name(*x)).collect()
+    }
+
+    fn lint_empty(names: &Names) -> Vec<QualifiedName> {
+        names.0.iter().filter(|x| x.is_empty()).map(|x| name(*x)).collect()
+    }
+
+    #[test]
+    fn lint_set() {
+        let lints: LintSet<Names> = [
+            Lint {
+                name: "short",
+                severity: Severity::Info,
+                message: "Name is short",
+                check: lint_short,
+            },
+            Lint {
+                name: "empty",
+                severity: Severity::Warning,
+                message: "Name is empty",
+                check: lint_empty,
+            },
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(lints.names().collect::<Vec<_>>(), vec!["short", "empty"]);
+
+        let diagnostics = lints.run(&Names(vec!["x", "", "long"]));
+        let summary: Vec<_> = diagnostics.iter().map(|d| (d.lint.as_str(), d.severity)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("empty", Severity::Warning),
+                ("short", Severity::Info),
+                ("short", Severity::Info)
+            ]
+        );
+
+        let lints = lints.allow("short");
+        assert_eq!(lints.run(&Names(vec!["x", "", "long"])).len(), 1);
+    }
+}
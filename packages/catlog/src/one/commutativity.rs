@@ -0,0 +1,130 @@
+//! Enumeration of commutative diagrams in finitely presented categories.
+//!
+//! In a finitely presented category, such as a schema, the path equations are
+//! often the whole point: they say which diagrams commute. This module enumerates
+//! the pairs of parallel paths between two objects and reports which of them
+//! commute, that is, are equal in the presented category.
+
+use std::hash::Hash;
+
+use super::{
+    category::Category, fp_category::FpCategory, graph_algorithms::bounded_simple_paths, path::Path,
+};
+
+/// Shape of a diagram formed by a pair of parallel paths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiagramShape {
+    /// A pair of parallel morphisms, both generators.
+    Parallel,
+
+    /// A triangle: a generator parallel to a path of length two.
+    Triangle,
+
+    /// A square: two paths of length two.
+    Square,
+
+    /// Any other shape, including those involving identities.
+    Other,
+}
+
+/// A pair of parallel paths in an f.p. category.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParallelPaths<V, E> {
+    /// Left-hand path.
+    pub lhs: Path<V, E>,
+
+    /// Right-hand path.
+    pub rhs: Path<V, E>,
+
+    /// Whether the two paths are equal in the category.
+    pub commutes: bool,
+}
+
+impl<V, E> ParallelPaths<V, E> {
+    /// Gets the shape of the diagram formed by the two paths.
+    pub fn shape(&self) -> DiagramShape {
+        match (self.lhs.len(), self.rhs.len()) {
+            (1, 1) => DiagramShape::Parallel,
+            (1, 2) | (2, 1) => DiagramShape::Triangle,
+            (2, 2) => DiagramShape::Square,
+            _ => DiagramShape::Other,
+        }
+    }
+}
+
+/// Enumerates pairs of parallel paths between two objects of an f.p. category.
+///
+/// Only [simple paths](bounded_simple_paths) with length at most `max_length` are
+/// considered, so that the enumeration is finite even when the generating graph
+/// has cycles. Each unordered pair of distinct paths is reported once, together
+/// with whether the paths are equal under the path equations of the category.
+pub fn parallel_paths<V, E>(
+    cat: &FpCategory<V, E>,
+    x: &V,
+    y: &V,
+    max_length: usize,
+) -> Vec<ParallelPaths<V, E>>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash,
+{
+    let paths: Vec<_> = bounded_simple_paths(cat.generators(), x, y, Some(max_length)).collect();
+    let mut pairs = Vec::new();
+    for (i, lhs) in paths.iter().enumerate() {
+        for rhs in &paths[(i + 1)..] {
+            let commutes = cat.morphisms_are_equal(lhs.clone(), rhs.clone());
+            pairs.push(ParallelPaths {
+                lhs: lhs.clone(),
+                rhs: rhs.clone(),
+                commutes,
+            });
+        }
+    }
+    pairs
+}
+
+/// Enumerates the commutative diagrams between two objects of an f.p. category.
+///
+/// Returns those pairs of [parallel paths](parallel_paths) that commute.
+pub fn commutative_diagrams<V, E>(
+    cat: &FpCategory<V, E>,
+    x: &V,
+    y: &V,
+    max_length: usize,
+) -> Vec<ParallelPaths<V, E>>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash,
+{
+    let mut pairs = parallel_paths(cat, x, y, max_length);
+    pairs.retain(|pair| pair.commutes);
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::one::fp_category::{sch_graph, sch_sgraph};
+    use crate::zero::name;
+
+    #[test]
+    fn graph_schema() {
+        let sch = sch_graph();
+        let pairs = parallel_paths(&sch, &name("E"), &name("V"), 2);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].shape(), DiagramShape::Parallel);
+        assert!(!pairs[0].commutes);
+        assert!(commutative_diagrams(&sch, &name("E"), &name("V"), 2).is_empty());
+    }
+
+    #[test]
+    fn symmetric_graph_schema() {
+        let sch = sch_sgraph();
+        let pairs = parallel_paths(&sch, &name("E"), &name("V"), 2);
+        // Parallel paths: `src`, `tgt`, `inv ⋅ src`, `inv ⋅ tgt`.
+        assert_eq!(pairs.len(), 6);
+        let commuting = commutative_diagrams(&sch, &name("E"), &name("V"), 2);
+        assert_eq!(commuting.len(), 2);
+        assert!(commuting.iter().all(|pair| pair.shape() == DiagramShape::Triangle));
+    }
+}
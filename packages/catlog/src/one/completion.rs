@@ -0,0 +1,257 @@
+//! Knuth-Bendix completion for finitely presented categories.
+//!
+//! Regarding paths as words in the morphism generators, the path equations of a
+//! [finitely presented category](super::fp_category) define a string rewriting
+//! system in which each equation is oriented from the larger side to the smaller
+//! one. *Knuth-Bendix completion* adds rewriting rules derived from overlaps
+//! between left-hand sides until the system is *confluent*, at which point every
+//! path rewrites to a unique normal form and two paths are equal in the category
+//! if and only if their normal forms coincide. This is a decision procedure for
+//! the word problem whenever completion terminates.
+//!
+//! Completion need not terminate, as the word problem for f.p. categories is
+//! undecidable in general, so it is bounded by a [`Budget`] on the number of
+//! critical pairs considered. Paths are ordered by *shortlex* order: shorter
+//! paths are smaller, and paths of the same length are compared
+//! lexicographically using the ordering on morphism generators. Since rewriting
+//! rules relate parallel paths, rewriting a composable path yields another
+//! composable path with the same source and target.
+
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use super::{category::Category, fp_category::FpCategory, graph::Graph, path::*};
+use crate::instrument::{Budget, BudgetExhausted, BudgetGuard, Instrument, SearchStats};
+
+/// A rewriting rule on paths, replacing the left-hand side by the right.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathRewriteRule<E> {
+    /// The sequence of edges to rewrite.
+    pub lhs: Vec<E>,
+
+    /// The sequence of edges to replace it with, empty for an identity.
+    pub rhs: Vec<E>,
+}
+
+/// A terminating rewriting system on paths in a graph.
+///
+/// Rules are oriented by shortlex order, so rewriting always terminates. When
+/// produced by [completion](complete), the system is also confluent and its
+/// normal forms decide equality of paths.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PathRewritingSystem<E> {
+    rules: Vec<PathRewriteRule<E>>,
+}
+
+impl<E: Ord + Clone> PathRewritingSystem<E> {
+    /// Iterates over the rules of the system.
+    pub fn rules(&self) -> impl Iterator<Item = &PathRewriteRule<E>> {
+        self.rules.iter()
+    }
+
+    /// Rewrites a sequence of edges until no rule applies.
+    pub fn reduce(&self, mut word: Vec<E>) -> Vec<E> {
+        while let Some((rule, i)) =
+            self.rules.iter().find_map(|rule| Some((rule, find_subword(&word, &rule.lhs)?)))
+        {
+            word.splice(i..i + rule.lhs.len(), rule.rhs.iter().cloned());
+        }
+        word
+    }
+
+    /// Computes the normal form of a path in a graph.
+    pub fn normalize<V>(&self, graph: &impl Graph<V = V, E = E>, path: Path<V, E>) -> Path<V, E>
+    where
+        V: Eq + Clone,
+    {
+        let src = path.src(graph);
+        Path::from_vec(self.reduce(path.into_iter().collect())).unwrap_or(Path::Id(src))
+    }
+
+    /// Are the two paths equal modulo the rewriting system?
+    ///
+    /// The paths are assumed to be parallel. The answer is conclusive only if the
+    /// system is confluent.
+    pub fn paths_are_equal<V>(&self, path1: Path<V, E>, path2: Path<V, E>) -> bool {
+        self.reduce(path1.into_iter().collect()) == self.reduce(path2.into_iter().collect())
+    }
+
+    /// Adds an equation between words to the system as a new rule, if it does not
+    /// already follow, and queues the resulting critical pairs.
+    fn add_equation(&mut self, lhs: Vec<E>, rhs: Vec<E>, pending: &mut VecDeque<(Vec<E>, Vec<E>)>) {
+        let (lhs, rhs) = (self.reduce(lhs), self.reduce(rhs));
+        let (lhs, rhs) = match shortlex_cmp(&lhs, &rhs) {
+            std::cmp::Ordering::Equal => return,
+            std::cmp::Ordering::Greater => (lhs, rhs),
+            std::cmp::Ordering::Less => (rhs, lhs),
+        };
+
+        // Rules whose left-hand side is reducible by the new rule are removed and
+        // reconsidered as equations.
+        let (reducible, mut rules): (Vec<_>, Vec<_>) = std::mem::take(&mut self.rules)
+            .into_iter()
+            .partition(|rule| find_subword(&rule.lhs, &lhs).is_some());
+        pending.extend(reducible.into_iter().map(|rule| (rule.lhs, rule.rhs)));
+
+        let new_rule = PathRewriteRule { lhs, rhs };
+        for rule in &rules {
+            pending.extend(critical_pairs(&new_rule, rule));
+            pending.extend(critical_pairs(rule, &new_rule));
+        }
+        pending.extend(critical_pairs(&new_rule, &new_rule));
+        rules.push(new_rule);
+        self.rules = rules;
+
+        // Keep the right-hand sides in normal form.
+        for i in 0..self.rules.len() {
+            let rhs = std::mem::take(&mut self.rules[i].rhs);
+            self.rules[i].rhs = self.reduce(rhs);
+        }
+    }
+}
+
+/// Completes a list of path equations to a confluent rewriting system.
+///
+/// Returns an error if the budget is exhausted before completion terminates.
+/// Each critical pair considered counts as one node against the budget.
+pub fn complete<'a, V, E>(
+    equations: impl IntoIterator<Item = &'a PathEq<V, E>>,
+    budget: Budget,
+) -> Result<PathRewritingSystem<E>, BudgetExhausted>
+where
+    V: 'a + Clone,
+    E: 'a + Ord + Clone,
+{
+    let guard = BudgetGuard::start(budget);
+    let mut stats = SearchStats::default();
+    let mut system = PathRewritingSystem { rules: Vec::new() };
+    let mut pending: VecDeque<_> = equations
+        .into_iter()
+        .map(|eq| (eq.lhs.clone().into_iter().collect(), eq.rhs.clone().into_iter().collect()))
+        .collect();
+    while let Some((lhs, rhs)) = pending.pop_front() {
+        stats.node();
+        guard.check(&stats)?;
+        system.add_equation(lhs, rhs, &mut pending);
+    }
+    Ok(system)
+}
+
+/// Computes the critical pairs arising from overlaps of a suffix of the first
+/// left-hand side with a prefix of the second.
+fn critical_pairs<E: Clone + Eq>(
+    first: &PathRewriteRule<E>,
+    second: &PathRewriteRule<E>,
+) -> Vec<(Vec<E>, Vec<E>)> {
+    let (l1, l2) = (&first.lhs, &second.lhs);
+    (1..l1.len().min(l2.len()))
+        .filter(|&k| l1[l1.len() - k..] == l2[..k])
+        .map(|k| {
+            // The word `l1[..n-k] l1[n-k..] l2[k..]` rewrites in two ways.
+            let left = first.rhs.iter().chain(&l2[k..]).cloned().collect();
+            let right = l1[..l1.len() - k].iter().chain(&second.rhs).cloned().collect();
+            (left, right)
+        })
+        .collect()
+}
+
+/// Finds the first position at which a nonempty subword occurs in a word.
+fn find_subword<E: Eq>(word: &[E], sub: &[E]) -> Option<usize> {
+    if sub.is_empty() {
+        return None;
+    }
+    word.windows(sub.len()).position(|w| w == sub)
+}
+
+/// Compares two words in shortlex order.
+fn shortlex_cmp<E: Ord>(w1: &[E], w2: &[E]) -> std::cmp::Ordering {
+    w1.len().cmp(&w2.len()).then_with(|| w1.cmp(w2))
+}
+
+/// Completion of the path equations of an f.p. category.
+impl<V, E> FpCategory<V, E>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash + Ord,
+{
+    /// Completes the presentation to a confluent rewriting system on paths.
+    ///
+    /// The returned system decides equality of morphisms in the category and can
+    /// be reused for many queries.
+    pub fn complete(&self, budget: Budget) -> Result<PathRewritingSystem<E>, BudgetExhausted> {
+        complete(self.equations(), budget)
+    }
+
+    /// Are the two morphisms equal, deciding by completion when possible?
+    ///
+    /// If completion terminates within the budget, the answer is decided by
+    /// comparing normal forms. Otherwise, it falls back to the e-graph, which can
+    /// prove equalities but may fail to detect that paths are equal.
+    pub fn morphisms_are_equal_within(
+        &self,
+        path1: Path<V, E>,
+        path2: Path<V, E>,
+        budget: Budget,
+    ) -> bool {
+        match self.complete(budget) {
+            Ok(system) => system.paths_are_equal(path1, path2),
+            Err(_) => self.morphisms_are_equal(path1, path2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nonempty::nonempty;
+
+    use super::*;
+    use crate::one::fp_category::sch_sgraph;
+    use crate::zero::name;
+
+    #[test]
+    fn complete_sch_sgraph() {
+        let sch = sch_sgraph();
+        let system = sch.complete(Budget::nodes(100)).unwrap();
+        assert_eq!(system.rules().count(), 3);
+        let path = Path::Seq(nonempty![name("inv"), name("inv"), name("inv"), name("src")]);
+        assert_eq!(system.normalize(sch.generators(), path), Path::single(name("tgt")));
+        let path = Path::pair(name("inv"), name("inv"));
+        assert_eq!(system.normalize(sch.generators(), path), Path::Id(name("E")));
+        assert!(!system.paths_are_equal(Path::single(name("src")), Path::single(name("tgt"))));
+    }
+
+    #[test]
+    fn complete_with_new_rules() {
+        // The monoid with generators `a`, `b` and relation `aba = b`, presented
+        // as a one-object category. Completion adds the rule `bba -> abb`.
+        let mut cat: FpCategory<char, char> = FpCategory::new();
+        cat.add_ob_generator('x');
+        cat.add_mor_generator('a', 'x', 'x');
+        cat.add_mor_generator('b', 'x', 'x');
+        cat.equate(Path::Seq(nonempty!['a', 'b', 'a']), Path::single('b'));
+        let system = cat.complete(Budget::nodes(1000)).unwrap();
+        assert_eq!(system.rules().count(), 2);
+        assert!(system.paths_are_equal(
+            Path::Seq(nonempty!['b', 'b', 'a']),
+            Path::Seq(nonempty!['a', 'b', 'b'])
+        ));
+        assert!(!system.paths_are_equal(Path::pair('a', 'b'), Path::pair('b', 'a')));
+    }
+
+    #[test]
+    fn budget_exhausted() {
+        // Completion of the braid relation `aba = bab` in shortlex order is
+        // infinite, so only the fallback to the e-graph can answer.
+        let mut cat: FpCategory<char, char> = FpCategory::new();
+        cat.add_ob_generator('x');
+        cat.add_mor_generator('a', 'x', 'x');
+        cat.add_mor_generator('b', 'x', 'x');
+        cat.equate(Path::Seq(nonempty!['a', 'b', 'a']), Path::Seq(nonempty!['b', 'a', 'b']));
+        assert!(cat.complete(Budget::nodes(200)).is_err());
+        assert!(cat.morphisms_are_equal_within(
+            Path::Seq(nonempty!['a', 'b', 'a', 'a']),
+            Path::Seq(nonempty!['b', 'a', 'b', 'a']),
+            Budget::nodes(200)
+        ));
+    }
+}
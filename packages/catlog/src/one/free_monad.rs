@@ -0,0 +1,221 @@
+//! Free monads on finite signatures.
+//!
+//! A finite *signature*, or finitary polynomial, consists of typed operations,
+//! each having a list of input types and an output type. The free monad on a
+//! signature sends a set of variables to the set of [open trees](OpenTree) built
+//! from the operations, with boundary nodes as variables. This module
+//! enumerates these trees up to a given depth, which is useful for exhaustively
+//! testing compositions of trees and for enumerating candidate cells, and counts
+//! them by depth or size using the generating functions of the signature.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::tree::OpenTree;
+
+/// An operation in a [signature](Signature).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureOp<Ty, Op> {
+    /// The operation.
+    pub op: Op,
+
+    /// Types of the inputs to the operation.
+    pub dom: Vec<Ty>,
+
+    /// Type of the output of the operation.
+    pub cod: Ty,
+}
+
+/// A finite signature of typed operations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature<Ty, Op> {
+    ops: Vec<SignatureOp<Ty, Op>>,
+}
+
+impl<Ty, Op> Default for Signature<Ty, Op> {
+    fn default() -> Self {
+        Self { ops: Vec::new() }
+    }
+}
+
+impl<Ty, Op> Signature<Ty, Op>
+where
+    Ty: Eq + Hash + Clone,
+    Op: Clone,
+{
+    /// Creates an empty signature.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds an operation to the signature.
+    pub fn add_op(&mut self, op: Op, dom: impl IntoIterator<Item = Ty>, cod: Ty) {
+        let dom = dom.into_iter().collect();
+        self.ops.push(SignatureOp { op, dom, cod });
+    }
+
+    /// Iterates over the operations in the signature.
+    pub fn ops(&self) -> impl Iterator<Item = &SignatureOp<Ty, Op>> {
+        self.ops.iter()
+    }
+
+    /// Iterates over the operations with the given output type.
+    pub fn ops_with_cod<'a>(&'a self, ty: &'a Ty) -> impl Iterator<Item = &'a SignatureOp<Ty, Op>> {
+        self.ops.iter().filter(move |op| op.cod == *ty)
+    }
+
+    /// Generates all open trees of the given type up to the given depth.
+    ///
+    /// The identity tree has depth 0, a single operation has depth 1, and so
+    /// on. Trees are generated in order of increasing depth, so the result
+    /// starts with the identity tree.
+    pub fn trees(&self, ty: &Ty, depth: usize) -> Vec<OpenTree<Ty, Op>> {
+        let mut memo = HashMap::new();
+        self.trees_memo(ty, depth, &mut memo).clone()
+    }
+
+    fn trees_memo<'a>(
+        &self,
+        ty: &Ty,
+        depth: usize,
+        memo: &'a mut HashMap<(Ty, usize), Vec<OpenTree<Ty, Op>>>,
+    ) -> &'a Vec<OpenTree<Ty, Op>> {
+        let key = (ty.clone(), depth);
+        if !memo.contains_key(&key) {
+            let mut trees = vec![OpenTree::Id(ty.clone())];
+            if depth > 0 {
+                for op in self.ops_with_cod(ty) {
+                    // Take all combinations of shallower subtrees.
+                    let mut combos: Vec<Vec<OpenTree<Ty, Op>>> = vec![Vec::new()];
+                    for input in &op.dom {
+                        let subtrees = self.trees_memo(input, depth - 1, memo);
+                        combos = combos
+                            .into_iter()
+                            .flat_map(|combo| {
+                                subtrees.iter().map(move |subtree| {
+                                    let mut combo = combo.clone();
+                                    combo.push(subtree.clone());
+                                    combo
+                                })
+                            })
+                            .collect();
+                    }
+                    trees.extend(
+                        combos.into_iter().map(|subtrees| OpenTree::graft(subtrees, op.op.clone())),
+                    );
+                }
+            }
+            memo.insert(key.clone(), trees);
+        }
+        &memo[&key]
+    }
+
+    /// Counts the open trees of the given type up to the given depth.
+    ///
+    /// Counts are computed by iterating the polynomial functor of the
+    /// signature, without generating the trees, and saturate at [`usize::MAX`].
+    pub fn count_by_depth(&self, ty: &Ty, depth: usize) -> usize {
+        let mut counts: HashMap<&Ty, usize> = HashMap::new();
+        for _ in 0..depth {
+            let mut next: HashMap<&Ty, usize> = HashMap::new();
+            for op in &self.ops {
+                let product = op.dom.iter().fold(1usize, |acc, input| {
+                    acc.saturating_mul(counts.get(input).copied().unwrap_or(1))
+                });
+                let count = next.entry(&op.cod).or_insert(1);
+                *count = count.saturating_add(product);
+            }
+            counts = next;
+        }
+        counts.get(ty).copied().unwrap_or(1)
+    }
+
+    /// Counts the open trees of the given type by size, up to the given size.
+    ///
+    /// Returns the coefficients of the generating function `F_ty(z)` of trees
+    /// counted by number of operations, which is the solution of the system of
+    /// equations `F_ty(z) = 1 + z Σ_op Π_i F_{dom_i}(z)`, truncated at degree
+    /// `max_size`. Counts saturate at [`usize::MAX`].
+    pub fn count_by_size(&self, ty: &Ty, max_size: usize) -> Vec<usize> {
+        let one = {
+            let mut series = vec![0; max_size + 1];
+            series[0] = 1;
+            series
+        };
+        let mut series: HashMap<&Ty, Vec<usize>> = HashMap::new();
+        // Each iteration fixes at least one more coefficient.
+        for _ in 0..=max_size {
+            let mut next: HashMap<&Ty, Vec<usize>> = HashMap::new();
+            for op in &self.ops {
+                let product = op.dom.iter().fold(one.clone(), |acc, input| {
+                    mul_truncated(&acc, series.get(input).unwrap_or(&one))
+                });
+                let sum = next.entry(&op.cod).or_insert_with(|| one.clone());
+                // Multiply by `z`, shifting coefficients up by one.
+                for k in 1..=max_size {
+                    sum[k] = sum[k].saturating_add(product[k - 1]);
+                }
+            }
+            series = next;
+        }
+        series.remove(ty).unwrap_or(one)
+    }
+}
+
+/// Multiplies two power series truncated at the same degree.
+fn mul_truncated(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let n = a.len();
+    let mut result = vec![0usize; n];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b[..n - i].iter().enumerate() {
+            result[i + j] = result[i + j].saturating_add(x.saturating_mul(y));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Signature of a single binary operation and a constant.
+    fn magma() -> Signature<char, char> {
+        let mut sig = Signature::new();
+        sig.add_op('m', ['X', 'X'], 'X');
+        sig.add_op('e', [], 'X');
+        sig
+    }
+
+    #[test]
+    fn generate_trees() {
+        let sig = magma();
+        assert_eq!(sig.trees(&'X', 0), vec![OpenTree::Id('X')]);
+
+        let trees = sig.trees(&'X', 1);
+        assert_eq!(trees.len(), 3);
+        assert!(trees[1].is_isomorphic_to(&OpenTree::single('m', 2)));
+        assert!(trees[2].is_isomorphic_to(&OpenTree::single('e', 0)));
+
+        for depth in 0..4 {
+            let trees = sig.trees(&'X', depth);
+            assert_eq!(trees.len(), sig.count_by_depth(&'X', depth));
+        }
+        assert_eq!(sig.trees(&'Y', 3), vec![OpenTree::Id('Y')]);
+    }
+
+    #[test]
+    fn count_trees() {
+        // Depth counts satisfy `c(d+1) = 2 + c(d)^2`.
+        let sig = magma();
+        let counts: Vec<_> = (0..5).map(|depth| sig.count_by_depth(&'X', depth)).collect();
+        assert_eq!(counts, vec![1, 3, 11, 123, 15131]);
+
+        // Size counts: `F = 1 + z (F^2 + 1)`.
+        let by_size = sig.count_by_size(&'X', 3);
+        assert_eq!(by_size, vec![1, 2, 4, 12]);
+        let trees = sig.trees(&'X', 3);
+        for (size, count) in by_size.iter().enumerate() {
+            assert_eq!(trees.iter().filter(|tree| tree.size() == size).count(), *count);
+        }
+    }
+}
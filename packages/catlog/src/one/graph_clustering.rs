@@ -0,0 +1,239 @@
+//! Hierarchical clustering of collections of graphs.
+//!
+//! Given many graphs, such as the underlying graphs of models submitted by the
+//! students of a class, the pairwise distances between them are computed and
+//! the graphs are clustered by agglomerative hierarchical clustering with
+//! average linkage (UPGMA). Cutting the resulting [dendrogram](Dendrogram) gives
+//! a flat clustering, in which each cluster is represented by its medoid, the
+//! member least distant in total from the others.
+//!
+//! The clustering itself needs only a [distance matrix](DistanceMatrix), so it
+//! applies to any distance, such as the [edit distance](super::graph_edit)
+//! between graphs.
+
+use super::graph_edit::EditCosts;
+use super::graph_io::LabeledGraph;
+use crate::instrument::{Budget, BudgetExhausted, BudgetGuard, Instrument, SearchStats};
+
+/// A symmetric matrix of distances, as a vector of rows.
+pub type DistanceMatrix = Vec<Vec<f32>>;
+
+/// A merge of two clusters in a dendrogram.
+///
+/// Clusters are numbered as in SciPy: the clusters `0..n` are the singletons
+/// and the cluster `n + k` is the one created by the `k`th merge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merge {
+    /// The two clusters merged.
+    pub clusters: (usize, usize),
+
+    /// Average distance between members of the two clusters.
+    pub distance: f32,
+
+    /// Number of members of the merged cluster.
+    pub size: usize,
+}
+
+/// The result of hierarchical clustering, as a sequence of merges.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dendrogram {
+    /// Number of clustered items.
+    pub n_items: usize,
+
+    /// Merges in order of increasing distance.
+    pub merges: Vec<Merge>,
+}
+
+/// A cluster in a flat clustering.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cluster {
+    /// Indices of the members, in increasing order.
+    pub members: Vec<usize>,
+
+    /// Index of the medoid of the cluster.
+    pub medoid: usize,
+}
+
+/// Computes the pairwise edit distances between graphs.
+///
+/// See [`LabeledGraph::edit_distance`] for the meaning of the arguments. Since
+/// the number of pairs is quadratic in the number of graphs, the computation is
+/// bounded by a budget, against which each pair counts as one node.
+pub fn edit_distance_matrix(
+    graphs: &[LabeledGraph],
+    costs: &EditCosts,
+    max_states: usize,
+    budget: Budget,
+) -> Result<DistanceMatrix, BudgetExhausted> {
+    let guard = BudgetGuard::start(budget);
+    let mut stats = SearchStats::default();
+    let n = graphs.len();
+    let mut distances = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            stats.node();
+            guard.check(&stats)?;
+            let d = graphs[i].edit_distance(&graphs[j], costs, max_states).cost;
+            distances[i][j] = d;
+            distances[j][i] = d;
+        }
+    }
+    Ok(distances)
+}
+
+/// Clusters items hierarchically by average linkage.
+pub fn hierarchical_clustering(distances: &DistanceMatrix) -> Dendrogram {
+    let n = distances.len();
+    // Active clusters, by ID, with their sizes and distances to each other.
+    let mut active: Vec<(usize, usize)> = (0..n).map(|i| (i, 1)).collect();
+    let mut linkage = distances.clone();
+    let mut merges = Vec::with_capacity(n.saturating_sub(1));
+    while active.len() > 1 {
+        let (mut a, mut b) = (0, 1);
+        for i in 0..active.len() {
+            for j in (i + 1)..active.len() {
+                if linkage[i][j] < linkage[a][b] {
+                    (a, b) = (i, j);
+                }
+            }
+        }
+        let ((id_a, size_a), (id_b, size_b)) = (active[a], active[b]);
+        let size = size_a + size_b;
+        merges.push(Merge {
+            clusters: (id_a.min(id_b), id_a.max(id_b)),
+            distance: linkage[a][b],
+            size,
+        });
+
+        // The average distance to the merged cluster is the size-weighted mean
+        // of the average distances to its parts.
+        for k in 0..active.len() {
+            let d = (size_a as f32 * linkage[a][k] + size_b as f32 * linkage[b][k]) / size as f32;
+            linkage[a][k] = d;
+            linkage[k][a] = d;
+        }
+        linkage[a][a] = 0.0;
+        active[a] = (n + merges.len() - 1, size);
+        active.remove(b);
+        linkage.remove(b);
+        for row in &mut linkage {
+            row.remove(b);
+        }
+    }
+    Dendrogram { n_items: n, merges }
+}
+
+impl Dendrogram {
+    /// Cuts the dendrogram into at most the given number of clusters.
+    ///
+    /// Returns the members of each cluster, with clusters ordered by their least
+    /// member.
+    pub fn cut(&self, n_clusters: usize) -> Vec<Vec<usize>> {
+        let n_merges = self.n_items.saturating_sub(n_clusters.max(1));
+        let mut clusters: Vec<Option<Vec<usize>>> =
+            (0..self.n_items).map(|i| Some(vec![i])).collect();
+        for merge in &self.merges[..n_merges.min(self.merges.len())] {
+            let (a, b) = merge.clusters;
+            let mut members = clusters[a].take().expect("Cluster should be merged once");
+            members.extend(clusters[b].take().expect("Cluster should be merged once"));
+            clusters.push(Some(members));
+        }
+        let mut result: Vec<_> = clusters
+            .into_iter()
+            .flatten()
+            .map(|mut members| {
+                members.sort();
+                members
+            })
+            .collect();
+        result.sort();
+        result
+    }
+}
+
+/// Finds the medoid of a set of items, the one with least total distance to
+/// the others.
+pub fn medoid(distances: &DistanceMatrix, members: &[usize]) -> Option<usize> {
+    members.iter().copied().min_by(|&i, &j| {
+        let total = |k: usize| members.iter().map(|&l| distances[k][l]).sum::<f32>();
+        total(i).total_cmp(&total(j))
+    })
+}
+
+/// Clusters items into at most the given number of clusters, with medoids.
+pub fn cluster_with_medoids(distances: &DistanceMatrix, n_clusters: usize) -> Vec<Cluster> {
+    hierarchical_clustering(distances)
+        .cut(n_clusters)
+        .into_iter()
+        .map(|members| {
+            let medoid = medoid(distances, &members).expect("Clusters should be nonempty");
+            Cluster { members, medoid }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::graph_io::{LabeledEdge, LabeledNode};
+    use super::*;
+
+    fn path_graph(labels: &[&str]) -> LabeledGraph {
+        LabeledGraph {
+            nodes: labels
+                .iter()
+                .map(|label| LabeledNode {
+                    id: label.to_string(),
+                    label: Some(label.to_string()),
+                    node_type: None,
+                })
+                .collect(),
+            edges: labels
+                .windows(2)
+                .map(|w| LabeledEdge {
+                    id: format!("{}{}", w[0], w[1]),
+                    source: w[0].to_string(),
+                    target: w[1].to_string(),
+                    label: None,
+                    edge_type: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn average_linkage() {
+        let distances = vec![
+            vec![0.0, 1.0, 5.0, 6.0],
+            vec![1.0, 0.0, 4.0, 5.0],
+            vec![5.0, 4.0, 0.0, 2.0],
+            vec![6.0, 5.0, 2.0, 0.0],
+        ];
+        let dendrogram = hierarchical_clustering(&distances);
+        let merges: Vec<_> = dendrogram.merges.iter().map(|m| (m.clusters, m.distance)).collect();
+        assert_eq!(merges, vec![((0, 1), 1.0), ((2, 3), 2.0), ((4, 5), 5.0)]);
+        assert_eq!(dendrogram.cut(2), vec![vec![0, 1], vec![2, 3]]);
+        assert_eq!(dendrogram.cut(1), vec![vec![0, 1, 2, 3]]);
+        assert_eq!(dendrogram.cut(10).len(), 4);
+    }
+
+    #[test]
+    fn cluster_graphs() {
+        let graphs = vec![
+            path_graph(&["S", "I", "R"]),
+            path_graph(&["S", "I", "R", "D"]),
+            path_graph(&["x", "y"]),
+            path_graph(&["S", "E", "I", "R"]),
+            path_graph(&["y", "x"]),
+        ];
+        let costs = EditCosts::default();
+        assert!(edit_distance_matrix(&graphs, &costs, 1000, Budget::nodes(5)).is_err());
+        let distances = edit_distance_matrix(&graphs, &costs, 1000, Budget::unlimited()).unwrap();
+        assert_eq!(distances[0][1], 2.0);
+        assert_eq!(distances[2][4], distances[4][2]);
+
+        let clusters = cluster_with_medoids(&distances, 2);
+        let members: Vec<_> = clusters.iter().map(|c| c.members.clone()).collect();
+        assert_eq!(members, vec![vec![0, 1, 3], vec![2, 4]]);
+        assert_eq!(clusters[0].medoid, 1);
+    }
+}
@@ -0,0 +1,509 @@
+//! Edit distance between labeled graphs.
+//!
+//! The **graph edit distance** between two graphs is the least total cost of a
+//! sequence of edits, namely insertions, deletions, and substitutions of nodes
+//! and edges, transforming one graph into the other. Applied to the underlying
+//! [labeled graphs](LabeledGraph) of two models, via
+//! [`from_model`](LabeledGraph::from_model), it measures how different the
+//! models are, say when comparing models built by different students for the
+//! same exercise. Along with the distance, the edits are returned as an
+//! alignment between the nodes and edges of the two graphs.
+//!
+//! Computing the edit distance exactly is NP-hard. It is found by A* search over
+//! assignments of nodes of the first graph to nodes of the second, which is
+//! exact but exponential in the worst case. When the search visits more than a
+//! given number of states, it falls back to completing the most promising
+//! partial assignment greedily, yielding an upper bound on the distance.
+//! Between each pair of assigned nodes, edges are matched greedily, which is
+//! exact unless there are parallel edges with differing labels and types.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::graph_io::LabeledGraph;
+
+/// Costs of edits between labeled graphs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EditCosts {
+    /// Cost of inserting or deleting a node.
+    pub node: f32,
+
+    /// Cost of inserting or deleting an edge.
+    pub edge: f32,
+
+    /// Cost of changing the label of a node.
+    pub node_label: f32,
+
+    /// Cost of changing the type of a node.
+    pub node_type: f32,
+
+    /// Cost of changing the label of an edge.
+    pub edge_label: f32,
+
+    /// Cost of changing the type of an edge.
+    pub edge_type: f32,
+}
+
+impl Default for EditCosts {
+    fn default() -> Self {
+        Self {
+            node: 1.0,
+            edge: 1.0,
+            node_label: 0.5,
+            node_type: 1.0,
+            edge_label: 0.5,
+            edge_type: 1.0,
+        }
+    }
+}
+
+/// An alignment between two labeled graphs, with its cost.
+///
+/// Each pair of IDs matches a node or edge of the first graph with one of the
+/// second. A missing first ID means an insertion and a missing second ID a
+/// deletion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphAlignment {
+    /// Total cost of the edits.
+    pub cost: f32,
+
+    /// Pairs of matched node IDs.
+    pub nodes: Vec<(Option<String>, Option<String>)>,
+
+    /// Pairs of matched edge IDs.
+    pub edges: Vec<(Option<String>, Option<String>)>,
+
+    /// Whether the alignment is known to be optimal.
+    ///
+    /// This is false when the search was cut off and the alignment completed
+    /// greedily.
+    pub exact: bool,
+}
+
+impl LabeledGraph {
+    /// Computes the edit distance to another graph and an optimal alignment.
+    ///
+    /// At most `max_states` partial alignments are expanded before the search
+    /// falls back to a greedy completion.
+    pub fn edit_distance(
+        &self,
+        other: &LabeledGraph,
+        costs: &EditCosts,
+        max_states: usize,
+    ) -> GraphAlignment {
+        let problem = EditProblem::new(self, other, costs);
+        let (mapping, exact) = problem.search(max_states);
+        problem.alignment(&mapping, exact)
+    }
+}
+
+/// A graph with nodes indexed by integers and edges grouped by endpoints.
+struct IndexedGraph<'a> {
+    graph: &'a LabeledGraph,
+    edges: HashMap<(usize, usize), Vec<usize>>,
+}
+
+impl<'a> IndexedGraph<'a> {
+    fn new(graph: &'a LabeledGraph) -> Self {
+        let index: HashMap<_, _> =
+            graph.nodes.iter().enumerate().map(|(i, node)| (node.id.as_str(), i)).collect();
+        let mut edges: HashMap<_, Vec<_>> = HashMap::new();
+        for (e, edge) in graph.edges.iter().enumerate() {
+            let (Some(&source), Some(&target)) =
+                (index.get(edge.source.as_str()), index.get(edge.target.as_str()))
+            else {
+                continue;
+            };
+            edges.entry((source, target)).or_default().push(e);
+        }
+        Self { graph, edges }
+    }
+
+    fn edges_between(&self, source: usize, target: usize) -> &[usize] {
+        self.edges.get(&(source, target)).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+struct EditProblem<'a> {
+    first: IndexedGraph<'a>,
+    second: IndexedGraph<'a>,
+    costs: &'a EditCosts,
+    /// Nodes of the first graph in the order they are assigned.
+    order: Vec<usize>,
+}
+
+/// A partial assignment of nodes of the first graph in the search.
+#[derive(Clone)]
+struct SearchState {
+    /// Images of the first nodes in the order of assignment; `None` for deleted.
+    mapping: Vec<Option<usize>>,
+    used: Vec<bool>,
+    cost: f32,
+    bound: f32,
+}
+
+impl PartialEq for SearchState {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SearchState {}
+
+impl PartialOrd for SearchState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SearchState {
+    /// Orders states so that the max-heap pops the least bound first, breaking
+    /// ties in favor of deeper states.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .bound
+            .total_cmp(&self.bound)
+            .then_with(|| self.mapping.len().cmp(&other.mapping.len()))
+    }
+}
+
+impl<'a> EditProblem<'a> {
+    fn new(first: &'a LabeledGraph, second: &'a LabeledGraph, costs: &'a EditCosts) -> Self {
+        let first = IndexedGraph::new(first);
+        let second = IndexedGraph::new(second);
+
+        // Assigning nodes of high degree first prunes the search sooner.
+        let mut degree = vec![0; first.graph.nodes.len()];
+        for (&(source, target), edges) in &first.edges {
+            degree[source] += edges.len();
+            degree[target] += edges.len();
+        }
+        let mut order: Vec<_> = (0..degree.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(degree[i]));
+
+        Self { first, second, costs, order }
+    }
+
+    /// Searches for an optimal assignment of nodes, returning the images of the
+    /// first nodes by index and whether the assignment is known to be optimal.
+    fn search(&self, max_states: usize) -> (Vec<Option<usize>>, bool) {
+        let n = self.second.graph.nodes.len();
+        let root = SearchState {
+            mapping: Vec::new(),
+            used: vec![false; n],
+            cost: 0.0,
+            bound: self.heuristic(0, n),
+        };
+        let mut queue = BinaryHeap::from([root]);
+        let mut n_expanded = 0;
+        while let Some(state) = queue.pop() {
+            if state.mapping.len() == self.order.len() {
+                return (self.unorder(&state.mapping), true);
+            }
+            if n_expanded >= max_states {
+                let state = self.greedy_completion(state);
+                return (self.unorder(&state.mapping), false);
+            }
+            n_expanded += 1;
+            queue.extend(self.children(&state));
+        }
+        unreachable!("Search should reach a complete assignment")
+    }
+
+    /// Gets the states extending a state by assigning the next node.
+    fn children(&self, state: &SearchState) -> Vec<SearchState> {
+        let candidates = (0..state.used.len()).filter(|&j| !state.used[j]).map(Some);
+        std::iter::once(None)
+            .chain(candidates)
+            .map(|image| {
+                let mut child = state.clone();
+                child.cost += self.assignment_cost(&state.mapping, image);
+                if let Some(j) = image {
+                    child.used[j] = true;
+                }
+                child.mapping.push(image);
+                let n_free = child.used.iter().filter(|used| !**used).count();
+                if child.mapping.len() == self.order.len() {
+                    child.cost += self.insertion_cost(&child.used);
+                    child.bound = child.cost;
+                } else {
+                    child.bound = child.cost + self.heuristic(child.mapping.len(), n_free);
+                }
+                child
+            })
+            .collect()
+    }
+
+    /// Completes a partial assignment by choosing the cheapest child at each step.
+    fn greedy_completion(&self, mut state: SearchState) -> SearchState {
+        while state.mapping.len() < self.order.len() {
+            state = self
+                .children(&state)
+                .into_iter()
+                .min_by(|s, t| s.cost.total_cmp(&t.cost))
+                .expect("Deletion is always possible");
+        }
+        state
+    }
+
+    /// Lower bound on the cost of assigning the remaining nodes.
+    fn heuristic(&self, depth: usize, n_free: usize) -> f32 {
+        let n_remaining = self.order.len() - depth;
+        n_remaining.abs_diff(n_free) as f32 * self.costs.node
+    }
+
+    /// Cost of assigning the next node, given the previous assignments.
+    ///
+    /// Includes the cost of the edges between the node and previously assigned
+    /// nodes, including itself.
+    fn assignment_cost(&self, mapping: &[Option<usize>], image: Option<usize>) -> f32 {
+        let i = self.order[mapping.len()];
+        let mut cost = match image {
+            Some(j) => self.node_cost(i, j),
+            None => self.costs.node,
+        };
+        let previous = std::iter::zip(&self.order, mapping).map(|(&i, &image)| (i, image));
+        for (k, k_image) in previous.chain([(i, image)]) {
+            let mut pairs = vec![((i, image), (k, k_image))];
+            if k != i {
+                pairs.push(((k, k_image), (i, image)));
+            }
+            for ((source, source_image), (target, target_image)) in pairs {
+                let first = self.first.edges_between(source, target);
+                let second: &[usize] = match (source_image, target_image) {
+                    (Some(s), Some(t)) => self.second.edges_between(s, t),
+                    _ => &[],
+                };
+                cost += self.match_edges(first, second).0;
+            }
+        }
+        cost
+    }
+
+    /// Cost of inserting the nodes of the second graph left unassigned, and
+    /// their edges.
+    fn insertion_cost(&self, used: &[bool]) -> f32 {
+        let n_nodes = used.iter().filter(|used| !**used).count();
+        let n_edges = self
+            .second
+            .edges
+            .iter()
+            .filter(|((s, t), _)| !used[*s] || !used[*t])
+            .map(|(_, edges)| edges.len())
+            .sum::<usize>();
+        n_nodes as f32 * self.costs.node + n_edges as f32 * self.costs.edge
+    }
+
+    fn node_cost(&self, i: usize, j: usize) -> f32 {
+        let (x, y) = (&self.first.graph.nodes[i], &self.second.graph.nodes[j]);
+        let mut cost = 0.0;
+        if x.label != y.label {
+            cost += self.costs.node_label;
+        }
+        if x.node_type != y.node_type {
+            cost += self.costs.node_type;
+        }
+        cost.min(2.0 * self.costs.node)
+    }
+
+    fn edge_cost(&self, e: usize, f: usize) -> f32 {
+        let (e, f) = (&self.first.graph.edges[e], &self.second.graph.edges[f]);
+        let mut cost = 0.0;
+        if e.label != f.label {
+            cost += self.costs.edge_label;
+        }
+        if e.edge_type != f.edge_type {
+            cost += self.costs.edge_type;
+        }
+        cost
+    }
+
+    /// Matches parallel edges greedily, cheapest pairs first.
+    ///
+    /// Returns the cost and the matched pairs, with unmatched edges inserted or
+    /// deleted.
+    fn match_edges(&self, first: &[usize], second: &[usize]) -> (f32, Vec<(usize, usize)>) {
+        if first.is_empty() || second.is_empty() {
+            return ((first.len() + second.len()) as f32 * self.costs.edge, Vec::new());
+        }
+        let mut candidates: Vec<_> = first
+            .iter()
+            .flat_map(|&e| second.iter().map(move |&f| (e, f)))
+            .map(|(e, f)| (self.edge_cost(e, f), e, f))
+            .filter(|(cost, _, _)| *cost < 2.0 * self.costs.edge)
+            .collect();
+        candidates.sort_by(|(c, _, _), (d, _, _)| c.total_cmp(d));
+
+        let (mut used_first, mut used_second) = (Vec::new(), Vec::new());
+        let mut cost = 0.0;
+        let mut pairs = Vec::new();
+        for (pair_cost, e, f) in candidates {
+            if !used_first.contains(&e) && !used_second.contains(&f) {
+                used_first.push(e);
+                used_second.push(f);
+                cost += pair_cost;
+                pairs.push((e, f));
+            }
+        }
+        let n_unmatched = first.len() + second.len() - 2 * pairs.len();
+        (cost + n_unmatched as f32 * self.costs.edge, pairs)
+    }
+
+    /// Converts a mapping in the order of assignment to one by node index.
+    fn unorder(&self, mapping: &[Option<usize>]) -> Vec<Option<usize>> {
+        let mut result = vec![None; mapping.len()];
+        for (&i, &image) in std::iter::zip(&self.order, mapping) {
+            result[i] = image;
+        }
+        result
+    }
+
+    /// Computes the alignment and its cost from a complete node mapping.
+    fn alignment(&self, mapping: &[Option<usize>], exact: bool) -> GraphAlignment {
+        let (nodes1, nodes2) = (&self.first.graph.nodes, &self.second.graph.nodes);
+        let mut used = vec![false; nodes2.len()];
+        let mut nodes = Vec::new();
+        let mut cost = 0.0;
+        for (i, &image) in mapping.iter().enumerate() {
+            let id = Some(nodes1[i].id.clone());
+            match image {
+                Some(j) => {
+                    used[j] = true;
+                    cost += self.node_cost(i, j);
+                    nodes.push((id, Some(nodes2[j].id.clone())));
+                }
+                None => {
+                    cost += self.costs.node;
+                    nodes.push((id, None));
+                }
+            }
+        }
+        for j in (0..nodes2.len()).filter(|&j| !used[j]) {
+            cost += self.costs.node;
+            nodes.push((None, Some(nodes2[j].id.clone())));
+        }
+
+        // Match the edges between each pair of nodes of the first graph with
+        // those between their images, if any.
+        let (edges1, edges2) = (&self.first.graph.edges, &self.second.graph.edges);
+        let preimage: HashMap<_, _> = mapping
+            .iter()
+            .enumerate()
+            .filter_map(|(i, image)| Some(((*image)?, i)))
+            .collect();
+        let mut matched_first = vec![false; edges1.len()];
+        let mut matched_second = vec![false; edges2.len()];
+        let mut edges = Vec::new();
+        let mut keys: Vec<_> = self.first.edges.keys().copied().collect();
+        keys.sort();
+        for (s, t) in keys {
+            let second: &[usize] = match (mapping[s], mapping[t]) {
+                (Some(s), Some(t)) => self.second.edges_between(s, t),
+                _ => &[],
+            };
+            let (pair_cost, pairs) = self.match_edges(self.first.edges_between(s, t), second);
+            cost += pair_cost;
+            for (e, f) in pairs {
+                matched_first[e] = true;
+                matched_second[f] = true;
+                edges.push((Some(edges1[e].id.clone()), Some(edges2[f].id.clone())));
+            }
+        }
+        for (e, edge) in edges1.iter().enumerate() {
+            if !matched_first[e] {
+                edges.push((Some(edge.id.clone()), None));
+            }
+        }
+        for (f, edge) in edges2.iter().enumerate() {
+            if !matched_second[f] {
+                edges.push((None, Some(edge.id.clone())));
+            }
+        }
+        // Edges of the second graph not between images of the first graph
+        // were not counted above.
+        let n_inserted = self
+            .second
+            .edges
+            .iter()
+            .filter(|((s, t), _)| {
+                let (Some(s), Some(t)) = (preimage.get(s), preimage.get(t)) else {
+                    return true;
+                };
+                !self.first.edges.contains_key(&(*s, *t))
+            })
+            .map(|(_, edges)| edges.len())
+            .sum::<usize>();
+        cost += n_inserted as f32 * self.costs.edge;
+
+        GraphAlignment { cost, nodes, edges, exact }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::graph_io::{LabeledEdge, LabeledNode};
+    use super::*;
+
+    fn graph(nodes: &[(&str, &str)], edges: &[(&str, &str, &str)]) -> LabeledGraph {
+        LabeledGraph {
+            nodes: nodes
+                .iter()
+                .map(|(id, label)| LabeledNode {
+                    id: id.to_string(),
+                    label: Some(label.to_string()),
+                    node_type: Some("Object".into()),
+                })
+                .collect(),
+            edges: edges
+                .iter()
+                .map(|(id, source, target)| LabeledEdge {
+                    id: id.to_string(),
+                    source: source.to_string(),
+                    target: target.to_string(),
+                    label: Some(id.to_string()),
+                    edge_type: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn compare_sir_models() {
+        let sir = graph(
+            &[("s", "S"), ("i", "I"), ("r", "R")],
+            &[("infect", "s", "i"), ("recover", "i", "r")],
+        );
+        let costs = EditCosts::default();
+        let alignment = sir.edit_distance(&sir, &costs, 1000);
+        assert_eq!(alignment.cost, 0.0);
+        assert!(alignment.exact);
+
+        // A student's SIRS model, with different IDs and a relabeled node.
+        let sirs = graph(
+            &[("1", "S"), ("2", "Infected"), ("3", "R")],
+            &[("infect", "1", "2"), ("recover", "2", "3"), ("wane", "3", "1")],
+        );
+        let alignment = sir.edit_distance(&sirs, &costs, 1000);
+        assert!(alignment.exact);
+        assert_eq!(alignment.cost, 1.5);
+        assert!(alignment.nodes.contains(&(Some("i".into()), Some("2".into()))));
+        assert!(alignment.edges.contains(&(Some("recover".into()), Some("recover".into()))));
+        assert!(alignment.edges.contains(&(None, Some("wane".into()))));
+
+        // Greedy fallback gives an upper bound.
+        let greedy = sir.edit_distance(&sirs, &costs, 0);
+        assert!(!greedy.exact);
+        assert!(greedy.cost >= alignment.cost);
+    }
+
+    #[test]
+    fn insertions_and_deletions() {
+        let empty = LabeledGraph::default();
+        let g = graph(&[("x", "x"), ("y", "y")], &[("f", "x", "y"), ("g", "y", "y")]);
+        let costs = EditCosts::default();
+        assert_eq!(empty.edit_distance(&g, &costs, 100).cost, 4.0);
+        let alignment = g.edit_distance(&empty, &costs, 100);
+        assert_eq!(alignment.cost, 4.0);
+        assert_eq!(alignment.nodes, vec![(Some("x".into()), None), (Some("y".into()), None)]);
+    }
+}
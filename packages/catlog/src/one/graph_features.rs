@@ -0,0 +1,270 @@
+//! Feature vectors of labeled graphs.
+//!
+//! A feature vector summarizes a graph, such as the underlying graph of a model,
+//! by a fixed-length vector of numbers, so that many graphs can be compared
+//! cheaply, say for search by similarity or [clustering](super::graph_clustering).
+//! Unlike the [edit distance](super::graph_edit), comparing feature vectors does
+//! not require aligning the graphs.
+//!
+//! The features are deterministic and do not depend on the IDs or labels of
+//! nodes and edges, so isomorphic graphs have the same features. They comprise:
+//!
+//! - counts of nodes and edges, in total and of each type;
+//! - counts of small motifs: self-loops, reciprocal pairs, paths of length two,
+//!   feed-forward loops, and directed triangles;
+//! - the largest eigenvalues of the adjacency matrix of the underlying simple
+//!   undirected graph.
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::graph_clustering::DistanceMatrix;
+use super::graph_io::LabeledGraph;
+
+/// Names of the motif features, in order.
+const MOTIFS: [&str; 5] = [
+    "self_loops",
+    "reciprocal_pairs",
+    "two_paths",
+    "feed_forward_loops",
+    "three_cycles",
+];
+
+/// Specification of the features extracted from graphs.
+///
+/// The types are fixed in advance so that all graphs, whichever types they
+/// use, have feature vectors of the same length.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeatureSpec {
+    /// Node types to count.
+    pub node_types: Vec<String>,
+
+    /// Edge types to count.
+    pub edge_types: Vec<String>,
+
+    /// Number of eigenvalues of the adjacency matrix to include.
+    pub n_eigenvalues: usize,
+}
+
+impl FeatureSpec {
+    /// Specifies features counting all the types used in a collection of graphs.
+    pub fn from_graphs<'a>(
+        graphs: impl IntoIterator<Item = &'a LabeledGraph>,
+        n_eigenvalues: usize,
+    ) -> Self {
+        let mut node_types = BTreeSet::new();
+        let mut edge_types = BTreeSet::new();
+        for graph in graphs {
+            node_types.extend(graph.nodes.iter().filter_map(|node| node.node_type.clone()));
+            edge_types.extend(graph.edges.iter().filter_map(|edge| edge.edge_type.clone()));
+        }
+        Self {
+            node_types: node_types.into_iter().collect(),
+            edge_types: edge_types.into_iter().collect(),
+            n_eigenvalues,
+        }
+    }
+
+    /// Number of features, the length of the feature vectors.
+    pub fn n_features(&self) -> usize {
+        2 + self.node_types.len() + self.edge_types.len() + MOTIFS.len() + self.n_eigenvalues
+    }
+
+    /// Names of the features, in order.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec!["nodes".to_string(), "edges".to_string()];
+        names.extend(self.node_types.iter().map(|t| format!("node_type:{t}")));
+        names.extend(self.edge_types.iter().map(|t| format!("edge_type:{t}")));
+        names.extend(MOTIFS.iter().map(|m| m.to_string()));
+        names.extend((0..self.n_eigenvalues).map(|i| format!("eigenvalue:{i}")));
+        names
+    }
+}
+
+impl LabeledGraph {
+    /// Computes the feature vector of the graph.
+    ///
+    /// Edges whose source or target is not a node are counted as edges but are
+    /// otherwise ignored. Motifs are counted in the underlying simple directed
+    /// graph, in which parallel edges are collapsed. Eigenvalues are listed in
+    /// decreasing order and padded with zeros if the graph has too few nodes.
+    pub fn features(&self, spec: &FeatureSpec) -> Vec<f32> {
+        let mut features = Vec::with_capacity(spec.n_features());
+        features.push(self.nodes.len() as f32);
+        features.push(self.edges.len() as f32);
+        for ty in &spec.node_types {
+            let count =
+                self.nodes.iter().filter(|node| node.node_type.as_ref() == Some(ty)).count();
+            features.push(count as f32);
+        }
+        for ty in &spec.edge_types {
+            let count =
+                self.edges.iter().filter(|edge| edge.edge_type.as_ref() == Some(ty)).count();
+            features.push(count as f32);
+        }
+
+        let adj = self.adjacency();
+        let n = adj.len();
+        let (mut loops, mut reciprocal, mut two_paths, mut feed_forward, mut cycles) =
+            (0, 0, 0, 0, 0);
+        for u in 0..n {
+            if adj[u][u] {
+                loops += 1;
+            }
+            for v in (0..n).filter(|&v| v != u && adj[u][v]) {
+                if u < v && adj[v][u] {
+                    reciprocal += 1;
+                }
+                for w in (0..n).filter(|&w| w != u && w != v && adj[v][w]) {
+                    two_paths += 1;
+                    if adj[u][w] {
+                        feed_forward += 1;
+                    }
+                    if adj[w][u] {
+                        cycles += 1;
+                    }
+                }
+            }
+        }
+        // Each directed triangle is found once from each of its nodes.
+        let motifs = [loops, reciprocal, two_paths, feed_forward, cycles / 3];
+        features.extend(motifs.map(|m| m as f32));
+
+        let symmetric: Vec<Vec<f64>> = (0..n)
+            .map(|u| {
+                (0..n)
+                    .map(|v| f64::from(u8::from(u != v && (adj[u][v] || adj[v][u]))))
+                    .collect()
+            })
+            .collect();
+        let mut eigenvalues = symmetric_eigenvalues(symmetric);
+        eigenvalues.sort_by(|x, y| y.total_cmp(x));
+        eigenvalues.resize(spec.n_eigenvalues.max(eigenvalues.len()), 0.0);
+        features.extend(eigenvalues[..spec.n_eigenvalues].iter().map(|&x| x as f32));
+        features
+    }
+
+    /// Adjacency matrix of the underlying simple directed graph.
+    fn adjacency(&self) -> Vec<Vec<bool>> {
+        let index: HashMap<_, _> =
+            self.nodes.iter().enumerate().map(|(i, node)| (node.id.as_str(), i)).collect();
+        let n = self.nodes.len();
+        let mut adj = vec![vec![false; n]; n];
+        for edge in &self.edges {
+            if let (Some(&u), Some(&v)) =
+                (index.get(edge.source.as_str()), index.get(edge.target.as_str()))
+            {
+                adj[u][v] = true;
+            }
+        }
+        adj
+    }
+}
+
+/// Computes the pairwise Euclidean distances between feature vectors.
+///
+/// The distances can be used to [cluster](super::graph_clustering) graphs in
+/// place of edit distances, which are much more expensive to compute.
+pub fn feature_distance_matrix(features: &[Vec<f32>]) -> DistanceMatrix {
+    let distance = |x: &[f32], y: &[f32]| -> f32 {
+        x.iter().zip(y).map(|(a, b)| (a - b).powi(2)).sum::<f32>().sqrt()
+    };
+    features
+        .iter()
+        .map(|x| features.iter().map(|y| distance(x, y)).collect())
+        .collect()
+}
+
+/// Computes the eigenvalues of a symmetric matrix by the cyclic Jacobi method.
+fn symmetric_eigenvalues(mut a: Vec<Vec<f64>>) -> Vec<f64> {
+    let n = a.len();
+    for _ in 0..100 {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|p| ((p + 1)..n).map(move |q| (p, q)))
+            .map(|(p, q)| a[p][q].powi(2))
+            .sum();
+        if off_diagonal < 1e-18 {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                // Rotate in the (p, q) plane to eliminate the entry at (p, q).
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p][k], a[q][k]);
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+            }
+        }
+    }
+    (0..n).map(|i| a[i][i]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::graph_io::{LabeledEdge, LabeledNode};
+    use super::*;
+
+    fn graph(nodes: &[(&str, &str)], edges: &[(&str, &str, &str)]) -> LabeledGraph {
+        LabeledGraph {
+            nodes: nodes
+                .iter()
+                .map(|(id, ty)| LabeledNode {
+                    id: id.to_string(),
+                    label: None,
+                    node_type: Some(ty.to_string()),
+                })
+                .collect(),
+            edges: edges
+                .iter()
+                .enumerate()
+                .map(|(i, (source, target, ty))| LabeledEdge {
+                    id: format!("e{i}"),
+                    source: source.to_string(),
+                    target: target.to_string(),
+                    label: None,
+                    edge_type: Some(ty.to_string()),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn triangle_features() {
+        let nodes = [("x", "Ob"), ("y", "Ob"), ("z", "Ob")];
+        let cycle = graph(&nodes, &[("x", "y", "Pos"), ("y", "z", "Pos"), ("z", "x", "Neg")]);
+        let spec = FeatureSpec::from_graphs([&cycle], 4);
+        assert_eq!(spec.edge_types, vec!["Neg", "Pos"]);
+        assert_eq!(spec.names().len(), spec.n_features());
+
+        let features = cycle.features(&spec);
+        assert_eq!(features.len(), spec.n_features());
+        assert_eq!(features[..10], [3.0, 3.0, 3.0, 1.0, 2.0, 0.0, 0.0, 3.0, 0.0, 1.0]);
+        for (x, y) in features[10..].iter().zip([2.0, -1.0, -1.0, 0.0]) {
+            assert!((x - y).abs() < 1e-5);
+        }
+
+        // Reversing an edge changes only the motifs.
+        let feed_forward =
+            graph(&nodes, &[("x", "y", "Pos"), ("y", "z", "Pos"), ("x", "z", "Neg")]);
+        let other = feed_forward.features(&spec);
+        assert_eq!(other[..5], features[..5]);
+        assert_eq!(other[5..10], [0.0, 0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(other[10..], features[10..]);
+
+        let distances = feature_distance_matrix(&[features, other]);
+        assert_eq!(distances[0][0], 0.0);
+        assert_eq!(distances[0][1], 6f32.sqrt());
+    }
+}
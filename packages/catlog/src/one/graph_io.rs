@@ -0,0 +1,1216 @@
+//! Reading and writing graphs in standard interchange formats.
+//!
+//! The underlying graph of a finitely generated category, or of a model of a
+//! double theory, has a vertex for each object and an edge for each morphism
+//! generator. Writing the graph in a format understood by standard graph tooling
+//! lets users visualize and manipulate models outside of CatColab, and reading
+//! a graph back yields object and morphism generators named by strings.
+//!
+//! # DOT
+//!
+//! Graphs are written in the [DOT](https://graphviz.org/doc/info/lang.html)
+//! language of Graphviz as directed graphs, with each morphism generator named
+//! by the `label` attribute of its edge. When reading DOT, the edges of an
+//! undirected graph are oriented as written. Edges without a label are named
+//! `e0`, `e1`, and so on, skipping names already in use. Attributes other than
+//! labels are ignored, as are ports on nodes. Subgraphs and HTML strings are not
+//! supported.
+//!
+//! # GraphML
+//!
+//! Graphs with labels and types on nodes and edges, represented by
+//! [`LabeledGraph`], are written in [GraphML](http://graphml.graphdrawing.org/)
+//! with the labels and types stored as data under keys named `label` and `type`.
+//! The reader accepts data keyed in this way by other tools, ignoring other
+//! data, including the graphics of yEd. Nested graphs and hyperedges are not
+//! supported.
+//!
+//! # JSON Graph Format
+//!
+//! Labeled graphs are also converted to and from the [JSON Graph
+//! Format](https://jsongraphformat.info/), with the types stored in the metadata
+//! of nodes and edges. The format is represented by plain Rust types, which are
+//! serializable when the `serde` feature is enabled.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::str::Chars;
+
+use nonempty::NonEmpty;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{FgCategory, Path, graph::HashGraph};
+use crate::dbl::discrete::{DiscreteDblModel, DiscreteDblTheory};
+use crate::dbl::model::{MutDblModel, PrintableDblModel};
+use crate::dbl::theory::DblTheory;
+use crate::tt::util::pretty::*;
+use crate::validate::{self, Validate};
+use crate::zero::{Namespace, QualifiedName};
+
+/// Writes the underlying graph of a finitely generated category in DOT.
+///
+/// Nodes and edges are written in sorted order, so the output is deterministic.
+pub fn write_dot<C>(cat: &C) -> String
+where
+    C: FgCategory,
+    C::Ob: Display,
+    C::MorGen: Display,
+{
+    let mut nodes: Vec<_> = cat.objects().map(|x| format!("  {};\n", dot_id(&x))).collect();
+    let mut edges: Vec<_> = cat
+        .mor_generators()
+        .map(|f| {
+            let (dom, cod) = (cat.mor_generator_dom(&f), cat.mor_generator_cod(&f));
+            format!("  {} -> {} [label={}];\n", dot_id(&dom), dot_id(&cod), dot_id(&f))
+        })
+        .collect();
+    nodes.sort();
+    edges.sort();
+    let mut text = "digraph {\n".to_string();
+    text.extend(nodes);
+    text.extend(edges);
+    text.push_str("}\n");
+    text
+}
+
+/// Writes a value as a quoted DOT identifier.
+fn dot_id(x: &impl Display) -> String {
+    format!("\"{}\"", x.to_string().replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A failure to read a graph from DOT.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum InvalidDot {
+    /// A character that cannot begin a token.
+    #[error("Unexpected character `{0}`")]
+    UnexpectedChar(char),
+
+    /// A quoted string or comment is not terminated.
+    #[error("Unterminated string or comment")]
+    Unterminated,
+
+    /// A token other than the one expected.
+    #[error("Expected {expected}, found {found}")]
+    UnexpectedToken {
+        /// Description of the expected token.
+        expected: String,
+        /// The token found, or the end of input.
+        found: String,
+    },
+
+    /// A feature of DOT not supported by the reader.
+    #[error("Unsupported DOT feature: {0}")]
+    Unsupported(String),
+
+    /// Two edges have the same label.
+    #[error("Duplicate edge label `{0}`")]
+    DuplicateEdge(String),
+}
+
+/// Reads a graph from DOT, with vertices and edges named by strings.
+///
+/// Vertices are named by their node IDs and edges by their labels.
+pub fn read_dot(text: &str) -> Result<HashGraph<String, String>, InvalidDot> {
+    let tokens = tokenize_dot(text)?;
+    let mut parser = DotParser { tokens: tokens.into_iter().peekable() };
+    let (vertices, edges) = parser.graph()?;
+
+    let mut graph = HashGraph::default();
+    for v in vertices {
+        graph.add_vertex(v);
+    }
+    let (labels, ends): (Vec<_>, Vec<_>) =
+        edges.into_iter().map(|(label, src, tgt)| (label, (src, tgt))).unzip();
+    for (e, (src, tgt)) in name_edges(labels).zip(ends) {
+        if !graph.add_edge(e.clone(), src, tgt) {
+            return Err(InvalidDot::DuplicateEdge(e));
+        }
+    }
+    Ok(graph)
+}
+
+/// A token of the DOT language.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    /// An identifier, numeral, or quoted string, with quotes removed.
+    Id(String),
+    /// An edge operator, `->` or `--`.
+    EdgeOp,
+    /// A punctuation character.
+    Punct(char),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Id(id) => write!(f, "`{id}`"),
+            Token::EdgeOp => write!(f, "edge operator"),
+            Token::Punct(c) => write!(f, "`{c}`"),
+        }
+    }
+}
+
+fn tokenize_dot(text: &str) -> Result<Vec<Token>, InvalidDot> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            // Lines beginning with `#` are preprocessor output and are ignored.
+            '#' if line_start => skip_line(&mut chars),
+            '/' if chars.peek() == Some(&'/') => skip_line(&mut chars),
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = None;
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == Some('*') => break,
+                        Some(c) => prev = Some(c),
+                        None => return Err(InvalidDot::Unterminated),
+                    }
+                }
+            }
+            '-' if matches!(chars.peek(), Some('>' | '-')) => {
+                chars.next();
+                tokens.push(Token::EdgeOp);
+            }
+            '"' => {
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => id.push(c),
+                            Some('\n') => {}
+                            Some(c) => id.extend(['\\', c]),
+                            None => return Err(InvalidDot::Unterminated),
+                        },
+                        Some('"') => break,
+                        Some(c) => id.push(c),
+                        None => return Err(InvalidDot::Unterminated),
+                    }
+                }
+                tokens.push(Token::Id(id));
+            }
+            '<' => return Err(InvalidDot::Unsupported("HTML strings".into())),
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' | ':' => tokens.push(Token::Punct(c)),
+            c if c.is_alphanumeric() || matches!(c, '_' | '.' | '-') => {
+                let mut id = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || matches!(c, '_' | '.')) {
+                        break;
+                    }
+                    id.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Id(id));
+            }
+            c => return Err(InvalidDot::UnexpectedChar(c)),
+        }
+        line_start = false;
+    }
+    Ok(tokens)
+}
+
+fn skip_line(chars: &mut Peekable<Chars<'_>>) {
+    while chars.next_if(|&c| c != '\n').is_some() {}
+}
+
+/// An edge read from DOT: its label, if any, source, and target.
+type DotEdge = (Option<String>, String, String);
+
+/// Recursive descent parser for the subset of DOT that we support.
+struct DotParser {
+    tokens: Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl DotParser {
+    fn unexpected<T>(&mut self, expected: &str) -> Result<T, InvalidDot> {
+        let found = self.tokens.next().map_or("end of input".into(), |t| t.to_string());
+        Err(InvalidDot::UnexpectedToken { expected: expected.into(), found })
+    }
+
+    fn punct(&mut self, c: char) -> Result<(), InvalidDot> {
+        if self.tokens.next_if_eq(&Token::Punct(c)).is_some() {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{c}`"))
+        }
+    }
+
+    fn id(&mut self) -> Result<String, InvalidDot> {
+        match self.tokens.next_if(|t| matches!(t, Token::Id(_))) {
+            Some(Token::Id(id)) => Ok(id),
+            _ => self.unexpected("identifier"),
+        }
+    }
+
+    /// Consumes an identifier if it is the given keyword, case insensitively.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(|t| matches!(t, Token::Id(id) if id.eq_ignore_ascii_case(keyword)))
+            .is_some()
+    }
+
+    fn graph(&mut self) -> Result<(Vec<String>, Vec<DotEdge>), InvalidDot> {
+        self.keyword("strict");
+        if !(self.keyword("digraph") || self.keyword("graph")) {
+            return self.unexpected("`digraph` or `graph`");
+        }
+        if matches!(self.tokens.peek(), Some(Token::Id(_))) {
+            self.id()?;
+        }
+        self.punct('{')?;
+        let (mut vertices, mut edges) = (Vec::new(), Vec::new());
+        while self.tokens.next_if_eq(&Token::Punct('}')).is_none() {
+            self.statement(&mut vertices, &mut edges)?;
+            self.tokens.next_if_eq(&Token::Punct(';'));
+        }
+        if self.tokens.peek().is_some() {
+            return self.unexpected("end of input");
+        }
+        Ok((vertices, edges))
+    }
+
+    fn statement(
+        &mut self,
+        vertices: &mut Vec<String>,
+        edges: &mut Vec<DotEdge>,
+    ) -> Result<(), InvalidDot> {
+        if self.keyword("subgraph") || self.tokens.peek() == Some(&Token::Punct('{')) {
+            return Err(InvalidDot::Unsupported("subgraphs".into()));
+        }
+        if self.keyword("graph") || self.keyword("node") || self.keyword("edge") {
+            self.attributes()?;
+            return Ok(());
+        }
+        let first = self.node_id()?;
+        if self.tokens.next_if_eq(&Token::Punct('=')).is_some() {
+            // A graph attribute.
+            self.id()?;
+            return Ok(());
+        }
+        let mut chain = vec![first];
+        while self.tokens.next_if_eq(&Token::EdgeOp).is_some() {
+            if self.tokens.peek() == Some(&Token::Punct('{')) {
+                return Err(InvalidDot::Unsupported("subgraphs".into()));
+            }
+            chain.push(self.node_id()?);
+        }
+        let label = self.attributes()?;
+        for v in &chain {
+            if !vertices.contains(v) {
+                vertices.push(v.clone());
+            }
+        }
+        if chain.len() == 1 {
+            return Ok(());
+        }
+        for pair in chain.windows(2) {
+            edges.push((label.clone(), pair[0].clone(), pair[1].clone()));
+        }
+        Ok(())
+    }
+
+    /// Parses a node ID, discarding its port if any.
+    fn node_id(&mut self) -> Result<String, InvalidDot> {
+        let id = self.id()?;
+        while self.tokens.next_if_eq(&Token::Punct(':')).is_some() {
+            self.id()?;
+        }
+        Ok(id)
+    }
+
+    /// Parses optional lists of attributes, returning the label if any.
+    fn attributes(&mut self) -> Result<Option<String>, InvalidDot> {
+        let mut label = None;
+        while self.tokens.next_if_eq(&Token::Punct('[')).is_some() {
+            while self.tokens.next_if_eq(&Token::Punct(']')).is_none() {
+                let key = self.id()?;
+                self.punct('=')?;
+                let value = self.id()?;
+                if key == "label" {
+                    label = Some(value);
+                }
+                let _ = self.tokens.next_if(|t| matches!(t, Token::Punct(';' | ',')));
+            }
+        }
+        Ok(label)
+    }
+}
+
+/// Names the edges without a name `e0`, `e1`, and so on, skipping names in use.
+fn name_edges(names: Vec<Option<String>>) -> impl Iterator<Item = String> {
+    let used: HashSet<_> = names.iter().flatten().cloned().collect();
+    let mut fresh = (0..).map(|i| format!("e{i}")).filter(move |e| !used.contains(e));
+    names.into_iter().map(move |name| name.unwrap_or_else(|| fresh.next().unwrap()))
+}
+
+/// A graph whose nodes and edges have optional labels and types.
+///
+/// This is the common representation of graphs read from and written to GraphML
+/// and the JSON Graph Format. Nodes and edges are identified by strings.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LabeledGraph {
+    /// Nodes of the graph.
+    pub nodes: Vec<LabeledNode>,
+
+    /// Edges of the graph.
+    pub edges: Vec<LabeledEdge>,
+}
+
+/// A node in a [labeled graph](LabeledGraph).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabeledNode {
+    /// ID of the node.
+    pub id: String,
+
+    /// Human-readable label of the node, if any.
+    pub label: Option<String>,
+
+    /// Type of the node, if any.
+    pub node_type: Option<String>,
+}
+
+/// An edge in a [labeled graph](LabeledGraph).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabeledEdge {
+    /// ID of the edge.
+    pub id: String,
+
+    /// ID of the source node.
+    pub source: String,
+
+    /// ID of the target node.
+    pub target: String,
+
+    /// Human-readable label of the edge, if any.
+    pub label: Option<String>,
+
+    /// Type of the edge, if any.
+    pub edge_type: Option<String>,
+}
+
+/// A failure of a labeled graph to be well defined.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum InvalidLabeledGraph {
+    /// Two nodes have the same ID.
+    #[error("Duplicate node ID `{0}`")]
+    DuplicateNode(String),
+
+    /// Two edges have the same ID.
+    #[error("Duplicate edge ID `{0}`")]
+    DuplicateEdge(String),
+
+    /// An edge has a source or target that is not a node.
+    #[error("Edge `{edge}` refers to unknown node `{node}`")]
+    UnknownNode {
+        /// ID of the edge.
+        edge: String,
+        /// ID of the unknown node.
+        node: String,
+    },
+}
+
+impl LabeledGraph {
+    /// Gets the underlying graph of a finitely generated category.
+    ///
+    /// Nodes and edges are identified by objects and morphism generators, and
+    /// have neither labels nor types.
+    pub fn from_category<C>(cat: &C) -> Self
+    where
+        C: FgCategory,
+        C::Ob: Display,
+        C::MorGen: Display,
+    {
+        let mut nodes: Vec<_> = cat
+            .objects()
+            .map(|x| LabeledNode {
+                id: x.to_string(),
+                label: None,
+                node_type: None,
+            })
+            .collect();
+        let mut edges: Vec<_> = cat
+            .mor_generators()
+            .map(|f| LabeledEdge {
+                id: f.to_string(),
+                source: cat.mor_generator_dom(&f).to_string(),
+                target: cat.mor_generator_cod(&f).to_string(),
+                label: None,
+                edge_type: None,
+            })
+            .collect();
+        nodes.sort_by(|x, y| x.id.cmp(&y.id));
+        edges.sort_by(|f, g| f.id.cmp(&g.id));
+        Self { nodes, edges }
+    }
+
+    /// Gets the underlying graph of a model of a double theory.
+    ///
+    /// Nodes and edges are identified by the serialized names of the object and
+    /// morphism generators, labeled using the given namespaces, and typed by the
+    /// pretty-printed object and morphism types of the generators.
+    pub fn from_model<M>(model: &M, ob_ns: &Namespace, mor_ns: &Namespace) -> Self
+    where
+        M: PrintableDblModel<Ob = QualifiedName>,
+    {
+        let mut nodes: Vec<_> = model
+            .ob_generators()
+            .map(|x| LabeledNode {
+                id: x.serialize_string(),
+                label: Some(ob_ns.label_string(&x)),
+                node_type: Some(
+                    M::ob_type_to_doc(&model.ob_generator_type(&x)).pretty().to_string(),
+                ),
+            })
+            .collect();
+        let mut edges: Vec<_> = model
+            .mor_generators()
+            .map(|f| LabeledEdge {
+                id: f.serialize_string(),
+                source: model.mor_generator_dom(&f).serialize_string(),
+                target: model.mor_generator_cod(&f).serialize_string(),
+                label: Some(mor_ns.label_string(&f)),
+                edge_type: Some(
+                    M::mor_type_to_doc(&model.mor_generator_type(&f)).pretty().to_string(),
+                ),
+            })
+            .collect();
+        nodes.sort_by(|x, y| x.id.cmp(&y.id));
+        edges.sort_by(|f, g| f.id.cmp(&g.id));
+        Self { nodes, edges }
+    }
+
+    /// Converts into a model of a discrete double theory.
+    ///
+    /// Nodes become object generators and edges become morphism generators,
+    /// named by their labels when these are nonempty and unique, and otherwise
+    /// by their IDs. Nodes and edges have their own types when the theory has
+    /// them. Otherwise, nodes have the given object type and edges have the hom
+    /// type on the type of their source. Edges to or from unknown nodes are
+    /// skipped.
+    pub fn to_discrete_model(
+        &self,
+        th: Rc<DiscreteDblTheory>,
+        ob_type: QualifiedName,
+    ) -> DiscreteDblModel {
+        let ob_names = generator_names(self.nodes.iter().map(|x| (&x.id, &x.label)));
+        let mor_names = generator_names(self.edges.iter().map(|f| (&f.id, &f.label)));
+
+        let mut model = DiscreteDblModel::new(th.clone());
+        let mut ob_types = HashMap::new();
+        for node in &self.nodes {
+            let x_type = node
+                .node_type
+                .as_deref()
+                .map(QualifiedName::from)
+                .filter(|x_type| th.has_ob_type(x_type))
+                .unwrap_or_else(|| ob_type.clone());
+            model.add_ob(ob_names[node.id.as_str()].clone(), x_type.clone());
+            ob_types.insert(node.id.as_str(), x_type);
+        }
+        for edge in &self.edges {
+            let (Some(source_type), Some(dom), Some(cod)) = (
+                ob_types.get(edge.source.as_str()),
+                ob_names.get(edge.source.as_str()),
+                ob_names.get(edge.target.as_str()),
+            ) else {
+                continue;
+            };
+            let f_type = edge
+                .edge_type
+                .as_deref()
+                .map(|f_type| Path::single(QualifiedName::from(f_type)))
+                .filter(|f_type| th.has_mor_type(f_type))
+                .unwrap_or_else(|| Path::Id(source_type.clone()));
+            model.add_mor(mor_names[edge.id.as_str()].clone(), dom.clone(), cod.clone(), f_type);
+        }
+        model
+    }
+
+    /// Converts into a graph with vertices and edges named by their IDs,
+    /// discarding labels and types.
+    ///
+    /// The labeled graph should be [valid](Validate) for the result to be a
+    /// graph.
+    pub fn into_graph(self) -> HashGraph<String, String> {
+        let mut graph = HashGraph::default();
+        for node in self.nodes {
+            graph.add_vertex(node.id);
+        }
+        for edge in self.edges {
+            graph.add_edge(edge.id, edge.source, edge.target);
+        }
+        graph
+    }
+
+    /// Iterates over failures of the labeled graph to be well defined.
+    pub fn iter_invalid(&self) -> impl Iterator<Item = InvalidLabeledGraph> {
+        let mut errors = Vec::new();
+        let mut node_ids = HashSet::new();
+        for node in &self.nodes {
+            if !node_ids.insert(node.id.as_str()) {
+                errors.push(InvalidLabeledGraph::DuplicateNode(node.id.clone()));
+            }
+        }
+        let mut edge_ids = HashSet::new();
+        for edge in &self.edges {
+            if !edge_ids.insert(edge.id.as_str()) {
+                errors.push(InvalidLabeledGraph::DuplicateEdge(edge.id.clone()));
+            }
+            for node in [&edge.source, &edge.target] {
+                if !node_ids.contains(node.as_str()) {
+                    errors.push(InvalidLabeledGraph::UnknownNode {
+                        edge: edge.id.clone(),
+                        node: node.clone(),
+                    });
+                }
+            }
+        }
+        errors.into_iter()
+    }
+}
+
+impl Validate for LabeledGraph {
+    type ValidationError = InvalidLabeledGraph;
+
+    fn validate(&self) -> Result<(), NonEmpty<Self::ValidationError>> {
+        validate::wrap_errors(self.iter_invalid())
+    }
+}
+
+/// Names generators by their labels, falling back to IDs.
+///
+/// A label is used only when it is nonempty, shared by no other generator, and
+/// not the ID of another generator.
+fn generator_names<'a>(
+    elements: impl Iterator<Item = (&'a String, &'a Option<String>)> + Clone,
+) -> HashMap<&'a str, QualifiedName> {
+    let ids: HashSet<_> = elements.clone().map(|(id, _)| id.as_str()).collect();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for label in elements.clone().filter_map(|(_, label)| label.as_deref()) {
+        *counts.entry(label).or_default() += 1;
+    }
+    elements
+        .map(|(id, label)| {
+            let name = match label.as_deref() {
+                Some(label)
+                    if !label.is_empty()
+                        && counts[label] == 1
+                        && (label == id || !ids.contains(label)) =>
+                {
+                    label
+                }
+                _ => id.as_str(),
+            };
+            (id.as_str(), QualifiedName::from(name))
+        })
+        .collect()
+}
+
+/// Preamble of a GraphML document, declaring the keys for labels and types.
+const GRAPHML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="node_label" for="node" attr.name="label" attr.type="string"/>
+  <key id="node_type" for="node" attr.name="type" attr.type="string"/>
+  <key id="edge_label" for="edge" attr.name="label" attr.type="string"/>
+  <key id="edge_type" for="edge" attr.name="type" attr.type="string"/>
+  <graph id="G" edgedefault="directed">
+"#;
+
+/// A failure to read a graph from GraphML.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum InvalidGraphMl {
+    /// The document is not well-formed XML.
+    #[error("Malformed XML: {0}")]
+    Xml(String),
+
+    /// An element is missing a required attribute.
+    #[error("Element `{element}` is missing attribute `{attribute}`")]
+    MissingAttribute {
+        /// Name of the element.
+        element: String,
+        /// Name of the missing attribute.
+        attribute: String,
+    },
+
+    /// A feature of GraphML not supported by the reader.
+    #[error("Unsupported GraphML feature: {0}")]
+    Unsupported(String),
+}
+
+impl LabeledGraph {
+    /// Writes the graph in GraphML.
+    pub fn to_graphml(&self) -> String {
+        let mut xml = GRAPHML_HEADER.to_string();
+        for node in &self.nodes {
+            let data = graphml_data("node_label", &node.label)
+                + &graphml_data("node_type", &node.node_type);
+            xml.push_str(&graphml_element("node", &[("id", &node.id)], &data));
+        }
+        for edge in &self.edges {
+            let attrs = [("id", &edge.id), ("source", &edge.source), ("target", &edge.target)];
+            let data = graphml_data("edge_label", &edge.label)
+                + &graphml_data("edge_type", &edge.edge_type);
+            xml.push_str(&graphml_element("edge", &attrs, &data));
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    /// Reads a graph from GraphML.
+    ///
+    /// Labels and types are read from the data whose keys have the attribute
+    /// names `label` and `type`. Edges without an ID are named `e0`, `e1`, and so
+    /// on, skipping IDs already in use.
+    pub fn from_graphml(text: &str) -> Result<Self, InvalidGraphMl> {
+        let mut keys = HashMap::new();
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut edge_ids = Vec::new();
+        let mut graphs = 0;
+        let mut stack: Vec<String> = Vec::new();
+        let mut data: Option<(String, String)> = None;
+
+        for event in parse_xml(text)? {
+            match event {
+                XmlEvent::Start { name, attrs, empty } => {
+                    let attr = |attribute: &str| {
+                        attrs.get(attribute).cloned().ok_or_else(|| {
+                            InvalidGraphMl::MissingAttribute {
+                                element: name.clone(),
+                                attribute: attribute.into(),
+                            }
+                        })
+                    };
+                    match name.as_str() {
+                        "key" => {
+                            let attr_name = attrs.get("attr.name").cloned().unwrap_or_default();
+                            keys.insert(attr("id")?, attr_name);
+                        }
+                        "graph" => {
+                            graphs += 1;
+                            if graphs > 1 {
+                                return Err(InvalidGraphMl::Unsupported(
+                                    "multiple or nested graphs".into(),
+                                ));
+                            }
+                        }
+                        "node" => nodes.push(LabeledNode {
+                            id: attr("id")?,
+                            label: None,
+                            node_type: None,
+                        }),
+                        "edge" => {
+                            edge_ids.push(attrs.get("id").cloned());
+                            edges.push(LabeledEdge {
+                                id: String::new(),
+                                source: attr("source")?,
+                                target: attr("target")?,
+                                label: None,
+                                edge_type: None,
+                            });
+                        }
+                        "hyperedge" => {
+                            return Err(InvalidGraphMl::Unsupported("hyperedges".into()));
+                        }
+                        "data" => data = Some((attr("key")?, String::new())),
+                        _ => {}
+                    }
+                    if !empty {
+                        stack.push(name);
+                    }
+                }
+                XmlEvent::End(name) => {
+                    if stack.pop().as_ref() != Some(&name) {
+                        return Err(InvalidGraphMl::Xml(format!("Unexpected `</{name}>`")));
+                    }
+                    if name != "data" {
+                        continue;
+                    }
+                    let Some((key, value)) = data.take() else {
+                        continue;
+                    };
+                    let (label, ty) = match stack.last().map(String::as_str) {
+                        Some("node") => {
+                            let node = nodes.last_mut().unwrap();
+                            (&mut node.label, &mut node.node_type)
+                        }
+                        Some("edge") => {
+                            let edge = edges.last_mut().unwrap();
+                            (&mut edge.label, &mut edge.edge_type)
+                        }
+                        _ => continue,
+                    };
+                    match keys.get(&key).map(String::as_str) {
+                        Some("label") => *label = Some(value),
+                        Some("type") => *ty = Some(value),
+                        _ => {}
+                    }
+                }
+                XmlEvent::Text(text) => {
+                    if let (Some((_, value)), Some("data")) =
+                        (&mut data, stack.last().map(String::as_str))
+                    {
+                        value.push_str(&text);
+                    }
+                }
+            }
+        }
+        if let Some(name) = stack.pop() {
+            return Err(InvalidGraphMl::Xml(format!("Unclosed element `{name}`")));
+        }
+
+        for (edge, id) in edges.iter_mut().zip(name_edges(edge_ids)) {
+            edge.id = id;
+        }
+        Ok(Self { nodes, edges })
+    }
+}
+
+fn graphml_data(key: &str, value: &Option<String>) -> String {
+    value.as_ref().map_or(String::new(), |value| {
+        format!("      <data key=\"{key}\">{}</data>\n", xml_escape(value))
+    })
+}
+
+fn graphml_element(name: &str, attrs: &[(&str, &String)], data: &str) -> String {
+    let attrs: String = attrs
+        .iter()
+        .map(|(key, value)| format!(" {key}=\"{}\"", xml_escape(value)))
+        .collect();
+    if data.is_empty() {
+        format!("    <{name}{attrs}/>\n")
+    } else {
+        format!("    <{name}{attrs}>\n{data}    </{name}>\n")
+    }
+}
+
+/// Escapes text for use in XML, in both content and attribute values.
+pub(crate) fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn xml_unescape(text: &str) -> Result<String, InvalidGraphMl> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        unescaped.push_str(&rest[..i]);
+        let end = rest[i..]
+            .find(';')
+            .ok_or_else(|| InvalidGraphMl::Xml("Unterminated entity".into()))?;
+        let entity = &rest[i + 1..i + end];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        unescaped
+            .push(c.ok_or_else(|| InvalidGraphMl::Xml(format!("Unknown entity `&{entity};`")))?);
+        rest = &rest[i + end + 1..];
+    }
+    unescaped.push_str(rest);
+    Ok(unescaped)
+}
+
+/// An event in a minimal, non-validating reader of XML.
+enum XmlEvent {
+    /// The start of an element, which is empty if self-closing.
+    Start {
+        name: String,
+        attrs: HashMap<String, String>,
+        empty: bool,
+    },
+    /// The end of an element.
+    End(String),
+    /// Character data, with entities replaced.
+    Text(String),
+}
+
+/// Reads an XML document as a sequence of events.
+///
+/// Comments, processing instructions, and document type declarations are
+/// skipped. Namespaces are not resolved, so prefixed names are kept as is.
+fn parse_xml(text: &str) -> Result<Vec<XmlEvent>, InvalidGraphMl> {
+    let until = |rest: &str, delim: &str| {
+        rest.find(delim)
+            .ok_or_else(|| InvalidGraphMl::Xml(format!("Expected `{delim}`")))
+    };
+    let mut events = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = &after[until(after, "-->")? + 3..];
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = until(after, "]]>")?;
+            events.push(XmlEvent::Text(after[..end].to_string()));
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<?") {
+            rest = &after[until(after, "?>")? + 2..];
+        } else if let Some(after) = rest.strip_prefix("<!") {
+            rest = &after[until(after, ">")? + 1..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = until(after, ">")?;
+            events.push(XmlEvent::End(after[..end].trim().to_string()));
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let end = xml_tag_end(after)?;
+            let (tag, empty) = match after[..end].strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (&after[..end], false),
+            };
+            let (name, attrs) = parse_xml_tag(tag)?;
+            events.push(XmlEvent::Start { name, attrs, empty });
+            rest = &after[end + 1..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            events.push(XmlEvent::Text(xml_unescape(&rest[..end])?));
+            rest = &rest[end..];
+        }
+    }
+    Ok(events)
+}
+
+/// Finds the end of a start tag, skipping over quoted attribute values.
+fn xml_tag_end(text: &str) -> Result<usize, InvalidGraphMl> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '>') => return Ok(i),
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            _ => {}
+        }
+    }
+    Err(InvalidGraphMl::Xml("Unterminated tag".into()))
+}
+
+/// Parses the name and attributes of a start tag.
+fn parse_xml_tag(tag: &str) -> Result<(String, HashMap<String, String>), InvalidGraphMl> {
+    let malformed = || InvalidGraphMl::Xml(format!("Malformed tag `<{tag}>`"));
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let (name, mut rest) = tag.split_at(name_end);
+    if name.is_empty() {
+        return Err(malformed());
+    }
+    let mut attrs = HashMap::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let (key, after) = rest.split_once('=').ok_or_else(malformed)?;
+        let after = after.trim_start();
+        let quote =
+            after.chars().next().filter(|c| matches!(c, '"' | '\'')).ok_or_else(malformed)?;
+        let end = after[1..].find(quote).ok_or_else(malformed)?;
+        attrs.insert(key.trim().to_string(), xml_unescape(&after[1..end + 1])?);
+        rest = &after[end + 2..];
+    }
+    Ok((name.to_string(), attrs))
+}
+
+/// A document in the [JSON Graph Format](https://jsongraphformat.info/),
+/// version 2, containing a single graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JsonGraphDocument {
+    /// The graph.
+    pub graph: JsonGraph,
+}
+
+/// A graph in the JSON Graph Format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JsonGraph {
+    /// Whether the graph is directed, as it is if not specified.
+    #[cfg_attr(feature = "serde", serde(default = "directed_default"))]
+    pub directed: bool,
+
+    /// Nodes of the graph, keyed by ID.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub nodes: BTreeMap<String, JsonGraphNode>,
+
+    /// Edges of the graph.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub edges: Vec<JsonGraphEdge>,
+}
+
+/// A node in the JSON Graph Format.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JsonGraphNode {
+    /// Label of the node.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub label: Option<String>,
+
+    /// Metadata of the node, with its type under the key `type`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// An edge in the JSON Graph Format.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JsonGraphEdge {
+    /// ID of the edge, optional in the format.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub id: Option<String>,
+
+    /// ID of the source node.
+    pub source: String,
+
+    /// ID of the target node.
+    pub target: String,
+
+    /// Label of the edge.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub label: Option<String>,
+
+    /// Metadata of the edge, with its type under the key `type`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[cfg(feature = "serde")]
+fn directed_default() -> bool {
+    true
+}
+
+/// Metadata with the given type, if any.
+fn type_metadata(ty: &Option<String>) -> BTreeMap<String, String> {
+    ty.iter().map(|ty| ("type".to_string(), ty.clone())).collect()
+}
+
+impl LabeledGraph {
+    /// Converts the graph into the JSON Graph Format.
+    ///
+    /// Types are stored in the metadata of nodes and edges under the key `type`.
+    pub fn to_json_graph(&self) -> JsonGraphDocument {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let metadata = type_metadata(&node.node_type);
+                (node.id.clone(), JsonGraphNode { label: node.label.clone(), metadata })
+            })
+            .collect();
+        let edges = self
+            .edges
+            .iter()
+            .map(|edge| JsonGraphEdge {
+                id: Some(edge.id.clone()),
+                source: edge.source.clone(),
+                target: edge.target.clone(),
+                label: edge.label.clone(),
+                metadata: type_metadata(&edge.edge_type),
+            })
+            .collect();
+        JsonGraphDocument {
+            graph: JsonGraph { directed: true, nodes, edges },
+        }
+    }
+
+    /// Converts a graph in the JSON Graph Format.
+    ///
+    /// Nodes are sorted by ID. Edges without an ID are named `e0`, `e1`, and so
+    /// on, skipping IDs already in use.
+    pub fn from_json_graph(doc: JsonGraphDocument) -> Self {
+        let JsonGraph { nodes, edges, .. } = doc.graph;
+        let nodes = nodes
+            .into_iter()
+            .map(|(id, mut node)| LabeledNode {
+                id,
+                label: node.label,
+                node_type: node.metadata.remove("type"),
+            })
+            .collect();
+        let (ids, edges): (Vec<_>, Vec<_>) =
+            edges.into_iter().map(|edge| (edge.id.clone(), edge)).unzip();
+        let edges = name_edges(ids)
+            .zip(edges)
+            .map(|(id, mut edge)| LabeledEdge {
+                id,
+                source: edge.source,
+                target: edge.target,
+                label: edge.label,
+                edge_type: edge.metadata.remove("type"),
+            })
+            .collect();
+        Self { nodes, edges }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::one::{FinGraph, Graph, fp_category::sch_graph};
+    use crate::stdlib::{models::walking_attr, theories::th_schema};
+
+    #[test]
+    fn write_and_read_dot() {
+        let dot = write_dot(&sch_graph());
+        assert_eq!(
+            dot,
+            "digraph {\n  \"E\";\n  \"V\";\n  \"E\" -> \"V\" [label=\"src\"];\n  \
+             \"E\" -> \"V\" [label=\"tgt\"];\n}\n"
+        );
+        let graph = read_dot(&dot).unwrap();
+        assert_eq!(graph.vertices().count(), 2);
+        assert_eq!(graph.src(&"tgt".to_string()), "E");
+        assert_eq!(graph.tgt(&"src".to_string()), "V");
+
+        let model = walking_attr(Rc::new(th_schema()));
+        let graph = read_dot(&write_dot(&model)).unwrap();
+        assert_eq!(graph.vertices().count(), model.objects().count());
+        assert_eq!(graph.edges().count(), model.mor_generators().count());
+    }
+
+    #[test]
+    fn read_dot_features() {
+        let text = r#"
+            // A comment.
+            strict graph G {
+                rankdir = LR; node [shape=box]
+                a -- b -- c [color=red]
+                b:n -- "quoted \"name\"" [label="f", weight=2];
+                /* Another comment. */
+                e0 [label="ignored"]
+            }
+        "#;
+        let graph = read_dot(text).unwrap();
+        assert_eq!(graph.vertices().count(), 5);
+        assert_eq!(graph.edges().count(), 3);
+        assert_eq!(graph.tgt(&"f".to_string()), "quoted \"name\"");
+        assert_eq!(graph.src(&"e1".to_string()), "b");
+
+        assert_eq!(
+            read_dot("digraph { a -> b [label=f]; c -> d [label=f] }"),
+            Err(InvalidDot::DuplicateEdge("f".into()))
+        );
+        assert_eq!(
+            read_dot("digraph { subgraph { a } }"),
+            Err(InvalidDot::Unsupported("subgraphs".into()))
+        );
+        assert!(matches!(read_dot("digraph { a -> }"), Err(InvalidDot::UnexpectedToken { .. })));
+        assert_eq!(read_dot("digraph { \"a }"), Err(InvalidDot::Unterminated));
+    }
+
+    #[test]
+    fn graphml_and_json_graph() {
+        let graph = LabeledGraph::from_category(&sch_graph());
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges[0].source, "E");
+        assert_eq!(LabeledGraph::from_graphml(&graph.to_graphml()), Ok(graph.clone()));
+        assert_eq!(LabeledGraph::from_json_graph(graph.to_json_graph()), graph);
+
+        let ns = Namespace::new_for_text();
+        let graph = LabeledGraph::from_model(&walking_attr(Rc::new(th_schema())), &ns, &ns);
+        assert!(graph.validate().is_ok());
+        assert_eq!(graph.nodes[0].label.as_deref(), Some("entity"));
+        assert!(graph.edges.iter().all(|edge| edge.edge_type.is_some()));
+        assert_eq!(LabeledGraph::from_graphml(&graph.to_graphml()), Ok(graph.clone()));
+        assert_eq!(LabeledGraph::from_json_graph(graph.to_json_graph()), graph);
+        assert_eq!(graph.into_graph().edges().count(), 1);
+    }
+
+    #[test]
+    fn read_graphml_features() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <graphml xmlns="http://graphml.graphdrawing.org/xmlns"
+                     xmlns:y="http://www.yworks.com/xml/graphml">
+              <!-- A comment. -->
+              <key id="d0" for="node" attr.name="label" attr.type="string"/>
+              <key id="d1" for="node" yfiles.type="nodegraphics"/>
+              <graph edgedefault="directed">
+                <node id="n0">
+                  <data key="d0"><![CDATA[x < y]]></data>
+                  <data key="d1"><y:ShapeNode><y:NodeLabel>z</y:NodeLabel></y:ShapeNode></data>
+                </node>
+                <node id='n1'><data key="d0">&#65;&amp;&#x42;</data></node>
+                <edge source="n0" target="n1"/>
+                <edge id="e0" source="n1" target="n0"></edge>
+              </graph>
+            </graphml>"#;
+        let graph = LabeledGraph::from_graphml(text).unwrap();
+        assert_eq!(graph.nodes[0].label.as_deref(), Some("x < y"));
+        assert_eq!(graph.nodes[1].label.as_deref(), Some("A&B"));
+        assert_eq!(graph.edges[0].id, "e1");
+        assert!(graph.validate().is_ok());
+
+        assert!(matches!(
+            LabeledGraph::from_graphml("<graphml><graph><node/></graph></graphml>"),
+            Err(InvalidGraphMl::MissingAttribute { .. })
+        ));
+        assert!(matches!(
+            LabeledGraph::from_graphml("<graphml><graph></graphml>"),
+            Err(InvalidGraphMl::Xml(_))
+        ));
+
+        let mut graph = LabeledGraph::from_category(&sch_graph());
+        graph.edges[1].target = "W".into();
+        assert_eq!(
+            graph.iter_invalid().collect::<Vec<_>>(),
+            vec![InvalidLabeledGraph::UnknownNode { edge: "tgt".into(), node: "W".into() }]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_graph_serde() {
+        let text = r#"{"graph": {"nodes": {"a": {"label": "A"}, "b": {}},
+                       "edges": [{"source": "a", "target": "b",
+                                  "metadata": {"type": "Attr"}}]}}"#;
+        let doc: JsonGraphDocument = serde_json::from_str(text).unwrap();
+        assert!(doc.graph.directed);
+        let graph = LabeledGraph::from_json_graph(doc);
+        assert_eq!(graph.edges[0].id, "e0");
+        assert_eq!(graph.edges[0].edge_type.as_deref(), Some("Attr"));
+    }
+}
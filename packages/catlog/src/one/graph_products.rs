@@ -0,0 +1,172 @@
+//! Products of graphs.
+//!
+//! The vertices of each product of two graphs are the pairs of vertices, and
+//! the products differ in their edges:
+//!
+//! - in the **box product**, an edge moves along an edge in one factor while
+//!   staying at a vertex in the other;
+//! - in the **tensor product**, the categorical product of graphs, an edge
+//!   moves along edges in both factors at once;
+//! - in the **strong product**, an edge is of either kind.
+//!
+//! Products build structured graphs from simple ones, such as grids from paths.
+//! For models, the box product of a model with a graph of strata, like age
+//! groups, duplicates the model in each stratum and the strata transitions for
+//! each object, which is the basic step of stratification.
+
+use std::hash::Hash;
+
+use super::graph::*;
+
+/// An edge in a product of graphs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProductEdge<V1, E1, V2, E2> {
+    /// An edge in the first factor at a vertex in the second.
+    Left(E1, V2),
+
+    /// An edge in the second factor at a vertex in the first.
+    Right(V1, E2),
+
+    /// A pair of edges, one in each factor.
+    Both(E1, E2),
+}
+
+/// A product of two graphs.
+pub type ProductGraph<G1, G2> = HashGraph<
+    (<G1 as Graph>::V, <G2 as Graph>::V),
+    ProductEdge<<G1 as Graph>::V, <G1 as Graph>::E, <G2 as Graph>::V, <G2 as Graph>::E>,
+>;
+
+/// Computes the box product of two finite graphs.
+pub fn box_product<G1, G2>(g: &G1, h: &G2) -> ProductGraph<G1, G2>
+where
+    G1: FinGraph,
+    G2: FinGraph,
+    G1::V: Hash,
+    G1::E: Hash,
+    G2::V: Hash,
+    G2::E: Hash,
+{
+    let mut product = vertex_product(g, h);
+    add_box_edges(&mut product, g, h);
+    product
+}
+
+/// Computes the tensor product of two finite graphs.
+pub fn tensor_product<G1, G2>(g: &G1, h: &G2) -> ProductGraph<G1, G2>
+where
+    G1: FinGraph,
+    G2: FinGraph,
+    G1::V: Hash,
+    G1::E: Hash,
+    G2::V: Hash,
+    G2::E: Hash,
+{
+    let mut product = vertex_product(g, h);
+    add_tensor_edges(&mut product, g, h);
+    product
+}
+
+/// Computes the strong product of two finite graphs.
+///
+/// The strong product is the union of the box and tensor products.
+pub fn strong_product<G1, G2>(g: &G1, h: &G2) -> ProductGraph<G1, G2>
+where
+    G1: FinGraph,
+    G2: FinGraph,
+    G1::V: Hash,
+    G1::E: Hash,
+    G2::V: Hash,
+    G2::E: Hash,
+{
+    let mut product = vertex_product(g, h);
+    add_box_edges(&mut product, g, h);
+    add_tensor_edges(&mut product, g, h);
+    product
+}
+
+/// Product of the vertex sets of two graphs, as a graph without edges.
+fn vertex_product<G1, G2>(g: &G1, h: &G2) -> ProductGraph<G1, G2>
+where
+    G1: FinGraph,
+    G2: FinGraph,
+    G1::V: Hash,
+    G1::E: Hash,
+    G2::V: Hash,
+    G2::E: Hash,
+{
+    let mut product = ProductGraph::<G1, G2>::default();
+    for v in g.vertices() {
+        product.add_vertices(h.vertices().map(|w| (v.clone(), w)));
+    }
+    product
+}
+
+/// Adds the edges of the box product to a product of vertex sets.
+fn add_box_edges<G1, G2>(product: &mut ProductGraph<G1, G2>, g: &G1, h: &G2)
+where
+    G1: FinGraph,
+    G2: FinGraph,
+    G1::V: Hash,
+    G1::E: Hash,
+    G2::V: Hash,
+    G2::E: Hash,
+{
+    for e in g.edges() {
+        for w in h.vertices() {
+            let (src, tgt) = ((g.src(&e), w.clone()), (g.tgt(&e), w.clone()));
+            product.add_edge(ProductEdge::Left(e.clone(), w), src, tgt);
+        }
+    }
+    for v in g.vertices() {
+        for f in h.edges() {
+            let (src, tgt) = ((v.clone(), h.src(&f)), (v.clone(), h.tgt(&f)));
+            product.add_edge(ProductEdge::Right(v.clone(), f), src, tgt);
+        }
+    }
+}
+
+/// Adds the edges of the tensor product to a product of vertex sets.
+fn add_tensor_edges<G1, G2>(product: &mut ProductGraph<G1, G2>, g: &G1, h: &G2)
+where
+    G1: FinGraph,
+    G2: FinGraph,
+    G1::V: Hash,
+    G1::E: Hash,
+    G2::V: Hash,
+    G2::E: Hash,
+{
+    for e in g.edges() {
+        for f in h.edges() {
+            let (src, tgt) = ((g.src(&e), h.src(&f)), (g.tgt(&e), h.tgt(&f)));
+            product.add_edge(ProductEdge::Both(e.clone(), f), src, tgt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::Validate;
+
+    #[test]
+    fn products_of_paths() {
+        let (g, h) = (SkelGraph::path(2), SkelGraph::path(3));
+
+        let grid = box_product(&g, &h);
+        assert!(grid.validate().is_ok());
+        assert_eq!((grid.vertex_count(), grid.edge_count()), (6, 7));
+        assert_eq!(grid.src(&ProductEdge::Right(1, 0)), (1, 0));
+        assert_eq!(grid.tgt(&ProductEdge::Left(0, 2)), (1, 2));
+
+        let diagonals = tensor_product(&g, &h);
+        assert!(diagonals.validate().is_ok());
+        assert_eq!((diagonals.vertex_count(), diagonals.edge_count()), (6, 2));
+        assert_eq!(diagonals.tgt(&ProductEdge::Both(0, 1)), (1, 2));
+
+        let strong = strong_product(&g, &h);
+        assert!(strong.validate().is_ok());
+        assert_eq!(strong.edge_count(), 9);
+        assert_eq!(strong.out_degree(&(0, 0)), 3);
+    }
+}
@@ -0,0 +1,323 @@
+//! Instances of finitely presented categories.
+//!
+//! An *instance* of a category C, also known as a copresheaf on C or a C-set, is
+//! a functor from C to the category of sets. When C is a database schema, an
+//! instance is a database conforming to the schema, with a set of rows for each
+//! table and a function for each foreign key. This module implements finite
+//! instances of [finitely presented categories](super::fp_category), where the
+//! sets are [skeletal](crate::zero::SkelFinSet) and so the functions are vectors
+//! of row numbers, along with natural transformations between them. Presheaves
+//! on C are instances of the opposite category.
+//!
+//! An instance is specified by its values on the generators of the category.
+//! It is a well-defined functor just when the functions have the right domains
+//! and codomains and the path equations of the category hold elementwise.
+
+use std::hash::Hash;
+
+use derivative::Derivative;
+use nonempty::NonEmpty;
+use thiserror::Error;
+
+use super::{FgCategory, FpCategory, Path};
+use crate::validate;
+use crate::zero::{
+    Column, FinSet, Function, HashColumn, InvalidFunction, Mapping, MutMapping, SkelColumn,
+    SkelFinSet,
+};
+
+/// A finite instance of a finitely presented category.
+#[derive(Clone, Debug, Derivative)]
+#[derivative(Default(bound = ""))]
+#[derivative(PartialEq(bound = "V: Eq + Hash, E: Eq + Hash"))]
+#[derivative(Eq(bound = "V: Eq + Hash, E: Eq + Hash"))]
+pub struct Instance<V, E> {
+    sets: HashColumn<V, SkelFinSet>,
+    functions: HashColumn<E, SkelColumn>,
+}
+
+impl<V, E> Instance<V, E>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash,
+{
+    /// Creates an empty instance, assigning nothing to the generators.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the size of the set assigned to an object generator.
+    pub fn set_size(&mut self, x: V, n: usize) {
+        self.sets.set(x, SkelFinSet::from(n));
+    }
+
+    /// Sets the function assigned to a morphism generator.
+    pub fn set_function(&mut self, f: E, values: Vec<usize>) {
+        self.functions.set(f, SkelColumn::new(values));
+    }
+
+    /// Gets the set assigned to an object generator, if any.
+    pub fn ob_set(&self, x: &V) -> Option<SkelFinSet> {
+        self.sets.apply_to_ref(x)
+    }
+
+    /// Gets the function assigned to a morphism generator, if any.
+    pub fn function(&self, f: &E) -> Option<&SkelColumn> {
+        self.functions.get(f)
+    }
+
+    /// Applies the action of a path to an element.
+    ///
+    /// Returns `None` if the action is undefined for any morphism along the
+    /// path. Elements of identity paths are not checked to belong to the set.
+    pub fn apply_path(&self, path: &Path<V, E>, x: usize) -> Option<usize> {
+        path.iter().try_fold(x, |x, f| self.functions.get(f)?.apply(x))
+    }
+
+    /// Validates that the instance is a functor on the given category.
+    pub fn validate_on(
+        &self,
+        cat: &FpCategory<V, E>,
+    ) -> Result<(), NonEmpty<InvalidInstance<V, E>>> {
+        validate::wrap_errors(self.iter_invalid_on(cat))
+    }
+
+    /// Iterates over failures of the instance to be a functor.
+    pub fn iter_invalid_on<'a>(
+        &'a self,
+        cat: &'a FpCategory<V, E>,
+    ) -> impl Iterator<Item = InvalidInstance<V, E>> + 'a {
+        let ob_errors =
+            cat.ob_generators().filter(|x| !self.sets.is_set(x)).map(InvalidInstance::ObGen);
+        let mor_errors = cat.mor_generators().flat_map(|f| {
+            let (Some(function), Some(dom), Some(cod)) = (
+                self.functions.get(&f),
+                self.sets.get(&cat.mor_generator_dom(&f)),
+                self.sets.get(&cat.mor_generator_cod(&f)),
+            ) else {
+                return vec![InvalidInstance::MorGen(f)];
+            };
+            Function(function, dom, cod)
+                .iter_invalid()
+                .map(|err| match err {
+                    InvalidFunction::Dom(x) => InvalidInstance::Dom(f.clone(), x),
+                    InvalidFunction::Cod(x) => InvalidInstance::Cod(f.clone(), x),
+                })
+                .collect()
+        });
+        let eq_errors = cat.equations().enumerate().filter_map(|(id, eq)| {
+            let set = self.sets.get(&eq.lhs.src(cat.generators()))?;
+            set.iter()
+                .find(|x| self.apply_path(&eq.lhs, *x) != self.apply_path(&eq.rhs, *x))
+                .map(|x| InvalidInstance::Eq(id, x))
+        });
+        ob_errors.chain(mor_errors).chain(eq_errors)
+    }
+}
+
+/// A failure of an instance to be a functor.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum InvalidInstance<V, E> {
+    /// An object generator not assigned a set.
+    #[error("Object generator `{0}` is not assigned a set")]
+    ObGen(V),
+
+    /// A morphism generator not assigned a function between assigned sets.
+    #[error("Morphism generator `{0}` is not assigned a function")]
+    MorGen(E),
+
+    /// A morphism generator whose function is undefined at an element.
+    #[error("Function for `{0}` is not defined at element {1}")]
+    Dom(E, usize),
+
+    /// A morphism generator whose function has a value outside the codomain.
+    #[error("Function for `{0}` has a value outside its codomain at element {1}")]
+    Cod(E, usize),
+
+    /// A path equation that fails at an element.
+    #[error("Path equation `{0}` fails at element {1}")]
+    Eq(usize, usize),
+}
+
+/// A natural transformation between finite instances.
+#[derive(Clone, Debug, Derivative)]
+#[derivative(Default(bound = ""))]
+#[derivative(PartialEq(bound = "V: Eq + Hash"))]
+#[derivative(Eq(bound = "V: Eq + Hash"))]
+pub struct InstanceMorphism<V> {
+    components: HashColumn<V, SkelColumn>,
+}
+
+impl<V> InstanceMorphism<V>
+where
+    V: Eq + Clone + Hash,
+{
+    /// Creates a transformation with no components.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Constructs the identity transformation on an instance.
+    pub fn identity<E>(inst: &Instance<V, E>) -> Self {
+        let components =
+            inst.sets.iter().map(|(x, set)| (x, SkelColumn::new(set.iter().collect())));
+        Self { components: components.collect() }
+    }
+
+    /// Sets the component at an object generator.
+    pub fn set_component(&mut self, x: V, values: Vec<usize>) {
+        self.components.set(x, SkelColumn::new(values));
+    }
+
+    /// Gets the component at an object generator, if any.
+    pub fn component(&self, x: &V) -> Option<&SkelColumn> {
+        self.components.get(x)
+    }
+
+    /// Composes with another transformation, in diagrammatic order.
+    ///
+    /// The composite has components only where both transformations do.
+    pub fn compose(&self, other: &Self) -> Self {
+        let components = self.components.iter().filter_map(|(x, first)| {
+            let second = other.components.get(&x)?;
+            let values = first.iter().filter_map(|(i, y)| Some((i, second.apply(*y)?)));
+            Some((x, values.collect()))
+        });
+        Self { components: components.collect() }
+    }
+
+    /// Validates that the transformation is natural between two instances.
+    pub fn validate_on<E>(
+        &self,
+        cat: &FpCategory<V, E>,
+        dom: &Instance<V, E>,
+        cod: &Instance<V, E>,
+    ) -> Result<(), NonEmpty<InvalidInstanceMorphism<V, E>>>
+    where
+        E: Eq + Clone + Hash,
+    {
+        validate::wrap_errors(self.iter_invalid_on(cat, dom, cod))
+    }
+
+    /// Iterates over failures of the transformation to be natural.
+    ///
+    /// Assumes that the domain and codomain are valid instances.
+    pub fn iter_invalid_on<'a, E>(
+        &'a self,
+        cat: &'a FpCategory<V, E>,
+        dom: &'a Instance<V, E>,
+        cod: &'a Instance<V, E>,
+    ) -> impl Iterator<Item = InvalidInstanceMorphism<V, E>> + 'a
+    where
+        E: Eq + Clone + Hash,
+    {
+        let component_errors = cat.ob_generators().flat_map(|x| {
+            let (Some(component), Some(dom_set), Some(cod_set)) =
+                (self.components.get(&x), dom.sets.get(&x), cod.sets.get(&x))
+            else {
+                return vec![InvalidInstanceMorphism::ObGen(x)];
+            };
+            Function(component, dom_set, cod_set)
+                .iter_invalid()
+                .map(|err| InvalidInstanceMorphism::Component(x.clone(), err.take()))
+                .collect()
+        });
+        let naturality_errors = cat.mor_generators().filter_map(|f| {
+            let (x, y) = (cat.mor_generator_dom(&f), cat.mor_generator_cod(&f));
+            let (α_x, α_y) = (self.components.get(&x)?, self.components.get(&y)?);
+            let path = Path::single(f.clone());
+            dom.sets
+                .get(&x)?
+                .iter()
+                .find(|i| {
+                    let lhs = dom.apply_path(&path, *i).and_then(|j| α_y.apply(j));
+                    let rhs = α_x.apply(*i).and_then(|j| cod.apply_path(&path, j));
+                    lhs != rhs
+                })
+                .map(|i| InvalidInstanceMorphism::Naturality(f, i))
+        });
+        component_errors.chain(naturality_errors)
+    }
+}
+
+/// A failure of a transformation between instances to be natural.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum InvalidInstanceMorphism<V, E> {
+    /// An object generator without a component.
+    #[error("Component at object generator `{0}` is not defined")]
+    ObGen(V),
+
+    /// A component that is not a function between the assigned sets.
+    #[error("Component at object generator `{0}` is ill-defined at element {1}")]
+    Component(V, usize),
+
+    /// A naturality square that fails to commute at an element.
+    #[error("Naturality square for `{0}` does not commute at element {1}")]
+    Naturality(E, usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::one::fp_category::{sch_graph, sch_sgraph};
+    use crate::zero::{QualifiedName, name};
+
+    /// The directed graph with two vertices and a single edge between them.
+    fn arrow() -> Instance<QualifiedName, QualifiedName> {
+        let mut inst = Instance::new();
+        inst.set_size(name("V"), 2);
+        inst.set_size(name("E"), 1);
+        inst.set_function(name("src"), vec![0]);
+        inst.set_function(name("tgt"), vec![1]);
+        inst
+    }
+
+    #[test]
+    fn validate_instance() {
+        let sch = sch_graph();
+        let inst = arrow();
+        assert!(inst.validate_on(&sch).is_ok());
+        assert_eq!(inst.apply_path(&Path::single(name("tgt")), 0), Some(1));
+
+        let mut inst = arrow();
+        inst.set_function(name("tgt"), vec![2]);
+        assert_eq!(
+            inst.validate_on(&sch).map_err(|errs| errs.head),
+            Err(InvalidInstance::Cod(name("tgt"), 0))
+        );
+
+        // The arrow is not a symmetric graph, as it has no involution.
+        let sch = sch_sgraph();
+        let mut inst = arrow();
+        inst.set_function(name("inv"), vec![0]);
+        assert!(matches!(
+            inst.validate_on(&sch).map_err(|errs| errs.head),
+            Err(InvalidInstance::Eq(_, 0))
+        ));
+    }
+
+    #[test]
+    fn validate_instance_morphism() {
+        let sch = sch_graph();
+        let inst = arrow();
+        let id = InstanceMorphism::identity(&inst);
+        assert!(id.validate_on(&sch, &inst, &inst).is_ok());
+        assert_eq!(id.compose(&id), id);
+
+        // Collapsing the graph onto a loop is natural, swapping vertices is not.
+        let mut point = Instance::new();
+        point.set_size(name("V"), 1);
+        point.set_size(name("E"), 1);
+        point.set_function(name("src"), vec![0]);
+        point.set_function(name("tgt"), vec![0]);
+        let mut collapse = InstanceMorphism::new();
+        collapse.set_component(name("V"), vec![0, 0]);
+        collapse.set_component(name("E"), vec![0]);
+        assert!(collapse.validate_on(&sch, &inst, &point).is_ok());
+
+        let mut swap = InstanceMorphism::new();
+        swap.set_component(name("V"), vec![1, 0]);
+        swap.set_component(name("E"), vec![0]);
+        assert_eq!(swap.validate_on(&sch, &inst, &inst).map_err(|errs| errs.len()), Err(2));
+    }
+}
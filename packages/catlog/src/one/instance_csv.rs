@@ -0,0 +1,281 @@
+//! Reading and writing instances as CSV tables.
+//!
+//! An [instance](super::instance) of a finitely presented category is stored as
+//! one CSV table per object generator. Each row of the table for an object is an
+//! element of its set. Rows are identified by an `id` column, if present, and
+//! otherwise by their position, starting from zero. The function assigned to a
+//! morphism generator is stored in the table for its domain, as a column named
+//! after the morphism whose values are row IDs in the table for its codomain.
+//! Other columns are ignored when reading.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+
+use derivative::Derivative;
+use thiserror::Error;
+
+use super::{FgCategory, FpCategory, instance::Instance};
+use crate::zero::Mapping;
+
+/// Name of the column identifying rows.
+pub const ID_COLUMN: &str = "id";
+
+/// An instance read from CSV tables, along with the IDs of its rows.
+#[derive(Clone, Debug, Derivative)]
+#[derivative(PartialEq(bound = "V: Eq + Hash, E: Eq + Hash"))]
+#[derivative(Eq(bound = "V: Eq + Hash, E: Eq + Hash"))]
+pub struct CsvInstance<V, E> {
+    /// The instance.
+    pub instance: Instance<V, E>,
+
+    /// IDs of the rows of the table for each object generator.
+    pub row_ids: HashMap<V, Vec<String>>,
+}
+
+/// A failure to read an instance from CSV tables.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum InvalidCsv {
+    /// No table is given for an object generator.
+    #[error("Missing table `{0}`")]
+    MissingTable(String),
+
+    /// A table has no header row.
+    #[error("Table `{0}` has no header")]
+    MissingHeader(String),
+
+    /// A table has no column for a morphism generator out of its object.
+    #[error("Table `{table}` has no column `{column}`")]
+    MissingColumn {
+        /// Name of the table.
+        table: String,
+        /// Name of the column.
+        column: String,
+    },
+
+    /// A row has a different number of fields than the header.
+    #[error("Row {row} of table `{table}` has the wrong number of fields")]
+    RaggedRow {
+        /// Name of the table.
+        table: String,
+        /// Number of the row, starting from one after the header.
+        row: usize,
+    },
+
+    /// A quoted field is not terminated.
+    #[error("Unterminated quoted field in table `{0}`")]
+    UnterminatedQuote(String),
+
+    /// Two rows in a table have the same ID.
+    #[error("Duplicate ID `{id}` in table `{table}`")]
+    DuplicateId {
+        /// Name of the table.
+        table: String,
+        /// The duplicated ID.
+        id: String,
+    },
+
+    /// A foreign key refers to a row that does not exist.
+    #[error("Column `{column}` of table `{table}` refers to unknown ID `{id}`")]
+    UnknownId {
+        /// Name of the table.
+        table: String,
+        /// Name of the column.
+        column: String,
+        /// The unknown ID.
+        id: String,
+    },
+}
+
+impl<V, E> CsvInstance<V, E>
+where
+    V: Eq + Clone + Hash + Display,
+    E: Eq + Clone + Hash + Display,
+{
+    /// Reads an instance from CSV tables, one for each object generator.
+    pub fn read(cat: &FpCategory<V, E>, tables: &HashMap<V, String>) -> Result<Self, InvalidCsv> {
+        let mut parsed = HashMap::new();
+        let mut row_ids = HashMap::new();
+        for v in cat.ob_generators() {
+            let name = v.to_string();
+            let text = tables.get(&v).ok_or_else(|| InvalidCsv::MissingTable(name.clone()))?;
+            let mut rows =
+                parse_csv(text).ok_or_else(|| InvalidCsv::UnterminatedQuote(name.clone()))?;
+            if rows.is_empty() {
+                return Err(InvalidCsv::MissingHeader(name));
+            }
+            let header = rows.remove(0);
+            if let Some(row) = rows.iter().position(|row| row.len() != header.len()) {
+                return Err(InvalidCsv::RaggedRow { table: name, row: row + 1 });
+            }
+            let ids: Vec<_> = match header.iter().position(|column| column == ID_COLUMN) {
+                Some(j) => rows.iter().map(|row| row[j].clone()).collect(),
+                None => (0..rows.len()).map(|i| i.to_string()).collect(),
+            };
+            let mut index = HashMap::new();
+            for (i, id) in ids.iter().enumerate() {
+                if index.insert(id.clone(), i).is_some() {
+                    return Err(InvalidCsv::DuplicateId { table: name, id: id.clone() });
+                }
+            }
+            row_ids.insert(v.clone(), ids);
+            parsed.insert(v, (header, rows, index));
+        }
+
+        let mut instance = Instance::new();
+        for (v, ids) in &row_ids {
+            instance.set_size(v.clone(), ids.len());
+        }
+        for f in cat.mor_generators() {
+            let (v, w) = (cat.mor_generator_dom(&f), cat.mor_generator_cod(&f));
+            let (header, rows, _) = &parsed[&v];
+            let (table, column) = (v.to_string(), f.to_string());
+            let Some(j) = header.iter().position(|name| *name == column) else {
+                return Err(InvalidCsv::MissingColumn { table, column });
+            };
+            let index = &parsed[&w].2;
+            let values = rows.iter().map(|row| {
+                index.get(&row[j]).copied().ok_or_else(|| InvalidCsv::UnknownId {
+                    table: table.clone(),
+                    column: column.clone(),
+                    id: row[j].clone(),
+                })
+            });
+            instance.set_function(f, values.collect::<Result<_, _>>()?);
+        }
+        Ok(Self { instance, row_ids })
+    }
+
+    /// Writes the instance as CSV tables, one for each object generator.
+    ///
+    /// Rows are identified by their stored IDs.
+    pub fn write(&self, cat: &FpCategory<V, E>) -> HashMap<V, String> {
+        write_tables(cat, &self.instance, |v, i| self.row_ids[v][i].clone())
+    }
+}
+
+impl<V, E> Instance<V, E>
+where
+    V: Eq + Clone + Hash + Display,
+    E: Eq + Clone + Hash + Display,
+{
+    /// Writes the instance as CSV tables, one for each object generator.
+    ///
+    /// Rows are identified by their positions.
+    pub fn to_csv(&self, cat: &FpCategory<V, E>) -> HashMap<V, String> {
+        write_tables(cat, self, |_, i| i.to_string())
+    }
+}
+
+fn write_tables<V, E>(
+    cat: &FpCategory<V, E>,
+    inst: &Instance<V, E>,
+    id: impl Fn(&V, usize) -> String,
+) -> HashMap<V, String>
+where
+    V: Eq + Clone + Hash + Display,
+    E: Eq + Clone + Hash + Display,
+{
+    let mut tables = HashMap::new();
+    for v in cat.ob_generators() {
+        let mors: Vec<_> = cat.mor_generators().filter(|f| cat.mor_generator_dom(f) == v).collect();
+        let header =
+            std::iter::once(ID_COLUMN.to_string()).chain(mors.iter().map(|f| f.to_string()));
+        let mut text = write_csv_row(header);
+        for i in inst.ob_set(&v).unwrap_or_default() {
+            let fields = mors.iter().map(|f| {
+                let w = cat.mor_generator_cod(f);
+                inst.function(f)
+                    .and_then(|func| func.apply(i))
+                    .map_or_else(String::new, |j| id(&w, j))
+            });
+            text.push_str(&write_csv_row(std::iter::once(id(&v, i)).chain(fields)));
+        }
+        tables.insert(v, text);
+    }
+    tables
+}
+
+/// Parses CSV text into rows of fields, or returns `None` on an unterminated
+/// quoted field.
+fn parse_csv(text: &str) -> Option<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let (mut row, mut field) = (Vec::new(), String::new());
+    let (mut quoted, mut chars) = (false, text.chars().peekable());
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Some(rows)
+}
+
+/// Writes a row of CSV, quoting fields where necessary.
+fn write_csv_row(fields: impl Iterator<Item = String>) -> String {
+    let fields: Vec<_> = fields
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    fields.join(",") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::one::fp_category::sch_graph;
+    use crate::zero::name;
+
+    #[test]
+    fn csv_round_trip() {
+        let sch = sch_graph();
+        let tables = HashMap::from([
+            (name("V"), "id,label\nx,\"first, vertex\"\ny,second\n".to_string()),
+            (name("E"), "src,tgt,id\nx,y,e\ny,y,loop\n".to_string()),
+        ]);
+        let csv = CsvInstance::read(&sch, &tables).unwrap();
+        assert!(csv.instance.validate_on(&sch).is_ok());
+        assert_eq!(csv.instance.function(&name("tgt")).unwrap().apply(0), Some(1));
+        assert_eq!(csv.row_ids[&name("E")], vec!["e", "loop"]);
+
+        let written = csv.write(&sch);
+        assert_eq!(written[&name("E")], "id,src,tgt\ne,x,y\nloop,y,y\n");
+        assert_eq!(CsvInstance::read(&sch, &written), Ok(csv.clone()));
+        assert_eq!(csv.instance.to_csv(&sch)[&name("V")], "id\n0\n1\n");
+
+        let mut tables = tables;
+        tables.insert(name("E"), "src,tgt\nx,z\n".to_string());
+        assert_eq!(
+            CsvInstance::read(&sch, &tables),
+            Err(InvalidCsv::UnknownId {
+                table: "E".into(),
+                column: "tgt".into(),
+                id: "z".into(),
+            })
+        );
+    }
+}
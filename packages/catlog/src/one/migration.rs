@@ -0,0 +1,539 @@
+//! Functorial data migration of instances.
+//!
+//! A functor F: C → D between finitely presented categories, thought of as a
+//! mapping between database schemas, induces three data migration functors
+//! between their [instances](super::instance):
+//!
+//! - [`delta`] (Δ_F), pulling back an instance on D to C by precomposition
+//! - [`sigma`] (Σ_F), the left adjoint of Δ_F, pushing forward an instance on C
+//!   by gluing together its elements
+//! - [`pi`] (Π_F), the right adjoint of Δ_F, pushing forward an instance on C by
+//!   taking compatible families of its elements
+//!
+//! Pulling back is always finite, but pushing forward need not be: when D has
+//! infinitely many morphisms, even the pushforward of a single element can be
+//! infinite. Σ is computed by the chase, which generates elements until the
+//! equations of D are satisfied, and Π by enumerating transformations out of
+//! representable instances, which are themselves computed by the chase. Both
+//! are bounded by [limits](MigrationLimits) on the work done, and fail rather
+//! than run forever.
+//!
+//! All functions assume that the given functor and instances are valid.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use thiserror::Error;
+
+use super::{FgCategory, FpCategory, GraphMapping, Path, instance::Instance};
+use crate::zero::FinSet;
+
+/// Limits on the work done when pushing forward an instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MigrationLimits {
+    /// Maximum number of elements generated by the chase, including elements
+    /// later identified with others.
+    pub max_elements: usize,
+
+    /// Maximum number of partial assignments tried when enumerating
+    /// transformations.
+    pub max_candidates: usize,
+}
+
+impl Default for MigrationLimits {
+    fn default() -> Self {
+        Self {
+            max_elements: 10_000,
+            max_candidates: 1_000_000,
+        }
+    }
+}
+
+/// A failure to migrate an instance.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum MigrationError {
+    /// The functor is not defined on a generator of its domain.
+    #[error("Functor is not defined on a generator")]
+    Unmapped,
+
+    /// The chase generated more than the maximum number of elements.
+    #[error("Migration exceeded the limit of {0} elements")]
+    ElementLimit(usize),
+
+    /// Enumerating transformations tried more than the maximum number of
+    /// candidates.
+    #[error("Migration exceeded the limit of {0} candidates")]
+    CandidateLimit(usize),
+}
+
+/// Pulls back an instance along a functor.
+pub fn delta<V, E, V2, E2, Map>(
+    map: &Map,
+    dom: &FpCategory<V, E>,
+    inst: &Instance<V2, E2>,
+) -> Result<Instance<V, E>, MigrationError>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash,
+    V2: Eq + Clone + Hash,
+    E2: Eq + Clone + Hash,
+    Map: GraphMapping<DomV = V, DomE = E, CodV = V2, CodE = Path<V2, E2>>,
+{
+    let mut result = Instance::new();
+    for c in dom.ob_generators() {
+        let d = map.apply_vertex(c.clone()).ok_or(MigrationError::Unmapped)?;
+        result.set_size(c, inst.ob_set(&d).unwrap_or_default().len());
+    }
+    for f in dom.mor_generators() {
+        let path = map.apply_edge(f.clone()).ok_or(MigrationError::Unmapped)?;
+        let d = map.apply_vertex(dom.mor_generator_dom(&f)).ok_or(MigrationError::Unmapped)?;
+        let values = inst.ob_set(&d).unwrap_or_default().into_iter();
+        let values = values.map(|x| inst.apply_path(&path, x).unwrap_or_default());
+        result.set_function(f, values.collect());
+    }
+    Ok(result)
+}
+
+/// Pushes forward an instance along a functor, by the left adjoint to pullback.
+pub fn sigma<V, E, V2, E2, Map>(
+    map: &Map,
+    dom: &FpCategory<V, E>,
+    cod: &FpCategory<V2, E2>,
+    inst: &Instance<V, E>,
+    limits: &MigrationLimits,
+) -> Result<Instance<V2, E2>, MigrationError>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash,
+    V2: Eq + Clone + Hash,
+    E2: Eq + Clone + Hash,
+    Map: GraphMapping<DomV = V, DomE = E, CodV = V2, CodE = Path<V2, E2>>,
+{
+    let mut chase = Chase::new(cod, limits);
+    let mut generators = HashMap::new();
+    for c in dom.ob_generators() {
+        let d = map.apply_vertex(c.clone()).ok_or(MigrationError::Unmapped)?;
+        for x in inst.ob_set(&c).unwrap_or_default() {
+            generators.insert((c.clone(), x), chase.add_element(d.clone(), Path::Id(d.clone()))?);
+        }
+    }
+
+    // Each morphism in the domain identifies an element in the pushforward
+    // with the image of another element.
+    let mut relations = Vec::new();
+    for f in dom.mor_generators() {
+        let path = map.apply_edge(f.clone()).ok_or(MigrationError::Unmapped)?;
+        let (c, c2) = (dom.mor_generator_dom(&f), dom.mor_generator_cod(&f));
+        let f = Path::single(f);
+        for x in inst.ob_set(&c).unwrap_or_default() {
+            if let Some(y) = inst.apply_path(&f, x)
+                && let Some(&image) = generators.get(&(c2.clone(), y))
+            {
+                relations.push((path.clone(), generators[&(c.clone(), x)], image));
+            }
+        }
+    }
+
+    chase.run(&relations)?;
+    Ok(chase.extract().instance)
+}
+
+/// Pushes forward an instance along a functor, by the right adjoint to pullback.
+///
+/// The pushforward at an object d is the set of transformations from the
+/// pullback of the representable instance at d to the given instance, so the
+/// representable instances of the codomain must be finite.
+pub fn pi<V, E, V2, E2, Map>(
+    map: &Map,
+    dom: &FpCategory<V, E>,
+    cod: &FpCategory<V2, E2>,
+    inst: &Instance<V, E>,
+    limits: &MigrationLimits,
+) -> Result<Instance<V2, E2>, MigrationError>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash,
+    V2: Eq + Clone + Hash,
+    E2: Eq + Clone + Hash,
+    Map: GraphMapping<DomV = V, DomE = E, CodV = V2, CodE = Path<V2, E2>>,
+{
+    let obs: Vec<_> = dom.ob_generators().collect();
+    let mut reps = HashMap::new();
+    let mut result = Instance::new();
+    for d in cod.ob_generators() {
+        let mut chase = Chase::new(cod, limits);
+        chase.add_element(d.clone(), Path::Id(d.clone()))?;
+        chase.run(&[])?;
+        let rep = chase.extract();
+        let pullback = delta(map, dom, &rep.instance)?;
+        let homs = transformations(dom, &obs, &pullback, inst, limits)?;
+        result.set_size(d.clone(), homs.len());
+        reps.insert(d, (rep, homs));
+    }
+
+    // A morphism g: d → d' acts on transformations by precomposing with the
+    // map of representables sending h at d' to g⋅h at d.
+    for g in cod.mor_generators() {
+        let (d, d2) = (cod.mor_generator_dom(&g), cod.mor_generator_cod(&g));
+        let (rep, homs) = &reps[&d];
+        let (rep2, homs2) = &reps[&d2];
+        let index: HashMap<_, _> = homs2.iter().enumerate().map(|(i, hom)| (hom, i)).collect();
+        let mut values = Vec::new();
+        for hom in homs {
+            let mut image = Vec::new();
+            for (c, component) in obs.iter().zip(hom) {
+                let fc = map.apply_vertex(c.clone()).ok_or(MigrationError::Unmapped)?;
+                let witnesses = rep2.witnesses.get(&fc).map(|w| w.as_slice()).unwrap_or_default();
+                let image_component = witnesses.iter().map(|h| {
+                    let edges = std::iter::once(g.clone()).chain(h.iter().cloned()).collect();
+                    let gh = Path::from_vec(edges).unwrap();
+                    component[rep.instance.apply_path(&gh, 0).unwrap()]
+                });
+                image.push(image_component.collect::<Vec<_>>());
+            }
+            values.push(index[&image]);
+        }
+        result.set_function(g, values);
+    }
+    Ok(result)
+}
+
+/// Result of the chase.
+struct ChaseOutput<V, E> {
+    /// The instance generated.
+    instance: Instance<V, E>,
+
+    /// For each object, paths from a generating element to each element.
+    witnesses: HashMap<V, Vec<Path<V, E>>>,
+}
+
+/// State of the chase on a finitely presented category.
+///
+/// Elements are stored in a union-find structure, where the representative of
+/// each class is its oldest element.
+struct Chase<'a, V, E> {
+    cat: &'a FpCategory<V, E>,
+    max_elements: usize,
+    parents: Vec<usize>,
+    obs: Vec<V>,
+    witnesses: Vec<Path<V, E>>,
+    images: HashMap<(E, usize), usize>,
+}
+
+impl<'a, V, E> Chase<'a, V, E>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash,
+{
+    fn new(cat: &'a FpCategory<V, E>, limits: &MigrationLimits) -> Self {
+        Self {
+            cat,
+            max_elements: limits.max_elements,
+            parents: Vec::new(),
+            obs: Vec::new(),
+            witnesses: Vec::new(),
+            images: HashMap::new(),
+        }
+    }
+
+    fn find(&self, mut x: usize) -> usize {
+        while self.parents[x] != x {
+            x = self.parents[x];
+        }
+        x
+    }
+
+    fn merge(&mut self, x: usize, y: usize) -> bool {
+        let (x, y) = (self.find(x), self.find(y));
+        if x == y {
+            return false;
+        }
+        self.parents[x.max(y)] = x.min(y);
+        true
+    }
+
+    fn add_element(&mut self, ob: V, witness: Path<V, E>) -> Result<usize, MigrationError> {
+        let x = self.parents.len();
+        if x >= self.max_elements {
+            return Err(MigrationError::ElementLimit(self.max_elements));
+        }
+        self.parents.push(x);
+        self.obs.push(ob);
+        self.witnesses.push(witness);
+        Ok(x)
+    }
+
+    fn apply(&mut self, f: &E, x: usize) -> Result<usize, MigrationError> {
+        let x = self.find(x);
+        if let Some(&y) = self.images.get(&(f.clone(), x)) {
+            return Ok(self.find(y));
+        }
+        let edges = self.witnesses[x].iter().cloned().chain([f.clone()]).collect();
+        let y = self.add_element(self.cat.mor_generator_cod(f), Path::from_vec(edges).unwrap())?;
+        self.images.insert((f.clone(), x), y);
+        Ok(y)
+    }
+
+    fn apply_path(&mut self, path: &Path<V, E>, x: usize) -> Result<usize, MigrationError> {
+        path.iter().try_fold(x, |x, f| self.apply(f, x))
+    }
+
+    fn roots(&self) -> Vec<usize> {
+        (0..self.parents.len()).filter(|x| self.find(*x) == *x).collect()
+    }
+
+    /// Runs the chase to completion, identifying elements as required by the
+    /// given relations, the equations of the category, and functionality.
+    fn run(&mut self, relations: &[(Path<V, E>, usize, usize)]) -> Result<(), MigrationError> {
+        let cat = self.cat;
+        loop {
+            let (n, mut changed) = (self.parents.len(), false);
+            for (path, x, y) in relations {
+                let x = self.apply_path(path, *x)?;
+                changed |= self.merge(x, *y);
+            }
+            let roots = self.roots();
+            for eq in cat.equations() {
+                let ob = eq.lhs.src(cat.generators());
+                let xs: Vec<_> = roots.iter().copied().filter(|x| self.obs[*x] == ob).collect();
+                for x in xs {
+                    let lhs = self.apply_path(&eq.lhs, x)?;
+                    let rhs = self.apply_path(&eq.rhs, x)?;
+                    changed |= self.merge(lhs, rhs);
+                }
+            }
+
+            // Make the action of every generator total.
+            for x in self.roots() {
+                for f in cat.mor_generators() {
+                    if cat.mor_generator_dom(&f) == self.obs[x] {
+                        self.apply(&f, x)?;
+                    }
+                }
+            }
+
+            // Identify images of identified elements.
+            for ((f, x), y) in std::mem::take(&mut self.images) {
+                let (key, y) = ((f, self.find(x)), self.find(y));
+                if let Some(&other) = self.images.get(&key) {
+                    changed |= self.merge(other, y);
+                } else {
+                    self.images.insert(key, y);
+                }
+            }
+
+            if !changed && self.parents.len() == n {
+                return Ok(());
+            }
+        }
+    }
+
+    fn extract(&self) -> ChaseOutput<V, E> {
+        let roots = self.roots();
+        let mut instance = Instance::new();
+        let mut witnesses = HashMap::new();
+        let mut index = HashMap::new();
+        for v in self.cat.ob_generators() {
+            let elems: Vec<_> = roots.iter().copied().filter(|x| self.obs[*x] == v).collect();
+            index.extend(elems.iter().enumerate().map(|(i, x)| (*x, i)));
+            instance.set_size(v.clone(), elems.len());
+            witnesses.insert(v, elems.iter().map(|x| self.witnesses[*x].clone()).collect());
+        }
+        for f in self.cat.mor_generators() {
+            let v = self.cat.mor_generator_dom(&f);
+            let values = roots.iter().filter(|x| self.obs[**x] == v).map(|x| {
+                let y = self.find(self.images[&(f.clone(), *x)]);
+                index[&y]
+            });
+            instance.set_function(f, values.collect());
+        }
+        ChaseOutput { instance, witnesses }
+    }
+}
+
+/// Enumerates the transformations between instances on a category.
+///
+/// Each transformation is given by its components at the given objects, in
+/// order. Transformations are found by backtracking over the elements of the
+/// domain, checking naturality as soon as both sides of a square are assigned.
+fn transformations<V, E>(
+    cat: &FpCategory<V, E>,
+    obs: &[V],
+    dom: &Instance<V, E>,
+    cod: &Instance<V, E>,
+    limits: &MigrationLimits,
+) -> Result<Vec<Vec<Vec<usize>>>, MigrationError>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash,
+{
+    let size = |inst: &Instance<V, E>, v: &V| inst.ob_set(v).map_or(0, |set| set.len());
+    let ob_index: HashMap<_, _> = obs.iter().enumerate().map(|(i, v)| (v.clone(), i)).collect();
+    let squares: Vec<_> = cat
+        .mor_generators()
+        .map(|f| {
+            let (i, j) =
+                (ob_index[&cat.mor_generator_dom(&f)], ob_index[&cat.mor_generator_cod(&f)]);
+            (Path::single(f), i, j)
+        })
+        .collect();
+
+    let slots: Vec<_> = (0..obs.len())
+        .flat_map(|i| (0..size(dom, &obs[i])).map(move |x| (i, x)))
+        .collect();
+    let mut search = TransformationSearch {
+        dom,
+        cod,
+        squares,
+        assignment: obs.iter().map(|v| vec![None; size(dom, v)]).collect(),
+        cod_sizes: obs.iter().map(|v| size(cod, v)).collect(),
+        candidates: 0,
+        max_candidates: limits.max_candidates,
+        solutions: Vec::new(),
+    };
+    search.extend(&slots)?;
+    Ok(search.solutions)
+}
+
+/// State of the backtracking search for transformations.
+struct TransformationSearch<'a, V, E> {
+    dom: &'a Instance<V, E>,
+    cod: &'a Instance<V, E>,
+    squares: Vec<(Path<V, E>, usize, usize)>,
+    assignment: Vec<Vec<Option<usize>>>,
+    cod_sizes: Vec<usize>,
+    candidates: usize,
+    max_candidates: usize,
+    solutions: Vec<Vec<Vec<usize>>>,
+}
+
+impl<V, E> TransformationSearch<'_, V, E>
+where
+    V: Eq + Clone + Hash,
+    E: Eq + Clone + Hash,
+{
+    fn extend(&mut self, slots: &[(usize, usize)]) -> Result<(), MigrationError> {
+        let Some(((i, x), rest)) = slots.split_first() else {
+            let solution =
+                self.assignment.iter().map(|c| c.iter().flatten().copied().collect::<Vec<_>>());
+            self.solutions.push(solution.collect());
+            return Ok(());
+        };
+        for y in 0..self.cod_sizes[*i] {
+            self.candidates += 1;
+            if self.candidates > self.max_candidates {
+                return Err(MigrationError::CandidateLimit(self.max_candidates));
+            }
+            self.assignment[*i][*x] = Some(y);
+            if self.is_natural_at(*i, *x) {
+                self.extend(rest)?;
+            }
+        }
+        self.assignment[*i][*x] = None;
+        Ok(())
+    }
+
+    /// Checks the naturality squares involving an assigned element.
+    fn is_natural_at(&self, i: usize, x: usize) -> bool {
+        let assigned = |i: usize, x: usize| self.assignment[i][x];
+        self.squares.iter().all(|(f, j, k)| {
+            // Squares out of the element.
+            let out_ok = *j != i || {
+                let fx = self.dom.apply_path(f, x).unwrap_or_default();
+                match (assigned(i, x), assigned(*k, fx)) {
+                    (Some(y), Some(z)) => self.cod.apply_path(f, y) == Some(z),
+                    _ => true,
+                }
+            };
+            // Squares into the element.
+            let in_ok = *k != i || {
+                (0..self.assignment[*j].len()).all(|w| match (assigned(*j, w), assigned(i, x)) {
+                    (Some(y), Some(z)) if self.dom.apply_path(f, w) == Some(x) => {
+                        self.cod.apply_path(f, y) == Some(z)
+                    }
+                    _ => true,
+                })
+            };
+            out_ok && in_ok
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::one::FpFunctorData;
+    use crate::one::fp_category::sch_graph;
+    use crate::zero::{HashColumn, QualifiedName, SkelFinSet, name};
+
+    type Inst = Instance<QualifiedName, QualifiedName>;
+
+    fn graph(vertices: usize, edges: &[(usize, usize)]) -> Inst {
+        let mut inst = Instance::new();
+        inst.set_size(name("V"), vertices);
+        inst.set_size(name("E"), edges.len());
+        inst.set_function(name("src"), edges.iter().map(|e| e.0).collect());
+        inst.set_function(name("tgt"), edges.iter().map(|e| e.1).collect());
+        inst
+    }
+
+    /// The category with one object and no generating morphisms.
+    fn terminal() -> FpCategory<QualifiedName, QualifiedName> {
+        let mut cat = FpCategory::new();
+        cat.add_ob_generator(name("*"));
+        cat
+    }
+
+    #[test]
+    fn migrate_along_identity() {
+        let sch = sch_graph();
+        let ob_map = HashColumn::from_iter([(name("V"), name("V")), (name("E"), name("E"))]);
+        let mor_map =
+            HashColumn::from_iter(["src", "tgt"].map(|f| (name(f), Path::single(name(f)))));
+        let id = FpFunctorData::new(ob_map, mor_map);
+        let limits = MigrationLimits::default();
+
+        let inst = graph(2, &[(0, 1)]);
+        assert_eq!(delta(&id, &sch, &inst), Ok(graph(2, &[(0, 1)])));
+        assert_eq!(sigma(&id, &sch, &sch, &inst, &limits), Ok(graph(2, &[(0, 1)])));
+        assert_eq!(pi(&id, &sch, &sch, &inst, &limits), Ok(graph(2, &[(0, 1)])));
+    }
+
+    #[test]
+    fn migrate_to_terminal() {
+        let (sch, term) = (sch_graph(), terminal());
+        let ob_map = HashColumn::from_iter([(name("V"), name("*")), (name("E"), name("*"))]);
+        let mor_map = HashColumn::from_iter(["src", "tgt"].map(|f| (name(f), Path::Id(name("*")))));
+        let to_terminal = FpFunctorData::new(ob_map, mor_map);
+        let limits = MigrationLimits::default();
+
+        // Σ computes connected components and Π computes loops.
+        let inst = graph(3, &[(0, 1), (2, 2)]);
+        let components = sigma(&to_terminal, &sch, &term, &inst, &limits).unwrap();
+        assert_eq!(components.ob_set(&name("*")), Some(SkelFinSet::from(2)));
+        let loops = pi(&to_terminal, &sch, &term, &inst, &limits).unwrap();
+        assert_eq!(loops.ob_set(&name("*")), Some(SkelFinSet::from(1)));
+
+        let mut single = Instance::new();
+        single.set_size(name("*"), 1);
+        assert_eq!(delta(&to_terminal, &sch, &single), Ok(graph(1, &[(0, 0)])));
+    }
+
+    #[test]
+    fn chase_limits() {
+        // The free monoid on one generator is infinite.
+        let mut cat = terminal();
+        cat.add_mor_generator(name("s"), name("*"), name("*"));
+        let ob_map = HashColumn::from_iter([(name("*"), name("*"))]);
+        let mor_map: HashColumn<_, Path<_, _>> = HashColumn::default();
+        let inclusion = FpFunctorData::new(ob_map, mor_map);
+        let limits = MigrationLimits { max_elements: 100, ..Default::default() };
+
+        let mut single = Instance::new();
+        single.set_size(name("*"), 1);
+        assert_eq!(
+            sigma(&inclusion, &terminal(), &cat, &single, &limits),
+            Err(MigrationError::ElementLimit(100))
+        );
+    }
+}
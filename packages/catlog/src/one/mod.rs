@@ -1,12 +1,25 @@
 //! Category theory in dimension one, plus a little graph theory.
 
 pub mod category;
+pub mod commutativity;
+pub mod completion;
 pub mod computad;
 pub mod fp_category;
+pub mod free_monad;
 pub mod functor;
 pub mod graph;
 pub mod graph_algorithms;
+pub mod graph_clustering;
+pub mod graph_edit;
+pub mod graph_features;
+pub mod graph_io;
+pub mod graph_products;
+pub mod instance;
+pub mod instance_csv;
+pub mod migration;
 pub mod path;
+pub mod svg;
+pub mod tikz_cd;
 pub mod tree;
 pub mod tree_algorithms;
 
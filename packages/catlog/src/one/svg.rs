@@ -0,0 +1,337 @@
+//! Rendering of graphs as SVG images.
+//!
+//! A [labeled graph](LabeledGraph), such as the underlying graph of a model, is
+//! rendered as a standalone SVG image without needing a browser, so that images
+//! of models can be produced by the server. Nodes are drawn as labeled boxes
+//! and edges as labeled arrows, curved apart when parallel. Edges typed as
+//! positive or negative, as in causal loop diagrams, are decorated with their
+//! sign near the arrowhead.
+//!
+//! Nodes are placed by a [layout](Layout) computed elsewhere. Nodes missing
+//! from the layout, which may be all of them, are placed on a circle, or in a
+//! row below the other nodes if some are positioned.
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::fmt::Write;
+
+use super::graph_io::{LabeledGraph, xml_escape};
+use super::tikz_cd::Layout;
+
+/// Height of a node box.
+const NODE_HEIGHT: f32 = 30.0;
+
+/// Approximate width of a character in a label, for sizing boxes.
+const CHAR_WIDTH: f32 = 8.0;
+
+/// Distance between nodes placed automatically.
+const NODE_SPACING: f32 = 120.0;
+
+/// Padding around the drawing.
+const PADDING: f32 = 20.0;
+
+/// Curvature, as the offset of the midpoint, between parallel edges.
+const EDGE_CURVE: f32 = 25.0;
+
+const SVG_STYLE: &str = "\
+text { font-family: sans-serif; font-size: 14px; }
+.node rect { fill: white; stroke: black; stroke-width: 1.5; }
+.edge path { fill: none; stroke: black; stroke-width: 1.5; }
+.edge text { font-size: 12px; }
+.sign { font-weight: bold; }";
+
+/// A node placed in the drawing.
+struct PlacedNode {
+    center: (f32, f32),
+    half_width: f32,
+    label: String,
+}
+
+impl PlacedNode {
+    /// Gets the point where a ray from the center in a direction leaves the box.
+    fn boundary(&self, (dx, dy): (f32, f32)) -> (f32, f32) {
+        let half_height = NODE_HEIGHT / 2.0;
+        let tx = if dx == 0.0 {
+            f32::INFINITY
+        } else {
+            self.half_width / dx.abs()
+        };
+        let ty = if dy == 0.0 {
+            f32::INFINITY
+        } else {
+            half_height / dy.abs()
+        };
+        let t = tx.min(ty);
+        (self.center.0 + t * dx, self.center.1 + t * dy)
+    }
+}
+
+impl LabeledGraph {
+    /// Renders the graph as an SVG image, with nodes placed by the layout.
+    ///
+    /// The image has its natural size unless a width and height are given, in
+    /// which case the drawing is scaled to fit, as for thumbnails.
+    pub fn to_svg(&self, layout: &Layout, size: Option<(f32, f32)>) -> String {
+        let nodes = self.place_nodes(layout);
+        let index: HashMap<_, _> =
+            self.nodes.iter().enumerate().map(|(k, node)| (node.id.as_str(), k)).collect();
+
+        // Offset parallel edges symmetrically about the straight line.
+        let mut parallel: HashMap<_, Vec<_>> = HashMap::new();
+        let edges: Vec<_> = self
+            .edges
+            .iter()
+            .filter_map(|edge| {
+                let source = *index.get(edge.source.as_str())?;
+                let target = *index.get(edge.target.as_str())?;
+                Some((edge, source, target))
+            })
+            .collect();
+        for (k, (_, source, target)) in edges.iter().enumerate() {
+            parallel
+                .entry(((*source).min(*target), (*source).max(*target)))
+                .or_default()
+                .push(k);
+        }
+        let mut offsets = vec![0.0; edges.len()];
+        for group in parallel.values() {
+            let m = group.len() as f32;
+            for (i, &k) in group.iter().enumerate() {
+                let (_, source, target) = edges[k];
+                let offset = (2.0 * i as f32 - (m - 1.0)) * EDGE_CURVE;
+                offsets[k] = if source <= target { offset } else { -offset };
+            }
+        }
+
+        let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for node in &nodes {
+            let (x, y) = node.center;
+            min_x = min_x.min(x - node.half_width);
+            max_x = max_x.max(x + node.half_width);
+            // Leave room for loops above nodes.
+            min_y = min_y.min(y - NODE_HEIGHT / 2.0 - NODE_HEIGHT);
+            max_y = max_y.max(y + NODE_HEIGHT / 2.0);
+        }
+        if nodes.is_empty() {
+            (min_x, min_y, max_x, max_y) = (0.0, 0.0, 0.0, 0.0);
+        }
+        let (x0, y0) = (min_x - PADDING, min_y - PADDING);
+        let (width, height) = (max_x - min_x + 2.0 * PADDING, max_y - min_y + 2.0 * PADDING);
+        let (image_width, image_height) = size.unwrap_or((width, height));
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" "#,
+                r#"viewBox="{} {} {} {}">"#
+            ),
+            image_width, image_height, x0, y0, width, height
+        )
+        .unwrap();
+        writeln!(svg, "<style>\n{SVG_STYLE}\n</style>").unwrap();
+        svg.push_str(concat!(
+            r#"<defs><marker id="arrowhead" viewBox="0 0 10 10" refX="10" refY="5" "#,
+            r#"markerWidth="8" markerHeight="8" orient="auto-start-reverse">"#,
+            r#"<path d="M 0 0 L 10 5 L 0 10 z"/></marker></defs>"#,
+            "\n"
+        ));
+
+        for ((edge, source, target), offset) in std::iter::zip(edges, offsets) {
+            let label = edge.label.as_ref().unwrap_or(&edge.id);
+            let (path, label_pos, sign_pos) = if source == target {
+                loop_path(&nodes[source])
+            } else {
+                edge_path(&nodes[source], &nodes[target], offset)
+            };
+            svg.push_str("<g class=\"edge\">");
+            write!(svg, r#"<path d="{path}" marker-end="url(#arrowhead)"/>"#).unwrap();
+            write!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                label_pos.0,
+                label_pos.1,
+                xml_escape(label)
+            )
+            .unwrap();
+            if let Some(sign) = edge.edge_type.as_deref().and_then(edge_sign) {
+                write!(
+                    svg,
+                    r#"<text class="sign" x="{:.1}" y="{:.1}" text-anchor="middle">{sign}</text>"#,
+                    sign_pos.0, sign_pos.1
+                )
+                .unwrap();
+            }
+            svg.push_str("</g>\n");
+        }
+        for node in &nodes {
+            let (x, y) = node.center;
+            write!(
+                svg,
+                r#"<g class="node"><rect x="{:.1}" y="{:.1}" width="{:.1}" height="{}" rx="5"/>"#,
+                x - node.half_width,
+                y - NODE_HEIGHT / 2.0,
+                2.0 * node.half_width,
+                NODE_HEIGHT,
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                concat!(
+                    r#"<text x="{:.1}" y="{:.1}" text-anchor="middle" "#,
+                    r#"dominant-baseline="central">{}</text></g>"#
+                ),
+                x,
+                y,
+                xml_escape(&node.label)
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Places the nodes using the layout, falling back to automatic placement.
+    fn place_nodes(&self, layout: &Layout) -> Vec<PlacedNode> {
+        let positions: Vec<_> =
+            self.nodes.iter().map(|node| layout.get(&node.id).copied()).collect();
+        let n_missing = positions.iter().filter(|pos| pos.is_none()).count();
+        let mut automatic: Box<dyn Iterator<Item = (f32, f32)>> = if n_missing == self.nodes.len() {
+            let n = n_missing as f32;
+            let radius = if n_missing > 1 {
+                (NODE_SPACING * n / (2.0 * PI)).max(NODE_SPACING / 2.0)
+            } else {
+                0.0
+            };
+            Box::new((0..n_missing).map(move |i| {
+                let angle = -PI / 2.0 + 2.0 * PI * i as f32 / n;
+                (radius * angle.cos(), radius * angle.sin())
+            }))
+        } else {
+            let placed = positions.iter().flatten();
+            let min_x = placed.clone().map(|(x, _)| *x).fold(f32::INFINITY, f32::min);
+            let max_y = placed.map(|(_, y)| *y).fold(f32::NEG_INFINITY, f32::max);
+            Box::new(
+                (0..n_missing)
+                    .map(move |i| (min_x + NODE_SPACING * i as f32, max_y + NODE_SPACING)),
+            )
+        };
+        std::iter::zip(&self.nodes, positions)
+            .map(|(node, pos)| {
+                let label = node.label.clone().unwrap_or_else(|| node.id.clone());
+                let half_width = (CHAR_WIDTH * label.chars().count() as f32 + 20.0) / 2.0;
+                let center = pos.unwrap_or_else(|| automatic.next().unwrap());
+                PlacedNode { center, half_width, label }
+            })
+            .collect()
+    }
+}
+
+/// Gets the SVG path of an edge, with the positions of its label and sign.
+fn edge_path(
+    source: &PlacedNode,
+    target: &PlacedNode,
+    offset: f32,
+) -> (String, (f32, f32), (f32, f32)) {
+    let (sx, sy) = source.center;
+    let (tx, ty) = target.center;
+    let length = ((tx - sx).powi(2) + (ty - sy).powi(2)).sqrt().max(f32::EPSILON);
+    let (ux, uy) = ((tx - sx) / length, (ty - sy) / length);
+    // Unit normal to the left of the direction of the edge, in SVG coordinates.
+    let (nx, ny) = (uy, -ux);
+    let control = ((sx + tx) / 2.0 + 2.0 * offset * nx, (sy + ty) / 2.0 + 2.0 * offset * ny);
+
+    let towards = |from: (f32, f32), to: (f32, f32)| {
+        let d = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt().max(f32::EPSILON);
+        ((to.0 - from.0) / d, (to.1 - from.1) / d)
+    };
+    let start = source.boundary(towards(source.center, control));
+    let end = target.boundary(towards(target.center, control));
+    let path = format!(
+        "M {:.1} {:.1} Q {:.1} {:.1} {:.1} {:.1}",
+        start.0, start.1, control.0, control.1, end.0, end.1
+    );
+
+    // The midpoint of the quadratic curve is halfway to the control point.
+    let mid = ((sx + tx) / 2.0 + offset * nx, (sy + ty) / 2.0 + offset * ny);
+    let side = if offset < 0.0 { -1.0 } else { 1.0 };
+    let label = (mid.0 + 12.0 * side * nx, mid.1 + 12.0 * side * ny + 4.0);
+    let back = towards(end, control);
+    let sign = (end.0 + 14.0 * back.0 + 10.0 * nx, end.1 + 14.0 * back.1 + 10.0 * ny + 4.0);
+    (path, label, sign)
+}
+
+/// Gets the SVG path of a loop above a node, with the positions of its label
+/// and sign.
+fn loop_path(node: &PlacedNode) -> (String, (f32, f32), (f32, f32)) {
+    let (x, y) = node.center;
+    let top = y - NODE_HEIGHT / 2.0;
+    let path = format!(
+        "M {:.1} {top:.1} C {:.1} {:.1} {:.1} {:.1} {:.1} {top:.1}",
+        x - 8.0,
+        x - 25.0,
+        top - 35.0,
+        x + 25.0,
+        top - 35.0,
+        x + 8.0,
+    );
+    (path, (x, top - 30.0), (x + 22.0, top - 8.0))
+}
+
+/// Gets the sign of an edge from its type, if it is positive or negative.
+///
+/// Delayed edges in causal loop diagrams, such as `NegativeSlow`, also count.
+fn edge_sign(edge_type: &str) -> Option<&'static str> {
+    if edge_type.contains("Negative") {
+        Some("\u{2212}")
+    } else if edge_type.contains("Positive") {
+        Some("+")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::graph_io::{LabeledEdge, LabeledNode};
+    use super::*;
+
+    #[test]
+    fn render_signed_graph() {
+        let node = |id: &str| LabeledNode {
+            id: id.into(),
+            label: Some(format!("<{id}>")),
+            node_type: None,
+        };
+        let edge = |id: &str, source: &str, target: &str, sign: &str| LabeledEdge {
+            id: id.into(),
+            source: source.into(),
+            target: target.into(),
+            label: None,
+            edge_type: Some(sign.into()),
+        };
+        let graph = LabeledGraph {
+            nodes: vec![node("x"), node("y")],
+            edges: vec![
+                edge("f", "x", "y", "Positive"),
+                edge("g", "y", "x", "Negative"),
+                edge("h", "y", "y", "Hom Object"),
+            ],
+        };
+
+        let svg = graph.to_svg(&Layout::new(), None);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect").count(), 2);
+        assert_eq!(svg.matches("marker-end").count(), 3);
+        assert_eq!(svg.matches("class=\"sign\"").count(), 2);
+        assert!(svg.contains(">&lt;x&gt;</text>"));
+        assert!(svg.contains(">\u{2212}</text>"));
+
+        let layout: Layout = [("x".to_string(), (0.0, 0.0))].into_iter().collect();
+        let thumbnail = graph.to_svg(&layout, Some((160.0, 120.0)));
+        assert!(thumbnail.contains("width=\"160\" height=\"120\""));
+        assert!(thumbnail.contains(r#"<rect x="-22.0" y="105.0""#));
+    }
+}
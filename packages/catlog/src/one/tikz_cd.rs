@@ -0,0 +1,503 @@
+//! Export of graphs as commutative diagrams for papers.
+//!
+//! A [labeled graph](LabeledGraph), such as the underlying graph of a model, is
+//! drawn as a commutative diagram in [TikZ-cd](https://ctan.org/pkg/tikz-cd) or
+//! in the JSON format of [quiver](https://q.uiver.app), which can be edited
+//! further in quiver and then exported to LaTeX from there.
+//!
+//! Both formats place objects on a grid. The positions of the nodes are taken
+//! from a [layout](Layout) computed elsewhere, usually by the layout engine of
+//! the frontend, and snapped to the grid while preserving the left-to-right and
+//! top-to-bottom order of the nodes. Nodes missing from the layout are placed
+//! in rows below the others. Parallel edges are curved apart from each other.
+//!
+//! Diagrams made in quiver can also be imported, from either the exported JSON
+//! or a URL to the diagram. Vertices and 1-cells of the diagram become nodes and
+//! edges of the graph, identified by their indices in the diagram, and the
+//! positions of vertices become the layout. Labels of the form `\mathrm{...}`,
+//! as written by the exporter, are converted back to plain text, while other
+//! labels are kept as LaTeX. Higher cells and styling are ignored. The graph
+//! can be turned into a model of, say, the theory of categories or of schemas
+//! by [`to_discrete_model`](LabeledGraph::to_discrete_model).
+//!
+//! Export to and import from quiver require the `serde` feature.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+#[cfg(feature = "serde")]
+use base64::{
+    Engine,
+    alphabet::STANDARD,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+#[cfg(feature = "serde")]
+use serde_json::{Value, json};
+#[cfg(feature = "serde")]
+use thiserror::Error;
+
+use super::graph_io::{LabeledEdge, LabeledGraph, LabeledNode};
+
+/// Positions of nodes in a layout, by node ID.
+///
+/// Coordinates are in arbitrary units, with the y-axis pointing downward as in
+/// SVG and Graphviz output.
+pub type Layout = HashMap<String, (f32, f32)>;
+
+/// Positions closer than this, in layout units, are put in the same row or
+/// column of the grid.
+const GRID_TOLERANCE: f32 = 1.0;
+
+/// Distance between adjacent rows or columns of a quiver diagram, in layout
+/// units, when importing.
+#[cfg(feature = "serde")]
+const QUIVER_SPACING: f32 = 100.0;
+
+/// Base64 encoding used in the URLs of quiver, accepting unpadded input.
+#[cfg(feature = "serde")]
+const QUIVER_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// A failure to read a diagram in the format of quiver.
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum InvalidQuiver {
+    /// The URL does not contain an encoded diagram.
+    #[error("URL does not contain a quiver diagram")]
+    Url,
+
+    /// The diagram in the URL is not validly encoded in Base64.
+    #[error("Malformed Base64 encoding")]
+    Base64,
+
+    /// The diagram is not well-formed JSON.
+    #[error("Malformed JSON: {0}")]
+    Json(String),
+
+    /// The JSON does not describe a quiver diagram.
+    #[error("Invalid quiver diagram: {0}")]
+    Diagram(String),
+}
+
+/// A graph placed on a grid, ready to be written as a commutative diagram.
+struct GridDiagram {
+    /// Row, column, and label of each node.
+    cells: Vec<(usize, usize, String)>,
+
+    /// Source index, target index, label, and curvature of each edge.
+    arrows: Vec<(usize, usize, String, i32)>,
+}
+
+impl LabeledGraph {
+    /// Writes the graph as a TikZ-cd diagram, with nodes placed by the layout.
+    pub fn to_tikz_cd(&self, layout: &Layout) -> String {
+        let diagram = GridDiagram::new(self, layout);
+        let n_rows = diagram.cells.iter().map(|(i, _, _)| i + 1).max().unwrap_or(0);
+        let mut rows = vec![Vec::new(); n_rows];
+        for (i, j, label) in &diagram.cells {
+            let row = &mut rows[*i];
+            if row.len() <= *j {
+                row.resize(j + 1, String::new());
+            }
+            row[*j] = label.clone();
+        }
+
+        let mut result = "\\begin{tikzcd}\n".to_string();
+        for (i, row) in rows.iter().enumerate() {
+            let end = if i + 1 < n_rows { " \\\\" } else { "" };
+            writeln!(result, "\t{}{end}", row.join(" & ")).unwrap();
+        }
+        for (source, target, label, curve) in &diagram.arrows {
+            let (si, sj, _) = &diagram.cells[*source];
+            let (ti, tj, _) = &diagram.cells[*target];
+            let mut options =
+                format!("\"{label}\", from={}-{}, to={}-{}", si + 1, sj + 1, ti + 1, tj + 1);
+            if source == target {
+                options.push_str(", loop, in=55, out=125, distance=10mm");
+            } else if *curve != 0 {
+                let side = if *curve > 0 { "left" } else { "right" };
+                write!(options, ", bend {side}={}", 15 * curve.abs()).unwrap();
+            }
+            writeln!(result, "\t\\arrow[{options}]").unwrap();
+        }
+        result.push_str("\\end{tikzcd}\n");
+        result
+    }
+}
+
+#[cfg(feature = "serde")]
+impl LabeledGraph {
+    /// Writes the graph in the JSON format of quiver, with nodes placed by the
+    /// layout.
+    pub fn to_quiver(&self, layout: &Layout) -> String {
+        let diagram = GridDiagram::new(self, layout);
+        let mut items = vec![json!(0), json!(diagram.cells.len())];
+        for (i, j, label) in &diagram.cells {
+            items.push(json!([j, i, label]));
+        }
+        for (source, target, label, curve) in &diagram.arrows {
+            if source != target && *curve != 0 {
+                items.push(json!([source, target, label, 0, { "curve": curve }]));
+            } else {
+                items.push(json!([source, target, label]));
+            }
+        }
+        Value::Array(items).to_string()
+    }
+
+    /// Gets a URL that opens the graph in quiver, with nodes placed by the
+    /// layout.
+    pub fn to_quiver_url(&self, layout: &Layout) -> String {
+        let encoded = QUIVER_BASE64.encode(self.to_quiver(layout));
+        format!("https://q.uiver.app/#q={encoded}")
+    }
+
+    /// Reads a graph and its layout from a diagram in the JSON format of quiver.
+    pub fn from_quiver(text: &str) -> Result<(Self, Layout), InvalidQuiver> {
+        let invalid = |msg: &str| InvalidQuiver::Diagram(msg.to_string());
+        let value: Value =
+            serde_json::from_str(text).map_err(|err| InvalidQuiver::Json(err.to_string()))?;
+        let Value::Array(items) = value else {
+            return Err(invalid("expected an array"));
+        };
+        let n_vertices = match items.get(1) {
+            Some(n) => as_index(n).ok_or_else(|| invalid("expected number of vertices"))?,
+            None => 0,
+        };
+        if items.len() < 2 + n_vertices {
+            return Err(invalid("fewer vertices than declared"));
+        }
+
+        let mut graph = LabeledGraph::default();
+        let mut layout = Layout::new();
+        for (k, item) in items[2..2 + n_vertices].iter().enumerate() {
+            let Value::Array(vertex) = item else {
+                return Err(invalid("expected vertex to be an array"));
+            };
+            let (Some(x), Some(y)) =
+                (vertex.first().and_then(Value::as_f64), vertex.get(1).and_then(Value::as_f64))
+            else {
+                return Err(invalid("expected vertex to have a position"));
+            };
+            let id = k.to_string();
+            layout.insert(id.clone(), (x as f32 * QUIVER_SPACING, y as f32 * QUIVER_SPACING));
+            graph.nodes.push(LabeledNode {
+                id,
+                label: vertex.get(2).and_then(plain_label),
+                node_type: None,
+            });
+        }
+        for (k, item) in items[2 + n_vertices..].iter().enumerate() {
+            let Value::Array(arrow) = item else {
+                return Err(invalid("expected arrow to be an array"));
+            };
+            let (Some(source), Some(target)) =
+                (arrow.first().and_then(as_index), arrow.get(1).and_then(as_index))
+            else {
+                return Err(invalid("expected arrow to have a source and target"));
+            };
+            if source >= n_vertices + k || target >= n_vertices + k {
+                return Err(invalid("arrow refers to a later cell"));
+            }
+            // Arrows between arrows are 2-cells, which a graph cannot hold.
+            if source >= n_vertices || target >= n_vertices {
+                continue;
+            }
+            graph.edges.push(LabeledEdge {
+                id: (n_vertices + k).to_string(),
+                source: source.to_string(),
+                target: target.to_string(),
+                label: arrow.get(2).and_then(plain_label),
+                edge_type: None,
+            });
+        }
+        Ok((graph, layout))
+    }
+
+    /// Reads a graph and its layout from a URL to a diagram in quiver.
+    pub fn from_quiver_url(url: &str) -> Result<(Self, Layout), InvalidQuiver> {
+        let (_, query) = url.split_once("q=").ok_or(InvalidQuiver::Url)?;
+        let encoded = query.split('&').next().unwrap_or_default();
+        let decoded = QUIVER_BASE64
+            .decode(encoded.replace("%3D", "="))
+            .map_err(|_| InvalidQuiver::Base64)?;
+        let text = String::from_utf8(decoded).map_err(|_| InvalidQuiver::Base64)?;
+        Self::from_quiver(&text)
+    }
+}
+
+impl GridDiagram {
+    fn new(graph: &LabeledGraph, layout: &Layout) -> Self {
+        let positions: Vec<_> = graph.nodes.iter().map(|node| layout.get(&node.id)).collect();
+        let xs = grid_lines(positions.iter().flatten().map(|(x, _)| *x));
+        let ys = grid_lines(positions.iter().flatten().map(|(_, y)| *y));
+
+        // Place nodes with positions, moving right any node whose cell is taken.
+        let mut taken = HashSet::new();
+        let mut place = |mut cell: (usize, usize)| {
+            while !taken.insert(cell) {
+                cell.1 += 1;
+            }
+            cell
+        };
+        let mut cells: Vec<_> = positions
+            .iter()
+            .map(|pos| pos.map(|(x, y)| place((grid_index(&ys, *y), grid_index(&xs, *x)))))
+            .collect();
+
+        // Place the remaining nodes on a square grid below the others.
+        let unplaced: Vec<_> = (0..cells.len()).filter(|&k| cells[k].is_none()).collect();
+        let width = (unplaced.len() as f32).sqrt().ceil() as usize;
+        for (n, k) in unplaced.into_iter().enumerate() {
+            cells[k] = Some(place((ys.len() + n / width, n % width)));
+        }
+
+        let index: HashMap<_, _> =
+            graph.nodes.iter().enumerate().map(|(k, node)| (node.id.as_str(), k)).collect();
+        let cells = std::iter::zip(&graph.nodes, cells)
+            .map(|(node, cell)| {
+                let (i, j) = cell.unwrap();
+                (i, j, latex_label(node.label.as_ref().unwrap_or(&node.id)))
+            })
+            .collect();
+
+        // Curve parallel edges apart, including edges in opposite directions.
+        let mut parallel: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        let mut arrows = Vec::new();
+        for edge in &graph.edges {
+            let (Some(&source), Some(&target)) =
+                (index.get(edge.source.as_str()), index.get(edge.target.as_str()))
+            else {
+                continue;
+            };
+            parallel
+                .entry((source.min(target), source.max(target)))
+                .or_default()
+                .push(arrows.len());
+            let label = latex_label(edge.label.as_ref().unwrap_or(&edge.id));
+            arrows.push((source, target, label, 0));
+        }
+        for group in parallel.values() {
+            let m = group.len() as i32;
+            for (k, &a) in group.iter().enumerate() {
+                let (source, target, _, curve) = &mut arrows[a];
+                let offset = 2 * k as i32 - (m - 1);
+                *curve = if source <= target { offset } else { -offset };
+            }
+        }
+        Self { cells, arrows }
+    }
+}
+
+/// Gets the distinct coordinates along an axis, up to the tolerance, in order.
+fn grid_lines(coords: impl Iterator<Item = f32>) -> Vec<f32> {
+    let mut coords: Vec<_> = coords.collect();
+    coords.sort_by(f32::total_cmp);
+    coords.dedup_by(|b, a| *b - *a < GRID_TOLERANCE);
+    coords
+}
+
+/// Gets the index of the grid line to which a coordinate is snapped.
+fn grid_index(lines: &[f32], coord: f32) -> usize {
+    lines.partition_point(|&line| line + GRID_TOLERANCE <= coord)
+}
+
+/// Formats a label as LaTeX in math mode.
+fn latex_label(label: &str) -> String {
+    if label.chars().count() <= 1 {
+        return label.to_string();
+    }
+    let mut text = String::new();
+    for c in label.chars() {
+        match c {
+            ' ' => text.push_str("\\ "),
+            '_' | '&' | '%' | '$' | '#' | '{' | '}' => {
+                text.push('\\');
+                text.push(c);
+            }
+            '"' => text.push_str("{''}"),
+            _ => text.push(c),
+        }
+    }
+    format!("\\mathrm{{{text}}}")
+}
+
+/// Converts a label in LaTeX to plain text, if it was written by the exporter.
+///
+/// Empty labels are treated as no label.
+#[cfg(feature = "serde")]
+fn plain_label(label: &Value) -> Option<String> {
+    let label = label.as_str().filter(|label| !label.is_empty())?;
+    let Some(text) = label.strip_prefix("\\mathrm{").and_then(|s| s.strip_suffix('}')) else {
+        return Some(label.to_string());
+    };
+    let text = text.replace("{''}", "\"");
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    Some(result)
+}
+
+/// Gets a JSON value as an index, if it is a nonnegative integer.
+#[cfg(feature = "serde")]
+fn as_index(value: &Value) -> Option<usize> {
+    let x = value.as_f64()?;
+    (x >= 0.0 && x.fract() == 0.0).then_some(x as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use expect_test::expect;
+
+    use super::*;
+    use crate::dbl::model::FpDblModel;
+    use crate::one::{FgCategory, Path};
+    use crate::stdlib::theories::th_schema;
+    use crate::validate::Validate;
+    use crate::zero::name;
+
+    fn graph(nodes: &[&str], edges: &[(&str, &str, &str)]) -> LabeledGraph {
+        LabeledGraph {
+            nodes: nodes
+                .iter()
+                .map(|id| LabeledNode {
+                    id: id.to_string(),
+                    label: None,
+                    node_type: None,
+                })
+                .collect(),
+            edges: edges
+                .iter()
+                .map(|(id, source, target)| LabeledEdge {
+                    id: id.to_string(),
+                    source: source.to_string(),
+                    target: target.to_string(),
+                    label: None,
+                    edge_type: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn commutative_square() {
+        let square = graph(
+            &["a", "b", "c", "d"],
+            &[("f", "a", "b"), ("g", "a", "c"), ("h", "b", "d"), ("k", "c", "d")],
+        );
+        let layout: Layout = [
+            ("a", (10.0, 5.0)),
+            ("b", (110.3, 5.2)),
+            ("c", (10.0, 80.0)),
+            ("d", (109.9, 80.0)),
+        ]
+        .into_iter()
+        .map(|(id, pos)| (id.to_string(), pos))
+        .collect();
+
+        let expected = expect![[r#"
+            \begin{tikzcd}
+            	a & b \\
+            	c & d
+            	\arrow["f", from=1-1, to=1-2]
+            	\arrow["g", from=1-1, to=2-1]
+            	\arrow["h", from=1-2, to=2-2]
+            	\arrow["k", from=2-1, to=2-2]
+            \end{tikzcd}
+        "#]];
+        expected.assert_eq(&square.to_tikz_cd(&layout));
+
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            square.to_quiver(&layout),
+            concat!(
+                r#"[0,4,[0,0,"a"],[1,0,"b"],[0,1,"c"],[1,1,"d"],"#,
+                r#"[0,1,"f"],[0,2,"g"],[1,3,"h"],[2,3,"k"]]"#
+            )
+        );
+        #[cfg(feature = "serde")]
+        assert!(square.to_quiver_url(&layout).starts_with("https://q.uiver.app/#q=WzAsNC"));
+    }
+
+    #[test]
+    fn parallel_edges_and_loops() {
+        let mut g = graph(&["x", "y"], &[("s", "x", "y"), ("t", "y", "x"), ("id", "y", "y")]);
+        g.nodes[1].label = Some("my set".into());
+        let expected = expect![[r#"
+            \begin{tikzcd}
+            	x & \mathrm{my\ set}
+            	\arrow["s", from=1-1, to=1-2, bend right=15]
+            	\arrow["t", from=1-2, to=1-1, bend right=15]
+            	\arrow["\mathrm{id}", from=1-2, to=1-2, loop, in=55, out=125, distance=10mm]
+            \end{tikzcd}
+        "#]];
+        expected.assert_eq(&g.to_tikz_cd(&Layout::new()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn quiver_url_padding() {
+        let url = graph(&["x"], &[]).to_quiver_url(&Layout::new());
+        assert!(url.ends_with("=="));
+        for url in [url.clone(), url.replace('=', "%3D"), url.trim_end_matches('=').into()] {
+            let (graph, _) = LabeledGraph::from_quiver_url(&url).unwrap();
+            assert_eq!(graph.nodes[0].label.as_deref(), Some("x"));
+        }
+        let url = "https://q.uiver.app/#q=not*base64";
+        assert_eq!(LabeledGraph::from_quiver_url(url), Err(InvalidQuiver::Base64));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn import_quiver() {
+        let text = concat!(
+            r#"[0,3,[0,0,"A"],[1,0,"\\mathrm{my\\ set}"],[0,1,"",{"colour":[0,0,50,1]}],"#,
+            r#"[0,1,"f",0,{"curve":-2}],[0,1,"g"],[3,4,"\\alpha",1,{"level":2}],[1,1]]"#
+        );
+        let (graph, layout) = LabeledGraph::from_quiver(text).unwrap();
+        let labels: Vec<_> = graph.nodes.iter().map(|x| x.label.as_deref()).collect();
+        assert_eq!(labels, vec![Some("A"), Some("my set"), None]);
+        assert_eq!(layout["1"], (100.0, 0.0));
+        assert_eq!(layout["2"], (0.0, 100.0));
+        let edges: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|f| (f.id.as_str(), f.source.as_str(), f.target.as_str(), f.label.as_deref()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![("3", "0", "1", Some("f")), ("4", "0", "1", Some("g")), ("6", "1", "1", None)]
+        );
+
+        let url = graph.to_quiver_url(&layout);
+        let (round_trip, _) = LabeledGraph::from_quiver_url(&url).unwrap();
+        assert_eq!(round_trip.nodes[1].label.as_deref(), Some("my set"));
+        assert_eq!(round_trip.edges.len(), 3);
+
+        assert!(LabeledGraph::from_quiver("[0,2,[0,0,\"A\"]]").is_err());
+        assert!(LabeledGraph::from_quiver("[0,1,[0,0],[0,2]]").is_err());
+        assert_eq!(LabeledGraph::from_quiver_url("https://q.uiver.app"), Err(InvalidQuiver::Url));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn quiver_to_schema() {
+        let text = r#"[0,2,[0,0,"Person"],[1,0,"Company"],[0,1,"employer"],[0,0,"boss"]]"#;
+        let (graph, _) = LabeledGraph::from_quiver(text).unwrap();
+        let th = Rc::new(th_schema());
+        let model = graph.to_discrete_model(th, name("Entity"));
+        assert_eq!(model.ob_generators().count(), 2);
+        assert_eq!(model.mor_generator_cod(&name("employer")), name("Company"));
+        assert_eq!(model.mor_generator_type(&name("boss")), Path::Id(name("Entity")));
+        assert!(model.validate().is_ok());
+    }
+}
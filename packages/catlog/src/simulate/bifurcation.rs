@@ -0,0 +1,357 @@
+//! Bifurcation analysis by one-parameter continuation.
+//!
+//! A family of [ODE systems](ODESystem) depending on a parameter, such as the
+//! transmission rate of an epidemic model, is scanned over a range of parameter
+//! values. At each value, the equilibria of the system are found by Newton's
+//! method, starting from the equilibria at the previous value, so that each
+//! equilibrium is tracked along a branch, and from extra initial guesses, to
+//! discover new branches. Optionally, the system is also simulated from a fixed
+//! initial state to detect whether it settles into a limit cycle.
+//!
+//! Qualitative changes between consecutive parameter values are reported as
+//! [bifurcation events](BifurcationEvent): branches of equilibria appearing or
+//! disappearing, as at a saddle-node bifurcation, changes in stability, as at a
+//! transcritical or Hopf bifurcation, and limit cycles appearing or
+//! disappearing. Since the scan is over a grid, an event is located only up to
+//! the interval between grid points.
+
+use nalgebra::DVector;
+
+use super::ode::{Equilibrium, EquilibriumSolver, ODEProblem, ODESystem, Stability};
+
+/// A one-parameter bifurcation scan.
+#[derive(Clone, Debug)]
+pub struct BifurcationScan {
+    values: Vec<f32>,
+    guesses: Vec<DVector<f32>>,
+    solver: EquilibriumSolver,
+    cycle_detection: Option<(DVector<f32>, f32)>,
+}
+
+/// Result of a [bifurcation scan](BifurcationScan).
+#[derive(Clone, Debug, PartialEq)]
+pub struct BifurcationDiagram {
+    /// Equilibria and limit cycles at each parameter value.
+    pub points: Vec<BifurcationPoint>,
+
+    /// Qualitative changes between consecutive parameter values.
+    pub events: Vec<BifurcationEvent>,
+}
+
+/// Equilibria and limit cycle of a system at one parameter value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BifurcationPoint {
+    /// Value of the parameter.
+    pub parameter: f32,
+
+    /// Equilibria, with the branch to which each belongs.
+    pub equilibria: Vec<(usize, Equilibrium)>,
+
+    /// Limit cycle reached from the initial state, if detection is enabled and
+    /// a cycle is found.
+    pub cycle: Option<LimitCycle>,
+}
+
+/// A limit cycle found by simulation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LimitCycle {
+    /// Largest peak-to-trough amplitude of a state variable over the cycle.
+    pub amplitude: f32,
+
+    /// Estimated period of the cycle.
+    pub period: f32,
+}
+
+/// A qualitative change between consecutive parameter values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BifurcationEvent {
+    /// Parameter values between which the change occurs.
+    pub interval: (f32, f32),
+
+    /// Branch of equilibria involved, if any.
+    pub branch: Option<usize>,
+
+    /// Kind of change.
+    pub kind: BifurcationKind,
+}
+
+/// Kind of qualitative change in a bifurcation scan.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BifurcationKind {
+    /// A branch of equilibria begins.
+    Appearance,
+
+    /// A branch of equilibria ends, usually by colliding with another.
+    Disappearance,
+
+    /// An equilibrium changes stability as a real eigenvalue crosses zero, as
+    /// at a transcritical or pitchfork bifurcation.
+    StabilityChange {
+        /// Stability before the change.
+        from: Stability,
+        /// Stability after the change.
+        to: Stability,
+    },
+
+    /// An equilibrium changes stability as a pair of complex eigenvalues
+    /// crosses the imaginary axis, as at a Hopf bifurcation.
+    Hopf {
+        /// Stability before the change.
+        from: Stability,
+        /// Stability after the change.
+        to: Stability,
+    },
+
+    /// The system starts to settle into a limit cycle.
+    CycleAppearance,
+
+    /// The system stops settling into a limit cycle.
+    CycleDisappearance,
+}
+
+impl BifurcationScan {
+    /// Creates a scan over the given parameter values, in order.
+    pub fn new(values: Vec<f32>) -> Self {
+        Self {
+            values,
+            guesses: Vec::new(),
+            solver: EquilibriumSolver::new(),
+            cycle_detection: None,
+        }
+    }
+
+    /// Creates a scan over evenly spaced parameter values, including both ends.
+    pub fn linspace(start: f32, end: f32, n: usize) -> Self {
+        let step = if n > 1 {
+            (end - start) / (n - 1) as f32
+        } else {
+            0.0
+        };
+        Self::new((0..n).map(|i| start + step * i as f32).collect())
+    }
+
+    /// Sets initial guesses for equilibria, tried at every parameter value.
+    pub fn guesses(mut self, guesses: Vec<DVector<f32>>) -> Self {
+        self.guesses = guesses;
+        self
+    }
+
+    /// Sets the solver used to find equilibria.
+    pub fn solver(mut self, solver: EquilibriumSolver) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// Enables detection of limit cycles by simulating the system from the
+    /// initial state for the given duration.
+    pub fn detect_cycles(mut self, initial_values: DVector<f32>, duration: f32) -> Self {
+        self.cycle_detection = Some((initial_values, duration));
+        self
+    }
+
+    /// Runs the scan on a family of systems indexed by the parameter.
+    pub fn run<Sys: ODESystem>(&self, family: impl Fn(f32) -> Sys) -> BifurcationDiagram {
+        let mut points: Vec<BifurcationPoint> = Vec::new();
+        let mut events = Vec::new();
+        let mut n_branches = 0;
+        for &parameter in &self.values {
+            let system = family(parameter);
+            let interval = (points.last().map_or(parameter, |p| p.parameter), parameter);
+
+            // Continue the branches from the previous parameter value.
+            let mut equilibria: Vec<(usize, Equilibrium)> = Vec::new();
+            for (branch, previous) in points.last().map_or(&[][..], |p| p.equilibria.as_slice()) {
+                let continued = self
+                    .solver
+                    .equilibria(&system, [previous.state.clone()])
+                    .pop()
+                    .filter(|eq| !equilibria.iter().any(|(_, other)| self.same(eq, other)));
+                let Some(eq) = continued else {
+                    events.push(BifurcationEvent {
+                        interval,
+                        branch: Some(*branch),
+                        kind: BifurcationKind::Disappearance,
+                    });
+                    continue;
+                };
+                if eq.stability != previous.stability {
+                    let (from, to) = (previous.stability, eq.stability);
+                    let kind = if is_oscillatory(previous) || is_oscillatory(&eq) {
+                        BifurcationKind::Hopf { from, to }
+                    } else {
+                        BifurcationKind::StabilityChange { from, to }
+                    };
+                    events.push(BifurcationEvent { interval, branch: Some(*branch), kind });
+                }
+                equilibria.push((*branch, eq));
+            }
+
+            // Start new branches from the initial guesses.
+            for eq in self.solver.equilibria(&system, self.guesses.iter().cloned()) {
+                if equilibria.iter().any(|(_, other)| self.same(&eq, other)) {
+                    continue;
+                }
+                if !points.is_empty() {
+                    events.push(BifurcationEvent {
+                        interval,
+                        branch: Some(n_branches),
+                        kind: BifurcationKind::Appearance,
+                    });
+                }
+                equilibria.push((n_branches, eq));
+                n_branches += 1;
+            }
+
+            let cycle = self
+                .cycle_detection
+                .as_ref()
+                .and_then(|(initial, duration)| detect_cycle(&system, initial, *duration));
+            if let Some(previous) = points.last() {
+                let kind = match (&previous.cycle, &cycle) {
+                    (None, Some(_)) => Some(BifurcationKind::CycleAppearance),
+                    (Some(_), None) => Some(BifurcationKind::CycleDisappearance),
+                    _ => None,
+                };
+                events.extend(kind.map(|kind| BifurcationEvent { interval, branch: None, kind }));
+            }
+            points.push(BifurcationPoint { parameter, equilibria, cycle });
+        }
+        BifurcationDiagram { points, events }
+    }
+
+    fn same(&self, eq: &Equilibrium, other: &Equilibrium) -> bool {
+        self.solver.same_state(&eq.state, &other.state)
+    }
+}
+
+/// Whether the leading eigenvalues of an equilibrium are complex.
+fn is_oscillatory(eq: &Equilibrium) -> bool {
+    eq.eigenvalues
+        .iter()
+        .max_by(|z, w| z.re.total_cmp(&w.re))
+        .is_some_and(|z| z.im.abs() > 1e-3)
+}
+
+/// Simulates the system and checks whether it settles into a limit cycle.
+///
+/// The trajectory is a cycle if its amplitude is nonnegligible and does not
+/// decay over the last half of the simulation, where the system is assumed to
+/// have settled.
+fn detect_cycle<Sys: ODESystem>(
+    system: &Sys,
+    initial_values: &DVector<f32>,
+    duration: f32,
+) -> Option<LimitCycle> {
+    let problem = ODEProblem::new(system, initial_values.clone());
+    let n_steps = 1000;
+    let times: Vec<_> = (1..=n_steps).map(|i| duration * i as f32 / n_steps as f32).collect();
+    let result = problem.solve_dopri5_at(&times).ok()?;
+    let (t_out, x_out) = result.get();
+    let (third, fourth) = (&x_out[n_steps / 2..3 * n_steps / 4], &x_out[3 * n_steps / 4..]);
+
+    let range = |states: &[DVector<f32>], i: usize| {
+        let values = states.iter().map(|x| x[i]);
+        values.clone().fold(f32::NEG_INFINITY, f32::max) - values.fold(f32::INFINITY, f32::min)
+    };
+    let (i, amplitude) = (0..initial_values.len())
+        .map(|i| (i, range(&x_out[n_steps / 2..], i)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+    let scale = 1.0 + x_out.last()?.amax();
+    if amplitude < 1e-3 * scale || range(fourth, i) < 0.9 * range(third, i) {
+        return None;
+    }
+
+    // Estimate the period from upward crossings of the mean.
+    let settled = &x_out[n_steps / 2..];
+    let mean = settled.iter().map(|x| x[i]).sum::<f32>() / settled.len() as f32;
+    let crossings: Vec<_> = settled
+        .windows(2)
+        .zip(&t_out[n_steps / 2 + 1..])
+        .filter(|(w, _)| w[0][i] < mean && w[1][i] >= mean)
+        .map(|(_, t)| *t)
+        .collect();
+    let (first, last) = (crossings.first()?, crossings.last()?);
+    if crossings.len() < 3 {
+        return None;
+    }
+    let period = (last - first) / (crossings.len() - 1) as f32;
+    Some(LimitCycle { amplitude, period })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SIS epidemic with transmission rate `β` and recovery rate 1, in terms of
+    /// the infected fraction, `I' = βI(1 - I) - I`.
+    struct Sis {
+        beta: f32,
+    }
+
+    impl ODESystem for Sis {
+        fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+            dx[0] = self.beta * x[0] * (1.0 - x[0]) - x[0];
+        }
+    }
+
+    /// Hopf normal form, which has a stable limit cycle of radius `√μ` when
+    /// `μ > 0`.
+    struct HopfNormalForm {
+        mu: f32,
+    }
+
+    impl ODESystem for HopfNormalForm {
+        fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+            let r2 = x[0] * x[0] + x[1] * x[1];
+            dx[0] = self.mu * x[0] - x[1] - r2 * x[0];
+            dx[1] = x[0] + self.mu * x[1] - r2 * x[1];
+        }
+    }
+
+    #[test]
+    fn epidemic_threshold() {
+        let diagram = BifurcationScan::linspace(0.55, 1.95, 15)
+            .guesses(vec![DVector::from_element(1, 0.0), DVector::from_element(1, 0.9)])
+            .run(|beta| Sis { beta });
+        assert_eq!(diagram.points.len(), 15);
+
+        // The disease-free equilibrium loses stability when `β` passes 1.
+        let (lo, hi) = diagram
+            .events
+            .iter()
+            .find_map(|event| match event.kind {
+                BifurcationKind::StabilityChange {
+                    from: Stability::Stable,
+                    to: Stability::Unstable,
+                } => Some(event.interval),
+                _ => None,
+            })
+            .unwrap();
+        assert!(lo < 1.0 && 1.0 < hi);
+
+        // Above the threshold, the endemic equilibrium `1 - 1/β` is stable.
+        let last = diagram.points.last().unwrap();
+        let endemic = 1.0 - 1.0 / last.parameter;
+        assert!(last.equilibria.iter().any(|(_, eq)| {
+            (eq.state[0] - endemic).abs() < 1e-3 && eq.stability == Stability::Stable
+        }));
+    }
+
+    #[test]
+    fn hopf_bifurcation() {
+        let diagram = BifurcationScan::new(vec![-0.25, -0.05, 0.15, 0.35])
+            .guesses(vec![DVector::from_vec(vec![0.1, 0.0])])
+            .detect_cycles(DVector::from_vec(vec![0.5, 0.0]), 60.0)
+            .run(|mu| HopfNormalForm { mu });
+        let kinds: Vec<_> = diagram.events.iter().map(|event| &event.kind).collect();
+        assert!(kinds.contains(&&BifurcationKind::Hopf {
+            from: Stability::Stable,
+            to: Stability::Unstable
+        }));
+        assert!(kinds.contains(&&BifurcationKind::CycleAppearance));
+
+        let cycle = diagram.points.last().unwrap().cycle.as_ref().unwrap();
+        assert!((cycle.amplitude - 2.0 * 0.35f32.sqrt()).abs() < 0.05);
+        assert!((cycle.period - 2.0 * std::f32::consts::PI).abs() < 0.2);
+    }
+}
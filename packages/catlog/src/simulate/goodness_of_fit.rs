@@ -0,0 +1,170 @@
+//! Goodness of fit of simulated output to observed data.
+//!
+//! Observations are rarely made at the times where a simulator reports its
+//! output, so the simulated time series is aligned to the observations by
+//! linear interpolation at the observed times. Observations outside the time
+//! span of the simulation are not extrapolated to and are skipped, as are
+//! missing observations. The residuals, observed minus simulated values, are
+//! then summarized by standard metrics in a [report](FitReport).
+//!
+//! The metrics are meant to be shared by anything comparing models to data, so
+//! that, say, the objective minimized when fitting parameters agrees with the
+//! goodness of fit reported to the user.
+
+use std::f32::consts::PI;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::time_series::interpolate;
+
+/// Observed data for a single variable.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ObservedSeries {
+    /// Times of the observations, in increasing order.
+    pub times: Vec<f32>,
+
+    /// Observed values, or `None` where the observation is missing.
+    pub values: Vec<Option<f32>>,
+}
+
+/// Report on the goodness of fit of simulated output to observed data.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct FitReport {
+    /// Number of observations compared with the simulation.
+    #[cfg_attr(feature = "serde", serde(rename = "nPoints"))]
+    pub n_points: usize,
+
+    /// Root mean squared error.
+    pub rmse: f32,
+
+    /// Mean absolute error.
+    pub mae: f32,
+
+    /// Standard deviation of the Gaussian noise assumed in the likelihood.
+    pub sigma: f32,
+
+    /// Log-likelihood of the observations under Gaussian noise.
+    #[cfg_attr(feature = "serde", serde(rename = "logLikelihood"))]
+    pub log_likelihood: f32,
+}
+
+impl FitReport {
+    /// Computes a report from residuals.
+    ///
+    /// The noise is assumed to be Gaussian with the given standard deviation
+    /// or, if none is given, with its maximum likelihood estimate, which is the
+    /// RMSE. In the latter case, a perfect fit has infinite log-likelihood.
+    /// Returns `None` if there are no residuals.
+    pub fn from_residuals(residuals: &[f32], sigma: Option<f32>) -> Option<Self> {
+        if residuals.is_empty() {
+            return None;
+        }
+        let n = residuals.len() as f32;
+        let sse: f32 = residuals.iter().map(|r| r * r).sum();
+        let rmse = (sse / n).sqrt();
+        let mae = residuals.iter().map(|r| r.abs()).sum::<f32>() / n;
+        let sigma = sigma.unwrap_or(rmse);
+        let log_likelihood = if sigma > 0.0 {
+            -0.5 * n * (2.0 * PI * sigma * sigma).ln() - sse / (2.0 * sigma * sigma)
+        } else if sse == 0.0 {
+            f32::INFINITY
+        } else {
+            f32::NEG_INFINITY
+        };
+        Some(Self {
+            n_points: residuals.len(),
+            rmse,
+            mae,
+            sigma,
+            log_likelihood,
+        })
+    }
+}
+
+/// Computes the residuals of observed data against a simulated time series.
+///
+/// The simulated series is interpolated linearly at each observed time within
+/// its time span. Missing and non-finite observations are skipped.
+pub fn residuals(times: &[f32], values: &[f32], observed: &ObservedSeries) -> Vec<f32> {
+    assert_eq!(times.len(), values.len(), "Times and values should have the same length");
+    assert_eq!(
+        observed.times.len(),
+        observed.values.len(),
+        "Observed times and values should have the same length"
+    );
+    let (Some(&start), Some(&end)) = (times.first(), times.last()) else {
+        return Vec::new();
+    };
+    observed
+        .times
+        .iter()
+        .zip(&observed.values)
+        .filter(|(t, _)| start <= **t && **t <= end)
+        .filter_map(|(&t, &x)| x.filter(|x| x.is_finite()).map(|x| (t, x)))
+        .map(|(t, x)| x - interpolate(times, values, t))
+        .collect()
+}
+
+/// Computes the goodness of fit of a simulated time series to observed data.
+///
+/// See [`residuals`] for how the series are aligned and
+/// [`FitReport::from_residuals`] for how the noise is treated. Returns `None`
+/// if no observations can be compared with the simulation.
+pub fn goodness_of_fit(
+    times: &[f32],
+    values: &[f32],
+    observed: &ObservedSeries,
+    sigma: Option<f32>,
+) -> Option<FitReport> {
+    FitReport::from_residuals(&residuals(times, values, observed), sigma)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolated_fit() {
+        let times = [0.0, 1.0, 2.0, 3.0];
+        let values = [0.0, 2.0, 4.0, 6.0];
+        let observed = ObservedSeries {
+            times: vec![0.5, 1.5, 2.0, 2.5, 4.0],
+            values: vec![Some(2.0), None, Some(4.0), Some(4.0), Some(100.0)],
+        };
+        // Residuals are 1, 0, and -1; the missing and late points are skipped.
+        assert_eq!(residuals(&times, &values, &observed), vec![1.0, 0.0, -1.0]);
+
+        let report = goodness_of_fit(&times, &values, &observed, None).unwrap();
+        assert_eq!(report.n_points, 3);
+        assert!((report.rmse - (2f32 / 3.0).sqrt()).abs() < 1e-6);
+        assert!((report.mae - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(report.sigma, report.rmse);
+        let expected = -1.5 * (2.0 * PI * 2.0 / 3.0).ln() - 1.5;
+        assert!((report.log_likelihood - expected).abs() < 1e-5);
+
+        let report = goodness_of_fit(&times, &values, &observed, Some(1.0)).unwrap();
+        assert!((report.log_likelihood - (-1.5 * (2.0 * PI).ln() - 1.0)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn degenerate_fits() {
+        let empty = ObservedSeries::default();
+        assert_eq!(goodness_of_fit(&[0.0, 1.0], &[0.0, 1.0], &empty, None), None);
+
+        let exact = ObservedSeries {
+            times: vec![0.0, 1.0],
+            values: vec![Some(0.0), Some(1.0)],
+        };
+        let report = goodness_of_fit(&[0.0, 1.0], &[0.0, 1.0], &exact, None).unwrap();
+        assert_eq!((report.rmse, report.log_likelihood), (0.0, f32::INFINITY));
+    }
+}
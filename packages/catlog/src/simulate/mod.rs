@@ -8,5 +8,14 @@
 //! Julia. If this code does stick around it should eventually become its own crate.
 //! For now it's convenient to keep everything in the same place.
 
+#[cfg(feature = "ode")]
+pub mod bifurcation;
+
 #[cfg(feature = "ode")]
 pub mod ode;
+
+#[cfg(feature = "stochastic")]
+pub mod ssa;
+
+pub mod goodness_of_fit;
+pub mod time_series;
@@ -0,0 +1,243 @@
+//! Linear conservation laws of polynomial ODE systems.
+//!
+//! A linear combination of the variables of a polynomial system is conserved
+//! when its derivative vanishes identically, which happens exactly when the
+//! coefficient vector annihilates the matrix of coefficients of the system,
+//! having a row for each monomial and a column for each variable. For a
+//! mass-action system, this matrix is the stoichiometry matrix scaled by the
+//! rates, so the conservation laws are the familiar ones of closed reaction
+//! networks, such as the total population of a compartmental model.
+//!
+//! Each independent conservation law determines one variable in terms of the
+//! others and of the conserved total, fixed by the initial values. A [reduced
+//! system](ReducedSystem) integrates only the remaining variables, so that the
+//! conserved quantities are preserved exactly rather than up to the error of
+//! the solver.
+
+use nalgebra::DVector;
+
+use super::{NumericalPolynomialSystem, ODEProblem, ODESystem};
+
+/// Tolerance for treating an entry of the coefficient matrix as zero, relative
+/// to the largest entry.
+const RELATIVE_TOLERANCE: f64 = 1e-6;
+
+/// A linear conservation law of an ODE system.
+///
+/// The conserved quantity is the eliminated variable plus the linear
+/// combination of the other variables given by the coefficients.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConservationLaw {
+    /// Index of the variable eliminated using the law.
+    pub variable: usize,
+
+    /// Nonzero coefficients of the other variables in the conserved quantity.
+    pub coefficients: Vec<(usize, f32)>,
+}
+
+impl ConservationLaw {
+    /// Evaluates the conserved quantity at a state.
+    pub fn eval(&self, x: &DVector<f32>) -> f32 {
+        x[self.variable] + self.coefficients.iter().map(|(j, c)| c * x[*j]).sum::<f32>()
+    }
+}
+
+impl<Exp> NumericalPolynomialSystem<Exp>
+where
+    Exp: Ord,
+{
+    /// Finds a basis of the linear conservation laws of the system.
+    ///
+    /// The laws are computed from the reduced row echelon form of the
+    /// coefficient matrix. Their eliminated variables are distinct and do not
+    /// appear in the coefficients of any law, so the variables can be
+    /// eliminated all at once. Later variables are eliminated in preference to
+    /// earlier ones.
+    pub fn conservation_laws(&self) -> Vec<ConservationLaw> {
+        let n = self.components.len();
+        let mut monomials = Vec::new();
+        let mut rows: Vec<Vec<f64>> = Vec::new();
+        for (i, component) in self.components.iter().enumerate() {
+            for (coef, monomial) in component.terms() {
+                let k = monomials.iter().position(|m| *m == monomial).unwrap_or_else(|| {
+                    monomials.push(monomial);
+                    rows.push(vec![0.0; n]);
+                    monomials.len() - 1
+                });
+                rows[k][i] += f64::from(*coef);
+            }
+        }
+
+        let scale = rows.iter().flatten().fold(0.0, |max: f64, a| max.max(a.abs()));
+        let tolerance = RELATIVE_TOLERANCE * scale;
+        let mut pivots = Vec::new();
+        for col in 0..n {
+            let r = pivots.len();
+            let Some(best) =
+                (r..rows.len()).max_by(|&i, &j| rows[i][col].abs().total_cmp(&rows[j][col].abs()))
+            else {
+                break;
+            };
+            if rows[best][col].abs() <= tolerance {
+                continue;
+            }
+            rows.swap(r, best);
+            let pivot = rows[r][col];
+            let pivot_row: Vec<f64> = rows[r].iter().map(|a| a / pivot).collect();
+            for (i, row) in rows.iter_mut().enumerate() {
+                let factor = row[col];
+                if i != r && factor != 0.0 {
+                    row.iter_mut().zip(&pivot_row).for_each(|(a, p)| *a -= factor * p);
+                }
+            }
+            rows[r] = pivot_row;
+            pivots.push(col);
+        }
+
+        (0..n)
+            .filter(|col| !pivots.contains(col))
+            .map(|free| ConservationLaw {
+                variable: free,
+                coefficients: pivots
+                    .iter()
+                    .zip(&rows)
+                    .filter(|(_, row)| row[free].abs() > RELATIVE_TOLERANCE)
+                    .map(|(&j, row)| (j, -row[free] as f32))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// An ODE system reduced by eliminating variables using conservation laws.
+///
+/// The state of the reduced system comprises the variables kept, in order. The
+/// eliminated variables are recovered from the conserved totals.
+#[derive(Clone, Debug)]
+pub struct ReducedSystem<Sys> {
+    system: Sys,
+    laws: Vec<ConservationLaw>,
+    totals: Vec<f32>,
+    kept: Vec<usize>,
+}
+
+impl<Sys> ReducedSystem<Sys> {
+    /// Reduces a system by conservation laws, with totals taken from the
+    /// given initial values of the full system.
+    ///
+    /// The laws should be as returned by
+    /// [`conservation_laws`](NumericalPolynomialSystem::conservation_laws).
+    pub fn new(system: Sys, laws: Vec<ConservationLaw>, initial_values: &DVector<f32>) -> Self {
+        let totals = laws.iter().map(|law| law.eval(initial_values)).collect();
+        let kept = (0..initial_values.len())
+            .filter(|i| laws.iter().all(|law| law.variable != *i))
+            .collect();
+        Self { system, laws, totals, kept }
+    }
+
+    /// Gets the conservation laws used to eliminate variables.
+    pub fn laws(&self) -> &[ConservationLaw] {
+        &self.laws
+    }
+
+    /// Gets the conserved totals, in the same order as the laws.
+    pub fn totals(&self) -> &[f32] {
+        &self.totals
+    }
+
+    /// Gets the indices of the variables kept in the reduced system.
+    pub fn kept_variables(&self) -> &[usize] {
+        &self.kept
+    }
+
+    /// Restricts a state of the full system to the reduced system.
+    pub fn reduce(&self, x: &DVector<f32>) -> DVector<f32> {
+        DVector::from_iterator(self.kept.len(), self.kept.iter().map(|i| x[*i]))
+    }
+
+    /// Extends a state of the reduced system to the full system.
+    pub fn expand(&self, y: &DVector<f32>) -> DVector<f32> {
+        let mut x = DVector::zeros(self.kept.len() + self.laws.len());
+        for (k, i) in self.kept.iter().enumerate() {
+            x[*i] = y[k];
+        }
+        for (law, total) in self.laws.iter().zip(&self.totals) {
+            let rest: f32 = law.coefficients.iter().map(|(j, c)| c * x[*j]).sum();
+            x[law.variable] = total - rest;
+        }
+        x
+    }
+}
+
+impl<Sys: ODESystem> ODESystem for ReducedSystem<Sys> {
+    fn vector_field(&self, dy: &mut DVector<f32>, y: &DVector<f32>, t: f32) {
+        let dx = self.system.eval_vector_field(&self.expand(y), t);
+        for (k, i) in self.kept.iter().enumerate() {
+            dy[k] = dx[*i];
+        }
+    }
+}
+
+impl<Exp> ODEProblem<NumericalPolynomialSystem<Exp>>
+where
+    Exp: Ord,
+{
+    /// Reduces the problem by the conservation laws of its system.
+    pub fn reduce_by_conservation_laws(
+        self,
+    ) -> ODEProblem<ReducedSystem<NumericalPolynomialSystem<Exp>>> {
+        let laws = self.system.conservation_laws();
+        let system = ReducedSystem::new(self.system, laws, &self.initial_values);
+        ODEProblem {
+            initial_values: system.reduce(&self.initial_values),
+            system,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            rtol: self.rtol,
+            atol: self.atol,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::PolynomialSystem;
+    use super::*;
+    use crate::zero::alg::Polynomial;
+
+    type Parameter<Id> = Polynomial<Id, f32, u8>;
+
+    /// The SIR model with infection rate 2 and recovery rate 1.
+    fn sir() -> NumericalPolynomialSystem<u8> {
+        let param = |c: char| Parameter::<_>::generator(c);
+        let var = |c: char| Polynomial::<_, Parameter<_>, u8>::generator(c);
+        let terms = [
+            ('S', -var('S') * var('I') * param('β')),
+            ('I', var('S') * var('I') * param('β')),
+            ('I', -var('I') * param('γ')),
+            ('R', var('I') * param('γ')),
+        ];
+        let sys: PolynomialSystem<_, _, _> = terms.into_iter().collect();
+        let sys = sys.extend_scalars(|p| p.eval(|c| if *c == 'β' { 2.0 } else { 1.0 }));
+        sys.to_numerical()
+    }
+
+    #[test]
+    fn sir_conservation() {
+        let laws = sir().conservation_laws();
+        assert_eq!(laws.len(), 1);
+        assert_eq!(laws[0].variable, 2);
+        assert_eq!(laws[0].coefficients, vec![(0, 1.0), (1, 1.0)]);
+
+        let x0 = DVector::from_column_slice(&[0.99, 0.01, 0.0]);
+        let problem = ODEProblem::new(sir(), x0).end_time(10.0).reduce_by_conservation_laws();
+        assert_eq!(problem.system.kept_variables(), &[0, 1]);
+        assert_eq!(problem.system.totals(), &[1.0]);
+
+        let result = problem.solve_dopri5(0.5).unwrap();
+        let (_, y_out) = result.get();
+        let x = problem.system.expand(y_out.last().unwrap());
+        assert!((x.sum() - 1.0).abs() < 1e-6);
+        assert!(x[2] > 0.5);
+    }
+}
@@ -0,0 +1,452 @@
+//! Delay differential equations.
+//!
+//! A delay differential equation (DDE) expresses the rate of change of a state
+//! in terms of the present state and the state at earlier times, as in
+//! `x'(t) = f(x(t), x(t - τ))`. Delays arise in models with incubation or
+//! maturation periods, which cannot be faithfully modeled by ODEs. We consider
+//! only constant delays and constant initial histories: before the start time,
+//! the state is held at its initial values.
+//!
+//! DDEs are solved by the *method of steps*. On any time interval shorter than
+//! the smallest delay, the delayed states are already known from the solution
+//! on earlier intervals, so the DDE reduces to an ODE that is integrated by a
+//! Runge-Kutta method. Delayed states between the computed time points are
+//! obtained by cubic Hermite interpolation, which matches the accuracy of the
+//! integrator.
+
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::ops::Add;
+
+use indexmap::{IndexMap, IndexSet};
+use nalgebra::DVector;
+use num_traits::{One, Pow};
+use ode_solvers::dop_shared::SolverResult;
+
+use crate::zero::alg::Polynomial;
+
+/// A system of delay differential equations with constant delays.
+pub trait DDESystem {
+    /// Delays occurring in the system, which should be positive.
+    fn delays(&self) -> &[f32];
+
+    /// Computes the vector field in place.
+    ///
+    /// The vector field is evaluated at the time `t` and the state `x`, given
+    /// the states `x_lagged[k]` at the times `t - delays()[k]`.
+    fn vector_field(
+        &self,
+        dx: &mut DVector<f32>,
+        x: &DVector<f32>,
+        x_lagged: &[DVector<f32>],
+        t: f32,
+    );
+}
+
+/// A DDE problem ready to be solved.
+///
+/// Like an [ODE problem](super::ODEProblem), a DDE problem comprises a [DDE
+/// system](DDESystem) plus its initial values and time span. The initial values
+/// also serve as the history of the system before the start time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DDEProblem<Sys> {
+    pub(crate) system: Sys,
+    pub(crate) initial_values: DVector<f32>,
+    pub(crate) start_time: f32,
+    pub(crate) end_time: f32,
+}
+
+impl<Sys> DDEProblem<Sys> {
+    /// Creates a new DDE problem.
+    pub fn new(system: Sys, initial_values: DVector<f32>) -> Self {
+        DDEProblem {
+            system,
+            initial_values,
+            start_time: 0.0,
+            end_time: 0.0,
+        }
+    }
+
+    /// Sets the start time for the problem.
+    pub fn start_time(mut self, t: f32) -> Self {
+        self.start_time = t;
+        self
+    }
+
+    /// Sets the end time for the problem.
+    pub fn end_time(mut self, t: f32) -> Self {
+        self.end_time = t;
+        self
+    }
+
+    /// Sets the time span (start and end time) for the problem.
+    pub fn time_span(mut self, tspan: (f32, f32)) -> Self {
+        (self.start_time, self.end_time) = tspan;
+        self
+    }
+}
+
+impl<Sys> DDEProblem<Sys>
+where
+    Sys: DDESystem,
+{
+    /// Solves the DDE system by the method of steps, using the Runge-Kutta
+    /// method with fixed step size.
+    ///
+    /// The step size is reduced as needed to be at most the smallest delay and
+    /// to evenly divide the time span. Panics if the step size or any delay is
+    /// not positive.
+    pub fn solve_rk4(&self, step_size: f32) -> SolverResult<f32, DVector<f32>> {
+        let min_delay = self.system.delays().iter().copied().fold(f32::INFINITY, f32::min);
+        assert!(min_delay > 0.0, "Delays should be positive");
+        assert!(step_size > 0.0, "Step size should be positive");
+
+        let span = self.end_time - self.start_time;
+        let n_steps = (span / step_size.min(min_delay)).ceil().max(0.0) as usize;
+        let h = if n_steps > 0 {
+            span / n_steps as f32
+        } else {
+            0.0
+        };
+
+        let mut history = History {
+            initial_values: self.initial_values.clone(),
+            times: Vec::with_capacity(n_steps + 1),
+            states: Vec::with_capacity(n_steps + 1),
+            derivatives: Vec::with_capacity(n_steps + 1),
+        };
+        let (mut t, mut x) = (self.start_time, self.initial_values.clone());
+        let mut dx = self.eval(&history, &x, t);
+        history.push(t, x.clone(), dx.clone());
+
+        for i in 1..=n_steps {
+            // Each stage depends only on states at least one delay in the past,
+            // which lie on earlier steps since the step size is at most the
+            // smallest delay.
+            let k1 = dx;
+            let x2 = &x + &k1 * (h / 2.0);
+            let k2 = self.eval(&history, &x2, t + h / 2.0);
+            let x3 = &x + &k2 * (h / 2.0);
+            let k3 = self.eval(&history, &x3, t + h / 2.0);
+            let x4 = &x + &k3 * h;
+            let k4 = self.eval(&history, &x4, t + h);
+            x += (k1 + k2 * 2.0 + k3 * 2.0 + k4) * (h / 6.0);
+            t = self.start_time + i as f32 * h;
+            dx = self.eval(&history, &x, t);
+            history.push(t, x.clone(), dx.clone());
+        }
+        SolverResult::new(history.times, history.states)
+    }
+
+    fn eval(&self, history: &History, x: &DVector<f32>, t: f32) -> DVector<f32> {
+        let x_lagged: Vec<_> =
+            self.system.delays().iter().map(|delay| history.state_at(t - delay)).collect();
+        let mut dx = DVector::from_element(x.len(), 0.0f32);
+        self.system.vector_field(&mut dx, x, &x_lagged, t);
+        dx
+    }
+}
+
+/// The solution of a DDE computed so far, with derivatives for interpolation.
+struct History {
+    initial_values: DVector<f32>,
+    times: Vec<f32>,
+    states: Vec<DVector<f32>>,
+    derivatives: Vec<DVector<f32>>,
+}
+
+impl History {
+    fn push(&mut self, t: f32, x: DVector<f32>, dx: DVector<f32>) {
+        self.times.push(t);
+        self.states.push(x);
+        self.derivatives.push(dx);
+    }
+
+    /// Gets the state at a time no later than the last computed time.
+    fn state_at(&self, t: f32) -> DVector<f32> {
+        let i = self.times.partition_point(|&s| s <= t);
+        if i == 0 {
+            return self.initial_values.clone();
+        }
+        if i == self.times.len() {
+            return self.states[i - 1].clone();
+        }
+        // Cubic Hermite interpolation between the neighboring time points.
+        let (t0, t1) = (self.times[i - 1], self.times[i]);
+        let h = t1 - t0;
+        let s = (t - t0) / h;
+        let (s2, s3) = (s * s, s * s * s);
+        let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+        let h10 = s3 - 2.0 * s2 + s;
+        let h01 = -2.0 * s3 + 3.0 * s2;
+        let h11 = s3 - s2;
+        &self.states[i - 1] * h00
+            + &self.derivatives[i - 1] * (h10 * h)
+            + &self.states[i] * h01
+            + &self.derivatives[i] * (h11 * h)
+    }
+}
+
+/// A variable of a DDE system, evaluated at a lag behind the present time.
+///
+/// A lag of zero refers to the present state, `x(t)`, while a positive lag `τ`
+/// refers to the delayed state `x(t - τ)`.
+#[derive(Clone, Copy, Debug)]
+pub struct Lagged<Var> {
+    /// The state variable.
+    pub var: Var,
+
+    /// The lag, nonnegative.
+    pub lag: f32,
+}
+
+impl<Var> Lagged<Var> {
+    /// The variable at the present time.
+    pub fn now(var: Var) -> Self {
+        Self { var, lag: 0.0 }
+    }
+
+    /// The variable at the given delay.
+    pub fn delayed(var: Var, lag: f32) -> Self {
+        Self { var, lag }
+    }
+}
+
+impl<Var: PartialEq> PartialEq for Lagged<Var> {
+    fn eq(&self, other: &Self) -> bool {
+        self.var == other.var && self.lag.total_cmp(&other.lag) == Ordering::Equal
+    }
+}
+
+impl<Var: Eq> Eq for Lagged<Var> {}
+
+impl<Var: Ord> PartialOrd for Lagged<Var> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Var: Ord> Ord for Lagged<Var> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.var.cmp(&other.var).then_with(|| self.lag.total_cmp(&other.lag))
+    }
+}
+
+impl<Var: Hash> Hash for Lagged<Var> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.var.hash(state);
+        self.lag.to_bits().hash(state);
+    }
+}
+
+impl<Var: Display> Display for Lagged<Var> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.lag == 0.0 {
+            write!(f, "{}", self.var)
+        } else {
+            write!(f, "{}(t - {})", self.var, self.lag)
+        }
+    }
+}
+
+/// A system of polynomial delay differential equations.
+///
+/// The right-hand sides are polynomials in the state variables, each evaluated
+/// at the present time or at a delay.
+#[derive(Clone, Debug)]
+pub struct PolynomialDDESystem<Var, Exp> {
+    /// Components of the vector field.
+    pub components: IndexMap<Var, Polynomial<Lagged<Var>, f32, Exp>>,
+}
+
+impl<Var, Exp> Default for PolynomialDDESystem<Var, Exp> {
+    fn default() -> Self {
+        Self { components: IndexMap::new() }
+    }
+}
+
+impl<Var, Exp> PolynomialDDESystem<Var, Exp>
+where
+    Var: Clone + Hash + Ord,
+    Exp: Clone + Ord + Add<Output = Exp>,
+{
+    /// Constructs a new polynomial DDE system, with no equations.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a new term to the system.
+    pub fn add_term(&mut self, var: Var, term: Polynomial<Lagged<Var>, f32, Exp>) {
+        if let Some(component) = self.components.get_mut(&var) {
+            *component = std::mem::take(component) + term;
+        } else {
+            self.components.insert(var, term);
+        }
+    }
+
+    /// Converts the polynomial system to a numerical one.
+    ///
+    /// The order of the components in the new system is given by the order of
+    /// the variables in the old one, and the delays are sorted.
+    pub fn to_numerical(&self) -> NumericalPolynomialDDESystem<Exp> {
+        let indices: IndexMap<Var, usize> =
+            self.components.keys().enumerate().map(|(i, var)| (var.clone(), i)).collect();
+        let mut lags: Vec<_> = self
+            .components
+            .values()
+            .flat_map(|poly| poly.monomials().flat_map(|m| m.variables()))
+            .map(|v| v.lag)
+            .filter(|&lag| lag != 0.0)
+            .collect();
+        lags.sort_by(f32::total_cmp);
+        let lags: IndexSet<_> = lags.into_iter().map(f32::to_bits).collect();
+        let components = self
+            .components
+            .values()
+            .map(|poly| {
+                poly.map_variables(|v| {
+                    let i = *indices.get(&v.var).expect("Variable should have an equation");
+                    let k = if v.lag == 0.0 {
+                        0
+                    } else {
+                        lags.get_index_of(&v.lag.to_bits()).unwrap() + 1
+                    };
+                    (i, k)
+                })
+            })
+            .collect();
+        NumericalPolynomialDDESystem {
+            delays: lags.into_iter().map(f32::from_bits).collect(),
+            components,
+        }
+    }
+}
+
+impl<Var, Exp> Display for PolynomialDDESystem<Var, Exp>
+where
+    Var: Display,
+    Polynomial<Lagged<Var>, f32, Exp>: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (var, component) in self.components.iter() {
+            writeln!(f, "d{var} = {component}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A numerical system of polynomial delay differential equations.
+///
+/// The variables are pairs of indices `(i, k)`, referring to the `i`th state
+/// variable at the present time if `k` is zero and otherwise at the `k`th
+/// delay, counting from one.
+pub struct NumericalPolynomialDDESystem<Exp> {
+    /// Delays of the system, in increasing order.
+    pub delays: Vec<f32>,
+
+    /// Components of the vector field.
+    pub components: Vec<Polynomial<(usize, usize), f32, Exp>>,
+}
+
+impl<Exp> DDESystem for NumericalPolynomialDDESystem<Exp>
+where
+    Exp: Clone + Ord,
+    f32: Pow<Exp, Output = f32>,
+{
+    fn delays(&self) -> &[f32] {
+        &self.delays
+    }
+
+    fn vector_field(
+        &self,
+        dx: &mut DVector<f32>,
+        x: &DVector<f32>,
+        x_lagged: &[DVector<f32>],
+        _t: f32,
+    ) {
+        for i in 0..dx.len() {
+            dx[i] =
+                self.components[i].eval(|&(j, k)| if k == 0 { x[j] } else { x_lagged[k - 1][j] })
+        }
+    }
+}
+
+impl<Exp> Display for NumericalPolynomialDDESystem<Exp>
+where
+    Exp: Clone + Ord + Add<Output = Exp> + One + Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let var_name = |&(i, k): &(usize, usize)| {
+            if k == 0 {
+                format!("x{i}")
+            } else {
+                format!("x{i}(t - {})", self.delays[k - 1])
+            }
+        };
+        for (i, component) in self.components.iter().enumerate() {
+            let component = component.map_variables(var_name);
+            writeln!(f, "dx{i} = {component}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    type Poly = Polynomial<Lagged<char>, f32, u8>;
+
+    #[test]
+    fn delayed_decay() {
+        // The DDE `x'(t) = -x(t - 1)` with history `x = 1` has the exact
+        // solution `x(t) = 1 - t + (t - 1)^2 / 2` for `1 <= t <= 2`.
+        let mut sys = PolynomialDDESystem::new();
+        sys.add_term('x', -Poly::generator(Lagged::delayed('x', 1.0)));
+        let expected = expect![[r#"
+            dx = -x(t - 1)
+        "#]];
+        expected.assert_eq(&sys.to_string());
+
+        let problem =
+            DDEProblem::new(sys.to_numerical(), DVector::from_element(1, 1.0)).end_time(2.0);
+        let result = problem.solve_rk4(0.05);
+        let (t_out, x_out) = result.get();
+        assert_eq!(t_out.len(), 41);
+        assert!((t_out.last().unwrap() - 2.0).abs() < 1e-5);
+        assert!((x_out[20][0] - 0.0).abs() < 1e-4);
+        assert!((x_out[40][0] + 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn sir_with_incubation() {
+        // Susceptibles become infectious after an incubation period of 2.
+        let var = |c| Poly::generator(Lagged::now(c));
+        let lagged = |c| Poly::generator(Lagged::delayed(c, 2.0));
+        let terms = [
+            ('S', -var('S') * var('I')),
+            ('E', var('S') * var('I')),
+            ('E', -lagged('S') * lagged('I')),
+            ('I', lagged('S') * lagged('I')),
+            ('I', -var('I') * 0.5),
+            ('R', var('I') * 0.5),
+        ];
+        let mut sys = PolynomialDDESystem::new();
+        for (v, term) in terms {
+            sys.add_term(v, term);
+        }
+        let sys = sys.to_numerical();
+        assert_eq!(sys.delays, vec![2.0]);
+
+        let initial = DVector::from_column_slice(&[0.9, 0.0, 0.1, 0.0]);
+        let problem = DDEProblem::new(sys, initial).end_time(20.0);
+        let result = problem.solve_rk4(0.1);
+        let (_, x_out) = result.get();
+        // The total population is conserved and the recovered only increase.
+        assert!(x_out.iter().all(|x| (x.sum() - 1.0).abs() < 1e-3));
+        assert!(x_out.windows(2).all(|w| w[1][3] >= w[0][3]));
+    }
+}
@@ -0,0 +1,261 @@
+//! Equilibria of ODE systems and their stability.
+//!
+//! Equilibria, or fixed points, of an [ODE system](ODESystem) are found by
+//! Newton's method, started from each of several initial guesses since a
+//! nonlinear system can have many equilibria. The stability of each
+//! equilibrium is classified by the eigenvalues of the Jacobian there, which is
+//! approximated by finite differences.
+
+use nalgebra::{Complex, DMatrix, DVector};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use super::ODESystem;
+
+/// Stability of an equilibrium, as determined by linearization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum Stability {
+    /// All eigenvalues have negative real part, so nearby states converge to
+    /// the equilibrium.
+    Stable,
+
+    /// All eigenvalues with nonzero real part have positive real part, so
+    /// nearby states diverge from the equilibrium.
+    Unstable,
+
+    /// Some eigenvalues have positive real part and others negative real part.
+    Saddle,
+
+    /// Some eigenvalues have zero real part and none have positive real part,
+    /// so linearization does not determine stability. Centers, such as the
+    /// interior equilibrium of the Lotka-Volterra predator-prey model, are of
+    /// this kind.
+    Marginal,
+}
+
+/// An equilibrium of an ODE system.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Equilibrium {
+    /// State of the system at equilibrium.
+    pub state: DVector<f32>,
+
+    /// Eigenvalues of the Jacobian of the vector field at the equilibrium.
+    pub eigenvalues: Vec<Complex<f32>>,
+
+    /// Stability of the equilibrium.
+    pub stability: Stability,
+}
+
+/// Solver for equilibria of ODE systems.
+///
+/// The vector field is evaluated at a fixed time, so for non-autonomous
+/// systems the result is the equilibria at that instant.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EquilibriumSolver {
+    time: f32,
+    tolerance: f32,
+    max_iterations: usize,
+}
+
+impl Default for EquilibriumSolver {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            tolerance: 1e-4,
+            max_iterations: 100,
+        }
+    }
+}
+
+impl EquilibriumSolver {
+    /// Creates a solver with default settings.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the time at which to evaluate the vector field.
+    pub fn time(mut self, t: f32) -> Self {
+        self.time = t;
+        self
+    }
+
+    /// Sets the tolerance, on both the norm of the vector field at an
+    /// equilibrium and the real parts of eigenvalues considered to be zero.
+    pub fn tolerance(mut self, tol: f32) -> Self {
+        self.tolerance = tol;
+        self
+    }
+
+    /// Sets the maximum number of Newton iterations from each initial guess.
+    pub fn max_iterations(mut self, n: usize) -> Self {
+        self.max_iterations = n;
+        self
+    }
+
+    /// Finds equilibria of the system by Newton's method from each initial
+    /// guess.
+    ///
+    /// Guesses from which Newton's method fails to converge are skipped, and
+    /// equilibria found more than once are reported once, in the order that
+    /// they are first found.
+    pub fn equilibria<Sys: ODESystem>(
+        &self,
+        system: &Sys,
+        guesses: impl IntoIterator<Item = DVector<f32>>,
+    ) -> Vec<Equilibrium> {
+        let mut result: Vec<Equilibrium> = Vec::new();
+        for guess in guesses {
+            let Some(state) = self.newton(system, guess) else {
+                continue;
+            };
+            if result.iter().any(|eq| self.same_state(&eq.state, &state)) {
+                continue;
+            }
+            result.push(self.classify(system, state));
+        }
+        result
+    }
+
+    /// Classifies the stability of an equilibrium of the system.
+    pub fn classify<Sys: ODESystem>(&self, system: &Sys, state: DVector<f32>) -> Equilibrium {
+        let jacobian = numerical_jacobian(system, &state, self.time);
+        self.classify_with_jacobian(state, &jacobian)
+    }
+
+    /// Classifies the stability of an equilibrium given the Jacobian there.
+    ///
+    /// Use this when the Jacobian is known exactly, such as for
+    /// [polynomial systems](super::NumericalPolynomialSystem::jacobian).
+    pub fn classify_with_jacobian(
+        &self,
+        state: DVector<f32>,
+        jacobian: &DMatrix<f32>,
+    ) -> Equilibrium {
+        let eigenvalues: Vec<_> = jacobian.complex_eigenvalues().iter().copied().collect();
+        let tol = self.tolerance.sqrt();
+        let any_positive = eigenvalues.iter().any(|z| z.re > tol);
+        let any_negative = eigenvalues.iter().any(|z| z.re < -tol);
+        let all_negative = eigenvalues.iter().all(|z| z.re < -tol);
+        let stability = match (any_positive, any_negative) {
+            (true, true) => Stability::Saddle,
+            (true, false) => Stability::Unstable,
+            (false, _) if all_negative => Stability::Stable,
+            (false, _) => Stability::Marginal,
+        };
+        Equilibrium { state, eigenvalues, stability }
+    }
+
+    /// Whether two states are the same equilibrium, up to the tolerance.
+    pub(crate) fn same_state(&self, x: &DVector<f32>, y: &DVector<f32>) -> bool {
+        (x - y).norm() < 10.0 * self.tolerance * (1.0 + x.norm())
+    }
+
+    /// Runs damped Newton iteration from an initial guess.
+    fn newton<Sys: ODESystem>(&self, system: &Sys, mut x: DVector<f32>) -> Option<DVector<f32>> {
+        let mut f = system.eval_vector_field(&x, self.time);
+        for _ in 0..self.max_iterations {
+            if f.norm() < self.tolerance {
+                return Some(x);
+            }
+            let jacobian = numerical_jacobian(system, &x, self.time);
+            let step = jacobian.lu().solve(&f)?;
+
+            // Halve the step until the residual decreases, to avoid overshooting.
+            let mut alpha = 1.0;
+            loop {
+                let x_new = &x - &step * alpha;
+                let f_new = system.eval_vector_field(&x_new, self.time);
+                if f_new.norm() < f.norm() || alpha < 1e-3 {
+                    (x, f) = (x_new, f_new);
+                    break;
+                }
+                alpha /= 2.0;
+            }
+            if !x.iter().all(|xi| xi.is_finite()) {
+                return None;
+            }
+        }
+        (f.norm() < self.tolerance).then_some(x)
+    }
+}
+
+/// Finds equilibria of an ODE system using default settings.
+///
+/// See [`EquilibriumSolver::equilibria`] for details.
+pub fn equilibria<Sys: ODESystem>(
+    system: &Sys,
+    guesses: impl IntoIterator<Item = DVector<f32>>,
+) -> Vec<Equilibrium> {
+    EquilibriumSolver::new().equilibria(system, guesses)
+}
+
+/// Approximates the Jacobian of the vector field by central differences.
+pub fn numerical_jacobian<Sys: ODESystem>(system: &Sys, x: &DVector<f32>, t: f32) -> DMatrix<f32> {
+    let n = x.len();
+    let mut jacobian = DMatrix::zeros(n, n);
+    let mut x_step = x.clone();
+    for j in 0..n {
+        // Step size balancing truncation and rounding error in single precision.
+        let h = 1e-2 * x[j].abs().max(1.0);
+        x_step[j] = x[j] + h;
+        let f_plus = system.eval_vector_field(&x_step, t);
+        x_step[j] = x[j] - h;
+        let f_minus = system.eval_vector_field(&x_step, t);
+        x_step[j] = x[j];
+        jacobian.set_column(j, &((f_plus - f_minus) / (2.0 * h)));
+    }
+    jacobian
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Predator-prey model, `x' = x(2 - y)`, `y' = y(x - 1)`.
+    struct PredatorPrey;
+
+    impl ODESystem for PredatorPrey {
+        fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+            dx[0] = x[0] * (2.0 - x[1]);
+            dx[1] = x[1] * (x[0] - 1.0);
+        }
+    }
+
+    /// Logistic growth, `x' = x(1 - x)`.
+    struct Logistic;
+
+    impl ODESystem for Logistic {
+        fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+            dx[0] = x[0] * (1.0 - x[0]);
+        }
+    }
+
+    #[test]
+    fn predator_prey() {
+        let guesses = [(0.1, 0.2), (0.8, 1.5), (1.2, 2.5), (0.0, 0.0)];
+        let eqs = equilibria(&PredatorPrey, guesses.map(|(x, y)| DVector::from_vec(vec![x, y])));
+        assert_eq!(eqs.len(), 2);
+        assert!(eqs[0].state.norm() < 1e-3);
+        assert_eq!(eqs[0].stability, Stability::Saddle);
+        assert!((&eqs[1].state - DVector::from_vec(vec![1.0, 2.0])).norm() < 1e-3);
+        assert_eq!(eqs[1].stability, Stability::Marginal);
+        // The interior equilibrium is a center, with eigenvalues `±i√2`.
+        for z in &eqs[1].eigenvalues {
+            assert!(z.re.abs() < 1e-2 && (z.im.abs() - 2f32.sqrt()).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn logistic() {
+        let guesses = [-0.2, 0.3, 2.0].map(|x| DVector::from_element(1, x));
+        let eqs = equilibria(&Logistic, guesses);
+        let summary: Vec<_> = eqs.iter().map(|eq| (eq.state[0].round(), eq.stability)).collect();
+        assert_eq!(summary, vec![(0.0, Stability::Unstable), (1.0, Stability::Stable)]);
+    }
+}
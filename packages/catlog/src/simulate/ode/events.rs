@@ -0,0 +1,258 @@
+//! Events during the integration of ODE systems.
+//!
+//! An [event](Event) happens when a function of the state and time, its
+//! *condition*, crosses zero. Events can stop the integration, say when a
+//! population dies out, or reset the state, say when a dose of a drug is
+//! administered. They are detected by checking for sign changes of the
+//! conditions between output steps of the solver and located by bisection, so
+//! an event whose condition crosses zero and back within a single output step
+//! is missed.
+
+use nalgebra::DVector;
+use ode_solvers::dop_shared::{IntegrationError, SolverResult};
+
+use super::{ODEProblem, ODESystem, SolveResult};
+
+/// Maximum number of bisections used to locate an event.
+const MAX_BISECTIONS: usize = 50;
+
+/// Direction in which the condition of an event crosses zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CrossingDirection {
+    /// From negative to nonnegative.
+    Rising,
+
+    /// From positive to nonpositive.
+    Falling,
+
+    /// In either direction.
+    #[default]
+    Either,
+}
+
+impl CrossingDirection {
+    /// Whether the condition crosses zero in this direction between two values.
+    fn crosses(self, before: f32, after: f32) -> bool {
+        let rising = before < 0.0 && after >= 0.0;
+        let falling = before > 0.0 && after <= 0.0;
+        match self {
+            CrossingDirection::Rising => rising,
+            CrossingDirection::Falling => falling,
+            CrossingDirection::Either => rising || falling,
+        }
+    }
+}
+
+/// What happens when an event occurs.
+pub enum EventAction<'a> {
+    /// Stop the integration.
+    Stop,
+
+    /// Reset the state, given the time of the event, and continue.
+    Reset(Box<dyn Fn(&mut DVector<f32>, f32) + 'a>),
+}
+
+/// An event during the integration of an ODE system.
+pub struct Event<'a> {
+    condition: Box<dyn Fn(&DVector<f32>, f32) -> f32 + 'a>,
+    direction: CrossingDirection,
+    action: EventAction<'a>,
+}
+
+impl<'a> Event<'a> {
+    /// Creates an event stopping the integration when the condition crosses zero.
+    pub fn stop_when(condition: impl Fn(&DVector<f32>, f32) -> f32 + 'a) -> Self {
+        Self {
+            condition: Box::new(condition),
+            direction: CrossingDirection::default(),
+            action: EventAction::Stop,
+        }
+    }
+
+    /// Creates an event resetting the state when the condition crosses zero.
+    pub fn reset_when(
+        condition: impl Fn(&DVector<f32>, f32) -> f32 + 'a,
+        reset: impl Fn(&mut DVector<f32>, f32) + 'a,
+    ) -> Self {
+        Self {
+            condition: Box::new(condition),
+            direction: CrossingDirection::default(),
+            action: EventAction::Reset(Box::new(reset)),
+        }
+    }
+
+    /// Sets the direction of zero crossings that trigger the event.
+    pub fn direction(mut self, direction: CrossingDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    fn eval(&self, x: &DVector<f32>, t: f32) -> f32 {
+        (self.condition)(x, t)
+    }
+}
+
+/// An occurrence of an event during integration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EventOccurrence {
+    /// Index of the event in the list of events given to the solver.
+    pub event: usize,
+
+    /// Time at which the event occurred.
+    pub time: f32,
+}
+
+/// Result of solving an ODE problem with events.
+#[derive(Clone, Debug)]
+pub struct EventSolverResult {
+    /// Times and states output by the solver.
+    ///
+    /// When an event occurs, the state at the time of the event is output and,
+    /// if the state is reset, the new state is output at the same time.
+    pub result: SolverResult<f32, DVector<f32>>,
+
+    /// Events that occurred, in order of time.
+    pub events: Vec<EventOccurrence>,
+
+    /// Whether the integration was stopped by an event before the end time.
+    pub stopped: bool,
+}
+
+impl<Sys> ODEProblem<Sys>
+where
+    Sys: ODESystem,
+{
+    /// Solves the ODE system with events using the Dormand-Prince method.
+    ///
+    /// The conditions of the events are checked at every output step, so the
+    /// step size should be small compared to the time scale of the events. If
+    /// several events occur in the same step, only the earliest is applied.
+    pub fn solve_dopri5_with_events(
+        &self,
+        output_step_size: f32,
+        events: &[Event],
+    ) -> Result<EventSolverResult, IntegrationError> {
+        assert!(output_step_size > 0.0, "Output step size should be positive");
+        let (mut t, mut x) = (self.start_time, self.initial_values.clone());
+        let (mut times, mut states) = (vec![t], vec![x.clone()]);
+        let mut occurrences = Vec::new();
+        while t < self.end_time {
+            let next = (t + output_step_size).min(self.end_time);
+            let x_next = self.advance(x.clone(), t, next, dopri5)?;
+            let before: Vec<_> = events.iter().map(|event| event.eval(&x, t)).collect();
+            let triggered = |x_after: &DVector<f32>, t_after: f32| {
+                events
+                    .iter()
+                    .zip(&before)
+                    .any(|(event, g)| event.direction.crosses(*g, event.eval(x_after, t_after)))
+            };
+            if !triggered(&x_next, next) {
+                (t, x) = (next, x_next);
+                times.push(t);
+                states.push(x.clone());
+                continue;
+            }
+
+            // Locate the earliest event by bisection, integrating from the
+            // start of the step to keep errors from accumulating.
+            let (mut lo, mut hi, mut x_hi) = (t, next, x_next);
+            for _ in 0..MAX_BISECTIONS {
+                let mid = (lo + hi) / 2.0;
+                if mid <= lo || mid >= hi {
+                    break;
+                }
+                let x_mid = self.advance(x.clone(), t, mid, dopri5)?;
+                if triggered(&x_mid, mid) {
+                    (hi, x_hi) = (mid, x_mid);
+                } else {
+                    lo = mid;
+                }
+            }
+            let index = events
+                .iter()
+                .zip(&before)
+                .position(|(event, g)| event.direction.crosses(*g, event.eval(&x_hi, hi)))
+                .expect("Some event should be triggered");
+            occurrences.push(EventOccurrence { event: index, time: hi });
+            (t, x) = (hi, x_hi);
+            times.push(t);
+            states.push(x.clone());
+            match &events[index].action {
+                EventAction::Stop => {
+                    return Ok(EventSolverResult {
+                        result: SolverResult::new(times, states),
+                        events: occurrences,
+                        stopped: true,
+                    });
+                }
+                EventAction::Reset(reset) => {
+                    reset(&mut x, t);
+                    times.push(t);
+                    states.push(x.clone());
+                }
+            }
+        }
+        Ok(EventSolverResult {
+            result: SolverResult::new(times, states),
+            events: occurrences,
+            stopped: false,
+        })
+    }
+}
+
+/// Solves a problem with the Dormand-Prince method, with output at the end.
+fn dopri5<Sys: ODESystem>(problem: &ODEProblem<&Sys>) -> SolveResult {
+    problem.solve_dopri5(problem.end_time - problem.start_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exponential decay, `x' = -x`.
+    struct Decay;
+
+    impl ODESystem for Decay {
+        fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+            dx[0] = -x[0];
+        }
+    }
+
+    #[test]
+    fn stop_at_threshold() {
+        let problem = ODEProblem::new(Decay, DVector::from_element(1, 1.0))
+            .end_time(10.0)
+            .tolerances(1e-6, 1e-8);
+        let events = [Event::stop_when(|x, _| x[0] - 0.5).direction(CrossingDirection::Falling)];
+        let solution = problem.solve_dopri5_with_events(0.1, &events).unwrap();
+        assert!(solution.stopped);
+        assert_eq!(solution.events.len(), 1);
+        assert!((solution.events[0].time - 2f32.ln()).abs() < 1e-4);
+        let (t_out, x_out) = solution.result.get();
+        assert_eq!(*t_out.last().unwrap(), solution.events[0].time);
+        assert!((x_out.last().unwrap()[0] - 0.5).abs() < 1e-4);
+
+        // A rising crossing never happens.
+        let events = [Event::stop_when(|x, _| x[0] - 0.5).direction(CrossingDirection::Rising)];
+        let solution = problem.solve_dopri5_with_events(0.1, &events).unwrap();
+        assert!(!solution.stopped && solution.events.is_empty());
+    }
+
+    #[test]
+    fn periodic_dosing() {
+        // Redose to the initial level whenever the level falls to a quarter.
+        let problem = ODEProblem::new(Decay, DVector::from_element(1, 1.0))
+            .end_time(5.0)
+            .tolerances(1e-6, 1e-8);
+        let events = [Event::reset_when(|x, _| x[0] - 0.25, |x, _| x[0] = 1.0)];
+        let solution = problem.solve_dopri5_with_events(0.1, &events).unwrap();
+        assert!(!solution.stopped);
+        let times: Vec<_> = solution.events.iter().map(|e| e.time).collect();
+        assert_eq!(times.len(), 3);
+        for (t, k) in times.iter().zip(1..) {
+            assert!((t - k as f32 * 4f32.ln()).abs() < 1e-3);
+        }
+        let (t_out, _) = solution.result.get();
+        assert_eq!(*t_out.last().unwrap(), 5.0);
+    }
+}
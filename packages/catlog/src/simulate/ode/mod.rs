@@ -1,87 +1,113 @@
 //! Simulation of dynamical systems defined by ODEs.
 
-use nalgebra::DVector;
+use std::iter::{Product, Sum};
+
+use nalgebra::{DVector, RealField};
 use ode_solvers::{
     self,
-    dop_shared::{IntegrationError, SolverResult},
+    dop_shared::{FloatNumber, IntegrationError, SolverResult},
 };
 
 #[cfg(test)]
 use textplots::{Chart, Plot, Shape};
 
+/// A floating point type in which ODEs can be solved, either `f32` or `f64`.
+///
+/// Single precision is the default throughout. Double precision is slower but
+/// accumulates much less rounding error over long time spans.
+pub trait ODEScalar: FloatNumber + RealField + Sum + Product + From<f32> + Into<f64> {}
+
+impl<T> ODEScalar for T where T: FloatNumber + RealField + Sum + Product + From<f32> + Into<f64> {}
+
 /// A system of ordinary differential equations (ODEs).
 ///
-/// An ODE system is anything that can compute a vector field.
-pub trait ODESystem {
+/// An ODE system is anything that can compute a vector field. The scalars are
+/// single precision floats unless otherwise specified.
+pub trait ODESystem<T: ODEScalar = f32> {
     /// Compute the vector field at the given time and state in place.
-    fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, t: f32);
+    fn vector_field(&self, dx: &mut DVector<T>, x: &DVector<T>, t: T);
 
     /// Compute and return the vector field at the given time and state.
-    fn eval_vector_field(&self, x: &DVector<f32>, t: f32) -> DVector<f32> {
-        let mut dx = DVector::from_element(x.len(), 0.0f32);
+    fn eval_vector_field(&self, x: &DVector<T>, t: T) -> DVector<T> {
+        let mut dx = DVector::from_element(x.len(), T::zero());
         self.vector_field(&mut dx, x, t);
         dx
     }
 }
 
+impl<T: ODEScalar, Sys: ODESystem<T>> ODESystem<T> for &Sys {
+    fn vector_field(&self, dx: &mut DVector<T>, x: &DVector<T>, t: T) {
+        (*self).vector_field(dx, x, t)
+    }
+}
+
+/// Result of solving an ODE problem.
+type SolveResult<T = f32> = Result<SolverResult<T, DVector<T>>, IntegrationError>;
+
 /// An ODE problem ready to be solved.
 ///
 /// An ODE problem comprises an [ODE system](ODESystem) plus the extra information
 /// needed to solve the system, namely the initial values and the time span.
 #[derive(Clone, Debug, PartialEq)]
-pub struct ODEProblem<Sys> {
+pub struct ODEProblem<Sys, T = f32> {
     pub(crate) system: Sys,
-    pub(crate) initial_values: DVector<f32>,
-    pub(crate) start_time: f32,
-    pub(crate) end_time: f32,
-    rtol: f32,
-    atol: f32,
+    pub(crate) initial_values: DVector<T>,
+    pub(crate) start_time: T,
+    pub(crate) end_time: T,
+    rtol: T,
+    atol: T,
 }
 
-impl<Sys> ODEProblem<Sys> {
+impl<Sys, T: ODEScalar> ODEProblem<Sys, T> {
     /// Creates a new ODE problem.
-    pub fn new(system: Sys, initial_values: DVector<f32>) -> Self {
+    ///
+    /// The problem is solved in the precision of the initial values.
+    pub fn new(system: Sys, initial_values: DVector<T>) -> Self {
         ODEProblem {
             system,
             initial_values,
-            start_time: 0.0,
-            end_time: 0.0,
+            start_time: T::zero(),
+            end_time: T::zero(),
             // Same defaults as `scipy.integrate.RK45`.
-            rtol: 0.001,
-            atol: 1e-6,
+            rtol: nalgebra::convert::<f64, T>(1e-3),
+            atol: nalgebra::convert::<f64, T>(1e-6),
         }
     }
 
     /// Sets the start time for the problem.
-    pub fn start_time(mut self, t: f32) -> Self {
+    pub fn start_time(mut self, t: T) -> Self {
         self.start_time = t;
         self
     }
 
     /// Sets the end time for the problem.
-    pub fn end_time(mut self, t: f32) -> Self {
+    pub fn end_time(mut self, t: T) -> Self {
         self.end_time = t;
         self
     }
 
     /// Sets the time span (start and end time) for the problem.
-    pub fn time_span(mut self, tspan: (f32, f32)) -> Self {
+    pub fn time_span(mut self, tspan: (T, T)) -> Self {
         (self.start_time, self.end_time) = tspan;
         self
     }
+
+    /// Sets the relative and absolute error tolerances for adaptive solvers.
+    pub fn tolerances(mut self, rtol: T, atol: T) -> Self {
+        (self.rtol, self.atol) = (rtol, atol);
+        self
+    }
 }
 
-impl<Sys> ODEProblem<Sys>
+impl<Sys, T> ODEProblem<Sys, T>
 where
-    Sys: ODESystem,
+    Sys: ODESystem<T>,
+    T: ODEScalar,
 {
     /// Solves the ODE system using the Runge-Kutta method.
     ///
     /// Returns the solver results if successful and an integration error otherwise.
-    pub fn solve_rk4(
-        &self,
-        step_size: f32,
-    ) -> Result<SolverResult<f32, DVector<f32>>, IntegrationError> {
+    pub fn solve_rk4(&self, step_size: T) -> Result<SolverResult<T, DVector<T>>, IntegrationError> {
         let mut stepper = ode_solvers::Rk4::new(
             self,
             self.start_time,
@@ -99,8 +125,8 @@ where
     /// selection of initial step size.
     pub fn solve_dopri5(
         &self,
-        output_step_size: f32,
-    ) -> Result<SolverResult<f32, DVector<f32>>, IntegrationError> {
+        output_step_size: T,
+    ) -> Result<SolverResult<T, DVector<T>>, IntegrationError> {
         let mut stepper = ode_solvers::Dopri5::new(
             self,
             self.start_time,
@@ -113,13 +139,106 @@ where
         stepper.integrate()?;
         Ok(stepper.into())
     }
+
+    /// Solves the ODE system using the Dormand-Prince method of order 8(5,3).
+    ///
+    /// Like [`solve_dopri5`](Self::solve_dopri5) but of higher order, which is
+    /// more efficient when tight error tolerances are required.
+    pub fn solve_dop853(
+        &self,
+        output_step_size: T,
+    ) -> Result<SolverResult<T, DVector<T>>, IntegrationError> {
+        let mut stepper = ode_solvers::Dop853::new(
+            self,
+            self.start_time,
+            self.end_time,
+            output_step_size,
+            self.initial_values.clone(),
+            self.rtol,
+            self.atol,
+        );
+        stepper.integrate()?;
+        Ok(stepper.into())
+    }
+
+    /// Solves the ODE system using the Dormand-Prince method, with output at
+    /// the given times.
+    ///
+    /// The times should be increasing and no earlier than the start time; the
+    /// end time of the problem is ignored. Each output is computed by the
+    /// adaptive solver up to the requested time, so, unlike with a fixed step
+    /// size, the output grid need not be related to the steps taken.
+    pub fn solve_dopri5_at(
+        &self,
+        times: &[T],
+    ) -> Result<SolverResult<T, DVector<T>>, IntegrationError> {
+        self.solve_at(times, |problem| problem.solve_dopri5(problem.end_time - problem.start_time))
+    }
+
+    /// Solves the ODE system using the Dormand-Prince method of order 8(5,3),
+    /// with output at the given times.
+    ///
+    /// See [`solve_dopri5_at`](Self::solve_dopri5_at) for details.
+    pub fn solve_dop853_at(
+        &self,
+        times: &[T],
+    ) -> Result<SolverResult<T, DVector<T>>, IntegrationError> {
+        self.solve_at(times, |problem| problem.solve_dop853(problem.end_time - problem.start_time))
+    }
+
+    /// Solves the problem successively between the given times.
+    fn solve_at(
+        &self,
+        times: &[T],
+        solve: impl Fn(&ODEProblem<&Sys, T>) -> SolveResult<T>,
+    ) -> SolveResult<T> {
+        assert!(
+            times.first().is_none_or(|&t| t >= self.start_time)
+                && times.windows(2).all(|w| w[0] <= w[1]),
+            "Output times should be increasing and no earlier than the start time"
+        );
+        let mut states = Vec::with_capacity(times.len());
+        let (mut t, mut x) = (self.start_time, self.initial_values.clone());
+        for &next in times {
+            if next > t {
+                x = self.advance(x, t, next, &solve)?;
+                t = next;
+            }
+            states.push(x.clone());
+        }
+        Ok(SolverResult::new(times.to_vec(), states))
+    }
+
+    /// Advances a state from one time to a later one.
+    fn advance(
+        &self,
+        x: DVector<T>,
+        start: T,
+        end: T,
+        solve: impl Fn(&ODEProblem<&Sys, T>) -> SolveResult<T>,
+    ) -> Result<DVector<T>, IntegrationError> {
+        if end <= start {
+            return Ok(x);
+        }
+        let segment = ODEProblem {
+            system: &self.system,
+            initial_values: x,
+            start_time: start,
+            end_time: end,
+            rtol: self.rtol,
+            atol: self.atol,
+        };
+        let result = solve(&segment)?;
+        Ok(result.get().1.last().expect("Solver should output final state").clone())
+    }
 }
 
-impl<Sys> ode_solvers::dop_shared::System<f32, DVector<f32>> for &ODEProblem<Sys>
+impl<Sys, T> ode_solvers::dop_shared::System<T, DVector<T>> for &ODEProblem<Sys, T>
 where
-    Sys: ODESystem,
+    Sys: ODESystem<T>,
+    T: ODEScalar,
 {
-    fn system(&self, x: f32, y: &DVector<f32>, dy: &mut DVector<f32>) {
+    fn system(&self, x: T, y: &DVector<T>, dy: &mut DVector<T>) {
         self.system.vector_field(dy, y, x);
     }
 }
@@ -157,8 +276,57 @@ pub(crate) fn textplot_mapped_ode_result<Sys>(
     chart.to_string()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exponential decay, `x' = -x`.
+    struct Decay;
+
+    impl ODESystem for Decay {
+        fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+            dx[0] = -x[0];
+        }
+    }
+
+    #[test]
+    fn dense_output() {
+        let problem = ODEProblem::new(Decay, DVector::from_element(1, 1.0)).tolerances(1e-6, 1e-8);
+        let times: Vec<_> = (0..=20).map(|i| i as f32 * 0.25).collect();
+        for result in [problem.solve_dopri5_at(&times), problem.solve_dop853_at(&times)] {
+            let result = result.unwrap();
+            let (t_out, x_out) = result.get();
+            assert_eq!(t_out, &times);
+            for (t, x) in std::iter::zip(t_out, x_out) {
+                assert!((x[0] - (-t).exp()).abs() < 1e-4);
+            }
+        }
+
+        let result = problem.end_time(5.0).solve_dop853(0.5).unwrap();
+        let (t_out, x_out) = result.get();
+        assert!(t_out.len() > 1);
+        for (t, x) in std::iter::zip(t_out, x_out) {
+            assert!((x[0] - (-t).exp()).abs() < 1e-4);
+        }
+    }
+}
+
+pub mod conservation;
+pub mod dde;
+pub mod equilibria;
+pub mod events;
 pub mod kuramoto;
+pub mod piecewise;
 pub mod polynomial;
+pub mod qssa;
+pub mod units;
 
+pub use conservation::*;
+pub use dde::*;
+pub use equilibria::*;
+pub use events::*;
 pub use kuramoto::*;
+pub use piecewise::*;
 pub use polynomial::*;
+pub use qssa::*;
+pub use units::*;
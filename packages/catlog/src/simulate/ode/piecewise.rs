@@ -0,0 +1,99 @@
+//! ODE systems defined piecewise in time.
+//!
+//! Interventions, such as a lockdown lowering the infection rate of an
+//! epidemic from a given day, change the dynamics of a system at fixed times.
+//! A [piecewise system](PiecewiseSystem) follows one system until the first
+//! switching time, then the next system until the following switching time, and
+//! so on. The systems are typically built from the same model with different
+//! parameters.
+//!
+//! The vector field of a piecewise system is discontinuous at the switching
+//! times. Adaptive solvers handle this by shrinking their steps, but it is more
+//! accurate and efficient to include the switching times among the output times
+//! of [`ODEProblem::solve_dopri5_at`](super::ODEProblem::solve_dopri5_at), so
+//! that no step crosses a switch.
+
+use nalgebra::DVector;
+
+use super::ODESystem;
+
+/// An ODE system defined piecewise in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PiecewiseSystem<Sys> {
+    initial: Sys,
+    pieces: Vec<(f32, Sys)>,
+}
+
+impl<Sys> PiecewiseSystem<Sys> {
+    /// Creates a piecewise system following the given system at all times.
+    pub fn new(initial: Sys) -> Self {
+        Self { initial, pieces: Vec::new() }
+    }
+
+    /// Switches to another system from the given time onwards.
+    ///
+    /// Switches can be added in any order. If a switch is already at the given
+    /// time, it is replaced.
+    pub fn switch_at(mut self, time: f32, system: Sys) -> Self {
+        match self.pieces.binary_search_by(|(t, _)| t.total_cmp(&time)) {
+            Ok(i) => self.pieces[i] = (time, system),
+            Err(i) => self.pieces.insert(i, (time, system)),
+        }
+        self
+    }
+
+    /// Gets the system followed at the given time.
+    pub fn system_at(&self, time: f32) -> &Sys {
+        let i = self.pieces.partition_point(|(t, _)| *t <= time);
+        if i == 0 {
+            &self.initial
+        } else {
+            &self.pieces[i - 1].1
+        }
+    }
+
+    /// Iterates over the switching times, in increasing order.
+    pub fn switching_times(&self) -> impl ExactSizeIterator<Item = f32> + '_ {
+        self.pieces.iter().map(|(t, _)| *t)
+    }
+}
+
+impl<Sys: ODESystem> ODESystem for PiecewiseSystem<Sys> {
+    fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, t: f32) {
+        self.system_at(t).vector_field(dx, x, t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ODEProblem;
+    use super::*;
+
+    /// Exponential growth or decay, `x' = rx`.
+    struct Exponential(f32);
+
+    impl ODESystem for Exponential {
+        fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+            dx[0] = self.0 * x[0];
+        }
+    }
+
+    #[test]
+    fn switching() {
+        let sys = PiecewiseSystem::new(Exponential(1.0))
+            .switch_at(2.0, Exponential(0.0))
+            .switch_at(1.0, Exponential(-1.0));
+        assert_eq!(sys.switching_times().collect::<Vec<_>>(), vec![1.0, 2.0]);
+        assert_eq!(sys.system_at(0.5).0, 1.0);
+        assert_eq!(sys.system_at(1.0).0, -1.0);
+        assert_eq!(sys.system_at(5.0).0, 0.0);
+
+        // Growth for one unit of time and decay for another returns to the start.
+        let problem = ODEProblem::new(sys, DVector::from_element(1, 1.0));
+        let result = problem.solve_dopri5_at(&[1.0, 2.0, 3.0]).unwrap();
+        let (_, x_out) = result.get();
+        assert!((x_out[0][0] - 1f32.exp()).abs() < 1e-2);
+        assert!((x_out[1][0] - 1.0).abs() < 1e-2);
+        assert!((x_out[2][0] - 1.0).abs() < 1e-2);
+    }
+}
@@ -2,11 +2,11 @@
 
 use std::fmt::Display;
 use std::hash::Hash;
-use std::ops::{Add, Neg};
+use std::ops::{Add, Neg, Sub};
 
 use derivative::Derivative;
 use indexmap::IndexMap;
-use nalgebra::DVector;
+use nalgebra::{DMatrix, DVector};
 use num_traits::{One, Pow, Zero};
 
 #[cfg(feature = "serde")]
@@ -16,7 +16,7 @@ use tsify::Tsify;
 
 #[cfg(test)]
 use super::ODEProblem;
-use super::ODESystem;
+use super::{ODEScalar, ODESystem};
 use crate::zero::{alg::Polynomial, rig::DisplayCoef};
 
 /// A system of polynomial differential equations.
@@ -94,6 +94,23 @@ where
         PolynomialSystem { components }
     }
 
+    /// Computes the Jacobian matrix of the vector field symbolically.
+    ///
+    /// The entry in row `i` and column `j` is the partial derivative of the
+    /// `i`th component with respect to the `j`th variable, both in the order of
+    /// the components.
+    pub fn jacobian(&self) -> Vec<Vec<Polynomial<Var, Coef, Exp>>>
+    where
+        Var: Clone,
+        Coef: Clone + Zero + Add<Output = Coef> + Neg<Output = Coef>,
+        Exp: Clone + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+    {
+        self.components
+            .values()
+            .map(|poly| self.components.keys().map(|var| poly.partial_derivative(var)).collect())
+            .collect()
+    }
+
     /// Converts to equations as LaTeX strings.
     pub fn to_latex_equations(&self) -> Vec<LatexEquation>
     where
@@ -123,16 +140,19 @@ pub struct LatexEquation {
     pub rhs: String,
 }
 
-impl<Var, Exp> PolynomialSystem<Var, f32, Exp>
+impl<Var, T, Exp> PolynomialSystem<Var, T, Exp>
 where
     Var: Clone + Hash + Ord,
+    T: ODEScalar,
     Exp: Clone + Ord + Add<Output = Exp>,
 {
     /// Converts the polynomial system to a numerical one.
     ///
     /// The order of the components in the new system is given by the order of the
-    /// variables in the old one.
-    pub fn to_numerical(&self) -> NumericalPolynomialSystem<Exp> {
+    /// variables in the old one. The numerical system has the same precision as
+    /// the coefficients, so a system with `f32` coefficients can be solved in
+    /// double precision by first extending the scalars with `f64::from`.
+    pub fn to_numerical(&self) -> NumericalPolynomialSystem<Exp, T> {
         let indices: IndexMap<Var, usize> =
             self.components.keys().enumerate().map(|(i, var)| (var.clone(), i)).collect();
         let components = self
@@ -176,24 +196,64 @@ where
 /// A numerical system of polynomial differential equations.
 ///
 /// Such a system is ready for use in numerical solvers: the coefficients are
-/// floating point numbers and the variables are consecutive integer indices.
-pub struct NumericalPolynomialSystem<Exp> {
+/// floating point numbers, by default of single precision, and the variables
+/// are consecutive integer indices.
+pub struct NumericalPolynomialSystem<Exp, T = f32> {
     /// Components of the vector field.
-    pub components: Vec<Polynomial<usize, f32, Exp>>,
+    pub components: Vec<Polynomial<usize, T, Exp>>,
 }
 
-impl<Exp> ODESystem for NumericalPolynomialSystem<Exp>
+impl<Exp, T> ODESystem<T> for NumericalPolynomialSystem<Exp, T>
 where
     Exp: Clone + Ord,
-    f32: Pow<Exp, Output = f32>,
+    T: ODEScalar + Pow<Exp, Output = T>,
 {
-    fn vector_field(&self, dx: &mut DVector<f32>, x: &DVector<f32>, _t: f32) {
+    fn vector_field(&self, dx: &mut DVector<T>, x: &DVector<T>, _t: T) {
         for i in 0..dx.len() {
             dx[i] = self.components[i].eval(|var| x[*var])
         }
     }
 }
 
+impl<Exp, T> NumericalPolynomialSystem<Exp, T>
+where
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+    T: ODEScalar,
+{
+    /// Computes the Jacobian matrix of the vector field symbolically.
+    pub fn jacobian(&self) -> PolynomialJacobian<Exp, T> {
+        let n = self.components.len();
+        let entries = self
+            .components
+            .iter()
+            .map(|poly| (0..n).map(|j| poly.partial_derivative(&j)).collect())
+            .collect();
+        PolynomialJacobian { entries }
+    }
+}
+
+/// The Jacobian matrix of a numerical polynomial system, computed symbolically.
+///
+/// Evaluating the Jacobian is exact, unlike the
+/// [numerical Jacobian](super::numerical_jacobian), so that eigenvalues on the
+/// imaginary axis, such as those of centers, are found reliably.
+pub struct PolynomialJacobian<Exp, T = f32> {
+    /// Entries of the matrix, as rows of partial derivatives.
+    pub entries: Vec<Vec<Polynomial<usize, T, Exp>>>,
+}
+
+impl<Exp, T> PolynomialJacobian<Exp, T>
+where
+    Exp: Clone + Ord,
+    T: ODEScalar + Pow<Exp, Output = T>,
+{
+    /// Evaluates the Jacobian at a state.
+    pub fn eval(&self, x: &DVector<T>) -> DMatrix<T> {
+        let n = self.entries.len();
+        DMatrix::from_fn(n, n, |i, j| self.entries[i][j].eval(|var| x[*var]))
+    }
+}
+
 impl<Exp> Display for NumericalPolynomialSystem<Exp>
 where
     Exp: Clone + Ord + Add<Output = Exp> + One + Display,
@@ -213,7 +273,7 @@ where
 mod tests {
     use expect_test::expect;
 
-    use super::super::textplot_ode_result;
+    use super::super::{EquilibriumSolver, Stability, textplot_ode_result};
     use super::*;
 
     type Parameter<Id> = Polynomial<Id, f32, u8>;
@@ -273,4 +333,52 @@ mod tests {
         "#]];
         expected.assert_eq(&textplot_ode_result(&problem, &result));
     }
+
+    #[test]
+    fn predator_prey_jacobian() {
+        let var = |c: char| Polynomial::<_, f32, u8>::generator(c);
+        let terms = [
+            ('x', var('x') * 2.0 + -(var('x') * var('y'))),
+            ('y', var('x') * var('y') + -var('y')),
+        ];
+        let sys: PolynomialSystem<_, _, _> = terms.into_iter().collect();
+        let jacobian = sys.jacobian();
+        assert_eq!(jacobian[0][1].to_string(), "-x");
+        assert_eq!(jacobian[1][0].to_string(), "y");
+        assert_eq!(jacobian[1][1].to_string(), "-1 + x");
+
+        // The interior equilibrium is a center, with eigenvalues exactly `±i√2`.
+        let state = DVector::from_column_slice(&[1.0, 2.0]);
+        let jacobian = sys.to_numerical().jacobian().eval(&state);
+        assert_eq!(jacobian, DMatrix::from_row_slice(2, 2, &[0.0, -1.0, 2.0, 0.0]));
+        let eq = EquilibriumSolver::new().classify_with_jacobian(state, &jacobian);
+        assert_eq!(eq.stability, Stability::Marginal);
+    }
+
+    #[test]
+    fn double_precision() {
+        // Harmonic oscillator, with solution `x = cos t`, over many periods.
+        let var = |c: char| Polynomial::<_, f32, u8>::generator(c);
+        let terms = [('x', var('y')), ('y', -var('x'))];
+        let sys: PolynomialSystem<_, _, _> = terms.into_iter().collect();
+        let end_time = 100.0f32;
+
+        let initial = DVector::from_column_slice(&[1.0f32, 0.0]);
+        let problem = ODEProblem::new(sys.to_numerical(), initial).end_time(end_time);
+        let result = problem.solve_rk4(0.001).unwrap();
+        let (t_out, x_out) = result.get();
+        let (t, x) = (f64::from(*t_out.last().unwrap()), f64::from(x_out.last().unwrap()[0]));
+        let single_error = (x - t.cos()).abs();
+
+        let sys = sys.extend_scalars(f64::from);
+        let initial = DVector::from_column_slice(&[1.0f64, 0.0]);
+        let problem = ODEProblem::new(sys.to_numerical(), initial).end_time(end_time.into());
+        let result = problem.solve_rk4(0.001).unwrap();
+        let (t_out, x_out) = result.get();
+        let (t, x) = (*t_out.last().unwrap(), x_out.last().unwrap()[0]);
+        let double_error = (x - t.cos()).abs();
+
+        assert!(double_error < 1e-8);
+        assert!(double_error < single_error);
+    }
 }
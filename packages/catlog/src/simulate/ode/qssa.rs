@@ -0,0 +1,291 @@
+//! Model reduction by the quasi-steady-state approximation.
+//!
+//! When some variables of a system, the *fast* ones, relax to equilibrium much
+//! faster than the others, the *slow* ones, the fast variables can be assumed
+//! to be at equilibrium given the slow ones. This is the quasi-steady-state
+//! approximation (QSSA), the classic example being the derivation of
+//! Michaelis-Menten kinetics, where the enzyme-substrate complex is fast.
+//!
+//! Setting the derivatives of the fast variables to zero gives algebraic
+//! equations for them. Here the fast variables must occur linearly in their own
+//! equations, possibly multiplied by slow variables, so that the equations are
+//! linear in the fast variables with coefficients polynomial in the slow ones.
+//! The coefficients are extracted symbolically and the linear system solved at
+//! each evaluation of the [reduced system](QSSASystem). In general the solution
+//! is a rational function of the slow variables and so is not itself a
+//! polynomial system.
+//!
+//! The approximation is only valid when the fast subsystem is stable and the
+//! timescales are well separated, which is [diagnosed](QSSADiagnostics) from
+//! the eigenvalues of the Jacobians of the fast and the reduced systems.
+
+use std::ops::Add;
+
+use nalgebra::{Complex, DMatrix, DVector};
+use num_traits::{One, Pow};
+use thiserror::Error;
+
+use super::{NumericalPolynomialSystem, ODEProblem, ODESystem, numerical_jacobian};
+use crate::zero::{alg::Polynomial, rig::Monomial};
+
+/// Minimum ratio of the fast and slow timescales for the QSSA to be valid.
+const MIN_TIMESCALE_RATIO: f32 = 10.0;
+
+/// Error in applying the quasi-steady-state approximation.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum QSSAError {
+    /// A fast variable is not a variable of the system.
+    #[error("Variable {0} is not in the system")]
+    UnknownVariable(usize),
+
+    /// The equation of a fast variable is nonlinear in the fast variables.
+    #[error("Equation of variable {0} is not linear in the fast variables")]
+    Nonlinear(usize),
+}
+
+/// A polynomial system reduced by the quasi-steady-state approximation.
+///
+/// The state of the reduced system comprises the slow variables, in order.
+pub struct QSSASystem<Exp> {
+    system: NumericalPolynomialSystem<Exp>,
+    fast: Vec<usize>,
+    slow: Vec<usize>,
+    /// Coefficient of each fast variable in the equation of each fast variable.
+    matrix: Vec<Vec<Polynomial<usize, f32, Exp>>>,
+    /// Terms without fast variables in the equation of each fast variable.
+    constant: Vec<Polynomial<usize, f32, Exp>>,
+}
+
+impl<Exp> NumericalPolynomialSystem<Exp>
+where
+    Exp: Clone + Ord + One + Add<Output = Exp>,
+{
+    /// Reduces the system by assuming the given variables are at equilibrium.
+    pub fn quasi_steady_state(self, fast: &[usize]) -> Result<QSSASystem<Exp>, QSSAError> {
+        let n = self.components.len();
+        if let Some(&i) = fast.iter().find(|&&i| i >= n) {
+            return Err(QSSAError::UnknownVariable(i));
+        }
+        let mut fast: Vec<_> = fast.to_vec();
+        fast.sort();
+        fast.dedup();
+        let slow = (0..n).filter(|i| fast.binary_search(i).is_err()).collect();
+
+        let mut matrix: Vec<Vec<Polynomial<_, _, _>>> = Vec::with_capacity(fast.len());
+        let mut constant: Vec<Polynomial<_, _, _>> = Vec::with_capacity(fast.len());
+        for &i in &fast {
+            let mut row = vec![Vec::new(); fast.len()];
+            let mut rest = Vec::new();
+            for (coef, monomial) in self.components[i].terms() {
+                let (in_fast, in_slow): (Vec<_>, Vec<_>) = monomial
+                    .clone()
+                    .into_iter()
+                    .partition(|(var, _)| fast.binary_search(var).is_ok());
+                let term: (f32, Monomial<_, _>) = (*coef, in_slow.into_iter().collect());
+                match in_fast.as_slice() {
+                    [] => rest.push(term),
+                    [(var, exp)] if exp.is_one() => {
+                        let j = fast.binary_search(var).unwrap();
+                        row[j].push(term);
+                    }
+                    _ => return Err(QSSAError::Nonlinear(i)),
+                }
+            }
+            matrix.push(row.into_iter().map(|terms| terms.into_iter().collect()).collect());
+            constant.push(rest.into_iter().collect());
+        }
+        Ok(QSSASystem {
+            system: self,
+            fast,
+            slow,
+            matrix,
+            constant,
+        })
+    }
+}
+
+impl<Exp> QSSASystem<Exp>
+where
+    Exp: Clone + Ord,
+    f32: Pow<Exp, Output = f32>,
+{
+    /// Gets the indices of the fast variables, in increasing order.
+    pub fn fast_variables(&self) -> &[usize] {
+        &self.fast
+    }
+
+    /// Gets the indices of the slow variables, in increasing order.
+    pub fn slow_variables(&self) -> &[usize] {
+        &self.slow
+    }
+
+    /// Restricts a state of the full system to the slow variables.
+    pub fn reduce(&self, x: &DVector<f32>) -> DVector<f32> {
+        DVector::from_iterator(self.slow.len(), self.slow.iter().map(|i| x[*i]))
+    }
+
+    /// Extends a state of the slow variables to the full system, with the fast
+    /// variables at their quasi-steady state.
+    ///
+    /// The fast variables are NaN if the fast subsystem is singular.
+    pub fn expand(&self, y: &DVector<f32>) -> DVector<f32> {
+        let mut x = DVector::zeros(self.fast.len() + self.slow.len());
+        for (k, i) in self.slow.iter().enumerate() {
+            x[*i] = y[k];
+        }
+        let (a, b) = self.linear_system(&x);
+        let x_fast = a
+            .lu()
+            .solve(&-b)
+            .unwrap_or_else(|| DVector::from_element(self.fast.len(), f32::NAN));
+        for (k, i) in self.fast.iter().enumerate() {
+            x[*i] = x_fast[k];
+        }
+        x
+    }
+
+    /// Computes the linear system `Ax + b = 0` satisfied by the fast variables,
+    /// given the slow variables.
+    fn linear_system(&self, x: &DVector<f32>) -> (DMatrix<f32>, DVector<f32>) {
+        let eval = |poly: &Polynomial<usize, f32, Exp>| poly.eval(|var| x[*var]);
+        let m = self.fast.len();
+        let a = DMatrix::from_fn(m, m, |i, j| eval(&self.matrix[i][j]));
+        let b = DVector::from_fn(m, |i, _| eval(&self.constant[i]));
+        (a, b)
+    }
+
+    /// Diagnoses the validity of the approximation at a state of the slow
+    /// variables.
+    pub fn diagnose(&self, y: &DVector<f32>, t: f32) -> QSSADiagnostics {
+        let (a, _) = self.linear_system(&self.expand(y));
+        let fast_eigenvalues: Vec<_> = a.complex_eigenvalues().iter().copied().collect();
+        let slow_eigenvalues: Vec<_> =
+            numerical_jacobian(self, y, t).complex_eigenvalues().iter().copied().collect();
+        let slowest_fast =
+            fast_eigenvalues.iter().map(|z| z.re.abs()).fold(f32::INFINITY, f32::min);
+        let fastest_slow = slow_eigenvalues.iter().map(|z| z.re.abs()).fold(0.0, f32::max);
+        QSSADiagnostics {
+            stable: fast_eigenvalues.iter().all(|z| z.re < 0.0),
+            timescale_ratio: slowest_fast / fastest_slow,
+            fast_eigenvalues,
+            slow_eigenvalues,
+        }
+    }
+}
+
+impl<Exp> ODESystem for QSSASystem<Exp>
+where
+    Exp: Clone + Ord,
+    f32: Pow<Exp, Output = f32>,
+{
+    fn vector_field(&self, dy: &mut DVector<f32>, y: &DVector<f32>, _t: f32) {
+        let x = self.expand(y);
+        for (k, i) in self.slow.iter().enumerate() {
+            dy[k] = self.system.components[*i].eval(|var| x[*var]);
+        }
+    }
+}
+
+impl<Exp> ODEProblem<NumericalPolynomialSystem<Exp>>
+where
+    Exp: Clone + Ord + One + Add<Output = Exp>,
+    f32: Pow<Exp, Output = f32>,
+{
+    /// Reduces the problem by assuming the given variables are at equilibrium.
+    ///
+    /// The initial values of the fast variables are discarded.
+    pub fn quasi_steady_state(
+        self,
+        fast: &[usize],
+    ) -> Result<ODEProblem<QSSASystem<Exp>>, QSSAError> {
+        let system = self.system.quasi_steady_state(fast)?;
+        Ok(ODEProblem {
+            initial_values: system.reduce(&self.initial_values),
+            system,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            rtol: self.rtol,
+            atol: self.atol,
+        })
+    }
+}
+
+/// Diagnostics of the validity of the quasi-steady-state approximation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QSSADiagnostics {
+    /// Eigenvalues of the Jacobian of the fast subsystem in the fast variables.
+    pub fast_eigenvalues: Vec<Complex<f32>>,
+
+    /// Eigenvalues of the Jacobian of the reduced system.
+    pub slow_eigenvalues: Vec<Complex<f32>>,
+
+    /// Whether the fast subsystem is stable, so that the fast variables relax
+    /// to their quasi-steady state.
+    pub stable: bool,
+
+    /// Ratio of the slowest rate of the fast subsystem to the fastest rate of
+    /// the reduced system.
+    pub timescale_ratio: f32,
+}
+
+impl QSSADiagnostics {
+    /// Whether the approximation is valid: the fast subsystem is stable and
+    /// the timescales are separated by at least an order of magnitude.
+    pub fn is_valid(&self) -> bool {
+        self.stable && self.timescale_ratio >= MIN_TIMESCALE_RATIO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Enzyme kinetics `E + S <-> C -> E + P`, with the enzyme conserved so that
+    /// `E = e0 - C`. The variables are `S`, `C`, and `P`.
+    fn enzyme_kinetics(
+        k_on: f32,
+        k_off: f32,
+        k_cat: f32,
+        e0: f32,
+    ) -> NumericalPolynomialSystem<u8> {
+        let var = Polynomial::<usize, f32, u8>::generator;
+        let (s, c) = (var(0), var(1));
+        let binding = (-c.clone() + e0) * s * k_on;
+        let unbinding = c.clone() * k_off;
+        let catalysis = c * k_cat;
+        NumericalPolynomialSystem {
+            components: vec![
+                unbinding.clone() + -binding.clone(),
+                binding + -unbinding + -catalysis.clone(),
+                catalysis,
+            ],
+        }
+    }
+
+    #[test]
+    fn michaelis_menten() {
+        let (k_on, k_off, k_cat, e0) = (10.0, 10.0, 1.0, 0.1);
+        let sys = enzyme_kinetics(k_on, k_off, k_cat, e0).quasi_steady_state(&[1]).unwrap();
+        assert_eq!(sys.slow_variables(), &[0, 2]);
+
+        // The reduced rate of production is `v S / (K + S)`.
+        let (v, k) = (k_cat * e0, (k_off + k_cat) / k_on);
+        let y = DVector::from_column_slice(&[1.0, 0.0]);
+        let dy = sys.eval_vector_field(&y, 0.0);
+        assert!((dy[1] - v / (k + 1.0)).abs() < 1e-6);
+        assert!((dy[0] + dy[1]).abs() < 1e-6);
+
+        let diagnostics = sys.diagnose(&y, 0.0);
+        assert!(diagnostics.stable);
+        assert!(diagnostics.is_valid());
+
+        let problem = ODEProblem::new(&sys, y).end_time(100.0);
+        let result = problem.solve_dopri5(10.0).unwrap();
+        let (_, y_out) = result.get();
+        let x = sys.expand(y_out.last().unwrap());
+        assert!(x[2] > 0.5 && x[0] + x[2] <= 1.0 + 1e-4);
+
+        let err = enzyme_kinetics(k_on, k_off, k_cat, e0).quasi_steady_state(&[0, 1]);
+        assert_eq!(err.err(), Some(QSSAError::Nonlinear(0)));
+    }
+}
@@ -0,0 +1,539 @@
+//! Physical units and dimensional analysis of polynomial systems.
+//!
+//! The state variables, parameters, and time of a [polynomial
+//! system](PolynomialSystem) can be annotated with physical units, such as
+//! `person` or `1/(person day)`. Each term in the equation for a variable `x`
+//! must then have the unit of `x` per unit of time. Checking this catches
+//! silent unit mix-ups, such as a rate given per year in a model simulated in
+//! days. Units with the same dimension but different scales are not converted
+//! automatically; the conversion factor is reported instead.
+//!
+//! Units are products of powers of named units. Common units of time, length,
+//! mass, volume, and amount are known along with their scale relative to SI
+//! base units. Any other name, such as `person` or `rabbit`, is treated as a
+//! base unit of its own.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display};
+use std::hash::Hash;
+use std::iter::Product;
+use std::ops::{Div, Mul};
+use std::str::FromStr;
+
+use num_traits::{One, Pow};
+use thiserror::Error;
+
+use super::PolynomialSystem;
+use crate::zero::alg::Polynomial;
+use crate::zero::rig::Monomial;
+
+/// Known units, with their scale and the power of a base unit they measure.
+const KNOWN_UNITS: &[(&str, f64, &str, i32)] = &[
+    ("s", 1.0, "s", 1),
+    ("sec", 1.0, "s", 1),
+    ("min", 60.0, "s", 1),
+    ("h", 3600.0, "s", 1),
+    ("hr", 3600.0, "s", 1),
+    ("hour", 3600.0, "s", 1),
+    ("d", 86400.0, "s", 1),
+    ("day", 86400.0, "s", 1),
+    ("week", 604800.0, "s", 1),
+    ("month", 2629800.0, "s", 1),
+    ("yr", 31557600.0, "s", 1),
+    ("year", 31557600.0, "s", 1),
+    ("m", 1.0, "m", 1),
+    ("mm", 1e-3, "m", 1),
+    ("cm", 1e-2, "m", 1),
+    ("km", 1e3, "m", 1),
+    ("kg", 1.0, "kg", 1),
+    ("g", 1e-3, "kg", 1),
+    ("mg", 1e-6, "kg", 1),
+    ("L", 1e-3, "m", 3),
+    ("mL", 1e-6, "m", 3),
+    ("mol", 1.0, "mol", 1),
+    ("mmol", 1e-3, "mol", 1),
+];
+
+/// Relative tolerance when comparing the scales of units.
+const SCALE_TOLERANCE: f64 = 1e-9;
+
+/// A physical unit, as a product of powers of named units.
+///
+/// Units are compared structurally by `==`, so that `km/h` and `m/s` are
+/// different units. Use [`Unit::conversion_factor`] to compare units up to
+/// scale.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Unit(BTreeMap<String, i32>);
+
+impl Unit {
+    /// The dimensionless unit.
+    pub fn dimensionless() -> Self {
+        Default::default()
+    }
+
+    /// Constructs the unit with the given name.
+    pub fn named(name: impl Into<String>) -> Self {
+        Unit([(name.into(), 1)].into_iter().collect())
+    }
+
+    /// Parses a unit, such as `km/h`, `1/(person day)`, or `m s^-2`.
+    ///
+    /// Units are multiplied by juxtaposition or `*` and divided by `/`, which
+    /// applies to the following unit only, so that `mol/L/s` is `mol L^-1 s^-1`.
+    pub fn parse(text: &str) -> Result<Self, InvalidUnit> {
+        let mut parser = UnitParser { text, pos: 0 };
+        let unit = parser.product()?;
+        parser.skip_whitespace();
+        if parser.pos < text.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(unit)
+    }
+
+    /// Whether the unit is dimensionless.
+    pub fn is_dimensionless(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Factor by which to multiply a quantity in this unit to convert it to
+    /// the other unit.
+    ///
+    /// Returns `None` if the units have different dimensions.
+    pub fn conversion_factor(&self, to: &Unit) -> Option<f64> {
+        let (scale, dims) = self.to_base();
+        let (to_scale, to_dims) = to.to_base();
+        (dims == to_dims).then_some(scale / to_scale)
+    }
+
+    /// Expresses the unit as a scale times a product of powers of base units.
+    fn to_base(&self) -> (f64, BTreeMap<&str, i32>) {
+        let mut scale = 1.0;
+        let mut dims = BTreeMap::new();
+        for (name, &exp) in &self.0 {
+            let (factor, base, base_exp) = KNOWN_UNITS
+                .iter()
+                .find(|(known, ..)| *known == name.as_str())
+                .map(|&(_, factor, base, base_exp)| (factor, base, base_exp))
+                .unwrap_or((1.0, name.as_str(), 1));
+            scale *= factor.powi(exp);
+            *dims.entry(base).or_insert(0) += base_exp * exp;
+        }
+        dims.retain(|_, exp| *exp != 0);
+        (scale, dims)
+    }
+}
+
+impl FromStr for Unit {
+    type Err = InvalidUnit;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Unit::parse(s)
+    }
+}
+
+impl Mul for Unit {
+    type Output = Unit;
+
+    fn mul(mut self, rhs: Unit) -> Unit {
+        for (name, exp) in rhs.0 {
+            *self.0.entry(name).or_insert(0) += exp;
+        }
+        self.0.retain(|_, exp| *exp != 0);
+        self
+    }
+}
+
+impl Div for Unit {
+    type Output = Unit;
+
+    fn div(self, rhs: Unit) -> Unit {
+        self * rhs.pow(-1i32)
+    }
+}
+
+impl<Exp: Into<i32>> Pow<Exp> for Unit {
+    type Output = Unit;
+
+    fn pow(mut self, exp: Exp) -> Unit {
+        let exp = exp.into();
+        if exp == 0 {
+            return Unit::dimensionless();
+        }
+        for value in self.0.values_mut() {
+            *value *= exp;
+        }
+        self
+    }
+}
+
+impl One for Unit {
+    fn one() -> Self {
+        Unit::dimensionless()
+    }
+}
+
+impl Product for Unit {
+    fn product<I: Iterator<Item = Unit>>(iter: I) -> Self {
+        iter.fold(Unit::dimensionless(), Mul::mul)
+    }
+}
+
+/// Prints the unit with positive powers first, such as `person day^-1`.
+impl Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_dimensionless() {
+            return write!(f, "1");
+        }
+        let positive = self.0.iter().filter(|(_, exp)| **exp > 0);
+        let negative = self.0.iter().filter(|(_, exp)| **exp < 0);
+        for (i, (name, exp)) in positive.chain(negative).enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{name}")?;
+            if *exp != 1 {
+                write!(f, "^{exp}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A syntax error in a unit.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("Invalid unit at position {position}: {message}")]
+pub struct InvalidUnit {
+    /// Byte offset in the text at which the error occurred.
+    pub position: usize,
+
+    /// Description of the error.
+    pub message: String,
+}
+
+/// Recursive descent parser for units.
+struct UnitParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl UnitParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn error(&self, message: &str) -> InvalidUnit {
+        InvalidUnit {
+            position: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn product(&mut self) -> Result<Unit, InvalidUnit> {
+        let mut unit = self.factor()?;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(c @ ('*' | '·')) => {
+                    self.pos += c.len_utf8();
+                    unit = unit * self.factor()?;
+                }
+                Some('/') => {
+                    self.pos += 1;
+                    unit = unit / self.factor()?;
+                }
+                None | Some(')') => return Ok(unit),
+                Some(_) => unit = unit * self.factor()?,
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Result<Unit, InvalidUnit> {
+        self.skip_whitespace();
+        let base = match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let unit = self.product()?;
+                if self.peek() != Some(')') {
+                    return Err(self.error("expected `)`"));
+                }
+                self.pos += 1;
+                unit
+            }
+            Some('1') => {
+                self.pos += 1;
+                Unit::dimensionless()
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let start = self.pos;
+                while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_') {
+                    self.pos += c.len_utf8();
+                }
+                Unit::named(&self.text[start..self.pos])
+            }
+            _ => return Err(self.error("expected a unit")),
+        };
+        if self.peek() != Some('^') {
+            return Ok(base);
+        }
+        self.pos += 1;
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let exp: i32 = self.text[start..self.pos].parse().map_err(|_| InvalidUnit {
+            position: start,
+            message: "expected an integer exponent".into(),
+        })?;
+        Ok(base.pow(exp))
+    }
+}
+
+/// Unit annotations on the time, state variables, and parameters of a system.
+///
+/// Annotations are optional: terms involving a variable or parameter without a
+/// unit are not checked.
+#[derive(Clone, Debug)]
+pub struct UnitAnnotations<Var, Id> {
+    /// Unit of time.
+    pub time: Unit,
+
+    /// Units of the state variables.
+    pub variables: HashMap<Var, Unit>,
+
+    /// Units of the parameters.
+    pub parameters: HashMap<Id, Unit>,
+}
+
+impl<Var, Id> UnitAnnotations<Var, Id> {
+    /// Creates annotations with the given unit of time and no other units.
+    pub fn new(time: Unit) -> Self {
+        Self {
+            time,
+            variables: HashMap::new(),
+            parameters: HashMap::new(),
+        }
+    }
+}
+
+/// A term in a polynomial system whose unit is inconsistent with its equation.
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum DimensionError<Var> {
+    /// The term has a different dimension than required.
+    #[error("Term `{term}` in the equation for `{variable}` has unit {found}, expected {expected}")]
+    Dimension {
+        /// Variable whose equation contains the term.
+        variable: Var,
+
+        /// The term, with its numerical coefficient omitted.
+        term: String,
+
+        /// Unit required by the equation.
+        expected: Unit,
+
+        /// Unit of the term.
+        found: Unit,
+    },
+
+    /// The term has the required dimension but a different scale.
+    #[error(
+        "Term `{term}` in the equation for `{variable}` has unit {found}, \
+         which is {factor} times the expected unit {expected}"
+    )]
+    Scale {
+        /// Variable whose equation contains the term.
+        variable: Var,
+
+        /// The term, with its numerical coefficient omitted.
+        term: String,
+
+        /// Unit required by the equation.
+        expected: Unit,
+
+        /// Unit of the term.
+        found: Unit,
+
+        /// Factor converting the unit of the term to the expected unit.
+        factor: f64,
+    },
+}
+
+impl<Var, Id, Coef, Exp> PolynomialSystem<Var, Polynomial<Id, Coef, Exp>, Exp>
+where
+    Var: Clone + Eq + Hash + Ord + Display,
+    Id: Eq + Hash + Ord + Display,
+    Exp: Clone + Ord + Into<i32> + Display + One,
+{
+    /// Checks that the system is dimensionally consistent.
+    ///
+    /// The coefficients of the system are polynomials in the parameters, as in
+    /// mass-action dynamics. Every product of a monomial in the parameters with
+    /// a monomial in the state variables must have the unit of the variable
+    /// being differentiated per unit of time. All inconsistent terms are
+    /// reported, in the order of the equations.
+    pub fn check_units(
+        &self,
+        units: &UnitAnnotations<Var, Id>,
+    ) -> Result<(), Vec<DimensionError<Var>>> {
+        let mut errors = Vec::new();
+        for (var, component) in &self.components {
+            let Some(var_unit) = units.variables.get(var) else {
+                continue;
+            };
+            let expected = var_unit.clone() / units.time.clone();
+            for (coef, monomial) in component.terms() {
+                let Some(state_unit) = monomial_unit(monomial, &units.variables) else {
+                    continue;
+                };
+                for param_monomial in coef.monomials() {
+                    let Some(param_unit) = monomial_unit(param_monomial, &units.parameters) else {
+                        continue;
+                    };
+                    let found = param_unit * state_unit.clone();
+                    let term = || term_text(param_monomial, monomial);
+                    match found.conversion_factor(&expected) {
+                        None => errors.push(DimensionError::Dimension {
+                            variable: var.clone(),
+                            term: term(),
+                            expected: expected.clone(),
+                            found,
+                        }),
+                        Some(factor) if (factor - 1.0).abs() > SCALE_TOLERANCE => {
+                            errors.push(DimensionError::Scale {
+                                variable: var.clone(),
+                                term: term(),
+                                expected: expected.clone(),
+                                found,
+                                factor,
+                            })
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Computes the unit of a monomial, if all its variables have units.
+fn monomial_unit<V, Exp>(monomial: &Monomial<V, Exp>, units: &HashMap<V, Unit>) -> Option<Unit>
+where
+    V: Eq + Hash + Ord,
+    Exp: Clone + Into<i32>,
+{
+    if !monomial.variables().all(|v| units.contains_key(v)) {
+        return None;
+    }
+    Some(monomial.eval(|v| units[v].clone()))
+}
+
+/// Prints the product of a monomial in parameters and one in state variables.
+fn term_text<Id, Var, Exp>(params: &Monomial<Id, Exp>, state: &Monomial<Var, Exp>) -> String
+where
+    Id: Ord + Display,
+    Var: Ord + Display,
+    Exp: Display + PartialEq + One,
+{
+    match (params.is_empty(), state.is_empty()) {
+        (true, _) => state.to_string(),
+        (false, true) => params.to_string(),
+        (false, false) => format!("{params} {state}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Parameter = Polynomial<char, f32, i8>;
+
+    fn sir() -> PolynomialSystem<char, Parameter, i8> {
+        let param = |c: char| Parameter::generator(c);
+        let var = |c: char| Polynomial::<_, Parameter, i8>::generator(c);
+        let terms = [
+            ('S', -var('S') * var('I') * param('β')),
+            ('I', var('S') * var('I') * param('β')),
+            ('I', -var('I') * param('γ')),
+            ('R', var('I') * param('γ')),
+        ];
+        terms.into_iter().collect()
+    }
+
+    fn annotations(gamma: &str) -> UnitAnnotations<char, char> {
+        let mut units = UnitAnnotations::new(Unit::named("day"));
+        for var in ['S', 'I', 'R'] {
+            units.variables.insert(var, Unit::named("person"));
+        }
+        units.parameters.insert('β', Unit::parse("1/(person day)").unwrap());
+        units.parameters.insert('γ', Unit::parse(gamma).unwrap());
+        units
+    }
+
+    #[test]
+    fn parse_units() {
+        let unit = Unit::parse("1/(person day)").unwrap();
+        assert_eq!(unit.to_string(), "day^-1 person^-1");
+        assert_eq!(Unit::parse("m s^-2"), Unit::parse("m / s / s"));
+        assert_eq!(Unit::parse("mol/L/s").unwrap().to_string(), "mol L^-1 s^-1");
+        assert!(Unit::parse("1").unwrap().is_dimensionless());
+
+        assert_eq!(Unit::parse("m/").unwrap_err().position, 2);
+        assert_eq!(Unit::parse("(day").unwrap_err().position, 4);
+        assert_eq!(Unit::parse("m^x").unwrap_err().position, 2);
+    }
+
+    #[test]
+    fn conversion() {
+        let kmh = Unit::parse("km/h").unwrap();
+        let factor = kmh.conversion_factor(&Unit::parse("m/s").unwrap()).unwrap();
+        assert!((factor - 1.0 / 3.6).abs() < 1e-12);
+        let factor = Unit::named("year").conversion_factor(&Unit::named("day")).unwrap();
+        assert!((factor - 365.25).abs() < 1e-9);
+        assert_eq!(Unit::named("person").conversion_factor(&Unit::named("day")), None);
+        assert_eq!(
+            Unit::parse("L").unwrap().conversion_factor(&Unit::parse("m^3").unwrap()),
+            Some(1e-3)
+        );
+    }
+
+    #[test]
+    fn check_sir() {
+        let sys = sir();
+        assert_eq!(sys.check_units(&annotations("1/day")), Ok(()));
+
+        // Recovery rate given per year in a model simulated in days.
+        let errors = sys.check_units(&annotations("1/year")).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        let DimensionError::Scale { variable, term, factor, .. } = &errors[0] else {
+            panic!("Should be a scale error");
+        };
+        assert_eq!((*variable, term.as_str()), ('I', "γ I"));
+        assert!((factor - 1.0 / 365.25).abs() < 1e-9);
+
+        // Recovery rate missing its unit of time.
+        let errors = sys.check_units(&annotations("1")).unwrap_err();
+        assert!(matches!(&errors[0], DimensionError::Dimension { variable: 'I', .. }));
+        assert_eq!(
+            errors[1].to_string(),
+            "Term `γ I` in the equation for `R` has unit person, expected person day^-1"
+        );
+
+        // Terms with unannotated parameters are not checked.
+        let mut units = annotations("1");
+        units.parameters.remove(&'γ');
+        assert_eq!(sys.check_units(&units), Ok(()));
+    }
+}
@@ -0,0 +1,413 @@
+//! Stochastic simulation of reaction networks.
+//!
+//! Gillespie's *stochastic simulation algorithm* (SSA) samples exact
+//! trajectories of the continuous-time Markov chain in which species are present
+//! in integer quantities and reactions fire at random times, with propensities
+//! given by the law of mass action. It is the stochastic counterpart of the
+//! deterministic mass-action dynamics of ODE simulation, which it approximates
+//! when populations are large.
+//!
+//! This module implements the *direct method*. At each step, the time until the
+//! next event is drawn from an exponential distribution whose rate is the total
+//! propensity, and the reaction that fires is chosen with probability
+//! proportional to its propensity. Simulations are reproducible given a seed.
+//! Independent runs use non-overlapping streams of the random number generator,
+//! a xoshiro256++ generator jumped ahead by 2<sup>128</sup> steps for each run.
+
+/// A reaction with mass-action kinetics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reaction {
+    /// Rate constant of the reaction.
+    pub rate: f64,
+
+    /// Number of individuals of each species consumed by the reaction.
+    pub inputs: Vec<u32>,
+
+    /// Number of individuals of each species produced by the reaction.
+    pub outputs: Vec<u32>,
+}
+
+impl Reaction {
+    /// Computes the mass-action propensity of the reaction in a state.
+    ///
+    /// The propensity is the rate constant times, for each species consumed,
+    /// the number of ordered ways of choosing the consumed individuals. For
+    /// example, a reaction consuming two individuals of a species present `n`
+    /// times has propensity proportional to `n(n-1)`.
+    pub fn propensity(&self, state: &[u64]) -> f64 {
+        let mut propensity = self.rate;
+        for (&k, &n) in self.inputs.iter().zip(state) {
+            let k = u64::from(k);
+            if n < k {
+                return 0.0;
+            }
+            propensity *= (0..k).map(|j| (n - j) as f64).product::<f64>();
+        }
+        propensity
+    }
+
+    /// Fires the reaction in a state where it has positive propensity.
+    fn fire(&self, state: &mut [u64]) {
+        for ((x, &input), &output) in state.iter_mut().zip(&self.inputs).zip(&self.outputs) {
+            *x = *x - u64::from(input) + u64::from(output);
+        }
+    }
+}
+
+/// A network of reactions between species indexed by integers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReactionNetwork {
+    n_species: usize,
+    reactions: Vec<Reaction>,
+}
+
+impl ReactionNetwork {
+    /// Creates a network with the given number of species and no reactions.
+    pub fn new(n_species: usize) -> Self {
+        Self { n_species, reactions: Vec::new() }
+    }
+
+    /// Gets the number of species in the network.
+    pub fn n_species(&self) -> usize {
+        self.n_species
+    }
+
+    /// Iterates over the reactions in the network.
+    pub fn reactions(&self) -> impl ExactSizeIterator<Item = &Reaction> {
+        self.reactions.iter()
+    }
+
+    /// Adds a reaction to the network, returning its index.
+    ///
+    /// Panics if the rate is negative or not finite, or if the inputs or
+    /// outputs do not have one entry per species.
+    pub fn add_reaction(&mut self, rate: f64, inputs: Vec<u32>, outputs: Vec<u32>) -> usize {
+        assert!(rate.is_finite() && rate >= 0.0, "Rate should be nonnegative");
+        assert_eq!(inputs.len(), self.n_species, "Inputs should have one entry per species");
+        assert_eq!(outputs.len(), self.n_species, "Outputs should have one entry per species");
+        self.reactions.push(Reaction { rate, inputs, outputs });
+        self.reactions.len() - 1
+    }
+}
+
+/// An event in a trajectory: the firing of a reaction at some time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SSAEvent {
+    /// Time of the event.
+    pub time: f64,
+
+    /// Index of the reaction that fired.
+    pub reaction: usize,
+}
+
+/// A trajectory sampled by the stochastic simulation algorithm.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SSATrajectory {
+    /// Initial values of the species.
+    pub initial_values: Vec<u64>,
+
+    /// Time at which the trajectory starts.
+    pub start_time: f64,
+
+    /// Events in the trajectory, in chronological order.
+    pub events: Vec<SSAEvent>,
+
+    /// Whether the simulation stopped at the maximum number of events before
+    /// reaching the end time.
+    pub truncated: bool,
+}
+
+impl SSATrajectory {
+    /// Iterates over the states of the trajectory with the times at which they
+    /// are entered, starting with the initial state.
+    pub fn states<'a>(
+        &'a self,
+        network: &'a ReactionNetwork,
+    ) -> impl Iterator<Item = (f64, Vec<u64>)> + 'a {
+        let mut state = self.initial_values.clone();
+        let initial = (self.start_time, state.clone());
+        std::iter::once(initial).chain(self.events.iter().map(move |event| {
+            network.reactions[event.reaction].fire(&mut state);
+            (event.time, state.clone())
+        }))
+    }
+
+    /// Samples the state of the trajectory at the given times, in sorted order.
+    ///
+    /// The state at a time is that entered by the last event at or before it.
+    pub fn sample(&self, network: &ReactionNetwork, times: &[f64]) -> Vec<Vec<u64>> {
+        let mut states = self.states(network).peekable();
+        let mut current = states.next().expect("Trajectory has an initial state").1;
+        times
+            .iter()
+            .map(|&t| {
+                while let Some((_, state)) = states.next_if(|(time, _)| *time <= t) {
+                    current = state;
+                }
+                current.clone()
+            })
+            .collect()
+    }
+
+    /// Gets the final state of the trajectory.
+    pub fn final_state(&self, network: &ReactionNetwork) -> Vec<u64> {
+        self.states(network).last().expect("Trajectory has an initial state").1
+    }
+}
+
+/// Default seed for stochastic simulation.
+pub const DEFAULT_SEED: u64 = 0;
+
+/// Default maximum number of events in a trajectory.
+pub const DEFAULT_MAX_EVENTS: usize = 1_000_000;
+
+/// A stochastic simulation problem ready to be solved.
+///
+/// Analogous to an ODE problem, a stochastic simulation problem comprises a
+/// [reaction network](ReactionNetwork) plus the initial values of the species,
+/// the time span, and the seed of the random number generator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SSAProblem {
+    network: ReactionNetwork,
+    initial_values: Vec<u64>,
+    start_time: f64,
+    end_time: f64,
+    seed: u64,
+    max_events: usize,
+}
+
+impl SSAProblem {
+    /// Creates a new stochastic simulation problem.
+    ///
+    /// Panics if the initial values do not have one entry per species.
+    pub fn new(network: ReactionNetwork, initial_values: Vec<u64>) -> Self {
+        assert_eq!(initial_values.len(), network.n_species, "Initial values should match species");
+        SSAProblem {
+            network,
+            initial_values,
+            start_time: 0.0,
+            end_time: 0.0,
+            seed: DEFAULT_SEED,
+            max_events: DEFAULT_MAX_EVENTS,
+        }
+    }
+
+    /// Gets the reaction network of the problem.
+    pub fn network(&self) -> &ReactionNetwork {
+        &self.network
+    }
+
+    /// Sets the start time for the problem.
+    pub fn start_time(mut self, t: f64) -> Self {
+        self.start_time = t;
+        self
+    }
+
+    /// Sets the end time for the problem.
+    pub fn end_time(mut self, t: f64) -> Self {
+        self.end_time = t;
+        self
+    }
+
+    /// Sets the time span (start and end time) for the problem.
+    pub fn time_span(mut self, tspan: (f64, f64)) -> Self {
+        (self.start_time, self.end_time) = tspan;
+        self
+    }
+
+    /// Sets the seed of the random number generator.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the maximum number of events in a trajectory.
+    pub fn max_events(mut self, n: usize) -> Self {
+        self.max_events = n;
+        self
+    }
+
+    /// Simulates a single trajectory.
+    pub fn simulate(&self) -> SSATrajectory {
+        self.run(&mut Xoshiro256::new(self.seed))
+    }
+
+    /// Simulates independent trajectories.
+    ///
+    /// The first trajectory is the one returned by [`simulate`](Self::simulate).
+    pub fn simulate_runs(&self, n: usize) -> Vec<SSATrajectory> {
+        let mut stream = Xoshiro256::new(self.seed);
+        (0..n)
+            .map(|_| {
+                let trajectory = self.run(&mut stream.clone());
+                stream.jump();
+                trajectory
+            })
+            .collect()
+    }
+
+    fn run(&self, rng: &mut Xoshiro256) -> SSATrajectory {
+        let reactions = &self.network.reactions;
+        let mut state = self.initial_values.clone();
+        let mut propensities = vec![0.0; reactions.len()];
+        let mut time = self.start_time;
+        let mut events = Vec::new();
+        let mut truncated = false;
+        loop {
+            for (p, reaction) in propensities.iter_mut().zip(reactions) {
+                *p = reaction.propensity(&state);
+            }
+            let total: f64 = propensities.iter().sum();
+            if total <= 0.0 {
+                break;
+            }
+            time += -(1.0 - rng.next_f64()).ln() / total;
+            if time > self.end_time {
+                break;
+            }
+            if events.len() >= self.max_events {
+                truncated = true;
+                break;
+            }
+            let mut threshold = rng.next_f64() * total;
+            let reaction = propensities
+                .iter()
+                .position(|&p| {
+                    if threshold < p {
+                        return true;
+                    }
+                    threshold -= p;
+                    false
+                })
+                // Guard against rounding error in the cumulative sum.
+                .or_else(|| propensities.iter().rposition(|&p| p > 0.0))
+                .unwrap();
+            reactions[reaction].fire(&mut state);
+            events.push(SSAEvent { time, reaction });
+        }
+        SSATrajectory {
+            initial_values: self.initial_values.clone(),
+            start_time: self.start_time,
+            events,
+            truncated,
+        }
+    }
+}
+
+/// The xoshiro256++ random number generator of Blackman and Vigna.
+#[derive(Clone, Debug)]
+struct Xoshiro256 {
+    state: [u64; 4],
+}
+
+impl Xoshiro256 {
+    /// Seeds the generator using SplitMix64, as recommended by the authors.
+    fn new(seed: u64) -> Self {
+        let mut z = seed;
+        let mut splitmix = || {
+            z = z.wrapping_add(0x9e3779b97f4a7c15);
+            let mut x = z;
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+            x ^ (x >> 31)
+        };
+        Self {
+            state: [splitmix(), splitmix(), splitmix(), splitmix()],
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Samples uniformly from the unit interval [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (-53.0f64).exp2()
+    }
+
+    /// Advances the generator by 2<sup>128</sup> steps.
+    fn jump(&mut self) {
+        const JUMP: [u64; 4] =
+            [0x180ec6d33cfd0aba, 0xd5a61266f0c9392c, 0xa9582618e03fc9aa, 0x39abdc4529b1661c];
+        let mut state = [0; 4];
+        for word in JUMP {
+            for bit in 0..64 {
+                if word & (1 << bit) != 0 {
+                    for (x, y) in state.iter_mut().zip(self.state) {
+                        *x ^= y;
+                    }
+                }
+                self.next_u64();
+            }
+        }
+        self.state = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exponential decay `A -> 0` with unit rate.
+    fn decay(n: u64) -> SSAProblem {
+        let mut network = ReactionNetwork::new(1);
+        network.add_reaction(1.0, vec![1], vec![0]);
+        SSAProblem::new(network, vec![n]).end_time(1.0)
+    }
+
+    #[test]
+    fn decay_mean() {
+        let problem = decay(100);
+        let runs = problem.simulate_runs(200);
+        assert_eq!(runs[0], problem.simulate());
+        assert_ne!(runs[0], runs[1]);
+        assert_eq!(problem.clone().seed(1).simulate(), problem.clone().seed(1).simulate());
+
+        // The expected number remaining is `n exp(-t)`.
+        let finals: Vec<_> = runs.iter().map(|run| run.final_state(problem.network())[0]).collect();
+        let mean = finals.iter().sum::<u64>() as f64 / finals.len() as f64;
+        assert!((mean - 100.0 * (-1.0f64).exp()).abs() < 2.0);
+        assert!(runs.iter().all(|run| run.events.windows(2).all(|w| w[0].time <= w[1].time)));
+    }
+
+    #[test]
+    fn sir_trajectory() {
+        // Infection `S + I -> 2I` and recovery `I -> R`.
+        let mut network = ReactionNetwork::new(3);
+        network.add_reaction(0.01, vec![1, 1, 0], vec![0, 2, 0]);
+        network.add_reaction(0.1, vec![0, 1, 0], vec![0, 0, 1]);
+        let problem = SSAProblem::new(network, vec![99, 1, 0]).time_span((0.0, 100.0)).seed(7);
+        let trajectory = problem.simulate();
+        assert!(!trajectory.truncated);
+        assert!(trajectory.states(problem.network()).all(|(_, x)| x.iter().sum::<u64>() == 100));
+
+        let samples = trajectory.sample(problem.network(), &[0.0, 100.0]);
+        assert_eq!(samples[0], vec![99, 1, 0]);
+        assert_eq!(samples[1], trajectory.final_state(problem.network()));
+
+        // The epidemic takes off, so the trajectory has more than three events.
+        let truncated = problem.max_events(3).simulate();
+        assert!(truncated.truncated);
+        assert_eq!(truncated.events, trajectory.events[..3]);
+    }
+
+    #[test]
+    fn propensities() {
+        let reaction = Reaction {
+            rate: 2.0,
+            inputs: vec![2, 1],
+            outputs: vec![0, 0],
+        };
+        assert_eq!(reaction.propensity(&[3, 5]), 2.0 * 6.0 * 5.0);
+        assert_eq!(reaction.propensity(&[1, 5]), 0.0);
+    }
+}
@@ -0,0 +1,291 @@
+//! Post-processing of time series produced by simulations.
+//!
+//! A time series is given by a slice of increasing times and a slice of values
+//! of the same length. The utilities here find peaks, estimate the period of
+//! oscillations by autocorrelation, detect when a series settles to an
+//! equilibrium, and compute summary statistics. They are meant to be applied to
+//! the output of simulators, which is sampled finely enough that no
+//! interpolation beyond linear is needed.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+/// Minimum prominence of the peaks reported in a summary, relative to the range
+/// of the series.
+const RELATIVE_PROMINENCE: f32 = 0.05;
+
+/// Tolerance for settling to an equilibrium, relative to the range of the
+/// series.
+const RELATIVE_SETTLING_TOLERANCE: f32 = 1e-3;
+
+/// Minimum autocorrelation of a periodic series at its period.
+const MIN_AUTOCORRELATION: f32 = 0.5;
+
+/// A peak, or local maximum, of a time series.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct Peak {
+    /// Index of the peak in the series.
+    pub index: usize,
+
+    /// Time of the peak.
+    pub time: f32,
+
+    /// Value at the peak.
+    pub value: f32,
+
+    /// Prominence of the peak: its height above the higher of the lowest
+    /// points separating it from higher values on either side.
+    pub prominence: f32,
+}
+
+/// Summary of a time series.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct TimeSeriesSummary {
+    /// Minimum value.
+    pub min: f32,
+
+    /// Time at which the minimum is first attained.
+    #[cfg_attr(feature = "serde", serde(rename = "timeOfMin"))]
+    pub time_of_min: f32,
+
+    /// Maximum value.
+    pub max: f32,
+
+    /// Time at which the maximum is first attained.
+    #[cfg_attr(feature = "serde", serde(rename = "timeOfMax"))]
+    pub time_of_max: f32,
+
+    /// Mean value over time, by the trapezoidal rule.
+    pub mean: f32,
+
+    /// Final value.
+    #[cfg_attr(feature = "serde", serde(rename = "finalValue"))]
+    pub final_value: f32,
+
+    /// Peaks whose prominence is at least 5% of the range of the series.
+    pub peaks: Vec<Peak>,
+
+    /// Period of oscillation, if the series is periodic.
+    pub period: Option<f32>,
+
+    /// Time from which the series stays at its final value, if it settles.
+    #[cfg_attr(feature = "serde", serde(rename = "settlingTime"))]
+    pub settling_time: Option<f32>,
+}
+
+/// Summarizes a time series.
+///
+/// Returns `None` if the series is empty.
+pub fn summarize(times: &[f32], values: &[f32]) -> Option<TimeSeriesSummary> {
+    assert_eq!(times.len(), values.len(), "Times and values should have the same length");
+    let (i_min, &min) = values.iter().enumerate().min_by(|(_, x), (_, y)| x.total_cmp(y))?;
+    let (i_max, &max) = values.iter().enumerate().rev().max_by(|(_, x), (_, y)| x.total_cmp(y))?;
+    let range = max - min;
+    Some(TimeSeriesSummary {
+        min,
+        time_of_min: times[i_min],
+        max,
+        time_of_max: times[i_max],
+        mean: time_average(times, values),
+        final_value: *values.last()?,
+        peaks: find_peaks(times, values, RELATIVE_PROMINENCE * range),
+        period: estimate_period(times, values),
+        settling_time: settling_time(times, values, RELATIVE_SETTLING_TOLERANCE * range),
+    })
+}
+
+/// Computes the mean value of a time series over time.
+///
+/// The series is integrated by the trapezoidal rule. If the series has zero
+/// duration, the plain mean of the values is returned.
+pub fn time_average(times: &[f32], values: &[f32]) -> f32 {
+    let duration = times.last().zip(times.first()).map_or(0.0, |(end, start)| end - start);
+    if duration <= 0.0 {
+        return values.iter().sum::<f32>() / values.len().max(1) as f32;
+    }
+    let integral: f32 = times
+        .windows(2)
+        .zip(values.windows(2))
+        .map(|(t, x)| (t[1] - t[0]) * (x[0] + x[1]) / 2.0)
+        .sum();
+    integral / duration
+}
+
+/// Finds the peaks of a time series with at least the given prominence.
+///
+/// A peak on a plateau is placed at the middle of the plateau. The endpoints
+/// of the series are never peaks, since it is unknown whether the series rises
+/// beyond them.
+pub fn find_peaks(times: &[f32], values: &[f32], min_prominence: f32) -> Vec<Peak> {
+    let n = values.len();
+    let mut peaks = Vec::new();
+    let mut i = 1;
+    while i + 1 < n {
+        if values[i - 1] >= values[i] {
+            i += 1;
+            continue;
+        }
+        let mut j = i;
+        while j + 1 < n && values[j + 1] == values[i] {
+            j += 1;
+        }
+        if j + 1 < n && values[j + 1] < values[i] {
+            let index = (i + j) / 2;
+            let prominence = prominence(values, i, j);
+            if prominence >= min_prominence {
+                peaks.push(Peak {
+                    index,
+                    time: times[index],
+                    value: values[index],
+                    prominence,
+                });
+            }
+        }
+        i = j + 1;
+    }
+    peaks
+}
+
+/// Computes the prominence of a peak occupying the given range of indices.
+fn prominence(values: &[f32], start: usize, end: usize) -> f32 {
+    let value = values[start];
+    let base = |iter: &mut dyn Iterator<Item = &f32>| {
+        iter.take_while(|x| **x <= value).fold(value, |min, x| min.min(*x))
+    };
+    let left = base(&mut values[..start].iter().rev());
+    let right = base(&mut values[end + 1..].iter());
+    value - left.max(right)
+}
+
+/// Estimates the period of an oscillating time series by autocorrelation.
+///
+/// The series is resampled uniformly and its autocorrelation computed, as the
+/// correlation between the series and its shift by each lag. The period is the
+/// lag of the first maximum of the autocorrelation after it becomes negative,
+/// refined by parabolic interpolation. Returns `None` if the series does not
+/// complete two periods or is not strongly periodic.
+pub fn estimate_period(times: &[f32], values: &[f32]) -> Option<f32> {
+    let n = values.len();
+    let (start, end) = (*times.first()?, *times.last()?);
+    if n < 4 || end <= start {
+        return None;
+    }
+    let dt = (end - start) / (n - 1) as f32;
+    let samples: Vec<f32> =
+        (0..n).map(|k| interpolate(times, values, start + k as f32 * dt)).collect();
+    let r: Vec<f32> = (0..=n / 2)
+        .map(|lag| correlation(&samples[..n - lag], &samples[lag..]))
+        .collect();
+    let first_negative = r.iter().position(|&x| x < 0.0)?;
+    let lag = (first_negative..r.len() - 1)
+        .find(|&k| r[k] >= r[k - 1] && r[k] > r[k + 1] && r[k] >= MIN_AUTOCORRELATION)?;
+    let (a, b, c) = (r[lag - 1], r[lag], r[lag + 1]);
+    let offset = (a - c) / (2.0 * (a - 2.0 * b + c));
+    Some((lag as f32 + offset) * dt)
+}
+
+/// Pearson correlation between two series of the same length.
+fn correlation(xs: &[f32], ys: &[f32]) -> f32 {
+    let mean = |zs: &[f32]| zs.iter().sum::<f32>() / zs.len() as f32;
+    let (mx, my) = (mean(xs), mean(ys));
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        sxy += (x - mx) * (y - my);
+        sxx += (x - mx) * (x - mx);
+        syy += (y - my) * (y - my);
+    }
+    if sxx > 0.0 && syy > 0.0 {
+        sxy / (sxx * syy).sqrt()
+    } else {
+        0.0
+    }
+}
+
+/// Time from which a time series stays within a tolerance of its final value.
+///
+/// Returns `None` if the series only reaches its final value at the last time,
+/// so that it cannot be said to have settled.
+pub fn settling_time(times: &[f32], values: &[f32], tolerance: f32) -> Option<f32> {
+    let last = *values.last()?;
+    let unsettled = values.iter().rposition(|x| (x - last).abs() > tolerance);
+    match unsettled {
+        None => times.first().copied(),
+        Some(i) if i + 2 < values.len() => Some(times[i + 1]),
+        Some(_) => None,
+    }
+}
+
+/// Evaluates a time series at a time by linear interpolation.
+pub(crate) fn interpolate(times: &[f32], values: &[f32], t: f32) -> f32 {
+    let i = times.partition_point(|&s| s <= t);
+    if i == 0 {
+        return values[0];
+    }
+    if i == times.len() {
+        return values[i - 1];
+    }
+    let (t0, t1) = (times[i - 1], times[i]);
+    let w = (t - t0) / (t1 - t0);
+    values[i - 1] * (1.0 - w) + values[i] * w
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    fn grid(n: usize, end: f32) -> Vec<f32> {
+        (0..n).map(|k| k as f32 * end / (n - 1) as f32).collect()
+    }
+
+    #[test]
+    fn peaks_and_period() {
+        let times = grid(1001, 10.0);
+        let values: Vec<f32> = times.iter().map(|t| (2.0 * PI * t / 2.5).sin()).collect();
+        let peaks = find_peaks(&times, &values, 0.5);
+        let peak_times: Vec<_> = peaks.iter().map(|p| p.time).collect();
+        assert_eq!(peak_times.len(), 4);
+        for (t, expected) in peak_times.iter().zip([0.625, 3.125, 5.625, 8.125]) {
+            assert!((t - expected).abs() < 0.02);
+        }
+        assert!((peaks[1].prominence - 2.0).abs() < 1e-3);
+
+        let period = estimate_period(&times, &values).unwrap();
+        assert!((period - 2.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn plateau_and_prominence() {
+        let times = grid(9, 8.0);
+        let values = [0.0, 2.0, 2.0, 2.0, 1.0, 1.5, 1.0, 3.0, 0.0];
+        let peaks = find_peaks(&times, &values, 0.0);
+        let summary: Vec<_> = peaks.iter().map(|p| (p.index, p.prominence)).collect();
+        assert_eq!(summary, vec![(2, 1.0), (5, 0.5), (7, 3.0)]);
+        assert_eq!(find_peaks(&times, &values, 0.8).len(), 2);
+    }
+
+    #[test]
+    fn logistic_summary() {
+        let times = grid(201, 20.0);
+        let values: Vec<f32> = times.iter().map(|t| 1.0 / (1.0 + 99.0 * (-t).exp())).collect();
+        let summary = summarize(&times, &values).unwrap();
+        assert_eq!((summary.min, summary.time_of_min), (0.01, 0.0));
+        assert!(summary.peaks.is_empty());
+        assert_eq!(summary.period, None);
+        let settling = summary.settling_time.unwrap();
+        assert!(settling > 10.0 && settling < 15.0);
+        assert!(summary.mean > 0.7 && summary.mean < 0.8);
+
+        assert_eq!(settling_time(&[0.0, 1.0, 2.0], &[0.0, 0.0, 1.0], 0.1), None);
+        assert_eq!(settling_time(&[0.0, 1.0], &[1.0, 1.0], 0.0), Some(0.0));
+    }
+}
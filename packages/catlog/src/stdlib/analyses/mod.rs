@@ -5,10 +5,13 @@ pub(crate) mod petri;
 #[cfg(feature = "ode")]
 pub mod ode;
 
+pub mod query;
 pub mod reachability;
 
 #[cfg(feature = "sql")]
 pub mod sql;
 
+pub mod summary;
+
 #[cfg(feature = "stochastic")]
 pub mod stochastic;
@@ -22,7 +22,7 @@ use crate::dbl::{
     theory::{ModalMorType, ModalObType, TabMorType, TabObType, Unital},
 };
 use crate::one::FgCategory;
-use crate::simulate::ode::{NumericalPolynomialSystem, ODEProblem, PolynomialSystem};
+use crate::simulate::ode::{NumericalPolynomialSystem, ODEProblem, ODEScalar, PolynomialSystem};
 use crate::stdlib::analyses::petri::transition_interface;
 use crate::zero::{QualifiedName, alg::Polynomial, name, rig::Monomial};
 
@@ -184,6 +184,14 @@ pub struct MassActionProblemData {
 
     /// Duration of simulation.
     pub duration: f32,
+
+    /// Whether to solve the ODEs in double precision.
+    ///
+    /// Single precision is faster, but long simulations can accumulate
+    /// visible rounding error.
+    #[cfg_attr(feature = "serde", serde(default, rename = "doublePrecision"))]
+    #[cfg_attr(feature = "serde-wasm", tsify(optional))]
+    pub double_precision: bool,
 }
 
 /// Mass-action ODE analysis for Petri nets.
@@ -447,21 +455,21 @@ pub fn extend_mass_action_scalars(
 }
 
 /// Builds the numerical ODE analysis for a mass-action system whose scalars have been substituted.
-pub fn into_mass_action_analysis(
-    sys: PolynomialSystem<QualifiedName, f32, i8>,
+pub fn into_mass_action_analysis<T: ODEScalar>(
+    sys: PolynomialSystem<QualifiedName, T, i8>,
     data: MassActionProblemData,
-) -> ODEAnalysis<NumericalPolynomialSystem<i8>> {
+) -> ODEAnalysis<NumericalPolynomialSystem<i8, T>, T> {
     let ob_index: IndexMap<_, _> =
         sys.components.keys().cloned().enumerate().map(|(i, x)| (x, i)).collect();
     let n = ob_index.len();
 
     let initial_values = ob_index
         .keys()
-        .map(|ob| data.initial_values.get(ob).copied().unwrap_or_default());
+        .map(|ob| data.initial_values.get(ob).copied().unwrap_or_default().into());
     let x0 = DVector::from_iterator(n, initial_values);
 
     let num_sys = sys.to_numerical();
-    let problem = ODEProblem::new(num_sys, x0).end_time(data.duration);
+    let problem = ODEProblem::new(num_sys, x0).end_time(data.duration.into());
 
     ODEAnalysis::new(problem, ob_index)
 }
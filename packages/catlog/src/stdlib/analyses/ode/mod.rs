@@ -1,18 +1,26 @@
 //! ODE analyses of models.
 
 use std::collections::HashMap;
+use std::ops::Add;
 
 use derivative::Derivative;
 use derive_more::Constructor;
 use indexmap::IndexMap;
-use ode_solvers::dop_shared::IntegrationError;
+use nalgebra::{DVector, RealField};
+use num_traits::{One, Pow};
+use ode_solvers::dop_shared::{IntegrationError, SolverResult};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "serde-wasm")]
 use tsify::Tsify;
 
-use crate::simulate::ode::{ODEProblem, ODESystem};
+use crate::simulate::goodness_of_fit::{self, FitReport, ObservedSeries};
+use crate::simulate::ode::{
+    EquilibriumSolver, NumericalPolynomialSystem, ODEProblem, ODEScalar, ODESystem,
+    QSSADiagnostics, QSSAError, QSSASystem, Stability,
+};
+use crate::simulate::time_series::{self, TimeSeriesSummary};
 use crate::zero::{QualifiedName, alg::Polynomial};
 
 /// Symbolic parameter in a polynomial system.
@@ -26,49 +34,301 @@ pub type Parameter<Id> = Polynomial<Id, f32, i8>;
 #[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct ODESolution {
     /// Values of time variable for the duration of the simulation.
-    pub(in crate::stdlib::analyses) time: Vec<f32>,
+    pub(in crate::stdlib::analyses) time: Vec<f64>,
 
     /// Values of state variables for the duration of the simulation.
-    pub(in crate::stdlib::analyses) states: HashMap<QualifiedName, Vec<f32>>,
+    pub(in crate::stdlib::analyses) states: HashMap<QualifiedName, Vec<f64>>,
+
+    /// Seed of the random number generator, if the simulation is stochastic.
+    ///
+    /// Echoing the seed back with the solution allows a simulation to be
+    /// reproduced exactly by passing the same seed again.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    #[cfg_attr(feature = "serde-wasm", tsify(optional))]
+    pub(in crate::stdlib::analyses) seed: Option<u64>,
+}
+
+impl ODESolution {
+    /// Gets the seed used to produce the solution, if any.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Summarizes the time series of each state variable.
+    ///
+    /// See [`time_series::summarize`] for details.
+    pub fn summarize(&self) -> HashMap<QualifiedName, TimeSeriesSummary> {
+        let time = single_precision(&self.time);
+        self.states
+            .iter()
+            .filter_map(|(ob, values)| {
+                let values = single_precision(values);
+                Some((ob.clone(), time_series::summarize(&time, &values)?))
+            })
+            .collect()
+    }
+
+    /// Computes the goodness of fit of the solution to observed data.
+    ///
+    /// Observations of variables not in the solution are ignored. The overall
+    /// report pools the residuals of all variables, so it is only meaningful
+    /// when the variables are measured on comparable scales. See
+    /// [`goodness_of_fit`] for details.
+    pub fn goodness_of_fit(
+        &self,
+        observed: &HashMap<QualifiedName, ObservedSeries>,
+        sigma: Option<f32>,
+    ) -> ODEFitReport {
+        let time = single_precision(&self.time);
+        let mut variables = HashMap::new();
+        let mut pooled = Vec::new();
+        for (ob, series) in observed {
+            let Some(values) = self.states.get(ob) else {
+                continue;
+            };
+            let residuals = goodness_of_fit::residuals(&time, &single_precision(values), series);
+            if let Some(report) = FitReport::from_residuals(&residuals, sigma) {
+                variables.insert(ob.clone(), report);
+            }
+            pooled.extend(residuals);
+        }
+        ODEFitReport {
+            variables,
+            overall: FitReport::from_residuals(&pooled, sigma),
+        }
+    }
+}
+
+/// Rounds values of a solution to single precision, for summary statistics.
+fn single_precision(values: &[f64]) -> Vec<f32> {
+    values.iter().map(|&x| x as f32).collect()
+}
+
+/// Goodness of fit of an ODE solution to observed data.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ODEFitReport {
+    /// Goodness of fit of each observed state variable.
+    pub variables: HashMap<QualifiedName, FitReport>,
+
+    /// Goodness of fit of all observations together, if there are any.
+    pub overall: Option<FitReport>,
+}
+
+/// Equilibrium of an ODE analysis of a model.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ODEEquilibrium {
+    /// Values of state variables at equilibrium.
+    pub state: HashMap<QualifiedName, f32>,
+
+    /// Stability of the equilibrium.
+    pub stability: Stability,
+}
+
+/// A quantity conserved by an ODE analysis of a model.
+///
+/// The variable is eliminated from the simulation by substituting the total
+/// minus the linear combination of the other variables.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ConservedQuantity {
+    /// The eliminated variable.
+    pub variable: QualifiedName,
+
+    /// Conserved total, determined by the initial values.
+    pub total: f32,
+
+    /// Coefficients of the other variables in the conserved quantity.
+    pub coefficients: HashMap<QualifiedName, f32>,
 }
 
 /// Data needed to simulate and interpret an ODE analysis of a model.
+///
+/// The ODE is solved in single precision unless otherwise specified. Solving in
+/// double precision avoids accumulating rounding error over long simulations,
+/// and the solution keeps the full precision of the solver.
 #[derive(Constructor)]
-pub struct ODEAnalysis<Sys> {
+pub struct ODEAnalysis<Sys, T = f32> {
     /// ODE problem for the analysis.
-    pub problem: ODEProblem<Sys>,
+    pub problem: ODEProblem<Sys, T>,
 
     /// Map from IDs in model (usually object IDs) to variable indices.
     pub variable_index: IndexMap<QualifiedName, usize>,
 }
 
-impl<Sys> ODEAnalysis<Sys> {
+impl<Sys, T: ODEScalar> ODEAnalysis<Sys, T> {
     /// Solves the ODE with reasonable default settings and collects results.
     pub fn solve_with_defaults(self) -> Result<ODESolution, IntegrationError>
     where
-        Sys: ODESystem,
+        Sys: ODESystem<T>,
     {
         // ODE solver will fail in the degenerate case of an empty system.
         if self.variable_index.is_empty() {
             return Ok(Default::default());
         }
 
-        let duration = self.problem.end_time - self.problem.start_time;
-        let output_step_size = (duration / 100.0).min(0.01f32);
-        let result = self.problem.solve_dopri5(output_step_size)?;
+        let result = self.problem.solve_dopri5(default_output_step_size(&self.problem))?;
+        Ok(self.collect_solution(result))
+    }
+
+    /// Solves the ODE with output at the given times and collects results.
+    ///
+    /// The solver has adaptive step size, so the output times can be chosen
+    /// for plotting, such as a uniform grid, without sacrificing accuracy.
+    pub fn solve_at(self, times: &[f32]) -> Result<ODESolution, IntegrationError>
+    where
+        Sys: ODESystem<T>,
+    {
+        if self.variable_index.is_empty() {
+            return Ok(Default::default());
+        }
+        let times: Vec<T> = times.iter().map(|&t| t.into()).collect();
+        let result = self.problem.solve_dopri5_at(&times)?;
+        Ok(self.collect_solution(result))
+    }
 
+    fn collect_solution(self, result: SolverResult<T, DVector<T>>) -> ODESolution {
         let (t_out, x_out) = result.get();
-        Ok(ODESolution {
-            time: t_out.clone(),
+        ODESolution {
+            time: t_out.iter().map(|&t| t.into()).collect(),
             states: self
                 .variable_index
                 .into_iter()
-                .map(|(ob, i)| (ob, x_out.iter().map(|x| x[i]).collect()))
+                .map(|(ob, i)| (ob, x_out.iter().map(|x| x[i].into()).collect()))
                 .collect(),
-        })
+            seed: None,
+        }
     }
 }
 
+impl<Sys> ODEAnalysis<Sys> {
+    /// Finds equilibria of the ODE and classifies their stability.
+    ///
+    /// Newton's method is started from the initial values of the problem and
+    /// then from each of the extra guesses, given as maps from IDs in the model
+    /// to values. Variables missing from a guess take their initial values.
+    pub fn equilibria(&self, guesses: &[HashMap<QualifiedName, f32>]) -> Vec<ODEEquilibrium>
+    where
+        Sys: ODESystem,
+    {
+        if self.variable_index.is_empty() {
+            return Vec::new();
+        }
+        let initial = &self.problem.initial_values;
+        let guesses = guesses.iter().map(|guess| {
+            let mut x = initial.clone();
+            for (ob, value) in guess {
+                if let Some(&i) = self.variable_index.get(ob) {
+                    x[i] = *value;
+                }
+            }
+            x
+        });
+        let solver = EquilibriumSolver::new().time(self.problem.start_time);
+        solver
+            .equilibria(&self.problem.system, std::iter::once(initial.clone()).chain(guesses))
+            .into_iter()
+            .map(|eq| ODEEquilibrium {
+                state: self
+                    .variable_index
+                    .iter()
+                    .map(|(ob, &i)| (ob.clone(), eq.state[i]))
+                    .collect(),
+                stability: eq.stability,
+            })
+            .collect()
+    }
+}
+
+impl<Exp> ODEAnalysis<NumericalPolynomialSystem<Exp>>
+where
+    Exp: Clone + Ord,
+    f32: Pow<Exp, Output = f32>,
+{
+    /// Solves the ODE after eliminating variables by linear conservation laws.
+    ///
+    /// The settings are the same as in
+    /// [`solve_with_defaults`](Self::solve_with_defaults), and the solution
+    /// includes the eliminated variables. The conserved quantities used are
+    /// returned along with the solution.
+    pub fn solve_reduced(self) -> Result<(ODESolution, Vec<ConservedQuantity>), IntegrationError> {
+        if self.variable_index.is_empty() {
+            return Ok(Default::default());
+        }
+        let problem = self.problem.reduce_by_conservation_laws();
+        let names: HashMap<usize, &QualifiedName> =
+            self.variable_index.iter().map(|(ob, &i)| (i, ob)).collect();
+        let reduced = &problem.system;
+        let quantities = reduced
+            .laws()
+            .iter()
+            .zip(reduced.totals())
+            .map(|(law, total)| ConservedQuantity {
+                variable: names[&law.variable].clone(),
+                total: *total,
+                coefficients: law
+                    .coefficients
+                    .iter()
+                    .map(|(j, c)| (names[j].clone(), *c))
+                    .collect(),
+            })
+            .collect();
+
+        let result = problem.solve_dopri5(default_output_step_size(&problem))?;
+        let (t_out, y_out) = result.get();
+        let x_out = y_out.iter().map(|y| reduced.expand(y)).collect();
+        let result = SolverResult::new(t_out.clone(), x_out);
+        let analysis = ODEAnalysis::new(problem, self.variable_index);
+        Ok((analysis.collect_solution(result), quantities))
+    }
+}
+
+impl<Exp> ODEAnalysis<NumericalPolynomialSystem<Exp>>
+where
+    Exp: Clone + Ord + One + Add<Output = Exp>,
+    f32: Pow<Exp, Output = f32>,
+{
+    /// Reduces the ODE by the quasi-steady-state approximation.
+    ///
+    /// The given variables are assumed to be at equilibrium given the others,
+    /// which must be solvable linearly; IDs not in the analysis are ignored.
+    /// The reduced analysis has only the slow variables. The validity of the
+    /// approximation is diagnosed at the initial values.
+    pub fn quasi_steady_state(
+        self,
+        fast: &[QualifiedName],
+    ) -> Result<(ODEAnalysis<QSSASystem<Exp>>, QSSADiagnostics), QSSAError> {
+        let fast: Vec<_> =
+            fast.iter().filter_map(|ob| self.variable_index.get(ob).copied()).collect();
+        let problem = self.problem.quasi_steady_state(&fast)?;
+        let diagnostics = problem.system.diagnose(&problem.initial_values, problem.start_time);
+        let slow = problem.system.slow_variables();
+        let variable_index = self
+            .variable_index
+            .into_iter()
+            .filter_map(|(ob, i)| Some((ob, slow.binary_search(&i).ok()?)))
+            .collect();
+        Ok((ODEAnalysis::new(problem, variable_index), diagnostics))
+    }
+}
+
+/// Output step size used by default when solving an ODE problem.
+fn default_output_step_size<Sys, T: ODEScalar>(problem: &ODEProblem<Sys, T>) -> T {
+    let duration = problem.end_time - problem.start_time;
+    let (steps, max_step): (T, T) = (100.0f32.into(), 0.01f32.into());
+    RealField::min(duration / steps, max_step)
+}
+
 pub mod kuramoto;
 pub mod linear_ode;
 pub mod lotka_volterra;
@@ -15,7 +15,7 @@ use crate::{
         model::{FpDblModel, ModalDblModel, ModalOb, MutDblModel},
         theory::NonUnital,
     },
-    simulate::ode::{NumericalPolynomialSystem, ODEProblem, PolynomialSystem},
+    simulate::ode::{NumericalPolynomialSystem, ODEProblem, ODEScalar, PolynomialSystem},
     zero::{QualifiedName, alg::Polynomial, name, rig::Monomial},
 };
 
@@ -38,6 +38,14 @@ pub struct PolynomialODEProblemData {
 
     /// Duration of simulation.
     pub duration: f32,
+
+    /// Whether to solve the ODEs in double precision.
+    ///
+    /// Single precision is faster, but long simulations can accumulate
+    /// visible rounding error.
+    #[cfg_attr(feature = "serde", serde(default, rename = "doublePrecision"))]
+    #[cfg_attr(feature = "serde-wasm", tsify(optional))]
+    pub double_precision: bool,
 }
 
 /// Polynomial ODE analysis.
@@ -122,21 +130,21 @@ pub fn extend_polynomial_ode_scalars(
 }
 
 /// Builds the numerical ODE analysis for a system of polynomial ODEs whose scalars have been substituted.
-pub fn polynomial_ode_analysis(
-    sys: PolynomialSystem<QualifiedName, f32, i8>,
+pub fn polynomial_ode_analysis<T: ODEScalar>(
+    sys: PolynomialSystem<QualifiedName, T, i8>,
     data: PolynomialODEProblemData,
-) -> ODEAnalysis<NumericalPolynomialSystem<i8>> {
+) -> ODEAnalysis<NumericalPolynomialSystem<i8, T>, T> {
     let ob_index: IndexMap<_, _> =
         sys.components.keys().cloned().enumerate().map(|(i, x)| (x, i)).collect();
     let n = ob_index.len();
 
     let initial_values = ob_index
         .keys()
-        .map(|ob| data.initial_values.get(ob).copied().unwrap_or_default());
+        .map(|ob| data.initial_values.get(ob).copied().unwrap_or_default().into());
     let x0 = DVector::from_iterator(n, initial_values);
 
     let num_sys = sys.to_numerical();
-    let problem = ODEProblem::new(num_sys, x0).end_time(data.duration);
+    let problem = ODEProblem::new(num_sys, x0).end_time(data.duration.into());
 
     ODEAnalysis::new(problem, ob_index)
 }
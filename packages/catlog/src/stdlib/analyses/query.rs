@@ -0,0 +1,327 @@
+//! Conjunctive queries over models.
+//!
+//! A [conjunctive query](https://en.wikipedia.org/wiki/Conjunctive_query) is a
+//! select-where pattern: a list of atoms, each asserting that some objects have a
+//! given type or that there is a morphism between some objects, together with a
+//! list of variables to select. For example, the question "find all objects `x`
+//! with a morphism into `y`" is the query selecting `x` subject to the single atom
+//! `?f : x -> y`.
+//!
+//! Queries are evaluated by backtracking search over the generators of the model,
+//! binding variables one atom at a time. Results have set semantics: each distinct
+//! assignment of the selected variables is reported once.
+
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::dbl::model::{DiscreteDblModel, FpDblModel, MutDblModel};
+use crate::instrument::{Budget, BudgetExhausted, BudgetGuard, Instrument, SearchStats};
+use crate::one::{FgCategory, QualifiedPath};
+use crate::zero::QualifiedName;
+
+/// A term in a query atom: either a variable or a fixed generator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag", content = "content"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum Term {
+    /// A variable, to be bound by the query.
+    Var(String),
+
+    /// A generator of the model.
+    Const(QualifiedName),
+}
+
+impl Term {
+    /// Constructs a variable term.
+    pub fn var(name: impl Into<String>) -> Self {
+        Term::Var(name.into())
+    }
+}
+
+/// An atomic formula in a conjunctive query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "tag", content = "content"))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum Atom {
+    /// An object generator, optionally of a given type.
+    Ob {
+        /// Object generator.
+        ob: Term,
+        /// Type of the object, if constrained.
+        #[cfg_attr(feature = "serde", serde(rename = "obType"))]
+        ob_type: Option<QualifiedName>,
+    },
+
+    /// A morphism generator between two objects, optionally of a given type.
+    Mor {
+        /// Morphism generator.
+        mor: Term,
+        /// Domain of the morphism.
+        dom: Term,
+        /// Codomain of the morphism.
+        cod: Term,
+        /// Type of the morphism, if constrained.
+        #[cfg_attr(feature = "serde", serde(rename = "morType"))]
+        mor_type: Option<QualifiedPath>,
+    },
+}
+
+/// A conjunctive query over a model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct ConjunctiveQuery {
+    /// Variables to select, in order.
+    pub select: Vec<String>,
+
+    /// Atoms that must all hold.
+    #[cfg_attr(feature = "serde", serde(rename = "where"))]
+    pub atoms: Vec<Atom>,
+}
+
+/// Answers to a conjunctive query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(feature = "serde-wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct QueryResult {
+    /// Names of the selected variables.
+    pub columns: Vec<String>,
+
+    /// Bindings of the selected variables, one row per distinct answer.
+    pub rows: Vec<Vec<QualifiedName>>,
+
+    /// Counters reported by the search.
+    pub stats: SearchStats,
+}
+
+/// A query that cannot be evaluated.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum InvalidQuery {
+    /// A selected variable does not occur in any atom.
+    #[error("Selected variable `{0}` does not occur in the query")]
+    UnboundVar(String),
+
+    /// Evaluation of the query ran out of budget.
+    #[error(transparent)]
+    BudgetExhausted(#[from] BudgetExhausted),
+}
+
+type Bindings = HashMap<String, QualifiedName>;
+
+impl ConjunctiveQuery {
+    /// Evaluates the query on a model of a discrete double theory.
+    pub fn eval(&self, model: &DiscreteDblModel) -> Result<QueryResult, InvalidQuery> {
+        self.eval_within(model, Budget::unlimited())
+    }
+
+    /// Evaluates the query on a model, within a budget for the search.
+    pub fn eval_within(
+        &self,
+        model: &DiscreteDblModel,
+        budget: Budget,
+    ) -> Result<QueryResult, InvalidQuery> {
+        let bound: HashSet<_> = self.atoms.iter().flat_map(atom_vars).collect();
+        if let Some(var) = self.select.iter().find(|var| !bound.contains(var.as_str())) {
+            return Err(InvalidQuery::UnboundVar(var.clone()));
+        }
+
+        let mut seen = HashSet::new();
+        let mut rows = Vec::new();
+        let mut stats = SearchStats::default();
+        let guard = BudgetGuard::start(budget);
+        let mut emit = |bindings: &Bindings| {
+            let row: Vec<_> = self.select.iter().map(|var| bindings[var].clone()).collect();
+            if seen.insert(row.clone()) {
+                rows.push(row);
+            }
+        };
+        let mut bindings = Bindings::new();
+        search(model, &self.atoms, &mut bindings, &mut emit, &mut stats, &guard)?;
+        guard.stop(&mut stats);
+        Ok(QueryResult {
+            columns: self.select.clone(),
+            rows,
+            stats,
+        })
+    }
+}
+
+fn atom_vars(atom: &Atom) -> Vec<&str> {
+    let terms = match atom {
+        Atom::Ob { ob, .. } => vec![ob],
+        Atom::Mor { mor, dom, cod, .. } => vec![mor, dom, cod],
+    };
+    terms
+        .into_iter()
+        .filter_map(|term| match term {
+            Term::Var(var) => Some(var.as_str()),
+            Term::Const(_) => None,
+        })
+        .collect()
+}
+
+/// Tries to unify a term with a generator, extending the bindings if needed.
+///
+/// Returns whether unification succeeded and, if so, the variable newly bound.
+fn unify(term: &Term, value: &QualifiedName, bindings: &mut Bindings) -> (bool, Option<String>) {
+    match term {
+        Term::Const(name) => (name == value, None),
+        Term::Var(var) => match bindings.get(var) {
+            Some(bound) => (bound == value, None),
+            None => {
+                bindings.insert(var.clone(), value.clone());
+                (true, Some(var.clone()))
+            }
+        },
+    }
+}
+
+fn search(
+    model: &DiscreteDblModel,
+    atoms: &[Atom],
+    bindings: &mut Bindings,
+    emit: &mut impl FnMut(&Bindings),
+    stats: &mut SearchStats,
+    guard: &BudgetGuard,
+) -> Result<(), BudgetExhausted> {
+    stats.node();
+    guard.check(stats)?;
+    let Some((atom, rest)) = atoms.split_first() else {
+        stats.solution();
+        emit(bindings);
+        return Ok(());
+    };
+    match atom {
+        Atom::Ob { ob, ob_type } => {
+            for x in model.ob_generators() {
+                if ob_type.as_ref().is_some_and(|t| model.ob_generator_type(&x) != *t) {
+                    continue;
+                }
+                let (ok, new_var) = unify(ob, &x, bindings);
+                if ok {
+                    search(model, rest, bindings, emit, stats, guard)?;
+                } else {
+                    stats.backtrack();
+                }
+                if let Some(var) = new_var {
+                    bindings.remove(&var);
+                }
+            }
+        }
+        Atom::Mor { mor, dom, cod, mor_type } => {
+            for f in model.mor_generators() {
+                if mor_type.as_ref().is_some_and(|t| model.mor_generator_type(&f) != *t) {
+                    continue;
+                }
+                let (Some(x), Some(y)) = (model.get_dom(&f), model.get_cod(&f)) else {
+                    continue;
+                };
+                let mut new_vars = Vec::new();
+                let mut ok = true;
+                for (term, value) in [(mor, &f), (dom, x), (cod, y)] {
+                    let (unified, new_var) = unify(term, value, bindings);
+                    new_vars.extend(new_var);
+                    if !unified {
+                        ok = false;
+                        break;
+                    }
+                }
+                if ok {
+                    search(model, rest, bindings, emit, stats, guard)?;
+                } else {
+                    stats.backtrack();
+                }
+                for var in new_vars {
+                    bindings.remove(&var);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::one::Path;
+    use crate::stdlib::theories::th_schema;
+    use crate::zero::name;
+
+    fn schema() -> DiscreteDblModel {
+        let mut model = DiscreteDblModel::new(Rc::new(th_schema()));
+        model.add_ob(name("Person"), name("Entity"));
+        model.add_ob(name("Dept"), name("Entity"));
+        model.add_ob(name("String"), name("AttrType"));
+        model.add_mor(name("works_in"), name("Person"), name("Dept"), Path::Id(name("Entity")));
+        model.add_mor(name("manager"), name("Dept"), name("Person"), Path::Id(name("Entity")));
+        model.add_mor(name("name"), name("Person"), name("String"), name("Attr").into());
+        model.add_mor(name("title"), name("Dept"), name("String"), name("Attr").into());
+        model
+    }
+
+    #[test]
+    fn arrows_into() {
+        let query = ConjunctiveQuery {
+            select: vec!["x".into()],
+            atoms: vec![Atom::Mor {
+                mor: Term::var("f"),
+                dom: Term::var("x"),
+                cod: Term::Const(name("String")),
+                mor_type: None,
+            }],
+        };
+        let mut rows = query.eval(&schema()).unwrap().rows;
+        rows.sort();
+        assert_eq!(rows, vec![vec![name("Dept")], vec![name("Person")]]);
+    }
+
+    #[test]
+    fn join() {
+        // Entities with a round trip through another entity.
+        let query = ConjunctiveQuery {
+            select: vec!["x".into(), "y".into()],
+            atoms: vec![
+                Atom::Ob {
+                    ob: Term::var("x"),
+                    ob_type: Some(name("Entity")),
+                },
+                Atom::Mor {
+                    mor: Term::var("f"),
+                    dom: Term::var("x"),
+                    cod: Term::var("y"),
+                    mor_type: Some(Path::Id(name("Entity"))),
+                },
+                Atom::Mor {
+                    mor: Term::var("g"),
+                    dom: Term::var("y"),
+                    cod: Term::var("x"),
+                    mor_type: None,
+                },
+            ],
+        };
+        let result = query.eval(&schema()).unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.stats.solutions, 2);
+        assert!(result.stats.backtracks > 0);
+        assert!(matches!(
+            query.eval_within(&schema(), Budget::nodes(3)),
+            Err(InvalidQuery::BudgetExhausted(_))
+        ));
+
+        let query = ConjunctiveQuery { select: vec!["z".into()], atoms: vec![] };
+        assert_eq!(query.eval(&schema()), Err(InvalidQuery::UnboundVar("z".into())));
+    }
+}
@@ -0,0 +1,380 @@
+//! Continuous-time Markov chain semantics for state-transition diagrams.
+//!
+//! A state-transition diagram, presented as a model of the theory of categories,
+//! becomes a [continuous-time Markov
+//! chain](https://en.wikipedia.org/wiki/Continuous-time_Markov_chain) (CTMC) once
+//! each transition is assigned a rate. The objects of the model are the states of
+//! the chain and each morphism generator is a transition between states. Loops
+//! are ignored, since a transition from a state to itself has no effect.
+//!
+//! Besides long-run quantities such as the stationary distribution, we compute
+//! the transient distribution of the chain at given times using
+//! [uniformization](https://en.wikipedia.org/wiki/Uniformization_(probability_theory)).
+//! Unlike stochastic simulation, this gives the exact probabilities up to a
+//! truncation error, without sampling trajectories.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use nalgebra::{DMatrix, DVector};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::dbl::model::DiscreteDblModel;
+use crate::one::FgCategory;
+use crate::stdlib::analyses::ode::ODESolution;
+use crate::zero::QualifiedName;
+
+/// Data defining a continuous-time Markov chain problem for a model.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct MarkovChainProblemData {
+    /// Map from morphism IDs to transition rates (nonnegative reals).
+    pub rates: HashMap<QualifiedName, f32>,
+
+    /// Object IDs of target states for computing hitting probabilities.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub targets: Vec<QualifiedName>,
+}
+
+/// Data defining a transient analysis of a continuous-time Markov chain.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct MarkovChainTransientData {
+    /// Map from morphism IDs to transition rates (nonnegative reals).
+    pub rates: HashMap<QualifiedName, f32>,
+
+    /// Map from object IDs to initial probabilities.
+    ///
+    /// The values are normalized to sum to one, so they can also be given as
+    /// relative weights.
+    #[cfg_attr(feature = "serde", serde(rename = "initialValues"))]
+    pub initial_values: HashMap<QualifiedName, f32>,
+
+    /// Times at which to compute the distribution, in increasing order.
+    pub times: Vec<f32>,
+}
+
+/// Results of analyzing a continuous-time Markov chain.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct MarkovChainResult {
+    /// Stationary distribution of the chain, if it is unique.
+    #[cfg_attr(feature = "serde", serde(rename = "stationaryDistribution"))]
+    pub stationary_distribution: Option<HashMap<QualifiedName, f32>>,
+
+    /// Probabilities of eventually hitting the target states from each state.
+    ///
+    /// Omitted when no target states are given.
+    #[cfg_attr(feature = "serde", serde(rename = "hittingProbabilities"))]
+    pub hitting_probabilities: Option<HashMap<QualifiedName, f32>>,
+}
+
+/// A continuous-time Markov chain on the objects of a model.
+pub struct ContinuousTimeMarkovChain {
+    /// Generator matrix of the chain, also known as the transition-rate matrix.
+    ///
+    /// The off-diagonal entry in row `i` and column `j` is the rate of
+    /// transitioning from state `i` to state `j`, and each row sums to zero.
+    pub generator: DMatrix<f64>,
+
+    /// Map from object IDs to state indices.
+    pub state_index: IndexMap<QualifiedName, usize>,
+}
+
+impl ContinuousTimeMarkovChain {
+    /// Constructs the Markov chain of a model with the given transition rates.
+    ///
+    /// Transitions with no assigned rate are given rate zero. Transitions whose
+    /// source or target is not a state, as in an invalid model, are ignored.
+    pub fn from_model(model: &DiscreteDblModel, rates: &HashMap<QualifiedName, f32>) -> Self {
+        let state_index: IndexMap<_, _> =
+            model.ob_generators().enumerate().map(|(i, x)| (x, i)).collect();
+        let n = state_index.len();
+        let mut generator = DMatrix::zeros(n, n);
+        for f in model.mor_generators() {
+            let (Some(&i), Some(&j)) = (
+                state_index.get(&model.mor_generator_dom(&f)),
+                state_index.get(&model.mor_generator_cod(&f)),
+            ) else {
+                continue;
+            };
+            let rate = rates.get(&f).copied().unwrap_or_default() as f64;
+            if i != j {
+                generator[(i, j)] += rate;
+                generator[(i, i)] -= rate;
+            }
+        }
+        Self { generator, state_index }
+    }
+
+    /// Number of states of the chain.
+    pub fn num_states(&self) -> usize {
+        self.state_index.len()
+    }
+
+    /// Computes the stationary distribution of the chain.
+    ///
+    /// Solves the linear system `πQ = 0` subject to the entries of `π` summing to
+    /// one. Returns `None` when the stationary distribution is not unique, as
+    /// happens when the chain has more than one closed communicating class.
+    pub fn stationary_distribution(&self) -> Option<DVector<f64>> {
+        let n = self.num_states();
+        if n == 0 {
+            return None;
+        }
+        let mut system = self.generator.transpose();
+        system.row_mut(n - 1).fill(1.0);
+        let mut rhs = DVector::zeros(n);
+        rhs[n - 1] = 1.0;
+        let pi = system.lu().solve(&rhs)?;
+        // Guard against numerically singular systems slipping through.
+        pi.iter().all(|p| p.is_finite() && *p >= -1e-9).then_some(pi)
+    }
+
+    /// Computes the probabilities of eventually hitting a set of target states.
+    ///
+    /// The probability is one for the targets themselves and zero for states from
+    /// which no target is reachable. For the remaining states, the probabilities
+    /// are found by solving the linear system expressing that the probability at
+    /// each state is the rate-weighted average of the probabilities at the states
+    /// it transitions to.
+    pub fn hitting_probabilities(&self, targets: &[usize]) -> DVector<f64> {
+        let n = self.num_states();
+        let mut probs = DVector::zeros(n);
+        for &t in targets {
+            probs[t] = 1.0;
+        }
+
+        // Find the non-target states from which some target is reachable.
+        let mut reaches = vec![false; n];
+        let mut stack = targets.to_vec();
+        for &t in targets {
+            reaches[t] = true;
+        }
+        while let Some(j) = stack.pop() {
+            let sources: Vec<_> =
+                (0..n).filter(|&i| !reaches[i] && self.generator[(i, j)] > 0.0).collect();
+            for i in sources {
+                reaches[i] = true;
+                stack.push(i);
+            }
+        }
+        let unknowns: Vec<_> = (0..n).filter(|i| reaches[*i] && !targets.contains(i)).collect();
+        if unknowns.is_empty() {
+            return probs;
+        }
+
+        let m = unknowns.len();
+        let system = DMatrix::from_fn(m, m, |a, b| self.generator[(unknowns[a], unknowns[b])]);
+        let rhs = DVector::from_fn(m, |a, _| {
+            -targets.iter().map(|&t| self.generator[(unknowns[a], t)]).sum::<f64>()
+        });
+        let solution = system.lu().solve(&rhs).expect("Targets should be reachable");
+        for (a, &i) in unknowns.iter().enumerate() {
+            probs[i] = solution[a];
+        }
+        probs
+    }
+
+    /// Analyzes the chain, computing the quantities requested in the data.
+    pub fn analyze(&self, data: &MarkovChainProblemData) -> MarkovChainResult {
+        let stationary_distribution = self.stationary_distribution().map(|pi| self.by_state(&pi));
+        let hitting_probabilities = (!data.targets.is_empty()).then(|| {
+            let targets: Vec<_> =
+                data.targets.iter().filter_map(|x| self.state_index.get(x).copied()).collect();
+            self.by_state(&self.hitting_probabilities(&targets))
+        });
+        MarkovChainResult {
+            stationary_distribution,
+            hitting_probabilities,
+        }
+    }
+
+    /// Computes the distribution of the chain at the given times by uniformization.
+    ///
+    /// The chain is uniformized at the rate `λ` given by the largest exit rate of
+    /// any state, yielding the discrete-time chain with transition matrix
+    /// `P = I + Q/λ`. The distribution at time `t` is then a Poisson mixture of
+    /// the distributions of the discrete-time chain, `Σₖ e^{-λt} (λt)ᵏ/k! p₀ Pᵏ`,
+    /// truncated once the neglected Poisson mass falls below `tolerance`. Long
+    /// time intervals are split into steps to avoid underflow in `e^{-λt}`.
+    ///
+    /// The times should be nonnegative and in increasing order.
+    pub fn transient_distribution(
+        &self,
+        initial: &DVector<f64>,
+        times: &[f64],
+        tolerance: f64,
+    ) -> Vec<DVector<f64>> {
+        const MAX_STEP: f64 = 10.0;
+
+        let n = self.num_states();
+        let rate = (0..n).map(|i| -self.generator[(i, i)]).fold(0.0, f64::max);
+        if rate == 0.0 {
+            return times.iter().map(|_| initial.clone()).collect();
+        }
+        let jump = (DMatrix::identity(n, n) + &self.generator / rate).transpose();
+
+        let mut result = Vec::with_capacity(times.len());
+        let (mut p, mut t) = (initial.clone(), 0.0);
+        for &next_t in times {
+            // Advance from `t` to `next_t` in steps of bounded `λΔt`.
+            let mut remaining = (next_t - t).max(0.0) * rate;
+            while remaining > 0.0 {
+                let step = remaining.min(MAX_STEP);
+                p = poisson_mixture(&jump, &p, step, tolerance);
+                remaining -= step;
+            }
+            t = next_t.max(t);
+            result.push(p.clone());
+        }
+        result
+    }
+
+    /// Analyzes the transient behavior of the chain, for plotting.
+    pub fn transient_analysis(&self, data: &MarkovChainTransientData) -> ODESolution {
+        let mut initial = DVector::from_iterator(
+            self.num_states(),
+            self.state_index
+                .keys()
+                .map(|x| data.initial_values.get(x).copied().unwrap_or_default() as f64),
+        );
+        let total = initial.sum();
+        if total > 0.0 {
+            initial /= total;
+        }
+        let times: Vec<_> = data.times.iter().map(|t| *t as f64).collect();
+        let distributions = self.transient_distribution(&initial, &times, 1e-9);
+
+        let states = self
+            .state_index
+            .iter()
+            .map(|(x, &i)| (x.clone(), distributions.iter().map(|p| p[i]).collect()))
+            .collect();
+        ODESolution { time: times, states, seed: None }
+    }
+
+    /// Labels the entries of a vector indexed by states with object IDs.
+    fn by_state(&self, v: &DVector<f64>) -> HashMap<QualifiedName, f32> {
+        self.state_index.iter().map(|(x, &i)| (x.clone(), v[i] as f32)).collect()
+    }
+}
+
+/// Computes `Σₖ e^{-μ} μᵏ/k! Jᵏ p`, truncated at the given tolerance.
+fn poisson_mixture(jump: &DMatrix<f64>, p: &DVector<f64>, mu: f64, tolerance: f64) -> DVector<f64> {
+    let mut weight = (-mu).exp();
+    let mut term = p.clone();
+    let mut result = &term * weight;
+    let mut mass = weight;
+    let mut k = 0.0;
+    while 1.0 - mass > tolerance && weight > 0.0 {
+        k += 1.0;
+        term = jump * term;
+        weight *= mu / k;
+        result += &term * weight;
+        mass += weight;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::dbl::model::MutDblModel;
+    use crate::one::Path;
+    use crate::stdlib::theories::th_category;
+    use crate::zero::name;
+
+    fn state_diagram(transitions: &[(&str, &str, &str)]) -> DiscreteDblModel {
+        let mut model = DiscreteDblModel::new(Rc::new(th_category()));
+        for (_, x, y) in transitions {
+            for x in [*x, *y] {
+                model.add_ob(name(x), name("Object"));
+            }
+        }
+        for (f, x, y) in transitions {
+            model.add_mor(name(*f), name(*x), name(*y), Path::Id(name("Object")));
+        }
+        model
+    }
+
+    #[test]
+    fn two_state_chain() {
+        let model = state_diagram(&[("f", "a", "b"), ("g", "b", "a")]);
+        let rates = HashMap::from([(name("f"), 1.0), (name("g"), 2.0)]);
+        let chain = ContinuousTimeMarkovChain::from_model(&model, &rates);
+        assert!(chain.generator.row_sum().iter().all(|x| *x == 0.0));
+
+        let result = chain.analyze(&MarkovChainProblemData { rates, targets: vec![] });
+        let pi = result.stationary_distribution.unwrap();
+        assert!((pi[&name("a")] - 2.0 / 3.0).abs() < 1e-6);
+        assert!((pi[&name("b")] - 1.0 / 3.0).abs() < 1e-6);
+        assert!(result.hitting_probabilities.is_none());
+    }
+
+    #[test]
+    fn absorbing_chain() {
+        let model = state_diagram(&[("f", "a", "b"), ("g", "a", "c"), ("h", "d", "a")]);
+        let rates = HashMap::from([(name("f"), 1.0), (name("g"), 3.0), (name("h"), 1.0)]);
+        let chain = ContinuousTimeMarkovChain::from_model(&model, &rates);
+        // Two absorbing states, so no unique stationary distribution.
+        assert!(chain.stationary_distribution().is_none());
+
+        let data = MarkovChainProblemData { rates, targets: vec![name("b")] };
+        let probs = chain.analyze(&data).hitting_probabilities.unwrap();
+        assert!((probs[&name("a")] - 0.25).abs() < 1e-6);
+        assert!((probs[&name("d")] - 0.25).abs() < 1e-6);
+        assert_eq!(probs[&name("b")], 1.0);
+        assert_eq!(probs[&name("c")], 0.0);
+    }
+
+    #[test]
+    fn dangling_transition() {
+        let mut model = state_diagram(&[("f", "a", "b"), ("g", "b", "a")]);
+        model.add_mor(name("h"), name("a"), name("missing"), Path::Id(name("Object")));
+        let rates = HashMap::from([(name("f"), 1.0), (name("g"), 2.0), (name("h"), 5.0)]);
+        let chain = ContinuousTimeMarkovChain::from_model(&model, &rates);
+        assert_eq!(chain.num_states(), 2);
+        let a = chain.state_index[&name("a")];
+        assert_eq!(chain.generator[(a, a)], -1.0);
+    }
+
+    #[test]
+    fn transient_two_state_chain() {
+        let model = state_diagram(&[("f", "a", "b"), ("g", "b", "a")]);
+        let rates = HashMap::from([(name("f"), 1.0), (name("g"), 2.0)]);
+        let chain = ContinuousTimeMarkovChain::from_model(&model, &rates);
+        let data = MarkovChainTransientData {
+            rates,
+            initial_values: HashMap::from([(name("a"), 1.0)]),
+            times: vec![0.0, 0.5, 1.0, 20.0],
+        };
+        let solution = chain.transient_analysis(&data);
+
+        // Exact solution: p_a(t) = 2/3 + 1/3 e^{-3t}.
+        let p_a = &solution.states[&name("a")];
+        for (t, p) in data.times.iter().zip(p_a) {
+            let exact = 2.0 / 3.0 + (-3.0 * f64::from(*t)).exp() / 3.0;
+            assert!((p - exact).abs() < 1e-5);
+        }
+    }
+}
@@ -8,6 +8,7 @@ use std::collections::HashMap;
 
 use crate::{
     dbl::{modal::*, model::FpDblModel, theory::Unital},
+    simulate::ssa,
     stdlib::analyses::{ode::ODESolution, petri::transition_interface},
     zero::{QualifiedName, name},
 };
@@ -34,6 +35,23 @@ pub struct StochasticMassActionProblemData {
 
     /// Duration of simulation.
     pub duration: f32,
+
+    /// Seed for the random number generator.
+    ///
+    /// When omitted, a [random seed](random_seed) is drawn, which is echoed in
+    /// the solution so that the simulation can be reproduced.
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde-wasm", tsify(optional))]
+    pub seed: Option<u64>,
+}
+
+/// Draws a random seed for stochastic simulation.
+///
+/// The seed has at most 53 bits, so that it is represented exactly as a number
+/// in JavaScript and can be passed back unchanged from the frontend.
+pub fn random_seed() -> u64 {
+    let seed = getrandom::u64().expect("Source of randomness should be available");
+    seed >> 11
 }
 
 /// Stochastic mass-action analysis of a model.
@@ -49,6 +67,9 @@ pub struct StochasticMassActionAnalysis {
 
     /// Duration of simulation.
     pub duration: f32,
+
+    /// Seed with which the random number generator was initialized.
+    pub seed: u64,
 }
 
 impl StochasticMassActionAnalysis {
@@ -60,17 +81,17 @@ impl StochasticMassActionAnalysis {
             .keys()
             .map(|id| {
                 let initial = self.initial_values.get(id).copied().unwrap_or_default();
-                (id.clone(), vec![initial as f32])
+                (id.clone(), vec![initial as f64])
             })
             .collect();
         for t in 0..(self.duration as usize) {
             self.problem.advance_until(t as f64);
-            time.push(self.problem.get_time() as f32);
+            time.push(self.problem.get_time());
             for (id, idx) in self.variable_index.iter() {
-                states.get_mut(id).unwrap().push(self.problem.get_species(*idx) as f32)
+                states.get_mut(id).unwrap().push(self.problem.get_species(*idx) as f64)
             }
         }
-        ODESolution { time, states }
+        ODESolution { time, states, seed: Some(self.seed) }
     }
 }
 
@@ -105,30 +126,16 @@ impl PetriNetStochasticMassActionAnalysis {
             .iter()
             .map(|id| data.initial_values.get(id).copied().unwrap_or_default() as isize)
             .collect();
+        let seed = data.seed.unwrap_or_else(random_seed);
         let mut problem = gillespie::Gillespie::new(initial, false);
+        problem.seed(seed);
 
         for mor in model.mor_generators_with_type(&self.transition_mor_type) {
-            let (inputs, outputs) = transition_interface(model, &mor);
-
-            // 1. convert the inputs/outputs to sequences of counts
-            let input_vec = ob_generators.iter().map(|id| {
-                inputs
-                    .iter()
-                    .filter(|&ob| matches!(ob, ModalOb::Generator(id2) if id2 == id))
-                    .count() as u32
-            });
-            let output_vec = ob_generators.iter().map(|id| {
-                outputs
-                    .iter()
-                    .filter(|&ob| matches!(ob, ModalOb::Generator(id2) if id2 == id))
-                    .count() as isize
-            });
-
-            // 2. output := output - input
-            let input_vec: Vec<_> = input_vec.collect();
+            let (input_vec, output_vec) = stoichiometry(model, &ob_generators, &mor);
             let output_vec: Vec<_> = output_vec
+                .into_iter()
                 .zip(input_vec.iter().copied())
-                .map(|(o, i)| o - (i as isize))
+                .map(|(o, i)| o as isize - i as isize)
                 .collect();
             if let Some(rate) = data.rates.get(&mor) {
                 problem.add_reaction(gillespie::Rate::lma(*rate as f64, input_vec), output_vec)
@@ -143,10 +150,60 @@ impl PetriNetStochasticMassActionAnalysis {
             variable_index,
             initial_values: data.initial_values,
             duration: data.duration,
+            seed,
+        }
+    }
+
+    /// Creates a stochastic simulation problem solved by the [SSA](ssa) of
+    /// `catlog`, with the species indexed as in the returned map.
+    ///
+    /// Transitions without a rate are omitted.
+    pub fn build_ssa_problem(
+        &self,
+        model: &ModalDblModel<Unital>,
+        data: &StochasticMassActionProblemData,
+    ) -> (ssa::SSAProblem, IndexMap<QualifiedName, usize>) {
+        let ob_generators: Vec<_> = model.ob_generators_with_type(&self.place_ob_type).collect();
+        let mut network = ssa::ReactionNetwork::new(ob_generators.len());
+        for mor in model.mor_generators_with_type(&self.transition_mor_type) {
+            if let Some(rate) = data.rates.get(&mor) {
+                let (inputs, outputs) = stoichiometry(model, &ob_generators, &mor);
+                network.add_reaction(*rate as f64, inputs, outputs);
+            }
         }
+        let initial: Vec<_> = ob_generators
+            .iter()
+            .map(|id| u64::from(data.initial_values.get(id).copied().unwrap_or_default()))
+            .collect();
+        let problem = ssa::SSAProblem::new(network, initial)
+            .end_time(data.duration as f64)
+            .seed(data.seed.unwrap_or_else(random_seed));
+        let variable_index = ob_generators.into_iter().enumerate().map(|(i, x)| (x, i)).collect();
+        (problem, variable_index)
     }
 }
 
+/// Counts the number of times that each place is an input and an output of a
+/// transition.
+fn stoichiometry(
+    model: &ModalDblModel<Unital>,
+    places: &[QualifiedName],
+    transition: &QualifiedName,
+) -> (Vec<u32>, Vec<u32>) {
+    let (inputs, outputs) = transition_interface(model, transition);
+    let count = |obs: &[ModalOb]| -> Vec<u32> {
+        places
+            .iter()
+            .map(|id| {
+                obs.iter()
+                    .filter(|&ob| matches!(ob, ModalOb::Generator(id2) if id2 == id))
+                    .count() as u32
+            })
+            .collect()
+    };
+    (count(&inputs), count(&outputs))
+}
+
 #[cfg(test)]
 mod tests {
     use std::rc::Rc;
@@ -156,11 +213,8 @@ mod tests {
     use crate::stdlib::theories::*;
     use crate::zero::name;
 
-    #[test]
-    fn sir_petri_stochastic_dynamics() {
-        let th = Rc::new(th_sym_monoidal_category());
-        let model = sir_petri(th);
-        let data = StochasticMassActionProblemData {
+    fn sir_petri_data(seed: Option<u64>) -> StochasticMassActionProblemData {
+        StochasticMassActionProblemData {
             rates: HashMap::from_iter([(name("infect"), 1e-5f32), (name("recover"), 1e-2f32)]),
             initial_values: HashMap::from_iter([
                 (name("S"), 1e5 as u32),
@@ -168,10 +222,52 @@ mod tests {
                 (name("R"), 0),
             ]),
             duration: 10f32,
-        };
+            seed,
+        }
+    }
+
+    #[test]
+    fn sir_petri_stochastic_dynamics() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let data = sir_petri_data(None);
         let sys =
             PetriNetStochasticMassActionAnalysis::default().build_stochastic_system(&model, data);
         assert_eq!(2, sys.problem.nb_reactions());
         assert_eq!(3, sys.problem.nb_species());
     }
+
+    #[test]
+    fn sir_petri_stochastic_seed() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let analysis = PetriNetStochasticMassActionAnalysis::default();
+        let simulate =
+            |seed| analysis.build_stochastic_system(&model, sir_petri_data(seed)).simulate();
+
+        let (first, second) = (simulate(Some(42)), simulate(Some(42)));
+        assert_eq!(first.seed(), Some(42));
+        assert_eq!(first.states, second.states);
+        let seed = simulate(None).seed().expect("Seed should be echoed");
+        assert!(seed < 1 << 53);
+        assert_eq!(simulate(Some(seed)).seed(), Some(seed));
+    }
+
+    #[test]
+    fn sir_petri_ssa() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let model = sir_petri(th);
+        let data = sir_petri_data(Some(42));
+        let analysis = PetriNetStochasticMassActionAnalysis::default();
+        let (problem, index) = analysis.build_ssa_problem(&model, &data);
+        assert_eq!(problem.network().reactions().len(), 2);
+
+        let runs = problem.simulate_runs(3);
+        let total = |state: &[u64]| state.iter().sum::<u64>();
+        for run in &runs {
+            let state = run.final_state(problem.network());
+            assert_eq!(total(&state), 100_001);
+            assert!(state[index[&name("S")]] <= 100_000);
+        }
+    }
 }
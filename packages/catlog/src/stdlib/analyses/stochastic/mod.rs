@@ -1,5 +1,7 @@
 //! Stochastic analyses of models.
 
+pub mod markov_chain;
 pub mod mass_action;
 
+pub use markov_chain::*;
 pub use mass_action::*;
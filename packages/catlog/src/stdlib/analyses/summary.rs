@@ -0,0 +1,160 @@
+//! Summaries of large models.
+//!
+//! A summary collapses a model along a grouping of its objects, producing the
+//! quotient model in which each group becomes a single object. Morphisms
+//! between the same groups and having the same type are collapsed into a single
+//! morphism, annotated with the number of morphisms that it stands for. This is
+//! useful for navigating models with thousands of objects, such as imported
+//! networks, at the level of their modules.
+
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde-wasm")]
+use tsify::Tsify;
+
+use crate::dbl::model::{DblModel, DiscreteDblModel, FpDblModel, MutDblModel};
+use crate::one::{Category, FgCategory, QualifiedPath};
+use crate::zero::QualifiedName;
+
+/// A grouping of the objects of a model.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-wasm", derive(Tsify))]
+#[cfg_attr(
+    feature = "serde-wasm",
+    tsify(into_wasm_abi, from_wasm_abi, hashmap_as_object)
+)]
+pub struct Grouping {
+    /// Map from object generators to the names of their groups.
+    ///
+    /// Objects not in the map form singleton groups named after themselves.
+    pub groups: HashMap<QualifiedName, QualifiedName>,
+}
+
+/// Summary of a model, collapsed along a grouping of its objects.
+#[derive(Clone, Debug)]
+pub struct ModelSummary {
+    /// The summary model, whose objects are the groups.
+    pub model: DiscreteDblModel,
+
+    /// Number of objects in each group.
+    pub ob_multiplicities: HashMap<QualifiedName, usize>,
+
+    /// Number of morphisms collapsed into each morphism of the summary.
+    ///
+    /// Each morphism of the summary is named after the first morphism
+    /// generator collapsed into it.
+    pub mor_multiplicities: HashMap<QualifiedName, usize>,
+}
+
+/// A grouping along which a model cannot be summarized.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum InvalidGrouping {
+    /// The objects in a group do not all have the same type.
+    #[error("Objects in group `{0}` have different types")]
+    MixedObTypes(QualifiedName),
+}
+
+impl Grouping {
+    /// Gets the group containing an object.
+    pub fn group(&self, ob: &QualifiedName) -> QualifiedName {
+        self.groups.get(ob).unwrap_or(ob).clone()
+    }
+}
+
+/// Summarizes a model of a discrete double theory along a grouping of objects.
+///
+/// Morphisms within a group become endomorphisms of the group. Equations are
+/// not carried over to the summary, which is always freely generated.
+pub fn summarize(
+    model: &DiscreteDblModel,
+    grouping: &Grouping,
+) -> Result<ModelSummary, InvalidGrouping> {
+    let mut summary = DiscreteDblModel::new(model.theory());
+    let mut ob_multiplicities = HashMap::new();
+    for x in model.ob_generators() {
+        let group = grouping.group(&x);
+        let ob_type = model.ob_generator_type(&x);
+        if summary.has_ob(&group) {
+            if summary.ob_generator_type(&group) != ob_type {
+                return Err(InvalidGrouping::MixedObTypes(group));
+            }
+        } else {
+            summary.add_ob(group.clone(), ob_type);
+        }
+        *ob_multiplicities.entry(group).or_insert(0) += 1;
+    }
+
+    let mut collapsed: HashMap<(QualifiedName, QualifiedName, QualifiedPath), QualifiedName> =
+        HashMap::new();
+    let mut mor_multiplicities = HashMap::new();
+    for f in model.mor_generators() {
+        let (Some(x), Some(y)) = (model.get_dom(&f), model.get_cod(&f)) else {
+            continue;
+        };
+        let (x, y) = (grouping.group(x), grouping.group(y));
+        let mor_type = model.mor_generator_type(&f);
+        let key = (x.clone(), y.clone(), mor_type.clone());
+        let g = collapsed.entry(key).or_insert_with(|| {
+            summary.add_mor(f.clone(), x, y, mor_type);
+            f
+        });
+        *mor_multiplicities.entry(g.clone()).or_insert(0) += 1;
+    }
+
+    Ok(ModelSummary {
+        model: summary,
+        ob_multiplicities,
+        mor_multiplicities,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::one::Path;
+    use crate::stdlib::theories::th_schema;
+    use crate::validate::Validate;
+    use crate::zero::name;
+
+    #[test]
+    fn summarize_schema() {
+        let mut model = DiscreteDblModel::new(Rc::new(th_schema()));
+        model.add_ob(name("Person"), name("Entity"));
+        model.add_ob(name("Dept"), name("Entity"));
+        model.add_ob(name("String"), name("AttrType"));
+        model.add_mor(name("works_in"), name("Person"), name("Dept"), Path::Id(name("Entity")));
+        model.add_mor(name("manager"), name("Dept"), name("Person"), Path::Id(name("Entity")));
+        model.add_mor(name("name"), name("Person"), name("String"), name("Attr").into());
+        model.add_mor(name("title"), name("Dept"), name("String"), name("Attr").into());
+
+        let grouping = Grouping {
+            groups: [("Person", "Org"), ("Dept", "Org")]
+                .into_iter()
+                .map(|(x, group)| (name(x), name(group)))
+                .collect(),
+        };
+        let summary = summarize(&model, &grouping).unwrap();
+        assert_eq!(summary.model.ob_generators().count(), 2);
+        assert_eq!(summary.ob_multiplicities[&name("Org")], 2);
+        assert_eq!(summary.ob_multiplicities[&name("String")], 1);
+        assert_eq!(summary.model.mor_generators().count(), 2);
+        assert_eq!(summary.mor_multiplicities[&name("works_in")], 2);
+        assert_eq!(summary.mor_multiplicities[&name("name")], 2);
+        assert_eq!(summary.model.get_dom(&name("works_in")), Some(&name("Org")));
+        assert!(summary.model.validate().is_ok());
+
+        let grouping = Grouping {
+            groups: [(name("Person"), name("All")), (name("String"), name("All"))].into(),
+        };
+        assert_eq!(
+            summarize(&model, &grouping).err(),
+            Some(InvalidGrouping::MixedObTypes(name("All")))
+        );
+    }
+}
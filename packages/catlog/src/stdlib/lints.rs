@@ -0,0 +1,160 @@
+//! Standard library of lints for models of double theories.
+
+use crate::dbl::{model::*, theory::*};
+use crate::lint::{Lint, LintSet, Severity};
+use crate::one::{FgCategory, FinGraph};
+use crate::stdlib::analyses::petri::transition_interface;
+use crate::zero::name;
+
+/// Lints for models of discrete double theories, such as causal loop diagrams.
+pub fn discrete_lints() -> LintSet<DiscreteDblModel> {
+    LintSet::new().with(isolated_object())
+}
+
+/// Lints for stock-flow diagrams.
+pub fn stock_flow_lints() -> LintSet<DiscreteTabModel> {
+    LintSet::new().with(stock_without_inflow()).with(unused_stock())
+}
+
+/// Lints for Petri nets.
+pub fn petri_net_lints() -> LintSet<ModalDblModel<Unital>> {
+    LintSet::new().with(unused_place())
+}
+
+/// Object that is neither the domain nor the codomain of any morphism.
+pub fn isolated_object() -> Lint<DiscreteDblModel> {
+    Lint {
+        name: "isolated_object",
+        severity: Severity::Warning,
+        message: "Object is not connected to any other object",
+        check: |model| {
+            let graph = model.generating_graph();
+            model.ob_generators().filter(|x| graph.degree(x) == 0).collect()
+        },
+    }
+}
+
+/// Stock in a stock-flow diagram that has no inflows.
+///
+/// Stocks that can only be drained are common, such as the susceptible population
+/// in an epidemiological model, so this lint is merely informational.
+pub fn stock_without_inflow() -> Lint<DiscreteTabModel> {
+    Lint {
+        name: "stock_without_inflow",
+        severity: Severity::Info,
+        message: "Stock has no inflow",
+        check: |model| {
+            let flows: Vec<_> = model.mor_generators_with_type(&flow_mor_type()).collect();
+            model
+                .ob_generators_with_type(&stock_ob_type())
+                .filter(|x| {
+                    let x = TabOb::Basic(x.clone());
+                    !flows.iter().any(|f| model.get_cod(f) == Some(&x))
+                })
+                .collect()
+        },
+    }
+}
+
+/// Stock in a stock-flow diagram that has no flows and no links.
+///
+/// Such a stock has no effect on, and is not affected by, the rest of the system.
+pub fn unused_stock() -> Lint<DiscreteTabModel> {
+    Lint {
+        name: "unused_stock",
+        severity: Severity::Warning,
+        message: "Stock is not used by any flow or link",
+        check: |model| {
+            let uses: Vec<_> = model.mor_generators().collect();
+            model
+                .ob_generators_with_type(&stock_ob_type())
+                .filter(|x| {
+                    let x = TabOb::Basic(x.clone());
+                    !uses
+                        .iter()
+                        .any(|f| model.get_dom(f) == Some(&x) || model.get_cod(f) == Some(&x))
+                })
+                .collect()
+        },
+    }
+}
+
+/// Place in a Petri net that is not an input or output of any transition.
+pub fn unused_place() -> Lint<ModalDblModel<Unital>> {
+    Lint {
+        name: "unused_place",
+        severity: Severity::Warning,
+        message: "Place is not an input or output of any transition",
+        check: |model| {
+            let ob_type = ModalObType::new(name("Object"));
+            let used: Vec<_> = model
+                .mor_generators_with_type(&ModalMorType::Zero(ob_type.clone()))
+                .flat_map(|f| {
+                    let (inputs, outputs) = transition_interface(model, &f);
+                    inputs.into_iter().chain(outputs)
+                })
+                .collect();
+            model
+                .ob_generators_with_type(&ob_type)
+                .filter(|x| !used.contains(&ModalOb::Generator(x.clone())))
+                .collect()
+        },
+    }
+}
+
+fn stock_ob_type() -> TabObType {
+    TabObType::Basic(name("Object"))
+}
+
+fn flow_mor_type() -> TabMorType {
+    TabMorType::Hom(Box::new(stock_ob_type()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::stdlib::{models::*, theories::*};
+
+    #[test]
+    fn discrete_model_lints() {
+        let th = Rc::new(th_signed_category());
+        let mut model = positive_feedback(th);
+        assert!(discrete_lints().run(&model).is_empty());
+
+        model.add_ob(name("z"), name("Object"));
+        let diagnostics = discrete_lints().run(&model);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].lint, "isolated_object");
+        assert_eq!(diagnostics[0].subject, name("z"));
+    }
+
+    #[test]
+    fn stock_flow_model_lints() {
+        let th = Rc::new(th_category_links());
+        let mut model = backward_link(th);
+        let diagnostics = stock_flow_lints().run(&model);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].lint, "stock_without_inflow");
+        assert_eq!(diagnostics[0].subject, name("x"));
+
+        model.add_ob(name("z"), stock_ob_type());
+        let diagnostics = stock_flow_lints().run(&model);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].lint, "unused_stock");
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn petri_net_lints_sir() {
+        let th = Rc::new(th_sym_monoidal_category());
+        let mut model = sir_petri(th);
+        assert!(petri_net_lints().run(&model).is_empty());
+
+        model.add_ob(name("D"), ModalObType::new(name("Object")));
+        let diagnostics = petri_net_lints().run(&model);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].subject, name("D"));
+    }
+}
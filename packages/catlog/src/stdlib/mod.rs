@@ -3,9 +3,11 @@
 pub mod models;
 pub mod theories;
 pub mod theory_morphisms;
+pub mod versions;
 
 pub use models::*;
 pub use theories::*;
 pub use theory_morphisms::*;
 
 pub mod analyses;
+pub mod lints;
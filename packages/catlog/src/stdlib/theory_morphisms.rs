@@ -2,13 +2,9 @@
 //!
 //! These can be used to migrate models from one theory to another.
 
-use crate::one::{FpFunctorData, Path, QualifiedPath};
-use crate::zero::{HashColumn, QualifiedName, name};
-
-type DiscreteDblTheoryMap = FpFunctorData<
-    HashColumn<QualifiedName, QualifiedName>,
-    HashColumn<QualifiedName, QualifiedPath>,
->;
+use crate::dbl::discrete::DiscreteDblTheoryMap;
+use crate::one::{FpFunctorData, Path};
+use crate::zero::{HashColumn, name};
 
 /// Map from theory of categories to the theories of schemas.
 ///
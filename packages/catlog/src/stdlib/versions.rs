@@ -0,0 +1,403 @@
+//! Versions of theories in the standard library.
+//!
+//! Documents record the theory of a model by an identifier such as
+//! `"causal-loop"`, optionally suffixed by a version as in `"causal-loop@2"`. A
+//! missing version is version 1. As the theories in the standard library evolve,
+//! their object and morphism types can be renamed or split into several types.
+//! Each such change bumps the version of the theory and comes with a
+//! [migration](TheoryMigration) from the previous version, so that models
+//! recorded against an old version can be checked for compatibility and then
+//! upgraded to the runtime theory.
+//!
+//! Only the version histories are kept here: the theories themselves are
+//! constructed by the caller, which already maps identifiers to theories.
+//! Theories with no recorded migrations are at version 1.
+
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::dbl::{discrete::*, model::*};
+use crate::one::{FgCategory, QualifiedPath};
+use crate::zero::QualifiedName;
+
+/// Identifier and version of a theory, as recorded in a document.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TheoryVersion {
+    /// Identifier of the theory.
+    pub id: String,
+
+    /// Version of the theory, starting from one.
+    pub version: u32,
+}
+
+impl FromStr for TheoryVersion {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.rsplit_once('@') {
+            Some((id, version)) => Self { id: id.into(), version: version.parse()? },
+            None => Self { id: s.into(), version: 1 },
+        })
+    }
+}
+
+impl fmt::Display for TheoryVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.version == 1 {
+            write!(f, "{}", self.id)
+        } else {
+            write!(f, "{}@{}", self.id, self.version)
+        }
+    }
+}
+
+/// A change to the types of a theory between consecutive versions.
+#[derive(Clone, Debug)]
+pub enum TypeChange {
+    /// Renames an object type.
+    RenameObType {
+        /// Object type in the old version.
+        from: QualifiedName,
+        /// Object type in the new version.
+        to: QualifiedName,
+    },
+
+    /// Renames a morphism type.
+    RenameMorType {
+        /// Morphism type in the old version.
+        from: QualifiedPath,
+        /// Morphism type in the new version.
+        to: QualifiedPath,
+    },
+
+    /// Splits an object type into several types.
+    ///
+    /// The new type of each object of the old type is chosen by a function of
+    /// the model, before migration, and the object.
+    SplitObType {
+        /// Object type in the old version.
+        from: QualifiedName,
+        /// Chooses the object type in the new version.
+        choose: fn(&DiscreteDblModel, &QualifiedName) -> QualifiedName,
+    },
+
+    /// Splits a morphism type into several types.
+    SplitMorType {
+        /// Morphism type in the old version.
+        from: QualifiedPath,
+        /// Chooses the morphism type in the new version.
+        choose: fn(&DiscreteDblModel, &QualifiedName) -> QualifiedPath,
+    },
+}
+
+/// Migration of models from the previous version of a theory.
+#[derive(Clone, Debug)]
+pub struct TheoryMigration {
+    /// Version migrated to, whose predecessor is migrated from.
+    pub version: u32,
+
+    /// Changes to the types of the theory.
+    pub changes: Vec<TypeChange>,
+}
+
+impl TheoryMigration {
+    fn ob_type(&self, model: &DiscreteDblModel, x: &QualifiedName) -> QualifiedName {
+        let ob_type = model.ob_generator_type(x);
+        for change in &self.changes {
+            match change {
+                TypeChange::RenameObType { from, to } if *from == ob_type => return to.clone(),
+                TypeChange::SplitObType { from, choose } if *from == ob_type => {
+                    return choose(model, x);
+                }
+                _ => {}
+            }
+        }
+        ob_type
+    }
+
+    fn mor_type(&self, model: &DiscreteDblModel, f: &QualifiedName) -> QualifiedPath {
+        let mor_type = model.mor_generator_type(f);
+        for change in &self.changes {
+            match change {
+                TypeChange::RenameMorType { from, to } if *from == mor_type => return to.clone(),
+                TypeChange::SplitMorType { from, choose } if *from == mor_type => {
+                    return choose(model, f);
+                }
+                _ => {}
+            }
+        }
+        mor_type
+    }
+
+    /// Migrates a model to the given theory.
+    pub fn migrate(
+        &self,
+        model: &DiscreteDblModel,
+        theory: Rc<DiscreteDblTheory>,
+    ) -> DiscreteDblModel {
+        let mut migrated = DiscreteDblModel::new(theory);
+        for x in model.ob_generators() {
+            migrated.add_ob(x.clone(), self.ob_type(model, &x));
+        }
+        for f in model.mor_generators() {
+            migrated.make_mor(f.clone(), self.mor_type(model, &f));
+            if let Some(x) = model.get_dom(&f) {
+                migrated.set_dom(f.clone(), x.clone());
+            }
+            if let Some(y) = model.get_cod(&f) {
+                migrated.set_cod(f.clone(), y.clone());
+            }
+        }
+        for eq in model.category.equations() {
+            migrated.add_equation(eq.clone());
+        }
+        migrated
+    }
+}
+
+/// Compatibility of a recorded theory version with the runtime theory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compatibility {
+    /// The recorded version is the runtime version.
+    Current,
+
+    /// The recorded version is older and models can be migrated.
+    Outdated {
+        /// The recorded version.
+        from: u32,
+        /// The runtime version.
+        to: u32,
+    },
+}
+
+/// An incompatibility between a recorded theory version and the runtime theory.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum IncompatibleTheory {
+    /// The recorded theory has a different identifier.
+    #[error("Expected theory `{expected}`, found `{found}`")]
+    WrongTheory {
+        /// Identifier of the runtime theory.
+        expected: String,
+        /// Recorded identifier.
+        found: String,
+    },
+
+    /// The recorded version is newer than the runtime version.
+    #[error("Theory `{id}` has version {version}, newer than runtime version {runtime}")]
+    NewerVersion {
+        /// Identifier of the theory.
+        id: String,
+        /// The recorded version.
+        version: u32,
+        /// The runtime version.
+        runtime: u32,
+    },
+}
+
+/// Version history of a discrete double theory in the standard library.
+#[derive(Clone, Debug)]
+pub struct VersionedTheory {
+    /// Identifier of the theory.
+    pub id: String,
+
+    migrations: Vec<TheoryMigration>,
+}
+
+impl VersionedTheory {
+    /// Creates a theory at version 1, with no migrations.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into(), migrations: Vec::new() }
+    }
+
+    /// Adds a migration to the next version of the theory.
+    pub fn with_migration(mut self, changes: Vec<TypeChange>) -> Self {
+        let version = self.version() + 1;
+        self.migrations.push(TheoryMigration { version, changes });
+        self
+    }
+
+    /// Gets the runtime version of the theory.
+    pub fn version(&self) -> u32 {
+        self.migrations.last().map_or(1, |migration| migration.version)
+    }
+
+    /// Checks the compatibility of a recorded version with the runtime theory.
+    pub fn check(&self, recorded: &TheoryVersion) -> Result<Compatibility, IncompatibleTheory> {
+        if recorded.id != self.id {
+            return Err(IncompatibleTheory::WrongTheory {
+                expected: self.id.clone(),
+                found: recorded.id.clone(),
+            });
+        }
+        let runtime = self.version();
+        if recorded.version > runtime {
+            return Err(IncompatibleTheory::NewerVersion {
+                id: self.id.clone(),
+                version: recorded.version,
+                runtime,
+            });
+        }
+        Ok(if recorded.version == runtime {
+            Compatibility::Current
+        } else {
+            Compatibility::Outdated { from: recorded.version, to: runtime }
+        })
+    }
+
+    /// Upgrades a model recorded against an old version to the runtime theory.
+    ///
+    /// Each migration is applied in turn, so that migration hooks see the model
+    /// as it was at the previous version. A current model is returned as is.
+    pub fn upgrade(
+        &self,
+        model: DiscreteDblModel,
+        recorded: &TheoryVersion,
+        theory: Rc<DiscreteDblTheory>,
+    ) -> Result<DiscreteDblModel, IncompatibleTheory> {
+        if self.check(recorded)? == Compatibility::Current {
+            return Ok(model);
+        }
+        let pending = self.migrations.iter().filter(|m| m.version > recorded.version);
+        Ok(pending.fold(model, |model, migration| migration.migrate(&model, theory.clone())))
+    }
+}
+
+/// Gets the version history of a theory in the standard library.
+///
+/// Migrations are registered here as theories change; a theory not listed has
+/// never changed and is at version 1.
+pub fn versioned_theory(id: &str) -> VersionedTheory {
+    VersionedTheory::new(id)
+}
+
+/// Checks the compatibility of a recorded theory version with the standard
+/// library.
+pub fn check_theory(
+    recorded: &TheoryVersion,
+) -> Result<(VersionedTheory, Compatibility), IncompatibleTheory> {
+    let theory = versioned_theory(&recorded.id);
+    let compatibility = theory.check(recorded)?;
+    Ok((theory, compatibility))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::one::{Category, Path};
+    use crate::stdlib::theories::*;
+    use crate::validate::Validate;
+    use crate::zero::name;
+
+    /// Old version of the theory of schemas, with a single object type.
+    fn th_schema_v1() -> DiscreteDblTheory {
+        let mut cat = crate::one::FpCategory::new();
+        cat.add_ob_generator(name("Table"));
+        cat.add_mor_generator(name("Column"), name("Table"), name("Table"));
+        cat.into()
+    }
+
+    /// Tables with no columns of their own become attribute types.
+    fn choose_table_type(model: &DiscreteDblModel, x: &QualifiedName) -> QualifiedName {
+        if model.mor_generators().any(|f| model.get_dom(&f) == Some(x)) {
+            name("Entity")
+        } else {
+            name("AttrType")
+        }
+    }
+
+    fn choose_column_type(model: &DiscreteDblModel, f: &QualifiedName) -> QualifiedPath {
+        let cod = model.get_cod(f).unwrap();
+        if choose_table_type(model, cod) == name("AttrType") {
+            name("Attr").into()
+        } else {
+            Path::Id(name("Entity"))
+        }
+    }
+
+    #[test]
+    fn parse_versions() {
+        let v: TheoryVersion = "causal-loop".parse().unwrap();
+        assert_eq!(v, TheoryVersion { id: "causal-loop".into(), version: 1 });
+        let v: TheoryVersion = "simple-schema@3".parse().unwrap();
+        assert_eq!(v.version, 3);
+        assert_eq!(v.to_string(), "simple-schema@3");
+        assert!("simple-schema@x".parse::<TheoryVersion>().is_err());
+    }
+
+    #[test]
+    fn check_stdlib_theories() {
+        let (theory, compat) = check_theory(&"causal-loop".parse().unwrap()).unwrap();
+        assert_eq!(compat, Compatibility::Current);
+        assert_eq!(theory.version(), 1);
+        assert!(matches!(
+            check_theory(&"causal-loop@2".parse().unwrap()),
+            Err(IncompatibleTheory::NewerVersion { version: 2, .. })
+        ));
+        assert_eq!(
+            versioned_theory("simple-schema").check(&"causal-loop".parse().unwrap()),
+            Err(IncompatibleTheory::WrongTheory {
+                expected: "simple-schema".into(),
+                found: "causal-loop".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn split_types() {
+        let schema = VersionedTheory::new("simple-schema").with_migration(vec![
+            TypeChange::SplitObType {
+                from: name("Table"),
+                choose: choose_table_type,
+            },
+            TypeChange::SplitMorType {
+                from: name("Column").into(),
+                choose: choose_column_type,
+            },
+        ]);
+        let recorded: TheoryVersion = "simple-schema".parse().unwrap();
+        assert_eq!(schema.check(&recorded), Ok(Compatibility::Outdated { from: 1, to: 2 }));
+
+        let mut model = DiscreteDblModel::new(Rc::new(th_schema_v1()));
+        model.add_ob(name("Person"), name("Table"));
+        model.add_ob(name("Dept"), name("Table"));
+        model.add_ob(name("String"), name("Table"));
+        model.add_mor(name("works_in"), name("Person"), name("Dept"), name("Column").into());
+        model.add_mor(name("title"), name("Dept"), name("String"), name("Column").into());
+
+        let upgraded = schema.upgrade(model, &recorded, Rc::new(th_schema())).unwrap();
+        assert!(upgraded.validate().is_ok());
+        assert_eq!(upgraded.ob_generator_type(&name("String")), name("AttrType"));
+        assert_eq!(upgraded.ob_generator_type(&name("Dept")), name("Entity"));
+        assert_eq!(upgraded.mor_generator_type(&name("title")), name("Attr").into());
+        assert!(upgraded.has_mor(&Path::pair(name("works_in"), name("title"))));
+    }
+
+    #[test]
+    fn rename_types() {
+        let causal_loop = VersionedTheory::new("causal-loop").with_migration(vec![
+            TypeChange::RenameObType {
+                from: name("Variable"),
+                to: name("Object"),
+            },
+            TypeChange::RenameMorType {
+                from: Path::Id(name("Variable")),
+                to: Path::Id(name("Object")),
+            },
+        ]);
+        let mut sgn = crate::one::FpCategory::new();
+        sgn.add_ob_generator(name("Variable"));
+        let mut model = DiscreteDblModel::new(Rc::new(sgn.into()));
+        model.add_ob(name("x"), name("Variable"));
+        model.add_mor(name("f"), name("x"), name("x"), Path::Id(name("Variable")));
+
+        let recorded = "causal-loop".parse().unwrap();
+        let theory = Rc::new(th_signed_category());
+        let upgraded = causal_loop.upgrade(model, &recorded, theory).unwrap();
+        assert!(upgraded.validate().is_ok());
+        assert_eq!(upgraded.ob_generator_type(&name("x")), name("Object"));
+        assert_eq!(upgraded.mor_generator_type(&name("f")), Path::Id(name("Object")));
+    }
+}
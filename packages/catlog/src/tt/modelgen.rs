@@ -105,6 +105,14 @@ impl Model {
         }
     }
 
+    /// Tries to extract a model of a discrete tabulator theory.
+    pub fn as_discrete_tab(self) -> Option<discrete_tabulator::DiscreteTabModel> {
+        match self {
+            Model::DiscreteTab(model) => Some(*model),
+            _ => None,
+        }
+    }
+
     /// Tries to extract a model of a unital modal theory.
     pub fn as_modal(self) -> Option<modal::ModalDblModel<Unital>> {
         match self {
@@ -2,12 +2,14 @@
 
 use itertools::Itertools;
 use num_traits::{One, Pow, Zero, one, zero};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Display;
 use std::iter::{Product, Sum};
-use std::ops::{Add, AddAssign, Mul, Neg};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
+use std::str::FromStr;
 
 use derivative::Derivative;
+use thiserror::Error;
 
 use super::rig::*;
 
@@ -67,6 +69,11 @@ where
         self.0.variables()
     }
 
+    /// Iterates over the terms (coefficient-monomial pairs) of the polynomial.
+    pub fn terms(&self) -> impl ExactSizeIterator<Item = (&Coef, &Monomial<Var, Exp>)> {
+        (&self.0).into_iter()
+    }
+
     /// Maps the coefficients of the polynomial.
     ///
     /// In the usual situations when the coefficients from commutative rigs and the
@@ -124,6 +131,99 @@ where
             .collect()
     }
 
+    /// Differentiates the polynomial with respect to a variable.
+    ///
+    /// The exponents should be integers, possibly negative. The coefficients
+    /// need only form an abelian group, so that the polynomial can have
+    /// symbolic coefficients, such as parameters that are themselves
+    /// polynomials.
+    pub fn partial_derivative(&self, var: &Var) -> Self
+    where
+        Var: Clone,
+        Coef: Clone + Zero + Add<Output = Coef> + Neg<Output = Coef>,
+        Exp: Clone + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+    {
+        let derivative: Self = (&self.0)
+            .into_iter()
+            .filter_map(|(coef, m)| {
+                let mut exp = None;
+                let m: Monomial<_, _> = m
+                    .clone()
+                    .into_iter()
+                    .map(|(v, e)| {
+                        if v == *var {
+                            exp = Some(e.clone());
+                            (v, e - Exp::one())
+                        } else {
+                            (v, e)
+                        }
+                    })
+                    .collect();
+                Some((integer_multiple(coef.clone(), exp?.into()), m))
+            })
+            .collect();
+        derivative.normalize()
+    }
+
+    /// Computes the gradient of the polynomial.
+    ///
+    /// The gradient is given as the partial derivatives with respect to the
+    /// variables occurring in the polynomial. The partial derivatives with
+    /// respect to all other variables are zero.
+    pub fn grad(&self) -> BTreeMap<Var, Self>
+    where
+        Var: Clone,
+        Coef: Clone + Zero + Add<Output = Coef> + Neg<Output = Coef>,
+        Exp: Clone + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+    {
+        let vars: BTreeSet<_> = self.monomials().flat_map(|m| m.variables()).cloned().collect();
+        vars.into_iter()
+            .map(|var| {
+                let derivative = self.partial_derivative(&var);
+                (var, derivative)
+            })
+            .collect()
+    }
+
+    /// Integrates the polynomial formally with respect to a variable.
+    ///
+    /// Each term `c x^n` is integrated to `c/(n+1) x^(n+1)`, so the antiderivative
+    /// has no constant term in the variable. The coefficients should form a
+    /// field. Returns `None` if a term has exponent -1 in the variable, since
+    /// its antiderivative is a logarithm.
+    pub fn antiderivative(&self, var: &Var) -> Option<Self>
+    where
+        Var: Clone,
+        Coef: Clone + Zero + One + Add<Output = Coef> + Neg<Output = Coef> + Div<Output = Coef>,
+        Exp: Clone + Zero + One + Add<Output = Exp> + Into<i32>,
+    {
+        let antiderivative: Option<Self> = (&self.0)
+            .into_iter()
+            .map(|(coef, m)| {
+                let mut exp = Exp::zero();
+                let mut m: Vec<_> = m
+                    .clone()
+                    .into_iter()
+                    .filter(|(v, e)| {
+                        let found = v == var;
+                        if found {
+                            exp = e.clone();
+                        }
+                        !found
+                    })
+                    .collect();
+                let exp = exp + Exp::one();
+                let n: i32 = exp.clone().into();
+                if n == 0 {
+                    return None;
+                }
+                m.push((var.clone(), exp));
+                Some((coef.clone() / integer_multiple(Coef::one(), n), m.into_iter().collect()))
+            })
+            .collect();
+        antiderivative.map(Self::normalize)
+    }
+
     /// Puts the polynomial into normal form.
     ///
     /// The data structure for polynomials is already pretty close to being a normal
@@ -186,6 +286,447 @@ where
     }
 }
 
+impl<Var, Coef, Exp> Polynomial<Var, Coef, Exp>
+where
+    Var: Ord + FromStr,
+    Coef: FromStr + Add<Output = Coef> + Neg<Output = Coef> + Zero + One,
+    Exp: Ord + FromStr + Add<Output = Exp> + One,
+{
+    /// Parses a polynomial, such as `2 x^2 y + 3 y`.
+    ///
+    /// The syntax is that of the `Display` implementation, so that displayed
+    /// polynomials can be parsed back. A term is an optional coefficient
+    /// followed by powers of variables, multiplied by juxtaposition or `*`.
+    /// Coefficients are numbers, such as `2`, `0.5`, or `1/2`, or else are
+    /// enclosed in parentheses. Exponents are integers, enclosed in braces when
+    /// they have more than one character, as in `x^{10}` or `x^{-1}`. Variables
+    /// are identifiers, such as `x` or `k_1`, which must parse as `Var`.
+    pub fn parse(text: &str) -> Result<Self, InvalidPolynomial> {
+        let mut parser = PolynomialParser { text, pos: 0 };
+        let p = parser.sum()?;
+        if parser.pos < text.len() {
+            return Err(parser.error("expected `+`, `-`, or end of input"));
+        }
+        Ok(p)
+    }
+}
+
+impl<Var, Coef, Exp> FromStr for Polynomial<Var, Coef, Exp>
+where
+    Var: Ord + FromStr,
+    Coef: FromStr + Add<Output = Coef> + Neg<Output = Coef> + Zero + One,
+    Exp: Ord + FromStr + Add<Output = Exp> + One,
+{
+    type Err = InvalidPolynomial;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Polynomial::parse(s)
+    }
+}
+
+/// A syntax error in a polynomial.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("Invalid polynomial at position {position}: {message}")]
+pub struct InvalidPolynomial {
+    /// Byte offset in the text at which the error occurred.
+    pub position: usize,
+
+    /// Description of the error.
+    pub message: String,
+}
+
+/// Recursive descent parser for polynomials.
+struct PolynomialParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> PolynomialParser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn skip_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let (text, start) = (self.text, self.pos);
+        while let Some(c) = self.peek().filter(|c| f(*c)) {
+            self.pos += c.len_utf8();
+        }
+        &text[start..self.pos]
+    }
+
+    fn error(&self, message: &str) -> InvalidPolynomial {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, position: usize, message: &str) -> InvalidPolynomial {
+        InvalidPolynomial { position, message: message.to_string() }
+    }
+
+    fn sum<Var, Coef, Exp>(&mut self) -> Result<Polynomial<Var, Coef, Exp>, InvalidPolynomial>
+    where
+        Var: Ord + FromStr,
+        Coef: FromStr + Add<Output = Coef> + Neg<Output = Coef> + Zero + One,
+        Exp: Ord + FromStr + Add<Output = Exp> + One,
+    {
+        let mut p = Polynomial::zero();
+        self.skip_whitespace();
+        let mut negate = self.peek() == Some('-');
+        if negate {
+            self.pos += 1;
+        }
+        loop {
+            let (coef, m) = self.term()?;
+            p += (if negate { -coef } else { coef }, m);
+            self.skip_whitespace();
+            match self.peek() {
+                Some('+') => negate = false,
+                Some('-') => negate = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        // Terms may have cancelled, as in `x - x`.
+        Ok(Polynomial(p.0.normalize()))
+    }
+
+    fn term<Var, Coef, Exp>(&mut self) -> Result<(Coef, Monomial<Var, Exp>), InvalidPolynomial>
+    where
+        Var: Ord + FromStr,
+        Coef: FromStr + One,
+        Exp: Ord + FromStr + Add<Output = Exp> + One,
+    {
+        self.skip_whitespace();
+        let start = self.pos;
+        let coef = match self.peek() {
+            Some('(') => Some(self.parenthesized()?),
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let number = self.skip_while(|c| c.is_ascii_digit() || c == '.' || c == '/');
+                Some(number.parse().map_err(|_| self.error_at(start, "invalid coefficient"))?)
+            }
+            _ => None,
+        };
+        let mut powers = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('*') => self.pos += 1,
+                Some(c) if c.is_alphabetic() || c == '_' => powers.push(self.power()?),
+                // The monomial `1`, displayed after the coefficient of a constant term.
+                Some('1') if coef.is_some() => {
+                    self.pos += 1;
+                    if self.peek().is_some_and(|c| c.is_alphanumeric() || c == '.') {
+                        return Err(self.error("expected a variable"));
+                    }
+                }
+                _ => break,
+            }
+        }
+        if coef.is_none() && powers.is_empty() {
+            return Err(self.error_at(start, "expected a term"));
+        }
+        let coef = coef.unwrap_or_else(Coef::one);
+        Ok((coef, powers.into_iter().collect()))
+    }
+
+    /// Parses a coefficient enclosed in parentheses.
+    fn parenthesized<Coef: FromStr>(&mut self) -> Result<Coef, InvalidPolynomial> {
+        let start = self.pos;
+        let mut depth = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => continue,
+            }
+            if depth == 0 {
+                self.pos = start + i + 1;
+                let inner = &self.text[start + 1..start + i];
+                return inner.parse().map_err(|_| self.error_at(start + 1, "invalid coefficient"));
+            }
+        }
+        Err(self.error_at(start, "unclosed `(`"))
+    }
+
+    fn power<Var: FromStr, Exp: FromStr + One>(&mut self) -> Result<(Var, Exp), InvalidPolynomial> {
+        let start = self.pos;
+        let name = self.skip_while(|c| c.is_alphanumeric() || c == '_');
+        let var = name
+            .parse()
+            .map_err(|_| self.error_at(start, &format!("invalid variable `{name}`")))?;
+        if self.peek() != Some('^') {
+            return Ok((var, Exp::one()));
+        }
+        self.pos += 1;
+        let start = self.pos;
+        let exp = if self.peek() == Some('{') {
+            self.pos += 1;
+            let exp = self.skip_while(|c| c != '}');
+            if self.peek() != Some('}') {
+                return Err(self.error_at(start, "unclosed `{`"));
+            }
+            self.pos += 1;
+            exp.trim()
+        } else {
+            if self.peek() == Some('-') {
+                self.pos += 1;
+            }
+            self.skip_while(|c| c.is_ascii_digit());
+            &self.text[start..self.pos]
+        };
+        let exp = exp.parse().map_err(|_| self.error_at(start, "expected an integer exponent"))?;
+        Ok((var, exp))
+    }
+}
+
+/// Division, GCDs, and factorization of polynomials over a field.
+///
+/// These operations use the lexicographic monomial ordering, as given by
+/// [`Monomial::lex_cmp`], and assume that the exponents are nonnegative
+/// integers. They rely on exact arithmetic, so the coefficients should be
+/// rational numbers, such as [`BigRational`](num_rational::BigRational), rather
+/// than floating point numbers.
+impl<Var, Coef, Exp> Polynomial<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    /// Constructs a polynomial with a single term.
+    fn term(coef: Coef, m: Monomial<Var, Exp>) -> Self {
+        [(coef, m)].into_iter().collect()
+    }
+
+    /// Gets the leading term of the polynomial, if it is nonzero.
+    pub fn leading_term(&self) -> Option<(&Coef, &Monomial<Var, Exp>)> {
+        self.terms()
+            .filter(|(coef, _)| !coef.is_zero())
+            .max_by(|(_, m), (_, n)| m.lex_cmp(n))
+    }
+
+    /// Scales the polynomial to make its leading coefficient one.
+    pub fn monic(self) -> Self {
+        let Some((coef, _)) = self.leading_term() else {
+            return self.normalize();
+        };
+        let scale = Coef::one() / coef.clone();
+        (self * scale).normalize()
+    }
+
+    /// Is the polynomial a constant, possibly zero?
+    pub fn is_constant(&self) -> bool {
+        self.terms().all(|(coef, m)| coef.is_zero() || m.clone().normalize().is_empty())
+    }
+
+    /// Divides the polynomial by a list of polynomials, with remainder.
+    ///
+    /// Returns the quotients, one for each divisor, and the remainder, none of
+    /// whose terms is divisible by the leading term of any divisor
+    /// ([*IVA*](crate::refs::IdealsVarietiesAlgorithms), Section 2.3). Zero
+    /// divisors are ignored, their quotients being zero.
+    pub fn div_rem(&self, divisors: &[Self]) -> (Vec<Self>, Self) {
+        let leading: Vec<_> = divisors
+            .iter()
+            .map(|g| g.leading_term().map(|(coef, m)| (coef.clone(), m.clone())))
+            .collect();
+        let mut quotients = vec![Self::zero(); divisors.len()];
+        let mut remainder = Self::zero();
+        let mut p = self.clone().normalize();
+        while let Some((coef, m)) = p.leading_term().map(|(coef, m)| (coef.clone(), m.clone())) {
+            let division = leading.iter().enumerate().find_map(|(i, lt)| {
+                let (d, n) = lt.as_ref()?;
+                Some((i, Self::term(coef.clone() / d.clone(), m.checked_div(n)?)))
+            });
+            if let Some((i, q)) = division {
+                p = p + -(q.clone() * divisors[i].clone());
+                quotients[i] = std::mem::take(&mut quotients[i]) + q;
+            } else {
+                remainder += (coef, m.clone());
+            }
+            // The leading term cancels, which is enforced to guard against
+            // rounding errors.
+            p = p.0.into_iter().filter(|(coef, n)| *n != m && !coef.is_zero()).collect();
+        }
+        (quotients.into_iter().map(Self::normalize).collect(), remainder.normalize())
+    }
+
+    /// Divides the polynomial by another that is known to divide it.
+    fn exact_div(&self, divisor: &Self) -> Self {
+        let (mut quotients, _) = self.div_rem(std::slice::from_ref(divisor));
+        quotients.pop().unwrap()
+    }
+
+    /// Divides the polynomial by another, if it divides exactly.
+    pub fn checked_div(&self, divisor: &Self) -> Option<Self> {
+        let (mut quotients, remainder) = self.div_rem(std::slice::from_ref(divisor));
+        remainder.is_zero().then(|| quotients.pop().unwrap())
+    }
+
+    /// Views the polynomial as a polynomial in a single variable, with
+    /// coefficients polynomials in the other variables, indexed by degree.
+    fn coefficients_in(&self, var: &Var) -> BTreeMap<Exp, Self> {
+        let mut coefs: BTreeMap<Exp, Self> = BTreeMap::new();
+        for (coef, m) in self.terms().filter(|(coef, _)| !coef.is_zero()) {
+            let degree = m.exponent(var).cloned().unwrap_or_else(Exp::zero);
+            let rest = m.clone().into_iter().filter(|(v, _)| v != var).collect();
+            *coefs.entry(degree).or_default() += (coef.clone(), rest);
+        }
+        coefs.retain(|_, coef| {
+            *coef = std::mem::take(coef).normalize();
+            !coef.is_zero()
+        });
+        coefs
+    }
+
+    /// Degree of the polynomial in a variable, or `None` if it is zero.
+    fn degree_in(&self, var: &Var) -> Option<Exp> {
+        self.coefficients_in(var).pop_last().map(|(degree, _)| degree)
+    }
+
+    /// Content of the polynomial in a variable: the GCD of its coefficients.
+    fn content_in(&self, var: &Var) -> Self {
+        self.coefficients_in(var).values().fold(Self::zero(), |acc, coef| acc.gcd(coef))
+    }
+
+    /// Pseudo-remainder of the polynomial divided by another in a variable.
+    fn pseudo_rem_in(&self, divisor: &Self, var: &Var) -> Self {
+        let Some((n, lead)) = divisor.coefficients_in(var).pop_last() else {
+            return self.clone();
+        };
+        let mut r = self.clone().normalize();
+        while let Some((d, r_lead)) = r.coefficients_in(var).pop_last() {
+            if d < n {
+                break;
+            }
+            let shift = Self::term(
+                Coef::one(),
+                [(var.clone(), d.clone() - n.clone())].into_iter().collect(),
+            );
+            r = lead.clone() * r + -(r_lead * shift * divisor.clone());
+            // The leading coefficient cancels, as above.
+            r =
+                r.0.into_iter()
+                    .filter(|(coef, m)| !coef.is_zero() && m.exponent(var) != Some(&d))
+                    .collect();
+        }
+        r
+    }
+
+    /// Computes the greatest common divisor of two polynomials.
+    ///
+    /// The GCD is made [monic](Self::monic) so that it is unique. It is
+    /// computed recursively in the variables, by the primitive polynomial
+    /// remainder sequence in the first variable and the GCD of contents in the
+    /// others. The GCD of zero and zero is zero.
+    pub fn gcd(&self, other: &Self) -> Self {
+        let (f, g) = (self.clone().normalize(), other.clone().normalize());
+        if f.is_zero() {
+            return g.monic();
+        }
+        if g.is_zero() {
+            return f.monic();
+        }
+        let Some(var) = f.monomials().chain(g.monomials()).flat_map(|m| m.variables()).min() else {
+            return Self::one();
+        };
+        let var = var.clone();
+
+        let (f_content, g_content) = (f.content_in(&var), g.content_in(&var));
+        let content = f_content.gcd(&g_content);
+        let (mut a, mut b) = (f.exact_div(&f_content), g.exact_div(&g_content));
+        if a.degree_in(&var) < b.degree_in(&var) {
+            std::mem::swap(&mut a, &mut b);
+        }
+        while !b.is_zero() {
+            if b.degree_in(&var) == Some(Exp::zero()) {
+                // A primitive polynomial of degree zero is a unit.
+                a = Self::one();
+                break;
+            }
+            let r = a.pseudo_rem_in(&b, &var);
+            a = b;
+            b = if r.is_zero() {
+                r
+            } else {
+                r.exact_div(&r.content_in(&var))
+            };
+        }
+        (content * a).monic()
+    }
+
+    /// Computes the square-free factorization of the polynomial.
+    ///
+    /// Returns the leading coefficient `c` and pairs `(f_i, i)` of monic,
+    /// square-free, and pairwise coprime polynomials with distinct
+    /// multiplicities, in increasing order, such that the polynomial is
+    /// `c f_1^1 ... f_k^k`. The factors are computed by Yun's algorithm in the
+    /// first variable and recursively in the others, so the coefficients should
+    /// have characteristic zero.
+    pub fn square_free_factorization(&self) -> (Coef, Vec<(Self, usize)>) {
+        let f = self.clone().normalize();
+        let Some((lead, _)) = f.leading_term() else {
+            return (Coef::zero(), Vec::new());
+        };
+        let lead = lead.clone();
+        let mut factors: BTreeMap<usize, Self> = BTreeMap::new();
+        f.monic().square_free_factors(&mut factors);
+        (lead, factors.into_iter().map(|(i, factor)| (factor, i)).collect())
+    }
+
+    /// Accumulates the square-free factors of a monic polynomial, multiplying
+    /// together factors of the same multiplicity.
+    fn square_free_factors(&self, factors: &mut BTreeMap<usize, Self>) {
+        let Some(var) = self.monomials().flat_map(|m| m.variables()).min() else {
+            return;
+        };
+        let var = var.clone();
+        let content = self.content_in(&var);
+        content.square_free_factors(factors);
+
+        // Yun's algorithm, applied to the primitive part.
+        let mut add_factor = |factor: Self, i: usize| {
+            if !factor.is_constant() {
+                let product = factors.remove(&i).unwrap_or_else(Self::one) * factor;
+                factors.insert(i, product.monic());
+            }
+        };
+        let f = self.exact_div(&content);
+        let df = f.partial_derivative(&var);
+        let a = f.gcd(&df);
+        let mut b = f.exact_div(&a);
+        let mut d = df.exact_div(&a) + -b.partial_derivative(&var);
+        let mut i = 1;
+        while !b.is_constant() {
+            let a = b.gcd(&d);
+            b = b.exact_div(&a);
+            d = d.exact_div(&a) + -b.partial_derivative(&var);
+            add_factor(a, i);
+            i += 1;
+        }
+    }
+}
+
+/// Multiplies an element of an abelian group by an integer.
+fn integer_multiple<A>(a: A, n: i32) -> A
+where
+    A: Clone + Zero + Add<Output = A> + Neg<Output = A>,
+{
+    let (mut result, mut power, mut k) = (A::zero(), a, n.unsigned_abs());
+    while k > 0 {
+        if k & 1 == 1 {
+            result = result + power.clone();
+        }
+        power = power.clone() + power;
+        k >>= 1;
+    }
+    if n < 0 { -result } else { result }
+}
+
 impl<Var, Coef, Exp> FromIterator<(Coef, Monomial<Var, Exp>)> for Polynomial<Var, Coef, Exp>
 where
     Var: Ord,
@@ -462,6 +1003,8 @@ where
 
 #[cfg(test)]
 mod tests {
+    use num_rational::Rational64;
+
     use super::*;
 
     #[test]
@@ -486,4 +1029,114 @@ mod tests {
         let p = (x() + y()) * (x() + y().neg());
         assert_eq!(p.normalize().to_string(), "x^2 - y^2");
     }
+
+    #[test]
+    fn parse_polynomials() {
+        type P = Polynomial<String, i32, u32>;
+        for text in ["2 x^2 y + 3 y", "x^2 - y^2", "2 1 - I S k_1", "x^{12}", "0"] {
+            assert_eq!(P::parse(text).unwrap().to_string(), text);
+        }
+        assert_eq!(P::parse("x*y - y x").unwrap(), P::zero());
+        assert_eq!(P::parse(" 2*x^2 ").unwrap(), P::parse("2 x x").unwrap());
+
+        let p: RationalPolynomial = "1/2 x^{-1} - 3".parse().unwrap();
+        assert_eq!(p.to_string(), "-3 1 + 1/2 x^{-1}");
+        assert_eq!(RationalPolynomial::parse(&p.to_string()), Ok(p));
+
+        // Coefficients that are themselves polynomials are parenthesized.
+        type Q = Polynomial<String, P, u32>;
+        let p = Q::parse("(1 + k) x - y").unwrap();
+        assert_eq!(p.to_string(), "(1 + k) x - y");
+
+        let err = P::parse("2 x +").unwrap_err();
+        assert_eq!((err.position, err.message.as_str()), (5, "expected a term"));
+        assert_eq!(P::parse("x^").unwrap_err().message, "expected an integer exponent");
+        assert_eq!(P::parse("x y)").unwrap_err().position, 3);
+        assert_eq!(P::parse("(2 x").unwrap_err().message, "unclosed `(`");
+        assert_eq!(RationalPolynomial::parse("xy").unwrap_err().message, "invalid variable `xy`");
+    }
+
+    #[test]
+    fn partial_derivatives() {
+        let x = || Polynomial::<_, i32, i8>::generator('x');
+        let y = || Polynomial::<_, i32, i8>::generator('y');
+        let p = x() * y() * x() * 2 + y() * x() * y() * 3;
+        assert_eq!(p.partial_derivative(&'x').to_string(), "4 x y + 3 y^2");
+        assert_eq!(p.partial_derivative(&'y').to_string(), "6 x y + 2 x^2");
+        assert!(p.partial_derivative(&'z').is_zero());
+
+        // Negative exponents and symbolic coefficients.
+        let k = Polynomial::<_, f32, i8>::generator('k');
+        let m: Monomial<_, i8> = [('x', -1)].into_iter().collect();
+        let p: Polynomial<_, _, i8> = [(k, m)].into_iter().collect();
+        let dp = p.partial_derivative(&'x').extend_scalars(|c| c.eval_pairs([('k', 4.0)]));
+        assert_eq!(dp.eval_pairs([('x', 2.0)]), -1.0);
+    }
+
+    #[test]
+    fn gradients_and_antiderivatives() {
+        let x = || Polynomial::<_, i32, i8>::generator('x');
+        let y = || Polynomial::<_, i32, i8>::generator('y');
+        let p = x() * y() * x() * 2 + y() * 3;
+        let grad = p.grad();
+        assert_eq!(grad.keys().collect::<Vec<_>>(), vec![&'x', &'y']);
+        assert_eq!(grad[&'x'].to_string(), "4 x y");
+        assert_eq!(grad[&'y'], p.partial_derivative(&'y'));
+
+        let x = || Polynomial::<_, f32, i8>::generator('x');
+        let y = || Polynomial::<_, f32, i8>::generator('y');
+        let p = x() * y() * 3.0 + y() * 2.0;
+        let q = p.antiderivative(&'x').unwrap();
+        assert_eq!(q.eval_pairs([('x', 2.0), ('y', 1.0)]), 10.0);
+        assert_eq!(q.partial_derivative(&'x'), p);
+
+        // Negative exponents, except for the logarithmic case.
+        let m: Monomial<_, i8> = [('x', -2)].into_iter().collect();
+        let p = Polynomial::<_, f32, _>::from_monomial(m);
+        let q = p.antiderivative(&'x').unwrap();
+        assert_eq!(q.eval_pairs([('x', 2.0)]), -0.5);
+        assert_eq!(q.antiderivative(&'x'), None);
+    }
+
+    type RationalPolynomial = Polynomial<char, Rational64, i8>;
+
+    fn q(n: i64) -> Rational64 {
+        Rational64::from_integer(n)
+    }
+
+    #[test]
+    fn division_and_gcd() {
+        let x = || RationalPolynomial::generator('x');
+        let y = || RationalPolynomial::generator('y');
+
+        // Example 2.3.2 in IVA.
+        let f = x() * x() * y() + x() * y() * y() + y() * y();
+        let divisors = [x() * y() + q(-1), y() * y() + q(-1)];
+        let (quotients, remainder) = f.div_rem(&divisors);
+        assert_eq!(quotients, vec![x() + y(), RationalPolynomial::one()]);
+        assert_eq!(remainder, x() + y() + q(1));
+        assert_eq!((f.clone() * y()).checked_div(&y()), Some(f));
+        assert_eq!(x().checked_div(&y()), None);
+
+        let f = (x() + y()) * (x() + y()) * (x() + q(-1)) * q(3);
+        let g = (x() + y()) * (x() + q(1)) * y();
+        assert_eq!(f.gcd(&g), x() + y());
+        assert_eq!(x().gcd(&y()), RationalPolynomial::one());
+        assert_eq!(RationalPolynomial::zero().gcd(&(x() * q(2))), x());
+    }
+
+    #[test]
+    fn square_free_factorization() {
+        let x = || RationalPolynomial::generator('x');
+        let y = || RationalPolynomial::generator('y');
+
+        let f = (x() + q(1)) * (x() + -y()) * (x() + -y()) * q(2);
+        let (lead, factors) = f.square_free_factorization();
+        assert_eq!(lead, q(2));
+        assert_eq!(factors, vec![(x() + q(1), 1), (x() + -y(), 2)]);
+
+        // Factors not involving the first variable come from the content.
+        let g = (x() + q(1)) * y() * y();
+        assert_eq!(g.square_free_factorization().1, vec![(x() + q(1), 1), (y(), 2)]);
+    }
 }
@@ -0,0 +1,278 @@
+//! Gröbner bases of polynomial ideals.
+//!
+//! A Gröbner basis of an ideal makes it possible to decide membership in the
+//! ideal and equality of ideals. Applied to the right-hand sides of a
+//! polynomial ODE system, it gives exact information about the steady states:
+//! the ideal is trivial exactly when there are none (over an algebraically
+//! closed field), and in the lexicographic order the basis is triangular, so
+//! that the steady states can be solved for one variable at a time
+//! ([*IVA*](crate::refs::IdealsVarietiesAlgorithms), Chapter 3).
+
+use num_traits::{One, Zero};
+use std::collections::BTreeSet;
+use std::ops::{Add, Sub};
+
+use super::alg::Polynomial;
+use super::rig::*;
+
+/// A reduced Gröbner basis of a polynomial ideal.
+///
+/// The basis is taken with respect to the lexicographic monomial order, in
+/// which earlier variables are greater (see [`Monomial::lex_cmp`]). A
+/// *reduced* Gröbner basis is unique: its polynomials are monic, and no term
+/// of one of them is divisible by the leading term of another
+/// ([*IVA*](crate::refs::IdealsVarietiesAlgorithms), Section 2.7). Two ideals
+/// are therefore equal if and only if their reduced Gröbner bases are equal.
+///
+/// The basis is computed by Buchberger's algorithm, so the coefficients should
+/// be rational numbers rather than floating point numbers.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GroebnerBasis<Var, Coef, Exp>(Vec<Polynomial<Var, Coef, Exp>>);
+
+impl<Var, Coef, Exp> GroebnerBasis<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    /// Computes the reduced Gröbner basis of the ideal generated by the given
+    /// polynomials.
+    ///
+    /// Uses Buchberger's algorithm, skipping the pairs ruled out by the two
+    /// criteria of Buchberger: pairs with coprime leading monomials, and pairs
+    /// whose S-polynomial reduces to zero by a chain of already processed pairs
+    /// ([*IVA*](crate::refs::IdealsVarietiesAlgorithms), Section 2.10).
+    pub fn new(generators: &[Polynomial<Var, Coef, Exp>]) -> Self {
+        let mut basis: Vec<_> =
+            generators.iter().map(|f| f.clone().monic()).filter(|f| !f.is_zero()).collect();
+        let mut leading: Vec<_> = basis.iter().map(leading_monomial).collect();
+        let mut pairs: BTreeSet<(usize, usize)> =
+            (0..basis.len()).flat_map(|j| (0..j).map(move |i| (i, j))).collect();
+
+        while let Some((i, j)) = pairs.pop_first() {
+            let lcm = monomial_lcm(&leading[i], &leading[j]);
+            if lcm == leading[i].clone() * leading[j].clone() {
+                continue;
+            }
+            let pending = |k: usize, l: usize| pairs.contains(&(k.min(l), k.max(l)));
+            let chain = (0..basis.len()).any(|k| {
+                k != i
+                    && k != j
+                    && !pending(i, k)
+                    && !pending(j, k)
+                    && lcm.checked_div(&leading[k]).is_some()
+            });
+            if chain {
+                continue;
+            }
+            let s = s_polynomial(&basis[i], &basis[j], &leading[i], &leading[j], &lcm);
+            let (_, remainder) = s.div_rem(&basis);
+            if !remainder.is_zero() {
+                let k = basis.len();
+                let remainder = remainder.monic();
+                leading.push(leading_monomial(&remainder));
+                basis.push(remainder);
+                pairs.extend((0..k).map(|i| (i, k)));
+            }
+        }
+
+        Self(reduce(basis))
+    }
+
+    /// Gets the polynomials of the basis.
+    ///
+    /// The polynomials are sorted by their leading monomials, in decreasing
+    /// order.
+    pub fn polynomials(&self) -> &[Polynomial<Var, Coef, Exp>] {
+        &self.0
+    }
+
+    /// Reduces a polynomial modulo the ideal.
+    ///
+    /// The remainder on division by a Gröbner basis is the unique polynomial
+    /// congruent to the given one modulo the ideal, none of whose terms is
+    /// divisible by a leading term of the basis.
+    pub fn normal_form(&self, p: &Polynomial<Var, Coef, Exp>) -> Polynomial<Var, Coef, Exp> {
+        let (_, remainder) = p.div_rem(&self.0);
+        remainder
+    }
+
+    /// Does the ideal contain the polynomial?
+    pub fn contains(&self, p: &Polynomial<Var, Coef, Exp>) -> bool {
+        self.normal_form(p).is_zero()
+    }
+
+    /// Is the ideal the whole polynomial ring?
+    ///
+    /// By the weak Nullstellensatz, this happens exactly when the polynomials
+    /// have no common zero over an algebraically closed field.
+    pub fn is_trivial(&self) -> bool {
+        self.0.iter().any(|g| g.is_constant())
+    }
+
+    /// Iterates over the polynomials of the basis in which neither the given
+    /// variable nor any earlier variable appears.
+    ///
+    /// By the elimination theorem, these polynomials form a Gröbner basis of
+    /// the elimination ideal, which consists of all polynomials in the ideal
+    /// involving only the later variables
+    /// ([*IVA*](crate::refs::IdealsVarietiesAlgorithms), Section 3.1).
+    pub fn eliminate<'a>(
+        &'a self,
+        var: &'a Var,
+    ) -> impl Iterator<Item = &'a Polynomial<Var, Coef, Exp>> + 'a {
+        self.0
+            .iter()
+            .filter(move |g| g.monomials().all(|m| m.variables().all(|v| v > var)))
+    }
+}
+
+/// Gets the leading monomial of a nonzero polynomial.
+fn leading_monomial<Var, Coef, Exp>(p: &Polynomial<Var, Coef, Exp>) -> Monomial<Var, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    let (_, m) = p.leading_term().expect("Polynomial should be nonzero");
+    m.clone()
+}
+
+/// Computes the least common multiple of two monomials.
+fn monomial_lcm<Var, Exp>(m: &Monomial<Var, Exp>, n: &Monomial<Var, Exp>) -> Monomial<Var, Exp>
+where
+    Var: Clone + Ord,
+    Exp: Clone + Ord + Zero + Add<Output = Exp>,
+{
+    let zero = Exp::zero();
+    let vars: BTreeSet<_> = m.variables().chain(n.variables()).collect();
+    vars.into_iter()
+        .map(|var| {
+            let exp = m.exponent(var).unwrap_or(&zero).max(n.exponent(var).unwrap_or(&zero));
+            (var.clone(), exp.clone())
+        })
+        .collect()
+}
+
+/// Computes the S-polynomial of two monic polynomials, given their leading
+/// monomials and the least common multiple thereof.
+fn s_polynomial<Var, Coef, Exp>(
+    f: &Polynomial<Var, Coef, Exp>,
+    g: &Polynomial<Var, Coef, Exp>,
+    m: &Monomial<Var, Exp>,
+    n: &Monomial<Var, Exp>,
+    lcm: &Monomial<Var, Exp>,
+) -> Polynomial<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    let cofactor = |m: &Monomial<Var, Exp>| -> Polynomial<Var, Coef, Exp> {
+        Polynomial::from_monomial(lcm.checked_div(m).expect("LCM is a multiple"))
+    };
+    (cofactor(m) * f.clone() + -(cofactor(n) * g.clone())).normalize()
+}
+
+/// Reduces a Gröbner basis consisting of monic polynomials.
+fn reduce<Var, Coef, Exp>(basis: Vec<Polynomial<Var, Coef, Exp>>) -> Vec<Polynomial<Var, Coef, Exp>>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    // Drop the polynomials whose leading monomial is divisible by that of
+    // another, keeping the first of those with equal leading monomials.
+    let leading: Vec<_> = basis.iter().map(leading_monomial).collect();
+    let minimal: Vec<_> = basis
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            !leading.iter().enumerate().any(|(j, n)| {
+                j != *i && leading[*i].checked_div(n).is_some() && (leading[*i] != *n || j < *i)
+            })
+        })
+        .map(|(_, g)| g)
+        .collect();
+
+    // Reduce each polynomial by the others, which leaves its leading term.
+    let mut reduced: Vec<_> = (0..minimal.len())
+        .map(|i| {
+            let others: Vec<_> = minimal
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, g)| g.clone())
+                .collect();
+            let (_, remainder) = minimal[i].div_rem(&others);
+            remainder
+        })
+        .collect();
+    reduced.sort_by(|f, g| leading_monomial(g).lex_cmp(&leading_monomial(f)));
+    reduced
+}
+
+#[cfg(test)]
+mod tests {
+    use num_rational::Rational64;
+
+    use super::*;
+
+    type RationalPolynomial = Polynomial<char, Rational64, i8>;
+    type RationalBasis = GroebnerBasis<char, Rational64, i8>;
+
+    fn q(n: i64) -> Rational64 {
+        Rational64::from_integer(n)
+    }
+
+    #[test]
+    fn reduced_basis() {
+        let x = || RationalPolynomial::generator('x');
+        let y = || RationalPolynomial::generator('y');
+
+        // IVA, Section 2.7, Example 1, in the lexicographic order.
+        let f = x() * x() * x() + x() * y() * q(-2);
+        let g = x() * x() * y() + y() * y() * q(-2) + x();
+        let basis = RationalBasis::new(&[f.clone(), g.clone()]);
+        assert_eq!(basis.polynomials(), &[x() + y() * y() * q(-2), y() * y() * y()]);
+        assert_eq!(basis, RationalBasis::new(&[x() + y() * y() * q(-2), y() * y() * y()]));
+
+        assert!(basis.contains(&f) && basis.contains(&g));
+        assert!(basis.contains(&(x() * x())));
+        assert!(!basis.contains(&(y() * y())));
+        assert_eq!(basis.normal_form(&(x() * y())), RationalPolynomial::zero());
+        assert!(!basis.is_trivial());
+    }
+
+    #[test]
+    fn elimination() {
+        let x = || RationalPolynomial::generator('x');
+        let y = || RationalPolynomial::generator('y');
+        let z = || RationalPolynomial::generator('z');
+
+        // Parametrized curve `(y, z) = (x^2, x^3)`.
+        let basis = RationalBasis::new(&[x() * x() + -y(), x() * x() * x() + -z()]);
+        assert_eq!(
+            basis.polynomials(),
+            &[
+                x() * x() + -y(),
+                x() * y() + -z(),
+                x() * z() + -(y() * y()),
+                y() * y() * y() + -(z() * z()),
+            ]
+        );
+        let eliminated: Vec<_> = basis.eliminate(&'x').cloned().collect();
+        assert_eq!(eliminated, vec![y() * y() * y() + -(z() * z())]);
+    }
+
+    #[test]
+    fn no_steady_states() {
+        let x = || RationalPolynomial::generator('x');
+        let y = || RationalPolynomial::generator('y');
+
+        // The system `x' = x y - 1, y' = x` has no steady states.
+        let basis = RationalBasis::new(&[x() * y() + q(-1), x()]);
+        assert!(basis.is_trivial());
+        assert_eq!(basis.polynomials(), &[RationalPolynomial::one()]);
+    }
+}
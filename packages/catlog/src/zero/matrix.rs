@@ -0,0 +1,392 @@
+//! Dense matrices over rigs.
+//!
+//! Matrices here have entries in an arbitrary [rig](super::rig::Rig), not just
+//! in the real or complex numbers as in numerical linear algebra libraries.
+//! Matrices over the [boolean rig](Boolean) are relations, so that powers of
+//! the adjacency matrix of a graph compute reachability. Over a
+//! [field](super::rig::Field), matrices can be row reduced to compute their
+//! rank and kernel. Over the integers, the [Smith normal form](SmithNormalForm)
+//! computes the invariant factors of a matrix, from which homology groups are
+//! read off.
+
+use num_traits::{One, Zero};
+use std::ops::{Add, Index, IndexMut, Mul};
+
+use super::rig::*;
+
+/// A dense matrix, stored in row-major order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix<T> {
+    nrows: usize,
+    ncols: usize,
+    entries: Vec<T>,
+}
+
+impl<T> Matrix<T> {
+    /// Constructs a matrix by computing each of its entries.
+    pub fn from_fn(nrows: usize, ncols: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        let entries = (0..nrows).flat_map(|i| (0..ncols).map(move |j| (i, j)));
+        let entries = entries.map(|(i, j)| f(i, j)).collect();
+        Self { nrows, ncols, entries }
+    }
+
+    /// Constructs a matrix from its rows, with the given number of columns.
+    ///
+    /// Panics if any row has a different number of entries.
+    pub fn from_rows(ncols: usize, rows: impl IntoIterator<Item = Vec<T>>) -> Self {
+        let mut nrows = 0;
+        let mut entries = Vec::new();
+        for row in rows {
+            assert_eq!(row.len(), ncols, "Rows should have the same number of entries");
+            entries.extend(row);
+            nrows += 1;
+        }
+        Self { nrows, ncols, entries }
+    }
+
+    /// Number of rows of the matrix.
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Number of columns of the matrix.
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Is the matrix square?
+    pub fn is_square(&self) -> bool {
+        self.nrows == self.ncols
+    }
+
+    /// Gets a row of the matrix.
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.entries[i * self.ncols..(i + 1) * self.ncols]
+    }
+
+    /// Iterates over the rows of the matrix.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.nrows).map(|i| self.row(i))
+    }
+
+    /// Computes the transpose of the matrix.
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_fn(self.ncols, self.nrows, |i, j| self[(j, i)].clone())
+    }
+
+    /// Maps the entries of the matrix.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Matrix<U> {
+        Matrix {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            entries: self.entries.into_iter().map(f).collect(),
+        }
+    }
+
+    fn swap_rows(&mut self, i: usize, k: usize) {
+        if i != k {
+            for j in 0..self.ncols {
+                self.entries.swap(i * self.ncols + j, k * self.ncols + j);
+            }
+        }
+    }
+
+    fn swap_cols(&mut self, j: usize, k: usize) {
+        if j != k {
+            for i in 0..self.nrows {
+                self.entries.swap(i * self.ncols + j, i * self.ncols + k);
+            }
+        }
+    }
+}
+
+impl<T: Zero> Matrix<T> {
+    /// Constructs the zero matrix of the given dimensions.
+    pub fn zeros(nrows: usize, ncols: usize) -> Self {
+        Self::from_fn(nrows, ncols, |_, _| T::zero())
+    }
+}
+
+impl<T: Zero + One> Matrix<T> {
+    /// Constructs the identity matrix of the given size.
+    pub fn identity(n: usize) -> Self {
+        Self::from_fn(n, n, |i, j| if i == j { T::one() } else { T::zero() })
+    }
+}
+
+impl<T: Clone + Rig> Matrix<T> {
+    /// Raises a square matrix to a power, by repeated squaring.
+    ///
+    /// Panics if the matrix is not square.
+    pub fn pow(&self, mut n: u32) -> Self {
+        assert!(self.is_square(), "Matrix should be square");
+        let (mut result, mut power) = (Self::identity(self.nrows), self.clone());
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result * power.clone();
+            }
+            power = power.clone() * power;
+            n >>= 1;
+        }
+        result
+    }
+}
+
+/// Row reduction over a field.
+///
+/// The pivots are the first nonzero entries found, so these algorithms rely on
+/// exact arithmetic and the entries should be rational numbers rather than
+/// floating point numbers.
+impl<T> Matrix<T>
+where
+    T: Clone + Field,
+{
+    /// Computes the reduced row echelon form of the matrix.
+    ///
+    /// Returns the reduced matrix and the columns of its pivots, in order.
+    pub fn rref(&self) -> (Self, Vec<usize>) {
+        let mut a = self.clone();
+        let mut pivots = Vec::new();
+        for j in 0..a.ncols {
+            let r = pivots.len();
+            let Some(i) = (r..a.nrows).find(|&i| !a[(i, j)].is_zero()) else {
+                continue;
+            };
+            a.swap_rows(r, i);
+            let scale = T::one() / a[(r, j)].clone();
+            for k in 0..a.ncols {
+                a[(r, k)] = a[(r, k)].clone() * scale.clone();
+            }
+            for i in (0..a.nrows).filter(|&i| i != r) {
+                let factor = a[(i, j)].clone();
+                if !factor.is_zero() {
+                    for k in 0..a.ncols {
+                        a[(i, k)] = a[(i, k)].clone() + -(factor.clone() * a[(r, k)].clone());
+                    }
+                }
+            }
+            pivots.push(j);
+            if pivots.len() == a.nrows {
+                break;
+            }
+        }
+        (a, pivots)
+    }
+
+    /// Computes the rank of the matrix.
+    pub fn rank(&self) -> usize {
+        self.rref().1.len()
+    }
+
+    /// Computes a basis of the kernel of the matrix.
+    ///
+    /// The kernel, or null space, comprises the vectors `v` such that `Av = 0`.
+    /// There is one basis vector for each non-pivot column of the reduced row
+    /// echelon form.
+    pub fn kernel(&self) -> Vec<Vec<T>> {
+        let (a, pivots) = self.rref();
+        let free = (0..a.ncols).filter(|j| pivots.binary_search(j).is_err());
+        free.map(|f| {
+            let mut v = vec![T::zero(); a.ncols];
+            v[f] = T::one();
+            for (r, &p) in pivots.iter().enumerate() {
+                v[p] = -a[(r, f)].clone();
+            }
+            v
+        })
+        .collect()
+    }
+}
+
+/// The Smith normal form of an integer matrix.
+///
+/// For a matrix `A`, this is a diagonal matrix `D = UAV`, where `U` and `V` are
+/// invertible over the integers, whose diagonal entries are nonnegative and
+/// each divide the next. The nonzero diagonal entries are the *invariant
+/// factors* of `A`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmithNormalForm {
+    /// The invertible matrix `U` acting on rows.
+    pub left: Matrix<i64>,
+
+    /// The diagonal matrix `D`.
+    pub diagonal: Matrix<i64>,
+
+    /// The invertible matrix `V` acting on columns.
+    pub right: Matrix<i64>,
+}
+
+impl SmithNormalForm {
+    /// Gets the invariant factors, the nonzero diagonal entries.
+    pub fn invariant_factors(&self) -> Vec<i64> {
+        let n = self.diagonal.nrows.min(self.diagonal.ncols);
+        (0..n).map(|i| self.diagonal[(i, i)]).take_while(|d| *d != 0).collect()
+    }
+
+    /// Gets the rank of the matrix.
+    pub fn rank(&self) -> usize {
+        self.invariant_factors().len()
+    }
+}
+
+impl Matrix<i64> {
+    /// Computes the Smith normal form of the matrix.
+    ///
+    /// The form is computed by elimination, repeatedly moving an entry of least
+    /// absolute value to the diagonal and reducing its row and column by it.
+    pub fn smith_normal_form(&self) -> SmithNormalForm {
+        let (m, n) = (self.nrows, self.ncols);
+        let (mut u, mut d, mut v) = (Matrix::identity(m), self.clone(), Matrix::identity(n));
+        for t in 0..m.min(n) {
+            loop {
+                let Some((i, j)) = (t..m)
+                    .flat_map(|i| (t..n).map(move |j| (i, j)))
+                    .filter(|&(i, j)| d[(i, j)] != 0)
+                    .min_by_key(|&(i, j)| d[(i, j)].abs())
+                else {
+                    return SmithNormalForm { left: u, diagonal: d, right: v };
+                };
+                d.swap_rows(t, i);
+                u.swap_rows(t, i);
+                d.swap_cols(t, j);
+                v.swap_cols(t, j);
+
+                let p = d[(t, t)];
+                let mut reduced = true;
+                for i in t + 1..m {
+                    let q = d[(i, t)] / p;
+                    add_row_multiple(&mut d, i, t, -q);
+                    add_row_multiple(&mut u, i, t, -q);
+                    reduced &= d[(i, t)] == 0;
+                }
+                for j in t + 1..n {
+                    let q = d[(t, j)] / p;
+                    add_col_multiple(&mut d, j, t, -q);
+                    add_col_multiple(&mut v, j, t, -q);
+                    reduced &= d[(t, j)] == 0;
+                }
+                if !reduced {
+                    continue;
+                }
+                // The pivot must divide every remaining entry. If not, adding
+                // the offending row brings a smaller remainder into play.
+                let offending = (t + 1..m).find(|&i| (t + 1..n).any(|j| d[(i, j)] % p != 0));
+                let Some(i) = offending else {
+                    break;
+                };
+                add_row_multiple(&mut d, t, i, 1);
+                add_row_multiple(&mut u, t, i, 1);
+            }
+            if d[(t, t)] < 0 {
+                add_row_multiple(&mut d, t, t, -2);
+                add_row_multiple(&mut u, t, t, -2);
+            }
+        }
+        SmithNormalForm { left: u, diagonal: d, right: v }
+    }
+}
+
+/// Adds a multiple of one row of an integer matrix to another.
+fn add_row_multiple(a: &mut Matrix<i64>, target: usize, source: usize, q: i64) {
+    for k in 0..a.ncols {
+        let x = q * a[(source, k)];
+        a[(target, k)] += x;
+    }
+}
+
+/// Adds a multiple of one column of an integer matrix to another.
+fn add_col_multiple(a: &mut Matrix<i64>, target: usize, source: usize, q: i64) {
+    for k in 0..a.nrows {
+        let x = q * a[(k, source)];
+        a[(k, target)] += x;
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        assert!(i < self.nrows && j < self.ncols, "Index should be within bounds");
+        &self.entries[i * self.ncols + j]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
+        assert!(i < self.nrows && j < self.ncols, "Index should be within bounds");
+        &mut self.entries[i * self.ncols + j]
+    }
+}
+
+/// Add matrices entrywise, panicking if their dimensions differ.
+impl<T: AdditiveMonoid> Add for Matrix<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        assert_eq!((self.nrows, self.ncols), (rhs.nrows, rhs.ncols), "Dimensions should agree");
+        let entries = self.entries.into_iter().zip(rhs.entries).map(|(a, b)| a + b).collect();
+        Self { entries, ..self }
+    }
+}
+
+/// Multiply matrices, panicking if their dimensions are incompatible.
+impl<T: Clone + Rig> Mul for Matrix<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        assert_eq!(self.ncols, rhs.nrows, "Dimensions should be compatible");
+        Self::from_fn(self.nrows, rhs.ncols, |i, j| {
+            (0..self.ncols)
+                .fold(T::zero(), |acc, k| acc + self[(i, k)].clone() * rhs[(k, j)].clone())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_rational::Rational64;
+
+    use super::*;
+
+    #[test]
+    fn boolean_reachability() {
+        // Adjacency matrix of the path graph `0 -> 1 -> 2`.
+        let adjacency = Matrix::from_fn(3, 3, |i, j| Boolean(j == i + 1));
+        let reachable = (Matrix::identity(3) + adjacency.clone()).pow(2);
+        assert_eq!(reachable[(0, 2)], Boolean(true));
+        assert_eq!(reachable[(2, 0)], Boolean(false));
+        assert!(adjacency.pow(3).entries.iter().all(|b| b.is_zero()));
+    }
+
+    #[test]
+    fn kernel() {
+        let q = Rational64::from_integer;
+        let a = Matrix::from_rows(3, [vec![q(1), q(2), q(3)], vec![q(2), q(4), q(6)]]);
+        assert_eq!(a.rank(), 1);
+        let kernel = a.kernel();
+        assert_eq!(kernel.len(), 2);
+        for v in kernel {
+            let v = Matrix::from_fn(3, 1, |i, _| v[i]);
+            assert_eq!(a.clone() * v, Matrix::zeros(2, 1));
+        }
+        assert!(Matrix::<Rational64>::identity(3).kernel().is_empty());
+    }
+
+    #[test]
+    fn smith_normal_form() {
+        let a = Matrix::from_rows(3, [vec![2, 4, 4], vec![-6, 6, 12], vec![10, -4, -16]]);
+        let snf = a.smith_normal_form();
+        assert_eq!(snf.invariant_factors(), vec![2, 6, 12]);
+        assert_eq!(snf.left.clone() * a * snf.right.clone(), snf.diagonal);
+
+        // A matrix of rank one, with a zero row and column.
+        let a = Matrix::from_rows(2, [vec![0, 0], vec![0, 3]]);
+        let snf = a.smith_normal_form();
+        assert_eq!(snf.invariant_factors(), vec![3]);
+        assert_eq!(snf.rank(), 1);
+        assert_eq!(snf.left.clone() * a * snf.right.clone(), snf.diagonal);
+    }
+}
@@ -2,7 +2,10 @@
 
 pub mod alg;
 pub mod column;
+pub mod groebner;
+pub mod matrix;
 pub mod qualified;
+pub mod rational_function;
 pub mod rig;
 pub mod set;
 
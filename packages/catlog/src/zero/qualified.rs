@@ -1,7 +1,8 @@
 //! Qualified names and labels.
 
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
-use std::{collections::HashMap, hash::Hash};
+use std::hash::Hash;
 
 use derive_more::From;
 use itertools::Itertools;
@@ -427,10 +428,21 @@ impl QualifiedLabel {
 }
 
 /// A namespace in which to resolve qualified labels as qualified names.
+///
+/// Besides resolving labels, a namespace assigns *display labels* to UUIDs for
+/// use in text output. Distinct UUIDs with the same label are disambiguated
+/// with primes, in the order that their labels were first set: `x`, `x′`, `x″`,
+/// and so on, skipping any primed label that is itself the label of a UUID.
+/// UUIDs set with an empty label are displayed as `_`, also disambiguated with
+/// primes. Display labels can also be set explicitly,
+/// taking precedence over the assigned ones.
 #[derive(Clone, Debug)]
 pub struct Namespace {
     inner: HashMap<NameSegment, Namespace>,
     uuid_labels: Option<IndexedHashColumn<Uuid, LabelSegment>>,
+    uuid_order: HashMap<Uuid, usize>,
+    display_labels: HashMap<Uuid, LabelSegment>,
+    pinned_labels: HashMap<Uuid, LabelSegment>,
 }
 
 /// The result of looking up a qualified name by qualified label.
@@ -456,14 +468,17 @@ impl Namespace {
         Self {
             inner: Default::default(),
             uuid_labels: None,
+            uuid_order: Default::default(),
+            display_labels: Default::default(),
+            pinned_labels: Default::default(),
         }
     }
 
     /// Creates an empty namespace for UUID segments.
     pub fn new_for_uuid() -> Self {
         Self {
-            inner: Default::default(),
             uuid_labels: Some(Default::default()),
+            ..Self::new_for_text()
         }
     }
 
@@ -478,16 +493,71 @@ impl Namespace {
     /// Sets the label segment associated with a UUID.
     pub fn set_label(&mut self, uuid: Uuid, label: LabelSegment) {
         let uuid_labels = self.uuid_labels.as_mut().expect("Should be a UUID namespace");
-        if let LabelSegment::Text(s) = &label
+        let old_label = if let LabelSegment::Text(s) = &label
             && s.is_empty()
         {
             // Treat an empty label as no label at all.
-            uuid_labels.unset(&uuid);
+            uuid_labels.unset(&uuid)
         } else {
-            uuid_labels.set(uuid, label);
+            uuid_labels.set(uuid, label)
+        };
+
+        let order = self.uuid_order.len();
+        self.uuid_order.entry(uuid).or_insert(order);
+        if old_label.is_none() || old_label != uuid_labels.apply_to_ref(&uuid) {
+            self.assign_display_labels();
         }
     }
 
+    /// Sets the display label of a UUID, overriding the assigned one.
+    ///
+    /// Unlike [`set_label`](Self::set_label), this does not affect how labels
+    /// are resolved as names.
+    pub fn set_display_label(&mut self, uuid: Uuid, label: LabelSegment) {
+        self.pinned_labels.insert(uuid, label);
+    }
+
+    /// Assigns display labels to all UUIDs in the namespace.
+    ///
+    /// The primed labels skip any that are already the label of some UUID, so
+    /// that a display label never coincides with another UUID's label.
+    fn assign_display_labels(&mut self) {
+        let Some(uuid_labels) = self.uuid_labels.as_ref() else {
+            return;
+        };
+        let mut uuids: Vec<_> = self.uuid_order.keys().copied().collect();
+        uuids.sort_by_key(|uuid| self.uuid_order[uuid]);
+
+        let mut taken: HashSet<String> =
+            uuid_labels.values().map(|label| label.to_string()).collect();
+        let mut seen = HashSet::new();
+        self.display_labels.clear();
+        for uuid in uuids {
+            let label = uuid_labels.apply_to_ref(&uuid);
+            if let Some(label) = label
+                && seen.insert(label)
+            {
+                continue;
+            }
+            let base = label.map_or_else(|| "_".to_string(), |label| label.to_string());
+            let display = (usize::from(label.is_some())..)
+                .map(|n| format!("{base}{}", primes(n)))
+                .find(|display| !taken.contains(display))
+                .expect("Some primed label should be free");
+            taken.insert(display.clone());
+            self.display_labels.insert(uuid, LabelSegment::Text(display.into()));
+        }
+    }
+
+    /// Gets the label under which a UUID is displayed, if any.
+    fn display_label(&self, uuid: &Uuid) -> Option<LabelSegment> {
+        self.pinned_labels
+            .get(uuid)
+            .or_else(|| self.display_labels.get(uuid))
+            .copied()
+            .or_else(|| self.uuid_labels.as_ref()?.apply_to_ref(uuid))
+    }
+
     /// Tries to get a human-readable label for a name.
     pub fn label(&self, name: &QualifiedName) -> Option<QualifiedLabel> {
         let mut namespace = Some(self);
@@ -509,17 +579,16 @@ impl Namespace {
 
     /// Gets a human-readable string label for a name.
     ///
-    /// Unlike [`label`](Self::label), this method is infallible: UUIDs without
-    /// a corresponding label are just displayed directly. That makes this
-    /// method suitable for debugging and text dumps but not for situations when
-    /// a user should never see a UUID.
+    /// Unlike [`label`](Self::label), this method is infallible and uses the
+    /// disambiguated display labels, so it is suitable for text output. UUIDs
+    /// unknown to the namespace are displayed directly, which should only
+    /// happen when debugging.
     pub fn label_string(&self, name: &QualifiedName) -> String {
         let mut namespace = Some(self);
         let labels = name.segments().map(|segment| {
             let label = match segment {
                 NameSegment::Uuid(uuid) => namespace
-                    .and_then(|ns| ns.uuid_labels.as_ref())
-                    .and_then(|ul| ul.apply_to_ref(uuid))
+                    .and_then(|ns| ns.display_label(uuid))
                     .unwrap_or_else(|| LabelSegment::Text(uuid.braced().to_string().into())),
                 NameSegment::Text(name) => LabelSegment::Text(*name),
             };
@@ -562,6 +631,17 @@ impl Namespace {
     }
 }
 
+/// Primes used to disambiguate the `n`th of several identical labels.
+fn primes(n: usize) -> String {
+    match n {
+        0 => String::new(),
+        1 => "′".into(),
+        2 => "″".into(),
+        3 => "‴".into(),
+        n => "′".repeat(n),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -636,4 +716,49 @@ mod tests {
         ambiguous.set_label(UUID2, "foo".into());
         assert!(matches!(ambiguous.name_with_label(&label("foo")), NameLookup::Arbitrary(_)));
     }
+
+    #[test]
+    fn display_labels() {
+        const UUID3: Uuid = uuid!("a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8");
+        let mut ns = Namespace::new_for_uuid();
+        ns.set_label(UUID1, "x".into());
+        ns.set_label(UUID2, "x".into());
+        ns.set_label(UUID3, "x".into());
+        assert_eq!(ns.label_string(&name(UUID1)), "x");
+        assert_eq!(ns.label_string(&name(UUID2)), "x′");
+        assert_eq!(ns.label_string(&name(UUID3)), "x″");
+        assert_eq!(ns.label(&name(UUID2)), Some(label("x")));
+
+        // Relabeling reassigns display labels in both groups.
+        ns.set_label(UUID1, "y".into());
+        assert_eq!(ns.label_string(&name(UUID1)), "y");
+        assert_eq!(ns.label_string(&name(UUID2)), "x");
+        assert_eq!(ns.label_string(&name(UUID3)), "x′");
+
+        // Unlabeled UUIDs are displayed as placeholders, not raw UUIDs.
+        ns.set_label(UUID1, "".into());
+        ns.set_label(UUID2, "".into());
+        assert_eq!(ns.label_string(&name(UUID1)), "_");
+        assert_eq!(ns.label_string(&name(UUID2)), "_′");
+        assert_eq!(ns.label_string(&name(UUID3)), "x");
+
+        ns.set_display_label(UUID3, "z".into());
+        assert_eq!(ns.label_string(&name(UUID3)), "z");
+        assert_eq!(ns.label(&name(UUID3)), Some(label("x")));
+    }
+
+    #[test]
+    fn display_labels_avoid_collisions() {
+        const UUID3: Uuid = uuid!("a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8");
+        let mut ns = Namespace::new_for_uuid();
+        ns.set_label(UUID1, "x".into());
+        ns.set_label(UUID2, "x".into());
+        assert_eq!(ns.label_string(&name(UUID2)), "x′");
+
+        // A label set later takes precedence over an assigned display label.
+        ns.set_label(UUID3, "x′".into());
+        assert_eq!(ns.label_string(&name(UUID1)), "x");
+        assert_eq!(ns.label_string(&name(UUID2)), "x″");
+        assert_eq!(ns.label_string(&name(UUID3)), "x′");
+    }
 }
@@ -0,0 +1,368 @@
+//! Rational functions in several variables.
+//!
+//! Rational functions are needed for rate laws that saturate, such as
+//! Michaelis-Menten kinetics `V S / (K + S)`, which are not polynomial.
+
+use num_traits::{One, Pow, Zero};
+use std::fmt::Display;
+use std::iter::{Product, Sum};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use super::alg::Polynomial;
+use super::rig::*;
+
+/// A rational function in several variables.
+///
+/// A **rational function** is a quotient of two polynomials. This data
+/// structure is for rational functions in *normal form*: the numerator and
+/// denominator have no common factor and the denominator is
+/// [monic](Polynomial::monic), so that equal rational functions have equal
+/// representations. The normal form is computed using [polynomial
+/// GCDs](Polynomial::gcd), so the coefficients should be rational numbers
+/// rather than floating point numbers.
+///
+/// In abstract terms, rational functions with coefficients in a
+/// [field](super::rig::Field) *K* form the field of fractions of the
+/// polynomials over *K*.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RationalFunction<Var, Coef, Exp> {
+    numerator: Polynomial<Var, Coef, Exp>,
+    denominator: Polynomial<Var, Coef, Exp>,
+}
+
+impl<Var, Coef, Exp> RationalFunction<Var, Coef, Exp> {
+    /// Gets the numerator of the rational function.
+    pub fn numerator(&self) -> &Polynomial<Var, Coef, Exp> {
+        &self.numerator
+    }
+
+    /// Gets the denominator of the rational function.
+    pub fn denominator(&self) -> &Polynomial<Var, Coef, Exp> {
+        &self.denominator
+    }
+
+    /// Evaluates the rational function by substituting for the variables.
+    pub fn eval<A, F>(&self, f: F) -> A
+    where
+        Var: Ord,
+        A: Clone + Mul<Coef, Output = A> + Pow<Exp, Output = A> + Sum + Product + Div<Output = A>,
+        F: Clone + FnMut(&Var) -> A,
+        Coef: Clone,
+        Exp: Clone + Ord,
+    {
+        self.numerator.eval(f.clone()) / self.denominator.eval(f)
+    }
+}
+
+impl<Var, Coef, Exp> RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    /// Constructs the quotient of two polynomials, in normal form.
+    ///
+    /// Panics if the denominator is zero.
+    pub fn new(
+        numerator: Polynomial<Var, Coef, Exp>,
+        denominator: Polynomial<Var, Coef, Exp>,
+    ) -> Self {
+        let denominator = denominator.normalize();
+        let (scale, _) = denominator.leading_term().expect("Denominator should be nonzero");
+        let scale = Coef::one() / scale.clone();
+        let (numerator, denominator) = (numerator.normalize() * scale.clone(), denominator * scale);
+        if numerator.is_zero() {
+            return Self::zero();
+        }
+        let gcd = numerator.gcd(&denominator);
+        let cancel = |p: Polynomial<_, _, _>| {
+            p.checked_div(&gcd).expect("GCD should divide polynomial").normalize()
+        };
+        // The GCD is monic, so the denominator stays monic.
+        Self {
+            numerator: cancel(numerator),
+            denominator: cancel(denominator),
+        }
+    }
+
+    /// Constructs the generating rational function corresponding to a variable.
+    pub fn generator(var: Var) -> Self {
+        Polynomial::generator(var).into()
+    }
+
+    /// Computes the multiplicative inverse, if the rational function is nonzero.
+    pub fn inv(self) -> Option<Self> {
+        (!self.is_zero()).then(|| Self::new(self.denominator, self.numerator))
+    }
+
+    /// Computes the partial derivative with respect to a variable.
+    ///
+    /// The derivative is computed by the quotient rule.
+    pub fn partial_derivative(&self, var: &Var) -> Self {
+        let (p, q) = (&self.numerator, &self.denominator);
+        Self::new(
+            p.partial_derivative(var) * q.clone() + -(p.clone() * q.partial_derivative(var)),
+            q.clone() * q.clone(),
+        )
+    }
+}
+
+impl<Var, Coef, Exp> From<Polynomial<Var, Coef, Exp>> for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Add<Output = Coef> + Zero + One,
+    Exp: Clone + Ord + Add<Output = Exp> + Zero,
+{
+    fn from(p: Polynomial<Var, Coef, Exp>) -> Self {
+        Self {
+            numerator: p.normalize(),
+            denominator: Polynomial::one(),
+        }
+    }
+}
+
+impl<Var, Coef, Exp> Display for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field + DisplayCoef,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+    Polynomial<Var, Coef, Exp>: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (num, den) = (&self.numerator, &self.denominator);
+        if den.is_constant() {
+            return write!(f, "{num}");
+        }
+        if num.needs_parentheses() {
+            write!(f, "({num})")?;
+        } else {
+            write!(f, "{num}")?;
+        }
+        if den.needs_parentheses() {
+            write!(f, " / ({den})")
+        } else {
+            write!(f, " / {den}")
+        }
+    }
+}
+
+impl<Var, Coef, Exp> Add for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.denominator == rhs.denominator {
+            return Self::new(self.numerator + rhs.numerator, self.denominator);
+        }
+        Self::new(
+            self.numerator * rhs.denominator.clone() + rhs.numerator * self.denominator.clone(),
+            self.denominator * rhs.denominator,
+        )
+    }
+}
+
+impl<Var, Coef, Exp> Zero for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    fn zero() -> Self {
+        Polynomial::zero().into()
+    }
+
+    fn is_zero(&self) -> bool {
+        self.numerator.is_zero()
+    }
+}
+
+impl<Var, Coef, Exp> Neg for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            numerator: -self.numerator,
+            denominator: self.denominator,
+        }
+    }
+}
+
+impl<Var, Coef, Exp> Mul for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(self.numerator * rhs.numerator, self.denominator * rhs.denominator)
+    }
+}
+
+/// Divide rational functions, panicking on division by zero.
+impl<Var, Coef, Exp> Div for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self * rhs.inv().expect("Division by zero")
+    }
+}
+
+impl<Var, Coef, Exp> One for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+    fn one() -> Self {
+        Polynomial::one().into()
+    }
+}
+
+impl<Var, Coef, Exp> AdditiveMonoid for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+}
+
+impl<Var, Coef, Exp> AbGroup for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+}
+
+impl<Var, Coef, Exp> Monoid for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+}
+
+impl<Var, Coef, Exp> CommMonoid for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+}
+
+impl<Var, Coef, Exp> Rig for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+}
+
+impl<Var, Coef, Exp> CommRig for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+}
+
+impl<Var, Coef, Exp> Ring for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+}
+
+impl<Var, Coef, Exp> CommRing for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+}
+
+impl<Var, Coef, Exp> Field for RationalFunction<Var, Coef, Exp>
+where
+    Var: Clone + Ord,
+    Coef: Clone + Default + PartialEq + Field,
+    Exp: Clone + Ord + Zero + One + Add<Output = Exp> + Sub<Output = Exp> + Into<i32>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use num_rational::Rational64;
+
+    use super::*;
+
+    type RationalPolynomial = Polynomial<char, Rational64, i8>;
+    type RationalFn = RationalFunction<char, Rational64, i8>;
+
+    fn q(n: i64) -> Rational64 {
+        Rational64::from_integer(n)
+    }
+
+    #[test]
+    fn normal_form() {
+        let x = || RationalPolynomial::generator('x');
+        let y = || RationalPolynomial::generator('y');
+
+        let f = RationalFn::new(x() * x() + q(-1), (x() + q(-1)) * q(2));
+        assert_eq!(f.numerator(), &((x() + q(1)) * Rational64::new(1, 2)));
+        assert_eq!(f.denominator(), &RationalPolynomial::one());
+
+        let f = RationalFn::new(x() * y() * q(3), x() * x() * q(-6));
+        assert_eq!(f, RationalFn::new(y() * Rational64::new(-1, 2), x()));
+        assert_eq!(RationalFn::new(RationalPolynomial::zero(), x()), RationalFn::zero());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let x = || RationalFn::generator('x');
+        let one = RationalFn::one;
+
+        // 1/(x-1) - 1/(x+1) = 2/(x^2-1)
+        let f = one() / (x() + -one()) + -(one() / (x() + one()));
+        let two = one() + one();
+        assert_eq!(f, two.clone() / (x() * x() + -one()));
+        assert_eq!(f.clone() * (x() * x() + -one()), two);
+        assert_eq!(f.clone() / f.clone(), one());
+        assert_eq!(RationalFn::zero().inv(), None);
+        assert_eq!(f.eval(|_| q(3)), Rational64::new(1, 4));
+    }
+
+    #[test]
+    fn michaelis_menten() {
+        // Rate law `V S / (K + S)` in the substrate `S`.
+        let var = RationalFn::generator;
+        let rate = var('V') * var('S') / (var('K') + var('S'));
+        assert_eq!(rate.to_string(), "S V / (K + S)");
+        let eval = |f: &RationalFn| f.eval(|v| if *v == 'S' { q(1) } else { q(2) });
+        assert_eq!(eval(&rate), Rational64::new(2, 3));
+
+        // Derivative in the substrate: `V K / (K + S)^2`.
+        let derivative = rate.partial_derivative(&'S');
+        assert_eq!(
+            derivative,
+            var('V') * var('K') / ((var('K') + var('S')) * (var('K') + var('S')))
+        );
+        assert_eq!(eval(&derivative), Rational64::new(4, 9));
+    }
+}
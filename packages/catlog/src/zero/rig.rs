@@ -13,11 +13,13 @@
 //! [linear combinations](Combination) and [monomials](Monomial). These are actually
 //! the same data structure, but with different notation!
 
+use num_rational::{BigRational, Rational64};
 use num_traits::{One, Pow, Signed, Zero};
-use std::collections::{BTreeMap, btree_map};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, btree_map};
 use std::fmt::Display;
 use std::iter::{Product, Sum};
-use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
 
 use derivative::Derivative;
 use duplicate::duplicate_item;
@@ -25,7 +27,7 @@ use duplicate::duplicate_item;
 /// A commutative monoid, written additively.
 pub trait AdditiveMonoid: Add<Output = Self> + Zero {}
 
-#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize])]
+#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize]; [Rational64]; [BigRational])]
 impl AdditiveMonoid for T {}
 
 /// An abelian group, written additively.
@@ -35,45 +37,103 @@ impl AdditiveMonoid for T {}
 /// automatically derived without macro magic.
 pub trait AbGroup: AdditiveMonoid + Neg<Output = Self> {}
 
-#[duplicate_item(T; [f32]; [f64]; [i32]; [i64])]
+#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [Rational64]; [BigRational])]
 impl AbGroup for T {}
 
 /// A monoid, written multiplicatively.
 pub trait Monoid: Mul<Output = Self> + One {}
 
-#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize])]
+#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize]; [Rational64]; [BigRational])]
 impl Monoid for T {}
 
 /// A commutative monoid, written multiplicatively.
 pub trait CommMonoid: Monoid {}
 
-#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize])]
+#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize]; [Rational64]; [BigRational])]
 impl CommMonoid for T {}
 
 /// A rig, also known as a semiring.
 pub trait Rig: Monoid + AdditiveMonoid {}
 
-#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize])]
+#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize]; [Rational64]; [BigRational])]
 impl Rig for T {}
 
 /// A commutative rig, also known as a commutative semiring.
 pub trait CommRig: Rig + CommMonoid {}
 
-#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize])]
+#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [u32]; [u64]; [usize]; [Rational64]; [BigRational])]
 impl CommRig for T {}
 
 /// A ring, assumed to be unital.
 pub trait Ring: Rig + AbGroup {}
 
-#[duplicate_item(T; [f32]; [f64]; [i32]; [i64])]
+#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [Rational64]; [BigRational])]
 impl Ring for T {}
 
 /// A commutative ring, assumed to be unital.
 pub trait CommRing: Ring + CommRig {}
 
-#[duplicate_item(T; [f32]; [f64]; [i32]; [i64])]
+#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [Rational64]; [BigRational])]
 impl CommRing for T {}
 
+/// A field.
+///
+/// Division by zero is not excluded by the type system, so it is up to callers
+/// to avoid it. Floating point numbers are regarded as a field, but algorithms
+/// relying on exact arithmetic in a field should be used with rational numbers.
+pub trait Field: CommRing + Div<Output = Self> {}
+
+#[duplicate_item(T; [f32]; [f64]; [Rational64]; [BigRational])]
+impl Field for T {}
+
+/// The boolean rig.
+///
+/// Addition is disjunction and multiplication is conjunction, so that matrices
+/// over the boolean rig are relations and their products compose relations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Boolean(pub bool);
+
+impl Add for Boolean {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Boolean(self.0 || rhs.0)
+    }
+}
+
+impl Mul for Boolean {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Boolean(self.0 && rhs.0)
+    }
+}
+
+impl Zero for Boolean {
+    fn zero() -> Self {
+        Boolean(false)
+    }
+
+    fn is_zero(&self) -> bool {
+        !self.0
+    }
+}
+
+impl One for Boolean {
+    fn one() -> Self {
+        Boolean(true)
+    }
+}
+
+impl Display for Boolean {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[duplicate_item(Trait; [AdditiveMonoid]; [Monoid]; [CommMonoid]; [Rig]; [CommRig])]
+impl Trait for Boolean {}
+
 /// A module over a commutative rig.
 pub trait RigModule: AdditiveMonoid + Mul<Self::Rig, Output = Self> {
     /// Base rig for the module.
@@ -110,7 +170,7 @@ impl DisplayCoef for T {
     }
 }
 
-#[duplicate_item(T; [f32]; [f64]; [i32]; [i64])]
+#[duplicate_item(T; [f32]; [f64]; [i32]; [i64]; [Rational64]; [BigRational])]
 impl DisplayCoef for T {
     fn has_negative_sign(&self) -> bool {
         self.is_negative()
@@ -446,9 +506,10 @@ where
 /// The underlying data structure is a [B-tree map](std::collections::BTreeMap) from
 /// variables to exponents. Thus, the variable type is assumed to be ordered.
 /// Moreover, when the exponents are also ordered, as they almost always are, the
-/// monomials themselves become ordered under the lexicographic order. This is a
-/// valid *monomial ordering* as used in Groebner bases
-/// ([*IVA*](crate::refs::IdealsVarietiesAlgorithms), Section 2.2).
+/// monomials themselves become ordered by comparing their maps. That order is
+/// not compatible with multiplication, so the *monomial ordering* as used in
+/// Groebner bases ([*IVA*](crate::refs::IdealsVarietiesAlgorithms), Section 2.2)
+/// is provided separately by [`lex_cmp`](Monomial::lex_cmp).
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Derivative)]
 #[derivative(Default(bound = ""))]
 pub struct Monomial<Var, Exp>(BTreeMap<Var, Exp>);
@@ -527,6 +588,53 @@ where
     {
         self.into_iter().filter(|(_, exp)| !exp.is_zero()).collect()
     }
+
+    /// Gets the exponent of a variable in the monomial, if it appears.
+    pub fn exponent(&self, var: &Var) -> Option<&Exp> {
+        self.0.get(var)
+    }
+
+    /// Compares monomials in the lexicographic monomial ordering.
+    ///
+    /// The exponents of the variables are compared in order of the variables,
+    /// so that earlier variables are greater: `x > y^2` when `x < y`. Absent
+    /// variables have exponent zero.
+    pub fn lex_cmp(&self, other: &Self) -> Ordering
+    where
+        Exp: Ord + Zero,
+    {
+        let zero = Exp::zero();
+        let vars: BTreeSet<_> = self.variables().chain(other.variables()).collect();
+        vars.into_iter()
+            .map(|var| {
+                let a = self.exponent(var).unwrap_or(&zero);
+                a.cmp(other.exponent(var).unwrap_or(&zero))
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// Divides by another monomial, if it divides this one.
+    ///
+    /// The exponents should be nonnegative.
+    pub fn checked_div(&self, other: &Self) -> Option<Self>
+    where
+        Var: Clone,
+        Exp: Clone + Ord + Zero + Sub<Output = Exp>,
+    {
+        let mut quotient = self.0.clone();
+        for (var, exp) in &other.0 {
+            if exp.is_zero() {
+                continue;
+            }
+            let e = quotient.get_mut(var)?;
+            if *e < *exp {
+                return None;
+            }
+            *e = e.clone() - exp.clone();
+        }
+        Some(Monomial(quotient).normalize())
+    }
 }
 
 /// Constructs a monomial from a sequence of variable-exponent pairs.
@@ -1,7 +1,7 @@
 import invariant from "tiny-invariant";
 import { v7 } from "uuid";
 
-import type { Cell, Notebook } from "catcolab-document-types";
+import type { Cell, Citation, Figure, Notebook } from "catcolab-document-types";
 
 /** A cell containing custom data, usually a formal object. */
 export type FormalCell<T> = Cell<T> & { tag: "formal" };
@@ -9,6 +9,15 @@ export type FormalCell<T> = Cell<T> & { tag: "formal" };
 /** A cell containing rich text. */
 export type RichTextCell = Cell<unknown> & { tag: "rich-text" };
 
+/** A cell displaying an attachment as a figure. */
+export type FigureCell = Cell<unknown> & { tag: "figure" };
+
+/** A cell citing a published work. */
+export type CitationCell = Cell<unknown> & { tag: "citation" };
+
+/** A cell listing the works cited in the notebook. */
+export type BibliographyCell = Cell<unknown> & { tag: "bibliography" };
+
 /** Creates an empty notebook. */
 export const newNotebook = <T>(): Notebook<T> => ({
     cellOrder: [],
@@ -22,6 +31,27 @@ export const newRichTextCell = (content?: string): RichTextCell => ({
     content: content ?? "",
 });
 
+/** Creates a figure cell displaying the attachment with the given ID. */
+export const newFigureCell = (attachmentId: string, caption?: string): FigureCell => ({
+    tag: "figure",
+    id: v7(),
+    content: { attachmentId, caption: caption ?? "", size: { tag: "auto" } } satisfies Figure,
+});
+
+/** Creates a citation cell citing a work by its DOI. */
+export const newCitationCell = (doi: string): CitationCell => ({
+    tag: "citation",
+    id: v7(),
+    content: { tag: "doi", doi } satisfies Citation,
+});
+
+/** Creates a bibliography cell with the default heading. */
+export const newBibliographyCell = (): BibliographyCell => ({
+    tag: "bibliography",
+    id: v7(),
+    content: { heading: "References" },
+});
+
 /** Creates a formal cell with the given content. */
 export const newFormalCell = <T>(content: T): FormalCell<T> => ({
     tag: "formal",
@@ -135,6 +165,12 @@ export function duplicateCell<T>(cell: Cell<T>, duplicateFn?: (cellContent: T) =
             const content = duplicateFn ? duplicateFn(cell.content) : structuredClone(cell.content);
             return newFormalCell(content);
         }
+        case "figure":
+            return { tag: "figure", id: v7(), content: structuredClone(cell.content) };
+        case "citation":
+            return { tag: "citation", id: v7(), content: structuredClone(cell.content) };
+        case "bibliography":
+            return { tag: "bibliography", id: v7(), content: structuredClone(cell.content) };
         case "rich-text":
             throw new Error("Rich text cells may not be duplicated");
         default:
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use tsify::Tsify;
+use uuid::Uuid;
+
+use super::cell::NotebookCell;
+use super::notebook::Notebook;
+
+/// An attachment declared in the manifest of a document.
+///
+/// The manifest lists the uploaded files that the document refers to, so that
+/// references from cells can be checked without contacting the backend.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct AttachmentEntry {
+    /// ID of the attachment in the backend.
+    pub id: Uuid,
+    /// Original file name of the attachment.
+    pub filename: String,
+    /// MIME type of the attachment, such as `image/png` or `text/csv`.
+    #[serde(rename = "contentType")]
+    pub content_type: String,
+}
+
+/// Content of a figure cell, displaying an attachment such as an image or CSV.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Figure {
+    /// ID of the attachment, which should be declared in the manifest.
+    #[serde(rename = "attachmentId")]
+    pub attachment_id: Uuid,
+    /// Caption of the figure, as rich text.
+    #[serde(default)]
+    pub caption: String,
+    /// Alternative text describing the figure, for accessibility.
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "altText")]
+    pub alt_text: Option<String>,
+    /// Size at which the figure is displayed.
+    #[serde(default)]
+    pub size: FigureSize,
+}
+
+/// Size at which a figure is displayed.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Serialize, Deserialize, Tsify)]
+#[serde(tag = "tag")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FigureSize {
+    /// Natural size of the attachment, shrunk to fit the notebook.
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    /// Percentage of the width of the notebook.
+    #[serde(rename = "relative")]
+    Relative { percent: u8 },
+    /// Fixed width in pixels, with the height scaled to preserve aspect ratio.
+    #[serde(rename = "fixed")]
+    Fixed { width: u32 },
+}
+
+/// A failure of the attachments of a document to be consistent.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum AttachmentError {
+    /// A cell refers to an attachment not declared in the manifest.
+    Undeclared { cell: Uuid, attachment: Uuid },
+    /// The manifest declares the same attachment more than once.
+    DuplicateEntry(Uuid),
+    /// A figure is sized relative to the notebook by more than 100 percent.
+    InvalidSize { cell: Uuid },
+}
+
+impl fmt::Display for AttachmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttachmentError::Undeclared { cell, attachment } => {
+                write!(f, "cell {cell} refers to undeclared attachment {attachment}")
+            }
+            AttachmentError::DuplicateEntry(id) => {
+                write!(f, "attachment {id} is declared more than once")
+            }
+            AttachmentError::InvalidSize { cell } => {
+                write!(f, "figure in cell {cell} is wider than the notebook")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttachmentError {}
+
+impl<T> Notebook<T> {
+    /// Iterates over the figure cells in the notebook, in order.
+    pub fn figures(&self) -> impl Iterator<Item = (Uuid, &Figure)> {
+        self.cells().filter_map(|cell| match cell {
+            NotebookCell::Figure { id, content } => Some((*id, content)),
+            _ => None,
+        })
+    }
+
+    /// Checks that the attachments referenced by the notebook are declared in
+    /// the manifest, returning all failures found.
+    pub fn validate_attachments(&self, manifest: &[AttachmentEntry]) -> Vec<AttachmentError> {
+        let mut errors = Vec::new();
+        let mut declared = HashSet::new();
+        for entry in manifest {
+            if !declared.insert(entry.id) {
+                errors.push(AttachmentError::DuplicateEntry(entry.id));
+            }
+        }
+        for (cell, figure) in self.figures() {
+            if !declared.contains(&figure.attachment_id) {
+                errors.push(AttachmentError::Undeclared { cell, attachment: figure.attachment_id });
+            }
+            if matches!(figure.size, FigureSize::Relative { percent } if percent > 100) {
+                errors.push(AttachmentError::InvalidSize { cell });
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn validate_figure_attachments() {
+        let (image, csv) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let cell = |id: u128, attachment: Uuid, size: FigureSize| {
+            let id = Uuid::from_u128(id);
+            let figure = Figure {
+                attachment_id: attachment,
+                caption: "A figure".into(),
+                alt_text: None,
+                size,
+            };
+            (id, NotebookCell::<()>::Figure { id, content: figure })
+        };
+        let cells = [
+            cell(10, image, FigureSize::Auto),
+            cell(11, csv, FigureSize::Relative { percent: 150 }),
+        ];
+        let notebook = Notebook {
+            cell_order: cells.iter().map(|(id, _)| *id).collect(),
+            cell_contents: cells.into_iter().collect(),
+        };
+        let entry = |id| AttachmentEntry {
+            id,
+            filename: "data".into(),
+            content_type: "text/csv".into(),
+        };
+
+        assert_eq!(notebook.figures().count(), 2);
+        assert_eq!(
+            notebook.validate_attachments(&[entry(image), entry(image)]),
+            vec![
+                AttachmentError::DuplicateEntry(image),
+                AttachmentError::Undeclared {
+                    cell: Uuid::from_u128(11),
+                    attachment: csv
+                },
+                AttachmentError::InvalidSize { cell: Uuid::from_u128(11) },
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_figure_cell() {
+        let id = Uuid::from_u128(1);
+        let cell: NotebookCell<()> = serde_json::from_value(json!({
+            "tag": "figure",
+            "id": id,
+            "content": { "attachmentId": id },
+        }))
+        .unwrap();
+        let NotebookCell::Figure { content, .. } = cell else {
+            panic!("Expected figure cell");
+        };
+        assert_eq!(content.size, FigureSize::Auto);
+        assert_eq!(content.caption, "");
+    }
+}
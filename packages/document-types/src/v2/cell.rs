@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use tsify::{Tsify, declare};
 use uuid::Uuid;
 
+use super::attachment::Figure;
+use super::citation::{Bibliography, Citation};
 use crate::v1;
 
 /// A cell in a notebook.
@@ -16,12 +18,29 @@ pub enum NotebookCell<T> {
     RichText { id: Uuid, content: String },
     #[serde(rename = "formal")]
     Formal { id: Uuid, content: T },
+    #[serde(rename = "figure")]
+    Figure { id: Uuid, content: Figure },
+    #[serde(rename = "citation")]
+    Citation { id: Uuid, content: Citation },
+    #[serde(rename = "bibliography")]
+    Bibliography { id: Uuid, content: Bibliography },
 }
 
 #[declare]
 pub type Cell<T> = NotebookCell<T>;
 
 impl<T> NotebookCell<T> {
+    /// ID of the cell.
+    pub fn id(&self) -> Uuid {
+        match self {
+            NotebookCell::RichText { id, .. }
+            | NotebookCell::Formal { id, .. }
+            | NotebookCell::Figure { id, .. }
+            | NotebookCell::Citation { id, .. }
+            | NotebookCell::Bibliography { id, .. } => *id,
+        }
+    }
+
     /// Migrate a [`v1::NotebookCell`] to v2.
     ///
     /// Stem cells are no longer representable, so attempting to migrate one
@@ -0,0 +1,395 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Write;
+use tsify::Tsify;
+use uuid::Uuid;
+
+use super::cell::NotebookCell;
+use super::notebook::Notebook;
+
+/// Content of a citation cell, referring to a published work.
+///
+/// A work can be cited either by a full bibliographic entry or just by its DOI,
+/// which the frontend can resolve to an entry for display.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize, Tsify)]
+#[serde(tag = "tag")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum Citation {
+    /// A bibliographic entry in CSL-JSON format.
+    #[serde(rename = "csl")]
+    Csl { entry: CslEntry },
+    /// A Digital Object Identifier, such as `10.1017/9781108668804`.
+    #[serde(rename = "doi")]
+    Doi { doi: String },
+}
+
+/// A bibliographic entry in CSL-JSON, the format of the Citation Style Language.
+///
+/// Only the commonly used variables of CSL-JSON are supported; others are
+/// dropped when the entry is deserialized.
+#[derive(PartialEq, Eq, Debug, Clone, Default, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct CslEntry {
+    /// Citation key of the entry.
+    pub id: String,
+    /// Type of the work, such as `article-journal` or `book`.
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub author: Vec<CslName>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issued: Option<CslDate>,
+    /// Title of the journal, book, or proceedings containing the work.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "container-title"
+    )]
+    pub container_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "DOI")]
+    pub doi: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "URL")]
+    pub url: Option<String>,
+}
+
+/// A name in CSL-JSON, either split into parts or given literally.
+#[derive(PartialEq, Eq, Debug, Clone, Default, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct CslName {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub given: Option<String>,
+    /// Name of an organization or other name not split into parts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub literal: Option<String>,
+}
+
+/// A date in CSL-JSON, as a list of year, month, and day.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct CslDate {
+    /// Parts of the date, one list for a single date or two for a range.
+    #[serde(rename = "date-parts")]
+    pub date_parts: Vec<Vec<i32>>,
+}
+
+/// Content of a bibliography cell, listing the works cited in the notebook.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Bibliography {
+    /// Heading displayed above the list of references.
+    #[serde(default = "default_heading")]
+    pub heading: String,
+}
+
+fn default_heading() -> String {
+    "References".into()
+}
+
+impl Default for Bibliography {
+    fn default() -> Self {
+        Self { heading: default_heading() }
+    }
+}
+
+impl CslName {
+    /// Formats the name with the family name first, as in "Lane, Saunders".
+    fn sorting_form(&self) -> String {
+        match (&self.family, &self.given, &self.literal) {
+            (Some(family), Some(given), _) => format!("{family}, {given}"),
+            (Some(family), None, _) => family.clone(),
+            (None, _, Some(literal)) => literal.clone(),
+            (None, Some(given), None) => given.clone(),
+            (None, None, None) => String::new(),
+        }
+    }
+}
+
+impl CslDate {
+    /// Year of the date, or of the start of the range.
+    pub fn year(&self) -> Option<i32> {
+        self.date_parts.first()?.first().copied()
+    }
+}
+
+/// Normalizes a DOI by stripping any resolver prefix and lowercasing it.
+///
+/// DOIs are case-insensitive, so normalized DOIs can be compared directly.
+pub fn normalize_doi(doi: &str) -> String {
+    let doi = doi.trim();
+    let doi = ["https://doi.org/", "http://doi.org/", "https://dx.doi.org/", "doi:"]
+        .iter()
+        .find_map(|prefix| doi.strip_prefix(prefix))
+        .unwrap_or(doi);
+    doi.to_lowercase()
+}
+
+impl Citation {
+    /// Key identifying the cited work.
+    ///
+    /// The key is the normalized DOI of the work if it has one, so that a work
+    /// cited both by DOI and by an entry is recognized as the same, and otherwise
+    /// the ID of the CSL entry.
+    pub fn key(&self) -> String {
+        match self {
+            Citation::Csl { entry } => {
+                entry.doi.as_deref().map(normalize_doi).unwrap_or_else(|| entry.id.clone())
+            }
+            Citation::Doi { doi } => normalize_doi(doi),
+        }
+    }
+
+    /// Key of the cited work usable in BibTeX and LaTeX `\cite` commands.
+    ///
+    /// This is the [key](Citation::key) with any characters not allowed in
+    /// BibTeX keys removed.
+    pub fn cite_key(&self) -> String {
+        self.key()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || "-_:./".contains(*c))
+            .collect()
+    }
+
+    /// Formats the citation as a reference in Markdown, in author-date style.
+    pub fn to_markdown(&self) -> String {
+        let entry = match self {
+            Citation::Csl { entry } => entry,
+            Citation::Doi { doi } => return format!("<https://doi.org/{}>", normalize_doi(doi)),
+        };
+        let mut parts = Vec::new();
+        if !entry.author.is_empty() {
+            let authors: Vec<_> = entry.author.iter().map(CslName::sorting_form).collect();
+            parts.push(authors.join("; "));
+        }
+        if let Some(year) = entry.issued.as_ref().and_then(CslDate::year) {
+            parts.push(format!("({year})"));
+        }
+        let mut result = parts.join(" ");
+        if !result.is_empty() {
+            result.push('.');
+        }
+        let mut push_sentence = |sentence: String| {
+            if !result.is_empty() {
+                result.push(' ');
+            }
+            result.push_str(&sentence);
+        };
+        if let Some(title) = &entry.title {
+            push_sentence(format!("{title}."));
+        }
+        if let Some(container) = &entry.container_title {
+            let mut source = format!("*{container}*");
+            if let Some(volume) = &entry.volume {
+                write!(source, ", {volume}").unwrap();
+                if let Some(issue) = &entry.issue {
+                    write!(source, "({issue})").unwrap();
+                }
+            }
+            if let Some(page) = &entry.page {
+                write!(source, ", {page}").unwrap();
+            }
+            push_sentence(format!("{source}."));
+        } else if let Some(publisher) = &entry.publisher {
+            push_sentence(format!("{publisher}."));
+        }
+        if let Some(doi) = &entry.doi {
+            push_sentence(format!("<https://doi.org/{}>", normalize_doi(doi)));
+        } else if let Some(url) = &entry.url {
+            push_sentence(format!("<{url}>"));
+        }
+        result
+    }
+
+    /// Formats the citation as a BibTeX entry, for export to LaTeX.
+    pub fn to_bibtex(&self) -> String {
+        let key = self.cite_key();
+        let mut fields = Vec::new();
+        let entry_type = match self {
+            Citation::Csl { entry } => {
+                if let Some(title) = &entry.title {
+                    fields.push(("title", title.clone()));
+                }
+                if !entry.author.is_empty() {
+                    let authors: Vec<_> = entry.author.iter().map(CslName::sorting_form).collect();
+                    fields.push(("author", authors.join(" and ")));
+                }
+                if let Some(year) = entry.issued.as_ref().and_then(CslDate::year) {
+                    fields.push(("year", year.to_string()));
+                }
+                let container_field = match entry.entry_type.as_str() {
+                    "article-journal" | "article" => "journal",
+                    _ => "booktitle",
+                };
+                let optional = [
+                    (container_field, &entry.container_title),
+                    ("volume", &entry.volume),
+                    ("number", &entry.issue),
+                    ("pages", &entry.page),
+                    ("publisher", &entry.publisher),
+                    ("url", &entry.url),
+                ];
+                fields.extend(
+                    optional.into_iter().filter_map(|(name, value)| Some((name, value.clone()?))),
+                );
+                if let Some(doi) = &entry.doi {
+                    fields.push(("doi", normalize_doi(doi)));
+                }
+                match entry.entry_type.as_str() {
+                    "article-journal" | "article" => "article",
+                    "book" => "book",
+                    "chapter" => "incollection",
+                    "paper-conference" => "inproceedings",
+                    "thesis" => "phdthesis",
+                    "report" => "techreport",
+                    _ => "misc",
+                }
+            }
+            Citation::Doi { doi } => {
+                fields.push(("doi", normalize_doi(doi)));
+                "misc"
+            }
+        };
+        let mut result = format!("@{entry_type}{{{key},\n");
+        for (name, value) in fields {
+            writeln!(result, "  {name} = {{{value}}},").unwrap();
+        }
+        result.push_str("}\n");
+        result
+    }
+}
+
+impl<T> Notebook<T> {
+    /// Iterates over the citation cells in the notebook, in order.
+    pub fn citations(&self) -> impl Iterator<Item = (Uuid, &Citation)> {
+        self.cells().filter_map(|cell| match cell {
+            NotebookCell::Citation { id, content } => Some((*id, content)),
+            _ => None,
+        })
+    }
+
+    /// Gets the works cited in the notebook, in order of first citation.
+    ///
+    /// Works cited more than once, as identified by their [key](Citation::key),
+    /// are listed only once.
+    pub fn references(&self) -> Vec<&Citation> {
+        let mut seen = HashSet::new();
+        self.citations()
+            .map(|(_, citation)| citation)
+            .filter(|citation| seen.insert(citation.key()))
+            .collect()
+    }
+
+    /// Formats the works cited in the notebook as a numbered list in Markdown.
+    pub fn bibliography_markdown(&self) -> String {
+        let mut result = String::new();
+        for (i, citation) in self.references().into_iter().enumerate() {
+            writeln!(result, "{}. {}", i + 1, citation.to_markdown()).unwrap();
+        }
+        result
+    }
+
+    /// Formats the works cited in the notebook as a BibTeX database.
+    pub fn bibliography_bibtex(&self) -> String {
+        let entries: Vec<_> = self.references().into_iter().map(Citation::to_bibtex).collect();
+        entries.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn notebook(cells: Vec<NotebookCell<()>>) -> Notebook<()> {
+        Notebook {
+            cell_order: cells.iter().map(|cell| cell.id()).collect(),
+            cell_contents: cells.into_iter().map(|cell| (cell.id(), cell)).collect(),
+        }
+    }
+
+    #[test]
+    fn extract_references() {
+        let entry: CslEntry = serde_json::from_value(json!({
+            "id": "fong2019",
+            "type": "book",
+            "title": "An Invitation to Applied Category Theory",
+            "author": [
+                { "family": "Fong", "given": "Brendan" },
+                { "family": "Spivak", "given": "David I." },
+            ],
+            "issued": { "date-parts": [[2019]] },
+            "publisher": "Cambridge University Press",
+            "DOI": "10.1017/9781108668804",
+            "ISBN": "9781108668804",
+        }))
+        .unwrap();
+        let cells = vec![
+            NotebookCell::Citation {
+                id: Uuid::from_u128(1),
+                content: Citation::Csl { entry },
+            },
+            NotebookCell::Bibliography {
+                id: Uuid::from_u128(2),
+                content: Bibliography::default(),
+            },
+            NotebookCell::Citation {
+                id: Uuid::from_u128(3),
+                content: Citation::Doi {
+                    doi: "https://doi.org/10.1017/9781108668804".into(),
+                },
+            },
+            NotebookCell::Citation {
+                id: Uuid::from_u128(4),
+                content: Citation::Doi { doi: "10.4204/EPTCS.380.8".into() },
+            },
+        ];
+        let notebook = notebook(cells);
+        assert_eq!(notebook.citations().count(), 3);
+        assert_eq!(notebook.references().len(), 2);
+
+        assert_eq!(
+            notebook.bibliography_markdown(),
+            "1. Fong, Brendan; Spivak, David I. (2019). \
+             An Invitation to Applied Category Theory. Cambridge University Press. \
+             <https://doi.org/10.1017/9781108668804>\n\
+             2. <https://doi.org/10.4204/eptcs.380.8>\n"
+        );
+        assert_eq!(
+            notebook.bibliography_bibtex(),
+            "@book{10.1017/9781108668804,\n  \
+             title = {An Invitation to Applied Category Theory},\n  \
+             author = {Fong, Brendan and Spivak, David I.},\n  \
+             year = {2019},\n  \
+             publisher = {Cambridge University Press},\n  \
+             doi = {10.1017/9781108668804},\n}\n\
+             \n\
+             @misc{10.4204/eptcs.380.8,\n  \
+             doi = {10.4204/eptcs.380.8},\n}\n"
+        );
+    }
+
+    #[test]
+    fn deserialize_bibliography_cell() {
+        let cell: NotebookCell<()> = serde_json::from_value(json!({
+            "tag": "bibliography",
+            "id": Uuid::from_u128(1),
+            "content": {},
+        }))
+        .unwrap();
+        let NotebookCell::Bibliography { content, .. } = cell else {
+            panic!("Expected bibliography cell");
+        };
+        assert_eq!(content.heading, "References");
+    }
+}
@@ -4,6 +4,7 @@ pub use crate::v1::DocumentType;
 
 use super::analysis::Analysis;
 use super::api::Link;
+use super::attachment::{AttachmentEntry, AttachmentError};
 use super::notebook::Notebook;
 
 use serde::{Deserialize, Serialize};
@@ -23,6 +24,8 @@ pub struct ModelDocumentContent {
     )]
     pub editor_variant: Option<String>,
     pub notebook: Notebook<super::model_judgment::ModelJudgment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentEntry>,
     pub version: String,
 }
 
@@ -33,6 +36,8 @@ pub struct DiagramDocumentContent {
     #[serde(rename = "diagramIn")]
     pub diagram_in: Link,
     pub notebook: Notebook<super::diagram_judgment::DiagramJudgment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentEntry>,
     pub version: String,
 }
 
@@ -45,6 +50,8 @@ pub struct AnalysisDocumentContent {
     #[serde(rename = "analysisOf")]
     pub analysis_of: Link,
     pub notebook: Notebook<Analysis>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentEntry>,
     pub version: String,
 }
 
@@ -68,6 +75,7 @@ impl Document {
                 theory: old.theory,
                 editor_variant: old.editor_variant,
                 notebook: Notebook::migrate_from_v1(old.notebook),
+                attachments: Vec::new(),
                 version: "2".to_string(),
             }),
 
@@ -75,6 +83,7 @@ impl Document {
                 name: old.name,
                 diagram_in: old.diagram_in,
                 notebook: Notebook::migrate_from_v1(old.notebook),
+                attachments: Vec::new(),
                 version: "2".to_string(),
             }),
 
@@ -83,8 +92,32 @@ impl Document {
                 analysis_type: old.analysis_type,
                 analysis_of: old.analysis_of,
                 notebook: Notebook::migrate_from_v1(old.notebook),
+                attachments: Vec::new(),
                 version: "2".to_string(),
             }),
         }
     }
+    /// Attachments declared in the manifest of the document.
+    pub fn attachments(&self) -> &[AttachmentEntry] {
+        match self {
+            Document::Model(doc) => &doc.attachments,
+            Document::Diagram(doc) => &doc.attachments,
+            Document::Analysis(doc) => &doc.attachments,
+        }
+    }
+
+    /// Checks that the attachments referenced by cells of the document are
+    /// declared in its manifest.
+    pub fn validate_attachments(&self) -> Result<(), Vec<AttachmentError>> {
+        let errors = match self {
+            Document::Model(doc) => doc.notebook.validate_attachments(&doc.attachments),
+            Document::Diagram(doc) => doc.notebook.validate_attachments(&doc.attachments),
+            Document::Analysis(doc) => doc.notebook.validate_attachments(&doc.attachments),
+        };
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::cell::NotebookCell;
+use super::notebook::Notebook;
+use super::patch::{DocumentPatch, PatchError};
+
+/// Maximum number of groups kept on the undo stack.
+const MAX_UNDO_GROUPS: usize = 200;
+
+/// A group of patches that are undone and redone together.
+///
+/// Stores the patches that revert the edit, in the order that they must be
+/// applied.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct EditGroup<T> {
+    pub patches: Vec<DocumentPatch<T>>,
+}
+
+/// Undo/redo history of a notebook.
+///
+/// The history is serializable, so it can be stored alongside the document,
+/// or separately, and survive reloads. Consecutive edits to the same rich text
+/// cell are compacted into a single group, so that undo reverts a whole run of
+/// typing rather than a single keystroke.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct EditHistory<T> {
+    pub undo: Vec<EditGroup<T>>,
+    pub redo: Vec<EditGroup<T>>,
+}
+
+impl<T> Default for EditHistory<T> {
+    fn default() -> Self {
+        Self { undo: Vec::new(), redo: Vec::new() }
+    }
+}
+
+impl<T> EditHistory<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Apply a group of patches to a notebook as a single edit.
+    ///
+    /// If any patch fails, the patches already applied are reverted and the
+    /// history is unchanged. Otherwise the edit is recorded and the redo stack
+    /// is cleared.
+    pub fn apply(
+        &mut self,
+        notebook: &mut Notebook<T>,
+        patches: impl IntoIterator<Item = DocumentPatch<T>>,
+    ) -> Result<(), PatchError> {
+        let inverse = apply_group(notebook, patches)?;
+        if inverse.patches.is_empty() {
+            return Ok(());
+        }
+        self.redo.clear();
+        self.record(inverse);
+        Ok(())
+    }
+
+    /// Undo the last edit, returning whether there was one to undo.
+    ///
+    /// If the edit cannot be undone, say because a collaborator has since
+    /// deleted the cell, the notebook is unchanged and the edit is dropped
+    /// from the history.
+    pub fn undo(&mut self, notebook: &mut Notebook<T>) -> Result<bool, PatchError> {
+        let Some(group) = self.undo.pop() else {
+            return Ok(false);
+        };
+        let inverse = apply_group(notebook, group.patches)?;
+        self.redo.push(inverse);
+        Ok(true)
+    }
+
+    /// Redo the last undone edit, returning whether there was one to redo.
+    ///
+    /// Failures are handled as in [`undo`](Self::undo).
+    pub fn redo(&mut self, notebook: &mut Notebook<T>) -> Result<bool, PatchError> {
+        let Some(group) = self.redo.pop() else {
+            return Ok(false);
+        };
+        let inverse = apply_group(notebook, group.patches)?;
+        self.undo.push(inverse);
+        Ok(true)
+    }
+
+    /// Push a group onto the undo stack, compacting it with the previous one
+    /// when possible.
+    fn record(&mut self, group: EditGroup<T>) {
+        if let Some(last) = self.undo.last()
+            && is_text_edit_of_same_cell(last, &group)
+        {
+            // The previous group already restores the text from before the
+            // whole run of edits.
+            return;
+        }
+        self.undo.push(group);
+        if self.undo.len() > MAX_UNDO_GROUPS {
+            self.undo.remove(0);
+        }
+    }
+}
+
+/// Apply patches in order, returning the group that reverts them.
+///
+/// On failure, the patches already applied are reverted.
+fn apply_group<T>(
+    notebook: &mut Notebook<T>,
+    patches: impl IntoIterator<Item = DocumentPatch<T>>,
+) -> Result<EditGroup<T>, PatchError> {
+    let mut inverses = Vec::new();
+    for patch in patches {
+        match patch.apply(notebook) {
+            Ok(inverse) => inverses.push(inverse),
+            Err(err) => {
+                for inverse in inverses.into_iter().rev() {
+                    inverse.apply(notebook).expect("Inverse patch should apply");
+                }
+                return Err(err);
+            }
+        }
+    }
+    inverses.reverse();
+    Ok(EditGroup { patches: inverses })
+}
+
+/// Whether both groups revert a single update of the same rich text cell.
+fn is_text_edit_of_same_cell<T>(prev: &EditGroup<T>, next: &EditGroup<T>) -> bool {
+    match (prev.patches.as_slice(), next.patches.as_slice()) {
+        (
+            [
+                DocumentPatch::UpdateCell {
+                    cell: NotebookCell::RichText { id: x, .. },
+                },
+            ],
+            [
+                DocumentPatch::UpdateCell {
+                    cell: NotebookCell::RichText { id: y, .. },
+                },
+            ],
+        ) => x == y,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn text(id: Uuid, content: &str) -> NotebookCell<()> {
+        NotebookCell::RichText { id, content: content.into() }
+    }
+
+    fn content(notebook: &Notebook<()>, id: Uuid) -> &str {
+        match &notebook.cell_contents[&id] {
+            NotebookCell::RichText { content, .. } => content,
+            _ => panic!("Expected rich text cell"),
+        }
+    }
+
+    #[test]
+    fn undo_redo_with_compaction() {
+        let (a, b) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let mut notebook = Notebook {
+            cell_contents: HashMap::from([(a, text(a, ""))]),
+            cell_order: vec![a],
+        };
+        let mut history = EditHistory::new();
+
+        for content in ["h", "hi", "hi!"] {
+            let patch = DocumentPatch::UpdateCell { cell: text(a, content) };
+            history.apply(&mut notebook, [patch]).unwrap();
+        }
+        let insert = DocumentPatch::InsertCell { index: 1, cell: text(b, "b") };
+        history.apply(&mut notebook, [insert]).unwrap();
+        assert_eq!(history.undo.len(), 2);
+
+        assert_eq!(history.undo(&mut notebook), Ok(true));
+        assert_eq!(notebook.cell_order, vec![a]);
+        assert_eq!(history.undo(&mut notebook), Ok(true));
+        assert_eq!(content(&notebook, a), "");
+        assert_eq!(history.undo(&mut notebook), Ok(false));
+
+        assert_eq!(history.redo(&mut notebook), Ok(true));
+        assert_eq!(content(&notebook, a), "hi!");
+        assert!(history.can_redo());
+
+        // A failed edit is rolled back and not recorded.
+        let patches = [
+            DocumentPatch::UpdateCell { cell: text(a, "bye") },
+            DocumentPatch::DeleteCell { id: b },
+        ];
+        assert_eq!(history.apply(&mut notebook, patches), Err(PatchError::UnknownCell(b)));
+        assert_eq!(content(&notebook, a), "hi!");
+        assert!(history.can_redo());
+    }
+}
@@ -0,0 +1,602 @@
+//! Export of documents as LaTeX.
+//!
+//! A document is exported as a standalone LaTeX article that compiles with
+//! `pdflatex`. Rich text cells, which hold a small subset of Markdown, are
+//! converted to LaTeX paragraphs, headings, and lists. The declarations in
+//! formal cells of models and diagrams are typeset as displayed equations, and
+//! the generating graph of the model or diagram is drawn as a TikZ figure.
+//! Figure cells include their attachments by file name, so the attachments
+//! should be downloaded alongside the exported file. Citations are typeset as
+//! `\cite` commands with a bibliography listing the cited works.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::attachment::{AttachmentEntry, Figure, FigureSize};
+use super::cell::NotebookCell;
+use super::citation::{Bibliography, Citation};
+use super::diagram_judgment::DiagramJudgment;
+use super::document::Document;
+use super::model::{Mor, Ob};
+use super::model_judgment::ModelJudgment;
+use super::notebook::Notebook;
+use super::path::Path;
+use super::theory::{MorType, ObOp, ObType};
+
+/// Radius of the circle on which objects are placed in the TikZ figure.
+const GRAPH_RADIUS_CM: f64 = 3.0;
+
+/// Exports a document as a compilable LaTeX article.
+pub fn export_latex(document: &Document) -> String {
+    let mut body = String::new();
+    let (name, references, has_bibliography) = match document {
+        Document::Model(doc) => {
+            let names: Names = doc
+                .notebook
+                .formal_content()
+                .filter_map(|j| match j {
+                    ModelJudgment::Object(decl) => Some((decl.id.to_string(), decl.name.as_str())),
+                    ModelJudgment::Morphism(decl) => {
+                        Some((decl.id.to_string(), decl.name.as_str()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            write_notebook(&mut body, &doc.notebook, &doc.attachments, |out, judgment| {
+                model_judgment_latex(out, judgment, &names)
+            });
+            let graph = doc.notebook.formal_content().filter_map(|j| match j {
+                ModelJudgment::Object(decl) => Some(GraphElement::Node {
+                    id: decl.id.to_string(),
+                    name: &decl.name,
+                }),
+                ModelJudgment::Morphism(decl) => Some(GraphElement::Edge {
+                    name: &decl.name,
+                    dom: decl.dom.as_ref(),
+                    cod: decl.cod.as_ref(),
+                }),
+                _ => None,
+            });
+            write_graph(&mut body, graph);
+            (&doc.name, doc.notebook.references(), has_bibliography(&doc.notebook))
+        }
+        Document::Diagram(doc) => {
+            let names: Names = doc
+                .notebook
+                .formal_content()
+                .filter_map(|j| match j {
+                    DiagramJudgment::Object(decl) => {
+                        Some((decl.id.to_string(), decl.name.as_str()))
+                    }
+                    DiagramJudgment::Morphism(decl) => {
+                        Some((decl.id.to_string(), decl.name.as_str()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            write_notebook(&mut body, &doc.notebook, &doc.attachments, |out, judgment| {
+                diagram_judgment_latex(out, judgment, &names)
+            });
+            let graph = doc.notebook.formal_content().filter_map(|j| match j {
+                DiagramJudgment::Object(decl) => Some(GraphElement::Node {
+                    id: decl.id.to_string(),
+                    name: &decl.name,
+                }),
+                DiagramJudgment::Morphism(decl) => Some(GraphElement::Edge {
+                    name: &decl.name,
+                    dom: decl.dom.as_ref(),
+                    cod: decl.cod.as_ref(),
+                }),
+                _ => None,
+            });
+            write_graph(&mut body, graph);
+            (&doc.name, doc.notebook.references(), has_bibliography(&doc.notebook))
+        }
+        Document::Analysis(doc) => {
+            write_notebook(&mut body, &doc.notebook, &doc.attachments, |out, _| {
+                out.push_str("% Analysis omitted from export.\n");
+            });
+            (&doc.name, doc.notebook.references(), has_bibliography(&doc.notebook))
+        }
+    };
+
+    // Cited works must be listed somewhere for the citations to resolve.
+    if !(has_bibliography || references.is_empty()) {
+        write_bibliography(&mut body, &Bibliography::default(), &references);
+    }
+
+    let mut latex = String::new();
+    latex.push_str("\\documentclass{article}\n");
+    let packages = [
+        "[utf8]{inputenc}",
+        "[T1]{fontenc}",
+        "{amsmath}",
+        "{graphicx}",
+        "{tikz}",
+        "{hyperref}",
+    ];
+    for package in packages {
+        writeln!(latex, "\\usepackage{package}").unwrap();
+    }
+    writeln!(latex, "\\title{{{}}}", escape(name)).unwrap();
+    latex.push_str("\\author{}\n\\date{}\n\n\\begin{document}\n\\maketitle\n\n");
+    latex.push_str(&body);
+    latex.push_str("\\end{document}\n");
+    latex
+}
+
+/// Names of declared objects and morphisms, by ID.
+type Names<'a> = HashMap<String, &'a str>;
+
+fn has_bibliography<T>(notebook: &Notebook<T>) -> bool {
+    notebook.cells().any(|cell| matches!(cell, NotebookCell::Bibliography { .. }))
+}
+
+/// Writes the cells of a notebook, using the given function for formal cells.
+fn write_notebook<T>(
+    out: &mut String,
+    notebook: &Notebook<T>,
+    attachments: &[AttachmentEntry],
+    mut write_formal: impl FnMut(&mut String, &T),
+) {
+    let references = notebook.references();
+    for cell in notebook.cells() {
+        match cell {
+            NotebookCell::RichText { content, .. } => rich_text_latex(out, content),
+            NotebookCell::Formal { content, .. } => write_formal(out, content),
+            NotebookCell::Figure { content, .. } => figure_latex(out, content, attachments),
+            NotebookCell::Citation { content, .. } => {
+                writeln!(out, "\\cite{{{}}}", content.cite_key()).unwrap();
+            }
+            NotebookCell::Bibliography { content, .. } => {
+                write_bibliography(out, content, &references);
+            }
+        }
+        out.push('\n');
+    }
+}
+
+fn write_bibliography(out: &mut String, bibliography: &Bibliography, references: &[&Citation]) {
+    writeln!(out, "\\renewcommand{{\\refname}}{{{}}}", escape(&bibliography.heading)).unwrap();
+    writeln!(out, "\\begin{{thebibliography}}{{{}}}", references.len()).unwrap();
+    for citation in references {
+        let text = inline_latex(&citation.to_markdown());
+        writeln!(out, "\\bibitem{{{}}} {}", citation.cite_key(), text).unwrap();
+    }
+    out.push_str("\\end{thebibliography}\n");
+}
+
+fn figure_latex(out: &mut String, figure: &Figure, attachments: &[AttachmentEntry]) {
+    let Some(entry) = attachments.iter().find(|entry| entry.id == figure.attachment_id) else {
+        writeln!(out, "% Figure with undeclared attachment {}", figure.attachment_id).unwrap();
+        return;
+    };
+    out.push_str("\\begin{figure}[h]\n\\centering\n");
+    if entry.content_type.starts_with("image/") {
+        let width = match figure.size {
+            FigureSize::Auto => "\\linewidth".to_string(),
+            FigureSize::Relative { percent } => {
+                format!("{:.2}\\linewidth", f64::from(percent) / 100.0)
+            }
+            // Pixels are taken at the CSS resolution of 96 per inch.
+            FigureSize::Fixed { width } => format!("{}bp", f64::from(width) * 0.75),
+        };
+        writeln!(out, "\\includegraphics[width={width}]{{{}}}", entry.filename).unwrap();
+    } else {
+        writeln!(out, "\\texttt{{{}}}", escape(&entry.filename)).unwrap();
+    }
+    if let Some(alt_text) = &figure.alt_text {
+        writeln!(out, "% {}", alt_text.replace('\n', " ")).unwrap();
+    }
+    if !figure.caption.is_empty() {
+        writeln!(out, "\\caption{{{}}}", inline_latex(&figure.caption)).unwrap();
+    }
+    out.push_str("\\end{figure}\n");
+}
+
+fn model_judgment_latex(out: &mut String, judgment: &ModelJudgment, names: &Names) {
+    match judgment {
+        ModelJudgment::Object(decl) => {
+            let ob_type = ob_type_latex(&decl.ob_type);
+            writeln!(out, "\\[ {} : {} \\]", name_latex(&decl.name), ob_type).unwrap();
+        }
+        ModelJudgment::Morphism(decl) => {
+            mor_decl_latex(out, &decl.name, &decl.mor_type, &decl.dom, &decl.cod, names)
+        }
+        ModelJudgment::Equation(decl) => {
+            equation_latex(out, &decl.lhs, &decl.rhs, names);
+        }
+        ModelJudgment::Instantiation(inst) => {
+            writeln!(out, "Instance of another model: \\emph{{{}}}.", escape(&inst.name)).unwrap();
+        }
+    }
+}
+
+fn diagram_judgment_latex(out: &mut String, judgment: &DiagramJudgment, names: &Names) {
+    match judgment {
+        DiagramJudgment::Object(decl) => {
+            let ob_type = ob_type_latex(&decl.ob_type);
+            writeln!(out, "\\[ {} : {} \\]", name_latex(&decl.name), ob_type).unwrap();
+        }
+        DiagramJudgment::Morphism(decl) => {
+            mor_decl_latex(out, &decl.name, &decl.mor_type, &decl.dom, &decl.cod, names)
+        }
+        DiagramJudgment::Equation(decl) => {
+            equation_latex(out, &decl.lhs, &decl.rhs, names);
+        }
+    }
+}
+
+fn mor_decl_latex(
+    out: &mut String,
+    name: &str,
+    mor_type: &MorType,
+    dom: &Option<Ob>,
+    cod: &Option<Ob>,
+    names: &Names,
+) {
+    let dom = dom.as_ref().map_or_else(|| "?".into(), |ob| ob_latex(ob, names));
+    let cod = cod.as_ref().map_or_else(|| "?".into(), |ob| ob_latex(ob, names));
+    write!(out, "\\[ {} \\colon {dom} \\to {cod}", name_latex(name)).unwrap();
+    if !matches!(mor_type, MorType::Hom(_)) {
+        write!(out, " \\quad ({})", mor_type_latex(mor_type)).unwrap();
+    }
+    out.push_str(" \\]\n");
+}
+
+fn equation_latex(out: &mut String, lhs: &Option<Mor>, rhs: &Option<Mor>, names: &Names) {
+    let lhs = lhs.as_ref().map_or_else(|| "?".into(), |mor| mor_latex(mor, names));
+    let rhs = rhs.as_ref().map_or_else(|| "?".into(), |mor| mor_latex(mor, names));
+    writeln!(out, "\\[ {lhs} = {rhs} \\]").unwrap();
+}
+
+fn name_latex(name: &str) -> String {
+    if name.is_empty() {
+        "?".into()
+    } else {
+        format!("\\text{{{}}}", escape(name))
+    }
+}
+
+fn ob_latex(ob: &Ob, names: &Names) -> String {
+    match ob {
+        Ob::Basic(id) => names.get(id).map_or_else(|| "?".into(), |name| name_latex(name)),
+        Ob::App { op, ob } => {
+            let ObOp::Basic(op) = op;
+            format!("\\mathsf{{{}}}({})", escape(op), ob_latex(ob, names))
+        }
+        Ob::List { objects, .. } => {
+            let objects: Vec<_> = objects
+                .iter()
+                .map(|ob| ob.as_ref().map_or_else(|| "?".into(), |ob| ob_latex(ob, names)))
+                .collect();
+            format!("[{}]", objects.join(", "))
+        }
+        Ob::Tabulated(mor) => mor_latex(mor, names),
+    }
+}
+
+fn mor_latex(mor: &Mor, names: &Names) -> String {
+    match mor {
+        Mor::Basic(id) => names.get(id).map_or_else(|| "?".into(), |name| name_latex(name)),
+        Mor::Composite(path) => match path.as_ref() {
+            Path::Id(ob) => format!("1_{{{}}}", ob_latex(ob, names)),
+            Path::Seq(mors) => {
+                let mors: Vec<_> = mors.iter().map(|mor| mor_latex(mor, names)).collect();
+                mors.join(" \\cdot ")
+            }
+        },
+        Mor::TabulatorSquare { dom, cod, .. } => {
+            format!("({} \\Rightarrow {})", mor_latex(dom, names), mor_latex(cod, names))
+        }
+    }
+}
+
+fn ob_type_latex(ob_type: &ObType) -> String {
+    match ob_type {
+        ObType::Basic(name) => format!("\\mathsf{{{}}}", escape(name)),
+        ObType::Tabulator(mor_type) => format!("\\top({})", mor_type_latex(mor_type)),
+        ObType::ModeApp { modality, ob_type } => {
+            format!("\\mathsf{{{modality:?}}}({})", ob_type_latex(ob_type))
+        }
+    }
+}
+
+fn mor_type_latex(mor_type: &MorType) -> String {
+    match mor_type {
+        MorType::Basic(name) => format!("\\mathsf{{{}}}", escape(name)),
+        MorType::Hom(ob_type) => format!("\\mathrm{{Hom}}({})", ob_type_latex(ob_type)),
+        MorType::Composite(types) => {
+            let types: Vec<_> = types.iter().map(mor_type_latex).collect();
+            types.join(" \\cdot ")
+        }
+        MorType::ModeApp { modality, mor_type } => {
+            format!("\\mathsf{{{modality:?}}}({})", mor_type_latex(mor_type))
+        }
+    }
+}
+
+/// An element of the generating graph of a model or diagram.
+enum GraphElement<'a> {
+    Node {
+        id: String,
+        name: &'a str,
+    },
+    Edge {
+        name: &'a str,
+        dom: Option<&'a Ob>,
+        cod: Option<&'a Ob>,
+    },
+}
+
+/// Draws the generating graph as a TikZ figure, with objects on a circle.
+///
+/// Only morphisms between basic objects are drawn.
+fn write_graph<'a>(out: &mut String, elements: impl Iterator<Item = GraphElement<'a>>) {
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for element in elements {
+        match element {
+            GraphElement::Node { id, name } => nodes.push((id, name)),
+            GraphElement::Edge {
+                name,
+                dom: Some(Ob::Basic(dom)),
+                cod: Some(Ob::Basic(cod)),
+            } => edges.push((name, dom, cod)),
+            GraphElement::Edge { .. } => {}
+        }
+    }
+    if nodes.is_empty() {
+        return;
+    }
+    let index: HashMap<&str, usize> =
+        nodes.iter().enumerate().map(|(i, (id, _))| (id.as_str(), i)).collect();
+
+    out.push_str("\\begin{figure}[h]\n\\centering\n");
+    out.push_str("\\begin{tikzpicture}[>=stealth, ob/.style={draw, rounded corners}]\n");
+    for (i, (_, name)) in nodes.iter().enumerate() {
+        let angle = 90.0 + 360.0 * i as f64 / nodes.len() as f64;
+        writeln!(
+            out,
+            "\\node[ob] (n{i}) at ({angle:.1}:{GRAPH_RADIUS_CM}cm) {{{}}};",
+            escape(name)
+        )
+        .unwrap();
+    }
+    for (name, dom, cod) in edges {
+        let (Some(dom), Some(cod)) = (index.get(dom.as_str()), index.get(cod.as_str())) else {
+            continue;
+        };
+        let path = if dom == cod {
+            "to[loop above]"
+        } else {
+            "to[bend left=10]"
+        };
+        writeln!(
+            out,
+            "\\draw[->] (n{dom}) {path} node[midway, fill=white] {{{}}} (n{cod});",
+            escape(name)
+        )
+        .unwrap();
+    }
+    out.push_str("\\end{tikzpicture}\n\\end{figure}\n\n");
+}
+
+/// Converts rich text, in a subset of Markdown, to LaTeX.
+///
+/// Supported are paragraphs, headings, bulleted lists, and the inline
+/// formatting handled by [`inline_latex`].
+fn rich_text_latex(out: &mut String, text: &str) {
+    fn flush(out: &mut String, paragraph: &mut Vec<&str>) {
+        if !paragraph.is_empty() {
+            writeln!(out, "{}\n", inline_latex(&paragraph.join(" "))).unwrap();
+            paragraph.clear();
+        }
+    }
+
+    let mut in_list = false;
+    let mut paragraph = Vec::new();
+    for line in text.lines().map(str::trim) {
+        let item = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "));
+        if item.is_none() && in_list {
+            out.push_str("\\end{itemize}\n\n");
+            in_list = false;
+        }
+        if let Some(item) = item {
+            flush(out, &mut paragraph);
+            if !in_list {
+                out.push_str("\\begin{itemize}\n");
+                in_list = true;
+            }
+            writeln!(out, "\\item {}", inline_latex(item)).unwrap();
+        } else if line.is_empty() {
+            flush(out, &mut paragraph);
+        } else if let Some((level, heading)) = heading(line) {
+            flush(out, &mut paragraph);
+            let command = ["section", "subsection", "subsubsection"][level.min(3) - 1];
+            writeln!(out, "\\{command}*{{{}}}\n", inline_latex(heading)).unwrap();
+        } else {
+            paragraph.push(line);
+        }
+    }
+    flush(out, &mut paragraph);
+    if in_list {
+        out.push_str("\\end{itemize}\n\n");
+    }
+}
+
+/// Parses a Markdown heading into its level and text.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (level > 0).then_some((level, text.trim()))
+}
+
+/// Converts inline Markdown to LaTeX.
+///
+/// Handles bold and italic text, code, links, and math between dollar signs,
+/// which is passed through. All other text is escaped.
+fn inline_latex(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some((inner, tail)) = delimited(rest, "**", "**") {
+            write!(out, "\\textbf{{{}}}", inline_latex(inner)).unwrap();
+            rest = tail;
+        } else if let Some((inner, tail)) = delimited(rest, "*", "*") {
+            write!(out, "\\emph{{{}}}", inline_latex(inner)).unwrap();
+            rest = tail;
+        } else if let Some((inner, tail)) = delimited(rest, "`", "`") {
+            write!(out, "\\texttt{{{}}}", escape(inner)).unwrap();
+            rest = tail;
+        } else if let Some((inner, tail)) = delimited(rest, "$", "$") {
+            write!(out, "${inner}$").unwrap();
+            rest = tail;
+        } else if let Some((url, tail)) = delimited(rest, "<", ">").filter(|(url, _)| is_url(url)) {
+            write!(out, "\\url{{{url}}}").unwrap();
+            rest = tail;
+        } else if let Some((label, url, tail)) = link(rest) {
+            write!(out, "\\href{{{url}}}{{{}}}", inline_latex(label)).unwrap();
+            rest = tail;
+        } else if is_url(rest) {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            // Trailing punctuation most likely ends the sentence, not the URL.
+            let url = rest[..end].trim_end_matches(['.', ',', ';', ':', ')']);
+            write!(out, "\\url{{{url}}}").unwrap();
+            rest = &rest[url.len()..];
+        } else {
+            out.push_str(&escape(c.encode_utf8(&mut [0; 4])));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+fn delimited<'a>(text: &'a str, open: &str, close: &str) -> Option<(&'a str, &'a str)> {
+    let body = text.strip_prefix(open)?;
+    let end = body.find(close)?;
+    (end > 0).then(|| (&body[..end], &body[end + close.len()..]))
+}
+
+/// Parses a Markdown link into its label, URL, and the remaining text.
+fn link(text: &str) -> Option<(&str, &str, &str)> {
+    let (label, rest) = delimited(text, "[", "](")?;
+    let end = rest.find(')')?;
+    Some((label, &rest[..end], &rest[end + 1..]))
+}
+
+fn is_url(text: &str) -> bool {
+    text.starts_with("https://") || text.starts_with("http://")
+}
+
+/// Escapes text for use in LaTeX outside of math mode.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '{' | '}' | '$' | '&' | '#' | '%' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn inline_markdown() {
+        assert_eq!(
+            inline_latex("**Bold** and *italic* with $x_1$ for 100% at <https://a.b/c_d>"),
+            "\\textbf{Bold} and \\emph{italic} with $x_1$ for 100\\% at \\url{https://a.b/c_d}"
+        );
+        assert_eq!(
+            inline_latex("See [the docs](https://catcolab.org/help), or https://catcolab.org."),
+            "See \\href{https://catcolab.org/help}{the docs}, or \\url{https://catcolab.org}."
+        );
+    }
+
+    #[test]
+    fn export_model() {
+        let (x, y, f) = (
+            "0194d7b9-0000-7000-8000-000000000001",
+            "0194d7b9-0000-7000-8000-000000000002",
+            "0194d7b9-0000-7000-8000-000000000003",
+        );
+        let ob_type = json!({ "tag": "Basic", "content": "Type" });
+        let document: Document = serde_json::from_value(json!({
+            "type": "model",
+            "name": "Graph & friends",
+            "theory": "simple-olog",
+            "version": "2",
+            "notebook": {
+                "cellOrder": ["00000000-0000-0000-0000-000000000001",
+                              "00000000-0000-0000-0000-000000000002",
+                              "00000000-0000-0000-0000-000000000003",
+                              "00000000-0000-0000-0000-000000000004",
+                              "00000000-0000-0000-0000-000000000005"],
+                "cellContents": {
+                    "00000000-0000-0000-0000-000000000001": {
+                        "tag": "rich-text",
+                        "id": "00000000-0000-0000-0000-000000000001",
+                        "content": "# Intro\nA *small* model.\n\n- one\n- two",
+                    },
+                    "00000000-0000-0000-0000-000000000002": {
+                        "tag": "formal",
+                        "id": "00000000-0000-0000-0000-000000000002",
+                        "content": { "tag": "object", "name": "x", "id": x,
+                                     "obType": ob_type },
+                    },
+                    "00000000-0000-0000-0000-000000000003": {
+                        "tag": "formal",
+                        "id": "00000000-0000-0000-0000-000000000003",
+                        "content": { "tag": "object", "name": "y", "id": y,
+                                     "obType": ob_type },
+                    },
+                    "00000000-0000-0000-0000-000000000004": {
+                        "tag": "formal",
+                        "id": "00000000-0000-0000-0000-000000000004",
+                        "content": { "tag": "morphism", "name": "f", "id": f,
+                                     "morType": { "tag": "Hom", "content": ob_type },
+                                     "dom": { "tag": "Basic", "content": x },
+                                     "cod": { "tag": "Basic", "content": y } },
+                    },
+                    "00000000-0000-0000-0000-000000000005": {
+                        "tag": "citation",
+                        "id": "00000000-0000-0000-0000-000000000005",
+                        "content": { "tag": "doi", "doi": "10.4204/EPTCS.380.8" },
+                    },
+                },
+            },
+        }))
+        .unwrap();
+
+        let latex = export_latex(&document);
+        assert!(latex.starts_with("\\documentclass{article}\n"));
+        assert!(latex.contains("\\title{Graph \\& friends}"));
+        assert!(latex.contains(
+            "\\section*{Intro}\n\nA \\emph{small} model.\n\n\\begin{itemize}\n\\item one\n"
+        ));
+        assert!(latex.contains("\\[ \\text{x} : \\mathsf{Type} \\]"));
+        assert!(latex.contains("\\[ \\text{f} \\colon \\text{x} \\to \\text{y} \\]"));
+        assert!(
+            latex.contains("\\draw[->] (n0) to[bend left=10] node[midway, fill=white] {f} (n1);")
+        );
+        assert!(latex.contains("\\cite{10.4204/eptcs.380.8}"));
+        assert!(latex.contains("\\bibitem{10.4204/eptcs.380.8}"));
+        assert!(latex.ends_with("\\end{document}\n"));
+        for env in ["document", "figure", "tikzpicture", "itemize", "thebibliography"] {
+            let begins = latex.matches(&format!("\\begin{{{env}}}")).count();
+            assert_eq!(begins, 1);
+            assert_eq!(begins, latex.matches(&format!("\\end{{{env}}}")).count());
+        }
+    }
+}
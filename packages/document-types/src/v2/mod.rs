@@ -2,18 +2,28 @@ use crate::v1;
 
 pub use v1::{analysis, api, diagram_judgment, model, model_judgment, path, theory};
 
+pub mod attachment;
 pub mod cell;
+pub mod citation;
 pub mod document;
+pub mod history;
+pub mod latex;
 pub mod notebook;
+pub mod patch;
 
 pub use analysis::*;
 pub use api::*;
+pub use attachment::*;
 pub use cell::*;
+pub use citation::*;
 pub use diagram_judgment::*;
 pub use document::*;
+pub use history::*;
+pub use latex::*;
 pub use model::*;
 pub use model_judgment::*;
 pub use notebook::*;
+pub use patch::*;
 pub use theory::*;
 
 #[cfg(test)]
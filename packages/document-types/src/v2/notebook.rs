@@ -56,6 +56,15 @@ pub(crate) mod arbitrary {
                         NotebookCell::Formal { content, .. } => {
                             NotebookCell::Formal { id, content }
                         }
+                        NotebookCell::Figure { content, .. } => {
+                            NotebookCell::Figure { id, content }
+                        }
+                        NotebookCell::Citation { content, .. } => {
+                            NotebookCell::Citation { id, content }
+                        }
+                        NotebookCell::Bibliography { content, .. } => {
+                            NotebookCell::Bibliography { id, content }
+                        }
                     };
                     cell_contents.insert(id, cell);
                     cell_order.push(id);
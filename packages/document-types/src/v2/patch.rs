@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tsify::Tsify;
+use uuid::Uuid;
+
+use super::cell::NotebookCell;
+use super::notebook::Notebook;
+
+/// A cell-level mutation of a notebook.
+///
+/// Patches are invertible: applying a patch returns the patch that undoes it.
+/// Cells are referred to by ID rather than position, except where a position
+/// is needed, so that patches remain meaningful when other cells change.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Tsify)]
+#[serde(tag = "tag")]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum DocumentPatch<T> {
+    /// Insert a new cell at the given position.
+    #[serde(rename = "insert-cell")]
+    InsertCell { index: usize, cell: NotebookCell<T> },
+    /// Delete the cell with the given ID.
+    #[serde(rename = "delete-cell")]
+    DeleteCell { id: Uuid },
+    /// Move the cell with the given ID to the given position.
+    #[serde(rename = "move-cell")]
+    MoveCell { id: Uuid, index: usize },
+    /// Replace the payload of the cell having the same ID.
+    #[serde(rename = "update-cell")]
+    UpdateCell { cell: NotebookCell<T> },
+}
+
+/// A patch that cannot be applied to a notebook.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PatchError {
+    /// No cell with the given ID exists.
+    UnknownCell(Uuid),
+    /// A cell with the given ID already exists.
+    DuplicateCell(Uuid),
+    /// The position is past the end of the notebook.
+    IndexOutOfBounds(usize),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::UnknownCell(id) => write!(f, "no cell with ID {id}"),
+            PatchError::DuplicateCell(id) => write!(f, "cell with ID {id} already exists"),
+            PatchError::IndexOutOfBounds(index) => write!(f, "cell index {index} out of bounds"),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl<T> DocumentPatch<T> {
+    /// ID of the cell affected by the patch.
+    pub fn cell_id(&self) -> Uuid {
+        match self {
+            DocumentPatch::InsertCell { cell, .. } | DocumentPatch::UpdateCell { cell } => {
+                cell.id()
+            }
+            DocumentPatch::DeleteCell { id } | DocumentPatch::MoveCell { id, .. } => *id,
+        }
+    }
+
+    /// Apply the patch to a notebook, returning the inverse patch.
+    ///
+    /// The notebook is left unchanged if the patch cannot be applied.
+    pub fn apply(self, notebook: &mut Notebook<T>) -> Result<Self, PatchError> {
+        match self {
+            DocumentPatch::InsertCell { index, cell } => {
+                let id = cell.id();
+                if notebook.cell_contents.contains_key(&id) {
+                    return Err(PatchError::DuplicateCell(id));
+                }
+                if index > notebook.cell_order.len() {
+                    return Err(PatchError::IndexOutOfBounds(index));
+                }
+                notebook.cell_order.insert(index, id);
+                notebook.cell_contents.insert(id, cell);
+                Ok(DocumentPatch::DeleteCell { id })
+            }
+            DocumentPatch::DeleteCell { id } => {
+                let index = notebook.cell_index(id)?;
+                let cell = notebook.cell_contents.remove(&id).ok_or(PatchError::UnknownCell(id))?;
+                notebook.cell_order.remove(index);
+                Ok(DocumentPatch::InsertCell { index, cell })
+            }
+            DocumentPatch::MoveCell { id, index } => {
+                let old_index = notebook.cell_index(id)?;
+                if index >= notebook.cell_order.len() {
+                    return Err(PatchError::IndexOutOfBounds(index));
+                }
+                let id = notebook.cell_order.remove(old_index);
+                notebook.cell_order.insert(index, id);
+                Ok(DocumentPatch::MoveCell { id, index: old_index })
+            }
+            DocumentPatch::UpdateCell { cell } => {
+                let id = cell.id();
+                let old = notebook.cell_contents.get_mut(&id).ok_or(PatchError::UnknownCell(id))?;
+                let cell = std::mem::replace(old, cell);
+                Ok(DocumentPatch::UpdateCell { cell })
+            }
+        }
+    }
+}
+
+impl<T> Notebook<T> {
+    /// Position of the cell with the given ID in the cell order.
+    fn cell_index(&self, id: Uuid) -> Result<usize, PatchError> {
+        self.cell_order
+            .iter()
+            .position(|other| *other == id)
+            .ok_or(PatchError::UnknownCell(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn text(id: Uuid, content: &str) -> NotebookCell<()> {
+        NotebookCell::RichText { id, content: content.into() }
+    }
+
+    #[test]
+    fn apply_and_invert() {
+        let (a, b) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let mut notebook = Notebook {
+            cell_contents: HashMap::from([(a, text(a, "a"))]),
+            cell_order: vec![a],
+        };
+
+        let undo_insert = DocumentPatch::InsertCell { index: 0, cell: text(b, "b") }
+            .apply(&mut notebook)
+            .unwrap();
+        assert_eq!(notebook.cell_order, vec![b, a]);
+
+        let undo_move = DocumentPatch::MoveCell { id: b, index: 1 }.apply(&mut notebook).unwrap();
+        assert_eq!(notebook.cell_order, vec![a, b]);
+
+        let undo_update =
+            DocumentPatch::UpdateCell { cell: text(a, "A") }.apply(&mut notebook).unwrap();
+        assert_eq!(notebook.cell_contents[&a], text(a, "A"));
+        assert_eq!(undo_update, DocumentPatch::UpdateCell { cell: text(a, "a") });
+
+        for undo in [undo_update, undo_move, undo_insert] {
+            undo.apply(&mut notebook).unwrap();
+        }
+        assert_eq!(notebook.cell_order, vec![a]);
+        assert_eq!(notebook.cell_contents, HashMap::from([(a, text(a, "a"))]));
+
+        let result =
+            DocumentPatch::InsertCell { index: 0, cell: text(a, "a") }.apply(&mut notebook);
+        assert_eq!(result, Err(PatchError::DuplicateCell(a)));
+        let result = DocumentPatch::<()>::DeleteCell { id: b }.apply(&mut notebook);
+        assert_eq!(result, Err(PatchError::UnknownCell(b)));
+    }
+}
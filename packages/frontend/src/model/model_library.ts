@@ -249,7 +249,12 @@ export class ModelLibrary<RefId> {
                 validatedModel = { tag: "Illformed", model: null, error };
             } else {
                 this.isElaborating.add(key);
-                validatedModel = await this._elaborateAndValidate(key, doc.notebook, theory.theory);
+                validatedModel = await this._elaborateAndValidate(
+                    key,
+                    doc.notebook,
+                    theory.theory,
+                    doc.theory,
+                );
             }
         } finally {
             this.isElaborating.delete(key);
@@ -263,6 +268,7 @@ export class ModelLibrary<RefId> {
         key: ModelKey,
         notebook: ModelNotebook,
         theory: DblTheory,
+        theoryVersion: string,
     ): Promise<ValidatedModel> {
         const instantiated = new DblModelMap();
         for (const cell of Nb.getFormalContent(notebook)) {
@@ -284,7 +290,7 @@ export class ModelLibrary<RefId> {
             instantiated.set(refId, entry.validatedModel.model);
         }
 
-        return elaborateAndValidateModel(notebook, instantiated, theory, key);
+        return elaborateAndValidateModel(notebook, instantiated, theory, key, theoryVersion);
     }
 }
 
@@ -294,10 +300,11 @@ function elaborateAndValidateModel(
     instantiated: DblModelMap,
     theory: DblTheory,
     refId: string,
+    theoryVersion: string,
 ): ValidatedModel {
     let model: DblModel;
     try {
-        model = elaborateModel(notebook, instantiated, theory, refId);
+        model = elaborateModel(notebook, instantiated, theory, refId, theoryVersion);
     } catch (e) {
         return { tag: "Illformed", model: null, error: String(e) };
     }
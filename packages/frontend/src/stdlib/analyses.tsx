@@ -14,6 +14,7 @@ import { defaultSchemaERDConfig, type SchemaERDConfig } from "./analyses/schema_
 import type * as Simulators from "./analyses/simulator_types";
 import type * as SQLDownloadConfig from "./analyses/sql";
 import { SQLBackend, type SQLRenderer } from "./analyses/sql_types";
+import { defaultStochasticMassActionData } from "./analyses/stochastic_mass_action_config";
 
 type AnalysisOptions = {
     id: string;
@@ -246,11 +247,7 @@ export function stochasticMassAction(
         description,
         help,
         component: (props) => <StochasticMassAction title={name} {...otherOptions} {...props} />,
-        initialContent: () => ({
-            rates: {},
-            initialValues: {},
-            duration: 10,
-        }),
+        initialContent: defaultStochasticMassActionData,
     };
 }
 
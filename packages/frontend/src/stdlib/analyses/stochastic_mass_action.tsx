@@ -19,6 +19,7 @@ import { morLabelOrDefault } from "../../model";
 import { ODEResultPlot } from "../../visualization";
 import { createModelODEPlot } from "./model_ode_plot";
 import type { StochasticMassActionSimulator } from "./simulator_types";
+import { randomSeed } from "./stochastic_mass_action_config";
 
 import "./simulation.css";
 
@@ -96,6 +97,16 @@ export default function StochasticMassAction(
                     content.duration = data;
                 }),
         }),
+        createNumericalColumn({
+            name: "Seed",
+            data: (_) => props.content.seed,
+            default: randomSeed(),
+            validate: (_, data) => Number.isSafeInteger(data) && data >= 0,
+            setData: (_, data) =>
+                props.changeContent((content) => {
+                    content.seed = data;
+                }),
+        }),
     ];
 
     const plotResult = createModelODEPlot(
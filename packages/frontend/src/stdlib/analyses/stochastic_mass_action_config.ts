@@ -0,0 +1,19 @@
+import type { StochasticMassActionProblemData } from "catlog-wasm";
+
+/** Draw a random seed for a stochastic simulation.
+
+The seed is a safe integer, so that it is passed to the simulator unchanged.
+ */
+export const randomSeed = (): number => Math.floor(Math.random() * Number.MAX_SAFE_INTEGER);
+
+/** Default parameters of a stochastic simulation, seeded at random.
+
+Storing the seed with the parameters makes re-running the analysis reproduce
+the same trajectory until the seed is changed.
+ */
+export const defaultStochasticMassActionData = (): StochasticMassActionProblemData => ({
+    rates: {},
+    initialValues: {},
+    duration: 10,
+    seed: randomSeed(),
+});
@@ -0,0 +1,69 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct AnalysisCache;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for AnalysisCache {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000000_analysis_cache"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateAnalysisCache]
+    }
+}
+
+/// Create the `analysis_cache` table, keyed by ref ID, document content hash,
+/// analysis ID, and configuration hash.
+///
+/// The ref ID is part of the key so that results stored for one ref are never
+/// served for another with the same content.
+struct CreateAnalysisCache;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateAnalysisCache {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE analysis_cache (
+                ref_id       UUID NOT NULL REFERENCES refs(id) ON DELETE CASCADE,
+                content_hash TEXT NOT NULL,
+                analysis_id  TEXT NOT NULL,
+                config_hash  TEXT NOT NULL,
+                result       JSONB NOT NULL,
+                created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                expires_at   TIMESTAMPTZ NOT NULL,
+                PRIMARY KEY (ref_id, content_hash, analysis_id, config_hash)
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE INDEX analysis_cache_expires_at_idx ON analysis_cache (expires_at)")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS analysis_cache").execute(conn).await?;
+        Ok(())
+    }
+}
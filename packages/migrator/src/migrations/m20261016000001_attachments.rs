@@ -0,0 +1,77 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct Attachments;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for Attachments {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000001_attachments"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateAttachments]
+    }
+}
+
+/// Create the `attachments` table, recording files attached to refs whose
+/// contents are kept in object storage.
+///
+/// An attachment is recorded before its contents are uploaded; `uploaded_at`
+/// is set once the upload is completed and its size checked.
+struct CreateAttachments;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateAttachments {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query("CREATE TYPE attachment_kind AS ENUM ('dataset', 'image', 'other')")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE attachments (
+                id           UUID PRIMARY KEY,
+                ref_id       UUID NOT NULL REFERENCES refs(id) ON DELETE CASCADE,
+                kind         attachment_kind NOT NULL,
+                filename     TEXT NOT NULL,
+                content_type TEXT NOT NULL,
+                size         BIGINT NOT NULL,
+                created_by   TEXT REFERENCES users(id) ON DELETE SET NULL,
+                created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                uploaded_at  TIMESTAMPTZ
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE INDEX attachments_ref_id_idx ON attachments (ref_id)")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+        sqlx::query("DROP TABLE IF EXISTS attachments").execute(&mut *tx).await?;
+        sqlx::query("DROP TYPE IF EXISTS attachment_kind").execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct Datasets;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for Datasets {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000002_datasets"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateDatasets]
+    }
+}
+
+/// Create the `datasets` table of tabular datasets associated with refs and the
+/// `dataset_columns` table storing their parsed columns.
+struct CreateDatasets;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateDatasets {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query("CREATE TYPE dataset_column_type AS ENUM ('number', 'text')")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE datasets (
+                id         UUID PRIMARY KEY,
+                ref_id     UUID NOT NULL REFERENCES refs(id) ON DELETE CASCADE,
+                name       TEXT NOT NULL,
+                num_rows   BIGINT NOT NULL,
+                created_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE INDEX datasets_ref_id_idx ON datasets (ref_id)")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE dataset_columns (
+                dataset_id  UUID NOT NULL REFERENCES datasets(id) ON DELETE CASCADE,
+                position    INTEGER NOT NULL,
+                name        TEXT NOT NULL,
+                column_type dataset_column_type NOT NULL,
+                data        JSONB NOT NULL,
+                PRIMARY KEY (dataset_id, position),
+                UNIQUE (dataset_id, name)
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+        sqlx::query("DROP TABLE IF EXISTS dataset_columns").execute(&mut *tx).await?;
+        sqlx::query("DROP TABLE IF EXISTS datasets").execute(&mut *tx).await?;
+        sqlx::query("DROP TYPE IF EXISTS dataset_column_type").execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use sqlx::{PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct UserVerification;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for UserVerification {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000003_user_verification"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![AddUserVerification]
+    }
+}
+
+/// Add a `verified` flag and a linked ORCID iD to users.
+struct AddUserVerification;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for AddUserVerification {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query(
+            "
+            ALTER TABLE users
+                ADD COLUMN verified BOOLEAN NOT NULL DEFAULT FALSE,
+                ADD COLUMN orcid TEXT UNIQUE
+            ",
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query(
+            "ALTER TABLE users DROP COLUMN IF EXISTS verified, DROP COLUMN IF EXISTS orcid",
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct ContentReports;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for ContentReports {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000004_content_reports"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateContentReports]
+    }
+}
+
+/// Create the `content_reports` moderation queue, an administrator flag on
+/// users, and a timestamp recording when a ref was hidden by moderators.
+struct CreateContentReports;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateContentReports {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query("ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("ALTER TABLE refs ADD COLUMN hidden_at TIMESTAMPTZ")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("CREATE TYPE report_status AS ENUM ('open', 'dismissed', 'actioned')")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE content_reports (
+                id          UUID PRIMARY KEY,
+                ref_id      UUID NOT NULL REFERENCES refs(id) ON DELETE CASCADE,
+                reporter    TEXT REFERENCES users(id) ON DELETE SET NULL,
+                reason      TEXT NOT NULL,
+                status      report_status NOT NULL DEFAULT 'open',
+                created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                resolved_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+                resolved_at TIMESTAMPTZ
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "
+            CREATE UNIQUE INDEX content_reports_open_idx ON content_reports (ref_id, reporter)
+            WHERE status = 'open'
+            ",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX content_reports_status_idx ON content_reports (status, created_at)",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+        sqlx::query("DROP TABLE IF EXISTS content_reports").execute(&mut *tx).await?;
+        sqlx::query("DROP TYPE IF EXISTS report_status").execute(&mut *tx).await?;
+        sqlx::query("ALTER TABLE refs DROP COLUMN IF EXISTS hidden_at")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("ALTER TABLE users DROP COLUMN IF EXISTS is_admin")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct EmbedTokens;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for EmbedTokens {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000005_embed_tokens"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateEmbedTokens]
+    }
+}
+
+/// Create the `embed_tokens` table of tokens granting read-only access to a
+/// single ref, stored as hashes.
+struct CreateEmbedTokens;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateEmbedTokens {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE embed_tokens (
+                id         UUID PRIMARY KEY,
+                ref_id     UUID NOT NULL REFERENCES refs(id) ON DELETE CASCADE,
+                token_hash TEXT NOT NULL UNIQUE,
+                created_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                expires_at TIMESTAMPTZ,
+                revoked_at TIMESTAMPTZ
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE INDEX embed_tokens_ref_id_idx ON embed_tokens (ref_id)")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS embed_tokens").execute(conn).await?;
+        Ok(())
+    }
+}
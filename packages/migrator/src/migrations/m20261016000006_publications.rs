@@ -0,0 +1,60 @@
+use sqlx::{PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct Publications;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for Publications {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000006_publications"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreatePublications]
+    }
+}
+
+/// Create the `publications` table of tagged versions of refs, together with
+/// the locations to which they were archived.
+struct CreatePublications;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreatePublications {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            CREATE TABLE publications (
+                id            UUID PRIMARY KEY,
+                ref_id        UUID NOT NULL REFERENCES refs(id) ON DELETE CASCADE,
+                snapshot_id   INTEGER NOT NULL REFERENCES snapshots(id),
+                tag           TEXT NOT NULL,
+                published_by  TEXT REFERENCES users(id) ON DELETE SET NULL,
+                published_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                archive_url   TEXT,
+                doi           TEXT,
+                archive_error TEXT,
+                UNIQUE (ref_id, tag)
+            )
+            "#,
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS publications").execute(conn).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct FeatureFlags;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for FeatureFlags {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000007_feature_flags"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateFeatureFlags]
+    }
+}
+
+/// Create the `feature_flags` table of settings of feature flags, each for
+/// everyone, for the users at an email domain, or for a single user.
+struct CreateFeatureFlags;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateFeatureFlags {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE feature_flags (
+                flag         TEXT NOT NULL,
+                user_id      TEXT REFERENCES users(id) ON DELETE CASCADE,
+                email_domain TEXT,
+                enabled      BOOLEAN NOT NULL,
+                updated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                CHECK (user_id IS NULL OR email_domain IS NULL)
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE UNIQUE INDEX feature_flags_scope_idx ON feature_flags
+                (flag, (COALESCE(user_id, '')), (COALESCE(email_domain, '')))
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS feature_flags").execute(conn).await?;
+        Ok(())
+    }
+}
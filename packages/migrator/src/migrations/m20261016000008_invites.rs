@@ -0,0 +1,65 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct Invites;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for Invites {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000008_invites"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateInvites]
+    }
+}
+
+/// Create the `invites` table of pending grants of permissions on refs to
+/// email addresses without an account.
+struct CreateInvites;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateInvites {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE invites (
+                id         UUID PRIMARY KEY,
+                ref_id     UUID NOT NULL REFERENCES refs(id) ON DELETE CASCADE,
+                email      TEXT NOT NULL,
+                level      permission_level NOT NULL,
+                invited_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                UNIQUE (ref_id, email)
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE INDEX invites_email_idx ON invites (email)")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS invites").execute(conn).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct ClassroomCopies;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for ClassroomCopies {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000009_classroom_copies"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateClassroomCopies]
+    }
+}
+
+/// Create the `classroom_copies` table recording the copies of a template ref
+/// provisioned for students.
+struct CreateClassroomCopies;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateClassroomCopies {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE classroom_copies (
+                copy_ref     UUID PRIMARY KEY REFERENCES refs(id) ON DELETE CASCADE,
+                template_ref UUID NOT NULL REFERENCES refs(id) ON DELETE CASCADE,
+                student      TEXT NOT NULL,
+                created_by   TEXT REFERENCES users(id) ON DELETE SET NULL,
+                created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX classroom_copies_template_ref_idx ON classroom_copies (template_ref)",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS classroom_copies").execute(conn).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use sqlx::{PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct RefSettings;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for RefSettings {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000010_ref_settings"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![AddRefSettings]
+    }
+}
+
+/// Add the `settings` column to the `refs` table, holding per-ref settings as
+/// JSON.
+struct AddRefSettings;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for AddRefSettings {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("ALTER TABLE refs ADD COLUMN settings JSONB NOT NULL DEFAULT '{}'")
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("ALTER TABLE refs DROP COLUMN IF EXISTS settings")
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct Jobs;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for Jobs {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000011_jobs"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateJobs]
+    }
+}
+
+/// Create the `jobs` table recording long-running computations started by
+/// users and their results.
+struct CreateJobs;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateJobs {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE jobs (
+                id          UUID PRIMARY KEY,
+                kind        TEXT NOT NULL,
+                owner       TEXT REFERENCES users(id) ON DELETE CASCADE,
+                status      TEXT NOT NULL DEFAULT 'queued',
+                params      JSONB NOT NULL DEFAULT '{}',
+                result      JSONB,
+                error       TEXT,
+                created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                started_at  TIMESTAMPTZ,
+                finished_at TIMESTAMPTZ
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE INDEX jobs_owner_idx ON jobs (owner, created_at)")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS jobs").execute(conn).await?;
+        Ok(())
+    }
+}
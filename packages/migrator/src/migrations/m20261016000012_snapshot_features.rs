@@ -0,0 +1,61 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct SnapshotFeatures;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for SnapshotFeatures {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000012_snapshot_features"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateSnapshotFeatures]
+    }
+}
+
+/// Create the `snapshot_features` table holding the feature vectors of the
+/// models in snapshots, used to search for similar documents.
+struct CreateSnapshotFeatures;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateSnapshotFeatures {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE snapshot_features (
+                snapshot_id INT PRIMARY KEY REFERENCES snapshots(id) ON DELETE CASCADE,
+                theory      TEXT,
+                features    JSONB NOT NULL
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE INDEX snapshot_features_theory_idx ON snapshot_features (theory)")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS snapshot_features").execute(conn).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct JobProgress;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for JobProgress {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000013_job_progress"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateJobProgress]
+    }
+}
+
+/// Create the `job_progress` table recording the progress reported by running
+/// jobs, in order of sequence number.
+struct CreateJobProgress;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateJobProgress {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE job_progress (
+                job_id         UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+                seq            INTEGER NOT NULL,
+                percent        REAL NOT NULL,
+                message        TEXT NOT NULL,
+                partial_result JSONB,
+                created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (job_id, seq)
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS job_progress").execute(conn).await?;
+        Ok(())
+    }
+}
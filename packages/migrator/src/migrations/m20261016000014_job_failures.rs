@@ -0,0 +1,59 @@
+use sqlx::{PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct JobFailures;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for JobFailures {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000014_job_failures"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![AddJobFailure]
+    }
+}
+
+/// Add a `failure` column recording the kind of failure of a failed job, such
+/// as exceeding its time or memory limit.
+struct AddJobFailure;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for AddJobFailure {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query(
+            "
+            ALTER TABLE jobs
+            ADD COLUMN failure TEXT NULL;
+            ",
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query(
+            "
+            ALTER TABLE jobs
+            DROP COLUMN failure;
+            ",
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use sqlx::{PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct EncryptedDocuments;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for EncryptedDocuments {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000015_encrypted_documents"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![AddRefEncryption]
+    }
+}
+
+/// Add an `encryption` column to `refs` holding metadata about the key of a
+/// document encrypted on the client, or null if the document is not encrypted.
+struct AddRefEncryption;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for AddRefEncryption {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query(
+            "
+            ALTER TABLE refs
+            ADD COLUMN encryption JSONB NULL;
+            ",
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query(
+            "
+            ALTER TABLE refs
+            DROP COLUMN encryption;
+            ",
+        )
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct ScratchDocs;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for ScratchDocs {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000018_scratch_docs"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateScratchDocsTable]
+    }
+}
+
+/// Create the `scratch_docs` table, which records the scratch documents whose
+/// Automerge storage has not been claimed by a ref, so that the storage can be
+/// deleted when they are discarded or expire.
+struct CreateScratchDocsTable;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateScratchDocsTable {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            "
+            CREATE TABLE scratch_docs (
+                doc_id      TEXT PRIMARY KEY,
+                owner_id    TEXT NOT NULL,
+                created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+            ",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE INDEX scratch_docs_created_at_idx ON scratch_docs (created_at)")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE scratch_docs").execute(conn).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use sqlx::{PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct SnapshotLints;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for SnapshotLints {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000020_snapshot_lints"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateSnapshotLints]
+    }
+}
+
+/// Create the `snapshot_lints` table holding the lint violations of the models
+/// in snapshots.
+struct CreateSnapshotLints;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateSnapshotLints {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            CREATE TABLE snapshot_lints (
+                snapshot_id INT PRIMARY KEY REFERENCES snapshots(id) ON DELETE CASCADE,
+                lints       JSONB NOT NULL
+            )
+            "#,
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS snapshot_lints").execute(conn).await?;
+        Ok(())
+    }
+}
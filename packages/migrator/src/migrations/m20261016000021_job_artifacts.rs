@@ -0,0 +1,64 @@
+use sqlx::{Acquire, PgConnection, Postgres};
+use sqlx_migrator::Migration;
+use sqlx_migrator::Operation;
+use sqlx_migrator::error::Error;
+use sqlx_migrator::vec_box;
+
+pub(crate) struct JobArtifacts;
+
+#[async_trait::async_trait]
+impl Migration<Postgres> for JobArtifacts {
+    fn app(&self) -> &str {
+        "backend"
+    }
+
+    fn name(&self) -> &str {
+        "m20261016000021_job_artifacts"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<Postgres>>> {
+        vec_box![CreateJobArtifacts]
+    }
+}
+
+/// Create the `job_artifacts` table, recording files such as plots and CSV
+/// exports produced by a job, whose contents are kept in object storage.
+struct CreateJobArtifacts;
+
+#[async_trait::async_trait]
+impl Operation<Postgres> for CreateJobArtifacts {
+    async fn up(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE job_artifacts (
+                id           UUID PRIMARY KEY,
+                job_id       UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+                filename     TEXT NOT NULL,
+                content_type TEXT NOT NULL,
+                size         BIGINT NOT NULL,
+                created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("CREATE INDEX job_artifacts_job_id_idx ON job_artifacts (job_id)")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn down(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE IF EXISTS job_artifacts").execute(conn).await?;
+        Ok(())
+    }
+}
@@ -12,6 +12,25 @@ mod m20251006141026_get_ref_stubs;
 mod m20260124120000_add_user_fk_cascade;
 mod m20260320000000_add_user_state_doc_id;
 mod m20260414000000_snapshot_history;
+mod m20261016000000_analysis_cache;
+mod m20261016000001_attachments;
+mod m20261016000002_datasets;
+mod m20261016000003_user_verification;
+mod m20261016000004_content_reports;
+mod m20261016000005_embed_tokens;
+mod m20261016000006_publications;
+mod m20261016000007_feature_flags;
+mod m20261016000008_invites;
+mod m20261016000009_classroom_copies;
+mod m20261016000010_ref_settings;
+mod m20261016000011_jobs;
+mod m20261016000012_snapshot_features;
+mod m20261016000013_job_progress;
+mod m20261016000014_job_failures;
+mod m20261016000015_encrypted_documents;
+mod m20261016000018_scratch_docs;
+mod m20261016000020_snapshot_lints;
+mod m20261016000021_job_artifacts;
 
 pub fn migrations() -> Vec<Box<dyn Migration<Postgres>>> {
     vec_box![
@@ -25,5 +44,24 @@ pub fn migrations() -> Vec<Box<dyn Migration<Postgres>>> {
         m20260124120000_add_user_fk_cascade::AddUserFkCascade,
         m20260320000000_add_user_state_doc_id::AddUserStateDocId,
         m20260414000000_snapshot_history::SnapshotHistory,
+        m20261016000000_analysis_cache::AnalysisCache,
+        m20261016000001_attachments::Attachments,
+        m20261016000002_datasets::Datasets,
+        m20261016000003_user_verification::UserVerification,
+        m20261016000004_content_reports::ContentReports,
+        m20261016000005_embed_tokens::EmbedTokens,
+        m20261016000006_publications::Publications,
+        m20261016000007_feature_flags::FeatureFlags,
+        m20261016000008_invites::Invites,
+        m20261016000009_classroom_copies::ClassroomCopies,
+        m20261016000010_ref_settings::RefSettings,
+        m20261016000011_jobs::Jobs,
+        m20261016000012_snapshot_features::SnapshotFeatures,
+        m20261016000013_job_progress::JobProgress,
+        m20261016000014_job_failures::JobFailures,
+        m20261016000015_encrypted_documents::EncryptedDocuments,
+        m20261016000018_scratch_docs::ScratchDocs,
+        m20261016000020_snapshot_lints::SnapshotLints,
+        m20261016000021_job_artifacts::JobArtifacts,
     ]
 }